@@ -175,11 +175,13 @@ fn substitute_macros(input: &str) -> String {
         ("[REMOVING]", "    Removing"),
         ("[DOCTEST]", "   Doc-tests"),
         ("[PACKAGING]", "   Packaging"),
+        ("[PATCHING]", "    Patching"),
         ("[PACKAGED]", "    Packaged"),
         ("[DOWNLOADING]", " Downloading"),
         ("[DOWNLOADED]", "  Downloaded"),
         ("[UPLOADING]", "   Uploading"),
         ("[VERIFYING]", "   Verifying"),
+        ("[VERIFIED]", "    Verified"),
         ("[ARCHIVING]", "   Archiving"),
         ("[INSTALLING]", "  Installing"),
         ("[REPLACING]", "   Replacing"),
@@ -200,6 +202,12 @@ fn substitute_macros(input: &str) -> String {
         ("[EXECUTABLE]", "  Executable"),
         ("[SKIPPING]", "    Skipping"),
         ("[WAITING]", "     Waiting"),
+        ("[SETTING]", "     Setting"),
+        ("[UPGRADING]", "   Upgrading"),
+        ("[DOWNGRADING]", " Downgrading"),
+        ("[COMPARING]", "   Comparing"),
+        ("[PROVIDING]", "   Providing"),
+        ("[FORMATTING]", "  Formatting"),
     ];
     let mut result = input.to_owned();
     for &(pat, subst) in &macros {
@@ -8,14 +8,24 @@
 //! support on macOS is going away, so macOS users are out of luck.
 //!
 //! These tests are all disabled on rust-lang/rust's CI, but run in Cargo's CI.
+//!
+//! For tests that only need Cargo to *believe* it is cross-compiling, such as
+//! tests of the target directory layout, of the environment given to build
+//! scripts, or of artifact dependencies forced to a `target`, see
+//! [`FakeTarget`]. Those tests work on every host without any extra targets
+//! installed, and are not affected by `CFG_DISABLE_CROSS_TESTS`.
 
-use crate::{basic_manifest, main_file, project};
-use cargo_util::ProcessError;
+use crate::{basic_manifest, main_file, paths, project, rustc_host, Execs, Project};
+use cargo_util::{ProcessBuilder, ProcessError};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
 use std::env;
 use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Output};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Once;
+use std::sync::{Mutex, Once};
 
 /// Whether or not the resulting cross binaries can run on the host.
 static CAN_RUN_ON_HOST: AtomicBool = AtomicBool::new(false);
@@ -254,3 +264,223 @@ pub fn can_run_on_host() -> bool {
         return true;
     }
 }
+
+/// How much of the standard library is available for a [`FakeTarget`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FakeStd {
+    /// The host's full standard library.
+    Full,
+    /// Only `core` and `alloc`, like many embedded targets.
+    Alloc,
+    /// Only `core`, like a bare-metal target without an allocator.
+    Core,
+}
+
+/// A made-up target triple which is compiled with the host toolchain.
+///
+/// Cargo treats the triple like any other `--target`: its units are laid
+/// out under `target/<triple>/`, build scripts see it in `TARGET`,
+/// `[target.<triple>]` config tables apply to it, and so on. Commands run
+/// with [`use_fake_targets`] get a rustc wrapper that replaces the fake
+/// triple with the host triple right before invoking rustc, so no real
+/// cross toolchain is needed.
+///
+/// With [`FakeStd::Alloc`] or [`FakeStd::Core`], rustc is given a sysroot
+/// which only has those crates, so anything that needs `std` fails to build
+/// with the same error as on a real target without `std`.
+///
+/// There are a few limitations:
+///
+/// * `rustdoc` is not wrapped, so `cargo doc` and doctests do not work.
+/// * `cfg` values are those of the host.
+/// * The wrapper is installed with `RUSTC_WRAPPER`, so it can't be combined
+///   with another wrapper.
+///
+/// ```no_run
+/// use cargo_test_support::cross_compile::{self, FakeStd};
+/// use cargo_test_support::project;
+///
+/// let target = cross_compile::fake_target("aarch64-fake-none", FakeStd::Core);
+/// let p = project().file("src/lib.rs", "#![no_std]").build();
+/// cross_compile::use_fake_targets(
+///     p.cargo("build --target").arg(target.triple()),
+///     &[&target],
+/// )
+/// .run();
+/// assert!(target.out_dir(&p, "debug").join("libfoo.rlib").is_file());
+/// ```
+#[derive(Clone, Debug)]
+pub struct FakeTarget {
+    triple: String,
+    std: FakeStd,
+    sysroot: Option<PathBuf>,
+}
+
+impl FakeTarget {
+    /// The fake target triple.
+    pub fn triple(&self) -> &str {
+        &self.triple
+    }
+
+    /// How much of the standard library is available.
+    pub fn std(&self) -> FakeStd {
+        self.std
+    }
+
+    /// The directory where the final artifacts for this target are placed,
+    /// for example `target/<triple>/debug`.
+    pub fn out_dir(&self, p: &Project, profile: &str) -> PathBuf {
+        p.build_dir().join(&self.triple).join(profile)
+    }
+
+    /// Path to a binary built for this target with the `dev` profile.
+    pub fn bin(&self, p: &Project, name: &str) -> PathBuf {
+        p.target_bin(&self.triple, name)
+    }
+
+    /// The triple suitable for use in a cargo environment variable, such as
+    /// `CARGO_TARGET_<TRIPLE>_RUNNER`.
+    pub fn env_name(&self) -> String {
+        self.triple.to_uppercase().replace('-', "_")
+    }
+}
+
+/// Creates a [`FakeTarget`] with the given triple.
+///
+/// The triple should not be a real target, and must not be the host.
+pub fn fake_target(triple: &str, std: FakeStd) -> FakeTarget {
+    assert_ne!(triple, rustc_host(), "a fake target cannot be the host");
+    let sysroot = match std {
+        FakeStd::Full => None,
+        FakeStd::Alloc | FakeStd::Core => Some(fake_sysroot(std)),
+    };
+    FakeTarget {
+        triple: triple.to_string(),
+        std,
+        sysroot,
+    }
+}
+
+/// Sets up `execs` so that the given fake targets can be used with
+/// `--target`, or anywhere else a target triple is accepted.
+pub fn use_fake_targets<'a>(execs: &'a mut Execs, targets: &[&FakeTarget]) -> &'a mut Execs {
+    let targets = targets
+        .iter()
+        .map(|t| match &t.sysroot {
+            Some(sysroot) => format!("{}={}", t.triple, sysroot.display()),
+            None => t.triple.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    execs
+        .env("RUSTC_WRAPPER", fake_target_wrapper())
+        .env("__CARGO_TEST_FAKE_TARGET_HOST", rustc_host())
+        .env("__CARGO_TEST_FAKE_TARGETS", targets)
+}
+
+lazy_static! {
+    static ref FAKE_TARGET_WRAPPER: Mutex<Option<PathBuf>> = Mutex::new(None);
+    static ref FAKE_SYSROOTS: Mutex<HashMap<FakeStd, PathBuf>> = Mutex::new(HashMap::new());
+}
+
+/// Returns the path to the rustc wrapper used by [`use_fake_targets`].
+fn fake_target_wrapper() -> PathBuf {
+    let mut lock = FAKE_TARGET_WRAPPER.lock().unwrap();
+    if let Some(path) = &*lock {
+        return path.clone();
+    }
+    let p = project()
+        .at(paths::global_root().join("rustc-fake-target-wrapper"))
+        .file(
+            "Cargo.toml",
+            &basic_manifest("rustc-fake-target-wrapper", "1.0.0"),
+        )
+        .file(
+            "src/main.rs",
+            r#"
+            use std::env;
+            use std::process::Command;
+
+            fn main() {
+                let mut args = env::args().skip(1);
+                let mut cmd = Command::new(args.next().unwrap());
+                let host = env::var("__CARGO_TEST_FAKE_TARGET_HOST").unwrap();
+                let fakes = env::var("__CARGO_TEST_FAKE_TARGETS").unwrap_or_default();
+                // Each line is either `<triple>` or `<triple>=<sysroot>`.
+                let lookup = |target: &str| {
+                    fakes.lines().find_map(|line| {
+                        let (triple, sysroot) = line.split_once('=').unwrap_or((line, ""));
+                        (triple == target).then(|| sysroot.to_string())
+                    })
+                };
+                let mut sysroot = None;
+                while let Some(arg) = args.next() {
+                    let target = if arg == "--target" {
+                        args.next().unwrap()
+                    } else if let Some(target) = arg.strip_prefix("--target=") {
+                        target.to_string()
+                    } else {
+                        cmd.arg(arg);
+                        continue;
+                    };
+                    match lookup(&target) {
+                        Some(fake_sysroot) => {
+                            cmd.arg("--target").arg(&host);
+                            sysroot = Some(fake_sysroot);
+                        }
+                        None => {
+                            cmd.arg("--target").arg(target);
+                        }
+                    }
+                }
+                if let Some(sysroot) = sysroot.filter(|s| !s.is_empty()) {
+                    cmd.arg("--sysroot").arg(sysroot);
+                }
+                let status = cmd.status().unwrap();
+                std::process::exit(status.code().unwrap_or(1));
+            }
+            "#,
+        )
+        .build();
+    p.cargo("build").run();
+    let path = p.bin("rustc-fake-target-wrapper");
+    *lock = Some(path.clone());
+    path
+}
+
+/// Creates a sysroot for the host which only contains the crates available
+/// for the given [`FakeStd`].
+fn fake_sysroot(std: FakeStd) -> PathBuf {
+    let mut lock = FAKE_SYSROOTS.lock().unwrap();
+    if let Some(path) = lock.get(&std) {
+        return path.clone();
+    }
+    let crates: &[&str] = match std {
+        FakeStd::Full => unreachable!("the host sysroot is used"),
+        FakeStd::Alloc => &["core", "compiler_builtins", "alloc"],
+        FakeStd::Core => &["core", "compiler_builtins"],
+    };
+    let output = ProcessBuilder::new("rustc")
+        .arg("--print=sysroot")
+        .exec_with_output()
+        .expect("rustc should exec");
+    let host_sysroot = String::from_utf8(output.stdout).expect("utf8 output");
+    let rustlib = Path::new("lib/rustlib").join(rustc_host()).join("lib");
+    let src = Path::new(host_sysroot.trim()).join(&rustlib);
+    let root = paths::global_root().join(format!("fake-sysroot-{:?}", std).to_lowercase());
+    let dst = root.join(&rustlib);
+    fs::create_dir_all(&dst).unwrap();
+    for entry in fs::read_dir(&src).unwrap() {
+        let entry = entry.unwrap();
+        let file_name = entry.file_name();
+        let file_name = file_name.to_str().unwrap();
+        let is_wanted = crates
+            .iter()
+            .any(|krate| file_name.starts_with(&format!("lib{}-", krate)));
+        if is_wanted {
+            fs::copy(entry.path(), dst.join(file_name)).unwrap();
+        }
+    }
+    lock.insert(std, root.clone());
+    root
+}
@@ -1238,6 +1238,7 @@ pub trait TestEnv: Sized {
             .env_remove("RUSTFLAGS")
             .env_remove("RUSTDOCFLAGS")
             .env_remove("XDG_CONFIG_HOME") // see #2345
+            .env_remove("XDG_CACHE_HOME")
             .env("GIT_CONFIG_NOSYSTEM", "1") // keep trying to sandbox ourselves
             .env_remove("EMAIL")
             .env_remove("USER") // not set on some rust-lang docker images
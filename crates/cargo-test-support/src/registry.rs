@@ -510,6 +510,7 @@ pub struct Request {
     pub authorization: Option<String>,
     pub if_modified_since: Option<String>,
     pub if_none_match: Option<String>,
+    pub range: Option<String>,
 }
 
 impl fmt::Debug for Request {
@@ -521,6 +522,7 @@ impl fmt::Debug for Request {
             .field("authorization", &self.authorization)
             .field("if_modified_since", &self.if_modified_since)
             .field("if_none_match", &self.if_none_match)
+            .field("range", &self.range)
             .finish()
     }
 }
@@ -595,6 +597,7 @@ impl HttpServer {
             let mut if_modified_since = None;
             let mut if_none_match = None;
             let mut authorization = None;
+            let mut range = None;
             let mut content_len = None;
             loop {
                 line.clear();
@@ -613,6 +616,7 @@ impl HttpServer {
                     "if-modified-since" => if_modified_since = Some(value),
                     "if-none-match" => if_none_match = Some(value),
                     "authorization" => authorization = Some(value),
+                    "range" => range = Some(value),
                     "content-length" => content_len = Some(value),
                     _ => {}
                 }
@@ -630,6 +634,7 @@ impl HttpServer {
                 authorization,
                 if_modified_since,
                 if_none_match,
+                range,
                 method,
                 url,
                 body,
@@ -865,6 +870,7 @@ impl HttpServer {
                 false,
                 new_crate.links,
                 None,
+                None,
             );
 
             write_to_index(&self.registry_path, &new_crate.name, line, false);
@@ -1131,6 +1137,7 @@ impl Package {
             self.features.clone(),
             self.yanked,
             self.links.clone(),
+            self.rust_version.as_deref(),
             self.v,
         );
 
@@ -3,6 +3,7 @@
 pub use self::read2::read2;
 pub use process_builder::ProcessBuilder;
 pub use process_error::{exit_status_to_string, is_simple_exit_code, ProcessError};
+pub use resource_usage::ResourceUsage;
 pub use sha256::Sha256;
 
 pub mod paths;
@@ -10,6 +11,7 @@ mod process_builder;
 mod process_error;
 mod read2;
 pub mod registry;
+mod resource_usage;
 mod sha256;
 
 /// Whether or not this running in a Continuous Integration environment.
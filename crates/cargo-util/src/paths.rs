@@ -160,6 +160,26 @@ pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()>
         .with_context(|| format!("failed to write `{}`", path.display()))
 }
 
+/// Equivalent to [`write()`], but atomic: the contents are written to a
+/// temporary file in the same directory, which is then renamed to `path`.
+/// If the process is killed, `path` has either its old or its new contents.
+///
+/// The file isn't synced to disk, so after a crash of the whole system, like
+/// a power loss, `path` may be empty or truncated. Files written too often to
+/// sync each of them, like Cargo's fingerprints, have to be checked when they
+/// are read back instead.
+pub fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()> {
+    let path = path.as_ref();
+    (|| -> Result<()> {
+        let parent = path.parent().unwrap();
+        let mut tmp = TempFileBuilder::new().prefix(".tmp").tempfile_in(parent)?;
+        tmp.write_all(contents.as_ref())?;
+        tmp.persist(path)?;
+        Ok(())
+    })()
+    .with_context(|| format!("failed to write `{}`", path.display()))
+}
+
 /// Equivalent to [`write()`], but does not write anything if the file contents
 /// are identical to the given contents.
 pub fn write_if_changed<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()> {
@@ -1,5 +1,6 @@
 use crate::process_error::ProcessError;
 use crate::read2;
+use crate::resource_usage::{self, ResourceUsage};
 
 use anyhow::{bail, Context, Result};
 use jobserver::Client;
@@ -334,6 +335,24 @@ impl ProcessBuilder {
         on_stdout_line: &mut dyn FnMut(&str) -> Result<()>,
         on_stderr_line: &mut dyn FnMut(&str) -> Result<()>,
         capture_output: bool,
+    ) -> Result<Output> {
+        self.exec_with_streaming_and_usage(
+            on_stdout_line,
+            on_stderr_line,
+            capture_output,
+            &mut None,
+        )
+    }
+
+    /// Like [`ProcessBuilder::exec_with_streaming`], but also stores the
+    /// resources used by the process in `usage` on platforms which report
+    /// them, even if the process fails.
+    pub fn exec_with_streaming_and_usage(
+        &self,
+        on_stdout_line: &mut dyn FnMut(&str) -> Result<()>,
+        on_stderr_line: &mut dyn FnMut(&str) -> Result<()>,
+        capture_output: bool,
+        usage: &mut Option<ResourceUsage>,
     ) -> Result<Output> {
         let mut stdout = Vec::new();
         let mut stderr = Vec::new();
@@ -344,19 +363,36 @@ impl ProcessBuilder {
 
         let spawn = |mut cmd| {
             if !debug_force_argfile(self.retry_with_argfile) {
-                match piped(&mut cmd, false).spawn() {
+                match piped(&mut cmd, self.stdin.is_some()).spawn() {
                     Err(ref e) if self.should_retry_with_argfile(e) => {}
                     Err(e) => return Err(e),
                     Ok(child) => return Ok((child, None)),
                 }
             }
             let (mut cmd, argfile) = self.build_command_with_argfile()?;
-            Ok((piped(&mut cmd, false).spawn()?, Some(argfile)))
+            Ok((
+                piped(&mut cmd, self.stdin.is_some()).spawn()?,
+                Some(argfile),
+            ))
         };
 
         let status = (|| {
             let cmd = self.build_command();
             let (mut child, argfile) = spawn(cmd)?;
+            // Write stdin from another thread, so that neither side blocks
+            // when the process writes output before reading all its input.
+            let stdin = match (&self.stdin, child.stdin.take()) {
+                (Some(data), Some(mut pipe)) => {
+                    let data = data.clone();
+                    Some(std::thread::spawn(move || match pipe.write_all(&data) {
+                        // The process exited or closed stdin without reading
+                        // it, which is up to the process to report.
+                        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                        result => result,
+                    }))
+                }
+                _ => None,
+            };
             let out = child.stdout.take().unwrap();
             let err = child.stderr.take().unwrap();
             read2(out, err, &mut |is_out, data, eof| {
@@ -402,7 +438,13 @@ impl ProcessBuilder {
                 data.drain(..idx);
                 *pos = 0;
             })?;
-            let status = child.wait();
+            if let Some(stdin) = stdin {
+                stdin.join().unwrap()?;
+            }
+            let status = resource_usage::wait(&mut child).map(|(status, child_usage)| {
+                *usage = child_usage;
+                status
+            });
             if let Some(argfile) = argfile {
                 close_tempfile_and_log_error(argfile);
             }
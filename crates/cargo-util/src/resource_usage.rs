@@ -0,0 +1,94 @@
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::time::Duration;
+
+/// Resources used by a finished process, as reported by the operating system.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// The maximum resident set size, in bytes.
+    pub max_rss: u64,
+    /// CPU time spent in user mode.
+    pub user_time: Duration,
+    /// CPU time spent in kernel mode.
+    pub system_time: Duration,
+    /// The number of block input operations.
+    pub blocks_read: u64,
+    /// The number of block output operations.
+    pub blocks_written: u64,
+}
+
+/// Waits for `child` to exit, returning its exit status and, on platforms
+/// which report it, the resources it used.
+pub(crate) fn wait(child: &mut Child) -> io::Result<(ExitStatus, Option<ResourceUsage>)> {
+    imp::wait(child)
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+))]
+mod imp {
+    use super::ResourceUsage;
+    use std::io;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{Child, ExitStatus};
+    use std::time::Duration;
+
+    pub fn wait(child: &mut Child) -> io::Result<(ExitStatus, Option<ResourceUsage>)> {
+        drop(child.stdin.take());
+        let pid = child.id() as libc::pid_t;
+        let mut status = 0;
+        let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+        while unsafe { libc::wait4(pid, &mut status, 0, &mut rusage) } == -1 {
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+        // `ru_maxrss` is in bytes on Apple platforms, and in kilobytes
+        // everywhere else.
+        let rss_unit = if cfg!(any(target_os = "macos", target_os = "ios")) {
+            1
+        } else {
+            1024
+        };
+        let usage = ResourceUsage {
+            max_rss: rusage.ru_maxrss as u64 * rss_unit,
+            user_time: to_duration(rusage.ru_utime),
+            system_time: to_duration(rusage.ru_stime),
+            blocks_read: rusage.ru_inblock as u64,
+            blocks_written: rusage.ru_oublock as u64,
+        };
+        Ok((ExitStatus::from_raw(status), Some(usage)))
+    }
+
+    fn to_duration(time: libc::timeval) -> Duration {
+        Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+)))]
+mod imp {
+    use super::ResourceUsage;
+    use std::io;
+    use std::process::{Child, ExitStatus};
+
+    pub fn wait(child: &mut Child) -> io::Result<(ExitStatus, Option<ResourceUsage>)> {
+        Ok((child.wait()?, None))
+    }
+}
@@ -8,7 +8,7 @@ use std::io::{Cursor, SeekFrom};
 use std::time::Instant;
 
 use anyhow::{bail, format_err, Context, Result};
-use curl::easy::{Easy, List};
+use curl::easy::{Easy, List, SeekResult};
 use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
 use serde::{Deserialize, Serialize};
 use url::Url;
@@ -36,6 +36,28 @@ pub struct Crate {
     pub name: String,
     pub description: Option<String>,
     pub max_version: String,
+    /// The number of downloads of all versions, if the registry counts them.
+    pub downloads: Option<u64>,
+    /// The number of downloads in the last 90 days, if the registry counts
+    /// them.
+    pub recent_downloads: Option<u64>,
+}
+
+/// The details of a crate which the registry knows beyond its index.
+#[derive(Deserialize)]
+pub struct CrateMetadata {
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub documentation: Option<String>,
+    pub repository: Option<String>,
+    /// The number of downloads of all versions, if the registry counts them.
+    pub downloads: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct CrateMetadataResponse {
+    #[serde(rename = "crate")]
+    krate: CrateMetadata,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -57,6 +79,19 @@ pub struct NewCrate {
     pub repository: Option<String>,
     pub badges: BTreeMap<String, BTreeMap<String, String>>,
     pub links: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<NewCrateProvenance>,
+}
+
+/// Where and how a crate was published, for registries which record it.
+#[derive(Serialize, Deserialize)]
+pub struct NewCrateProvenance {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vcs_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ci_run_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builder: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -110,6 +145,10 @@ struct ApiError {
 struct OwnersReq<'a> {
     users: &'a [&'a str],
 }
+#[derive(Serialize)]
+struct YankReq<'a> {
+    reason: &'a str,
+}
 #[derive(Deserialize)]
 struct Users {
     users: Vec<User>,
@@ -288,7 +327,9 @@ impl Registry {
 
         let started = Instant::now();
         let body = self
-            .handle(&mut |buf| body.read(buf).unwrap_or(0))
+            .handle(&mut |buf| body.read(buf).unwrap_or(0), &mut |_| {
+                SeekResult::CantSeek
+            })
             .map_err(|e| match e {
                 ResponseError::Code { code, .. }
                     if code == 503
@@ -340,20 +381,59 @@ impl Registry {
         })
     }
 
+    /// Returns the details of the crate `krate`.
+    pub fn crate_metadata(&mut self, krate: &str) -> Result<CrateMetadata> {
+        self.handle.get(true)?;
+        let body = self.req(&format!("/crates/{}", krate), None, Auth::Unauthorized)?;
+        Ok(serde_json::from_str::<CrateMetadataResponse>(&body)?.krate)
+    }
+
     pub fn search(&mut self, query: &str, limit: u32) -> Result<(Vec<Crate>, u32)> {
+        self.search_sorted(query, limit, None)
+    }
+
+    /// Searches crates, asking the registry to order them by `sort`, like
+    /// `downloads` or `alpha`.
+    ///
+    /// Registries which don't know the order ignore it.
+    pub fn search_sorted(
+        &mut self,
+        query: &str,
+        limit: u32,
+        sort: Option<&str>,
+    ) -> Result<(Vec<Crate>, u32)> {
         let formatted_query = percent_encode(query.as_bytes(), NON_ALPHANUMERIC);
-        let body = self.req(
-            &format!("/crates?q={}&per_page={}", formatted_query, limit),
-            None,
-            Auth::Unauthorized,
-        )?;
+        let mut path = format!("/crates?q={}&per_page={}", formatted_query, limit);
+        if let Some(sort) = sort {
+            path.push_str("&sort=");
+            path.extend(percent_encode(sort.as_bytes(), NON_ALPHANUMERIC));
+        }
+        let body = self.req(&path, None, Auth::Unauthorized)?;
 
         let crates = serde_json::from_str::<Crates>(&body)?;
         Ok((crates.crates, crates.meta.total))
     }
 
     pub fn yank(&mut self, krate: &str, version: &str) -> Result<()> {
-        let body = self.delete(&format!("/crates/{}/{}/yank", krate, version), None)?;
+        self.yank_with_reason(krate, version, None)
+    }
+
+    /// Yanks a version, sending the `reason` for it to the registry.
+    ///
+    /// Registries which don't record reasons ignore it.
+    pub fn yank_with_reason(
+        &mut self,
+        krate: &str,
+        version: &str,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let req = reason
+            .map(|reason| serde_json::to_string(&YankReq { reason }))
+            .transpose()?;
+        let body = self.delete(
+            &format!("/crates/{}/{}/yank", krate, version),
+            req.as_ref().map(|req| req.as_bytes()),
+        )?;
         assert!(serde_json::from_str::<R>(&body)?.ok);
         Ok(())
     }
@@ -394,25 +474,45 @@ impl Registry {
         }
         self.handle.http_headers(headers)?;
         match body {
-            Some(mut body) => {
+            Some(body) => {
                 self.handle.upload(true)?;
                 self.handle.in_filesize(body.len() as u64)?;
-                self.handle(&mut |buf| body.read(buf).unwrap_or(0))
-                    .map_err(|e| e.into())
+                // The body may have to be sent again, when a reused connection
+                // turns out to be closed.
+                let pos = std::cell::Cell::new(0);
+                self.handle(
+                    &mut |buf| {
+                        let n = (&body[pos.get()..]).read(buf).unwrap_or(0);
+                        pos.set(pos.get() + n);
+                        n
+                    },
+                    &mut |whence| match whence {
+                        SeekFrom::Start(0) => {
+                            pos.set(0);
+                            SeekResult::Ok
+                        }
+                        _ => SeekResult::CantSeek,
+                    },
+                )
+                .map_err(|e| e.into())
             }
-            None => self.handle(&mut |_| 0).map_err(|e| e.into()),
+            None => self
+                .handle(&mut |_| 0, &mut |_| SeekResult::CantSeek)
+                .map_err(|e| e.into()),
         }
     }
 
     fn handle(
         &mut self,
         read: &mut dyn FnMut(&mut [u8]) -> usize,
+        seek: &mut dyn FnMut(SeekFrom) -> SeekResult,
     ) -> std::result::Result<String, ResponseError> {
         let mut headers = Vec::new();
         let mut body = Vec::new();
         {
             let mut handle = self.handle.transfer();
             handle.read_function(|buf| Ok(read(buf)))?;
+            handle.seek_function(|whence| seek(whence))?;
             handle.write_function(|data| {
                 body.extend_from_slice(data);
                 Ok(data.len())
@@ -195,6 +195,7 @@ pub fn resolve_with_config_raw(
         &VersionPreferences::default(),
         Some(config),
         true,
+        &HashSet::new(),
     );
 
     // The largest test in our suite takes less then 30 sec.
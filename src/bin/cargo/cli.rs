@@ -32,7 +32,8 @@ pub fn main(config: &mut LazyConfig) -> CliResult {
     // the [alias] table).
     let config = config.get_mut();
 
-    let (expanded_args, global_args) = expand_aliases(config, args, vec![])?;
+    let alias_templates = super::alias_templates_enabled(config, &args);
+    let (expanded_args, global_args) = expand_aliases(config, args, alias_templates, vec![])?;
 
     if expanded_args
         .get_one::<String>("unstable-features")
@@ -127,14 +128,18 @@ Run with 'cargo -Z [FLAG] [COMMAND]'",
                         drop_println!(config, "    {}", name);
                     }
                 }
-                CommandInfo::Alias { target } => {
-                    drop_println!(
+                CommandInfo::Alias {
+                    target,
+                    description,
+                } => match description {
+                    Some(desc) => drop_println!(config, "    {:<20} {}", name, desc),
+                    None => drop_println!(
                         config,
                         "    {:<20} alias: {}",
                         name,
                         target.iter().join(" ")
-                    );
-                }
+                    ),
+                },
             }
         }
         return Ok(());
@@ -229,6 +234,7 @@ fn add_ssl(version_string: &mut String) {
 fn expand_aliases(
     config: &mut Config,
     args: ArgMatches,
+    alias_templates: bool,
     mut already_expanded: Vec<String>,
 ) -> Result<(ArgMatches, GlobalArgs), CliError> {
     if let Some((cmd, args)) = args.subcommand() {
@@ -279,11 +285,30 @@ For more information, see issue #10049 <https://github.com/rust-lang/cargo/issue
                     }
                 }
 
-                let mut alias = alias
-                    .into_iter()
-                    .map(|s| OsString::from(s))
+                let trailing = args
+                    .get_many::<OsString>("")
+                    .unwrap_or_default()
+                    .cloned()
                     .collect::<Vec<_>>();
-                alias.extend(args.get_many::<OsString>("").unwrap_or_default().cloned());
+                let alias = if alias.is_template {
+                    if !alias_templates {
+                        return Err(anyhow::format_err!(
+                            "the table form of alias `{}` is unstable, \
+                             pass `-Z alias-templates` to enable it",
+                            cmd
+                        )
+                        .into());
+                    }
+                    super::expand_alias_template(cmd, &alias.argv, trailing)?
+                } else {
+                    let mut argv = alias
+                        .argv
+                        .into_iter()
+                        .map(|s| OsString::from(s))
+                        .collect::<Vec<_>>();
+                    argv.extend(trailing);
+                    argv
+                };
                 // new_args strips out everything before the subcommand, so
                 // capture those global options now.
                 // Note that an alias to an external command will not receive
@@ -304,7 +329,8 @@ For more information, see issue #10049 <https://github.com/rust-lang/cargo/issue
                     .into());
                 }
 
-                let (expanded_args, _) = expand_aliases(config, new_args, already_expanded)?;
+                let (expanded_args, _) =
+                    expand_aliases(config, new_args, alias_templates, already_expanded)?;
                 return Ok((expanded_args, global_args));
             }
             (None, Err(e)) => return Err(e.into()),
@@ -341,6 +367,13 @@ fn config_configure(
     if let Some(values) = args.get_many::<String>("config") {
         config_args.extend(values.cloned());
     }
+    if let Some(name) = subcommand_args._value_of("machine-profile") {
+        config.set_machine_profile_arg();
+        config_args.push(format!(
+            "build.machine-profile={}",
+            toml_edit::Value::from(name)
+        ));
+    }
     config.configure(
         verbose,
         quiet,
@@ -352,6 +385,34 @@ fn config_configure(
         &unstable_flags,
         &config_args,
     )?;
+    // Commands like `cargo lock` take these arguments in their subcommands.
+    let lock_args = match subcommand_args.subcommand() {
+        Some((_, args)) => args,
+        None => subcommand_args,
+    };
+    if lock_args.flag("allow-patch-update") {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--allow-patch-update", None)?;
+        config.set_allow_patch_update(true);
+    }
+    if let Some(name) = lock_args._value_of("lockfile") {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--lockfile", None)?;
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow::format_err!(
+                "invalid lock file name `{}`, only letters, numbers, `-` and `_` are allowed",
+                name
+            )
+            .into());
+        }
+        config.set_lockfile(Some(name.to_string()));
+    }
     Ok(())
 }
 
@@ -74,6 +74,8 @@ Example uses:
 - Depend on crates with the same name from different registries"),
         ])
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_package("Package to modify")
         .arg_quiet()
         .arg_dry_run("Don't actually write the manifest")
@@ -29,22 +29,27 @@ pub fn cli() -> Command {
         .arg_release("Build artifacts in release mode, with optimizations")
         .arg_profile("Build artifacts with the specified profile")
         .arg_features()
+        .arg_auto_required_features()
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
-        .arg(
-            opt(
-                "out-dir",
-                "Copy final artifacts to this directory (unstable)",
-            )
-            .value_name("PATH"),
-        )
+        .arg_machine_profile()
+        .arg(opt("out-dir", "Copy final artifacts to this directory").value_name("PATH"))
+        .arg(flag(
+            "include-artifact-deps",
+            "Also copy the artifacts of artifact dependencies to the `--out-dir`",
+        ))
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_ignore_rust_version()
+        .arg_require_toolchain()
         .arg_message_format()
         .arg_build_plan()
         .arg_unit_graph()
         .arg_future_incompat_report()
+        .arg_warn_unused_dependencies()
         .arg_timings()
+        .arg_warnings()
         .after_help("Run `cargo help build` for more detailed information.\n")
 }
 
@@ -63,10 +68,14 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
         let out_dir = out_dir.resolve_path(config);
         compile_opts.build_config.export_dir = Some(out_dir);
     }
-    if compile_opts.build_config.export_dir.is_some() {
-        config
-            .cli_unstable()
-            .fail_if_stable_opt("--out-dir", 6790)?;
+    if args.flag("include-artifact-deps") {
+        if compile_opts.build_config.export_dir.is_none() {
+            return Err(anyhow::format_err!(
+                "the `--include-artifact-deps` flag requires an `--out-dir`"
+            )
+            .into());
+        }
+        compile_opts.build_config.export_artifact_deps = true;
     }
     ops::compile(&ws, &compile_opts)?;
     Ok(())
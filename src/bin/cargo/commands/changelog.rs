@@ -0,0 +1,37 @@
+use crate::command_prelude::*;
+
+use cargo::ops::{self, ChangelogFormat, ChangelogOptions};
+
+pub fn cli() -> Command {
+    subcommand("changelog")
+        .about("Draft a changelog for the workspace members from the git history")
+        .arg_quiet()
+        .arg(
+            opt(
+                "since",
+                "Git revision (usually a tag) to collect the changes from",
+            )
+            .value_name("REV")
+            .required(true),
+        )
+        .arg(
+            opt("format", "Output format")
+                .value_name("FMT")
+                .value_parser(ChangelogFormat::POSSIBLE_VALUES)
+                .default_value("markdown"),
+        )
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "changelog", None)?;
+    let ws = args.workspace(config)?;
+    let opts = ChangelogOptions {
+        since: args.get_one::<String>("since").unwrap().clone(),
+        format: args.get_one::<String>("format").unwrap().parse()?,
+    };
+    ops::changelog(&ws, &opts)?;
+    Ok(())
+}
@@ -29,14 +29,21 @@ pub fn cli() -> Command {
         .arg_release("Check artifacts in release mode, with optimizations")
         .arg_profile("Check artifacts with the specified profile")
         .arg_features()
+        .arg_auto_required_features()
         .arg_target_triple("Check for the target triple")
         .arg_target_dir()
+        .arg_machine_profile()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_ignore_rust_version()
+        .arg_require_toolchain()
         .arg_message_format()
         .arg_unit_graph()
         .arg_future_incompat_report()
+        .arg_warn_unused_dependencies()
         .arg_timings()
+        .arg_warnings()
         .after_help("Run `cargo help check` for more detailed information.\n")
 }
 
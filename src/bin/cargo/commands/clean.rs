@@ -9,15 +9,34 @@ pub fn cli() -> Command {
         .arg_quiet()
         .arg_package_spec_simple("Package to clean artifacts for")
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_target_triple("Target triple to clean output for")
         .arg_target_dir()
         .arg_release("Whether or not to clean release artifacts")
         .arg_profile("Clean artifacts of the specified profile")
         .arg_doc("Whether or not to clean just the documentation directory")
+        .arg(flag(
+            "gc",
+            "Remove the global target directories of workspaces that no longer exist (unstable)",
+        ))
         .after_help("Run `cargo help clean` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    if args.flag("gc") {
+        if !config.cli_unstable().global_target_dir {
+            return Err(anyhow::format_err!(
+                "the `--gc` flag requires `-Z global-target-dir`\n\
+                 See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#global-target-dir \
+                 for more information about the `--gc` flag."
+            )
+            .into());
+        }
+        ops::clean_global_target_dirs(config)?;
+        return Ok(());
+    }
+
     let ws = args.workspace(config)?;
 
     if args.is_present_with_zero_values("package") {
@@ -29,6 +29,32 @@ pub fn cli() -> Command {
                         .default_value("yes"),
                 ),
         )
+        .subcommand(
+            subcommand("set")
+                .about("Set a config value in a config file")
+                .arg(
+                    Arg::new("key")
+                        .action(ArgAction::Set)
+                        .required(true)
+                        .help("The config key to set"),
+                )
+                .arg(
+                    Arg::new("value")
+                        .action(ArgAction::Set)
+                        .required(true)
+                        .help("The value, as TOML (unquoted strings are accepted)"),
+                )
+                .arg(
+                    opt("file", "Config file to edit")
+                        .value_name("PATH")
+                        .value_parser(clap::value_parser!(std::path::PathBuf)),
+                )
+                .arg(flag("global", "Edit the config file in $CARGO_HOME").conflicts_with("file")),
+        )
+        .subcommand(
+            subcommand("lint")
+                .about("Check machine profiles and show where their values come from"),
+        )
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
@@ -45,6 +71,24 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
             };
             cargo_config::get(config, &opts)?;
         }
+        Some(("set", args)) => {
+            let location = if let Some(path) = args.get_one::<std::path::PathBuf>("file") {
+                cargo_config::SetLocation::File(path.clone())
+            } else if args.flag("global") {
+                cargo_config::SetLocation::Global
+            } else {
+                cargo_config::SetLocation::Auto
+            };
+            let opts = cargo_config::SetOptions {
+                key: args.get_one::<String>("key").unwrap(),
+                value: args.get_one::<String>("value").unwrap(),
+                location,
+            };
+            cargo_config::set(config, &opts)?;
+        }
+        Some(("lint", _args)) => {
+            cargo_config::lint(config)?;
+        }
         Some((cmd, _)) => {
             unreachable!("unexpected command {}", cmd)
         }
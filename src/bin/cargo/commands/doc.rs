@@ -22,6 +22,23 @@ pub fn cli() -> Command {
             "Don't build documentation for dependencies",
         ))
         .arg(flag("document-private-items", "Document private items"))
+        .arg(flag(
+            "include-private",
+            "Document private items of workspace members only (unstable)",
+        ))
+        .arg(flag(
+            "doc-manifest",
+            "Write a doc-manifest.json listing the documented crates (unstable)",
+        ))
+        .arg(
+            opt(
+                "output-format",
+                "The format of the documentation: html (default) or json (unstable)",
+            )
+            .value_name("FMT")
+            .value_parser(["html", "json"])
+            .conflicts_with_all(["open", "doc-manifest"]),
+        )
         .arg_jobs()
         .arg_targets_lib_bin_example(
             "Document only this package's library",
@@ -35,25 +52,48 @@ pub fn cli() -> Command {
         .arg_features()
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
+        .arg_machine_profile()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_message_format()
         .arg_ignore_rust_version()
+        .arg_require_toolchain()
         .arg_unit_graph()
         .arg_timings()
+        .arg_warnings()
         .after_help("Run `cargo help doc` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    for flag in ["include-private", "doc-manifest"] {
+        if args.flag(flag) {
+            config
+                .cli_unstable()
+                .fail_if_stable_opt(&format!("--{}", flag), None)?;
+        }
+    }
+
+    let output_format = args.get_one::<String>("output-format");
+    if output_format.is_some() {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--output-format", None)?;
+    }
+
     let ws = args.workspace(config)?;
     let mode = CompileMode::Doc {
         deps: !args.flag("no-deps"),
+        json: output_format.map(String::as_str) == Some("json"),
     };
     let mut compile_opts =
         args.compile_options(config, mode, Some(&ws), ProfileChecking::Custom)?;
     compile_opts.rustdoc_document_private_items = args.flag("document-private-items");
+    compile_opts.rustdoc_document_private_members = args.flag("include-private");
 
     let doc_opts = DocOptions {
         open_result: args.flag("open"),
+        output_manifest: args.flag("doc-manifest"),
         compile_opts,
     };
     ops::doc(&ws, &doc_opts)?;
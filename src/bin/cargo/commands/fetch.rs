@@ -8,6 +8,8 @@ pub fn cli() -> Command {
         .about("Fetch dependencies of a package from the network")
         .arg_quiet()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_target_triple("Fetch dependencies for the target triple")
         .after_help("Run `cargo help fetch` for more detailed information.\n")
 }
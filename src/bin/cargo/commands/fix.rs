@@ -27,9 +27,13 @@ pub fn cli() -> Command {
         .arg_release("Fix artifacts in release mode, with optimizations")
         .arg_profile("Build artifacts with the specified profile")
         .arg_features()
+        .arg_auto_required_features()
         .arg_target_triple("Fix for the target triple")
         .arg_target_dir()
+        .arg_machine_profile()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_message_format()
         .arg(flag(
             "broken-code",
@@ -53,6 +57,7 @@ pub fn cli() -> Command {
             "Fix code even if the working directory has staged changes",
         ))
         .arg_ignore_rust_version()
+        .arg_require_toolchain()
         .arg_timings()
         .after_help("Run `cargo help fix` for more detailed information.\n")
 }
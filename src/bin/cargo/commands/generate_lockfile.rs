@@ -7,6 +7,8 @@ pub fn cli() -> Command {
         .about("Generate the lockfile for a package")
         .arg_quiet()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .after_help("Run `cargo help generate-lockfile` for more detailed information.\n")
 }
 
@@ -1,5 +1,5 @@
-use crate::aliased_command;
 use crate::command_prelude::*;
+use crate::{aliased_command, Alias};
 use cargo::util::errors::CargoResult;
 use cargo::{drop_println, Config};
 use cargo_util::paths::resolve_executable;
@@ -37,16 +37,20 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
 
 fn try_help(config: &Config, subcommand: &str) -> CargoResult<bool> {
     let subcommand = match check_alias(config, subcommand) {
-        // If this alias is more than a simple subcommand pass-through, show the alias.
-        Some(argv) if argv.len() > 1 => {
-            let alias = argv.join(" ");
-            drop_println!(config, "`{}` is aliased to `{}`", subcommand, alias);
+        // If this alias is more than a simple subcommand pass-through, or
+        // documents itself, show the alias.
+        Some(alias) if alias.argv.len() > 1 || alias.description.is_some() => {
+            let argv = alias.argv.join(" ");
+            drop_println!(config, "`{}` is aliased to `{}`", subcommand, argv);
+            if let Some(description) = &alias.description {
+                drop_println!(config, "\n{}", description);
+            }
             return Ok(true);
         }
         // Otherwise, resolve the alias into its subcommand.
-        Some(argv) => {
+        Some(alias) => {
             // An alias with an empty argv can be created via `"empty-alias" = ""`.
-            let first = argv.get(0).map(String::as_str).unwrap_or(subcommand);
+            let first = alias.argv.get(0).map(String::as_str).unwrap_or(subcommand);
             first.to_string()
         }
         None => subcommand.to_string(),
@@ -82,7 +86,7 @@ fn try_help(config: &Config, subcommand: &str) -> CargoResult<bool> {
 /// Checks if the given subcommand is an alias.
 ///
 /// Returns None if it is not an alias.
-fn check_alias(config: &Config, subcommand: &str) -> Option<Vec<String>> {
+fn check_alias(config: &Config, subcommand: &str) -> Option<Alias> {
     aliased_command(config, subcommand).ok().flatten()
 }
 
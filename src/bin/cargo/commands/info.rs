@@ -0,0 +1,57 @@
+use crate::command_prelude::*;
+
+use cargo::ops::{self, InfoFormat, InfoOptions};
+
+pub fn cli() -> Command {
+    subcommand("info")
+        .about("Display information about a package in the registry (unstable)")
+        .arg_quiet()
+        .arg(
+            Arg::new("crate")
+                .value_name("CRATE[@VERSION]")
+                .help("The package to display, optionally with a version or version requirement")
+                .required(true),
+        )
+        .arg_index()
+        .arg(opt("registry", "Registry to use").value_name("REGISTRY"))
+        .arg(
+            opt("message-format", "Output format")
+                .value_name("FMT")
+                .value_parser(["human", "json"]),
+        )
+        .after_help(
+            "The versions, features and dependencies are read from the index of the registry. \
+             The description, links and downloads are read from the web API of the registry \
+             when it has one.\n",
+        )
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "info", None)?;
+    let spec = args.get_one::<String>("crate").unwrap();
+    let (krate, version) = match spec.split_once('@') {
+        Some((krate, version)) => (krate, Some(version.to_string())),
+        None => (spec.as_str(), None),
+    };
+    if krate.is_empty() {
+        return Err(anyhow::format_err!("missing crate name in `{}`", spec).into());
+    }
+    let format = match args.get_one::<String>("message-format").map(String::as_str) {
+        Some("json") => InfoFormat::Json,
+        _ => InfoFormat::Human,
+    };
+
+    ops::info(
+        config,
+        &InfoOptions {
+            krate: krate.to_string(),
+            version,
+            index: args.index()?,
+            registry: args.registry(config)?,
+            format,
+        },
+    )?;
+    Ok(())
+}
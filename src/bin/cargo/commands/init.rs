@@ -9,14 +9,38 @@ pub fn cli() -> Command {
         .arg(Arg::new("path").action(ArgAction::Set).default_value("."))
         .arg(opt("registry", "Registry to use").value_name("REGISTRY"))
         .arg_new_opts()
+        .arg(
+            flag(
+                "workspace",
+                "Create a virtual workspace with the packages in the directory as members (unstable)",
+            )
+            .conflicts_with_all([
+                "bin", "lib", "name", "edition", "registry", "template", "member",
+            ]),
+        )
         .after_help("Run `cargo help init` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     let opts = args.new_options(config)?;
+    if args.flag("workspace") {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--workspace", None)?;
+        let members = ops::init_workspace(&opts, config)?;
+        let created = match members.len() {
+            0 => "virtual workspace".to_string(),
+            1 => "virtual workspace with 1 member".to_string(),
+            n => format!("virtual workspace with {} members", n),
+        };
+        config.shell().status("Created", created)?;
+        return Ok(());
+    }
     let project_kind = ops::init(&opts, config)?;
-    config
-        .shell()
-        .status("Created", format!("{} package", project_kind))?;
+    let created = match &opts.template {
+        Some(template) => format!("package from template `{}`", template),
+        None => format!("{} package", project_kind),
+    };
+    config.shell().status("Created", created)?;
     Ok(())
 }
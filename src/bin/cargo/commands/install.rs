@@ -53,6 +53,31 @@ pub fn cli() -> Command {
         .arg_jobs()
         .arg(flag("force", "Force overwriting existing crates or binaries").short('f'))
         .arg(flag("no-track", "Do not save tracking information"))
+        .arg(
+            flag(
+                "use-project-config",
+                "Use the lockfile of the project at --path like `cargo build` (unstable)",
+            )
+            .requires("path"),
+        )
+        .arg(
+            flag(
+                "reuse-target-dir",
+                "Build in a target directory shared with other installs (unstable)",
+            )
+            .conflicts_with("target-dir"),
+        )
+        .arg(
+            flag(
+                "orphans",
+                "Only list packages whose source path no longer exists (unstable)",
+            )
+            .requires("list"),
+        )
+        .arg(flag(
+            "prefer-binary",
+            "Install pre-built binaries referenced by the package if available (unstable)",
+        ))
         .arg_features()
         .arg_profile("Install artifacts with the specified profile")
         .arg(flag(
@@ -67,6 +92,7 @@ pub fn cli() -> Command {
         )
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
+        .arg_machine_profile()
         .arg(opt("root", "Directory to install packages into").value_name("DIR"))
         .arg(
             opt("index", "Registry index to install from")
@@ -86,6 +112,29 @@ pub fn cli() -> Command {
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    let use_project_config = args.flag("use-project-config");
+    if use_project_config {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--use-project-config", None)?;
+    }
+    let reuse_target_dir = args.flag("reuse-target-dir");
+    if reuse_target_dir {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--reuse-target-dir", None)?;
+    }
+    if args.flag("orphans") {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--orphans", None)?;
+    }
+    let prefer_binary = args.flag("prefer-binary");
+    if prefer_binary {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--prefer-binary", None)?;
+    }
     let path = args.value_of_path("path", config);
     if let Some(path) = &path {
         config.reload_rooted_at(path)?;
@@ -162,7 +211,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
         args.get_profile_name(config, "release", ProfileChecking::Custom)?;
 
     if args.flag("list") {
-        ops::install_list(root, config)?;
+        ops::install_list(root, args.flag("orphans"), config)?;
     } else {
         ops::install(
             config,
@@ -173,6 +222,9 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
             &compile_opts,
             args.flag("force"),
             args.flag("no-track"),
+            use_project_config,
+            reuse_target_dir,
+            prefer_binary,
         )?;
     }
     Ok(())
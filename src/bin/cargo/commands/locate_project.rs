@@ -1,7 +1,10 @@
 use crate::command_prelude::*;
 use anyhow::bail;
+use cargo::core::Workspace;
+use cargo::util::important_paths::find_toolchain_file;
 use cargo::{drop_println, CargoResult};
 use serde::Serialize;
+use std::path::PathBuf;
 
 pub fn cli() -> Command {
     subcommand("locate-project")
@@ -16,26 +19,72 @@ pub fn cli() -> Command {
             .value_name("FMT"),
         )
         .arg(flag("workspace", "Locate Cargo.toml of the workspace root"))
+        .arg(flag(
+            "details",
+            "Also describe the workspace, without resolving dependencies (unstable)",
+        ))
         .after_help("Run `cargo help locate-project` for more detailed information.\n")
 }
 
 #[derive(Serialize)]
 pub struct ProjectLocation<'a> {
     root: &'a str,
+    #[serde(flatten)]
+    details: Option<ProjectDetails>,
+}
+
+/// The description of the workspace printed with `--details`.
+#[derive(Serialize)]
+struct ProjectDetails {
+    workspace_root: PathBuf,
+    members: Vec<PathBuf>,
+    toolchain_file: Option<PathBuf>,
+    config_files: Vec<PathBuf>,
+    target_directory: PathBuf,
+}
+
+impl ProjectDetails {
+    fn new(ws: &Workspace<'_>, config: &Config) -> CargoResult<ProjectDetails> {
+        Ok(ProjectDetails {
+            workspace_root: ws.root_manifest().to_path_buf(),
+            members: ws
+                .members()
+                .map(|pkg| pkg.manifest_path().to_path_buf())
+                .collect(),
+            toolchain_file: find_toolchain_file(config.cwd()),
+            config_files: config.config_files()?,
+            target_directory: ws.target_dir().into_path_unlocked(),
+        })
+    }
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    let details = args.flag("details");
+    if details {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--details", None)?;
+    }
+    let message_format = MessageFormat::parse(args)?;
+    if details && matches!(message_format, MessageFormat::Plain) {
+        return Err(
+            anyhow::format_err!("the `--details` flag requires `--message-format json`").into(),
+        );
+    }
+
+    let what = WhatToFind::parse(args);
+    let workspace = if details || matches!(what, WhatToFind::Workspace) {
+        Some(args.workspace(config)?)
+    } else {
+        None
+    };
     let root_manifest;
-    let workspace;
-    let root = match WhatToFind::parse(args) {
+    let root = match what {
         WhatToFind::CurrentManifest => {
             root_manifest = args.root_manifest(config)?;
             &root_manifest
         }
-        WhatToFind::Workspace => {
-            workspace = args.workspace(config)?;
-            workspace.root_manifest()
-        }
+        WhatToFind::Workspace => workspace.as_ref().unwrap().root_manifest(),
     };
 
     let root = root
@@ -48,9 +97,13 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
         })
         .map_err(|e| CliError::new(e, 1))?;
 
-    let location = ProjectLocation { root };
+    let details = match &workspace {
+        Some(ws) if details => Some(ProjectDetails::new(ws, config)?),
+        _ => None,
+    };
+    let location = ProjectLocation { root, details };
 
-    match MessageFormat::parse(args)? {
+    match message_format {
         MessageFormat::Json => config.shell().print_json(&location)?,
         MessageFormat::Plain => drop_println!(config, "{}", location.root),
     }
@@ -0,0 +1,98 @@
+use crate::command_prelude::*;
+
+use cargo::core::ResolveVersion;
+use cargo::ops::{self, LockDedupeOptions, LockFmtOptions, UpgradeFormatOptions};
+
+pub fn cli() -> Command {
+    subcommand("lock")
+        .about("Inspect and migrate the Cargo.lock file")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            subcommand("upgrade-format")
+                .about("Change Cargo.lock to a newer (or older) format version")
+                .arg_quiet()
+                .arg(
+                    opt(
+                        "to",
+                        "Lock file version to change to (defaults to the latest)",
+                    )
+                    .value_name("VERSION")
+                    .value_parser(clap::value_parser!(u32)),
+                )
+                .arg_dry_run("Show the changes without writing Cargo.lock")
+                .arg_manifest_path()
+                .arg_lockfile()
+                .arg_allow_patch_update(),
+        )
+        .subcommand(
+            subcommand("fmt")
+                .about("Rewrite Cargo.lock in the latest format and canonical order")
+                .arg_quiet()
+                .arg_dry_run("Show whether Cargo.lock changes without writing it")
+                .arg_manifest_path()
+                .arg_lockfile()
+                .arg_allow_patch_update(),
+        )
+        .subcommand(
+            subcommand("dedupe")
+                .about("Unify packages locked in several semver compatible versions")
+                .arg_quiet()
+                .arg_dry_run("Show the changes without writing Cargo.lock")
+                .arg_manifest_path()
+                .arg_lockfile()
+                .arg_allow_patch_update(),
+        )
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "lock", None)?;
+    match args.subcommand() {
+        Some(("upgrade-format", args)) => upgrade_format(config, args),
+        Some(("fmt", args)) => fmt(config, args),
+        Some(("dedupe", args)) => dedupe(config, args),
+        Some((cmd, _)) => {
+            unreachable!("unexpected command {}", cmd)
+        }
+        None => {
+            unreachable!("unexpected command")
+        }
+    }
+}
+
+fn upgrade_format(config: &Config, args: &ArgMatches) -> CliResult {
+    let ws = args.workspace(config)?;
+    let to = match args.get_one::<u32>("to") {
+        Some(&n) => ResolveVersion::from_number(n)?,
+        None => ResolveVersion::default(),
+    };
+    let opts = UpgradeFormatOptions {
+        config,
+        to,
+        dry_run: args.dry_run(),
+    };
+    ops::upgrade_lockfile_format(&ws, &opts)?;
+    Ok(())
+}
+
+fn fmt(config: &Config, args: &ArgMatches) -> CliResult {
+    let ws = args.workspace(config)?;
+    let opts = LockFmtOptions {
+        config,
+        dry_run: args.dry_run(),
+    };
+    ops::format_lockfile(&ws, &opts)?;
+    Ok(())
+}
+
+fn dedupe(config: &Config, args: &ArgMatches) -> CliResult {
+    let ws = args.workspace(config)?;
+    let opts = LockDedupeOptions {
+        config,
+        dry_run: args.dry_run(),
+    };
+    ops::dedupe_lockfile(&ws, &opts)?;
+    Ok(())
+}
@@ -20,7 +20,20 @@ pub fn cli() -> Command {
             "Output information only about the workspace members \
              and don't fetch dependencies",
         ))
+        .arg(
+            flag(
+                "features-graph",
+                "Describe the features of every package, and what activated them (unstable)",
+            )
+            .conflicts_with("no-deps"),
+        )
+        .arg(flag(
+            "build-scripts",
+            "Include the OUT_DIR of every build script which has already run (unstable)",
+        ))
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg(
             opt("format-version", "Format version")
                 .value_name("VERSION")
@@ -43,11 +56,27 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
         Some(version) => version.parse().unwrap(),
     };
 
+    let features_graph = args.flag("features-graph");
+    if features_graph {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--features-graph", None)?;
+    }
+
+    let build_scripts = args.flag("build-scripts");
+    if build_scripts {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--build-scripts", None)?;
+    }
+
     let options = OutputMetadataOptions {
         cli_features: args.cli_features()?,
         no_deps: args.flag("no-deps"),
         filter_platforms: args._values_of("filter-platform"),
         version,
+        features_graph,
+        build_scripts,
     };
 
     let result = ops::output_metadata(&ws, &options)?;
@@ -5,6 +5,7 @@ pub fn builtin() -> Vec<Command> {
         add::cli(),
         bench::cli(),
         build::cli(),
+        changelog::cli(),
         check::cli(),
         clean::cli(),
         config::cli(),
@@ -14,9 +15,11 @@ pub fn builtin() -> Vec<Command> {
         generate_lockfile::cli(),
         git_checkout::cli(),
         help::cli(),
+        info::cli(),
         init::cli(),
         install::cli(),
         locate_project::cli(),
+        lock::cli(),
         login::cli(),
         logout::cli(),
         metadata::cli(),
@@ -30,13 +33,17 @@ pub fn builtin() -> Vec<Command> {
         report::cli(),
         run::cli(),
         rustc::cli(),
+        rustc_info::cli(),
         rustdoc::cli(),
+        script::cli(),
         search::cli(),
+        semver_check::cli(),
         test::cli(),
         tree::cli(),
         uninstall::cli(),
         update::cli(),
         vendor::cli(),
+        verify_mirror::cli(),
         verify_project::cli(),
         version::cli(),
         yank::cli(),
@@ -48,6 +55,7 @@ pub fn builtin_exec(cmd: &str) -> Option<fn(&mut Config, &ArgMatches) -> CliResu
         "add" => add::exec,
         "bench" => bench::exec,
         "build" => build::exec,
+        "changelog" => changelog::exec,
         "check" => check::exec,
         "clean" => clean::exec,
         "config" => config::exec,
@@ -57,9 +65,11 @@ pub fn builtin_exec(cmd: &str) -> Option<fn(&mut Config, &ArgMatches) -> CliResu
         "generate-lockfile" => generate_lockfile::exec,
         "git-checkout" => git_checkout::exec,
         "help" => help::exec,
+        "info" => info::exec,
         "init" => init::exec,
         "install" => install::exec,
         "locate-project" => locate_project::exec,
+        "lock" => lock::exec,
         "login" => login::exec,
         "logout" => logout::exec,
         "metadata" => metadata::exec,
@@ -73,13 +83,17 @@ pub fn builtin_exec(cmd: &str) -> Option<fn(&mut Config, &ArgMatches) -> CliResu
         "report" => report::exec,
         "run" => run::exec,
         "rustc" => rustc::exec,
+        "rustc-info" => rustc_info::exec,
         "rustdoc" => rustdoc::exec,
+        "script" => script::exec,
         "search" => search::exec,
+        "semver-check" => semver_check::exec,
         "test" => test::exec,
         "tree" => tree::exec,
         "uninstall" => uninstall::exec,
         "update" => update::exec,
         "vendor" => vendor::exec,
+        "verify-mirror" => verify_mirror::exec,
         "verify-project" => verify_project::exec,
         "version" => version::exec,
         "yank" => yank::exec,
@@ -91,6 +105,7 @@ pub fn builtin_exec(cmd: &str) -> Option<fn(&mut Config, &ArgMatches) -> CliResu
 pub mod add;
 pub mod bench;
 pub mod build;
+pub mod changelog;
 pub mod check;
 pub mod clean;
 pub mod config;
@@ -100,9 +115,11 @@ pub mod fix;
 pub mod generate_lockfile;
 pub mod git_checkout;
 pub mod help;
+pub mod info;
 pub mod init;
 pub mod install;
 pub mod locate_project;
+pub mod lock;
 pub mod login;
 pub mod logout;
 pub mod metadata;
@@ -116,13 +133,17 @@ pub mod remove;
 pub mod report;
 pub mod run;
 pub mod rustc;
+pub mod rustc_info;
 pub mod rustdoc;
+pub mod script;
 pub mod search;
+pub mod semver_check;
 pub mod test;
 pub mod tree;
 pub mod uninstall;
 pub mod update;
 pub mod vendor;
+pub mod verify_mirror;
 pub mod verify_project;
 pub mod version;
 pub mod yank;
@@ -22,9 +22,10 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     } else {
         path
     };
-    config.shell().status(
-        "Created",
-        format!("{} `{}` package", opts.kind, package_name),
-    )?;
+    let created = match &opts.template {
+        Some(template) => format!("`{}` package from template `{}`", package_name, template),
+        None => format!("{} `{}` package", opts.kind, package_name),
+    };
+    config.shell().status("Created", created)?;
     Ok(())
 }
@@ -1,12 +1,23 @@
 use crate::command_prelude::*;
 
-use cargo::ops::{self, OwnersOptions};
+use cargo::ops::{self, OwnersListFormat, OwnersOptions};
 
 pub fn cli() -> Command {
     subcommand("owner")
         .about("Manage the owners of a crate on the registry")
         .arg_quiet()
-        .arg(Arg::new("crate").action(ArgAction::Set))
+        .arg(
+            Arg::new("crate")
+                .help("The crates to manage the owners of, more than one is unstable")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            flag(
+                "workspace",
+                "Manage the owners of all publishable workspace members (unstable)",
+            )
+            .conflicts_with("crate"),
+        )
         .arg(
             multi_opt(
                 "add",
@@ -24,6 +35,12 @@ pub fn cli() -> Command {
             .short('r'),
         )
         .arg(flag("list", "List owners of a crate").short('l'))
+        .arg(
+            opt("message-format", "Output format of `--list` (unstable)")
+                .value_name("FMT")
+                .value_parser(["human", "json"])
+                .requires("list"),
+        )
         .arg(opt("index", "Registry index to modify owners for").value_name("INDEX"))
         .arg(opt("token", "API token to use when authenticating").value_name("TOKEN"))
         .arg(opt("registry", "Registry to use").value_name("REGISTRY"))
@@ -32,8 +49,37 @@ pub fn cli() -> Command {
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     let registry = args.registry(config)?;
+    let krates: Vec<String> = args
+        .get_many::<String>("crate")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let list_format = match args.get_one::<String>("message-format").map(String::as_str) {
+        Some("json") => OwnersListFormat::Json,
+        _ => OwnersListFormat::Human,
+    };
+
+    if krates.len() > 1 && !config.cli_unstable().unstable_options {
+        return Err(anyhow::format_err!(
+            "the `crate` argument given more than once is unstable, \
+             pass `-Z unstable-options` to enable it"
+        )
+        .into());
+    }
+    if args.flag("workspace") {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--workspace", None)?;
+    }
+    if list_format == OwnersListFormat::Json {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--message-format", None)?;
+    }
+
     let opts = OwnersOptions {
-        krate: args.get_one::<String>("crate").cloned(),
+        krates,
+        workspace: args.flag("workspace"),
         token: args.get_one::<String>("token").cloned(),
         index: args.get_one::<String>("index").cloned(),
         to_add: args
@@ -43,6 +89,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
             .get_many::<String>("remove")
             .map(|xs| xs.cloned().collect()),
         list: args.flag("list"),
+        list_format,
         registry,
     };
     ops::modify_owners(config, &opts)?;
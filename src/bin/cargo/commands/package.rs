@@ -1,6 +1,6 @@
 use crate::command_prelude::*;
 
-use cargo::ops::{self, PackageOpts};
+use cargo::ops::{self, PackageListFormat, PackageOpts};
 
 pub fn cli() -> Command {
     subcommand("package")
@@ -13,6 +13,20 @@ pub fn cli() -> Command {
             )
             .short('l'),
         )
+        .arg(
+            opt("message-format", "Output format of `--list`")
+                .value_name("FMT")
+                .value_parser(["human", "json"])
+                .requires("list"),
+        )
+        .arg(
+            opt(
+                "diff",
+                "Print the files changed since VERSION was published, instead of packaging (unstable)",
+            )
+            .value_name("VERSION")
+            .conflicts_with("list"),
+        )
         .arg(flag(
             "no-verify",
             "Don't verify the contents by building them",
@@ -25,6 +39,11 @@ pub fn cli() -> Command {
             "allow-dirty",
             "Allow dirty working directories to be packaged",
         ))
+        .arg(multi_opt(
+            "allow-dirty-paths",
+            "GLOB",
+            "Allow uncommitted changes in files matching the glob (unstable)",
+        ))
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
         .arg_features()
@@ -34,13 +53,34 @@ pub fn cli() -> Command {
             "Don't assemble specified packages",
         )
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_jobs()
         .after_help("Run `cargo help package` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     let ws = args.workspace(config)?;
+    let allow_dirty_paths = args._values_of("allow-dirty-paths");
+    let diff = args.get_one::<String>("diff").cloned();
+    if diff.is_some() {
+        config.cli_unstable().fail_if_stable_opt("--diff", None)?;
+    }
+    if !allow_dirty_paths.is_empty() {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--allow-dirty-paths", None)?;
+    }
     let specs = args.packages_from_flags()?;
+    let list_format = match args.get_one::<String>("message-format").map(String::as_str) {
+        Some("json") => {
+            config
+                .cli_unstable()
+                .fail_if_stable_opt("--message-format", None)?;
+            PackageListFormat::Json
+        }
+        _ => PackageListFormat::Human,
+    };
 
     ops::package(
         &ws,
@@ -48,8 +88,11 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
             config,
             verify: !args.flag("no-verify"),
             list: args.flag("list"),
+            list_format,
+            diff,
             check_metadata: !args.flag("no-metadata"),
             allow_dirty: args.flag("allow-dirty"),
+            allow_dirty_paths,
             to_package: specs,
             targets: args.targets(),
             jobs: args.jobs()?,
@@ -10,6 +10,8 @@ pub fn cli() -> Command {
         .arg(Arg::new("spec").action(ArgAction::Set))
         .arg_package("Argument to get the package ID specifier for")
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .after_help("Run `cargo help pkgid` for more detailed information.\n")
 }
 
@@ -16,20 +16,43 @@ pub fn cli() -> Command {
             "allow-dirty",
             "Allow dirty working directories to be packaged",
         ))
+        .arg(multi_opt(
+            "allow-dirty-paths",
+            "GLOB",
+            "Allow uncommitted changes in files matching the glob (unstable)",
+        ))
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
         .arg_package("Package to publish")
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_features()
         .arg_jobs()
         .arg_dry_run("Perform all checks without uploading")
+        .arg(flag(
+            "allow-breaking",
+            "Publish even if the semver check detects breaking changes (unstable)",
+        ))
         .arg(opt("registry", "Registry to publish to").value_name("REGISTRY"))
         .after_help("Run `cargo help publish` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    if args.flag("allow-breaking") && !config.cli_unstable().semver_check {
+        return Err(anyhow::format_err!(
+            "the `--allow-breaking` flag is unstable, pass `-Z semver-check` to enable it"
+        )
+        .into());
+    }
     let registry = args.registry(config)?;
     let ws = args.workspace(config)?;
+    let allow_dirty_paths = args._values_of("allow-dirty-paths");
+    if !allow_dirty_paths.is_empty() {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--allow-dirty-paths", None)?;
+    }
     let index = args.index()?;
 
     ops::publish(
@@ -40,6 +63,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
             index,
             verify: !args.flag("no-verify"),
             allow_dirty: args.flag("allow-dirty"),
+            allow_dirty_paths,
             to_publish: args.packages_from_flags()?,
             targets: args.targets(),
             jobs: args.jobs()?,
@@ -47,6 +71,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
             dry_run: args.dry_run(),
             registry,
             cli_features: args.cli_features()?,
+            allow_breaking: args.flag("allow-breaking"),
         },
     )?;
     Ok(())
@@ -26,6 +26,8 @@ pub fn cli() -> clap::Command {
             .help("Dependencies to be removed")])
         .arg_package("Package to remove from")
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_quiet()
         .arg_dry_run("Don't actually write the manifest")
         .next_help_heading("Section")
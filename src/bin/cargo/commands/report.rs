@@ -1,6 +1,7 @@
 use crate::command_prelude::*;
-use cargo::core::compiler::future_incompat::{OnDiskReports, REPORT_PREAMBLE};
+use cargo::core::compiler::future_incompat::{DiffSince, OnDiskReports, REPORT_PREAMBLE};
 use cargo::drop_println;
+use cargo::ops::cargo_report;
 
 pub fn cli() -> Command {
     subcommand("report")
@@ -19,13 +20,92 @@ pub fn cli() -> Command {
                     )
                     .value_name("id"),
                 )
-                .arg_package("Package to display a report for"),
+                .arg_package("Package to display a report for")
+                .arg(
+                    opt(
+                        "diff-since",
+                        "Only show the lints introduced since an earlier report (unstable)",
+                    )
+                    .value_name("DATE|lock")
+                    .conflicts_with("package"),
+                ),
+        )
+        .subcommand(
+            subcommand("dep-kinds")
+                .about(
+                    "Reports through which kinds of dependencies each package is \
+                     reachable from the workspace members (unstable)",
+                )
+                .arg(
+                    opt("format", "Output format")
+                        .value_parser(cargo_report::ReportFormat::POSSIBLE_VALUES)
+                        .default_value("human"),
+                )
+                .arg_manifest_path()
+                .arg_lockfile()
+                .arg_allow_patch_update(),
+        )
+        .subcommand(
+            subcommand("build-script-output")
+                .about("Prints the saved output of the last build script runs (unstable)")
+                .arg_package_spec_simple("Package to print the build script output of")
+                .arg_manifest_path()
+                .arg_lockfile()
+                .arg_allow_patch_update(),
+        )
+        .subcommand(
+            subcommand("size")
+                .about(
+                    "Prints the sizes of the binaries of the last build with \
+                     `--timings=size` (unstable)",
+                )
+                .arg_package_spec_simple("Package to print the binary sizes of")
+                .arg(
+                    opt("format", "Output format")
+                        .value_parser(cargo_report::ReportFormat::POSSIBLE_VALUES)
+                        .default_value("human"),
+                )
+                .arg_manifest_path()
+                .arg_lockfile()
+                .arg_allow_patch_update(),
+        )
+        .subcommand(
+            subcommand("outdated")
+                .about(
+                    "Reports the dependencies which have newer versions in their \
+                     registry (unstable)",
+                )
+                .arg(
+                    opt("format", "Output format")
+                        .value_parser(cargo_report::ReportFormat::POSSIBLE_VALUES)
+                        .default_value("human"),
+                )
+                .arg_manifest_path()
+                .arg_lockfile()
+                .arg_allow_patch_update(),
+        )
+        .subcommand(
+            subcommand("unused-patches")
+                .about("Explains why `[patch]` entries weren't used (unstable)")
+                .arg(
+                    opt("format", "Output format")
+                        .value_parser(cargo_report::ReportFormat::POSSIBLE_VALUES)
+                        .default_value("human"),
+                )
+                .arg_manifest_path()
+                .arg_lockfile()
+                .arg_allow_patch_update(),
         )
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     match args.subcommand() {
         Some(("future-incompatibilities", args)) => report_future_incompatibilities(config, args),
+        Some(("dep-kinds", args)) => report_dep_kinds(config, args),
+        Some(("build-script-output", args)) => report_build_script_output(config, args),
+        Some(("size", args)) => report_size(config, args),
+        Some(("outdated", args)) => report_outdated(config, args),
+        Some(("unused-patches", args)) => report_unused_patches(config, args),
         Some((cmd, _)) => {
             unreachable!("unexpected command {}", cmd)
         }
@@ -41,9 +121,69 @@ fn report_future_incompatibilities(config: &Config, args: &ArgMatches) -> CliRes
     let id = args
         .value_of_u32("id")?
         .unwrap_or_else(|| reports.last_id());
+    if let Some(since) = args.get_one::<String>("diff-since") {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--diff-since", None)?;
+        let since: DiffSince = since.parse()?;
+        let diff = reports.get_diff(id, &since)?;
+        drop(config.shell().print_ansi_stdout(diff.as_bytes()));
+        return Ok(());
+    }
     let krate = args.get_one::<String>("package").map(String::as_str);
     let report = reports.get_report(id, config, krate)?;
     drop_println!(config, "{}", REPORT_PREAMBLE);
     drop(config.shell().print_ansi_stdout(report.as_bytes()));
     Ok(())
 }
+
+fn report_dep_kinds(config: &Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "report dep-kinds", None)?;
+    let ws = args.workspace(config)?;
+    let format = args.get_one::<String>("format").unwrap().parse()?;
+    cargo_report::report_dep_kinds(&ws, format)?;
+    Ok(())
+}
+
+fn report_build_script_output(config: &Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "report build-script-output", None)?;
+    let ws = args.workspace(config)?;
+    let specs = values(args, "package");
+    cargo_report::report_build_script_output(&ws, &specs)?;
+    Ok(())
+}
+
+fn report_size(config: &Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "report size", None)?;
+    let ws = args.workspace(config)?;
+    let specs = values(args, "package");
+    let format = args.get_one::<String>("format").unwrap().parse()?;
+    cargo_report::report_size(&ws, &specs, format)?;
+    Ok(())
+}
+
+fn report_outdated(config: &Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "report outdated", None)?;
+    let ws = args.workspace(config)?;
+    let format = args.get_one::<String>("format").unwrap().parse()?;
+    cargo_report::report_outdated(&ws, format)?;
+    Ok(())
+}
+
+fn report_unused_patches(config: &Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "report unused-patches", None)?;
+    let ws = args.workspace(config)?;
+    let format = args.get_one::<String>("format").unwrap().parse()?;
+    cargo_report::report_unused_patches(&ws, format)?;
+    Ok(())
+}
@@ -27,11 +27,16 @@ pub fn cli() -> Command {
         .arg_features()
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
+        .arg_machine_profile()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_message_format()
         .arg_unit_graph()
         .arg_ignore_rust_version()
+        .arg_require_toolchain()
         .arg_timings()
+        .arg_warnings()
         .after_help("Run `cargo help run` for more detailed information.\n")
 }
 
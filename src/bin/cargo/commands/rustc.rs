@@ -46,12 +46,17 @@ pub fn cli() -> Command {
             "Comma separated list of types of crates for the compiler to emit",
         ))
         .arg_target_dir()
+        .arg_machine_profile()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_message_format()
         .arg_unit_graph()
         .arg_ignore_rust_version()
+        .arg_require_toolchain()
         .arg_future_incompat_report()
         .arg_timings()
+        .arg_warnings()
         .after_help("Run `cargo help rustc` for more detailed information.\n")
 }
 
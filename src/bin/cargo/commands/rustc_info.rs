@@ -0,0 +1,23 @@
+use crate::command_prelude::*;
+
+use cargo::ops::{self, RustcInfoOptions};
+
+pub fn cli() -> Command {
+    subcommand("rustc-info")
+        .about("Print what Cargo knows about rustc and the targets as JSON (unstable)")
+        .arg_quiet()
+        .arg_target_triple("Target triple to describe, in addition to the host")
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "rustc-info", None)?;
+    let ws = args.workspace(config)?;
+    let opts = RustcInfoOptions {
+        targets: args.targets(),
+    };
+    ops::rustc_info(&ws, &opts)?;
+    Ok(())
+}
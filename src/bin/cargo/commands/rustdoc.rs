@@ -30,11 +30,16 @@ pub fn cli() -> Command {
         .arg_features()
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
+        .arg_machine_profile()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_message_format()
         .arg_unit_graph()
         .arg_ignore_rust_version()
+        .arg_require_toolchain()
         .arg_timings()
+        .arg_warnings()
         .after_help("Run `cargo help rustdoc` for more detailed information.\n")
 }
 
@@ -42,7 +47,10 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     let ws = args.workspace(config)?;
     let mut compile_opts = args.compile_options_for_single_package(
         config,
-        CompileMode::Doc { deps: false },
+        CompileMode::Doc {
+            deps: false,
+            json: false,
+        },
         Some(&ws),
         ProfileChecking::Custom,
     )?;
@@ -54,6 +62,7 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     };
     let doc_opts = DocOptions {
         open_result: args.flag("open"),
+        output_manifest: false,
         compile_opts,
     };
     ops::doc(&ws, &doc_opts)?;
@@ -0,0 +1,70 @@
+use crate::command_prelude::*;
+use cargo::core::{Verbosity, Workspace};
+use cargo::ops;
+use cargo_util::ProcessError;
+
+pub fn cli() -> Command {
+    subcommand("script")
+        .about("Run a single-file package (unstable)")
+        .arg_quiet()
+        .arg(
+            Arg::new("script")
+                .help("Path of the `.rs` file to run")
+                .value_parser(value_parser!(std::path::PathBuf))
+                .required(true),
+        )
+        .arg(
+            Arg::new("args")
+                .help("Arguments for the script")
+                .value_parser(value_parser!(std::ffi::OsString))
+                .num_args(0..)
+                .trailing_var_arg(true),
+        )
+        .arg_jobs()
+        .arg_release("Build the script in release mode, with optimizations")
+        .arg_profile("Build the script with the specified profile")
+        .arg_features()
+        .arg_target_triple("Build for the target triple")
+        .arg_target_dir()
+        .arg_message_format()
+        .arg_ignore_rust_version()
+        .after_help(
+            "The manifest of the script is read from a `cargo` code block of its leading \
+             inner doc comments (`//!`).\n",
+        )
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    if !config.cli_unstable().script {
+        return Err(anyhow::format_err!(
+            "the `cargo script` command is unstable, pass `-Z script` to enable it"
+        )
+        .into());
+    }
+    let script = args.get_one::<std::path::PathBuf>("script").unwrap();
+    let manifest_path = ops::prepare_script(config, script)?;
+    let ws = Workspace::new(&manifest_path, config)?;
+    let compile_opts = args.compile_options(
+        config,
+        CompileMode::Build,
+        Some(&ws),
+        ProfileChecking::Custom,
+    )?;
+
+    ops::run(&ws, &compile_opts, &values_os(args, "args")).map_err(|err| {
+        let proc_err = match err.downcast_ref::<ProcessError>() {
+            Some(e) => e,
+            None => return CliError::new(err, 101),
+        };
+        let exit_code = match proc_err.code {
+            Some(exit) => exit,
+            None => return CliError::new(err, 101),
+        };
+        // Like `cargo run`, the script is assumed to explain its failure.
+        if config.shell().verbosity() == Verbosity::Quiet {
+            CliError::code(exit_code)
+        } else {
+            CliError::new(err, exit_code)
+        }
+    })
+}
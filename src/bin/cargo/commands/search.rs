@@ -18,6 +18,23 @@ pub fn cli() -> Command {
             .value_name("LIMIT"),
         )
         .arg(opt("registry", "Registry to use").value_name("REGISTRY"))
+        .arg(
+            opt("sort", "Order of the results (unstable)")
+                .value_name("ORDER")
+                .value_parser([
+                    "relevance",
+                    "downloads",
+                    "recent-downloads",
+                    "recent-updates",
+                    "new",
+                    "alpha",
+                ]),
+        )
+        .arg(
+            opt("message-format", "Output format of the results (unstable)")
+                .value_name("FMT")
+                .value_parser(["human", "json"]),
+        )
         .after_help("Run `cargo help search` for more detailed information.\n")
 }
 
@@ -32,6 +49,23 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
         .map(String::as_str)
         .collect();
     let query: String = query.join("+");
-    ops::search(&query, config, index, limit, registry)?;
+    let sort = args.get_one::<String>("sort").map(String::as_str);
+    let format = match args.get_one::<String>("message-format").map(String::as_str) {
+        Some("json") => ops::SearchFormat::Json,
+        _ => ops::SearchFormat::Human,
+    };
+
+    if sort.is_some() {
+        config.cli_unstable().fail_if_stable_opt("--sort", None)?;
+    }
+    if format == ops::SearchFormat::Json {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--message-format", None)?;
+    }
+
+    // Relevance is the default order of registries.
+    let sort = sort.filter(|sort| *sort != "relevance");
+    ops::search(&query, config, index, limit, registry, sort, format)?;
     Ok(())
 }
@@ -0,0 +1,58 @@
+use crate::command_prelude::*;
+
+use cargo::ops::{self, SemverBaseline, SemverCheckOptions};
+
+pub fn cli() -> Command {
+    subcommand("semver-check")
+        .about("Check a library for breaking changes compared to a baseline version")
+        .arg_quiet()
+        .arg_package("Package to check")
+        .arg(
+            opt(
+                "baseline-version",
+                "Version published in the registry to compare to",
+            )
+            .value_name("VERSION"),
+        )
+        .arg(
+            opt(
+                "baseline-rev",
+                "Git revision of the workspace to compare to",
+            )
+            .value_name("REV")
+            .conflicts_with("baseline-version"),
+        )
+        .arg(opt("registry", "Registry of the baseline version").value_name("REGISTRY"))
+        .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
+        .after_help(
+            "By default, the current sources are compared to the newest older version \
+             published in the registry.\n",
+        )
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    if !config.cli_unstable().semver_check {
+        return Err(anyhow::format_err!(
+            "the `cargo semver-check` command is unstable, pass `-Z semver-check` to enable it"
+        )
+        .into());
+    }
+    let ws = args.workspace(config)?;
+    let baseline = match (
+        args.get_one::<String>("baseline-version"),
+        args.get_one::<String>("baseline-rev"),
+    ) {
+        (Some(version), _) => SemverBaseline::Version(version.clone()),
+        (None, Some(rev)) => SemverBaseline::Rev(rev.clone()),
+        (None, None) => SemverBaseline::Latest,
+    };
+    let opts = SemverCheckOptions {
+        to_check: args.packages_from_flags()?,
+        baseline,
+        registry: args.registry(config)?,
+    };
+    ops::semver_check(&ws, &opts)?;
+    Ok(())
+}
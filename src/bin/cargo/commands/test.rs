@@ -48,14 +48,20 @@ pub fn cli() -> Command {
         .arg_release("Build artifacts in release mode, with optimizations")
         .arg_profile("Build artifacts with the specified profile")
         .arg_features()
+        .arg_auto_required_features()
         .arg_target_triple("Build for the target triple")
         .arg_target_dir()
+        .arg_machine_profile()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_ignore_rust_version()
+        .arg_require_toolchain()
         .arg_message_format()
         .arg_unit_graph()
         .arg_future_incompat_report()
         .arg_timings()
+        .arg_warnings()
         .after_help(
             "Run `cargo help test` for more detailed information.\n\
              Run `cargo test -- --help` for test binary options.\n",
@@ -14,6 +14,8 @@ pub fn cli() -> Command {
         .about("Display a tree visualization of a dependency graph")
         .arg_quiet()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg_package_spec_no_all(
             "Package to be used as the root of the tree",
             "Display the tree for all packages in the workspace",
@@ -78,6 +80,11 @@ pub fn cli() -> Command {
             .short('d')
             .alias("duplicate"),
         )
+        .arg(flag(
+            "explain-edges",
+            "Show the dependency kinds and features behind each edge of an inverted tree \
+             (unstable)",
+        ))
         .arg(
             opt("charset", "Character set to use in output: utf8, ascii")
                 .value_name("CHARSET")
@@ -199,11 +206,28 @@ subtree of the package given to -p.\n\
         graph_features,
         max_display_depth: args.value_of_u32("depth")?.unwrap_or(u32::MAX),
         no_proc_macro,
+        explain_edges: args.flag("explain-edges"),
     };
 
     if opts.graph_features && opts.duplicates {
         return Err(format_err!("the `-e features` flag does not support `--duplicates`").into());
     }
+    if opts.explain_edges {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--explain-edges", None)?;
+        if opts.invert.is_empty() && !opts.duplicates {
+            return Err(format_err!(
+                "the `--explain-edges` flag requires `--invert` or `--duplicates`"
+            )
+            .into());
+        }
+        if opts.graph_features {
+            return Err(
+                format_err!("the `-e features` flag does not support `--explain-edges`").into(),
+            );
+        }
+    }
 
     tree::build_and_print(&ws, &opts)?;
     Ok(())
@@ -10,12 +10,38 @@ pub fn cli() -> Command {
         .arg_package_spec_simple("Package to uninstall")
         .arg(multi_opt("bin", "NAME", "Only uninstall the binary NAME"))
         .arg(opt("root", "Directory to uninstall packages from").value_name("DIR"))
+        .arg(
+            flag(
+                "workspace",
+                "Uninstall all packages installed from the workspace (unstable)",
+            )
+            .conflicts_with_all(&["spec", "package", "bin"]),
+        )
+        .arg(
+            opt(
+                "path",
+                "Path of the workspace, defaults to the current directory",
+            )
+            .value_name("PATH")
+            .requires("workspace"),
+        )
         .after_help("Run `cargo help uninstall` for more detailed information.\n")
 }
 
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     let root = args.get_one::<String>("root").map(String::as_str);
 
+    if args.flag("workspace") {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--workspace", None)?;
+        let path = args
+            .value_of_path("path", config)
+            .unwrap_or_else(|| config.cwd().to_path_buf());
+        ops::uninstall_workspace(root, &path, config)?;
+        return Ok(());
+    }
+
     if args.is_present_with_zero_values("package") {
         return Err(anyhow::anyhow!(
             "\"--package <SPEC>\" requires a SPEC format value.\n\
@@ -22,7 +22,26 @@ pub fn cli() -> Command {
             .value_name("PRECISE")
             .requires("package"),
         )
+        .arg(
+            flag(
+                "force-checksum",
+                "Accept the checksum the registry lists for SPEC, after downloading it again \
+                 (unstable)",
+            )
+            .requires("package")
+            .conflicts_with_all(["aggressive", "precise"]),
+        )
+        .arg(
+            opt(
+                "message-format",
+                "Also print the changes of the lock file in this format (unstable)",
+            )
+            .value_name("FMT")
+            .value_parser(["human", "json"]),
+        )
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .after_help("Run `cargo help update` for more detailed information.\n")
 }
 
@@ -33,12 +52,28 @@ pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
         print_available_packages(&ws)?;
     }
 
+    let force_checksum = args.flag("force-checksum");
+    if force_checksum {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--force-checksum", None)?;
+    }
+
+    let json = args.get_one::<String>("message-format").map(String::as_str) == Some("json");
+    if json {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--message-format", None)?;
+    }
+
     let update_opts = UpdateOptions {
         aggressive: args.flag("aggressive"),
         precise: args.get_one::<String>("precise").map(String::as_str),
         to_update: values(args, "package"),
         dry_run: args.dry_run(),
         workspace: args.flag("workspace"),
+        force_checksum,
+        json,
         config,
     };
     ops::update_lockfile(&ws, &update_opts)?;
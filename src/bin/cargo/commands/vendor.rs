@@ -7,6 +7,8 @@ pub fn cli() -> Command {
         .about("Vendor all dependencies for a project locally")
         .arg_quiet()
         .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
         .arg(
             Arg::new("path")
                 .action(ArgAction::Set)
@@ -34,6 +36,24 @@ pub fn cli() -> Command {
             "versioned-dirs",
             "Always include version in subdir name",
         ))
+        .arg(flag(
+            "prune",
+            "Leave out the tests, benches and examples of vendored crates (unstable)",
+        ))
+        .arg(
+            flag(
+                "reproducible",
+                "Normalize permissions and record an integrity manifest (unstable)",
+            )
+            .conflicts_with("no-delete"),
+        )
+        .arg(
+            flag(
+                "verify",
+                "Check the vendor directory against its checksums instead of vendoring (unstable)",
+            )
+            .conflicts_with_all(&["no-delete", "tomls", "prune", "reproducible"]),
+        )
         .arg(flag("no-merge-sources", "Not supported").hide(true))
         .arg(flag("relative-path", "Not supported").hide(true))
         .arg(flag("only-git-deps", "Not supported").hide(true))
@@ -78,11 +98,24 @@ https://github.com/rust-lang/cargo/issues/new
         .into());
     }
 
-    let ws = args.workspace(config)?;
+    for flag in ["prune", "reproducible", "verify"] {
+        if args.flag(flag) {
+            config
+                .cli_unstable()
+                .fail_if_stable_opt(&format!("--{}", flag), None)?;
+        }
+    }
+
     let path = args
         .get_one::<PathBuf>("path")
         .cloned()
         .unwrap_or_else(|| PathBuf::from("vendor"));
+    if args.flag("verify") {
+        ops::verify_vendor(config, &config.cwd().join(&path))?;
+        return Ok(());
+    }
+
+    let ws = args.workspace(config)?;
     ops::vendor(
         &ws,
         &ops::VendorOptions {
@@ -94,6 +127,8 @@ https://github.com/rust-lang/cargo/issues/new
                 .unwrap_or_default()
                 .cloned()
                 .collect(),
+            prune: args.flag("prune"),
+            reproducible: args.flag("reproducible"),
         },
     )?;
     Ok(())
@@ -0,0 +1,21 @@
+use crate::command_prelude::*;
+
+use cargo::ops;
+
+pub fn cli() -> Command {
+    subcommand("verify-mirror")
+        .about("Check the replacement sources in use against the checksums of upstream")
+        .arg_quiet()
+        .arg_manifest_path()
+        .arg_lockfile()
+        .arg_allow_patch_update()
+}
+
+pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
+    config
+        .cli_unstable()
+        .fail_if_stable_command(config, "verify-mirror", None)?;
+    let ws = args.workspace(config)?;
+    ops::verify_mirror(&ws)?;
+    Ok(())
+}
@@ -8,14 +8,30 @@ pub fn cli() -> Command {
         .arg_quiet()
         .arg(Arg::new("crate").action(ArgAction::Set))
         .arg(
-            opt("version", "The version to yank or un-yank")
-                .alias("vers")
-                .value_name("VERSION"),
+            opt(
+                "version",
+                "The version to yank or un-yank, or a version requirement (unstable)",
+            )
+            .alias("vers")
+            .value_name("VERSION")
+            .action(ArgAction::Append),
         )
         .arg(flag(
             "undo",
             "Undo a yank, putting a version back into the index",
         ))
+        .arg(
+            opt(
+                "reason",
+                "Reason for the yank, sent to the registry (unstable)",
+            )
+            .value_name("REASON")
+            .conflicts_with("undo"),
+        )
+        .arg(flag(
+            "dry-run",
+            "Show the versions which would be yanked without yanking them (unstable)",
+        ))
         .arg(opt("index", "Registry index to yank from").value_name("INDEX"))
         .arg(opt("token", "API token to use when authenticating").value_name("TOKEN"))
         .arg(opt("registry", "Registry to use").value_name("REGISTRY"))
@@ -25,22 +41,53 @@ pub fn cli() -> Command {
 pub fn exec(config: &mut Config, args: &ArgMatches) -> CliResult {
     let registry = args.registry(config)?;
 
+    let mut versions: Vec<&str> = args
+        .get_many::<String>("version")
+        .unwrap_or_default()
+        .map(String::as_str)
+        .collect();
     let (krate, version) = resolve_crate(
         args.get_one::<String>("crate").map(String::as_str),
-        args.get_one::<String>("version").map(String::as_str),
+        versions.first().copied(),
     )?;
-    if version.is_none() {
+    if versions.is_empty() {
+        versions.extend(version);
+    }
+    if versions.is_empty() {
         return Err(anyhow::format_err!("`--version` is required").into());
     }
 
+    // Several versions and version requirements are unstable.
+    let batch = versions.len() > 1
+        || versions.iter().any(|v| {
+            v.parse::<semver::Version>().is_err() && v.parse::<semver::VersionReq>().is_ok()
+        });
+    if batch {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--version", None)?;
+    }
+    if args.contains_id("reason") {
+        config.cli_unstable().fail_if_stable_opt("--reason", None)?;
+    }
+    if args.flag("dry-run") {
+        config
+            .cli_unstable()
+            .fail_if_stable_opt("--dry-run", None)?;
+    }
+
     ops::yank(
         config,
-        krate.map(|s| s.to_string()),
-        version.map(|s| s.to_string()),
-        args.get_one::<String>("token").cloned(),
-        args.get_one::<String>("index").cloned(),
-        args.flag("undo"),
-        registry,
+        &ops::YankOptions {
+            krate: krate.map(|s| s.to_string()),
+            versions: versions.into_iter().map(|s| s.to_string()).collect(),
+            token: args.get_one::<String>("token").cloned(),
+            index: args.get_one::<String>("index").cloned(),
+            undo: args.flag("undo"),
+            registry,
+            reason: args.get_one::<String>("reason").cloned(),
+            dry_run: args.flag("dry-run"),
+        },
     )?;
     Ok(())
 }
@@ -7,7 +7,7 @@ use cargo::util::{self, closest_msg, command_prelude, CargoResult, CliResult, Co
 use cargo_util::{ProcessBuilder, ProcessError};
 use std::collections::BTreeMap;
 use std::env;
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -54,6 +54,25 @@ fn builtin_aliases_execs(cmd: &str) -> Option<&(&str, &str, &str)> {
     BUILTIN_ALIASES.iter().find(|alias| alias.0 == cmd)
 }
 
+/// A command alias.
+pub struct Alias {
+    /// The command line the alias expands to.
+    pub argv: Vec<String>,
+    /// Documentation of the alias, shown by `cargo help <alias>`.
+    pub description: Option<String>,
+    /// Whether the alias is defined as a table, whose command may contain
+    /// `{N}` placeholders for the positional arguments.
+    pub is_template: bool,
+}
+
+/// An alias defined as a table in the `[alias]` config table, see
+/// `-Zalias-templates`.
+#[derive(serde::Deserialize)]
+struct AliasTable {
+    command: StringOrVec,
+    description: Option<String>,
+}
+
 /// Resolve the aliased command from the [`Config`] with a given command string.
 ///
 /// The search fallback chain is:
@@ -61,27 +80,149 @@ fn builtin_aliases_execs(cmd: &str) -> Option<&(&str, &str, &str)> {
 /// 1. Get the aliased command as a string.
 /// 2. If an `Err` occurs (missing key, type mismatch, or any possible error),
 ///    try to get it as an array again.
-/// 3. If still cannot find any, finds one insides [`BUILTIN_ALIASES`].
-fn aliased_command(config: &Config, command: &str) -> CargoResult<Option<Vec<String>>> {
+/// 3. If it is neither a string nor an array, try to get it as a table with
+///    a `command` and an optional `description`.
+/// 4. If still cannot find any, finds one insides [`BUILTIN_ALIASES`].
+fn aliased_command(config: &Config, command: &str) -> CargoResult<Option<Alias>> {
     let alias_name = format!("alias.{}", command);
+    let split = |s: &str| s.split_whitespace().map(|s| s.to_string()).collect();
     let user_alias = match config.get_string(&alias_name) {
-        Ok(Some(record)) => Some(
-            record
-                .val
-                .split_whitespace()
-                .map(|s| s.to_string())
-                .collect(),
-        ),
+        Ok(Some(record)) => Some(Alias {
+            argv: split(&record.val),
+            description: None,
+            is_template: false,
+        }),
         Ok(None) => None,
-        Err(_) => config.get::<Option<Vec<String>>>(&alias_name)?,
+        Err(_) => match config.get::<Option<Vec<String>>>(&alias_name) {
+            Ok(argv) => argv.map(|argv| Alias {
+                argv,
+                description: None,
+                is_template: false,
+            }),
+            Err(e) => match config.get::<Option<AliasTable>>(&alias_name) {
+                Ok(table) => table.map(|table| {
+                    let mut argv: Vec<String> = table.command.iter().cloned().collect();
+                    if argv.len() == 1 {
+                        argv = split(&argv[0]);
+                    }
+                    Alias {
+                        argv,
+                        description: table.description,
+                        is_template: true,
+                    }
+                }),
+                Err(_) => return Err(e),
+            },
+        },
     };
 
     let result = user_alias.or_else(|| {
-        builtin_aliases_execs(command).map(|command_str| vec![command_str.1.to_string()])
+        builtin_aliases_execs(command).map(|command_str| Alias {
+            argv: vec![command_str.1.to_string()],
+            description: None,
+            is_template: false,
+        })
     });
     Ok(result)
 }
 
+/// Whether `-Zalias-templates` is enabled.
+///
+/// Aliases are expanded before the config is configured with the unstable
+/// flags, so the flag is looked up in the command line `args` and the
+/// `[unstable]` table directly.
+fn alias_templates_enabled(config: &Config, args: &ArgMatches) -> bool {
+    let on_cli = args
+        .get_many::<String>("unstable-features")
+        .map_or(false, |mut flags| flags.any(|f| f == "alias-templates"));
+    let in_config = || {
+        config
+            .get::<Option<bool>>("unstable.alias-templates")
+            .ok()
+            .flatten()
+            .unwrap_or(false)
+    };
+    config.nightly_features_allowed && (on_cli || in_config())
+}
+
+/// Expands the placeholders `{1}`, `{2}`, ... of the template alias `name`
+/// with the positional arguments `args`.
+///
+/// The arguments not consumed by a placeholder are appended, like for
+/// other aliases.
+fn expand_alias_template(
+    name: &str,
+    template: &[String],
+    args: Vec<OsString>,
+) -> CargoResult<Vec<OsString>> {
+    let required = template
+        .iter()
+        .flat_map(|arg| placeholders(arg))
+        .map(|(_, n)| n)
+        .max()
+        .unwrap_or(0);
+    if args.len() < required {
+        anyhow::bail!(
+            "alias `{}` requires {} argument{}, but {} {} given\n\n\
+             Run `cargo help {}` for more information.",
+            name,
+            required,
+            if required == 1 { "" } else { "s" },
+            args.len(),
+            if args.len() == 1 { "was" } else { "were" },
+            name
+        )
+    }
+    let values = args[..required]
+        .iter()
+        .map(|arg| {
+            arg.to_str().map(str::to_string).ok_or_else(|| {
+                anyhow::format_err!(
+                    "argument `{}` of alias `{}` is not valid unicode",
+                    arg.to_string_lossy(),
+                    name
+                )
+            })
+        })
+        .collect::<CargoResult<Vec<_>>>()?;
+
+    let mut expanded = Vec::new();
+    for arg in template {
+        let mut out = String::new();
+        let mut last = 0;
+        for (range, n) in placeholders(arg) {
+            out.push_str(&arg[last..range.start]);
+            out.push_str(&values[n - 1]);
+            last = range.end;
+        }
+        out.push_str(&arg[last..]);
+        expanded.push(OsString::from(out));
+    }
+    expanded.extend(args.into_iter().skip(required));
+    Ok(expanded)
+}
+
+/// Finds the `{N}` placeholders in an argument of a template alias, returning
+/// their byte ranges and numbers.
+fn placeholders(arg: &str) -> Vec<(std::ops::Range<usize>, usize)> {
+    let mut found = Vec::new();
+    let mut rest = 0;
+    while let Some(start) = arg[rest..].find('{').map(|i| rest + i) {
+        let end = match arg[start..].find('}') {
+            Some(i) => start + i,
+            None => break,
+        };
+        match arg[start + 1..end].parse::<usize>() {
+            Ok(n) if n > 0 && arg[start + 1..end].bytes().all(|b| b.is_ascii_digit()) => {
+                found.push((start..end + 1, n));
+                rest = end + 1;
+            }
+            _ => rest = start + 1,
+        }
+    }
+    found
+}
+
 /// List all runnable commands
 fn list_commands(config: &Config) -> BTreeMap<String, CommandInfo> {
     let prefix = "cargo-";
@@ -132,12 +273,23 @@ fn list_commands(config: &Config) -> BTreeMap<String, CommandInfo> {
     }
 
     // Add the user-defined aliases
-    if let Ok(aliases) = config.get::<BTreeMap<String, StringOrVec>>("alias") {
-        for (name, target) in aliases.iter() {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum AliasValue {
+        Command(StringOrVec),
+        Table(AliasTable),
+    }
+    if let Ok(aliases) = config.get::<BTreeMap<String, AliasValue>>("alias") {
+        for (name, value) in aliases {
+            let (target, description) = match value {
+                AliasValue::Command(target) => (target, None),
+                AliasValue::Table(table) => (table.command, table.description),
+            };
             commands.insert(
-                name.to_string(),
+                name,
                 CommandInfo::Alias {
-                    target: target.clone(),
+                    target,
+                    description,
                 },
             );
         }
@@ -1,9 +1,10 @@
 /// Generate artifact information from unit dependencies for configuring the compiler environment.
 use crate::core::compiler::unit_graph::UnitDep;
 use crate::core::compiler::{Context, CrateType, FileFlavor, Unit};
-use crate::core::TargetKind;
+use crate::core::dependency::ArtifactKind;
+use crate::core::{Dependency, Target, TargetKind};
 use crate::CargoResult;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 
 /// Return all environment variables for the given unit-dependencies
@@ -55,3 +56,68 @@ fn unit_artifact_type_name_upper(unit: &Unit) -> &'static str {
         invalid => unreachable!("BUG: artifacts cannot be of type {:?}", invalid),
     }
 }
+
+/// Given a dependency with an artifact `artifact_dep` and a set of available `targets`
+/// of its package, find a target for each kind of artifacts that are to be built.
+///
+/// Failure to match any target results in an error mentioning the parent manifests
+/// `parent_package` name, along with the binaries the package actually provides if
+/// a binary was requested.
+pub(crate) fn match_artifacts_kind_with_targets<'a>(
+    artifact_dep: &Dependency,
+    targets: &'a [Target],
+    parent_package: &str,
+) -> CargoResult<HashSet<&'a Target>> {
+    let mut out = HashSet::new();
+    let artifact_requirements = artifact_dep.artifact().expect("artifact present");
+    for artifact_kind in artifact_requirements.kinds() {
+        let mut extend = |filter: &dyn Fn(&&Target) -> bool| {
+            let mut iter = targets.iter().filter(filter).peekable();
+            let found = iter.peek().is_some();
+            out.extend(iter);
+            found
+        };
+        let found = match artifact_kind {
+            ArtifactKind::Cdylib => extend(&|t| t.is_cdylib()),
+            ArtifactKind::Staticlib => extend(&|t| t.is_staticlib()),
+            ArtifactKind::AllBinaries => extend(&|t| t.is_bin()),
+            ArtifactKind::SelectedBinary(bin_name) => {
+                extend(&|t| t.is_bin() && t.name() == bin_name.as_str())
+            }
+        };
+        if !found {
+            let mut msg = format!(
+                "dependency `{}` in package `{}` requires a `{}` artifact to be present.",
+                artifact_dep.name_in_toml(),
+                parent_package,
+                artifact_kind
+            );
+            if matches!(
+                artifact_kind,
+                ArtifactKind::AllBinaries | ArtifactKind::SelectedBinary(_)
+            ) {
+                let mut bins: Vec<_> = targets
+                    .iter()
+                    .filter(|t| t.is_bin())
+                    .map(|t| t.name())
+                    .collect();
+                bins.sort_unstable();
+                let bins: Vec<_> = bins.iter().map(|name| format!("`{}`", name)).collect();
+                if bins.is_empty() {
+                    msg.push_str(&format!(
+                        "\nPackage `{}` does not provide any binaries.",
+                        artifact_dep.package_name()
+                    ));
+                } else {
+                    msg.push_str(&format!(
+                        "\nPackage `{}` provides the binaries: {}",
+                        artifact_dep.package_name(),
+                        bins.join(", ")
+                    ));
+                }
+            }
+            anyhow::bail!(msg);
+        }
+    }
+    Ok(out)
+}
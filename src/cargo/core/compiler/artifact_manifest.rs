@@ -0,0 +1,196 @@
+//! The manifests listing the final artifacts of a build.
+//!
+//! With `-Z artifact-manifest`, Cargo writes a `.artifacts.json` file into
+//! every profile directory it built artifacts for after a build, like
+//! `target/debug`, listing the files that build produced:
+//!
+//! * The files uplifted into the profile directory, like binaries.
+//! * The executables of tests and benchmarks, which stay in `deps`.
+//! * The outputs of artifact dependencies, which stay in their hashed
+//!   directory, like `deps/artifact/bar-<hash>/bin`.
+//!
+//! Each build replaces the manifest, so it only lists the artifacts of the
+//! last build.
+//!
+//! After a build with `--out-dir`, Cargo also writes a `manifest.json` into
+//! that directory, listing every file this build copied there and where it
+//! came from, so scripts don't need to know the layout of the directory:
+//!
+//! ```text
+//! out/
+//!   manifest.json
+//!   bin/foo
+//!   cdylib/libfoo.so
+//!   artifact-deps/bar/bin/bar
+//! ```
+//!
+//! Files copied by previous builds are left alone, but aren't listed.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use cargo_util::paths;
+use serde::Serialize;
+
+use super::context::OutputFile;
+use super::{CompileMode, Context, FileFlavor, Unit};
+use crate::core::{PackageId, Target};
+use crate::util::interning::InternedString;
+use crate::util::CargoResult;
+
+/// The name of the manifest in the profile directory.
+pub const MANIFEST: &str = ".artifacts.json";
+
+/// The name of the manifest in the `--out-dir`.
+pub const EXPORT_MANIFEST: &str = "manifest.json";
+
+/// The version of the format of both manifests.
+const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Manifest<T> {
+    version: u32,
+    artifacts: Vec<T>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry<'a> {
+    path: PathBuf,
+    package_id: PackageId,
+    target: &'a Target,
+    mode: CompileMode,
+    features: &'a [InternedString],
+    /// The metadata hash of the unit, which is also part of the names of
+    /// the files in `deps`.
+    hash: String,
+    /// The kind of file: `normal`, `linkable`, `debuginfo`, or `auxiliary`.
+    flavor: &'static str,
+    artifact_dep: bool,
+}
+
+#[derive(Serialize)]
+struct ExportedFile<'a> {
+    /// The path of the copy, relative to the `--out-dir` and with `/` as
+    /// the separator.
+    path: String,
+    /// The file in the target directory which was copied.
+    source: PathBuf,
+    package_id: PackageId,
+    target: &'a Target,
+    /// Whether the file is an artifact of an artifact dependency.
+    artifact_dep: bool,
+}
+
+/// Writes the manifest of every profile directory of the build.
+pub fn write(cx: &Context<'_, '_>) -> CargoResult<()> {
+    let mut manifests: BTreeMap<&Path, Vec<ManifestEntry<'_>>> = BTreeMap::new();
+    for kind in &cx.bcx.build_config.requested_kinds {
+        manifests.insert(cx.files().layout(*kind).dest(), Vec::new());
+    }
+    for_each_output(cx, |unit, output| {
+        if unit.target.is_custom_build() {
+            return;
+        }
+        let flavor = match output.flavor {
+            FileFlavor::Normal => "normal",
+            FileFlavor::DebugInfo => "debuginfo",
+            FileFlavor::Linkable => "linkable",
+            FileFlavor::Auxiliary => "auxiliary",
+            FileFlavor::Rmeta => return,
+        };
+        let path = match artifact_path(cx, unit, &output.path, output.hardlink.as_ref()) {
+            Some(path) if path.exists() => path,
+            _ => return,
+        };
+        manifests
+            .entry(cx.files().layout(unit.kind).dest())
+            .or_default()
+            .push(ManifestEntry {
+                path: path.to_path_buf(),
+                package_id: unit.pkg.package_id(),
+                target: &unit.target,
+                mode: unit.mode,
+                features: &unit.features,
+                hash: cx.files().metadata(unit).to_string(),
+                flavor,
+                artifact_dep: unit.artifact.is_true(),
+            });
+    })?;
+    for (dest, mut artifacts) in manifests {
+        artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+        write_manifest(&dest.join(MANIFEST), artifacts)?;
+    }
+    Ok(())
+}
+
+/// Writes the manifest of the `--out-dir`, if there is one.
+pub fn write_export(cx: &Context<'_, '_>) -> CargoResult<()> {
+    let export_dir = match cx.files().export_dir() {
+        Some(export_dir) => export_dir,
+        None => return Ok(()),
+    };
+    let mut artifacts = Vec::new();
+    for_each_output(cx, |unit, output| {
+        let export_path = match &output.export_path {
+            Some(export_path) if export_path.exists() => export_path,
+            _ => return,
+        };
+        let path = export_path
+            .strip_prefix(&export_dir)
+            .unwrap()
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        artifacts.push(ExportedFile {
+            path,
+            source: output.path.clone(),
+            package_id: unit.pkg.package_id(),
+            target: &unit.target,
+            artifact_dep: unit.artifact.is_true(),
+        });
+    })?;
+    artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+    paths::create_dir_all(&export_dir)?;
+    write_manifest(&export_dir.join(EXPORT_MANIFEST), artifacts)
+}
+
+/// Calls `f` with every output of every unit of the build, in a stable
+/// order.
+fn for_each_output<'a>(
+    cx: &'a Context<'_, '_>,
+    mut f: impl FnMut(&'a Unit, &OutputFile),
+) -> CargoResult<()> {
+    let mut units: Vec<_> = cx.bcx.unit_graph.keys().collect();
+    units.sort();
+    for unit in units {
+        for output in cx.outputs(unit)?.iter() {
+            f(unit, output);
+        }
+    }
+    Ok(())
+}
+
+fn write_manifest<T: Serialize>(path: &Path, artifacts: Vec<T>) -> CargoResult<()> {
+    let manifest = Manifest {
+        version: VERSION,
+        artifacts,
+    };
+    paths::write_atomic(path, serde_json::to_string_pretty(&manifest)?)
+}
+
+/// The path of an output of `unit` which is a final artifact, if it is one.
+fn artifact_path<'a>(
+    cx: &Context<'_, '_>,
+    unit: &Unit,
+    path: &'a Path,
+    hardlink: Option<&'a PathBuf>,
+) -> Option<&'a Path> {
+    if let Some(hardlink) = hardlink {
+        return Some(hardlink);
+    }
+    let is_artifact_dep = unit.artifact.is_true() && unit.mode == CompileMode::Build;
+    let is_test =
+        matches!(unit.mode, CompileMode::Test | CompileMode::Bench) && cx.bcx.roots.contains(unit);
+    (is_artifact_dep || is_test).then(|| path)
+}
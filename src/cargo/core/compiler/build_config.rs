@@ -1,5 +1,6 @@
-use crate::core::compiler::CompileKind;
+use crate::core::compiler::{CompileKind, UnusedDepsMode};
 use crate::util::interning::InternedString;
+use crate::util::toolchain::RequiredToolchain;
 use crate::util::{CargoResult, Config, RustfixDiagnosticServer};
 use anyhow::{bail, Context as _};
 use cargo_util::ProcessBuilder;
@@ -40,10 +41,26 @@ pub struct BuildConfig {
     // Note that, although the cmd-line flag name is `out-dir`, in code we use
     // `export_dir`, to avoid confusion with out dir at `target/debug/deps`.
     pub export_dir: Option<PathBuf>,
+    /// Whether the artifacts of artifact dependencies are copied to the
+    /// `export_dir` too (`--include-artifact-deps`).
+    pub export_artifact_deps: bool,
     /// `true` to output a future incompatibility report at the end of the build
     pub future_incompat_report: bool,
     /// Which kinds of build timings to output (empty if none).
     pub timing_outputs: Vec<TimingOutput>,
+    /// What to do about dependencies no unit used, if they are checked at
+    /// all (`--warn-unused-dependencies`).
+    pub unused_dependencies: Option<UnusedDepsMode>,
+    /// How warnings of workspace members are handled (`--warnings` or
+    /// `build.warnings`).
+    pub warnings: WarningHandling,
+    /// Which files are hard-linked into the profile directory
+    /// (`build.uplift`).
+    pub uplift: Vec<UpliftKind>,
+    /// The toolchain the build must use, verified before building
+    /// (`--require-toolchain`, `build.required-rust-version` or the toolchain
+    /// file).
+    pub required_toolchain: Option<RequiredToolchain>,
 }
 
 fn default_parallelism() -> CargoResult<u32> {
@@ -84,9 +101,14 @@ impl BuildConfig {
             Some(j) => j as u32,
         };
 
-        if config.cli_unstable().build_std.is_some() && requested_kinds[0].is_host() {
-            // TODO: This should eventually be fixed.
-            anyhow::bail!("-Zbuild-std requires --target");
+        if requested_kinds[0].is_host() {
+            if config.cli_unstable().build_std.is_some() {
+                // TODO: This should eventually be fixed.
+                anyhow::bail!("-Zbuild-std requires --target");
+            }
+            if cfg.std.is_some() {
+                anyhow::bail!("the `build.std` config requires --target or `build.target`");
+            }
         }
 
         Ok(BuildConfig {
@@ -102,8 +124,23 @@ impl BuildConfig {
             primary_unit_rustc: None,
             rustfix_diagnostic_server: RefCell::new(None),
             export_dir: None,
+            export_artifact_deps: false,
             future_incompat_report: false,
             timing_outputs: Vec::new(),
+            unused_dependencies: None,
+            warnings: match cfg.warnings {
+                Some(warnings) if config.cli_unstable().warnings => warnings,
+                _ => WarningHandling::Warn,
+            },
+            uplift: match &cfg.uplift {
+                Some(uplift) if config.cli_unstable().uplift => uplift.clone(),
+                _ => UpliftKind::DEFAULT.to_vec(),
+            },
+            required_toolchain: if config.cli_unstable().required_toolchain {
+                RequiredToolchain::from_config(config)?
+            } else {
+                None
+            },
         })
     }
 
@@ -138,10 +175,72 @@ pub enum MessageFormat {
         /// Whether the `rendered` field of rustc diagnostics embed ansi color
         /// codes.
         ansi: bool,
+        /// Whether the messages follow the versioned schema of `json-v2`.
+        v2: bool,
     },
     Short,
 }
 
+/// How the warnings of workspace members are handled, which is decided by
+/// Cargo rather than through lint levels passed to rustc, to keep the
+/// artifacts independent of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningHandling {
+    /// Warnings are displayed.
+    Warn,
+    /// Warnings are hidden.
+    Allow,
+    /// Warnings are displayed and fail the build.
+    Deny,
+}
+
+impl std::str::FromStr for WarningHandling {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> CargoResult<WarningHandling> {
+        match s {
+            "warn" => Ok(WarningHandling::Warn),
+            "allow" => Ok(WarningHandling::Allow),
+            "deny" => Ok(WarningHandling::Deny),
+            _ => bail!(
+                "invalid warnings setting `{}`, expected `warn`, `allow` or `deny`",
+                s
+            ),
+        }
+    }
+}
+
+/// A kind of file hard-linked out of `deps` into the profile directory, like
+/// `target/debug`, set by `build.uplift`.
+///
+/// The outputs of build scripts are always uplifted into their own directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpliftKind {
+    /// The binaries of all packages.
+    Bins,
+    /// The dynamic libraries of all packages, so the dynamic linker finds
+    /// their latest copies.
+    Dylibs,
+    /// The libraries of the packages built on the command line.
+    Libs,
+    /// The examples built on the command line, into `examples`.
+    Examples,
+    /// The outputs of artifact dependencies.
+    ArtifactDeps,
+}
+
+impl UpliftKind {
+    /// The files uplifted without `build.uplift`.
+    pub const DEFAULT: &'static [UpliftKind] = &[
+        UpliftKind::Bins,
+        UpliftKind::Dylibs,
+        UpliftKind::Libs,
+        UpliftKind::Examples,
+    ];
+}
+
 /// The general "mode" for what to do.
 /// This is used for two purposes. The commands themselves pass this in to
 /// `compile_ws` to tell it the general execution strategy. This influences
@@ -164,7 +263,8 @@ pub enum CompileMode {
     Bench,
     /// A target that will be documented with `rustdoc`.
     /// If `deps` is true, then it will also document all dependencies.
-    Doc { deps: bool },
+    /// If `json` is true, the documentation is emitted as JSON instead of HTML.
+    Doc { deps: bool, json: bool },
     /// A target that will be tested with `rustdoc`.
     Doctest,
     /// An example or library that will be scraped for function calls by `rustdoc`.
@@ -257,4 +357,6 @@ pub enum TimingOutput {
     Html,
     /// Machine-readable JSON (unstable)
     Json,
+    /// Sizes of the binaries (unstable)
+    Size,
 }
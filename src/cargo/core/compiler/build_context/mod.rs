@@ -1,7 +1,7 @@
 //! [`BuildContext`] is a (mostly) static information about a build task.
 
 use crate::core::compiler::unit_graph::UnitGraph;
-use crate::core::compiler::{BuildConfig, CompileKind, Unit};
+use crate::core::compiler::{BuildConfig, CompileKind, CompileMode, Unit};
 use crate::core::profiles::Profiles;
 use crate::core::PackageSet;
 use crate::core::Workspace;
@@ -118,6 +118,11 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
         &self.target_data.rustc
     }
 
+    /// Information of the `rustc` used to compile `unit`.
+    pub fn rustc_for(&self, unit: &Unit) -> &Rustc {
+        self.target_data.rustc_for(unit)
+    }
+
     /// Gets the user-specified linker for a particular host or target.
     pub fn linker(&self, kind: CompileKind) -> Option<PathBuf> {
         self.target_data
@@ -175,4 +180,19 @@ impl<'a, 'cfg> BuildContext<'a, 'cfg> {
     pub fn extra_args_for(&self, unit: &Unit) -> Option<&Vec<String>> {
         self.extra_compiler_args.get(unit)
     }
+
+    /// The mode `unit` is hashed with, for its metadata and its fingerprint.
+    ///
+    /// With `-Zcheck-build-sharing`, checking a library is hashed like
+    /// building it. Both then use the same `.rmeta` file and fingerprint, so
+    /// `cargo check` finds the libraries of an up-to-date `cargo build` fresh.
+    /// Building still compiles a library that was only checked, as its
+    /// fingerprint lists the missing `.rlib` among the outputs.
+    pub fn hashed_mode(&self, unit: &Unit) -> CompileMode {
+        if self.config.cli_unstable().check_build_sharing && unit.is_shareable_check() {
+            CompileMode::Build
+        } else {
+            unit.mode
+        }
+    }
 }
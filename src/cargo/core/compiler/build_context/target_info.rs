@@ -8,17 +8,19 @@
 //! * [`TargetInfo::rustc_outputs`] to get a list of supported file types.
 
 use crate::core::compiler::{
-    BuildOutput, CompileKind, CompileMode, CompileTarget, Context, CrateType,
+    BuildOutput, CompileKind, CompileMode, CompileTarget, Context, CrateType, Unit,
 };
-use crate::core::{Dependency, Package, Target, TargetKind, Workspace};
+use crate::core::{Dependency, LinksOverride, Package, PackageId, Target, TargetKind, Workspace};
 use crate::util::config::{Config, StringList, TargetConfig};
-use crate::util::{CargoResult, Rustc};
+use crate::util::interning::InternedString;
+use crate::util::{self, CargoResult, Rustc};
 use anyhow::Context as _;
 use cargo_platform::{Cfg, CfgExpr};
 use cargo_util::{paths, ProcessBuilder};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::path::{Path, PathBuf};
 use std::str::{self, FromStr};
@@ -57,6 +59,9 @@ pub struct TargetInfo {
     pub rustdocflags: Vec<String>,
     /// Whether or not rustc supports the `-Csplit-debuginfo` flag.
     pub supports_split_debuginfo: bool,
+    /// The target specification as JSON, for `CARGO_TARGET_SPEC_PATH`. Only
+    /// computed with `-Z target-spec-path`.
+    pub target_spec: Option<String>,
 }
 
 /// Kind of each file generated by a Unit, part of `FileType`.
@@ -163,6 +168,14 @@ impl TargetInfo {
             kind,
             Flags::Rust,
         )?;
+        let sysroot_args: Vec<String> = match kind {
+            CompileKind::Target(target) => provide_sysroot(config, rustc, target)?
+                .map(|sysroot| format!("--sysroot={}", sysroot.display()))
+                .into_iter()
+                .collect(),
+            CompileKind::Host => Vec::new(),
+        };
+        rustflags.extend(sysroot_args.iter().cloned());
         let mut turn = 0;
         loop {
             let extra_fingerprint = kind.fingerprint_hash();
@@ -259,7 +272,7 @@ impl TargetInfo {
 
             // recalculate `rustflags` from above now that we have `cfg`
             // information
-            let new_flags = extra_args(
+            let mut new_flags = extra_args(
                 config,
                 requested_kinds,
                 &rustc.host,
@@ -267,6 +280,7 @@ impl TargetInfo {
                 kind,
                 Flags::Rust,
             )?;
+            new_flags.extend(sysroot_args.iter().cloned());
 
             // Tricky: `RUSTFLAGS` defines the set of active `cfg` flags, active
             // `cfg` flags define which `.cargo/config` sections apply, and they
@@ -294,14 +308,23 @@ impl TargetInfo {
                 sysroot_host_libdir,
                 sysroot_target_libdir,
                 rustflags,
-                rustdocflags: extra_args(
-                    config,
-                    requested_kinds,
-                    &rustc.host,
-                    Some(&cfg),
-                    kind,
-                    Flags::Rustdoc,
-                )?,
+                rustdocflags: {
+                    let mut flags = extra_args(
+                        config,
+                        requested_kinds,
+                        &rustc.host,
+                        Some(&cfg),
+                        kind,
+                        Flags::Rustdoc,
+                    )?;
+                    flags.extend(sysroot_args.iter().cloned());
+                    flags
+                },
+                target_spec: if config.cli_unstable().target_spec_path {
+                    Some(target_spec(rustc, kind, &cfg, extra_fingerprint)?)
+                } else {
+                    None
+                },
                 cfg,
                 supports_split_debuginfo,
             });
@@ -325,6 +348,41 @@ impl TargetInfo {
         &self.cfg
     }
 
+    /// The target cfg as a JSON document, for `CARGO_CFG_JSON` and
+    /// `cargo rustc-info`.
+    ///
+    /// Names (like `unix`) are sorted, as are the keys of key-value pairs (like
+    /// `target_os`). The values of each key are kept in the order rustc reports
+    /// them. `debug_assertions` is left out, just like with `CARGO_CFG_*`.
+    pub fn cfg_json(&self) -> CargoResult<serde_json::Value> {
+        #[derive(Serialize, Default)]
+        struct CfgJson<'a> {
+            names: BTreeSet<&'a str>,
+            values: BTreeMap<&'a str, Vec<&'a str>>,
+        }
+
+        let mut json = CfgJson::default();
+        for cfg in &self.cfg {
+            match cfg {
+                Cfg::Name(n) if n == "debug_assertions" => {}
+                Cfg::Name(n) => {
+                    json.names.insert(n);
+                }
+                Cfg::KeyPair(k, v) => json.values.entry(k).or_default().push(v),
+            }
+        }
+        Ok(serde_json::to_value(&json)?)
+    }
+
+    /// The target specification as JSON, computed on demand if it isn't
+    /// already known from `-Z target-spec-path`.
+    pub fn target_spec_json(&self, rustc: &Rustc, kind: CompileKind) -> CargoResult<String> {
+        match &self.target_spec {
+            Some(spec) => Ok(spec.clone()),
+            None => target_spec(rustc, kind, &self.cfg, kind.fingerprint_hash()),
+        }
+    }
+
     /// Returns the list of file types generated by the given crate type.
     ///
     /// Returns `None` if the target does not support the given crate type.
@@ -462,6 +520,17 @@ impl TargetInfo {
                     // preserved.
                     should_replace_hyphens: true,
                 })
+            } else {
+                // With `-C split-debuginfo=packed`, the DWARF objects are
+                // packaged into a `.dwp` file named after the output, which
+                // debuggers look for next to it.
+                ret.push(FileType {
+                    suffix: format!("{}.dwp", suffix),
+                    prefix: prefix.clone(),
+                    flavor: FileFlavor::DebugInfo,
+                    crate_type: Some(crate_type.clone()),
+                    should_replace_hyphens: crate_type != CrateType::Bin,
+                })
             }
         }
 
@@ -545,6 +614,140 @@ impl TargetInfo {
     }
 }
 
+/// Returns the sysroot of the `sysroot-provider` of `target`, running the
+/// provider first if needed.
+///
+/// Sysroots are kept in `$CARGO_HOME/sysroots/<target>/<hash>`, where the
+/// hash covers the rustc version and the provider command. So the provider
+/// runs again, into a new directory, once either of them changes, and the
+/// directory provided before is removed.
+fn provide_sysroot(
+    config: &Config,
+    rustc: &Rustc,
+    target: CompileTarget,
+) -> CargoResult<Option<PathBuf>> {
+    if !config.cli_unstable().sysroot_provider {
+        return Ok(None);
+    }
+    let provider = match config
+        .target_cfg_triple(target.short_name())?
+        .sysroot_provider
+    {
+        Some(provider) => provider,
+        None => return Ok(None),
+    };
+    let mut cmd = ProcessBuilder::new(provider.val.path.resolve_program(config));
+    cmd.args(&provider.val.args);
+
+    let root = config.home().join("sysroots");
+    let _lock = root.open_rw(
+        format!(".{}.lock", target.short_name()),
+        config,
+        "sysroot cache",
+    )?;
+    let target_dir = root.as_path_unlocked().join(target.short_name());
+    let hash = util::short_hash(&(&rustc.verbose_version, cmd.to_string()));
+    let sysroot = target_dir.join(hash);
+    let complete = sysroot.join(".cargo-ok");
+    if complete.exists() {
+        return Ok(Some(sysroot));
+    }
+
+    config.shell().status(
+        "Providing",
+        format!("sysroot for `{}`", target.short_name()),
+    )?;
+    if target_dir.exists() {
+        paths::remove_dir_all(&target_dir)?;
+    }
+    paths::create_dir_all(&sysroot)?;
+    cmd.env("CARGO_SYSROOT_DIR", &sysroot)
+        .env("CARGO_SYSROOT_TARGET", target.rustc_target())
+        .env("CARGO_RUSTC_VERSION", rustc.version.to_string())
+        .env("RUSTC", &rustc.path);
+    cmd.exec_with_output().with_context(|| {
+        format!(
+            "failed to run the sysroot provider for target `{}`, defined at {}",
+            target.short_name(),
+            provider.definition
+        )
+    })?;
+    paths::write(&complete, "")?;
+    Ok(Some(sysroot))
+}
+
+/// Returns the complete target specification of `kind` as JSON.
+///
+/// The specification is printed by rustc with `--print=target-spec-json`,
+/// which is only available with nightly rustc. Otherwise custom targets use
+/// their JSON file, and built-in targets a specification derived from their
+/// `cfg`, with the subset of the options that can be known from it.
+fn target_spec(
+    rustc: &Rustc,
+    kind: CompileKind,
+    cfg: &[Cfg],
+    extra_fingerprint: u64,
+) -> CargoResult<String> {
+    let mut process = rustc.workspace_process();
+    process
+        .arg("--print=target-spec-json")
+        .arg("-Zunstable-options")
+        .env_remove("RUSTC_LOG");
+    if let CompileKind::Target(target) = kind {
+        process.arg("--target").arg(target.rustc_target());
+    }
+    if let Ok((output, _)) = rustc.cached_output(&process, extra_fingerprint) {
+        return Ok(output);
+    }
+
+    let triple = match kind {
+        CompileKind::Host => rustc.host.as_str(),
+        CompileKind::Target(target) if target.rustc_target().ends_with(".json") => {
+            return paths::read(Path::new(target.rustc_target().as_str()));
+        }
+        CompileKind::Target(target) => target.rustc_target().as_str(),
+    };
+    let mut spec = serde_json::Map::new();
+    spec.insert("llvm-target".to_string(), triple.into());
+    let mut families = Vec::new();
+    let mut max_atomic_width = None;
+    for cfg in cfg {
+        if let Cfg::KeyPair(key, value) = cfg {
+            match key.as_str() {
+                "target_arch" | "target_os" | "target_env" | "target_vendor" | "target_abi"
+                | "target_endian"
+                    if !value.is_empty() =>
+                {
+                    let key = key.strip_prefix("target_").unwrap();
+                    let key = if key == "endian" {
+                        "target-endian"
+                    } else {
+                        key
+                    };
+                    spec.insert(key.to_string(), value.as_str().into());
+                }
+                "target_pointer_width" => {
+                    if let Ok(width) = value.parse::<u64>() {
+                        spec.insert("target-pointer-width".to_string(), width.into());
+                    }
+                }
+                "target_family" => families.push(value.as_str()),
+                "target_has_atomic" => {
+                    if let Ok(width) = value.parse::<u64>() {
+                        max_atomic_width = max_atomic_width.max(Some(width));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    spec.insert("target-family".to_string(), families.into());
+    if let Some(width) = max_atomic_width {
+        spec.insert("max-atomic-width".to_string(), width.into());
+    }
+    Ok(serde_json::to_string_pretty(&spec)?)
+}
+
 /// Takes rustc output (using specialized command line args), and calculates the file prefix and
 /// suffix for the given crate type, or returns `None` if the type is not supported. (e.g., for a
 /// Rust library like `libcargo.rlib`, we have prefix "lib" and suffix "rlib").
@@ -800,6 +1003,12 @@ fn rustflags_from_build(config: &Config, flag: Flags) -> CargoResult<Option<Vec<
 pub struct RustcTargetData<'cfg> {
     /// Information about `rustc` itself.
     pub rustc: Rustc,
+    /// The compilers chosen with the `rustc` profile setting, keyed by the
+    /// setting. See [`RustcTargetData::rustc_for`].
+    pub profile_rustcs: HashMap<InternedString, Rustc>,
+    /// The root of the workspace, which relative `rustc` profile settings
+    /// are relative to.
+    ws_root: PathBuf,
 
     /// Config
     config: &'cfg Config,
@@ -816,6 +1025,12 @@ pub struct RustcTargetData<'cfg> {
     target_config: HashMap<CompileTarget, TargetConfig>,
     /// Information about the target platform that we're building for.
     target_info: HashMap<CompileTarget, TargetInfo>,
+
+    /// The `[links-overrides]` table of the workspace root.
+    links_overrides: BTreeMap<String, LinksOverride>,
+    /// The output of the build scripts skipped by `[links-overrides]` in
+    /// favor of the build script of another package.
+    skipped_script_output: BuildOutput,
 }
 
 impl<'cfg> RustcTargetData<'cfg> {
@@ -848,12 +1063,16 @@ impl<'cfg> RustcTargetData<'cfg> {
 
         let mut res = RustcTargetData {
             rustc,
+            profile_rustcs: HashMap::new(),
+            ws_root: ws.root().to_path_buf(),
             config,
             requested_kinds: requested_kinds.into(),
             host_config,
             host_info,
             target_config,
             target_info,
+            links_overrides: ws.root_links_overrides().clone(),
+            skipped_script_output: BuildOutput::default(),
         };
 
         // Get all kinds we currently know about.
@@ -904,6 +1123,64 @@ impl<'cfg> RustcTargetData<'cfg> {
         Ok(())
     }
 
+    /// Loads the compilers chosen with the `rustc` profile setting by any of
+    /// `units`.
+    ///
+    /// Target information keeps coming from the global `rustc`, so the
+    /// chosen compilers must be for the same host.
+    pub fn load_profile_rustcs<'a>(
+        &mut self,
+        units: impl Iterator<Item = &'a Unit>,
+    ) -> CargoResult<()> {
+        for unit in units {
+            let name = match unit.profile.rustc {
+                Some(name) if !self.profile_rustcs.contains_key(&name) => name,
+                _ => continue,
+            };
+            // Bare names are looked up in `PATH`, like `build.rustc`.
+            let path = Path::new(name.as_str());
+            let path = if path.components().count() > 1 {
+                self.ws_root.join(path)
+            } else {
+                path.to_path_buf()
+            };
+            let rustc = self.config.load_rustc(path, None).with_context(|| {
+                format!(
+                    "failed to load the compiler `{}` of profile `{}` for package `{}`",
+                    name, unit.profile.name, unit.pkg
+                )
+            })?;
+            if rustc.host != self.rustc.host {
+                anyhow::bail!(
+                    "the compiler `{}` of profile `{}` for package `{}` is for the host `{}`, \
+                     but the build uses a compiler for the host `{}`",
+                    name,
+                    unit.profile.name,
+                    unit.pkg,
+                    rustc.host,
+                    self.rustc.host
+                );
+            }
+            self.profile_rustcs.insert(name, rustc);
+        }
+        Ok(())
+    }
+
+    /// The compiler of `unit`, either the one chosen with the `rustc`
+    /// profile setting or the global one.
+    pub fn rustc_for(&self, unit: &Unit) -> &Rustc {
+        match unit.profile.rustc {
+            Some(name) => &self.profile_rustcs[&name],
+            None => &self.rustc,
+        }
+    }
+
+    /// All the kinds with target information, the host and every target.
+    pub fn all_kinds(&self) -> impl Iterator<Item = CompileKind> + '_ {
+        std::iter::once(CompileKind::Host)
+            .chain(self.target_info.keys().copied().map(CompileKind::Target))
+    }
+
     /// Returns a "short" name for the given kind, suitable for keying off
     /// configuration in Cargo or presenting to users.
     pub fn short_name<'a>(&'a self, kind: &'a CompileKind) -> &'a str {
@@ -949,10 +1226,31 @@ impl<'cfg> RustcTargetData<'cfg> {
 
     /// If a build script is overridden, this returns the `BuildOutput` to use.
     ///
-    /// `lib_name` is the `links` library name and `kind` is whether it is for
-    /// Host or Target.
-    pub fn script_override(&self, lib_name: &str, kind: CompileKind) -> Option<&BuildOutput> {
-        self.target_config(kind).links_overrides.get(lib_name)
+    /// `lib_name` is the `links` library name of the package `pkg_id` and
+    /// `kind` is whether it is for Host or Target.
+    ///
+    /// The `links` overrides of the target config take precedence over the
+    /// `[links-overrides]` table of the workspace root.
+    pub fn script_override(
+        &self,
+        pkg_id: PackageId,
+        lib_name: &str,
+        kind: CompileKind,
+    ) -> Option<&BuildOutput> {
+        if let Some(output) = self.target_config(kind).links_overrides.get(lib_name) {
+            return Some(output);
+        }
+        match self.links_overrides.get(lib_name)? {
+            LinksOverride::Output(output) => Some(output),
+            LinksOverride::Package(spec) if spec.matches(pkg_id) => None,
+            LinksOverride::Package(_) => Some(&self.skipped_script_output),
+        }
+    }
+
+    /// Returns `true` if several packages may use the `links` key `lib_name`,
+    /// as allowed by `[links-overrides]`.
+    pub fn is_shared_link(&self, lib_name: &str) -> bool {
+        self.links_overrides.contains_key(lib_name)
     }
 }
 
@@ -1,7 +1,7 @@
 use std::collections::{BTreeSet, HashMap};
 use std::env;
 use std::ffi::{OsStr, OsString};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use cargo_platform::CfgExpr;
 use cargo_util::{paths, ProcessBuilder};
@@ -9,7 +9,55 @@ use cargo_util::{paths, ProcessBuilder};
 use super::BuildContext;
 use crate::core::compiler::{CompileKind, Metadata, Unit};
 use crate::core::Package;
-use crate::util::{config, CargoResult, Config};
+use crate::util::config::{self, Definition, OptValue, TargetCfgConfig, Value};
+use crate::util::interning::InternedString;
+use crate::util::{CargoResult, Config};
+
+/// What kind of target is being run with [`Compilation::target_process`].
+///
+/// With `-Z target-runners`, each kind can have its own runner in the
+/// `[target]` tables (`test-runner`, `bench-runner`, `example-runner`), which
+/// is used instead of the plain `runner`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RunnerKind {
+    /// A binary run with `cargo run`.
+    Bin,
+    /// An example run with `cargo run --example`.
+    Example,
+    /// A test or doctest.
+    Test,
+    /// A benchmark.
+    Bench,
+}
+
+impl RunnerKind {
+    /// The config key of the runner specific to this kind, if any.
+    fn config_key(self) -> Option<&'static str> {
+        match self {
+            RunnerKind::Bin => None,
+            RunnerKind::Example => Some("example-runner"),
+            RunnerKind::Test => Some("test-runner"),
+            RunnerKind::Bench => Some("bench-runner"),
+        }
+    }
+
+    fn cfg_runner(self, cfg: &TargetCfgConfig) -> &OptValue<config::PathAndArgs> {
+        match self {
+            RunnerKind::Bin => &cfg.runner,
+            RunnerKind::Example => &cfg.example_runner,
+            RunnerKind::Test => &cfg.test_runner,
+            RunnerKind::Bench => &cfg.bench_runner,
+        }
+    }
+}
+
+/// A runner program from the config, with where it was defined.
+#[derive(Debug)]
+struct RunnerCandidate {
+    program: PathBuf,
+    args: Vec<String>,
+    definition: Definition,
+}
 
 /// Structure with enough information to run `rustdoc --test`.
 pub struct Doctest {
@@ -57,6 +105,9 @@ pub struct Compilation<'cfg> {
     /// The crate names of the root units specified on the command-line.
     pub root_crate_names: Vec<String>,
 
+    /// The `index.html` of every crate documented, including dependencies.
+    pub docs: Vec<UnitOutput>,
+
     /// All directories for the output of native build commands.
     ///
     /// This is currently used to drive some entries which are added to the
@@ -100,8 +151,13 @@ pub struct Compilation<'cfg> {
     /// Optional rustc process to be used for primary crates instead of either rustc_process or
     /// rustc_workspace_wrapper_process
     primary_rustc_process: Option<ProcessBuilder>,
+    /// Rustc processes for the compilers chosen with the `rustc` profile
+    /// setting, keyed by the setting, as for rustc_process and
+    /// rustc_workspace_wrapper_process
+    profile_rustc_processes: HashMap<InternedString, (ProcessBuilder, ProcessBuilder)>,
 
-    target_runners: HashMap<CompileKind, Option<(PathBuf, Vec<String>)>>,
+    /// The runners to try, in order, for each kind of target being run.
+    target_runners: HashMap<(CompileKind, RunnerKind), Vec<RunnerCandidate>>,
 }
 
 impl<'cfg> Compilation<'cfg> {
@@ -109,10 +165,20 @@ impl<'cfg> Compilation<'cfg> {
         let mut rustc = bcx.rustc().process();
         let mut primary_rustc_process = bcx.build_config.primary_unit_rustc.clone();
         let mut rustc_workspace_wrapper_process = bcx.rustc().workspace_process();
+        let mut profile_rustc_processes: HashMap<_, _> = bcx
+            .target_data
+            .profile_rustcs
+            .iter()
+            .map(|(name, rustc)| (*name, (rustc.process(), rustc.workspace_process())))
+            .collect();
 
         if bcx.config.extra_verbose() {
             rustc.display_env_vars();
             rustc_workspace_wrapper_process.display_env_vars();
+            for (rustc, workspace_process) in profile_rustc_processes.values_mut() {
+                rustc.display_env_vars();
+                workspace_process.display_env_vars();
+            }
 
             if let Some(rustc) = primary_rustc_process.as_mut() {
                 rustc.display_env_vars();
@@ -143,6 +209,7 @@ impl<'cfg> Compilation<'cfg> {
             binaries: Vec::new(),
             cdylibs: Vec::new(),
             root_crate_names: Vec::new(),
+            docs: Vec::new(),
             extra_env: HashMap::new(),
             to_doc_test: Vec::new(),
             config: bcx.config,
@@ -150,13 +217,27 @@ impl<'cfg> Compilation<'cfg> {
             rustc_process: rustc,
             rustc_workspace_wrapper_process,
             primary_rustc_process,
-            target_runners: bcx
-                .build_config
-                .requested_kinds
-                .iter()
-                .chain(Some(&CompileKind::Host))
-                .map(|kind| Ok((*kind, target_runner(bcx, *kind)?)))
-                .collect::<CargoResult<HashMap<_, _>>>()?,
+            profile_rustc_processes,
+            target_runners: {
+                let mut target_runners = HashMap::new();
+                for kind in bcx
+                    .build_config
+                    .requested_kinds
+                    .iter()
+                    .chain(Some(&CompileKind::Host))
+                {
+                    for runner_kind in [
+                        RunnerKind::Bin,
+                        RunnerKind::Example,
+                        RunnerKind::Test,
+                        RunnerKind::Bench,
+                    ] {
+                        let runners = target_runners_for(bcx, *kind, runner_kind)?;
+                        target_runners.insert((*kind, runner_kind), runners);
+                    }
+                }
+                target_runners
+            },
         })
     }
 
@@ -175,6 +256,13 @@ impl<'cfg> Compilation<'cfg> {
     ) -> CargoResult<ProcessBuilder> {
         let rustc = if is_primary && self.primary_rustc_process.is_some() {
             self.primary_rustc_process.clone().unwrap()
+        } else if let Some(name) = unit.profile.rustc {
+            let (rustc, workspace_process) = &self.profile_rustc_processes[&name];
+            if is_workspace {
+                workspace_process.clone()
+            } else {
+                rustc.clone()
+            }
         } else if is_workspace {
             self.rustc_workspace_wrapper_process.clone()
         } else {
@@ -224,8 +312,47 @@ impl<'cfg> Compilation<'cfg> {
         )
     }
 
-    pub fn target_runner(&self, kind: CompileKind) -> Option<&(PathBuf, Vec<String>)> {
-        self.target_runners.get(&kind).and_then(|x| x.as_ref())
+    /// Returns the runner to use for running a target of the given kind.
+    ///
+    /// With `-Z target-runners`, the configured runners are tried in order
+    /// and the first one that can be found is returned. An error listing
+    /// every attempt is returned if none of them can be found.
+    pub fn target_runner(
+        &self,
+        kind: CompileKind,
+        runner_kind: RunnerKind,
+    ) -> CargoResult<Option<(PathBuf, Vec<String>)>> {
+        let runners = match self.target_runners.get(&(kind, runner_kind)) {
+            Some(runners) if !runners.is_empty() => runners,
+            _ => return Ok(None),
+        };
+        if !self.config.cli_unstable().target_runners {
+            let runner = &runners[0];
+            return Ok(Some((runner.program.clone(), runner.args.clone())));
+        }
+
+        let mut failures = Vec::new();
+        for runner in runners {
+            match find_runner(&runner.program) {
+                Ok(()) => return Ok(Some((runner.program.clone(), runner.args.clone()))),
+                Err(reason) => failures.push(format!(
+                    "  `{}` (from {}): {}",
+                    runner.program.display(),
+                    runner.definition,
+                    reason
+                )),
+            }
+        }
+        let target = match kind {
+            CompileKind::Host => self.host.clone(),
+            CompileKind::Target(target) => target.short_name().to_string(),
+        };
+        anyhow::bail!(
+            "could not find a runner to start for target `{}`\n\
+             tried the following runners in order:\n{}",
+            target,
+            failures.join("\n")
+        )
     }
 
     /// Returns a [`ProcessBuilder`] appropriate for running a process for the
@@ -239,12 +366,13 @@ impl<'cfg> Compilation<'cfg> {
         &self,
         cmd: T,
         kind: CompileKind,
+        runner_kind: RunnerKind,
         pkg: &Package,
         script_meta: Option<Metadata>,
     ) -> CargoResult<ProcessBuilder> {
-        let builder = if let Some((runner, args)) = self.target_runner(kind) {
+        let builder = if let Some((runner, args)) = self.target_runner(kind, runner_kind)? {
             let mut builder = ProcessBuilder::new(runner);
-            builder.args(args);
+            builder.args(&args);
             builder.arg(cmd);
             builder
         } else {
@@ -281,7 +409,9 @@ impl<'cfg> Compilation<'cfg> {
             // libs from the sysroot that ships with rustc. This may not be
             // required (at least I cannot craft a situation where it
             // matters), but is here to be safe.
-            if self.config.cli_unstable().build_std.is_none() {
+            if self.config.cli_unstable().build_std.is_none()
+                && self.config.build_config()?.std.is_none()
+            {
                 search_path.push(self.sysroot_target_libdir[&kind].clone());
             }
         }
@@ -354,15 +484,13 @@ impl<'cfg> Compilation<'cfg> {
             .cwd(pkg.root());
 
         // Apply any environment variables from the config
-        for (key, value) in self.config.env_config()?.iter() {
+        for (key, value) in self.config.env_config_overrides()? {
             // never override a value that has already been set by cargo
-            if cmd.get_envs().contains_key(key) {
+            if cmd.get_envs().contains_key(&key) {
                 continue;
             }
 
-            if value.is_force() || env::var_os(key).is_none() {
-                cmd.env(key, value.resolve(self.config));
-            }
+            cmd.env(&key, value);
         }
 
         Ok(cmd)
@@ -384,44 +512,121 @@ fn fill_rustc_tool_env(mut cmd: ProcessBuilder, unit: &Unit) -> ProcessBuilder {
     cmd
 }
 
-fn target_runner(
+/// Collects the runners configured for running a target of the given kind,
+/// in the order they should be tried.
+///
+/// Without `-Z target-runners` this is just the `runner` value.
+fn target_runners_for(
     bcx: &BuildContext<'_, '_>,
     kind: CompileKind,
-) -> CargoResult<Option<(PathBuf, Vec<String>)>> {
+    runner_kind: RunnerKind,
+) -> CargoResult<Vec<RunnerCandidate>> {
+    let mut runners = Vec::new();
+    if !bcx.config.cli_unstable().target_runners {
+        if let Some(runner) = target_config_value(bcx, kind, "runner", |cfg| &cfg.runner)? {
+            runners.push(runner_candidate(bcx.config, runner.val));
+        }
+        return Ok(runners);
+    }
+
+    let specific = match runner_kind.config_key() {
+        Some(key) => target_config_value(bcx, kind, key, |cfg| runner_kind.cfg_runner(cfg))?,
+        None => None,
+    };
+    let primary = match specific {
+        Some(runner) => Some(runner),
+        None => target_config_value(bcx, kind, "runner", |cfg| &cfg.runner)?,
+    };
+    if let Some(runner) = primary {
+        runners.push(runner_candidate(bcx.config, runner.val));
+    }
+
+    let fallbacks =
+        target_config_value(bcx, kind, "runner-fallbacks", |cfg| &cfg.runner_fallbacks)?;
+    if let Some(fallbacks) = fallbacks {
+        for fallback in &fallbacks.val {
+            let mut parts = fallback.split_whitespace();
+            let program = match parts.next() {
+                Some(program) => program,
+                None => anyhow::bail!(
+                    "empty entry in `runner-fallbacks` defined in {}",
+                    fallbacks.definition
+                ),
+            };
+            runners.push(RunnerCandidate {
+                program: bcx.config.string_to_path(program, &fallbacks.definition),
+                args: parts.map(str::to_string).collect(),
+                definition: fallbacks.definition.clone(),
+            });
+        }
+    }
+    Ok(runners)
+}
+
+fn runner_candidate(config: &Config, runner: config::PathAndArgs) -> RunnerCandidate {
+    RunnerCandidate {
+        program: runner.path.resolve_program(config),
+        args: runner.args,
+        definition: runner.path.value().definition.clone(),
+    }
+}
+
+/// Checks that a runner program exists, returning the reason if it doesn't.
+fn find_runner(program: &Path) -> Result<(), String> {
+    if program.components().count() == 1 {
+        paths::resolve_executable(program)
+            .map(drop)
+            .map_err(|e| e.to_string())
+    } else if program.is_file() {
+        Ok(())
+    } else {
+        Err("file not found".to_string())
+    }
+}
+
+/// Looks up `name` in `[target.<triple>]`, then in the matching
+/// `[target.'cfg(..)']` table.
+fn target_config_value<T>(
+    bcx: &BuildContext<'_, '_>,
+    kind: CompileKind,
+    name: &str,
+    cfg_value: impl Fn(&TargetCfgConfig) -> &OptValue<T>,
+) -> CargoResult<OptValue<T>>
+where
+    T: Clone + for<'de> serde::Deserialize<'de>,
+{
     let target = bcx.target_data.short_name(&kind);
 
-    // try target.{}.runner
-    let key = format!("target.{}.runner", target);
+    // try target.{}.<name>
+    let key = format!("target.{}.{}", target, name);
 
-    if let Some(v) = bcx.config.get::<Option<config::PathAndArgs>>(&key)? {
-        let path = v.path.resolve_program(bcx.config);
-        return Ok(Some((path, v.args)));
+    if let Some(v) = bcx.config.get::<OptValue<T>>(&key)? {
+        return Ok(Some(v));
     }
 
-    // try target.'cfg(...)'.runner
+    // try target.'cfg(...)'.<name>
     let target_cfg = bcx.target_data.info(kind).cfg();
     let mut cfgs = bcx
         .config
         .target_cfgs()?
         .iter()
-        .filter_map(|(key, cfg)| cfg.runner.as_ref().map(|runner| (key, runner)))
-        .filter(|(key, _runner)| CfgExpr::matches_key(key, target_cfg));
-    let matching_runner = cfgs.next();
-    if let Some((key, runner)) = cfgs.next() {
+        .filter_map(|(key, cfg)| cfg_value(cfg).as_ref().map(|value| (key, value)))
+        .filter(|(key, _value)| CfgExpr::matches_key(key, target_cfg));
+    let matching = cfgs.next();
+    if let Some((key, value)) = cfgs.next() {
         anyhow::bail!(
-            "several matching instances of `target.'cfg(..)'.runner` in configurations\n\
+            "several matching instances of `target.'cfg(..)'.{}` in configurations\n\
              first match `{}` located in {}\n\
              second match `{}` located in {}",
-            matching_runner.unwrap().0,
-            matching_runner.unwrap().1.definition,
+            name,
+            matching.unwrap().0,
+            matching.unwrap().1.definition,
             key,
-            runner.definition
+            value.definition
         );
     }
-    Ok(matching_runner.map(|(_k, runner)| {
-        (
-            runner.val.path.clone().resolve_program(bcx.config),
-            runner.val.args.clone(),
-        )
+    Ok(matching.map(|(_k, value)| Value {
+        val: value.val.clone(),
+        definition: value.definition.clone(),
     }))
 }
@@ -1,17 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use cargo_util::paths;
 use lazycell::LazyCell;
 use log::debug;
 
 use super::{BuildContext, CompileKind, Context, FileFlavor, Layout};
-use crate::core::compiler::{CompileMode, CompileTarget, CrateType, FileType, Unit};
+use crate::core::compiler::{CompileMode, CompileTarget, CrateType, FileType, Unit, UpliftKind};
 use crate::core::{Target, TargetKind, Workspace};
-use crate::util::{self, CargoResult, StableHasher};
+use crate::util::{self, CargoResult, Rustc, StableHasher};
 
 /// This is a generic version number that can be changed to make
 /// backwards-incompatible changes to any file structures in the output
@@ -100,6 +101,11 @@ pub struct CompilationFiles<'a, 'cfg> {
     pub(super) target: HashMap<CompileTarget, Layout>,
     /// Additional directory to include a copy of the outputs.
     export_dir: Option<PathBuf>,
+    /// Whether the outputs of artifact dependencies are copied to the
+    /// `export_dir` too.
+    export_artifact_deps: bool,
+    /// Which files are hard-linked into the profile directory.
+    uplift: Vec<UpliftKind>,
     /// The root targets requested by the user on the command line (does not
     /// include dependencies).
     roots: Vec<Unit>,
@@ -125,6 +131,17 @@ pub struct OutputFile {
 }
 
 impl OutputFile {
+    /// Whether this is separate debug info which rustc only writes with
+    /// `-C split-debuginfo=packed`, like a `.dSYM` directory or a `.dwp`
+    /// file.
+    pub fn is_packed_debuginfo(&self) -> bool {
+        self.flavor == FileFlavor::DebugInfo
+            && matches!(
+                self.path.extension().and_then(|ext| ext.to_str()),
+                Some("dSYM" | "dwp")
+            )
+    }
+
     /// Gets the hard link if present; otherwise, returns the path.
     pub fn bin_dst(&self) -> &PathBuf {
         match self.hardlink {
@@ -154,6 +171,8 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
             host,
             target,
             export_dir: cx.bcx.build_config.export_dir.clone(),
+            export_artifact_deps: cx.bcx.build_config.export_artifact_deps,
+            uplift: cx.bcx.build_config.uplift.clone(),
             roots: cx.bcx.roots.clone(),
             metas,
             outputs,
@@ -193,7 +212,9 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
     pub fn out_dir(&self, unit: &Unit) -> PathBuf {
         // Docscrape units need to have doc/ set as the out_dir so sources for reverse-dependencies
         // will be put into doc/ and not into deps/ where the *.examples files are stored.
-        if unit.mode.is_doc() || unit.mode.is_doc_scrape() {
+        if let CompileMode::Doc { json: true, .. } = unit.mode {
+            self.layout(unit.kind).doc_json().to_path_buf()
+        } else if unit.mode.is_doc() || unit.mode.is_doc_scrape() {
             self.layout(unit.kind).doc().to_path_buf()
         } else if unit.mode.is_doc_test() {
             panic!("doc tests do not have an out dir");
@@ -213,18 +234,75 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         self.export_dir.clone()
     }
 
-    /// Directory name to use for a package in the form `NAME-HASH`.
+    /// Directory name to use for a package in the form `NAME-HASH`, or
+    /// `HASH` with the v2 layout.
     ///
     /// Note that some units may share the same directory, so care should be
     /// taken in those cases!
     fn pkg_dir(&self, unit: &Unit) -> String {
-        let name = unit.pkg.package_id().name();
+        let hash = self.pkg_hash(unit);
+        if self.layout(unit.kind).version() >= 2 {
+            hash
+        } else {
+            format!("{}-{}", unit.pkg.package_id().name(), hash)
+        }
+    }
+
+    /// The hash in the name of the directories of a package.
+    fn pkg_hash(&self, unit: &Unit) -> String {
         let meta = &self.metas[unit];
         if meta.use_extra_filename {
-            format!("{}-{}", name, meta.meta_hash)
+            meta.meta_hash.to_string()
         } else {
-            format!("{}-{}", name, self.target_short_hash(unit))
+            self.target_short_hash(unit)
+        }
+    }
+
+    /// Records the `HASH` directories of the v2 layout: the packages of all
+    /// of them in the layout manifest, and the artifacts in
+    /// `deps/artifact/index.json`, which maps the `NAME-HASH` directory names
+    /// of the original layout to them.
+    pub(super) fn record_v2_dirs(&self) -> CargoResult<()> {
+        let mut packages: HashMap<&Path, BTreeMap<String, String>> = HashMap::new();
+        let mut artifacts: HashMap<&Path, BTreeMap<String, String>> = HashMap::new();
+        for unit in self.metas.keys() {
+            let layout = self.layout(unit.kind);
+            if layout.version() < 2 {
+                continue;
+            }
+            let name = unit.pkg.package_id().name().to_string();
+            let hash = self.pkg_hash(unit);
+            packages
+                .entry(layout.dest())
+                .or_default()
+                .insert(hash.clone(), name.clone());
+            if unit.artifact.is_true() {
+                artifacts
+                    .entry(layout.artifact())
+                    .or_default()
+                    .insert(format!("{}-{}", name, hash), hash);
+            }
+        }
+        for layout in std::iter::once(&self.host).chain(self.target.values()) {
+            if let Some(packages) = packages.remove(layout.dest()) {
+                layout.record_packages(packages)?;
+            }
+        }
+        for (dir, entries) in artifacts {
+            let path = dir.join("index.json");
+            // Keep the artifacts of previous builds.
+            let mut index: BTreeMap<String, String> = paths::read(&path)
+                .ok()
+                .and_then(|index| serde_json::from_str(&index).ok())
+                .unwrap_or_default();
+            let len = index.len();
+            index.extend(entries);
+            if index.len() != len || !path.exists() {
+                paths::create_dir_all(dir)?;
+                paths::write(&path, serde_json::to_string_pretty(&index)?)?;
+            }
         }
+        Ok(())
     }
 
     /// Returns the final artifact path for the host (`/…/target/debug`)
@@ -381,11 +459,8 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
             return None;
         }
 
-        // Artifact dependencies are never uplifted.
-        if unit.artifact.is_true() {
-            return None;
-        }
-
+        // Which of the following are uplifted is decided by `build.uplift`,
+        // all but artifact dependencies by default:
         // - Binaries: The user always wants to see these, even if they are
         //   implicitly built (for example for integration tests).
         // - dylibs: This ensures that the dynamic linker pulls in all the
@@ -395,11 +470,25 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         //   This one is a little questionable for rlibs (see #6131), but is
         //   historically how Cargo has operated. This is primarily useful to
         //   give the user access to staticlibs and cdylibs.
-        if !unit.target.is_bin()
-            && !unit.target.is_custom_build()
-            && file_type.crate_type != Some(CrateType::Dylib)
-            && !self.roots.contains(unit)
-        {
+        // - Artifact dependencies: These are found through the environment
+        //   variables of their dependents instead.
+        // Build scripts are always uplifted into their own directory.
+        let uplift = |kind| self.uplift.contains(&kind);
+        let uplifted = if unit.target.is_custom_build() {
+            true
+        } else if unit.artifact.is_true() {
+            uplift(UpliftKind::ArtifactDeps)
+        } else {
+            (unit.target.is_bin() && uplift(UpliftKind::Bins))
+                || (file_type.crate_type == Some(CrateType::Dylib) && uplift(UpliftKind::Dylibs))
+                || (self.roots.contains(unit)
+                    && if unit.target.is_example() {
+                        uplift(UpliftKind::Examples)
+                    } else {
+                        unit.target.is_lib() && uplift(UpliftKind::Libs)
+                    })
+        };
+        if !uplifted {
             return None;
         }
 
@@ -421,12 +510,67 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         Some(uplift_path)
     }
 
+    /// Returns the path `--out-dir` copies an output to, if any.
+    ///
+    /// The outputs are sorted into subdirectories by the kind of artifact,
+    /// like `bin/foo` or `cdylib/libfoo.so`, and the ones of artifact
+    /// dependencies go to `artifact-deps/<package>/<kind>/`.
+    fn export_to(
+        &self,
+        unit: &Unit,
+        file_type: &FileType,
+        hardlink: Option<&Path>,
+    ) -> Option<PathBuf> {
+        let export_dir = self.export_dir.as_ref()?;
+        if unit.target.is_custom_build() {
+            return None;
+        }
+        let kind = if unit.target.is_example() {
+            "examples"
+        } else if unit.target.is_bin() {
+            "bin"
+        } else {
+            match file_type.crate_type {
+                Some(CrateType::Cdylib) => "cdylib",
+                Some(CrateType::Staticlib) => "staticlib",
+                _ => "lib",
+            }
+        };
+        if unit.artifact.is_true() {
+            if !self.export_artifact_deps
+                || unit.mode != CompileMode::Build
+                || file_type.flavor == FileFlavor::Rmeta
+            {
+                return None;
+            }
+            return Some(
+                export_dir
+                    .join("artifact-deps")
+                    .join(unit.pkg.name().as_str())
+                    .join(kind)
+                    .join(file_type.uplift_filename(&unit.target)),
+            );
+        }
+        Some(export_dir.join(kind).join(hardlink?.file_name()?))
+    }
+
     fn calc_outputs(
         &self,
         unit: &Unit,
         bcx: &BuildContext<'a, 'cfg>,
     ) -> CargoResult<Arc<Vec<OutputFile>>> {
         let ret = match unit.mode {
+            CompileMode::Doc { json: true, .. } => {
+                let path = self
+                    .out_dir(unit)
+                    .join(format!("{}.json", unit.target.crate_name()));
+                vec![OutputFile {
+                    path,
+                    hardlink: None,
+                    export_path: None,
+                    flavor: FileFlavor::Normal,
+                }]
+            }
             CompileMode::Doc { .. } => {
                 let path = self
                     .out_dir(unit)
@@ -508,6 +652,7 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
         }
 
         // Convert FileType to OutputFile.
+        let packed_debuginfo = writes_packed_debuginfo(bcx, unit);
         let mut outputs = Vec::new();
         for file_type in file_types {
             let meta = &self.metas[unit];
@@ -517,26 +662,81 @@ impl<'a, 'cfg: 'a> CompilationFiles<'a, 'cfg> {
             // If, the `different_binary_name` feature is enabled, the name of the hardlink will
             // be the name of the binary provided by the user in `Cargo.toml`.
             let hardlink = self.uplift_to(unit, &file_type, &path);
-            let export_path = if unit.target.is_custom_build() {
-                None
-            } else {
-                self.export_dir.as_ref().and_then(|export_dir| {
-                    hardlink
-                        .as_ref()
-                        .map(|hardlink| export_dir.join(hardlink.file_name().unwrap()))
-                })
-            };
-            outputs.push(OutputFile {
+            let export_path = self.export_to(unit, &file_type, hardlink.as_deref());
+            let output = OutputFile {
                 path,
                 hardlink,
                 export_path,
                 flavor: file_type.flavor,
-            });
+            };
+            if output.is_packed_debuginfo() && !packed_debuginfo {
+                continue;
+            }
+            outputs.push(output);
         }
         Ok(outputs)
     }
 }
 
+/// Whether rustc writes the debug info of `unit` into a separate file next to
+/// its output, a `.dSYM` directory on macOS, or a `.dwp` file on other
+/// platforms.
+fn writes_packed_debuginfo(bcx: &BuildContext<'_, '_>, unit: &Unit) -> bool {
+    let info = bcx.target_data.info(unit.kind);
+    let mut debuginfo = unit.profile.debuginfo.unwrap_or(0) != 0;
+    let mut split_debuginfo = unit
+        .profile
+        .split_debuginfo
+        .filter(|_| info.supports_split_debuginfo)
+        .map(|split| split.to_string());
+    // Flags passed to rustc after the ones of the profile override them.
+    let mut args = unit
+        .profile
+        .rustflags
+        .iter()
+        .map(|arg| arg.as_str())
+        .chain(bcx.rustflags_args(unit).iter().map(String::as_str))
+        .chain(
+            bcx.extra_args_for(unit)
+                .into_iter()
+                .flatten()
+                .map(String::as_str),
+        );
+    while let Some(arg) = args.next() {
+        let codegen = match arg {
+            "-g" => {
+                debuginfo = true;
+                continue;
+            }
+            "-C" | "--codegen" => match args.next() {
+                Some(codegen) => codegen,
+                None => break,
+            },
+            _ => match arg
+                .strip_prefix("-C")
+                .or_else(|| arg.strip_prefix("--codegen="))
+            {
+                Some(codegen) => codegen,
+                None => continue,
+            },
+        };
+        match codegen.split_once('=') {
+            Some(("debuginfo", level)) => debuginfo = !matches!(level, "0" | "none"),
+            Some(("split-debuginfo", split)) => split_debuginfo = Some(split.to_string()),
+            _ => {}
+        }
+    }
+    if !debuginfo {
+        return false;
+    }
+    match split_debuginfo {
+        Some(split) => split == "packed",
+        // The defaults of rustc: `packed` on macOS and Windows MSVC, where
+        // the `.pdb` is always written anyway, and `off` everywhere else.
+        None => bcx.target_data.short_name(&unit.kind).contains("-apple-"),
+    }
+}
+
 fn metadata_of<'a>(
     unit: &Unit,
     cx: &Context<'_, '_>,
@@ -586,7 +786,7 @@ fn compute_metadata(
     // `panic=abort` and `panic=unwind` artifacts, additionally with various
     // settings like debuginfo and whatnot.
     unit.profile.hash(&mut hasher);
-    unit.mode.hash(&mut hasher);
+    bcx.hashed_mode(unit).hash(&mut hasher);
     cx.lto[unit].hash(&mut hasher);
 
     // Artifacts compiled for the host should have a different
@@ -601,7 +801,7 @@ fn compute_metadata(
     unit.target.name().hash(&mut hasher);
     unit.target.kind().hash(&mut hasher);
 
-    hash_rustc_version(bcx, &mut hasher);
+    hash_rustc_version(bcx, bcx.rustc_for(unit), &mut hasher);
 
     if cx.bcx.ws.is_member(&unit.pkg) {
         // This is primarily here for clippy. This ensures that the clippy
@@ -632,12 +832,12 @@ fn compute_metadata(
     }
 }
 
-fn hash_rustc_version(bcx: &BuildContext<'_, '_>, hasher: &mut StableHasher) {
-    let vers = &bcx.rustc().version;
+fn hash_rustc_version(bcx: &BuildContext<'_, '_>, rustc: &Rustc, hasher: &mut StableHasher) {
+    let vers = &rustc.version;
     if vers.pre.is_empty() || bcx.config.cli_unstable().separate_nightlies {
         // For stable, keep the artifacts separate. This helps if someone is
         // testing multiple versions, to avoid recompiles.
-        bcx.rustc().verbose_version.hash(hasher);
+        rustc.verbose_version.hash(hasher);
         return;
     }
     // On "nightly"/"beta"/"dev"/etc, keep each "channel" separate. Don't hash
@@ -650,7 +850,7 @@ fn hash_rustc_version(bcx: &BuildContext<'_, '_>, hasher: &mut StableHasher) {
     // Keep "host" since some people switch hosts to implicitly change
     // targets, (like gnu vs musl or gnu vs msvc). In the future, we may want
     // to consider hashing `unit.kind.short_name()` instead.
-    bcx.rustc().host.hash(hasher);
+    rustc.host.hash(hasher);
     // None of the other lines are important. Currently they are:
     // binary: rustc  <-- or "rustdoc"
     // commit-hash: 38114ff16e7856f98b2b4be7ab4cd29b38bed59a
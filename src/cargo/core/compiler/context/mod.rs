@@ -12,7 +12,7 @@ use filetime::FileTime;
 use jobserver::Client;
 
 use super::build_plan::BuildPlan;
-use super::custom_build::{self, BuildDeps, BuildScriptOutputs, BuildScripts};
+use super::custom_build::{self, BuildDeps, BuildManifest, BuildScriptOutputs, BuildScripts};
 use super::fingerprint::Fingerprint;
 use super::job_queue::JobQueue;
 use super::layout::Layout;
@@ -82,6 +82,10 @@ pub struct Context<'a, 'cfg> {
     /// because the target has a type error. This is in an Arc<Mutex<..>>
     /// because it is continuously updated as the job progresses.
     pub failed_scrape_units: Arc<Mutex<HashSet<Metadata>>>,
+
+    /// Packages with units whose fingerprint files were found corrupt, and
+    /// are rebuilt. Cargo warns about them once, after preparing all units.
+    pub corrupt_fingerprints: BTreeSet<PackageId>,
 }
 
 impl<'a, 'cfg> Context<'a, 'cfg> {
@@ -121,9 +125,29 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             lto: HashMap::new(),
             metadata_for_doc_units: HashMap::new(),
             failed_scrape_units: Arc::new(Mutex::new(HashSet::new())),
+            corrupt_fingerprints: BTreeSet::new(),
         })
     }
 
+    /// Returns the build manifests declared by the previous run of each build
+    /// script in the unit graph, without building anything.
+    pub fn previous_build_manifests(mut self) -> CargoResult<HashMap<Unit, BuildManifest>> {
+        self.lto = super::lto::generate(self.bcx)?;
+        self.prepare_units()?;
+        Ok(custom_build::previous_build_manifests(&mut self))
+    }
+
+    /// Returns the `OUT_DIR` of each build script which has run in a
+    /// previous build, and the build manifest it declared, without building
+    /// anything.
+    pub fn previous_build_script_out_dirs(
+        mut self,
+    ) -> CargoResult<Vec<(Unit, PathBuf, Option<BuildManifest>)>> {
+        self.lto = super::lto::generate(self.bcx)?;
+        self.prepare_units()?;
+        Ok(custom_build::previous_build_script_out_dirs(&mut self))
+    }
+
     /// Starts compilation, waits for it to finish, and returns information
     /// about the result of compilation.
     pub fn compile(mut self, exec: &Arc<dyn Executor>) -> CargoResult<Compilation<'cfg>> {
@@ -158,6 +182,19 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             let force_rebuild = self.bcx.build_config.force_rebuild;
             super::compile(&mut self, &mut queue, &mut plan, unit, exec, force_rebuild)?;
         }
+        if !self.corrupt_fingerprints.is_empty() {
+            let packages = self
+                .corrupt_fingerprints
+                .iter()
+                .map(|id| format!("`{} v{}`", id.name(), id.version()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.bcx.config.shell().warn(format!(
+                "found corrupt fingerprint files for {}, rebuilding them\n\
+                 The target directory may have been left behind by a crash or a full disk.",
+                packages
+            ))?;
+        }
 
         // Now that we've got the full job queue and we've done all our
         // fingerprint analysis to determine what to run, bust all the memoized
@@ -175,6 +212,11 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         if build_plan {
             plan.set_inputs(self.build_plan_inputs()?);
             plan.output_plan(self.bcx.config);
+        } else {
+            super::artifact_manifest::write_export(&self)?;
+            if self.bcx.config.cli_unstable().artifact_manifest {
+                super::artifact_manifest::write(&self)?;
+            }
         }
 
         // Collect the result of the build into `self.compilation`.
@@ -281,6 +323,26 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             super::output_depinfo(&mut self, unit)?;
         }
 
+        let mut doc_units: Vec<_> = self
+            .bcx
+            .unit_graph
+            .keys()
+            .filter(|unit| unit.mode.is_doc())
+            .cloned()
+            .collect();
+        doc_units.sort();
+        for unit in doc_units {
+            let index = self
+                .outputs(&unit)?
+                .iter()
+                .find(|o| o.flavor == FileFlavor::Normal)
+                .map(|o| o.path.clone());
+            if let Some(index) = index {
+                let output = self.unit_output(&unit, &index);
+                self.compilation.docs.push(output);
+            }
+        }
+
         for (script_meta, output) in self.build_script_outputs.lock().unwrap().iter() {
             self.compilation
                 .extra_env
@@ -348,6 +410,7 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         }
 
         let files = self.files.as_ref().unwrap();
+        files.record_v2_dirs()?;
         for &kind in self.bcx.all_kinds.iter() {
             let layout = files.layout(kind);
             self.compilation
@@ -602,6 +665,25 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             && dep.mode == CompileMode::Build
     }
 
+    /// Returns whether when `parent` depends on the artifact `dep` if it only
+    /// requires the paths of the artifact files, not the files themselves.
+    ///
+    /// This is the case of an artifact dependency with `lib = true`: a library
+    /// compiles against the metadata of the dependency's lib while its
+    /// artifacts are still being linked. Units which link against the library
+    /// still wait for the artifacts, as they require all upstream objects.
+    pub fn only_requires_artifact_paths(&self, parent: &Unit, dep: &Unit) -> bool {
+        dep.artifact.is_true()
+            && !parent.requires_upstream_objects()
+            && parent.mode == CompileMode::Build
+            && self.unit_deps(parent).iter().any(|lib| {
+                lib.unit.pkg == dep.pkg
+                    && lib.unit.target.is_lib()
+                    && !lib.unit.artifact.is_true()
+                    && self.only_requires_rmeta(parent, &lib.unit)
+            })
+    }
+
     /// Returns whether when `unit` is built whether it should emit metadata as
     /// well because some compilations rely on that.
     pub fn rmeta_required(&self, unit: &Unit) -> bool {
@@ -1,22 +1,42 @@
 use super::job::{Freshness, Job, Work};
-use super::{fingerprint, Context, LinkType, Unit};
+use super::{fingerprint, system_deps, Context, LinkType, MessageFormat, Unit};
 use crate::core::compiler::artifact;
 use crate::core::compiler::context::Metadata;
 use crate::core::compiler::job_queue::JobState;
+use crate::core::dependency::{ArtifactKind, DepKind};
 use crate::core::{profiles::ProfileRoot, PackageId, Target};
 use crate::util::errors::CargoResult;
+use crate::util::interning::InternedString;
 use crate::util::machine_message::{self, Message};
 use crate::util::{internal, profile};
 use anyhow::{bail, Context as _};
 use cargo_platform::Cfg;
-use cargo_util::paths;
+use cargo_util::{paths, ProcessError};
 use std::collections::hash_map::{Entry, HashMap};
 use std::collections::{BTreeSet, HashSet};
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::str;
 use std::sync::{Arc, Mutex};
 
 const CARGO_WARNING: &str = "cargo:warning=";
+const CARGO_ARTIFACT_USES: &str = "cargo:artifact-uses=";
+
+/// When the output of build scripts is displayed, set by `build.script-output`.
+///
+/// Regardless of this setting, the output of successful runs is saved in the
+/// `output` and `stderr` files of the script's run directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScriptOutput {
+    /// Streamed while the script runs, as with `-vv`.
+    Always,
+    /// Included in the error if the script fails.
+    #[default]
+    OnFailure,
+    /// Not displayed at all, only saved.
+    Never,
+}
 
 /// Contains the parsed output of a custom build script.
 #[derive(Clone, Debug, Hash, Default)]
@@ -45,6 +65,31 @@ pub struct BuildOutput {
     /// These are only displayed if this is a "local" package, `-vv` is used,
     /// or there is a build error for any target in this package.
     pub warnings: Vec<String>,
+    /// Artifacts of build-dependencies the script uses, as `DEP:KIND`.
+    pub artifact_uses: Vec<String>,
+    /// The manifest declared with `cargo:build-manifest`, if any.
+    ///
+    /// Its `inputs` and `env` are already merged into `rerun_if_changed` and
+    /// `rerun_if_env_changed`.
+    pub build_manifest: Option<BuildManifest>,
+}
+
+/// The inputs, outputs and environment variables a build script declares
+/// with `cargo:build-manifest=PATH` under `-Z build-manifest`.
+#[derive(Clone, Debug, Hash, Default, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildManifest {
+    /// Files or directories read by the script, relative to the package root
+    /// like `rerun-if-changed`.
+    #[serde(default)]
+    pub inputs: Vec<PathBuf>,
+    /// Environment variables read by the script.
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Files the script writes, relative to `OUT_DIR`. The script is rerun if
+    /// any of them goes missing.
+    #[serde(default)]
+    pub outputs: Vec<PathBuf>,
 }
 
 /// Map of packages to build script output.
@@ -107,6 +152,9 @@ pub struct BuildDeps {
     pub rerun_if_changed: Vec<PathBuf>,
     /// Environment variables that trigger a rebuild if they change.
     pub rerun_if_env_changed: Vec<String>,
+    /// Outputs declared in the build manifest, relative to `OUT_DIR`, which
+    /// trigger a rebuild if they are missing.
+    pub declared_outputs: Vec<PathBuf>,
 }
 
 /// Prepares a `Work` that executes the target as a custom build script.
@@ -136,6 +184,7 @@ fn emit_build_output(
     output: &BuildOutput,
     out_dir: &Path,
     package_id: PackageId,
+    message_format: MessageFormat,
 ) -> CargoResult<()> {
     let library_paths = output
         .library_paths
@@ -151,7 +200,7 @@ fn emit_build_output(
         env: &output.env,
         out_dir,
     }
-    .to_json_string();
+    .to_json_string_for(message_format);
     state.stdout(msg)?;
     Ok(())
 }
@@ -202,7 +251,7 @@ fn build_work(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Job> {
             },
         )
         .env("HOST", &bcx.host_triple())
-        .env("RUSTC", &bcx.rustc().path)
+        .env("RUSTC", &bcx.rustc_for(unit).path)
         .env("RUSTDOC", &*bcx.config.rustdoc()?)
         .inherit_jobserver(&cx.jobserver);
 
@@ -243,6 +292,18 @@ fn build_work(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Job> {
             }
         }
     }
+    if bcx.config.cli_unstable().cfg_json {
+        cmd.env(
+            "CARGO_CFG_JSON",
+            bcx.target_data.info(unit.kind).cfg_json()?.to_string(),
+        );
+    }
+    if let Some(spec) = &bcx.target_data.info(unit.kind).target_spec {
+        let path = script_run_dir.join("target-spec.json");
+        paths::create_dir_all(&script_run_dir)?;
+        paths::write_if_changed(&path, spec)?;
+        cmd.env("CARGO_TARGET_SPEC_PATH", path);
+    }
     for (k, v) in cfg_map {
         if k == "debug_assertions" {
             // This cfg is always true and misleading, so avoid setting it.
@@ -316,7 +377,14 @@ fn build_work(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Job> {
     );
     let build_scripts = cx.build_scripts.get(unit).cloned();
     let json_messages = bcx.build_config.emit_json();
-    let extra_verbose = bcx.config.extra_verbose();
+    let message_format = bcx.build_config.message_format;
+    let script_output = if bcx.config.cli_unstable().script_output {
+        bcx.config.build_config()?.script_output.unwrap_or_default()
+    } else {
+        ScriptOutput::OnFailure
+    };
+    let stream_output =
+        bcx.config.extra_verbose() || (script_output == ScriptOutput::Always && !json_messages);
     let (prev_output, prev_script_out_dir) = prev_build_output(cx, unit);
     let metadata_hash = cx.get_run_build_script_metadata(unit);
 
@@ -328,6 +396,34 @@ fn build_work(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Job> {
         Some((_, _, _, output)) => output,
         None => false,
     };
+    let build_manifest = cx.bcx.config.cli_unstable().build_manifest;
+    let bindeps = cx.bcx.config.cli_unstable().bindeps;
+    let system_deps = if cx.bcx.config.cli_unstable().system_deps {
+        system_deps::system_deps(&unit.pkg)?
+    } else {
+        Vec::new()
+    };
+    let pkg_config: OsString = cx
+        .bcx
+        .config
+        .env()
+        .get("PKG_CONFIG")
+        .map_or_else(|| "pkg-config".into(), Into::into);
+    // The artifact kinds requested for each artifact build-dependency, to check
+    // the `cargo:artifact-uses` instructions against.
+    let artifact_deps = if bindeps {
+        let deps: Vec<_> = unit
+            .pkg
+            .dependencies()
+            .iter()
+            .filter(|dep| dep.kind() == DepKind::Build)
+            .filter_map(|dep| Some((dep.name_in_toml(), dep.artifact()?.kinds().to_vec())))
+            .collect();
+        Some(deps)
+    } else {
+        None
+    };
+    let manifest_path = unit.pkg.manifest_path().to_path_buf();
     let targets: Vec<Target> = unit.pkg.targets().to_vec();
     // Need a separate copy for the fresh closure.
     let targets_fresh = targets.clone();
@@ -381,43 +477,101 @@ fn build_work(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Job> {
             return Ok(());
         }
 
+        // Probe the libraries of `[package.metadata.system-deps]`, the ones
+        // found are linked as if the script had asked for them itself.
+        let mut system_dep_instructions = Vec::new();
+        for dep in &system_deps {
+            if let Some(library) = dep.probe(&pkg_config, &pkg_descr)? {
+                for (key, value) in dep.env(&library)? {
+                    cmd.env(&key, value);
+                }
+                system_dep_instructions.extend(library.instructions());
+            }
+        }
+
         // And now finally, run the build command itself!
         state.running(&cmd);
         let timestamp = paths::set_invocation_time(&script_run_dir)?;
         let prefix = format!("[{} {}] ", id.name(), id.version());
         let mut warnings_in_case_of_panic = Vec::new();
+        let mut artifact_uses_in_case_of_panic = Vec::new();
+        let mut usage = None;
         let output = cmd
-            .exec_with_streaming(
+            .exec_with_streaming_and_usage(
                 &mut |stdout| {
                     if let Some(warning) = stdout.strip_prefix(CARGO_WARNING) {
                         warnings_in_case_of_panic.push(warning.to_owned());
                     }
-                    if extra_verbose {
+                    if let Some(uses) = stdout.strip_prefix(CARGO_ARTIFACT_USES) {
+                        artifact_uses_in_case_of_panic.push(uses.trim().to_owned());
+                    }
+                    if stream_output {
                         state.stdout(format!("{}{}", prefix, stdout))?;
                     }
                     Ok(())
                 },
                 &mut |stderr| {
-                    if extra_verbose {
+                    if stream_output {
                         state.stderr(format!("{}{}", prefix, stderr))?;
                     }
                     Ok(())
                 },
                 true,
+                &mut usage,
             )
             .with_context(|| format!("failed to run custom build command for `{}`", pkg_descr));
+        if let Some(usage) = usage {
+            state.resource_usage(usage);
+        }
 
-        if let Err(error) = output {
+        if let Err(mut error) = output {
+            // Save what the script printed for `cargo report
+            // build-script-output`, even though nothing else is saved for
+            // failed runs.
+            if let Some(perr) = error.downcast_mut::<ProcessError>() {
+                if let Some(stdout) = &perr.stdout {
+                    paths::write(&output_file, stdout)?;
+                }
+                if let Some(stderr) = &perr.stderr {
+                    paths::write(&err_file, stderr)?;
+                }
+                if script_output == ScriptOutput::Never {
+                    if let Some(pos) = perr.desc.find("\n--- std") {
+                        perr.desc.truncate(pos);
+                    }
+                }
+            }
             insert_warnings_in_build_outputs(
                 build_script_outputs,
                 id,
                 metadata_hash,
                 warnings_in_case_of_panic,
             );
+            // A script that fails because an artifact it expected is missing
+            // gets the more helpful error about the manifest.
+            if let Some(artifact_deps) = &artifact_deps {
+                if let Err(e) = validate_artifact_uses(
+                    &artifact_uses_in_case_of_panic,
+                    artifact_deps,
+                    &pkg_descr,
+                    &manifest_path,
+                ) {
+                    return Err(error.context(e.to_string()));
+                }
+            }
             return Err(error);
         }
 
-        let output = output.unwrap();
+        let mut output = output.unwrap();
+        if !system_dep_instructions.is_empty() {
+            let mut stdout = Vec::new();
+            for instruction in system_dep_instructions {
+                stdout.extend_from_slice(instruction.as_bytes());
+                stdout.push(b'\n');
+            }
+            stdout.append(&mut output.stdout);
+            output.stdout = stdout;
+        }
 
         // After the build command has finished running, we need to be sure to
         // remember all of its output so we can later discover precisely what it
@@ -439,12 +593,40 @@ fn build_work(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Job> {
             &script_out_dir,
             &script_out_dir,
             extra_check_cfg,
+            build_manifest,
+            bindeps,
             nightly_features_allowed,
             &targets,
         )?;
+        if let Some(manifest) = &parsed_output.build_manifest {
+            for output in &manifest.outputs {
+                if !script_out_dir.join(output).exists() {
+                    bail!(
+                        "build script of `{}` declared the output `{}` in its build \
+                         manifest, but did not create it",
+                        pkg_descr,
+                        output.display()
+                    );
+                }
+            }
+        }
+        if let Some(artifact_deps) = &artifact_deps {
+            validate_artifact_uses(
+                &parsed_output.artifact_uses,
+                artifact_deps,
+                &pkg_descr,
+                &manifest_path,
+            )?;
+        }
 
         if json_messages {
-            emit_build_output(state, &parsed_output, script_out_dir.as_path(), id)?;
+            emit_build_output(
+                state,
+                &parsed_output,
+                script_out_dir.as_path(),
+                id,
+                message_format,
+            )?;
         }
         build_script_outputs
             .lock()
@@ -467,13 +649,15 @@ fn build_work(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Job> {
                 &prev_script_out_dir,
                 &script_out_dir,
                 extra_check_cfg,
+                build_manifest,
+                bindeps,
                 nightly_features_allowed,
                 &targets_fresh,
             )?,
         };
 
         if json_messages {
-            emit_build_output(state, &output, script_out_dir.as_path(), id)?;
+            emit_build_output(state, &output, script_out_dir.as_path(), id, message_format)?;
         }
 
         build_script_outputs
@@ -512,6 +696,77 @@ fn insert_warnings_in_build_outputs(
         .insert(id, metadata_hash, build_output_with_only_warnings);
 }
 
+/// Checks the `cargo:artifact-uses=DEP:KIND` instructions of a build script
+/// against the artifact kinds requested for its build-dependencies, so that a
+/// missing artifact is reported in terms of the manifest rather than as a
+/// missing environment variable in the script.
+fn validate_artifact_uses(
+    uses: &[String],
+    artifact_deps: &[(InternedString, Vec<ArtifactKind>)],
+    pkg_descr: &str,
+    manifest_path: &Path,
+) -> CargoResult<()> {
+    for value in uses {
+        let (dep_name, kind) = value.split_once(':').ok_or_else(|| {
+            anyhow::format_err!(
+                "invalid instruction `cargo:artifact-uses={}` from build script of `{}`\n\
+                 The instruction should have the form cargo:artifact-uses=DEP:KIND",
+                value,
+                pkg_descr
+            )
+        })?;
+        let kind = ArtifactKind::parse(kind).with_context(|| {
+            format!(
+                "invalid instruction `cargo:artifact-uses={}` from build script of `{}`",
+                value, pkg_descr
+            )
+        })?;
+        let requested = match artifact_deps.iter().find(|(name, _)| name == dep_name) {
+            Some((_, kinds)) => kinds,
+            None => bail!(
+                "the build script of `{}` uses the `{}` artifact of `{}`, \
+                 but `{}` is not an artifact build-dependency\n\
+                 add `{} = {{ ..., artifact = \"{}\" }}` to `[build-dependencies]` in `{}`",
+                pkg_descr,
+                kind,
+                dep_name,
+                dep_name,
+                dep_name,
+                kind,
+                manifest_path.display()
+            ),
+        };
+        let satisfied = requested.iter().any(|r| match (r, &kind) {
+            (ArtifactKind::AllBinaries, ArtifactKind::SelectedBinary(_)) => true,
+            (ArtifactKind::SelectedBinary(_), ArtifactKind::AllBinaries) => true,
+            (r, kind) => r == kind,
+        });
+        if satisfied {
+            continue;
+        }
+        let requested_list = requested
+            .iter()
+            .map(|k| format!("`{}`", k))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut fixed: toml_edit::Array = requested.iter().map(|k| k.to_string()).collect();
+        fixed.push(kind.to_string());
+        bail!(
+            "the build script of `{}` uses the `{}` artifact of `{}`, \
+             but the manifest only requests {}\n\
+             change the dependency on `{}` in {} to use `artifact = {}`",
+            pkg_descr,
+            kind,
+            dep_name,
+            requested_list,
+            dep_name,
+            manifest_path.display(),
+            fixed
+        );
+    }
+    Ok(())
+}
+
 impl BuildOutput {
     pub fn parse_file(
         path: &Path,
@@ -520,6 +775,8 @@ impl BuildOutput {
         script_out_dir_when_generated: &Path,
         script_out_dir: &Path,
         extra_check_cfg: bool,
+        build_manifest: bool,
+        bindeps: bool,
         nightly_features_allowed: bool,
         targets: &[Target],
     ) -> CargoResult<BuildOutput> {
@@ -531,6 +788,8 @@ impl BuildOutput {
             script_out_dir_when_generated,
             script_out_dir,
             extra_check_cfg,
+            build_manifest,
+            bindeps,
             nightly_features_allowed,
             targets,
         )
@@ -547,6 +806,8 @@ impl BuildOutput {
         script_out_dir_when_generated: &Path,
         script_out_dir: &Path,
         extra_check_cfg: bool,
+        build_manifest: bool,
+        bindeps: bool,
         nightly_features_allowed: bool,
         targets: &[Target],
     ) -> CargoResult<BuildOutput> {
@@ -560,6 +821,8 @@ impl BuildOutput {
         let mut rerun_if_changed = Vec::new();
         let mut rerun_if_env_changed = Vec::new();
         let mut warnings = Vec::new();
+        let mut artifact_uses = Vec::new();
+        let mut declared_manifest = None;
         let whence = format!("build script of `{}`", pkg_descr);
 
         for line in input.split(|b| *b == b'\n') {
@@ -738,6 +1001,33 @@ impl BuildOutput {
                 "warning" => warnings.push(value.to_string()),
                 "rerun-if-changed" => rerun_if_changed.push(PathBuf::from(value)),
                 "rerun-if-env-changed" => rerun_if_env_changed.push(value.to_string()),
+                "artifact-uses" => {
+                    if !bindeps {
+                        warnings.push(format!("cargo:{} requires -Zbindeps flag", key));
+                        metadata.push((key.to_string(), value.to_string()));
+                        continue;
+                    }
+                    artifact_uses.push(value.to_string());
+                }
+                "build-manifest" => {
+                    if !build_manifest {
+                        warnings.push(format!("cargo:{} requires -Zbuild-manifest flag", key));
+                        continue;
+                    }
+                    if declared_manifest.is_some() {
+                        bail!(
+                            "invalid instruction `cargo:{}` from {}\n\
+                             The build manifest may only be declared once.",
+                            key,
+                            whence
+                        );
+                    }
+                    let manifest =
+                        BuildOutput::parse_build_manifest(&script_out_dir.join(&value), &whence)?;
+                    rerun_if_changed.extend(manifest.inputs.iter().cloned());
+                    rerun_if_env_changed.extend(manifest.env.iter().cloned());
+                    declared_manifest = Some(manifest);
+                }
                 _ => metadata.push((key.to_string(), value.to_string())),
             }
         }
@@ -753,9 +1043,35 @@ impl BuildOutput {
             rerun_if_changed,
             rerun_if_env_changed,
             warnings,
+            artifact_uses,
+            build_manifest: declared_manifest,
         })
     }
 
+    /// Reads the JSON build manifest at `path`, which a build script declared
+    /// with `cargo:build-manifest`.
+    pub fn parse_build_manifest(path: &Path, whence: &str) -> CargoResult<BuildManifest> {
+        let contents = paths::read(path)
+            .with_context(|| format!("failed to read the build manifest of {}", whence))?;
+        let manifest: BuildManifest = serde_json::from_str(&contents).with_context(|| {
+            format!(
+                "failed to parse the build manifest `{}` of {}",
+                path.display(),
+                whence
+            )
+        })?;
+        if let Some(output) = manifest.outputs.iter().find(|p| !p.is_relative()) {
+            bail!(
+                "the build manifest `{}` of {} declares the output `{}`, \
+                 but outputs must be relative to OUT_DIR",
+                path.display(),
+                whence,
+                output.display()
+            );
+        }
+        Ok(manifest)
+    }
+
     pub fn parse_rustc_flags(
         value: &str,
         whence: &str,
@@ -852,6 +1168,10 @@ impl BuildDeps {
                 .map(|p| &p.rerun_if_env_changed)
                 .cloned()
                 .unwrap_or_default(),
+            declared_outputs: output
+                .and_then(|p| p.build_manifest.as_ref())
+                .map(|m| m.outputs.clone())
+                .unwrap_or_default(),
         }
     }
 }
@@ -897,7 +1217,11 @@ pub fn build_map(cx: &mut Context<'_, '_>) -> CargoResult<()> {
         // If there is a build script override, pre-fill the build output.
         if unit.mode.is_run_custom_build() {
             if let Some(links) = unit.pkg.manifest().links() {
-                if let Some(output) = cx.bcx.target_data.script_override(links, unit.kind) {
+                if let Some(output) =
+                    cx.bcx
+                        .target_data
+                        .script_override(unit.pkg.package_id(), links, unit.kind)
+                {
                     let metadata = cx.get_run_build_script_metadata(unit);
                     cx.build_script_outputs.lock().unwrap().insert(
                         unit.pkg.package_id(),
@@ -966,6 +1290,52 @@ pub fn build_map(cx: &mut Context<'_, '_>) -> CargoResult<()> {
     }
 }
 
+/// Returns the build manifests declared by the previous execution of each
+/// `RunCustomBuild` unit of the unit graph, for `--unit-graph`.
+pub fn previous_build_manifests(cx: &mut Context<'_, '_>) -> HashMap<Unit, BuildManifest> {
+    let units: Vec<Unit> = cx
+        .bcx
+        .unit_graph
+        .keys()
+        .filter(|unit| unit.mode.is_run_custom_build())
+        .cloned()
+        .collect();
+    units
+        .into_iter()
+        .filter_map(|unit| {
+            let manifest = prev_build_output(cx, &unit).0?.build_manifest?;
+            Some((unit, manifest))
+        })
+        .collect()
+}
+
+/// Returns the `OUT_DIR` of each `RunCustomBuild` unit of the unit graph
+/// which has run before, together with the build manifest it declared, for
+/// `cargo metadata --build-scripts`.
+pub fn previous_build_script_out_dirs(
+    cx: &mut Context<'_, '_>,
+) -> Vec<(Unit, PathBuf, Option<BuildManifest>)> {
+    let units: Vec<Unit> = cx
+        .bcx
+        .unit_graph
+        .keys()
+        .filter(|unit| unit.mode.is_run_custom_build())
+        .cloned()
+        .collect();
+    units
+        .into_iter()
+        .filter_map(|unit| {
+            let (output, _) = prev_build_output(cx, &unit);
+            let output = output?;
+            let out_dir = cx.files().build_script_out_dir(&unit);
+            if !out_dir.is_dir() {
+                return None;
+            }
+            Some((unit, out_dir, output.build_manifest))
+        })
+        .collect()
+}
+
 /// Returns the previous parsed `BuildOutput`, if any, from a previous
 /// execution.
 ///
@@ -992,6 +1362,8 @@ fn prev_build_output(cx: &mut Context<'_, '_>, unit: &Unit) -> (Option<BuildOutp
                 Some((_, _, _, output)) => output,
                 None => false,
             },
+            cx.bcx.config.cli_unstable().build_manifest,
+            cx.bcx.config.cli_unstable().bindeps,
             cx.bcx.config.nightly_features_allowed,
             unit.pkg.targets(),
         )
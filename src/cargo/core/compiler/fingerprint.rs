@@ -314,6 +314,7 @@
 
 use std::collections::hash_map::{Entry, HashMap};
 use std::env;
+use std::ffi::OsString;
 use std::hash::{self, Hash, Hasher};
 use std::io;
 use std::path::{Path, PathBuf};
@@ -339,6 +340,7 @@ use crate::CARGO_ENV;
 
 use super::custom_build::BuildDeps;
 use super::job::{Job, Work};
+use super::unused_deps;
 use super::{BuildContext, Context, FileFlavor, Unit};
 
 /// Determines if a `unit` is up-to-date, and if not prepares necessary work to
@@ -373,6 +375,9 @@ pub fn prepare_target(cx: &mut Context<'_, '_>, unit: &Unit, force: bool) -> Car
     let mtime_on_use = cx.bcx.config.cli_unstable().mtime_on_use;
     let compare = compare_old_fingerprint(&loc, &*fingerprint, mtime_on_use);
     log_compare(unit, &compare);
+    if compare.is_err() && has_corrupt_fingerprint(&loc, &dep_info_loc(cx, unit)) {
+        cx.corrupt_fingerprints.insert(unit.pkg.package_id());
+    }
 
     // If our comparison failed (e.g., we're going to trigger a rebuild of this
     // crate), then we also ensure the source of the crate passes all
@@ -443,7 +448,7 @@ pub fn prepare_target(cx: &mut Context<'_, '_>, unit: &Unit, force: bool) -> Car
         // thunk we can invoke on a foreign thread to calculate this.
         let build_script_outputs = Arc::clone(&cx.build_script_outputs);
         let metadata = cx.get_run_build_script_metadata(unit);
-        let (gen_local, _overridden) = build_script_local_fingerprints(cx, unit);
+        let (gen_local, _overridden) = build_script_local_fingerprints(cx, unit)?;
         let output_path = cx.build_explicit_deps[unit].build_script_output.clone();
         Work::new(move |_| {
             let outputs = build_script_outputs.lock().unwrap();
@@ -485,6 +490,10 @@ struct DepFingerprint {
     /// actually requires the rmeta from what we depend on, so when checking
     /// mtime information all files other than the rmeta can be ignored.
     only_requires_rmeta: bool,
+    /// Whether or not this dependency is an artifact which is only referred to
+    /// by its paths, see [`Context::only_requires_artifact_paths`]. The
+    /// artifact may be linked after we're built, so its mtime is ignored.
+    only_requires_artifact_paths: bool,
     /// The dependency's fingerprint we recursively point to, containing all the
     /// other hash information we'd otherwise need.
     fingerprint: Arc<Fingerprint>,
@@ -614,6 +623,7 @@ impl<'de> Deserialize<'de> for DepFingerprint {
             // `check_filesystem` which isn't used by fingerprints loaded from
             // disk.
             only_requires_rmeta: false,
+            only_requires_artifact_paths: false,
         })
     }
 }
@@ -675,6 +685,13 @@ enum LocalFingerprint {
     /// filesystem dependence here, and if the values are changed the hash will
     /// change forcing a recompile.
     RerunIfEnvChanged { var: String, val: Option<String> },
+
+    /// Files which only need to exist, like the outputs a build script
+    /// declared in its build manifest (`-Z build-manifest`), or the separate
+    /// debug info of a unit built with `-C split-debuginfo=packed`. The
+    /// `paths` are relative to `target_root(...)`, and the unit is rebuilt if
+    /// any of them is missing.
+    RerunIfMissing { paths: Vec<PathBuf> },
 }
 
 enum StaleItem {
@@ -710,12 +727,14 @@ impl LocalFingerprint {
         pkg_root: &Path,
         target_root: &Path,
         cargo_exe: &Path,
+        env_overrides: &HashMap<String, OsString>,
     ) -> CargoResult<Option<StaleItem>> {
         match self {
             // We need to parse `dep_info`, learn about the crate's dependencies.
             //
-            // For each env var we see if our current process's env var still
-            // matches, and for each file we see if any of them are newer than
+            // For each env var we see if the value Cargo would pass to rustc
+            // (our current process's env var, or the `[env]` config value)
+            // still matches, and for each file we see if any of them are newer than
             // the `dep_info` file itself whose mtime represents the start of
             // rustc.
             LocalFingerprint::CheckDepInfo { dep_info } => {
@@ -738,7 +757,7 @@ impl LocalFingerprint {
                                 .to_string(),
                         )
                     } else {
-                        env::var(key).ok()
+                        env_var_with_overrides(env_overrides, key)
                     };
                     if current == *previous {
                         continue;
@@ -765,6 +784,15 @@ impl LocalFingerprint {
             // tocheck for here.
             LocalFingerprint::RerunIfEnvChanged { .. } => Ok(None),
             LocalFingerprint::Precalculated(..) => Ok(None),
+
+            // These only need to still exist. The contents of declared
+            // outputs are the build script's business, and the mtime of a
+            // `.dSYM` directory doesn't change when `dsymutil` updates it.
+            LocalFingerprint::RerunIfMissing { paths } => Ok(paths
+                .iter()
+                .map(|p| target_root.join(p))
+                .find(|p| !p.exists())
+                .map(StaleItem::MissingFile)),
         }
     }
 
@@ -774,6 +802,7 @@ impl LocalFingerprint {
             LocalFingerprint::CheckDepInfo { .. } => "dep-info",
             LocalFingerprint::RerunIfChanged { .. } => "rerun-if-changed",
             LocalFingerprint::RerunIfEnvChanged { .. } => "rerun-if-env-changed",
+            LocalFingerprint::RerunIfMissing { .. } => "rerun-if-missing",
         }
     }
 }
@@ -934,6 +963,18 @@ impl Fingerprint {
                         )
                     }
                 }
+                (
+                    LocalFingerprint::RerunIfMissing { paths: apaths },
+                    LocalFingerprint::RerunIfMissing { paths: bpaths },
+                ) => {
+                    if apaths != bpaths {
+                        bail!(
+                            "required outputs changed: previously {:?}, now {:?}",
+                            bpaths,
+                            apaths,
+                        )
+                    }
+                }
                 (a, b) => bail!(
                     "local fingerprint type has changed ({} => {})",
                     b.kind(),
@@ -990,6 +1031,7 @@ impl Fingerprint {
         pkg_root: &Path,
         target_root: &Path,
         cargo_exe: &Path,
+        env_overrides: &HashMap<String, OsString>,
     ) -> CargoResult<()> {
         assert!(!self.fs_status.up_to_date());
 
@@ -1036,6 +1078,9 @@ impl Fingerprint {
                 // If our dependency is stale, so are we, so bail out.
                 FsStatus::Stale => return Ok(()),
             };
+            if dep.only_requires_artifact_paths {
+                continue;
+            }
 
             // If our dependency edge only requires the rmeta file to be present
             // then we only need to look at that one output file, otherwise we
@@ -1081,9 +1126,13 @@ impl Fingerprint {
         // files for this package itself. If we do find something log a helpful
         // message and bail out so we stay stale.
         for local in self.local.get_mut().unwrap().iter() {
-            if let Some(item) =
-                local.find_stale_item(mtime_cache, pkg_root, target_root, cargo_exe)?
-            {
+            if let Some(item) = local.find_stale_item(
+                mtime_cache,
+                pkg_root,
+                target_root,
+                cargo_exe,
+                env_overrides,
+            )? {
                 item.log();
                 return Ok(());
             }
@@ -1135,6 +1184,7 @@ impl hash::Hash for Fingerprint {
             public,
             fingerprint,
             only_requires_rmeta: _, // static property, no need to hash
+            only_requires_artifact_paths: _,
         } in deps
         {
             pkg_id.hash(h);
@@ -1171,6 +1221,7 @@ impl DepFingerprint {
             public: dep.public,
             fingerprint,
             only_requires_rmeta: cx.only_requires_rmeta(parent, &dep.unit),
+            only_requires_artifact_paths: cx.only_requires_artifact_paths(parent, &dep.unit),
         })
     }
 }
@@ -1238,11 +1289,13 @@ fn calculate(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Arc<Fingerpri
     // `fs_status` field of it.
     let target_root = target_root(cx);
     let cargo_exe = cx.bcx.config.cargo_exe()?;
+    let env_overrides = cx.bcx.config.env_config_overrides()?;
     fingerprint.check_filesystem(
         &mut cx.mtime_cache,
         unit.pkg.root(),
         &target_root,
         cargo_exe,
+        &env_overrides,
     )?;
 
     let fingerprint = Arc::new(fingerprint);
@@ -1287,7 +1340,25 @@ fn calculate_normal(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Finger
     } else {
         let dep_info = dep_info_loc(cx, unit);
         let dep_info = dep_info.strip_prefix(&target_root).unwrap().to_path_buf();
-        vec![LocalFingerprint::CheckDepInfo { dep_info }]
+        let mut local = vec![LocalFingerprint::CheckDepInfo { dep_info }];
+        // Separate debug info is copied next to the binaries, so rebuild if
+        // it went missing.
+        let paths: Vec<_> = cx
+            .outputs(unit)?
+            .iter()
+            .filter(|output| output.is_packed_debuginfo())
+            .map(|output| {
+                output
+                    .path
+                    .strip_prefix(&target_root)
+                    .unwrap()
+                    .to_path_buf()
+            })
+            .collect();
+        if !paths.is_empty() {
+            local.push(LocalFingerprint::RerunIfMissing { paths });
+        }
+        local
     };
 
     // Figure out what the outputs of our unit is, and we'll be storing them
@@ -1311,7 +1382,7 @@ fn calculate_normal(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Finger
 
     let profile_hash = util::hash_u64((
         &unit.profile,
-        unit.mode,
+        cx.bcx.hashed_mode(unit),
         cx.bcx.extra_args_for(unit),
         cx.lto[unit],
     ));
@@ -1330,9 +1401,25 @@ fn calculate_normal(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Finger
     if let Some(allow_features) = &cx.bcx.config.cli_unstable().allow_features {
         allow_features.hash(&mut config);
     }
+    if unused_deps::lints_unused_externs(cx, unit) {
+        "unused-crate-dependencies".hash(&mut config);
+    }
+    let backend_path = unit
+        .profile
+        .codegen_backend
+        .and_then(|backend| super::codegen_backend_path(cx.bcx.ws, &backend));
+    if let Some(path) = backend_path {
+        // A backend given by path is usually being developed, so rebuild
+        // whenever it changes.
+        paths::mtime(&path).ok().hash(&mut config);
+    }
+    let check_cfgs = unit.pkg.manifest().check_cfgs();
+    if !check_cfgs.is_empty() {
+        check_cfgs.hash(&mut config);
+    }
     let compile_kind = unit.kind.fingerprint_hash();
     Ok(Fingerprint {
-        rustc: util::hash_u64(&cx.bcx.rustc().verbose_version),
+        rustc: util::hash_u64(&cx.bcx.rustc_for(unit).verbose_version),
         target: util::hash_u64(&unit.target),
         profile: profile_hash,
         // Note that .0 is hashed here, not .1 which is the cwd. That doesn't
@@ -1361,7 +1448,7 @@ fn calculate_run_custom_build(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoRes
     // the build script this means we'll be watching files and env vars.
     // Otherwise if we haven't previously executed it we'll just start watching
     // the whole crate.
-    let (gen_local, overridden) = build_script_local_fingerprints(cx, unit);
+    let (gen_local, overridden) = build_script_local_fingerprints(cx, unit)?;
     let deps = &cx.build_explicit_deps[unit];
     let local = (gen_local)(
         deps,
@@ -1402,7 +1489,7 @@ See https://doc.rust-lang.org/cargo/reference/build-scripts.html#rerun-if-change
 
     Ok(Fingerprint {
         local: Mutex::new(local),
-        rustc: util::hash_u64(&cx.bcx.rustc().verbose_version),
+        rustc: util::hash_u64(&cx.bcx.rustc_for(unit).verbose_version),
         deps,
         outputs: if overridden { Vec::new() } else { vec![output] },
 
@@ -1450,7 +1537,7 @@ See https://doc.rust-lang.org/cargo/reference/build-scripts.html#rerun-if-change
 fn build_script_local_fingerprints(
     cx: &mut Context<'_, '_>,
     unit: &Unit,
-) -> (
+) -> CargoResult<(
     Box<
         dyn FnOnce(
                 &BuildDeps,
@@ -1459,20 +1546,20 @@ fn build_script_local_fingerprints(
             + Send,
     >,
     bool,
-) {
+)> {
     assert!(unit.mode.is_run_custom_build());
     // First up, if this build script is entirely overridden, then we just
     // return the hash of what we overrode it with. This is the easy case!
     if let Some(fingerprint) = build_script_override_fingerprint(cx, unit) {
         debug!("override local fingerprints deps {}", unit.pkg);
-        return (
+        return Ok((
             Box::new(
                 move |_: &BuildDeps, _: Option<&dyn Fn() -> CargoResult<String>>| {
                     Ok(Some(vec![fingerprint]))
                 },
             ),
             true, // this is an overridden build script
-        );
+        ));
     }
 
     // ... Otherwise this is a "real" build script and we need to return a real
@@ -1484,9 +1571,14 @@ fn build_script_local_fingerprints(
     // obvious.
     let pkg_root = unit.pkg.root().to_path_buf();
     let target_dir = target_root(cx);
+    let script_out_dir = cx.files().build_script_out_dir(unit);
+    let env_overrides = cx.bcx.config.env_config_overrides()?;
     let calculate =
         move |deps: &BuildDeps, pkg_fingerprint: Option<&dyn Fn() -> CargoResult<String>>| {
-            if deps.rerun_if_changed.is_empty() && deps.rerun_if_env_changed.is_empty() {
+            if deps.rerun_if_changed.is_empty()
+                && deps.rerun_if_env_changed.is_empty()
+                && deps.declared_outputs.is_empty()
+            {
                 match pkg_fingerprint {
                     // FIXME: this is somewhat buggy with respect to docker and
                     // weird filesystems. The `Precalculated` variant
@@ -1513,11 +1605,17 @@ fn build_script_local_fingerprints(
             // Ok so now we're in "new mode" where we can have files listed as
             // dependencies as well as env vars listed as dependencies. Process
             // them all here.
-            Ok(Some(local_fingerprints_deps(deps, &target_dir, &pkg_root)))
+            Ok(Some(local_fingerprints_deps(
+                deps,
+                &target_dir,
+                &pkg_root,
+                &script_out_dir,
+                &env_overrides,
+            )))
         };
 
     // Note that `false` == "not overridden"
-    (Box::new(calculate), false)
+    Ok((Box::new(calculate), false))
 }
 
 /// Create a `LocalFingerprint` for an overridden build script.
@@ -1541,11 +1639,14 @@ fn build_script_override_fingerprint(
 
 /// Compute the `LocalFingerprint` values for a `RunCustomBuild` unit for
 /// non-overridden new-style build scripts only. This is only used when `deps`
-/// is already known to have a nonempty `rerun-if-*` somewhere.
+/// is already known to have a nonempty `rerun-if-*` or declared outputs
+/// somewhere.
 fn local_fingerprints_deps(
     deps: &BuildDeps,
     target_root: &Path,
     pkg_root: &Path,
+    script_out_dir: &Path,
+    env_overrides: &HashMap<String, OsString>,
 ) -> Vec<LocalFingerprint> {
     debug!("new local fingerprints deps {:?}", pkg_root);
     let mut local = Vec::new();
@@ -1572,13 +1673,35 @@ fn local_fingerprints_deps(
             .iter()
             .map(|var| LocalFingerprint::RerunIfEnvChanged {
                 var: var.clone(),
-                val: env::var(var).ok(),
+                val: env_var_with_overrides(env_overrides, var),
             }),
     );
 
+    if !deps.declared_outputs.is_empty() {
+        let out_dir = script_out_dir.strip_prefix(target_root).unwrap();
+        let paths = deps
+            .declared_outputs
+            .iter()
+            .map(|p| out_dir.join(p))
+            .collect();
+        local.push(LocalFingerprint::RerunIfMissing { paths });
+    }
+
     local
 }
 
+/// Returns the value of the environment variable `key` as seen by processes
+/// spawned by Cargo, where `env_overrides` are the values from the `[env]`
+/// config table (see [`Config::env_config_overrides`]).
+///
+/// [`Config::env_config_overrides`]: crate::util::Config::env_config_overrides
+fn env_var_with_overrides(env_overrides: &HashMap<String, OsString>, key: &str) -> Option<String> {
+    match env_overrides.get(key) {
+        Some(value) => value.to_str().map(str::to_string),
+        None => env::var(key).ok(),
+    }
+}
+
 fn write_fingerprint(loc: &Path, fingerprint: &Fingerprint) -> CargoResult<()> {
     debug_assert_ne!(fingerprint.rustc, 0);
     // fingerprint::new().rustc == 0, make sure it doesn't make it to the file system.
@@ -1586,14 +1709,14 @@ fn write_fingerprint(loc: &Path, fingerprint: &Fingerprint) -> CargoResult<()> {
     // as we can use the full hash.
     let hash = fingerprint.hash_u64();
     debug!("write fingerprint ({:x}) : {}", hash, loc.display());
-    paths::write(loc, util::to_hex(hash).as_bytes())?;
+    paths::write_atomic(loc, util::to_hex(hash).as_bytes())?;
 
     let json = serde_json::to_string(fingerprint).unwrap();
     if cfg!(debug_assertions) {
         let f: Fingerprint = serde_json::from_str(&json).unwrap();
         assert_eq!(f.hash_u64(), hash);
     }
-    paths::write(&loc.with_extension("json"), json.as_bytes())?;
+    paths::write_atomic(&loc.with_extension("json"), json.as_bytes())?;
     Ok(())
 }
 
@@ -1656,6 +1779,26 @@ fn compare_old_fingerprint(
     result
 }
 
+/// Whether the fingerprint files at `loc`, or the dep-info file `dep_info`,
+/// exist but can't be read back, like after a crash while writing them.
+///
+/// Like any other mismatch, this makes the unit dirty, so it's rebuilt and
+/// its files are written again. The files are written with
+/// [`paths::write_atomic`], which doesn't sync them to disk, so this is also
+/// what recovers from the empty or truncated files a power loss leaves.
+fn has_corrupt_fingerprint(loc: &Path, dep_info: &Path) -> bool {
+    let corrupt = |path: &Path, valid: &dyn Fn(&[u8]) -> bool| match paths::read_bytes(path) {
+        Ok(data) => !valid(&data),
+        Err(_) => false,
+    };
+    // The hash is empty while the unit is rebuilt, see `prepare_target`.
+    corrupt(loc, &|hash| {
+        hash.is_empty() || (hash.len() == 16 && hash.iter().all(u8::is_ascii_hexdigit))
+    }) || corrupt(&loc.with_extension("json"), &|json| {
+        serde_json::from_slice::<Fingerprint>(json).is_ok()
+    }) || corrupt(dep_info, &|data| EncodedDepInfo::parse(data).is_some())
+}
+
 fn log_compare(unit: &Unit, compare: &CargoResult<()>) {
     let ce = match compare {
         Ok(..) => return,
@@ -1819,6 +1962,7 @@ pub fn translate_dep_info(
     pkg_root: &Path,
     target_root: &Path,
     rustc_cmd: &ProcessBuilder,
+    env_overrides: &HashMap<String, OsString>,
     allow_package: bool,
 ) -> CargoResult<()> {
     let depinfo = parse_rustc_dep_info(rustc_dep_info)?;
@@ -1856,9 +2000,16 @@ pub fn translate_dep_info(
     // This also includes `CARGO` since if the code is explicitly wanting to
     // know that path, it should be rebuilt if it changes. The CARGO path is
     // not tracked elsewhere in the fingerprint.
-    on_disk_info
-        .env
-        .retain(|(key, _)| !rustc_cmd.get_envs().contains_key(key) || key == CARGO_ENV);
+    //
+    // Values from the `[env]` config table are kept as well, as they are not
+    // tracked anywhere else. `find_stale_item` compares them against the
+    // config rather than Cargo's own environment.
+    on_disk_info.env.retain(|(key, _)| {
+        let from_env_config = env_overrides.get(key).map_or(false, |value| {
+            rustc_cmd.get_env(key).as_ref() == Some(value)
+        });
+        !rustc_cmd.get_envs().contains_key(key) || key == CARGO_ENV || from_env_config
+    });
 
     for file in depinfo.files {
         // The path may be absolute or relative, canonical or not. Make sure
@@ -1884,7 +2035,7 @@ pub fn translate_dep_info(
         };
         on_disk_info.files.push((ty, path.to_owned()));
     }
-    paths::write(cargo_dep_info, on_disk_info.serialize()?)?;
+    paths::write_atomic(cargo_dep_info, on_disk_info.serialize()?)?;
     Ok(())
 }
 
@@ -9,7 +9,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Write as _;
 use std::io::{Read, Write};
+use std::str::FromStr;
 use std::task::Poll;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub const REPORT_PREAMBLE: &str = "\
 The following warnings were discovered during the build. These warnings are an
@@ -49,11 +51,36 @@ pub struct FutureBreakageItem {
 }
 
 /// A diagnostic emitted by the compiler as a JSON message.
-/// We only care about the 'rendered' field
+/// We only care about the 'rendered' field, and the lint name in 'code'
+/// for `--diff-since`.
 #[derive(Serialize, Deserialize)]
 pub struct Diagnostic {
     pub rendered: String,
     pub level: String,
+    #[serde(default)]
+    pub code: Option<DiagnosticCode>,
+}
+
+/// The code of a diagnostic, which is the lint name for lints.
+#[derive(Serialize, Deserialize)]
+pub struct DiagnosticCode {
+    pub code: String,
+}
+
+impl Diagnostic {
+    /// The name identifying the lint of this diagnostic, falling back to
+    /// its first line if rustc didn't provide a code.
+    fn lint_name(&self) -> String {
+        match &self.code {
+            Some(code) => code.code.clone(),
+            None => {
+                let rendered = strip_ansi_escapes::strip(&self.rendered)
+                    .map(|v| String::from_utf8_lossy(&v).into_owned())
+                    .unwrap_or_else(|_| self.rendered.clone());
+                rendered.lines().next().unwrap_or_default().to_string()
+            }
+        }
+    }
 }
 
 /// The filename in the top-level `target` directory where we store
@@ -87,6 +114,44 @@ struct OnDiskReport {
     /// We use a `BTreeMap` so that the iteration order
     /// is stable across multiple runs of `cargo`
     per_package: BTreeMap<String, String>,
+    /// When the report was saved, in seconds since the Unix epoch.
+    #[serde(default)]
+    timestamp: u64,
+    /// A hash of `Cargo.lock` at the time the report was saved.
+    #[serde(default)]
+    lockfile: Option<String>,
+    /// The lints triggered by each package version, keyed like
+    /// `per_package`, to compare reports with `--diff-since`.
+    #[serde(default)]
+    lints: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// The report to compare with for `cargo report future-incompat --diff-since`.
+pub enum DiffSince {
+    /// The most recent report saved before the given time.
+    Date(SystemTime),
+    /// The most recent report saved with a different `Cargo.lock`.
+    Lock,
+}
+
+impl FromStr for DiffSince {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> CargoResult<DiffSince> {
+        if s == "lock" {
+            return Ok(DiffSince::Lock);
+        }
+        humantime::parse_rfc3339_weak(s)
+            .or_else(|_| humantime::parse_rfc3339_weak(&format!("{} 00:00:00", s)))
+            .map(DiffSince::Date)
+            .map_err(|_| {
+                format_err!(
+                    "invalid value `{}` for `--diff-since`, \
+                     expected `lock` or a date like `2022-11-30`",
+                    s
+                )
+            })
+    }
 }
 
 impl Default for OnDiskReports {
@@ -111,6 +176,13 @@ impl OnDiskReports {
             id: self.next_id,
             suggestion_message,
             per_package: render_report(per_package_reports),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            lockfile: std::fs::read(ws.lock_file_path())
+                .ok()
+                .map(|contents| crate::util::hex::short_hash(&contents)),
+            lints: report_lints(per_package_reports),
         };
         self.next_id += 1;
         self.reports.push(report);
@@ -176,13 +248,80 @@ impl OnDiskReports {
         self.reports.last().map(|r| r.id).unwrap()
     }
 
-    pub fn get_report(
-        &self,
-        id: u32,
-        config: &Config,
-        package: Option<&str>,
-    ) -> CargoResult<String> {
-        let report = self.reports.iter().find(|r| r.id == id).ok_or_else(|| {
+    /// Describes the lints the report `id` has in addition to the one
+    /// selected by `since`, per package.
+    pub fn get_diff(&self, id: u32, since: &DiffSince) -> CargoResult<String> {
+        let report = self.find(id)?;
+        let baseline = self
+            .reports
+            .iter()
+            .rev()
+            .filter(|r| r.id < id)
+            .find(|r| match since {
+                DiffSince::Date(date) => {
+                    let secs = date.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+                    r.timestamp < secs
+                }
+                DiffSince::Lock => r.lockfile != report.lockfile,
+            })
+            .ok_or_else(|| {
+                let what = match since {
+                    DiffSince::Date(_) => "saved before that date",
+                    DiffSince::Lock => "saved with a different Cargo.lock",
+                };
+                format_err!(
+                    "could not find a report older than report {} {}\n\
+                     Only the last {} reports are kept",
+                    id,
+                    what,
+                    MAX_REPORTS
+                )
+            })?;
+
+        // Lints are compared by package name, so updating a package to a
+        // version with the same problems doesn't report anything new.
+        let mut previous: BTreeMap<&str, (Vec<&str>, BTreeSet<&String>)> = BTreeMap::new();
+        for (spec, lints) in &baseline.lints {
+            let (name, version) = spec.rsplit_once('@').unwrap_or((spec, ""));
+            let entry = previous.entry(name).or_default();
+            entry.0.push(version);
+            entry.1.extend(lints);
+        }
+        let mut introduced = String::new();
+        for (spec, lints) in &report.lints {
+            let (name, version) = spec.rsplit_once('@').unwrap_or((spec, ""));
+            let (versions, known) = previous.get(name).cloned().unwrap_or_default();
+            let new: Vec<_> = lints.iter().filter(|l| !known.contains(l)).collect();
+            if new.is_empty() {
+                continue;
+            }
+            write!(introduced, "  - {}", spec).unwrap();
+            if !versions.is_empty() && !versions.contains(&version) {
+                write!(introduced, " (updated from {})", versions.join(", ")).unwrap();
+            }
+            writeln!(introduced, ": {}", iter_join(new, ", ")).unwrap();
+        }
+
+        let saved = humantime::format_rfc3339_seconds(
+            UNIX_EPOCH + std::time::Duration::from_secs(baseline.timestamp),
+        );
+        if introduced.is_empty() {
+            Ok(format!(
+                "No future incompatibilities were introduced in report {} \
+                 since report {} (saved {}).\n",
+                id, baseline.id, saved
+            ))
+        } else {
+            Ok(format!(
+                "The following future incompatibilities were introduced in report {} \
+                 since report {} (saved {}):\n{}",
+                id, baseline.id, saved, introduced
+            ))
+        }
+    }
+
+    fn find(&self, id: u32) -> CargoResult<&OnDiskReport> {
+        self.reports.iter().find(|r| r.id == id).ok_or_else(|| {
             let available = iter_join(self.reports.iter().map(|r| r.id.to_string()), ", ");
             format_err!(
                 "could not find report with ID {}\n\
@@ -190,7 +329,16 @@ impl OnDiskReports {
                 id,
                 available
             )
-        })?;
+        })
+    }
+
+    pub fn get_report(
+        &self,
+        id: u32,
+        config: &Config,
+        package: Option<&str>,
+    ) -> CargoResult<String> {
+        let report = self.find(id)?;
 
         let mut to_display = report.suggestion_message.clone();
         to_display += "\n";
@@ -257,6 +405,24 @@ fn render_report(per_package_reports: &[FutureIncompatReportPackage]) -> BTreeMa
     report
 }
 
+fn report_lints(
+    per_package_reports: &[FutureIncompatReportPackage],
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut lints: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for per_package in per_package_reports {
+        let package_spec = format!(
+            "{}@{}",
+            per_package.package_id.name(),
+            per_package.package_id.version()
+        );
+        lints
+            .entry(package_spec)
+            .or_default()
+            .extend(per_package.items.iter().map(|i| i.diagnostic.lint_name()));
+    }
+    lints
+}
+
 /// Returns a user-readable message explaining which of
 /// the packages in `package_ids` have updates available.
 /// This is best-effort - if an error occurs, `None` will be returned.
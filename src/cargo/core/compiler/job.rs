@@ -73,6 +73,13 @@ impl Job {
         let prev = mem::replace(&mut self.work, Work::noop());
         self.work = next.then(prev);
     }
+
+    /// Replaces the work of this job with the one returned by `f`, which
+    /// usually calls the original work.
+    pub fn wrap(&mut self, f: impl FnOnce(Work) -> Work) {
+        let prev = mem::replace(&mut self.work, Work::noop());
+        self.work = f(prev);
+    }
 }
 
 impl fmt::Debug for Job {
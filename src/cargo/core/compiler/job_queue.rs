@@ -59,7 +59,7 @@ use std::thread::{self, Scope};
 use std::time::Duration;
 
 use anyhow::{format_err, Context as _};
-use cargo_util::ProcessBuilder;
+use cargo_util::{ProcessBuilder, ResourceUsage};
 use jobserver::{Acquired, Client, HelperThread};
 use log::{debug, trace};
 use semver::Version;
@@ -70,6 +70,7 @@ use super::job::{
     Job,
 };
 use super::timings::Timings;
+use super::unused_deps;
 use super::{BuildContext, BuildPlan, CompileMode, Context, Unit};
 use crate::core::compiler::future_incompat::{
     self, FutureBreakageItem, FutureIncompatReportPackage,
@@ -78,6 +79,7 @@ use crate::core::resolver::ResolveBehavior;
 use crate::core::{PackageId, Shell, TargetKind};
 use crate::util::diagnostic_server::{self, DiagnosticPrinter};
 use crate::util::errors::AlreadyPrintedError;
+use crate::util::interning::InternedString;
 use crate::util::machine_message::{self, Message as _};
 use crate::util::CargoResult;
 use crate::util::{self, internal, profile};
@@ -137,6 +139,9 @@ struct DrainState<'cfg> {
     scraped: HashSet<PackageId>,
     counts: HashMap<PackageId, usize>,
     progress: Progress<'cfg>,
+    /// The `finished` count and `next_id` of the last `build-progress`
+    /// message, to only emit one when units were started or finished.
+    progress_emitted: Option<(usize, u32)>,
     next_id: u32,
     timings: Timings<'cfg>,
 
@@ -164,7 +169,13 @@ struct DrainState<'cfg> {
 
     /// How many jobs we've finished
     finished: usize,
+    /// How many warnings were suppressed across all jobs for being
+    /// duplicates of a previous warning.
+    duplicate_warnings: usize,
     per_package_future_incompat_reports: Vec<FutureIncompatReportPackage>,
+    /// The extern crates each unit reported as unused, for
+    /// `--warn-unused-dependencies`.
+    unused_externs: HashMap<Unit, HashSet<InternedString>>,
 }
 
 /// Count of warnings, used to print a summary after the job succeeds
@@ -296,6 +307,12 @@ pub struct JobState<'a, 'cfg> {
 /// Handler for deduplicating diagnostics.
 struct DiagDedupe<'cfg> {
     seen: RefCell<HashSet<u64>>,
+    /// Whether duplicates are suppressed, which `-Z show-duplicate-warnings`
+    /// turns off.
+    dedupe: bool,
+    /// With `-Z grouped-output`, the diagnostics of each job which are held
+    /// back until the job finishes.
+    groups: Option<RefCell<HashMap<JobId, Vec<String>>>>,
     config: &'cfg Config,
 }
 
@@ -303,24 +320,58 @@ impl<'cfg> DiagDedupe<'cfg> {
     fn new(config: &'cfg Config) -> Self {
         DiagDedupe {
             seen: RefCell::new(HashSet::new()),
+            dedupe: !config.cli_unstable().show_duplicate_warnings,
+            groups: config
+                .cli_unstable()
+                .grouped_output
+                .then(|| RefCell::new(HashMap::new())),
             config,
         }
     }
 
-    /// Emits a diagnostic message.
+    /// Emits a diagnostic message of the job `id`.
     ///
     /// Returns `true` if the message was emitted, or `false` if it was
     /// suppressed for being a duplicate.
-    fn emit_diag(&self, diag: &str) -> CargoResult<bool> {
+    fn emit_diag(&self, id: JobId, diag: &str) -> CargoResult<bool> {
         let h = util::hash_u64(diag);
-        if !self.seen.borrow_mut().insert(h) {
+        if !self.seen.borrow_mut().insert(h) && self.dedupe {
             return Ok(false);
         }
+        if let Some(groups) = &self.groups {
+            groups
+                .borrow_mut()
+                .entry(id)
+                .or_default()
+                .push(diag.to_string());
+            return Ok(true);
+        }
         let mut shell = self.config.shell();
         shell.print_ansi_stderr(diag.as_bytes())?;
         shell.err().write_all(b"\n")?;
         Ok(true)
     }
+
+    /// Prints the diagnostics held back for the job `id` as one collapsible
+    /// group named `title`, using the workflow commands of GitHub Actions.
+    fn flush_group(&self, id: JobId, title: &str) -> CargoResult<()> {
+        let diags = match self
+            .groups
+            .as_ref()
+            .and_then(|groups| groups.borrow_mut().remove(&id))
+        {
+            Some(diags) => diags,
+            None => return Ok(()),
+        };
+        let mut shell = self.config.shell();
+        writeln!(shell.err(), "::group::{}", title)?;
+        for diag in diags {
+            shell.print_ansi_stderr(diag.as_bytes())?;
+            shell.err().write_all(b"\n")?;
+        }
+        writeln!(shell.err(), "::endgroup::")?;
+        Ok(())
+    }
 }
 
 /// Possible artifacts that can be produced by compilations, used as edge values
@@ -348,6 +399,8 @@ enum Message {
     BuildPlanMsg(String, ProcessBuilder, Arc<Vec<OutputFile>>),
     Stdout(String),
     Stderr(String),
+    // A job waits for a lock held by another process.
+    Blocking(String),
 
     // This is for general stderr output from subprocesses
     Diagnostic {
@@ -375,6 +428,8 @@ enum Message {
     Token(io::Result<Acquired>),
     Finish(JobId, Artifact, CargoResult<()>),
     FutureIncompatReport(JobId, Vec<FutureBreakageItem>),
+    UnusedExtern(JobId, InternedString),
+    ResourceUsage(JobId, ResourceUsage),
 
     // This client should get release_raw called on it with one of our tokens
     NeedsToken(JobId),
@@ -418,10 +473,19 @@ impl<'a, 'cfg> JobState<'a, 'cfg> {
         Ok(())
     }
 
+    /// Reports that the job waits for the lock on `what`, held by another
+    /// process.
+    pub fn blocking(&self, what: &str) {
+        self.messages.push(Message::Blocking(format!(
+            "waiting for file lock on {}",
+            what
+        )));
+    }
+
     /// See [`Message::Diagnostic`] and [`Message::WarningCount`].
     pub fn emit_diag(&self, level: String, diag: String, fixable: bool) -> CargoResult<()> {
         if let Some(dedupe) = self.output {
-            let emitted = dedupe.emit_diag(&diag)?;
+            let emitted = dedupe.emit_diag(self.id, &diag)?;
             if level == "warning" {
                 self.messages.push(Message::WarningCount {
                     id: self.id,
@@ -465,6 +529,18 @@ impl<'a, 'cfg> JobState<'a, 'cfg> {
             .push(Message::FutureIncompatReport(self.id, report));
     }
 
+    /// The unit reported the extern crate `name` as unused, see
+    /// `--warn-unused-dependencies`.
+    pub fn unused_extern(&self, name: InternedString) {
+        self.messages.push(Message::UnusedExtern(self.id, name));
+    }
+
+    /// The process of this job used `usage`, which is shown in the timing
+    /// reports.
+    pub fn resource_usage(&self, usage: ResourceUsage) {
+        self.messages.push(Message::ResourceUsage(self.id, usage));
+    }
+
     /// The rustc underlying this Job is about to acquire a jobserver token (i.e., block)
     /// on the passed client.
     ///
@@ -503,6 +579,9 @@ impl<'cfg> JobQueue<'cfg> {
                     || dep.unit.artifact.is_true()
                     || dep.unit.mode.is_doc_scrape()
             })
+            // Artifacts of a dependency with `lib = true` are linked while we
+            // compile against the metadata of its lib.
+            .filter(|dep| !cx.only_requires_artifact_paths(unit, &dep.unit))
             .map(|dep| {
                 // Handle the case here where our `unit -> dep` dependency may
                 // only require the metadata, not the full compilation to
@@ -591,6 +670,7 @@ impl<'cfg> JobQueue<'cfg> {
             scraped: HashSet::new(),
             counts: self.counts,
             progress,
+            progress_emitted: None,
             next_id: 0,
             timings: self.timings,
             tokens: Vec::new(),
@@ -599,7 +679,9 @@ impl<'cfg> JobQueue<'cfg> {
             pending_queue: Vec::new(),
             print: DiagnosticPrinter::new(cx.bcx.config),
             finished: 0,
+            duplicate_warnings: 0,
             per_package_future_incompat_reports: Vec::new(),
+            unused_externs: HashMap::new(),
         };
 
         // Create a helper thread for acquiring jobserver tokens
@@ -749,13 +831,19 @@ impl<'cfg> DrainState<'cfg> {
                 shell.print_ansi_stderr(err.as_bytes())?;
                 shell.err().write_all(b"\n")?;
             }
+            Message::Blocking(msg) => {
+                cx.bcx
+                    .config
+                    .shell()
+                    .status_with_color("Blocking", msg, termcolor::Color::Cyan)?;
+            }
             Message::Diagnostic {
                 id,
                 level,
                 diag,
                 fixable,
             } => {
-                let emitted = self.diag_dedupe.emit_diag(&diag)?;
+                let emitted = self.diag_dedupe.emit_diag(id, &diag)?;
                 if level == "warning" {
                     self.bump_warning_count(id, emitted, fixable);
                 }
@@ -799,6 +887,14 @@ impl<'cfg> DrainState<'cfg> {
                             self.tokens.extend(rustc_tokens);
                         }
                         self.to_send_clients.remove(&id);
+                        let unit = &self.active[&id];
+                        let title = format!(
+                            "{} v{} ({})",
+                            unit.pkg.name(),
+                            unit.pkg.version(),
+                            unit_description(unit)
+                        );
+                        self.diag_dedupe.flush_group(id, &title)?;
                         self.report_warning_count(cx.bcx.config, id);
                         self.active.remove(&id).unwrap()
                     }
@@ -838,6 +934,13 @@ impl<'cfg> DrainState<'cfg> {
                 self.per_package_future_incompat_reports
                     .push(FutureIncompatReportPackage { package_id, items });
             }
+            Message::UnusedExtern(id, name) => {
+                let unit = self.active[&id].clone();
+                self.unused_externs.entry(unit).or_default().insert(name);
+            }
+            Message::ResourceUsage(id, usage) => {
+                self.timings.unit_resource_usage(id, usage);
+            }
             Message::Token(acquired_token) => {
                 let token = acquired_token.with_context(|| "failed to acquire jobserver token")?;
                 self.tokens.push(token);
@@ -946,6 +1049,10 @@ impl<'cfg> DrainState<'cfg> {
                 }
             }
 
+            if let Err(e) = self.emit_progress(cx) {
+                self.handle_error(&mut cx.bcx.config.shell(), &mut errors, e);
+            }
+
             // If after all that we're not actually running anything then we're
             // done!
             if self.active.is_empty() {
@@ -991,12 +1098,17 @@ impl<'cfg> DrainState<'cfg> {
         if let Err(e) = self.timings.finished(cx, &errors.to_error()) {
             self.handle_error(&mut cx.bcx.config.shell(), &mut errors, e);
         }
+        if errors.count == 0 && self.queue.is_empty() && self.pending_queue.is_empty() {
+            if let Err(e) = unused_deps::report(cx, &self.unused_externs) {
+                self.handle_error(&mut cx.bcx.config.shell(), &mut errors, e);
+            }
+        }
         if cx.bcx.build_config.emit_json() {
             let mut shell = cx.bcx.config.shell();
             let msg = machine_message::BuildFinished {
                 success: errors.count == 0,
             }
-            .to_json_string();
+            .to_json_string_for(cx.bcx.build_config.message_format);
             if let Err(e) = writeln!(shell.out(), "{}", msg) {
                 self.handle_error(&mut shell, &mut errors, e);
             }
@@ -1013,6 +1125,18 @@ impl<'cfg> DrainState<'cfg> {
             );
             if !cx.bcx.build_config.build_plan {
                 // It doesn't really matter if this fails.
+                if self.duplicate_warnings > 0 && cx.bcx.config.nightly_features_allowed {
+                    drop(cx.bcx.config.shell().note(format!(
+                        "{} duplicate warning{} not shown, \
+                         pass `-Z show-duplicate-warnings` to show every instance",
+                        self.duplicate_warnings,
+                        if self.duplicate_warnings == 1 {
+                            " was"
+                        } else {
+                            "s were"
+                        }
+                    )));
+                }
                 drop(cx.bcx.config.shell().status("Finished", message));
                 future_incompat::save_and_display_report(
                     cx.bcx,
@@ -1072,6 +1196,46 @@ impl<'cfg> DrainState<'cfg> {
         ));
     }
 
+    /// Emits a `build-progress` message with `-Z build-progress` and
+    /// `--message-format json`, if units were started or finished since the
+    /// last one.
+    fn emit_progress(&mut self, cx: &Context<'_, '_>) -> CargoResult<()> {
+        let config = cx.bcx.config;
+        if !cx.bcx.build_config.emit_json() || !config.cli_unstable().build_progress {
+            return Ok(());
+        }
+        let snapshot = (self.finished, self.next_id);
+        if self.progress_emitted == Some(snapshot) {
+            return Ok(());
+        }
+        self.progress_emitted = Some(snapshot);
+
+        let mut active = self.active.iter().collect::<Vec<_>>();
+        active.sort_by_key(|(id, _)| **id);
+        let active = active
+            .into_iter()
+            .map(|(_, unit)| self.name_for_progress(unit))
+            .collect();
+        // A naive estimate assuming the remaining units take as long on
+        // average as the finished ones did.
+        let eta_secs = if self.finished == 0 {
+            None
+        } else {
+            let elapsed = config.creation_time().elapsed().as_secs_f64();
+            let remaining = self.total_units.saturating_sub(self.finished);
+            Some((elapsed / self.finished as f64 * remaining as f64).round() as u64)
+        };
+        let msg = machine_message::BuildProgress {
+            total: self.total_units,
+            finished: self.finished,
+            active,
+            eta_secs,
+        }
+        .to_json_string_for(cx.bcx.build_config.message_format);
+        writeln!(config.shell().out(), "{}", msg)?;
+        Ok(())
+    }
+
     fn name_for_progress(&self, unit: &Unit) -> String {
         let pkg_name = unit.pkg.name();
         let target_name = unit.target.name();
@@ -1230,6 +1394,7 @@ impl<'cfg> DrainState<'cfg> {
         cnts.total += 1;
         if !emitted {
             cnts.duplicates += 1;
+            self.duplicate_warnings += 1;
         // Don't add to fixable if it's already been emitted
         } else if fixable {
             // Do not add anything to the fixable warning count if
@@ -1255,15 +1420,11 @@ impl<'cfg> DrainState<'cfg> {
             None | Some(_) => return,
         };
         let unit = &self.active[&id];
-        let mut message = format!("`{}` ({}", unit.pkg.name(), unit.target.description_named());
-        if unit.mode.is_rustc_test() && !(unit.target.is_test() || unit.target.is_bench()) {
-            message.push_str(" test");
-        } else if unit.mode.is_doc_test() {
-            message.push_str(" doctest");
-        } else if unit.mode.is_doc() {
-            message.push_str(" doc");
-        }
-        message.push_str(") generated ");
+        let mut message = format!(
+            "`{}` ({}) generated ",
+            unit.pkg.name(),
+            unit_description(unit)
+        );
         match count.total {
             1 => message.push_str("1 warning"),
             n => drop(write!(message, "{} warnings", n)),
@@ -1422,3 +1583,16 @@ impl ErrorsDuringDrain {
         }
     }
 }
+
+/// Describes the target of `unit` and what it's built as, like `lib test`.
+fn unit_description(unit: &Unit) -> String {
+    let mut description = unit.target.description_named();
+    if unit.mode.is_rustc_test() && !(unit.target.is_test() || unit.target.is_bench()) {
+        description.push_str(" test");
+    } else if unit.mode.is_doc_test() {
+        description.push_str(" doctest");
+    } else if unit.mode.is_doc() {
+        description.push_str(" doc");
+    }
+    description
+}
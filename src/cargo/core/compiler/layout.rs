@@ -22,6 +22,10 @@
 //!         # from using it at the same time.
 //!         .cargo-lock
 //!
+//!         # File locked while uplifting artifacts into this directory, when
+//!         # the units are locked individually (`-Z fine-grained-locking`).
+//!         .cargo-uplift-lock
+//!
 //!         # Hidden directory that holds all of the fingerprint files for all
 //!         # packages
 //!         .fingerprint/
@@ -56,6 +60,9 @@
 //!         # Directory used to store incremental data for the compiler (when
 //!         # incremental is enabled.
 //!         incremental/
+//!             # The data of each git branch, with
+//!             # `build.incremental-dir = "per-branch"`.
+//!             branches/$branch/
 //!
 //!         # This is the location at which the output of all custom build
 //!         # commands are rooted.
@@ -100,13 +107,87 @@
 //!
 //! When cross-compiling, the layout is the same, except it appears in
 //! `target/$TRIPLE`.
+//!
+//! # Layout v2
+//!
+//! With `-Z target-layout-v2`, the directories of the units in `.fingerprint`,
+//! `build` and `deps/artifact` are named `$META` instead of `$pkgname-$META`,
+//! which keeps paths short (Windows limits them to 260 characters by default).
+//! The layout is recorded in `.cargo-layout.json` in the profile directory:
+//!
+//! ```text
+//! target/
+//!     # Marks the directory as a cache, so backup tools skip it.
+//!     CACHEDIR.TAG
+//!     debug/
+//!         # The version, and the packages of the `$META` directories.
+//!         # Absent for the original layout.
+//!         .cargo-layout.json
+//!         deps/
+//!             artifact/
+//!                 # Maps `$pkgname-$META` to the `$META` directory of an
+//!                 # artifact.
+//!                 index.json
+//!                 $META/$kind
+//! ```
+//!
+//! Switching between layouts removes the directories whose names depend on
+//! the layout, so the units are built again in the new one.
+//!
+//! # Global target directory
+//!
+//! With `build.target-dir = "global"` (`-Z global-target-dir`), the target
+//! directory of a workspace is `$CACHE/cargo/targets/$HASH`, where `$HASH` is
+//! a hash of the path of the workspace root. The root is recorded in the
+//! target directory, so `cargo clean --gc` can remove the directories of
+//! workspaces that no longer exist:
+//!
+//! ```text
+//! ~/.cache/cargo/targets/
+//!     $HASH/
+//!         # The path of the workspace root.
+//!         .cargo-workspace
+//!         debug/
+//! ```
 
 use crate::core::compiler::CompileTarget;
 use crate::core::Workspace;
-use crate::util::{CargoResult, FileLock};
+use crate::util::config::IncrementalDir;
+use crate::util::{CargoResult, Config, FileLock};
+use anyhow::bail;
 use cargo_util::paths;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+/// The name of the file recording the layout version of a profile directory.
+const LAYOUT_MANIFEST: &str = ".cargo-layout.json";
+
+/// The name of the file recording the workspace of a global target directory.
+const WORKSPACE_MARKER: &str = ".cargo-workspace";
+
+/// The contents of [`LAYOUT_MANIFEST`].
+#[derive(Default, Serialize, Deserialize)]
+struct LayoutManifest {
+    version: u32,
+    /// The packages of the `$META` directories of the v2 layout, which
+    /// `cargo clean -p` can't tell from their names.
+    #[serde(default)]
+    packages: BTreeMap<String, String>,
+}
+
+impl LayoutManifest {
+    fn read(dest: &Path) -> Option<LayoutManifest> {
+        let manifest = paths::read(&dest.join(LAYOUT_MANIFEST)).ok()?;
+        // An unknown manifest is replaced, as if it was another layout.
+        Some(serde_json::from_str(&manifest).unwrap_or_default())
+    }
+
+    fn write(&self, dest: &Path) -> CargoResult<()> {
+        paths::write(&dest.join(LAYOUT_MANIFEST), serde_json::to_string(self)?)
+    }
+}
+
 /// Contains the paths of all target output locations.
 ///
 /// See module docs for more information.
@@ -130,8 +211,12 @@ pub struct Layout {
     examples: PathBuf,
     /// The directory for rustdoc output: `$root/doc`
     doc: PathBuf,
+    /// The directory for rustdoc JSON output: `$root/doc-json`
+    doc_json: PathBuf,
     /// The directory for temporary data of integration tests and benches: `$dest/tmp`
     tmp: PathBuf,
+    /// The version of the layout, 2 with `-Z target-layout-v2`.
+    version: u32,
     /// The lockfile for a build (`.cargo-lock`). Will be unlocked when this
     /// struct is `drop`ped.
     _lock: FileLock,
@@ -149,7 +234,8 @@ impl Layout {
         target: Option<CompileTarget>,
         dest: &str,
     ) -> CargoResult<Layout> {
-        let mut root = ws.target_dir();
+        let target_dir = ws.target_dir();
+        let mut root = target_dir.clone();
         if let Some(target) = target {
             root.push(target.short_name());
         }
@@ -162,13 +248,40 @@ impl Layout {
         // Now that the excluded from backups target root is created we can create the
         // actual destination (sub)subdirectory.
         paths::create_dir_all(dest.as_path_unlocked())?;
+        if ws.is_global_target_dir() {
+            record_workspace(ws, target_dir.as_path_unlocked())?;
+        }
+
+        let config = ws.config();
+        let version = if config.cli_unstable().target_layout_v2 {
+            let target_dir = target_dir.as_path_unlocked();
+            if !target_dir.join("CACHEDIR.TAG").exists() {
+                paths::exclude_from_backups_and_indexing(target_dir);
+            }
+            2
+        } else {
+            1
+        };
 
-        // For now we don't do any more finer-grained locking on the artifact
-        // directory, so just lock the entire thing for the duration of this
-        // compile.
-        let lock = dest.open_rw(".cargo-lock", ws.config(), "build directory")?;
+        // Lock the entire directory for the duration of this compile, unless
+        // the units are locked individually (`-Z fine-grained-locking`). Then
+        // the lock is shared, which still keeps out the processes locking the
+        // entire directory, and whatever removes the directories of a layout.
+        let migrating = layout_version(dest.as_path_unlocked()) != version;
+        let lock = if config.cli_unstable().fine_grained_locking && !migrating {
+            dest.open_with(".cargo-lock", false, &|| {
+                config.shell().status_with_color(
+                    "Blocking",
+                    "waiting for file lock on build directory",
+                    termcolor::Color::Cyan,
+                )
+            })?
+        } else {
+            dest.open_rw(".cargo-lock", config, "build directory")?
+        };
         let root = root.into_path_unlocked();
         let dest = dest.into_path_unlocked();
+        migrate(config, &dest, version)?;
         let deps = dest.join("deps");
         let artifact = deps.join("artifact");
 
@@ -176,11 +289,13 @@ impl Layout {
             deps,
             build: dest.join("build"),
             artifact,
-            incremental: dest.join("incremental"),
+            incremental: incremental_dir(ws, &dest)?,
             fingerprint: dest.join(".fingerprint"),
             examples: dest.join("examples"),
             doc: root.join("doc"),
+            doc_json: root.join("doc-json"),
             tmp: root.join("tmp"),
+            version,
             root,
             dest,
             _lock: lock,
@@ -214,6 +329,10 @@ impl Layout {
     pub fn doc(&self) -> &Path {
         &self.doc
     }
+    /// Fetch the path for rustdoc JSON output.
+    pub fn doc_json(&self) -> &Path {
+        &self.doc_json
+    }
     /// Fetch the root path (`/…/target`).
     pub fn root(&self) -> &Path {
         &self.root
@@ -239,4 +358,160 @@ impl Layout {
         paths::create_dir_all(&self.tmp)?;
         Ok(&self.tmp)
     }
+    /// Fetch the version of the layout.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Records the packages of the `$META` directories of the v2 layout.
+    pub fn record_packages(&self, packages: BTreeMap<String, String>) -> CargoResult<()> {
+        let mut manifest = LayoutManifest::read(&self.dest).unwrap_or_default();
+        let len = manifest.packages.len();
+        manifest.packages.extend(packages);
+        if manifest.packages.len() != len {
+            manifest.version = self.version;
+            manifest.write(&self.dest)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the `$META` directories of the v2 layout of the package `name`.
+    pub fn package_dirs(&self, name: &str) -> Vec<String> {
+        LayoutManifest::read(&self.dest)
+            .map(|manifest| {
+                manifest
+                    .packages
+                    .into_iter()
+                    .filter(|(_, package)| package == name)
+                    .map(|(dir, _)| dir)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the workspace root recorded in the global target directory
+    /// `target_dir`, if any.
+    pub fn global_workspace_root(target_dir: &Path) -> Option<PathBuf> {
+        let root = paths::read_bytes(&target_dir.join(WORKSPACE_MARKER)).ok()?;
+        paths::bytes2path(&root).ok()
+    }
+}
+
+/// Records the workspace owning the global target directory `target_dir`.
+fn record_workspace(ws: &Workspace<'_>, target_dir: &Path) -> CargoResult<()> {
+    let root = ws.root();
+    if let Some(recorded) = Layout::global_workspace_root(target_dir) {
+        if recorded == root {
+            return Ok(());
+        }
+        // Two workspaces with the same hash, unlikely but not impossible.
+        if recorded.join("Cargo.toml").exists() {
+            bail!(
+                "the global target directory `{}` of the workspace at `{}` is \
+                 already used by the workspace at `{}`\n\
+                 Set `build.target-dir` to another directory for one of them.",
+                target_dir.display(),
+                root.display(),
+                recorded.display()
+            );
+        }
+    }
+    paths::write(&target_dir.join(WORKSPACE_MARKER), paths::path2bytes(root)?)
+}
+
+/// The version of the layout the profile directory `dest` was last used with.
+fn layout_version(dest: &Path) -> u32 {
+    match LayoutManifest::read(dest) {
+        Some(manifest) => manifest.version,
+        // The original layout has no manifest.
+        None => 1,
+    }
+}
+
+/// Moves the profile directory `dest` to the layout `version`.
+///
+/// The directories named after the units differ between layouts, so they are
+/// removed if `dest` was used with another layout. The other files stay, and
+/// are overwritten once the units are built again.
+fn migrate(config: &Config, dest: &Path, version: u32) -> CargoResult<()> {
+    if layout_version(dest) == version {
+        return Ok(());
+    }
+
+    let unit_dirs = [".fingerprint", "build", "deps/artifact"].map(|dir| dest.join(dir));
+    if unit_dirs.iter().any(|dir| dir.exists()) {
+        config.shell().verbose(|shell| {
+            shell.status(
+                "Migrating",
+                format!("`{}` to target layout v{}", dest.display(), version),
+            )
+        })?;
+        for dir in &unit_dirs {
+            if dir.exists() {
+                paths::remove_dir_all(dir)?;
+            }
+        }
+    }
+    if version == 1 {
+        paths::remove_file(&dest.join(LAYOUT_MANIFEST))?;
+    } else {
+        LayoutManifest {
+            version,
+            packages: BTreeMap::new(),
+        }
+        .write(dest)?;
+    }
+    Ok(())
+}
+
+/// The directory for the incremental compilation cache in `dest`, which
+/// depends on the git branch with `build.incremental-dir = "per-branch"`.
+fn incremental_dir(ws: &Workspace<'_>, dest: &Path) -> CargoResult<PathBuf> {
+    let config = ws.config();
+    let shared = dest.join("incremental");
+    if !config.cli_unstable().incremental_dir
+        || config.build_config()?.incremental_dir != Some(IncrementalDir::PerBranch)
+    {
+        return Ok(shared);
+    }
+    // Outside of a git repository, or with a detached `HEAD`, all builds
+    // share a cache like without the config.
+    let branch = match git_branch(ws.root()) {
+        Some(branch) => branch,
+        None => return Ok(shared),
+    };
+    let branch: String = branch
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(shared.join("branches").join(branch))
+}
+
+/// The branch checked out in the git repository containing `path`, read from
+/// `HEAD` to avoid opening the repository for every build.
+fn git_branch(path: &Path) -> Option<String> {
+    for dir in path.ancestors() {
+        let dot_git = dir.join(".git");
+        let git_dir = if dot_git.is_dir() {
+            dot_git
+        } else if dot_git.is_file() {
+            // Worktrees and submodules point to their git directory.
+            let contents = paths::read(&dot_git).ok()?;
+            dir.join(contents.strip_prefix("gitdir:")?.trim())
+        } else {
+            continue;
+        };
+        let head = paths::read(&git_dir.join("HEAD")).ok()?;
+        return head
+            .trim()
+            .strip_prefix("ref: refs/heads/")
+            .map(str::to_string);
+    }
+    None
 }
@@ -1,11 +1,16 @@
 use super::unit_graph::UnitGraph;
+use super::RustcTargetData;
 use crate::core::resolver::errors::describe_path;
 use crate::core::{PackageId, Resolve};
 use crate::util::errors::CargoResult;
 use std::collections::{HashMap, HashSet};
 
 /// Validate `links` field does not conflict between packages.
-pub fn validate_links(resolve: &Resolve, unit_graph: &UnitGraph) -> CargoResult<()> {
+pub fn validate_links(
+    resolve: &Resolve,
+    unit_graph: &UnitGraph,
+    target_data: &RustcTargetData<'_>,
+) -> CargoResult<()> {
     // NOTE: This is the *old* links validator. Links are usually validated in
     // the resolver. However, the `links` field was added to the index in
     // early 2018 (see https://github.com/rust-lang/cargo/pull/4978). However,
@@ -24,8 +29,8 @@ pub fn validate_links(resolve: &Resolve, unit_graph: &UnitGraph) -> CargoResult<
             continue;
         }
         let lib = match unit.pkg.manifest().links() {
-            Some(lib) => lib,
-            None => continue,
+            Some(lib) if !target_data.is_shared_link(lib) => lib,
+            _ => continue,
         };
         if let Some(&prev) = links.get(lib) {
             let prev_path = resolve
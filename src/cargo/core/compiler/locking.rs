@@ -0,0 +1,113 @@
+//! Locks on the individual units of a build (`-Z fine-grained-locking`).
+//!
+//! By default, a build holds an exclusive lock on its profile directory, like
+//! `target/debug`, for its entire duration, so `cargo check` and `cargo doc`
+//! can't run at the same time in one workspace. With fine-grained locking,
+//! the lock on the profile directory is shared, and each job locks the units
+//! it touches instead, with the `.lock` file in their fingerprint directory:
+//!
+//! * A dirty job holds an exclusive lock on its unit while it runs, and shared
+//!   locks on the dependencies whose outputs it reads.
+//! * A fresh job only holds a shared lock on its unit, so it waits for another
+//!   process building the unit, but doesn't keep others from reading it.
+//!
+//! The locks of the dependencies are taken before the lock of the unit, and a
+//! job holding an exclusive lock never waits for another lock, so processes
+//! can't deadlock. Dependencies which only need to produce metadata, or whose
+//! outputs aren't read at all, aren't locked, so pipelining keeps working.
+//!
+//! Two processes building the same unit at the same time both build it, one
+//! after the other.
+//!
+//! The outputs of different units may still be uplifted to the same path in
+//! the profile directory, like `target/debug/foo` for builds with different
+//! features. They, and their `.d` dep-info files, are written while holding an
+//! exclusive lock on `.cargo-uplift-lock` in the profile directory instead.
+//! Nothing else is locked while holding it, so it can't deadlock either.
+
+use std::path::{Path, PathBuf};
+
+use super::job::{Freshness, Job, Work};
+use super::{Context, Unit};
+use crate::util::{CargoResult, FileLock, Filesystem};
+
+/// The locks a job of `unit` holds while it runs.
+pub struct UnitLocks {
+    /// The `.lock` file of the unit, and a description for the blocking
+    /// message.
+    unit: (PathBuf, String),
+    /// The `.lock` files of the dependencies read by the job.
+    deps: Vec<(PathBuf, String)>,
+}
+
+impl UnitLocks {
+    pub fn new(cx: &Context<'_, '_>, unit: &Unit) -> UnitLocks {
+        let unit_lock = lock_file(cx, unit);
+        let mut deps: Vec<_> = cx
+            .unit_deps(unit)
+            .iter()
+            .filter(|dep| {
+                !cx.only_requires_rmeta(unit, &dep.unit)
+                    && !cx.only_requires_artifact_paths(unit, &dep.unit)
+            })
+            .map(|dep| lock_file(cx, &dep.unit))
+            .filter(|dep| dep.0 != unit_lock.0)
+            .collect();
+        deps.sort();
+        deps.dedup();
+        UnitLocks {
+            unit: unit_lock,
+            deps,
+        }
+    }
+
+    /// Makes `job` hold these locks while its work runs.
+    pub fn apply(self, job: &mut Job) {
+        let exclusive = job.freshness() == Freshness::Dirty;
+        job.wrap(move |work| {
+            Work::new(move |state| {
+                let mut locks = Vec::new();
+                if exclusive {
+                    for (path, what) in &self.deps {
+                        locks.push(lock(path, false, &|| state.blocking(what))?);
+                    }
+                }
+                let (path, what) = &self.unit;
+                locks.push(lock(path, exclusive, &|| state.blocking(what))?);
+                work.call(state)
+            })
+        });
+    }
+}
+
+/// The `.cargo-uplift-lock` file of the directory `unit` uplifts its outputs
+/// into, like `target/debug`.
+pub fn uplift_lock_file(cx: &Context<'_, '_>, unit: &Unit) -> PathBuf {
+    cx.files()
+        .layout(unit.kind)
+        .dest()
+        .join(".cargo-uplift-lock")
+}
+
+/// Locks the outputs uplifted next to the `.cargo-uplift-lock` file `path`.
+pub fn lock_uplift(path: &Path, on_block: &dyn Fn()) -> CargoResult<FileLock> {
+    lock(path, true, on_block)
+}
+
+/// The `.lock` file of `unit`, and a description of the unit.
+fn lock_file(cx: &Context<'_, '_>, unit: &Unit) -> (PathBuf, String) {
+    let path = cx.files().fingerprint_dir(unit).join(".lock");
+    let what = format!("`{} v{}`", unit.pkg.name(), unit.pkg.version());
+    (path, what)
+}
+
+fn lock(path: &Path, exclusive: bool, on_block: &dyn Fn()) -> CargoResult<FileLock> {
+    Filesystem::new(path.parent().unwrap().to_path_buf()).open_with(
+        path.file_name().unwrap(),
+        exclusive,
+        &|| {
+            on_block();
+            Ok(())
+        },
+    )
+}
@@ -1,4 +1,5 @@
 pub mod artifact;
+mod artifact_manifest;
 mod build_config;
 mod build_context;
 mod build_plan;
@@ -13,14 +14,19 @@ mod job;
 mod job_queue;
 mod layout;
 mod links;
+mod locking;
 mod lto;
 mod output_depinfo;
+mod remote_executor;
 pub mod rustdoc;
+pub mod size_report;
 pub mod standard_lib;
+mod system_deps;
 mod timings;
 mod unit;
 pub mod unit_dependencies;
 pub mod unit_graph;
+mod unused_deps;
 
 use std::collections::{HashMap, HashSet};
 use std::env;
@@ -34,33 +40,39 @@ use anyhow::{Context as _, Error};
 use lazycell::LazyCell;
 use log::{debug, trace};
 
-pub use self::build_config::{BuildConfig, CompileMode, MessageFormat, TimingOutput};
+pub use self::build_config::{
+    BuildConfig, CompileMode, MessageFormat, TimingOutput, UpliftKind, WarningHandling,
+};
 pub use self::build_context::{
     BuildContext, FileFlavor, FileType, RustDocFingerprint, RustcTargetData, TargetInfo,
 };
 use self::build_plan::BuildPlan;
-pub use self::compilation::{Compilation, Doctest, UnitOutput};
+pub use self::compilation::{Compilation, Doctest, RunnerKind, UnitOutput};
 pub use self::compile_kind::{CompileKind, CompileTarget};
 pub use self::context::{Context, Metadata};
 pub use self::crate_type::CrateType;
-pub use self::custom_build::{BuildOutput, BuildScriptOutputs, BuildScripts};
+pub use self::custom_build::{
+    BuildManifest, BuildOutput, BuildScriptOutputs, BuildScripts, ScriptOutput,
+};
 pub use self::job::Freshness;
 use self::job::{Job, Work};
 use self::job_queue::{JobQueue, JobState};
 pub(crate) use self::layout::Layout;
 pub use self::lto::Lto;
 use self::output_depinfo::output_depinfo;
+pub use self::remote_executor::{RemoteExecutor, RemoteMessage, RemoteRequest};
 use self::unit_graph::UnitDep;
+pub use self::unused_deps::UnusedDepsMode;
 use crate::core::compiler::future_incompat::FutureIncompatReport;
 pub use crate::core::compiler::unit::{Unit, UnitInterner};
 use crate::core::manifest::TargetSourcePath;
 use crate::core::profiles::{PanicStrategy, Profile, Strip};
-use crate::core::{Feature, PackageId, Target, Verbosity};
+use crate::core::{Feature, PackageId, Target, Verbosity, Workspace};
 use crate::util::errors::{CargoResult, VerboseError};
 use crate::util::interning::InternedString;
 use crate::util::machine_message::{self, Message};
 use crate::util::{add_path_args, internal, iter_join_onto, profile};
-use cargo_util::{paths, ProcessBuilder, ProcessError};
+use cargo_util::{paths, ProcessBuilder, ProcessError, ResourceUsage};
 use rustfix::diagnostics::Applicability;
 
 const RUSTDOC_CRATE_VERSION_FLAG: &str = "--crate-version";
@@ -111,6 +123,24 @@ pub trait Executor: Send + Sync + 'static {
         on_stderr_line: &mut dyn FnMut(&str) -> CargoResult<()>,
     ) -> CargoResult<()>;
 
+    /// Like [`Executor::exec`], but also stores the resources used by the
+    /// process in `usage`, which are shown in the timing reports.
+    ///
+    /// The default implementation doesn't report any usage.
+    #[allow(clippy::too_many_arguments)]
+    fn exec_with_usage(
+        &self,
+        cmd: &ProcessBuilder,
+        id: PackageId,
+        target: &Target,
+        mode: CompileMode,
+        on_stdout_line: &mut dyn FnMut(&str) -> CargoResult<()>,
+        on_stderr_line: &mut dyn FnMut(&str) -> CargoResult<()>,
+        _usage: &mut Option<ResourceUsage>,
+    ) -> CargoResult<()> {
+        self.exec(cmd, id, target, mode, on_stdout_line, on_stderr_line)
+    }
+
     /// Queried when queuing each unit of work. If it returns true, then the
     /// unit will always be rebuilt, independent of whether it needs to be.
     fn force_rebuild(&self, _unit: &Unit) -> bool {
@@ -136,6 +166,20 @@ impl Executor for DefaultExecutor {
         cmd.exec_with_streaming(on_stdout_line, on_stderr_line, false)
             .map(drop)
     }
+
+    fn exec_with_usage(
+        &self,
+        cmd: &ProcessBuilder,
+        _id: PackageId,
+        _target: &Target,
+        _mode: CompileMode,
+        on_stdout_line: &mut dyn FnMut(&str) -> CargoResult<()>,
+        on_stderr_line: &mut dyn FnMut(&str) -> CargoResult<()>,
+        usage: &mut Option<ResourceUsage>,
+    ) -> CargoResult<()> {
+        cmd.exec_with_streaming_and_usage(on_stdout_line, on_stderr_line, false, usage)
+            .map(drop)
+    }
 }
 
 fn compile<'cfg>(
@@ -157,7 +201,7 @@ fn compile<'cfg>(
     let p = profile::start(format!("preparing: {}/{}", unit.pkg, unit.target.name()));
     fingerprint::prepare_init(cx, unit)?;
 
-    let job = if unit.mode.is_run_custom_build() {
+    let mut job = if unit.mode.is_run_custom_build() {
         custom_build::prepare(cx, unit)?
     } else if unit.mode.is_doc_test() {
         // We run these targets later, so this is just a no-op for now.
@@ -185,6 +229,8 @@ fn compile<'cfg>(
                 cx.bcx.build_config.message_format,
                 cx.bcx.config.shell().err_supports_color(),
                 unit.show_warnings(bcx.config),
+                unused_deps::lints_unused_externs(cx, unit),
+                warning_handling(cx, unit),
             );
             // Need to link targets on both the dirty and fresh.
             work.then(link_targets(cx, unit, true)?)
@@ -192,6 +238,16 @@ fn compile<'cfg>(
 
         job
     };
+    if let MessageFormat::Json { v2: true, .. } = bcx.build_config.message_format {
+        if !build_plan && !unit.mode.is_doc_test() {
+            if let Some(msg) = artifact_dep_env_message(cx, unit)? {
+                job.before(Work::new(move |state| state.stdout(msg)));
+            }
+        }
+    }
+    if bcx.config.cli_unstable().fine_grained_locking && !build_plan && !unit.mode.is_doc_test() {
+        locking::UnitLocks::new(cx, unit).apply(&mut job);
+    }
     jobs.enqueue(cx, unit, job)?;
     drop(p);
 
@@ -207,6 +263,26 @@ fn compile<'cfg>(
     Ok(())
 }
 
+/// The `artifact-dep-env` message of `--message-format json-v2`, with the
+/// environment variables pointing at the artifact dependencies of `unit`.
+fn artifact_dep_env_message(cx: &Context<'_, '_>, unit: &Unit) -> CargoResult<Option<String>> {
+    let env = artifact::get_env(cx, cx.unit_deps(unit))?;
+    if env.is_empty() {
+        return Ok(None);
+    }
+    let msg = machine_message::ArtifactDepEnv {
+        package_id: unit.pkg.package_id(),
+        target: &unit.target,
+        mode: unit.mode,
+        env: env
+            .into_iter()
+            .map(|(var, value)| (var, value.to_string_lossy().into_owned()))
+            .collect(),
+    }
+    .to_json_string_for(cx.bcx.build_config.message_format);
+    Ok(Some(msg))
+}
+
 fn rustc(cx: &mut Context<'_, '_>, unit: &Unit, exec: &Arc<dyn Executor>) -> CargoResult<Work> {
     let mut rustc = prepare_rustc(cx, &unit.target.rustc_crate_types(), unit)?;
     let build_plan = cx.bcx.build_config.build_plan;
@@ -264,6 +340,7 @@ fn rustc(cx: &mut Context<'_, '_>, unit: &Unit, exec: &Arc<dyn Executor>) -> Car
     let script_metadata = cx.find_build_script_metadata(unit);
     let is_local = unit.is_local();
     let artifact = unit.artifact;
+    let env_overrides = cx.bcx.config.env_config_overrides()?;
 
     return Ok(Work::new(move |state| {
         // Artifacts are in a different location than typical units,
@@ -339,7 +416,8 @@ fn rustc(cx: &mut Context<'_, '_>, unit: &Unit, exec: &Arc<dyn Executor>) -> Car
         if build_plan {
             state.build_plan(buildkey, rustc.clone(), outputs.clone());
         } else {
-            exec.exec(
+            let mut usage = None;
+            exec.exec_with_usage(
                 &rustc,
                 package_id,
                 &target,
@@ -355,6 +433,7 @@ fn rustc(cx: &mut Context<'_, '_>, unit: &Unit, exec: &Arc<dyn Executor>) -> Car
                         &mut output_options,
                     )
                 },
+                &mut usage,
             )
             .map_err(verbose_if_simple_exit_code)
             .with_context(|| {
@@ -373,6 +452,10 @@ fn rustc(cx: &mut Context<'_, '_>, unit: &Unit, exec: &Arc<dyn Executor>) -> Car
             })?;
             // Exec should never return with success *and* generate an error.
             debug_assert_eq!(output_options.errors_seen, 0);
+            output_options.check_denied_warnings(&name)?;
+            if let Some(usage) = usage {
+                state.resource_usage(usage);
+            }
         }
 
         if rustc_dep_info_loc.exists() {
@@ -383,6 +466,7 @@ fn rustc(cx: &mut Context<'_, '_>, unit: &Unit, exec: &Arc<dyn Executor>) -> Car
                 &pkg_root,
                 &target_dir,
                 &rustc,
+                &env_overrides,
                 // Do not track source files in the fingerprint for registry dependencies.
                 is_local,
             )
@@ -449,14 +533,21 @@ fn rustc(cx: &mut Context<'_, '_>, unit: &Unit, exec: &Arc<dyn Executor>) -> Car
 fn link_targets(cx: &mut Context<'_, '_>, unit: &Unit, fresh: bool) -> CargoResult<Work> {
     let bcx = cx.bcx;
     let outputs = cx.outputs(unit)?;
-    let export_dir = cx.files().export_dir();
     let package_id = unit.pkg.package_id();
     let manifest_path = PathBuf::from(unit.pkg.manifest_path());
     let profile = unit.profile.clone();
     let unit_mode = unit.mode;
     let features = unit.features.iter().map(|s| s.to_string()).collect();
     let json_messages = bcx.build_config.emit_json();
+    let message_format = bcx.build_config.message_format;
     let executable = cx.get_executable(unit)?;
+    let uplift_lock = if bcx.config.cli_unstable().fine_grained_locking
+        && outputs.iter().any(|output| output.hardlink.is_some())
+    {
+        Some(locking::uplift_lock_file(cx, unit))
+    } else {
+        None
+    };
     let mut target = Target::clone(&unit.target);
     if let TargetSourcePath::Metabuild = target.src_path() {
         // Give it something to serialize.
@@ -469,6 +560,12 @@ fn link_targets(cx: &mut Context<'_, '_>, unit: &Unit, fresh: bool) -> CargoResu
         // hard link our outputs out of the `deps` directory into the directory
         // above. This means that `cargo build` will produce binaries in
         // `target/debug` which one probably expects.
+        let _lock = match &uplift_lock {
+            Some(path) => Some(locking::lock_uplift(path, &|| {
+                state.blocking("build directory")
+            })?),
+            None => None,
+        };
         let mut destinations = vec![];
         for output in outputs.iter() {
             let src = &output.path;
@@ -477,19 +574,15 @@ fn link_targets(cx: &mut Context<'_, '_>, unit: &Unit, fresh: bool) -> CargoResu
             if !src.exists() {
                 continue;
             }
-            let dst = match output.hardlink.as_ref() {
-                Some(dst) => dst,
-                None => {
-                    destinations.push(src.clone());
-                    continue;
+            match output.hardlink.as_ref() {
+                Some(dst) => {
+                    destinations.push(dst.clone());
+                    paths::link_or_copy(src, dst)?;
                 }
-            };
-            destinations.push(dst.clone());
-            paths::link_or_copy(src, dst)?;
+                None => destinations.push(src.clone()),
+            }
             if let Some(ref path) = output.export_path {
-                let export_dir = export_dir.as_ref().unwrap();
-                paths::create_dir_all(export_dir)?;
-
+                paths::create_dir_all(path.parent().unwrap())?;
                 paths::link_or_copy(src, path)?;
             }
         }
@@ -513,7 +606,7 @@ fn link_targets(cx: &mut Context<'_, '_>, unit: &Unit, fresh: bool) -> CargoResu
                 executable,
                 fresh,
             }
-            .to_json_string();
+            .to_json_string_for(message_format);
             state.stdout(msg)?;
         }
         Ok(())
@@ -640,6 +733,11 @@ fn rustdoc(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Work> {
     paths::create_dir_all(&doc_dir)?;
 
     rustdoc.arg("-o").arg(&doc_dir);
+    if let CompileMode::Doc { json: true, .. } = unit.mode {
+        rustdoc
+            .arg("-Zunstable-options")
+            .arg("--output-format=json");
+    }
     rustdoc.args(&features_args(unit));
     rustdoc.args(&check_cfg_args(cx, unit));
 
@@ -756,8 +854,9 @@ fn rustdoc(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Work> {
         }
         state.running(&rustdoc);
 
+        let mut usage = None;
         let result = rustdoc
-            .exec_with_streaming(
+            .exec_with_streaming_and_usage(
                 &mut |line| on_stdout_line(state, line, package_id, &target),
                 &mut |line| {
                     on_stderr_line(
@@ -770,8 +869,12 @@ fn rustdoc(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<Work> {
                     )
                 },
                 false,
+                &mut usage,
             )
             .with_context(|| format!("could not document `{}`", name));
+        if let Some(usage) = usage {
+            state.resource_usage(usage);
+        }
 
         if let Err(e) = result {
             if hide_diagnostics_for_scrape_unit {
@@ -788,7 +891,7 @@ failed to scan {target_desc} in package `{name}` for example code usage
             return Err(e);
         }
 
-        Ok(())
+        output_options.check_denied_warnings(&name)
     }))
 }
 
@@ -903,6 +1006,9 @@ fn build_base_args(
     add_path_args(bcx.ws, unit, cmd);
     add_error_format_and_color(cx, cmd);
     add_allow_features(cx, cmd);
+    if unused_deps::lints_unused_externs(cx, unit) {
+        cmd.arg("-W").arg("unused-crate-dependencies");
+    }
 
     let mut contains_dy_lib = false;
     if !test {
@@ -953,7 +1059,12 @@ fn build_base_args(
     }
 
     if let Some(backend) = codegen_backend {
-        cmd.arg("-Z").arg(&format!("codegen-backend={}", backend));
+        let mut arg = OsString::from("codegen-backend=");
+        match codegen_backend_path(bcx.ws, &backend) {
+            Some(path) => arg.push(path),
+            None => arg.push(backend.as_str()),
+        }
+        cmd.arg("-Z").arg(arg);
     }
 
     if let Some(n) = codegen_units {
@@ -1081,6 +1192,17 @@ fn build_base_args(
     Ok(())
 }
 
+/// The path of the codegen backend `backend` of a profile, if it's a path to
+/// the dynamic library of a backend rather than the name of a backend shipped
+/// with rustc. Relative paths are relative to the workspace root.
+pub(crate) fn codegen_backend_path(ws: &Workspace<'_>, backend: &str) -> Option<PathBuf> {
+    if backend.contains(|c| c == '/' || c == '\\') {
+        Some(ws.root().join(backend))
+    } else {
+        None
+    }
+}
+
 /// All active features for the unit passed as --cfg
 fn features_args(unit: &Unit) -> Vec<OsString> {
     let mut args = Vec::with_capacity(unit.features.len() * 2);
@@ -1095,10 +1217,20 @@ fn features_args(unit: &Unit) -> Vec<OsString> {
 
 /// Generate the --check-cfg arguments for the unit
 fn check_cfg_args(cx: &Context<'_, '_>, unit: &Unit) -> Vec<OsString> {
-    if let Some((features, well_known_names, well_known_values, _output)) =
-        cx.bcx.config.cli_unstable().check_cfg
-    {
-        let mut args = Vec::with_capacity(unit.pkg.summary().features().len() * 2 + 4);
+    let manifest_check_cfgs = unit.pkg.manifest().check_cfgs();
+    let check_cfg = match cx.bcx.config.cli_unstable().check_cfg {
+        Some((features, well_known_names, well_known_values, _output)) => {
+            Some((features, well_known_names, well_known_values))
+        }
+        // Declaring cfgs in `[package.check-cfg]` enables checking of cfg
+        // names, so the features have to be declared as well.
+        None if !manifest_check_cfgs.is_empty() => Some((true, false, false)),
+        None => None,
+    };
+    if let Some((features, well_known_names, well_known_values)) = check_cfg {
+        let mut args = Vec::with_capacity(
+            unit.pkg.summary().features().len() * 2 + manifest_check_cfgs.len() * 2 + 4,
+        );
         args.push(OsString::from("-Zunstable-options"));
 
         if features {
@@ -1127,6 +1259,11 @@ fn check_cfg_args(cx: &Context<'_, '_>, unit: &Unit) -> Vec<OsString> {
             args.push(OsString::from("values()"));
         }
 
+        for check_cfg in manifest_check_cfgs {
+            args.push(OsString::from("--check-cfg"));
+            args.push(OsString::from(check_cfg));
+        }
+
         args
     } else {
         Vec::new()
@@ -1352,6 +1489,11 @@ struct OutputOptions {
     /// cache will be filled with diagnostics from dependencies. When the
     /// cache is replayed without `-vv`, we don't want to show them.
     show_diagnostics: bool,
+    /// If `true`, `unused_crate_dependencies` warnings are collected for
+    /// `--warn-unused-dependencies` instead of being displayed.
+    collect_unused_externs: bool,
+    /// How warnings are handled, see [`OutputOptions::check_denied_warnings`].
+    warnings: WarningHandling,
     warnings_seen: usize,
     errors_seen: usize,
 }
@@ -1368,10 +1510,39 @@ impl OutputOptions {
             color,
             cache_cell,
             show_diagnostics: true,
+            collect_unused_externs: unused_deps::lints_unused_externs(cx, unit),
+            warnings: warning_handling(cx, unit),
             warnings_seen: 0,
             errors_seen: 0,
         }
     }
+
+    /// Fails the unit if it emitted warnings which are denied.
+    ///
+    /// This happens after rustc finished successfully, and after replaying
+    /// the output of fresh units, so that no rebuild is needed when switching
+    /// between `--warnings` settings.
+    fn check_denied_warnings(&self, name: &str) -> CargoResult<()> {
+        if self.warnings != WarningHandling::Deny || self.warnings_seen == 0 {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "could not compile `{}` because warnings are denied ({} warning{} emitted)",
+            name,
+            self.warnings_seen,
+            if self.warnings_seen == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// The configured handling of warnings applies to workspace members only,
+/// dependencies are handled as usual.
+fn warning_handling(cx: &Context<'_, '_>, unit: &Unit) -> WarningHandling {
+    if cx.bcx.ws.is_member(&unit.pkg) {
+        cx.bcx.build_config.warnings
+    } else {
+        WarningHandling::Warn
+    }
 }
 
 fn on_stdout_line(
@@ -1453,6 +1624,44 @@ fn on_stderr_line_inner(
         return Ok(true);
     }
 
+    if options.warnings == WarningHandling::Allow {
+        #[derive(serde::Deserialize)]
+        struct CompilerMessage {
+            level: String,
+        }
+        if let Ok(msg) = serde_json::from_str::<CompilerMessage>(compiler_message.get()) {
+            if msg.level == "warning" {
+                return Ok(true);
+            }
+        }
+    }
+
+    if options.collect_unused_externs {
+        #[derive(serde::Deserialize)]
+        struct LintCode {
+            code: String,
+        }
+        #[derive(serde::Deserialize)]
+        struct LintMessage {
+            message: String,
+            level: String,
+            code: Option<LintCode>,
+        }
+        if let Ok(msg) = serde_json::from_str::<LintMessage>(compiler_message.get()) {
+            // Denied lints are still shown, since they fail the build.
+            if msg.level == "warning"
+                && msg
+                    .code
+                    .map_or(false, |c| c.code == "unused_crate_dependencies")
+            {
+                if let Some(name) = unused_deps::unused_extern_name(&msg.message) {
+                    state.unused_extern(InternedString::new(name));
+                    return Ok(true);
+                }
+            }
+        }
+    }
+
     // Depending on what we're emitting from Cargo itself, we figure out what to
     // do with this JSON message.
     match options.format {
@@ -1625,7 +1834,7 @@ fn on_stderr_line_inner(
         target,
         message: compiler_message,
     }
-    .to_json_string();
+    .to_json_string_for(options.format);
 
     // Switch json lines from rustc/rustdoc that appear on stderr to stdout
     // instead. We want the stdout of Cargo to always be machine parseable as
@@ -1642,13 +1851,18 @@ fn replay_output_cache(
     format: MessageFormat,
     color: bool,
     show_diagnostics: bool,
+    collect_unused_externs: bool,
+    warnings: WarningHandling,
 ) -> Work {
     let target = target.clone();
+    let name = package_id.name();
     let mut options = OutputOptions {
         format,
         color,
         cache_cell: None,
         show_diagnostics,
+        collect_unused_externs,
+        warnings,
         warnings_seen: 0,
         errors_seen: 0,
     };
@@ -1679,6 +1893,6 @@ fn replay_output_cache(
             )?;
             line.clear();
         }
-        Ok(())
+        options.check_denied_warnings(&name)
     })
 }
@@ -27,7 +27,7 @@ use std::collections::{BTreeSet, HashSet};
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 
-use super::{fingerprint, Context, FileFlavor, Unit};
+use super::{fingerprint, locking, Context, FileFlavor, Unit};
 use crate::util::{internal, CargoResult};
 use cargo_util::paths;
 use log::debug;
@@ -131,8 +131,26 @@ pub fn output_depinfo(cx: &mut Context<'_, '_>, unit: &Unit) -> CargoResult<()>
         .map(|f| render_filename(f, basedir))
         .collect::<CargoResult<Vec<_>>>()?;
 
-    for output in cx
-        .outputs(unit)?
+    let outputs = cx.outputs(unit)?;
+    // The units are unlocked by now, but other builds may still uplift the
+    // same outputs (`-Z fine-grained-locking`).
+    let _lock = if bcx.config.cli_unstable().fine_grained_locking
+        && outputs.iter().any(|output| output.hardlink.is_some())
+    {
+        Some(locking::lock_uplift(
+            &locking::uplift_lock_file(cx, unit),
+            &|| {
+                let _ = bcx.config.shell().status_with_color(
+                    "Blocking",
+                    "waiting for file lock on build directory",
+                    termcolor::Color::Cyan,
+                );
+            },
+        )?)
+    } else {
+        None
+    };
+    for output in outputs
         .iter()
         .filter(|o| !matches!(o.flavor, FileFlavor::DebugInfo | FileFlavor::Auxiliary))
     {
@@ -0,0 +1,243 @@
+//! An [`Executor`] handing the rustc invocations of a build to an external
+//! program, for example the client of a distributed build service
+//! (`-Z build-executor`).
+//!
+//! The program is configured with `build.executor` and runs once for each
+//! rustc invocation. Cargo writes a [`RemoteRequest`] as a single line of
+//! JSON to its stdin and closes it. The program answers with one
+//! [`RemoteMessage`] per line on stdout:
+//!
+//! ```text
+//! {"reason":"stderr","line":"{\"$message_type\":\"artifact\",...}"}
+//! {"reason":"exit","code":0}
+//! ```
+//!
+//! The lines of the `stdout` and `stderr` messages are handled as if rustc
+//! printed them, so they should be forwarded verbatim, including the JSON
+//! messages rustc emits for diagnostics and artifacts. Before reporting its
+//! exit, the program places the files rustc produced in the `out_dir` of the
+//! request, inside the target directory, where Cargo picks them up like the
+//! output of a local rustc.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as _};
+use cargo_util::{ProcessBuilder, ProcessError};
+use serde::{Deserialize, Serialize};
+
+use crate::core::compiler::{CompileMode, Executor};
+use crate::core::{PackageId, Target};
+use crate::util::config::Config;
+use crate::util::errors::CargoResult;
+
+/// The version of the protocol, sent in every [`RemoteRequest`].
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A rustc invocation, as sent to the executor program.
+#[derive(Debug, Serialize)]
+pub struct RemoteRequest {
+    /// Always [`PROTOCOL_VERSION`].
+    pub version: u32,
+    pub package_id: PackageId,
+    /// The name of the target being compiled.
+    pub target: String,
+    pub mode: CompileMode,
+    /// The program to run, usually `rustc` or a rustc wrapper.
+    pub program: String,
+    pub args: Vec<String>,
+    /// The environment variables Cargo sets for the invocation, including the
+    /// paths of artifact dependencies. `None` means the variable is removed.
+    /// The invocation doesn't inherit any other variable of the environment
+    /// of Cargo.
+    pub env: BTreeMap<String, Option<String>>,
+    pub cwd: Option<PathBuf>,
+    /// The files the invocation reads beyond the sources of the package: the
+    /// crate root, the dependencies passed with `--extern` and the artifacts
+    /// of artifact dependencies.
+    pub inputs: Vec<PathBuf>,
+    /// The directory the files produced by rustc are expected in.
+    pub out_dir: Option<PathBuf>,
+}
+
+/// A line printed by the executor program.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum RemoteMessage {
+    /// A line rustc printed to stdout.
+    Stdout { line: String },
+    /// A line rustc printed to stderr.
+    Stderr { line: String },
+    /// rustc exited with the given code.
+    Exit { code: i32 },
+}
+
+/// Runs the rustc invocations of a build with the program configured in
+/// `build.executor`.
+pub struct RemoteExecutor {
+    executor: ProcessBuilder,
+}
+
+impl RemoteExecutor {
+    /// Returns the executor of the `build.executor` config, if any.
+    pub fn from_config(config: &Config) -> CargoResult<Option<RemoteExecutor>> {
+        let executor = match &config.build_config()?.executor {
+            Some(executor) => executor,
+            None => return Ok(None),
+        };
+        if !config.cli_unstable().build_executor {
+            bail!(
+                "the `build.executor` config requires `-Z build-executor`\n\
+                 See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#build-executor \
+                 for more information about the `build.executor` config."
+            );
+        }
+        let mut process = ProcessBuilder::new(executor.path.resolve_program(config));
+        process.args(&executor.args);
+        Ok(Some(RemoteExecutor { executor: process }))
+    }
+
+    fn request(
+        &self,
+        cmd: &ProcessBuilder,
+        id: PackageId,
+        target: &Target,
+        mode: CompileMode,
+    ) -> RemoteRequest {
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let env: BTreeMap<_, _> = cmd
+            .get_envs()
+            .iter()
+            .map(|(key, value)| {
+                let value = value.as_ref().map(|v| v.to_string_lossy().into_owned());
+                (key.clone(), value)
+            })
+            .collect();
+
+        let mut inputs = Vec::new();
+        if let Some(src_path) = target.src_path().path() {
+            inputs.push(src_path.to_path_buf());
+        }
+        let mut out_dir = None;
+        let mut args_iter = args.iter();
+        while let Some(arg) = args_iter.next() {
+            match arg.as_str() {
+                "--extern" => {
+                    let dep = args_iter.next().and_then(|dep| dep.split_once('='));
+                    if let Some((_, path)) = dep {
+                        inputs.push(PathBuf::from(path));
+                    }
+                }
+                "--out-dir" => out_dir = args_iter.next().map(PathBuf::from),
+                _ => {}
+            }
+        }
+        for (key, value) in &env {
+            if is_artifact_file_var(key) {
+                if let Some(path) = value {
+                    inputs.push(PathBuf::from(path));
+                }
+            }
+        }
+
+        RemoteRequest {
+            version: PROTOCOL_VERSION,
+            package_id: id,
+            target: target.name().to_string(),
+            mode,
+            program: cmd.get_program().to_string_lossy().into_owned(),
+            args,
+            env,
+            cwd: cmd.get_cwd().map(Path::to_path_buf),
+            inputs,
+            out_dir,
+        }
+    }
+}
+
+/// Whether `key` is one of the `CARGO_<KIND>_FILE_<DEP>` variables pointing
+/// to an artifact, see [`super::artifact::get_env`].
+fn is_artifact_file_var(key: &str) -> bool {
+    [
+        "CARGO_BIN_FILE_",
+        "CARGO_CDYLIB_FILE_",
+        "CARGO_STATICLIB_FILE_",
+    ]
+    .iter()
+    .any(|prefix| key.starts_with(prefix))
+}
+
+impl Executor for RemoteExecutor {
+    fn exec(
+        &self,
+        cmd: &ProcessBuilder,
+        id: PackageId,
+        target: &Target,
+        mode: CompileMode,
+        on_stdout_line: &mut dyn FnMut(&str) -> CargoResult<()>,
+        on_stderr_line: &mut dyn FnMut(&str) -> CargoResult<()>,
+    ) -> CargoResult<()> {
+        let request = serde_json::to_string(&self.request(cmd, id, target, mode))?;
+        let mut executor = self.executor.clone();
+        executor.stdin(format!("{}\n", request).into_bytes());
+
+        // Lines of rustc and the executor's own diagnostics both go to stderr.
+        let on_stderr_line = RefCell::new(on_stderr_line);
+        let mut exit_code = None;
+        let mut invalid_message = None;
+        let result = executor.exec_with_streaming(
+            &mut |line| {
+                if invalid_message.is_some() {
+                    return Ok(());
+                }
+                let message = match serde_json::from_str::<RemoteMessage>(line) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        invalid_message = Some(anyhow::Error::new(e).context(format!(
+                            "invalid message from the build executor: `{}`",
+                            line
+                        )));
+                        return Ok(());
+                    }
+                };
+                match message {
+                    RemoteMessage::Stdout { line } => on_stdout_line(&line),
+                    RemoteMessage::Stderr { line } => (on_stderr_line.borrow_mut())(&line),
+                    RemoteMessage::Exit { code } => {
+                        exit_code = Some(code);
+                        Ok(())
+                    }
+                }
+            },
+            &mut |line| (on_stderr_line.borrow_mut())(line),
+            false,
+        );
+        // An invalid message explains a failure of the executor better than
+        // its exit status.
+        if let Some(e) = invalid_message {
+            return Err(e.context(format!("build executor failed to run {}", cmd)));
+        }
+        result.with_context(|| format!("build executor failed to run {}", cmd))?;
+
+        match exit_code {
+            Some(0) => Ok(()),
+            Some(code) => Err(ProcessError::new_raw(
+                &format!("process didn't exit successfully: {}", cmd),
+                Some(code),
+                &format!("exit status: {}", code),
+                None,
+                None,
+            )
+            .into()),
+            None => bail!(
+                "build executor {} exited without reporting the exit status of {}",
+                self.executor,
+                cmd
+            ),
+        }
+    }
+}
@@ -0,0 +1,667 @@
+//! Size reports of the binaries of a build (`--timings=size`).
+//!
+//! After a build, the binaries, cdylibs and dylibs of the root units are
+//! analyzed:
+//!
+//! * The sizes of their sections are read from the section headers of ELF,
+//!   Mach-O and PE files.
+//! * The contribution of each crate is computed from the symbol table of ELF
+//!   files, by attributing the size of each function and object to the crate
+//!   its mangled name starts with. Symbols which aren't Rust symbols, like
+//!   the ones of C libraries, are attributed to [`OTHER_CRATE`]. Stripped
+//!   binaries have no symbols to attribute.
+//!
+//! The report is saved as `cargo-timings/cargo-size.json` in the target
+//! directory, along with the previous sizes of each artifact, which are the
+//! sizes it had before the last build which changed it. `cargo report size`
+//! prints it.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use cargo_util::paths;
+use serde::{Deserialize, Serialize};
+
+use super::{CompileMode, Context, FileFlavor};
+use crate::core::PackageId;
+use crate::util::{human_readable_bytes, CargoResult, Config};
+
+/// The name of the report in the `cargo-timings` directory.
+pub const REPORT_FILE: &str = "cargo-size.json";
+
+/// The crate of the symbols which aren't Rust symbols.
+pub const OTHER_CRATE: &str = "[other]";
+
+/// The version of the format of the report.
+const VERSION: u32 = 1;
+
+/// The number of sections and crates displayed for each artifact, the
+/// others are summed up.
+const DISPLAYED_ENTRIES: usize = 10;
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct SizeReport {
+    version: u32,
+    artifacts: Vec<ArtifactSize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArtifactSize {
+    path: PathBuf,
+    package_id: PackageId,
+    /// The description of the target, like `bin "foo"`.
+    target: String,
+    current: Sizes,
+    /// The sizes before the last build which changed them.
+    previous: Option<Sizes>,
+    /// Whether the artifact was analyzed after the current build.
+    #[serde(skip)]
+    recorded: bool,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct Sizes {
+    file_size: u64,
+    /// The sizes of the sections, by name, empty if the format of the file
+    /// is unknown.
+    sections: BTreeMap<String, u64>,
+    /// The sizes of the symbols of each crate, empty if the file has no
+    /// symbol table.
+    crates: BTreeMap<String, u64>,
+}
+
+impl SizeReport {
+    /// Loads the report saved in the `cargo-timings` directory `dir`, if any.
+    pub fn load(dir: &Path) -> CargoResult<Option<SizeReport>> {
+        let path = dir.join(REPORT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let report: SizeReport = serde_json::from_str(&paths::read(&path)?)
+            .with_context(|| format!("failed to parse size report `{}`", path.display()))?;
+        if report.version != VERSION {
+            anyhow::bail!(
+                "size report `{}` has unsupported version {}, build again to replace it",
+                path.display(),
+                report.version
+            );
+        }
+        Ok(Some(report))
+    }
+
+    /// Analyzes the binaries of the root units of the build, and saves the
+    /// report in the `cargo-timings` directory `dir`.
+    pub fn record(cx: &Context<'_, '_>, dir: &Path) -> CargoResult<SizeReport> {
+        let mut previous: HashMap<PathBuf, ArtifactSize> = SizeReport::load(dir)
+            .unwrap_or_default()
+            .map(|report| report.artifacts)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|artifact| (artifact.path.clone(), artifact))
+            .collect();
+        let mut artifacts = Vec::new();
+        for unit in &cx.bcx.roots {
+            if unit.mode != CompileMode::Build
+                || !(unit.target.is_executable()
+                    || unit.target.is_cdylib()
+                    || unit.target.is_dylib())
+            {
+                continue;
+            }
+            for output in cx.outputs(unit)?.iter() {
+                if !matches!(output.flavor, FileFlavor::Normal | FileFlavor::Linkable) {
+                    continue;
+                }
+                let path = output.bin_dst();
+                let ext = path.extension().and_then(|ext| ext.to_str());
+                if matches!(ext, Some("rlib" | "rmeta" | "a" | "lib")) || !path.exists() {
+                    continue;
+                }
+                let current = analyze(path)
+                    .with_context(|| format!("failed to analyze `{}`", path.display()))?;
+                let previous = match previous.remove(path) {
+                    Some(old) if old.current == current => old.previous,
+                    Some(old) => Some(old.current),
+                    None => None,
+                };
+                artifacts.push(ArtifactSize {
+                    path: path.clone(),
+                    package_id: unit.pkg.package_id(),
+                    target: unit.target.description_named(),
+                    current,
+                    previous,
+                    recorded: true,
+                });
+            }
+        }
+        // Artifacts which weren't built this time are kept.
+        artifacts.extend(previous.into_values());
+        artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+        let report = SizeReport {
+            version: VERSION,
+            artifacts,
+        };
+        paths::create_dir_all(dir)?;
+        paths::write_atomic(
+            dir.join(REPORT_FILE),
+            serde_json::to_string_pretty(&report)?,
+        )?;
+        Ok(report)
+    }
+
+    /// Prints a status line with the size of each artifact analyzed after
+    /// the current build.
+    pub fn print_summary(&self, config: &Config) -> CargoResult<()> {
+        for artifact in self.artifacts.iter().filter(|a| a.recorded) {
+            let previous = artifact.previous.as_ref().map(|p| p.file_size);
+            config.shell().status_with_color(
+                "Size",
+                format!(
+                    "{} ({}) {}{}",
+                    artifact.package_id.name(),
+                    artifact.target,
+                    format_bytes(artifact.current.file_size),
+                    format_diff(artifact.current.file_size, previous)
+                ),
+                termcolor::Color::Cyan,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Keeps only the artifacts of the packages which `keep` returns `true`
+    /// for.
+    pub fn retain_packages(&mut self, keep: impl Fn(PackageId) -> bool) {
+        self.artifacts.retain(|artifact| keep(artifact.package_id));
+    }
+
+    /// Renders the breakdown of the sizes, with their differences to the
+    /// previous sizes.
+    pub fn render_human(&self) -> String {
+        let mut out = String::new();
+        for (i, artifact) in self.artifacts.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            let previous = artifact.previous.as_ref();
+            let _ = writeln!(
+                out,
+                "{} v{} ({}): {}",
+                artifact.package_id.name(),
+                artifact.package_id.version(),
+                artifact.target,
+                artifact.path.display()
+            );
+            let _ = writeln!(
+                out,
+                "  {:<32} {:>10}{}",
+                "total",
+                format_bytes(artifact.current.file_size),
+                format_diff(artifact.current.file_size, previous.map(|p| p.file_size))
+            );
+            render_sizes(
+                &mut out,
+                "sections",
+                &artifact.current.sections,
+                previous.map(|p| &p.sections),
+            );
+            render_sizes(
+                &mut out,
+                "crates",
+                &artifact.current.crates,
+                previous.map(|p| &p.crates),
+            );
+        }
+        out
+    }
+
+    /// Renders an HTML table of the sizes of the artifacts analyzed after the
+    /// current build, for the timing report.
+    pub fn render_html(&self) -> String {
+        let mut out = String::from(
+            "\n<h2>Binary sizes</h2>\n\
+             <table class=\"my-table\">\n  <thead>\n    <tr>\n      \
+             <th>Artifact</th>\n      <th>Size</th>\n      <th>Change</th>\n      \
+             <th>Largest crates</th>\n    </tr>\n  </thead>\n  <tbody>\n",
+        );
+        for artifact in self.artifacts.iter().filter(|a| a.recorded) {
+            let mut crates: Vec<_> = artifact.current.crates.iter().collect();
+            crates.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            let crates: Vec<_> = crates
+                .iter()
+                .take(3)
+                .map(|(name, size)| format!("{} ({})", name, format_bytes(**size)))
+                .collect();
+            let previous = artifact.previous.as_ref().map(|p| p.file_size);
+            let _ = writeln!(
+                out,
+                "<tr>\n  <td>{} v{} ({})</td>\n  <td>{}</td>\n  <td>{}</td>\n  <td>{}</td>\n</tr>",
+                artifact.package_id.name(),
+                artifact.package_id.version(),
+                artifact.target.replace('"', "&quot;"),
+                format_bytes(artifact.current.file_size),
+                format_diff(artifact.current.file_size, previous).trim(),
+                crates.join(", "),
+            );
+        }
+        out.push_str("</tbody>\n</table>\n");
+        out
+    }
+}
+
+fn render_sizes(
+    out: &mut String,
+    title: &str,
+    sizes: &BTreeMap<String, u64>,
+    previous: Option<&BTreeMap<String, u64>>,
+) {
+    if sizes.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "  {}:", title);
+    let mut entries: Vec<_> = sizes.iter().collect();
+    entries.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    for (name, size) in entries.iter().take(DISPLAYED_ENTRIES) {
+        let diff = match previous {
+            Some(previous) => match previous.get(*name) {
+                Some(old) => format_diff(**size, Some(*old)),
+                None => " (new)".to_string(),
+            },
+            None => String::new(),
+        };
+        let _ = writeln!(out, "    {:<30} {:>10}{}", name, format_bytes(**size), diff);
+    }
+    if entries.len() > DISPLAYED_ENTRIES {
+        let others = &entries[DISPLAYED_ENTRIES..];
+        let size = others.iter().map(|(_, size)| **size).sum();
+        let _ = writeln!(
+            out,
+            "    {:<30} {:>10}",
+            format!("{} others", others.len()),
+            format_bytes(size)
+        );
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let (size, unit) = human_readable_bytes(bytes);
+    format!("{:.1} {}", size, unit)
+}
+
+fn format_diff(current: u64, previous: Option<u64>) -> String {
+    match previous {
+        Some(previous) if previous < current => {
+            format!(" (+{})", format_bytes(current - previous))
+        }
+        Some(previous) if previous > current => {
+            format!(" (-{})", format_bytes(previous - current))
+        }
+        Some(_) => " (unchanged)".to_string(),
+        None => String::new(),
+    }
+}
+
+/// Reads the sizes of the sections and crates of the file at `path`.
+fn analyze(path: &Path) -> CargoResult<Sizes> {
+    let data = paths::read_bytes(path)?;
+    let mut sizes = Sizes {
+        file_size: data.len() as u64,
+        sections: BTreeMap::new(),
+        crates: BTreeMap::new(),
+    };
+    let parsed = if data.starts_with(b"\x7fELF") {
+        elf(&data, &mut sizes)
+    } else if data.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]) {
+        macho(&data, &mut sizes)
+    } else if data.starts_with(b"MZ") {
+        pe(&data, &mut sizes)
+    } else {
+        Some(())
+    };
+    if parsed.is_none() {
+        anyhow::bail!("the file is truncated or malformed");
+    }
+    Ok(sizes)
+}
+
+/// Reads integers of either endianness out of a file, `None` if they are
+/// out of bounds.
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn bytes(&self, offset: u64, len: usize) -> Option<&[u8]> {
+        let offset: usize = offset.try_into().ok()?;
+        self.data.get(offset..offset.checked_add(len)?)
+    }
+
+    fn u16(&self, offset: u64) -> Option<u64> {
+        let b: [u8; 2] = self.bytes(offset, 2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        } as u64)
+    }
+
+    fn u32(&self, offset: u64) -> Option<u64> {
+        let b: [u8; 4] = self.bytes(offset, 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        } as u64)
+    }
+
+    fn u64(&self, offset: u64) -> Option<u64> {
+        let b: [u8; 8] = self.bytes(offset, 8)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u64::from_be_bytes(b)
+        } else {
+            u64::from_le_bytes(b)
+        })
+    }
+
+    /// Reads a NUL-terminated string, or one filling `max_len` bytes.
+    fn str(&self, offset: u64, max_len: usize) -> Option<&str> {
+        let offset: usize = offset.try_into().ok()?;
+        let rest = self.data.get(offset..)?;
+        let rest = &rest[..rest.len().min(max_len)];
+        let end = rest.iter().position(|b| *b == 0).unwrap_or(rest.len());
+        std::str::from_utf8(&rest[..end]).ok()
+    }
+}
+
+const SHT_SYMTAB: u64 = 2;
+const SHT_NOBITS: u64 = 8;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+fn elf(data: &[u8], sizes: &mut Sizes) -> Option<()> {
+    let is_64 = *data.get(4)? == 2;
+    let r = Reader {
+        data,
+        big_endian: *data.get(5)? == 2,
+    };
+    let (shoff, shentsize, shnum, shstrndx) = if is_64 {
+        (r.u64(0x28)?, r.u16(0x3a)?, r.u16(0x3c)?, r.u16(0x3e)?)
+    } else {
+        (r.u32(0x20)?, r.u16(0x2e)?, r.u16(0x30)?, r.u16(0x32)?)
+    };
+    struct Section {
+        name: u64,
+        kind: u64,
+        offset: u64,
+        size: u64,
+        link: u64,
+        entsize: u64,
+    }
+    let sections = (0..shnum)
+        .map(|i| {
+            let sh = shoff.checked_add(i.checked_mul(shentsize)?)?;
+            Some(if is_64 {
+                Section {
+                    name: r.u32(sh)?,
+                    kind: r.u32(sh + 4)?,
+                    offset: r.u64(sh + 24)?,
+                    size: r.u64(sh + 32)?,
+                    link: r.u32(sh + 40)?,
+                    entsize: r.u64(sh + 56)?,
+                }
+            } else {
+                Section {
+                    name: r.u32(sh)?,
+                    kind: r.u32(sh + 4)?,
+                    offset: r.u32(sh + 16)?,
+                    size: r.u32(sh + 20)?,
+                    link: r.u32(sh + 24)?,
+                    entsize: r.u32(sh + 36)?,
+                }
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let names = sections.get(shstrndx as usize)?;
+    for section in &sections {
+        let name = r.str(names.offset.checked_add(section.name)?, usize::MAX)?;
+        if !name.is_empty() && section.size > 0 {
+            add_size(&mut sizes.sections, name, section.size)?;
+        }
+    }
+
+    let symtab = match sections.iter().find(|s| s.kind == SHT_SYMTAB) {
+        Some(symtab) if symtab.entsize > 0 => symtab,
+        _ => return Some(()),
+    };
+    let strtab = sections.get(symtab.link as usize)?;
+    if strtab.kind == SHT_NOBITS {
+        return Some(());
+    }
+    // Aliases of the same symbol are only counted once.
+    let mut seen = std::collections::HashSet::new();
+    for i in 0..symtab.size / symtab.entsize {
+        let sym = symtab.offset.checked_add(i.checked_mul(symtab.entsize)?)?;
+        let (name, info, shndx, value, size) = if is_64 {
+            (
+                r.u32(sym)?,
+                *r.bytes(sym + 4, 1)?.first()?,
+                r.u16(sym + 6)?,
+                r.u64(sym + 8)?,
+                r.u64(sym + 16)?,
+            )
+        } else {
+            (
+                r.u32(sym)?,
+                *r.bytes(sym + 12, 1)?.first()?,
+                r.u16(sym + 14)?,
+                r.u32(sym + 4)?,
+                r.u32(sym + 8)?,
+            )
+        };
+        if !matches!(info & 0xf, STT_FUNC | STT_OBJECT) || size == 0 || shndx == 0 {
+            continue;
+        }
+        if !seen.insert((shndx, value)) {
+            continue;
+        }
+        let name = r.str(strtab.offset.checked_add(name)?, usize::MAX)?;
+        let krate = symbol_crate(name).unwrap_or(OTHER_CRATE);
+        add_size(&mut sizes.crates, krate, size)?;
+    }
+    Some(())
+}
+
+const LC_SEGMENT_64: u64 = 0x19;
+
+fn macho(data: &[u8], sizes: &mut Sizes) -> Option<()> {
+    let r = Reader {
+        data,
+        big_endian: false,
+    };
+    let ncmds = r.u32(16)?;
+    let mut cmd = 32;
+    for _ in 0..ncmds {
+        let (kind, cmdsize) = (r.u32(cmd)?, r.u32(cmd + 4)?);
+        if kind == LC_SEGMENT_64 {
+            for i in 0..r.u32(cmd + 64)? {
+                let sect = cmd.checked_add(72 + i * 80)?;
+                let name = format!("{},{}", r.str(sect + 16, 16)?, r.str(sect, 16)?);
+                let size = r.u64(sect + 40)?;
+                if size > 0 {
+                    add_size(&mut sizes.sections, &name, size)?;
+                }
+            }
+        }
+        if cmdsize == 0 {
+            return None;
+        }
+        cmd = cmd.checked_add(cmdsize)?;
+    }
+    Some(())
+}
+
+fn pe(data: &[u8], sizes: &mut Sizes) -> Option<()> {
+    let r = Reader {
+        data,
+        big_endian: false,
+    };
+    let pe = r.u32(0x3c)?;
+    if r.bytes(pe, 4)? != b"PE\0\0" {
+        return Some(());
+    }
+    let coff = pe.checked_add(4)?;
+    let nsections = r.u16(coff + 2)?;
+    let optional_size = r.u16(coff + 16)?;
+    let table = coff.checked_add(20 + optional_size)?;
+    for i in 0..nsections {
+        let section = table.checked_add(i * 40)?;
+        let name = r.str(section, 8)?;
+        // The size in memory, the size in the file is rounded up.
+        let size = r.u32(section + 8)?;
+        if size > 0 {
+            add_size(&mut sizes.sections, name, size)?;
+        }
+    }
+    Some(())
+}
+
+/// Adds `size` to the total of `name`, `None` if it overflows.
+fn add_size(totals: &mut BTreeMap<String, u64>, name: &str, size: u64) -> Option<()> {
+    let total = totals.entry(name.to_string()).or_default();
+    *total = total.checked_add(size)?;
+    Some(())
+}
+
+/// The crate a mangled Rust symbol belongs to, `None` if it isn't a Rust
+/// symbol.
+///
+/// For legacy symbols like `_ZN4core3fmt5write17h0123456789abcdefE` this is
+/// the first path segment. Methods of trait implementations, like
+/// `_ZN64_$LT$alloc..vec..Vec$LT$T$GT$$u20$as$u20$core..ops..drop..Drop$GT$4drop17h..E`,
+/// are attributed to the crate of the implementing type. For v0 symbols like
+/// `_RNvCs1234_7mycrate4main` this is the first crate root.
+fn symbol_crate(symbol: &str) -> Option<&str> {
+    // macOS prefixes symbols with another underscore.
+    let symbol = symbol
+        .strip_prefix("__")
+        .or_else(|| symbol.strip_prefix('_'))?;
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if let Some(rest) = symbol.strip_prefix("ZN") {
+        let len_end = rest.find(|c: char| !c.is_ascii_digit())?;
+        let len: usize = rest[..len_end].parse().ok()?;
+        let ident = rest.get(len_end..len_end.checked_add(len)?)?;
+        let ident = match ident
+            .strip_prefix("_$LT$")
+            .or_else(|| ident.strip_prefix("$LT$"))
+        {
+            // Skip references and pointers of the implementing type.
+            Some(ty) => ty.trim_start_matches("$RF$").trim_start_matches("$BP$"),
+            None => ident,
+        };
+        let end = ident.find(|c| !is_ident(c)).unwrap_or(ident.len());
+        // Blanket implementations for type parameters and implementations
+        // for primitive types, like `<&T as core::fmt::Debug>`, are
+        // attributed to the crate of the trait.
+        let krate = match ident[end..].strip_prefix("$u20$as$u20$") {
+            Some(trait_path) => {
+                let end = trait_path
+                    .find(|c| !is_ident(c))
+                    .unwrap_or(trait_path.len());
+                &trait_path[..end]
+            }
+            None => &ident[..end],
+        };
+        return if krate.is_empty() { None } else { Some(krate) };
+    }
+    let rest = symbol.strip_prefix('R')?;
+    let bytes = rest.as_bytes();
+    for (i, b) in bytes.iter().enumerate() {
+        if *b != b'C' {
+            continue;
+        }
+        let mut rest = &rest[i + 1..];
+        if let Some(disambiguated) = rest.strip_prefix('s') {
+            let end = disambiguated.find('_')?;
+            rest = &disambiguated[end + 1..];
+        }
+        let len_end = match rest.find(|c: char| !c.is_ascii_digit()) {
+            Some(len_end) if len_end > 0 => len_end,
+            _ => continue,
+        };
+        let len: usize = rest[..len_end].parse().ok()?;
+        // Identifiers starting with a digit or `_` are separated from their
+        // length with another `_`.
+        let start = len_end + rest[len_end..].starts_with('_') as usize;
+        if let Some(krate) = rest.get(start..start.checked_add(len)?) {
+            if krate.chars().all(is_ident) {
+                return Some(krate);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{elf, symbol_crate, Sizes};
+
+    #[test]
+    fn symbol_crates() {
+        let cases = [
+            ("_ZN4core3fmt5write17h0123456789abcdefE", Some("core")),
+            ("__ZN3std2io5stdio6_print17h0123456789abcdefE", Some("std")),
+            (
+                "_ZN64_$LT$alloc..vec..Vec$LT$T$GT$$u20$as$u20$core..ops..drop..Drop$GT$4drop17h0123456789abcdefE",
+                Some("alloc"),
+            ),
+            (
+                "_ZN42_$LT$$RF$T$u20$as$u20$core..fmt..Debug$GT$3fmt17h0123456789abcdefE",
+                Some("core"),
+            ),
+            ("_RNvCs1234_7mycrate4main", Some("mycrate")),
+            ("_RNvNtCsdtmpeKXhVnJ_4core3fmt5write", Some("core")),
+            ("_RNvC7mycrate4main", Some("mycrate")),
+            ("_RNvCsfLfy6EI15iL_7___rustc12___rust_alloc", Some("__rustc")),
+            (
+                "_RINvNtNtCsjrHSEGnQ3l9_3std3sys9backtrace26___rust_end_short_backtraceNCNvNtB6_5alloc8rust_oom0zEB6_",
+                Some("std"),
+            ),
+            ("main", None),
+            ("__libc_start_main", None),
+            ("RC4_encrypt", None),
+            // Lengths which overflow when added to their offset.
+            ("_ZN18446744073709551615core", None),
+            ("_RNvC18446744073709551615mycrate", None),
+        ];
+        for (symbol, krate) in cases {
+            assert_eq!(symbol_crate(symbol), krate, "{}", symbol);
+        }
+    }
+
+    #[test]
+    fn elf_offsets_overflowing() {
+        // A 64-bit little-endian file with one section, whose name would be
+        // past the end of the address space.
+        let mut data = vec![0; 128];
+        data[4] = 2;
+        data[5] = 1;
+        data[0x28..0x30].copy_from_slice(&64u64.to_le_bytes());
+        data[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3c..0x3e].copy_from_slice(&1u16.to_le_bytes());
+        data[64..68].copy_from_slice(&16u32.to_le_bytes());
+        data[88..96].copy_from_slice(&u64::MAX.to_le_bytes());
+        let mut sizes = Sizes {
+            file_size: 128,
+            sections: Default::default(),
+            crates: Default::default(),
+        };
+        assert_eq!(elf(&data, &mut sizes), None);
+    }
+}
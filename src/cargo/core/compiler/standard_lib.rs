@@ -10,7 +10,7 @@ use crate::core::{Dependency, PackageId, PackageSet, Resolve, SourceId, Workspac
 use crate::ops::{self, Packages};
 use crate::util::errors::CargoResult;
 use crate::Config;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::path::PathBuf;
 
@@ -34,11 +34,66 @@ pub fn parse_unstable_flag(value: Option<&str>) -> Vec<String> {
     crates.into_iter().map(|s| s.to_string()).collect()
 }
 
-pub(crate) fn std_crates(config: &Config, units: Option<&[Unit]>) -> Option<Vec<String>> {
-    let crates = config.cli_unstable().build_std.as_ref()?.clone();
+/// The standard library crates requested with `-Zbuild-std` or the `build.std`
+/// config, or `None` if the standard library isn't built.
+pub fn requested_std_crates(config: &Config) -> CargoResult<Option<Vec<String>>> {
+    if let Some(crates) = &config.cli_unstable().build_std {
+        return Ok(Some(crates.clone()));
+    }
+    let std = match &config.build_config()?.std {
+        Some(std) => std,
+        None => return Ok(None),
+    };
+    if !config.nightly_features_allowed {
+        anyhow::bail!(
+            "the `build.std` config requires the nightly channel of Cargo\n\
+             See https://doc.rust-lang.org/cargo/reference/unstable.html#build-std \
+             for more information about building the standard library."
+        );
+    }
+    let crates = std.crates.as_ref().map(|crates| crates.join(","));
+    Ok(Some(parse_unstable_flag(crates.as_deref())))
+}
+
+/// The features to build the standard library with for `kind`.
+///
+/// `-Zbuild-std-features` takes precedence over the `std-features` of the
+/// `[target]` table, which takes precedence over `build.std.features`.
+fn std_features(
+    config: &Config,
+    target_data: &RustcTargetData<'_>,
+    kind: CompileKind,
+) -> CargoResult<Vec<String>> {
+    if let Some(list) = &config.cli_unstable().build_std_features {
+        return Ok(list.clone());
+    }
+    if let Some(list) = &target_data.target_config(kind).std_features {
+        return Ok(list.val.as_slice().to_vec());
+    }
+    if let Some(list) = config
+        .build_config()?
+        .std
+        .as_ref()
+        .and_then(|std| std.features.as_ref())
+    {
+        return Ok(list.clone());
+    }
+    Ok(vec![
+        "panic-unwind".to_string(),
+        "backtrace".to_string(),
+        "default".to_string(),
+    ])
+}
 
+pub(crate) fn std_crates(
+    config: &Config,
+    units: Option<&[Unit]>,
+) -> CargoResult<Option<Vec<String>>> {
     // Only build libtest if it looks like it is needed.
-    let mut crates = crates.clone();
+    let mut crates = match requested_std_crates(config)? {
+        Some(crates) => crates,
+        None => return Ok(None),
+    };
     // If we know what units we're building, we can filter for libtest depending on the jobs.
     if let Some(units) = units {
         if units
@@ -57,16 +112,54 @@ pub(crate) fn std_crates(config: &Config, units: Option<&[Unit]>) -> Option<Vec<
         }
     }
 
-    Some(crates)
+    Ok(Some(crates))
+}
+
+/// The kinds the standard library is built for.
+///
+/// These are the targets of all units, including the ones of artifact
+/// dependencies and of `forced-target` packages, not just the requested ones.
+pub fn std_kinds(target_data: &RustcTargetData<'_>) -> Vec<CompileKind> {
+    let mut kinds: Vec<_> = target_data
+        .all_kinds()
+        .filter(|kind| !kind.is_host())
+        .collect();
+    kinds.sort();
+    kinds
+}
+
+/// The resolves of the standard library for the kinds it is built for.
+///
+/// Kinds with the same standard library features share a resolve.
+#[derive(Default)]
+pub struct StdResolve {
+    resolves: Vec<(Resolve, ResolvedFeatures)>,
+    by_kind: HashMap<CompileKind, usize>,
 }
 
-/// Resolve the standard library dependencies.
+impl StdResolve {
+    /// The resolve and the features of the standard library for `kind`.
+    pub fn get(&self, kind: CompileKind) -> Option<(&Resolve, &ResolvedFeatures)> {
+        let (resolve, features) = &self.resolves[*self.by_kind.get(&kind)?];
+        Some((resolve, features))
+    }
+
+    /// The kinds the standard library is resolved for.
+    pub fn kinds(&self) -> Vec<CompileKind> {
+        let mut kinds: Vec<_> = self.by_kind.keys().copied().collect();
+        kinds.sort();
+        kinds
+    }
+}
+
+/// Resolve the standard library dependencies for each of `kinds`.
 pub fn resolve_std<'cfg>(
     ws: &Workspace<'cfg>,
     target_data: &RustcTargetData<'cfg>,
     build_config: &BuildConfig,
     crates: &[String],
-) -> CargoResult<(PackageSet<'cfg>, Resolve, ResolvedFeatures)> {
+    kinds: &[CompileKind],
+) -> CargoResult<(PackageSet<'cfg>, StdResolve)> {
     if build_config.build_plan {
         ws.config()
             .shell()
@@ -110,6 +203,7 @@ pub fn resolve_std<'cfg>(
         /*profiles*/ None,
         crate::core::Features::default(),
         None,
+        /*links_overrides*/ Default::default(),
     );
 
     let config = ws.config();
@@ -135,31 +229,47 @@ pub fn resolve_std<'cfg>(
     spec_pkgs.push("test".to_string());
     let spec = Packages::Packages(spec_pkgs);
     let specs = spec.to_package_id_specs(&std_ws)?;
-    let features = match &config.cli_unstable().build_std_features {
-        Some(list) => list.clone(),
-        None => vec![
-            "panic-unwind".to_string(),
-            "backtrace".to_string(),
-            "default".to_string(),
-        ],
-    };
-    let cli_features = CliFeatures::from_command_line(
-        &features, /*all_features*/ false, /*uses_default_features*/ false,
-    )?;
-    let resolve = ops::resolve_ws_with_opts(
-        &std_ws,
-        target_data,
-        &build_config.requested_kinds,
-        &cli_features,
-        &specs,
-        HasDevUnits::No,
-        crate::core::resolver::features::ForceAllTargets::No,
-    )?;
-    Ok((
-        resolve.pkg_set,
-        resolve.targeted_resolve,
-        resolve.resolved_features,
-    ))
+    let mut kinds_by_features: BTreeMap<Vec<String>, Vec<CompileKind>> = BTreeMap::new();
+    for &kind in kinds {
+        kinds_by_features
+            .entry(std_features(config, target_data, kind)?)
+            .or_default()
+            .push(kind);
+    }
+    if kinds_by_features.is_empty() {
+        kinds_by_features.insert(
+            std_features(config, target_data, CompileKind::Host)?,
+            build_config.requested_kinds.clone(),
+        );
+    }
+    let mut pkg_set: Option<PackageSet<'cfg>> = None;
+    let mut std_resolve = StdResolve::default();
+    for (features, kinds) in kinds_by_features {
+        let cli_features = CliFeatures::from_command_line(
+            &features, /*all_features*/ false, /*uses_default_features*/ false,
+        )?;
+        let resolve = ops::resolve_ws_with_opts(
+            &std_ws,
+            target_data,
+            &kinds,
+            &cli_features,
+            &specs,
+            HasDevUnits::No,
+            crate::core::resolver::features::ForceAllTargets::No,
+        )?;
+        match &mut pkg_set {
+            Some(pkg_set) => pkg_set.add_set(resolve.pkg_set),
+            None => pkg_set = Some(resolve.pkg_set),
+        }
+        let index = std_resolve.resolves.len();
+        std_resolve
+            .resolves
+            .push((resolve.targeted_resolve, resolve.resolved_features));
+        for kind in kinds {
+            std_resolve.by_kind.insert(kind, index);
+        }
+    }
+    Ok((pkg_set.unwrap(), std_resolve))
 }
 
 /// Generate a list of root `Unit`s for the standard library.
@@ -167,50 +277,50 @@ pub fn resolve_std<'cfg>(
 /// The given slice of crate names is the root set.
 pub fn generate_std_roots(
     crates: &[String],
-    std_resolve: &Resolve,
-    std_features: &ResolvedFeatures,
-    kinds: &[CompileKind],
+    std_resolve: &StdResolve,
     package_set: &PackageSet<'_>,
     interner: &UnitInterner,
     profiles: &Profiles,
 ) -> CargoResult<HashMap<CompileKind, Vec<Unit>>> {
-    // Generate the root Units for the standard library.
-    let std_ids = crates
-        .iter()
-        .map(|crate_name| std_resolve.query(crate_name))
-        .collect::<CargoResult<Vec<PackageId>>>()?;
-    // Convert PackageId to Package.
-    let std_pkgs = package_set.get_many(std_ids)?;
     // Generate a map of Units for each kind requested.
     let mut ret = HashMap::new();
-    for pkg in std_pkgs {
-        let lib = pkg
-            .targets()
+    for kind in std_resolve.kinds() {
+        let (resolve, std_features) = std_resolve.get(kind).unwrap();
+        // Generate the root Units for the standard library.
+        let std_ids = crates
             .iter()
-            .find(|t| t.is_lib())
-            .expect("std has a lib");
-        // I don't think we need to bother with Check here, the difference
-        // in time is minimal, and the difference in caching is
-        // significant.
-        let mode = CompileMode::Build;
-        let features = std_features.activated_features(pkg.package_id(), FeaturesFor::NormalOrDev);
-        for kind in kinds {
-            let list = ret.entry(*kind).or_insert_with(Vec::new);
-            let unit_for = UnitFor::new_normal(*kind);
+            .map(|crate_name| resolve.query(crate_name))
+            .collect::<CargoResult<Vec<PackageId>>>()?;
+        // Convert PackageId to Package.
+        let std_pkgs = package_set.get_many(std_ids)?;
+        let list = ret.entry(kind).or_insert_with(Vec::new);
+        for pkg in std_pkgs {
+            let lib = pkg
+                .targets()
+                .iter()
+                .find(|t| t.is_lib())
+                .expect("std has a lib");
+            // I don't think we need to bother with Check here, the difference
+            // in time is minimal, and the difference in caching is
+            // significant.
+            let mode = CompileMode::Build;
+            let features =
+                std_features.activated_features(pkg.package_id(), FeaturesFor::NormalOrDev);
+            let unit_for = UnitFor::new_normal(kind);
             let profile = profiles.get_profile(
                 pkg.package_id(),
                 /*is_member*/ false,
                 /*is_local*/ false,
                 unit_for,
-                *kind,
+                kind,
             );
             list.push(interner.intern(
                 pkg,
                 lib,
                 profile,
-                *kind,
+                kind,
                 mode,
-                features.clone(),
+                features,
                 /*is_std*/ true,
                 /*dep_hash*/ 0,
                 IsArtifact::No,
@@ -0,0 +1,196 @@
+//! Probing of the system libraries a package declares in
+//! `[package.metadata.system-deps]` (`-Z system-deps`).
+//!
+//! Each entry names a library to look up with `pkg-config` before the build
+//! script of the package runs:
+//!
+//! ```toml
+//! [package.metadata.system-deps]
+//! zlib = "1.2"
+//! gtk = { name = "gtk+-3.0", version = "3.24" }
+//! dbus = { version = "1.12", optional = true }
+//! ```
+//!
+//! The results are handed to the build script through
+//! `CARGO_SYSTEM_DEP_<NAME>_*` environment variables, and the libraries found
+//! are linked as if the build script had printed the corresponding
+//! `cargo:rustc-link-*` instructions.
+
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context as _};
+use cargo_util::ProcessBuilder;
+use toml_edit::easy as toml;
+
+use crate::core::Package;
+use crate::util::errors::CargoResult;
+
+/// A library of `[package.metadata.system-deps]`.
+#[derive(Clone, Debug)]
+pub struct SystemDep {
+    /// The key of the entry, used in the names of the environment variables.
+    key: String,
+    /// The name of the `pkg-config` package.
+    name: String,
+    /// The minimum version of the library.
+    version: Option<String>,
+    /// Whether the build goes on if the library isn't found.
+    optional: bool,
+}
+
+/// What `pkg-config` reported for a [`SystemDep`].
+#[derive(Clone, Debug, Default)]
+pub struct Library {
+    version: String,
+    libs: Vec<String>,
+    link_paths: Vec<PathBuf>,
+    include_paths: Vec<PathBuf>,
+}
+
+/// Parses the `[package.metadata.system-deps]` table of `pkg`.
+pub fn system_deps(pkg: &Package) -> CargoResult<Vec<SystemDep>> {
+    let table = match pkg
+        .manifest()
+        .custom_metadata()
+        .and_then(|metadata| metadata.get("system-deps"))
+    {
+        Some(table) => table,
+        None => return Ok(Vec::new()),
+    };
+    let table = match table.as_table() {
+        Some(table) => table,
+        None => bail!(
+            "`package.metadata.system-deps` of `{}` must be a table",
+            pkg.name()
+        ),
+    };
+    let mut deps = Vec::new();
+    for (key, value) in table {
+        let dep = match value {
+            toml::Value::String(version) => SystemDep {
+                key: key.clone(),
+                name: key.clone(),
+                version: Some(version.clone()),
+                optional: false,
+            },
+            toml::Value::Table(entry) => {
+                let mut dep = SystemDep {
+                    key: key.clone(),
+                    name: key.clone(),
+                    version: None,
+                    optional: false,
+                };
+                for (field, value) in entry {
+                    match (field.as_str(), value) {
+                        ("name", toml::Value::String(name)) => dep.name = name.clone(),
+                        ("version", toml::Value::String(version)) => {
+                            dep.version = Some(version.clone())
+                        }
+                        ("optional", toml::Value::Boolean(optional)) => dep.optional = *optional,
+                        _ => bail!(
+                            "invalid field `{}` in `package.metadata.system-deps.{}` of `{}`, \
+                             expected `name`, `version` (strings) or `optional` (a boolean)",
+                            field,
+                            key,
+                            pkg.name()
+                        ),
+                    }
+                }
+                dep
+            }
+            _ => bail!(
+                "`package.metadata.system-deps.{}` of `{}` must be a version string or a table",
+                key,
+                pkg.name()
+            ),
+        };
+        deps.push(dep);
+    }
+    Ok(deps)
+}
+
+impl SystemDep {
+    /// Looks up the library with the `pkg_config` program.
+    ///
+    /// Returns `None` if an optional library isn't available.
+    pub fn probe(&self, pkg_config: &OsStr, pkg_descr: &str) -> CargoResult<Option<Library>> {
+        match self.query(pkg_config) {
+            Ok(library) => Ok(Some(library)),
+            Err(_) if self.optional => Ok(None),
+            Err(e) => Err(e.context(format!(
+                "failed to find the system library `{}` required by `{}`",
+                self.name, pkg_descr
+            ))),
+        }
+    }
+
+    fn query(&self, pkg_config: &OsStr) -> CargoResult<Library> {
+        let run = |args: &[&str]| -> CargoResult<String> {
+            let mut cmd = ProcessBuilder::new(pkg_config);
+            cmd.args(args).arg(&self.name);
+            let output = cmd.exec_with_output()?;
+            String::from_utf8(output.stdout)
+                .with_context(|| format!("`{}` did not print valid UTF-8", cmd))
+        };
+
+        if let Some(version) = &self.version {
+            run(&[&format!("--atleast-version={}", version)]).with_context(|| {
+                format!(
+                    "`{}` must be at least version {} (`{}` in \
+                     `package.metadata.system-deps`)",
+                    self.name, version, self.key
+                )
+            })?;
+        }
+        let mut library = Library {
+            version: run(&["--modversion"])?.trim().to_string(),
+            ..Default::default()
+        };
+        for flag in run(&["--libs", "--cflags"])?.split_whitespace() {
+            if let Some(lib) = flag.strip_prefix("-l") {
+                library.libs.push(lib.to_string());
+            } else if let Some(path) = flag.strip_prefix("-L") {
+                library.link_paths.push(PathBuf::from(path));
+            } else if let Some(path) = flag.strip_prefix("-I") {
+                library.include_paths.push(PathBuf::from(path));
+            }
+        }
+        Ok(library)
+    }
+
+    /// The environment variables describing `library` to the build script.
+    pub fn env(&self, library: &Library) -> CargoResult<Vec<(String, OsString)>> {
+        let prefix = format!("CARGO_SYSTEM_DEP_{}", super::envify(&self.key));
+        Ok(vec![
+            (
+                format!("{}_VERSION", prefix),
+                library.version.clone().into(),
+            ),
+            (format!("{}_LIBS", prefix), library.libs.join(",").into()),
+            (
+                format!("{}_LINK_PATHS", prefix),
+                std::env::join_paths(&library.link_paths)?,
+            ),
+            (
+                format!("{}_INCLUDE_PATHS", prefix),
+                std::env::join_paths(&library.include_paths)?,
+            ),
+        ])
+    }
+}
+
+impl Library {
+    /// The build script instructions which link the library.
+    pub fn instructions(&self) -> Vec<String> {
+        let search = self
+            .link_paths
+            .iter()
+            .map(|path| format!("cargo:rustc-link-search=native={}", path.display()));
+        let libs = self
+            .libs
+            .iter()
+            .map(|lib| format!("cargo:rustc-link-lib={}", lib));
+        search.chain(libs).collect()
+    }
+}
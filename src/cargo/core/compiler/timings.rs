@@ -2,15 +2,16 @@
 //!
 //! This module implements some simple tracking information for timing of how
 //! long it takes for different units to compile.
+use super::size_report::SizeReport;
 use super::{CompileMode, Unit};
 use crate::core::compiler::job_queue::JobId;
-use crate::core::compiler::{BuildContext, Context, TimingOutput};
+use crate::core::compiler::{BuildContext, Context, MessageFormat, TimingOutput};
 use crate::core::PackageId;
 use crate::util::cpu::State;
 use crate::util::machine_message::{self, Message};
 use crate::util::{CargoResult, Config};
 use anyhow::Context as _;
-use cargo_util::paths;
+use cargo_util::{paths, ResourceUsage};
 use std::collections::HashMap;
 use std::io::{BufWriter, Write};
 use std::thread::available_parallelism;
@@ -24,6 +25,12 @@ pub struct Timings<'cfg> {
     report_html: bool,
     /// If true, emits JSON information with timing information.
     report_json: bool,
+    /// If true, analyzes the sizes of the binaries after the build.
+    report_size: bool,
+    /// The size report recorded after the build, if any.
+    size_report: Option<SizeReport>,
+    /// The format of the JSON messages, which may be versioned.
+    message_format: MessageFormat,
     /// When Cargo started.
     start: Instant,
     /// A rendered string of when compilation started.
@@ -71,6 +78,9 @@ struct UnitTime {
     unlocked_units: Vec<Unit>,
     /// Same as `unlocked_units`, but unlocked by rmeta.
     unlocked_rmeta_units: Vec<Unit>,
+    /// Resources used by the process of this unit, if the platform reports
+    /// them.
+    usage: Option<ResourceUsage>,
 }
 
 /// Periodic concurrency tracking information.
@@ -96,6 +106,7 @@ impl<'cfg> Timings<'cfg> {
         let has_report = |what| bcx.build_config.timing_outputs.contains(&what);
         let report_html = has_report(TimingOutput::Html);
         let report_json = has_report(TimingOutput::Json);
+        let report_size = has_report(TimingOutput::Size);
         let enabled = report_html | report_json;
 
         let mut root_map: HashMap<PackageId, Vec<String>> = HashMap::new();
@@ -132,6 +143,9 @@ impl<'cfg> Timings<'cfg> {
             enabled,
             report_html,
             report_json,
+            report_size,
+            size_report: None,
+            message_format: bcx.build_config.message_format,
             start: bcx.config.creation_time(),
             start_str,
             root_targets,
@@ -178,6 +192,7 @@ impl<'cfg> Timings<'cfg> {
             rmeta_time: None,
             unlocked_units: Vec::new(),
             unlocked_rmeta_units: Vec::new(),
+            usage: None,
         };
         assert!(self.active.insert(id, unit_time).is_none());
     }
@@ -202,6 +217,13 @@ impl<'cfg> Timings<'cfg> {
             .extend(unlocked.iter().cloned().cloned());
     }
 
+    /// Record the resources used by the process of a unit.
+    pub fn unit_resource_usage(&mut self, id: JobId, usage: ResourceUsage) {
+        if let Some(unit_time) = self.active.get_mut(&id) {
+            unit_time.usage = Some(usage);
+        }
+    }
+
     /// Mark that a unit has finished running.
     pub fn unit_finished(&mut self, id: JobId, unlocked: Vec<&Unit>) {
         if !self.enabled {
@@ -225,8 +247,13 @@ impl<'cfg> Timings<'cfg> {
                 mode: unit_time.unit.mode,
                 duration: unit_time.duration,
                 rmeta_time: unit_time.rmeta_time,
+                max_rss: unit_time.usage.map(|u| u.max_rss),
+                user_time: unit_time.usage.map(|u| u.user_time.as_secs_f64()),
+                system_time: unit_time.usage.map(|u| u.system_time.as_secs_f64()),
+                blocks_read: unit_time.usage.map(|u| u.blocks_read),
+                blocks_written: unit_time.usage.map(|u| u.blocks_written),
             }
-            .to_json_string();
+            .to_json_string_for(self.message_format);
             crate::drop_println!(self.config, "{}", msg);
         }
         self.unit_times.push(unit_time);
@@ -297,6 +324,13 @@ impl<'cfg> Timings<'cfg> {
         cx: &Context<'_, '_>,
         error: &Option<anyhow::Error>,
     ) -> CargoResult<()> {
+        if self.report_size && error.is_none() {
+            let dir = cx.files().host_root().join("cargo-timings");
+            let report =
+                SizeReport::record(cx, &dir).with_context(|| "failed to save size report")?;
+            report.print_summary(self.config)?;
+            self.size_report = Some(report);
+        }
         if !self.enabled {
             return Ok(());
         }
@@ -327,6 +361,9 @@ impl<'cfg> Timings<'cfg> {
         self.write_summary_table(&mut f, duration, cx.bcx, error)?;
         f.write_all(HTML_CANVAS.as_bytes())?;
         self.write_unit_table(&mut f)?;
+        if let Some(size_report) = &self.size_report {
+            f.write_all(size_report.render_html().as_bytes())?;
+        }
         // It helps with pixel alignment to use whole numbers.
         writeln!(
             f,
@@ -391,6 +428,23 @@ impl<'cfg> Timings<'cfg> {
             .max()
             .unwrap();
         let rustc_info = render_rustc_info(bcx);
+        let max_rss = match self
+            .unit_times
+            .iter()
+            .filter_map(|ut| Some((ut.usage?.max_rss, ut)))
+            .max_by_key(|(max_rss, _)| *max_rss)
+        {
+            Some((max_rss, ut)) => format!(
+                r#"
+  <tr>
+    <td>Max unit memory:</td><td>{} ({}{})</td>
+  </tr>"#,
+                format_bytes(max_rss),
+                ut.name_ver(),
+                ut.target
+            ),
+            None => "".to_string(),
+        };
         let error_msg = match error {
             Some(e) => format!(
                 r#"\
@@ -435,7 +489,7 @@ impl<'cfg> Timings<'cfg> {
   </tr>
   <tr>
     <td>Max (global) rustc threads concurrency:</td><td>{}</td>
-  </tr>
+  </tr>{}
 {}
 </table>
 "#,
@@ -451,6 +505,7 @@ impl<'cfg> Timings<'cfg> {
             total_time,
             rustc_info,
             max_rustc_concurrency,
+            max_rss,
             error_msg,
         )?;
         Ok(())
@@ -547,6 +602,9 @@ impl<'cfg> Timings<'cfg> {
       <th>Unit</th>
       <th>Total</th>
       <th>Codegen</th>
+      <th>Max RSS</th>
+      <th>CPU (user/sys)</th>
+      <th>Block I/O (in/out)</th>
       <th>Features</th>
     </tr>
   </thead>
@@ -560,6 +618,18 @@ impl<'cfg> Timings<'cfg> {
                 None => "".to_string(),
                 Some((_rt, ctime, cent)) => format!("{:.1}s ({:.0}%)", ctime, cent),
             };
+            let (max_rss, cpu, io) = match unit.usage {
+                None => Default::default(),
+                Some(usage) => (
+                    format_bytes(usage.max_rss),
+                    format!(
+                        "{:.1}s / {:.1}s",
+                        usage.user_time.as_secs_f64(),
+                        usage.system_time.as_secs_f64()
+                    ),
+                    format!("{} / {}", usage.blocks_read, usage.blocks_written),
+                ),
+            };
             let features = unit.unit.features.join(", ");
             write!(
                 f,
@@ -570,6 +640,9 @@ impl<'cfg> Timings<'cfg> {
   <td>{:.1}s</td>
   <td>{}</td>
   <td>{}</td>
+  <td>{}</td>
+  <td>{}</td>
+  <td>{}</td>
 </tr>
 "#,
                 i + 1,
@@ -577,6 +650,9 @@ impl<'cfg> Timings<'cfg> {
                 unit.target,
                 unit.duration,
                 codegen,
+                max_rss,
+                cpu,
+                io,
                 features,
             )?;
         }
@@ -600,6 +676,11 @@ impl UnitTime {
     }
 }
 
+fn format_bytes(bytes: u64) -> String {
+    let (size, unit) = crate::util::human_readable_bytes(bytes);
+    format!("{:.1} {}", size, unit)
+}
+
 fn render_rustc_info(bcx: &BuildContext<'_, '_>) -> String {
     let version = bcx
         .rustc()
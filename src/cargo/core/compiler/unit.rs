@@ -85,6 +85,14 @@ impl UnitInner {
         self.mode.is_any_test() || self.target.kind().requires_upstream_objects()
     }
 
+    /// Returns whether this unit checks a library, whose metadata may be shared
+    /// with the unit building it (`-Zcheck-build-sharing`).
+    pub fn is_shareable_check(&self) -> bool {
+        self.mode == (CompileMode::Check { test: false })
+            && self.target.is_lib()
+            && !self.target.proc_macro()
+    }
+
     /// Returns whether or not this is a "local" package.
     ///
     /// A "local" package is one that the user can likely edit, or otherwise
@@ -15,22 +15,25 @@
 //! (for example, with and without tests), so we actually build a dependency
 //! graph of [`Unit`]s, which capture these properties.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 
 use log::trace;
 
+use crate::core::compiler::artifact::match_artifacts_kind_with_targets;
+use crate::core::compiler::standard_lib::StdResolve;
 use crate::core::compiler::unit_graph::{UnitDep, UnitGraph};
 use crate::core::compiler::{
     CompileKind, CompileMode, CrateType, RustcTargetData, Unit, UnitInterner,
 };
-use crate::core::dependency::{Artifact, ArtifactKind, ArtifactTarget, DepKind};
+use crate::core::dependency::{Artifact, ArtifactTarget, DepKind};
 use crate::core::profiles::{Profile, Profiles, UnitFor};
 use crate::core::resolver::features::{FeaturesFor, ResolvedFeatures};
 use crate::core::resolver::Resolve;
 use crate::core::{Dependency, Package, PackageId, PackageSet, Target, TargetKind, Workspace};
 use crate::ops::resolve_all_features;
 use crate::util::interning::InternedString;
-use crate::util::Config;
+use crate::util::{Config, StableHasher};
 use crate::CargoResult;
 
 const IS_NO_ARTIFACT_DEP: Option<&'static Artifact> = None;
@@ -44,12 +47,22 @@ struct State<'a, 'cfg> {
     package_set: &'a PackageSet<'cfg>,
     usr_resolve: &'a Resolve,
     usr_features: &'a ResolvedFeatures,
+    /// The resolves of the standard library for each kind (`-Zbuild-std`).
+    std_resolves: Option<&'a StdResolve>,
     /// Like `usr_resolve` but for building standard library (`-Zbuild-std`).
+    ///
+    /// This is the resolve of the kind whose standard library is generated.
     std_resolve: Option<&'a Resolve>,
     /// Like `usr_features` but for building standard library (`-Zbuild-std`).
     std_features: Option<&'a ResolvedFeatures>,
     /// `true` while generating the dependencies for the standard library.
     is_std: bool,
+    /// The workspace member whose dependencies are being generated, if each
+    /// member has its own feature set.
+    member: Option<PackageId>,
+    /// The target whose dependencies are being generated, if it has its own
+    /// feature set with `--auto-required-features`.
+    target: Option<Target>,
     /// The mode we are compiling in. Used for preventing from building lib thrice.
     global_mode: CompileMode,
     target_data: &'a RustcTargetData<'cfg>,
@@ -85,7 +98,7 @@ pub fn build_unit_dependencies<'a, 'cfg>(
     package_set: &'a PackageSet<'cfg>,
     resolve: &'a Resolve,
     features: &'a ResolvedFeatures,
-    std_resolve: Option<&'a (Resolve, ResolvedFeatures)>,
+    std_resolve: Option<&'a StdResolve>,
     roots: &[Unit],
     scrape_units: &[Unit],
     std_roots: &HashMap<CompileKind, Vec<Unit>>,
@@ -100,10 +113,6 @@ pub fn build_unit_dependencies<'a, 'cfg>(
         // in the dep graph without a root.
         return Ok(HashMap::new());
     }
-    let (std_resolve, std_features) = match std_resolve {
-        Some((r, f)) => (Some(r), Some(f)),
-        None => (None, None),
-    };
     let mut state = State {
         ws,
         config: ws.config(),
@@ -111,9 +120,12 @@ pub fn build_unit_dependencies<'a, 'cfg>(
         package_set,
         usr_resolve: resolve,
         usr_features: features,
-        std_resolve,
-        std_features,
+        std_resolves: std_resolve,
+        std_resolve: None,
+        std_features: None,
         is_std: false,
+        member: None,
+        target: None,
         global_mode,
         target_data,
         profiles,
@@ -125,7 +137,8 @@ pub fn build_unit_dependencies<'a, 'cfg>(
     let std_unit_deps = calc_deps_of_std(&mut state, std_roots)?;
 
     deps_of_roots(roots, &mut state)?;
-    super::links::validate_links(state.resolve(), &state.unit_dependencies)?;
+    super::links::validate_links(state.resolve(), &state.unit_dependencies, target_data)?;
+    warn_on_build_script_artifact_profiles(&state)?;
     // Hopefully there aren't any links conflicts with the standard library?
 
     if let Some(std_unit_deps) = std_unit_deps {
@@ -133,6 +146,7 @@ pub fn build_unit_dependencies<'a, 'cfg>(
     }
 
     connect_run_custom_build_deps(&mut state);
+    connect_reexported_artifact_deps(&mut state);
 
     // Dependencies are used in tons of places throughout the backend, many of
     // which affect the determinism of the build itself. As a result be sure
@@ -156,7 +170,13 @@ fn calc_deps_of_std(
     }
     // Compute dependencies for the standard library.
     state.is_std = true;
-    for roots in std_roots.values() {
+    for (kind, roots) in std_roots {
+        let (resolve, features) = state
+            .std_resolves
+            .and_then(|std_resolves| std_resolves.get(*kind))
+            .expect("the standard library is resolved for each kind it is built for");
+        state.std_resolve = Some(resolve);
+        state.std_features = Some(features);
         deps_of_roots(roots, state)?;
     }
     state.is_std = false;
@@ -227,6 +247,15 @@ fn deps_of_roots(roots: &[Unit], state: &mut State<'_, '_>) -> CargoResult<()> {
         } else {
             UnitFor::new_normal(root_compile_kind)
         };
+        let pkg_id = unit.pkg.package_id();
+        let has_own_features = state
+            .usr_features
+            .for_target(pkg_id, &unit.target)
+            .is_some();
+        if state.usr_features.decouples_members() || has_own_features {
+            state.member = Some(pkg_id);
+        }
+        state.target = has_own_features.then(|| unit.target.clone());
         deps_of(unit, state, unit_for)?;
     }
 
@@ -461,7 +490,7 @@ fn compute_deps_custom_build(
     if let Some(links) = unit.pkg.manifest().links() {
         if state
             .target_data
-            .script_override(links, unit.kind)
+            .script_override(unit.pkg.package_id(), links, unit.kind)
             .is_some()
         {
             // Overridden build scripts don't have any dependencies.
@@ -536,6 +565,77 @@ fn compute_deps_custom_build(
     Ok(result)
 }
 
+/// Warns about artifact dependencies of build scripts that are built with a
+/// different `opt-level` than the package running the build script.
+///
+/// Units needed by build scripts use the `build-override` settings, which
+/// don't optimize by default. This is rarely what's intended for an artifact
+/// that the build script embeds into a release build, for example with
+/// `include_bytes!(env!("CARGO_BIN_FILE_..."))`.
+///
+/// Binaries built for the host are left alone, since build scripts mostly run
+/// them as generators, where the default is right. So are artifacts whose
+/// `opt-level` is set explicitly, which is how to opt out of the warning.
+fn warn_on_build_script_artifact_profiles(state: &State<'_, '_>) -> CargoResult<()> {
+    let mut mismatches = BTreeSet::new();
+    for (parent, deps) in &state.unit_dependencies {
+        if !parent.mode.is_run_custom_build() {
+            continue;
+        }
+        for dep in deps {
+            let unit = &dep.unit;
+            if !unit.artifact.is_true() || !dep.unit_for.is_for_host() {
+                continue;
+            }
+            if unit.target.is_bin() && unit.kind.is_host() {
+                continue;
+            }
+            if state
+                .profiles
+                .sets_build_opt_level(unit.pkg.package_id(), state.ws.is_member(&unit.pkg))
+            {
+                continue;
+            }
+            // The profile the consumer itself is built with, which is where
+            // an embedded artifact ends up.
+            let intended = state.profiles.get_profile(
+                parent.pkg.package_id(),
+                state.ws.is_member(&parent.pkg),
+                parent.is_local(),
+                UnitFor::new_normal(parent.kind),
+                parent.kind,
+            );
+            if intended.opt_level != unit.profile.opt_level {
+                mismatches.insert((
+                    parent.pkg.package_id(),
+                    unit.pkg.package_id(),
+                    unit.profile.name,
+                    unit.profile.opt_level,
+                    intended.opt_level,
+                ));
+            }
+        }
+    }
+    for (parent_id, dep_id, profile_name, actual, intended) in mismatches {
+        state.config.shell().warn(format!(
+            "artifact dependency `{name} v{version}` of the build script of `{parent} v{parent_version}` \
+             is built with opt-level={actual}, but the `{profile}` profile uses opt-level={intended}\n\
+             Dependencies of build scripts use the `[profile.{profile}.build-override]` settings. \
+             To build this dependency with opt-level={intended}, add `opt-level = {intended}` \
+             to `[profile.{profile}.package.{name}]`, or set another `opt-level` there \
+             to silence this warning.",
+            version = dep_id.version(),
+            parent = parent_id.name(),
+            parent_version = parent_id.version(),
+            actual = actual,
+            intended = intended,
+            profile = profile_name,
+            name = dep_id.name(),
+        ))?;
+    }
+    Ok(())
+}
+
 /// Given a `parent` unit containing a dependency `dep` whose package is `artifact_pkg`,
 /// find all targets in `artifact_pkg` which refer to the `dep`s artifact declaration
 /// and turn them into units.
@@ -598,45 +698,6 @@ fn artifact_targets_to_unit_deps(
     Ok(ret)
 }
 
-/// Given a dependency with an artifact `artifact_dep` and a set of available `targets`
-/// of its package, find a target for each kind of artifacts that are to be built.
-///
-/// Failure to match any target results in an error mentioning the parent manifests
-/// `parent_package` name.
-fn match_artifacts_kind_with_targets<'a>(
-    artifact_dep: &Dependency,
-    targets: &'a [Target],
-    parent_package: &str,
-) -> CargoResult<HashSet<&'a Target>> {
-    let mut out = HashSet::new();
-    let artifact_requirements = artifact_dep.artifact().expect("artifact present");
-    for artifact_kind in artifact_requirements.kinds() {
-        let mut extend = |filter: &dyn Fn(&&Target) -> bool| {
-            let mut iter = targets.iter().filter(filter).peekable();
-            let found = iter.peek().is_some();
-            out.extend(iter);
-            found
-        };
-        let found = match artifact_kind {
-            ArtifactKind::Cdylib => extend(&|t| t.is_cdylib()),
-            ArtifactKind::Staticlib => extend(&|t| t.is_staticlib()),
-            ArtifactKind::AllBinaries => extend(&|t| t.is_bin()),
-            ArtifactKind::SelectedBinary(bin_name) => {
-                extend(&|t| t.is_bin() && t.name() == bin_name.as_str())
-            }
-        };
-        if !found {
-            anyhow::bail!(
-                "dependency `{}` in package `{}` requires a `{}` artifact to be present.",
-                artifact_dep.name_in_toml(),
-                parent_package,
-                artifact_kind
-            );
-        }
-    }
-    Ok(out)
-}
-
 /// Returns the dependencies necessary to document a package.
 fn compute_deps_doc(
     unit: &Unit,
@@ -669,7 +730,7 @@ fn compute_deps_doc(
         )?;
         ret.push(lib_unit_dep);
         if dep_lib.documented() {
-            if let CompileMode::Doc { deps: true } = unit.mode {
+            if let CompileMode::Doc { deps: true, .. } = unit.mode {
                 // Document this lib as well.
                 let doc_unit_dep = new_unit_dep(
                     state,
@@ -894,7 +955,7 @@ fn new_unit_dep_with_profile(
         mode,
         features,
         state.is_std,
-        /*dep_hash*/ 0,
+        state.dep_hash(),
         artifact.map_or(IsArtifact::No, |_| IsArtifact::Yes),
     );
     Ok(UnitDep {
@@ -907,6 +968,84 @@ fn new_unit_dep_with_profile(
     })
 }
 
+/// Adds the artifact dependencies a package re-exports with
+/// `artifact-reexport = true` to the units of the packages depending on it
+/// with `artifact-env = "transitive"`, so these receive the environment
+/// variables of the re-exported artifacts as well.
+///
+/// Like direct artifact build-dependencies, the artifacts re-exported by a
+/// build-dependency are added to the execution of the build script.
+fn connect_reexported_artifact_deps(state: &mut State<'_, '_>) {
+    let mut new_deps = Vec::new();
+
+    {
+        let state = &*state;
+        let has_edge = |from: PackageId, to: PackageId, pred: &dyn Fn(&Dependency) -> bool| {
+            state
+                .resolve()
+                .deps(from)
+                .any(|(id, deps)| id == to && deps.iter().any(pred))
+        };
+        for (unit, deps) in state.unit_dependencies.iter() {
+            if unit.target.is_custom_build() && !unit.mode.is_run_custom_build() {
+                continue;
+            }
+            // The libraries of build-dependencies are dependencies of the
+            // compiled build script rather than of its execution.
+            let for_build_script = unit.mode.is_run_custom_build();
+            let libs = if for_build_script {
+                match deps
+                    .iter()
+                    .find(|d| d.unit.target.is_custom_build() && !d.unit.mode.is_run_custom_build())
+                {
+                    Some(script) => &state.unit_dependencies[&script.unit],
+                    None => continue,
+                }
+            } else {
+                deps
+            };
+
+            let mut to_add = Vec::new();
+            for lib in libs
+                .iter()
+                .filter(|d| !d.unit.artifact.is_true() && d.unit.target.is_lib())
+            {
+                let transitive = has_edge(unit.pkg.package_id(), lib.unit.pkg.package_id(), &|d| {
+                    d.has_transitive_artifact_env() && d.is_build() == for_build_script
+                });
+                if !transitive {
+                    continue;
+                }
+                for artifact in state.unit_dependencies[&lib.unit]
+                    .iter()
+                    .filter(|d| d.unit.artifact.is_true())
+                {
+                    if has_edge(
+                        lib.unit.pkg.package_id(),
+                        artifact.unit.pkg.package_id(),
+                        &|d| d.reexports_artifact(),
+                    ) && !deps.contains(artifact)
+                        && !to_add.contains(artifact)
+                    {
+                        to_add.push(artifact.clone());
+                    }
+                }
+            }
+            if !to_add.is_empty() {
+                new_deps.push((unit.clone(), to_add));
+            }
+        }
+    }
+
+    for (unit, new_deps) in new_deps {
+        state
+            .unit_dependencies
+            .get_mut(&unit)
+            .unwrap()
+            .extend(new_deps);
+    }
+}
+
 /// Fill in missing dependencies for units of the `RunCustomBuild`
 ///
 /// As mentioned above in `compute_deps_custom_build` each build script
@@ -1032,12 +1171,38 @@ impl<'a, 'cfg> State<'a, 'cfg> {
         }
     }
 
-    /// Gets `std_features` during building std, otherwise `usr_features`.
+    /// Gets `std_features` during building std, otherwise `usr_features`
+    /// (of the current member, if members are decoupled).
     fn features(&self) -> &'a ResolvedFeatures {
         if self.is_std {
             self.std_features.unwrap()
         } else {
-            self.usr_features
+            match (self.member, &self.target) {
+                (Some(member), Some(target)) => self.usr_features.for_root(member, target),
+                (Some(member), None) => self.usr_features.for_member(member),
+                (None, _) => self.usr_features,
+            }
+        }
+    }
+
+    /// The `dep_hash` of new dependency units.
+    ///
+    /// If members are decoupled, the dependencies of each member are kept
+    /// apart since the same unit may have different dependencies for
+    /// another member, and likewise for targets with their own features.
+    /// Units which turn out to be identical are merged again in
+    /// `rebuild_unit_graph_shared`.
+    fn dep_hash(&self) -> u64 {
+        match self.member {
+            Some(member) if !self.is_std => {
+                let mut hasher = StableHasher::new();
+                member.hash(&mut hasher);
+                if let Some(target) = &self.target {
+                    target.hash(&mut hasher);
+                }
+                hasher.finish()
+            }
+            _ => 0,
         }
     }
 
@@ -1094,6 +1259,19 @@ impl<'a, 'cfg> State<'a, 'cfg> {
                             return false;
                         }
 
+                        // Dev-dependencies scoped to examples are only available
+                        // to the examples listing them.
+                        if !dep.is_transitive()
+                            && is_scoped_dependency(unit.pkg.targets(), dep.name_in_toml())
+                            && !unit
+                                .target
+                                .scoped_dependencies()
+                                .iter()
+                                .any(|name| name == dep.name_in_toml().as_str())
+                        {
+                            return false;
+                        }
+
                         // If this dependency is only available for certain platforms,
                         // make sure we're only enabling it for that platform.
                         if !self.target_data.dep_platform_activated(dep, kind) {
@@ -1123,3 +1301,13 @@ impl<'a, 'cfg> State<'a, 'cfg> {
             .collect()
     }
 }
+
+/// Whether the dev-dependency `name` is scoped to some of `targets`.
+fn is_scoped_dependency(targets: &[Target], name: InternedString) -> bool {
+    targets.iter().any(|target| {
+        target
+            .scoped_dependencies()
+            .iter()
+            .any(|scoped| scoped == name.as_str())
+    })
+}
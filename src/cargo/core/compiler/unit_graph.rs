@@ -1,3 +1,4 @@
+use crate::core::compiler::custom_build::BuildManifest;
 use crate::core::compiler::Unit;
 use crate::core::compiler::{CompileKind, CompileMode};
 use crate::core::profiles::{Profile, UnitFor};
@@ -53,6 +54,9 @@ struct SerializedUnit<'a> {
     #[serde(skip_serializing_if = "std::ops::Not::not")] // hide for unstable build-std
     is_std: bool,
     dependencies: Vec<SerializedUnitDep>,
+    // Only for build script runs under unstable `-Z build-manifest`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_manifest: Option<&'a BuildManifest>,
 }
 
 #[derive(serde::Serialize)]
@@ -72,6 +76,7 @@ struct SerializedUnitDep {
 pub fn emit_serialized_unit_graph(
     root_units: &[Unit],
     unit_graph: &UnitGraph,
+    build_manifests: &HashMap<Unit, BuildManifest>,
     config: &Config,
 ) -> CargoResult<()> {
     let mut units: Vec<(&Unit, &Vec<UnitDep>)> = unit_graph.iter().collect();
@@ -112,6 +117,7 @@ pub fn emit_serialized_unit_graph(
                 features: &unit.features,
                 is_std: unit.is_std,
                 dependencies,
+                build_manifest: build_manifests.get(*unit),
             }
         })
         .collect();
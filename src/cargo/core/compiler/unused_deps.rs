@@ -0,0 +1,366 @@
+//! Support for `--warn-unused-dependencies`.
+//!
+//! Every rustc unit of a workspace member is compiled with the
+//! `unused_crate_dependencies` lint, and the `--extern` crates it reports
+//! are collected by the job queue instead of being displayed. Once the build
+//! finished, a dependency declared in a manifest is reported as unused if it
+//! was passed to at least one unit of the package, and every unit it was
+//! passed to reported it as unused. Dependencies which weren't needed by any
+//! of the built units, such as dev-dependencies in a plain `cargo build`,
+//! are never reported.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use toml_edit::{Document, Item, Table};
+
+use crate::core::compiler::{CompileMode, Context, Unit};
+use crate::core::dependency::DepKind;
+use crate::core::{Dependency, PackageId};
+use crate::util::interning::InternedString;
+use crate::util::machine_message::{self, Message};
+use crate::util::toml_mut::manifest::{DepTable, LocalManifest};
+use crate::CargoResult;
+
+/// What to do about unused dependencies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnusedDepsMode {
+    /// Print a warning for each unused dependency.
+    Warn,
+    /// Comment out the unused dependencies in their manifest.
+    Fix,
+}
+
+/// Whether `unit` is compiled with the `unused_crate_dependencies` lint.
+pub fn lints_unused_externs(cx: &Context<'_, '_>, unit: &Unit) -> bool {
+    cx.bcx.build_config.unused_dependencies.is_some()
+        && cx.bcx.ws.is_member(&unit.pkg)
+        && matches!(
+            unit.mode,
+            CompileMode::Build | CompileMode::Check { .. } | CompileMode::Test | CompileMode::Bench
+        )
+}
+
+/// Extracts the crate name from an `unused_crate_dependencies` diagnostic,
+/// like ``extern crate `bar` is unused in crate `foo` ``.
+pub fn unused_extern_name(message: &str) -> Option<&str> {
+    let start = message.find('`')? + 1;
+    let len = message[start..].find('`')?;
+    Some(&message[start..start + len])
+}
+
+/// An unused dependency declaration of a workspace member.
+struct UnusedDep<'a> {
+    package_id: PackageId,
+    manifest_path: PathBuf,
+    dep: &'a Dependency,
+}
+
+impl UnusedDep<'_> {
+    /// The manifest table declaring the dependency, like
+    /// `target.'cfg(unix)'.dev-dependencies`.
+    fn table(&self) -> String {
+        let kind = match self.dep.kind() {
+            DepKind::Normal => "dependencies",
+            DepKind::Development => "dev-dependencies",
+            DepKind::Build => "build-dependencies",
+        };
+        match self.dep.platform() {
+            Some(platform) => format!("target.'{}'.{}", platform, kind),
+            None => kind.to_string(),
+        }
+    }
+
+    /// The keys to the manifest table declaring the dependency.
+    fn table_path(&self) -> Vec<String> {
+        let mut table = DepTable::from(self.dep.kind());
+        if let Some(platform) = self.dep.platform() {
+            table = table.set_target(platform.to_string());
+        }
+        table.to_table().into_iter().map(String::from).collect()
+    }
+}
+
+/// Reports, or fixes, the dependencies of workspace members which none of
+/// their units used.
+///
+/// `unused_externs` holds the extern crate names each unit reported as
+/// unused.
+pub fn report(
+    cx: &Context<'_, '_>,
+    unused_externs: &HashMap<Unit, HashSet<InternedString>>,
+) -> CargoResult<()> {
+    let mode = match cx.bcx.build_config.unused_dependencies {
+        Some(mode) => mode,
+        None => return Ok(()),
+    };
+
+    // For every dependency declaration, whether any unit used it. The key is
+    // the package and the index of the declaration in its dependencies.
+    let mut declarations: BTreeMap<(PackageId, usize), bool> = BTreeMap::new();
+    let mut packages = HashMap::new();
+    for (unit, deps) in cx.bcx.unit_graph.iter() {
+        if !lints_unused_externs(cx, unit) {
+            continue;
+        }
+        let unused = unused_externs.get(unit);
+        packages.insert(unit.pkg.package_id(), &unit.pkg);
+        for dep in deps {
+            if !dep.unit.target.is_linkable()
+                || dep.unit.mode.is_doc()
+                || dep.unit.pkg.package_id() == unit.pkg.package_id()
+            {
+                continue;
+            }
+            let used = !unused.map_or(false, |u| u.contains(&dep.extern_crate_name));
+            let dep_id = dep.unit.pkg.package_id();
+            for (i, decl) in unit.pkg.dependencies().iter().enumerate() {
+                if !decl.matches_id(dep_id) || !declaration_applies(unit, decl) {
+                    continue;
+                }
+                *declarations
+                    .entry((unit.pkg.package_id(), i))
+                    .or_insert(false) |= used;
+            }
+        }
+    }
+
+    let mut unused: Vec<_> = declarations
+        .into_iter()
+        .filter(|&(_, used)| !used)
+        .map(|((package_id, i), _)| {
+            let pkg = packages[&package_id];
+            UnusedDep {
+                package_id,
+                manifest_path: pkg.manifest_path().to_path_buf(),
+                dep: &pkg.dependencies()[i],
+            }
+        })
+        .collect();
+    if unused.is_empty() {
+        return Ok(());
+    }
+    unused.sort_by_cached_key(|u| (u.package_id, u.table(), u.dep.name_in_toml()));
+
+    let config = cx.bcx.config;
+    if cx.bcx.build_config.emit_json() {
+        for unused in &unused {
+            let table = unused.table();
+            let msg = machine_message::UnusedDependency {
+                package_id: unused.package_id,
+                manifest_path: &unused.manifest_path,
+                dependency: unused.dep.name_in_toml().as_str(),
+                table: &table,
+            }
+            .to_json_string_for(cx.bcx.build_config.message_format);
+            writeln!(config.shell().out(), "{}", msg)?;
+        }
+    }
+
+    match mode {
+        UnusedDepsMode::Warn => {
+            let mut shell = config.shell();
+            for unused in &unused {
+                shell.warn(format!(
+                    "unused dependency `{}` in `[{}]` of package `{}`",
+                    unused.dep.name_in_toml(),
+                    unused.table(),
+                    unused.package_id
+                ))?;
+            }
+            shell.note(
+                "pass `--warn-unused-dependencies=fix` to comment out the unused dependencies",
+            )?;
+        }
+        UnusedDepsMode::Fix => {
+            let mut by_manifest: BTreeMap<&PathBuf, Vec<&UnusedDep<'_>>> = BTreeMap::new();
+            for unused in &unused {
+                by_manifest
+                    .entry(&unused.manifest_path)
+                    .or_default()
+                    .push(unused);
+            }
+            for (manifest_path, unused) in by_manifest {
+                let mut manifest = LocalManifest::try_new(manifest_path)?;
+                let mut fixed = Vec::new();
+                for unused in unused {
+                    let name = unused.dep.name_in_toml();
+                    if comment_out_dependency(&mut manifest.data, &unused.table_path(), &name) {
+                        fixed.push(format!("`{}`", name));
+                    } else {
+                        config.shell().warn(format!(
+                            "could not comment out unused dependency `{}` in `{}`, \
+                             please remove it manually",
+                            name,
+                            manifest_path.display()
+                        ))?;
+                    }
+                }
+                if fixed.is_empty() {
+                    continue;
+                }
+                manifest.write()?;
+                config.shell().status(
+                    "Fixed",
+                    format!(
+                        "{} (commented out {})",
+                        manifest_path.display(),
+                        fixed.join(", ")
+                    ),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the declaration `decl` could have been used for a dependency of
+/// `unit`, based on the kind of the declaration.
+fn declaration_applies(unit: &Unit, decl: &Dependency) -> bool {
+    if unit.target.is_custom_build() {
+        return decl.kind() == DepKind::Build;
+    }
+    match decl.kind() {
+        DepKind::Normal => true,
+        DepKind::Development => {
+            unit.mode.is_any_test()
+                || unit.target.is_test()
+                || unit.target.is_bench()
+                || unit.target.is_example()
+        }
+        DepKind::Build => false,
+    }
+}
+
+/// Comments out the declaration of the dependency `name` in the manifest
+/// table at `table_path`, either a `name = ...` entry or a `[table.name]`
+/// table.
+///
+/// The declaration is removed from the document, and its text is commented
+/// out in the decor of whatever follows it, so the rest of the manifest keeps
+/// its formatting.
+///
+/// Returns `false` if the declaration wasn't found, like when its table is
+/// an inline table.
+fn comment_out_dependency(doc: &mut Document, table_path: &[String], name: &str) -> bool {
+    // The keys of the tables leading to the declaration, to render it with
+    // its original formatting.
+    let mut keys = Vec::new();
+    let mut table = doc.as_table_mut();
+    for part in table_path {
+        let key = match table.get_key_value(part) {
+            Some((key, Item::Table(_))) => key.clone(),
+            _ => return false,
+        };
+        keys.push(key);
+        table = table.get_mut(part).unwrap().as_table_mut().unwrap();
+    }
+    let table_position = table.position();
+    let is_header = matches!(table.get(name), Some(Item::Table(t)) if !t.is_dotted());
+    let next_key = table
+        .iter()
+        .skip_while(|(key, _)| *key != name)
+        .skip(1)
+        .find(|(_, item)| !is_header_table(item))
+        .map(|(key, _)| key.to_string());
+    let (key, item) = match table.remove_entry(name) {
+        Some(entry) => entry,
+        None => return false,
+    };
+    let position = match &item {
+        Item::Table(t) if is_header => t.position(),
+        _ => table_position,
+    };
+
+    let mut rendered = Document::new();
+    let mut parent = rendered.as_table_mut();
+    if is_header {
+        for key in &keys {
+            let mut implicit = Table::new();
+            implicit.set_implicit(true);
+            parent.insert_formatted(key, Item::Table(implicit));
+            parent = parent.get_mut(key.get()).unwrap().as_table_mut().unwrap();
+        }
+    }
+    parent.insert_formatted(&key, item);
+    let commented: String = rendered
+        .to_string()
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.trim().is_empty() || trimmed.starts_with('#') {
+                line.to_string()
+            } else {
+                format!("{}# {}", &line[..line.len() - trimmed.len()], trimmed)
+            }
+        })
+        .collect();
+
+    // A `name = ...` entry is followed by the next entry of its table.
+    if !is_header {
+        if let Some(decor) = next_key.and_then(|next| table.key_decor_mut(&next)) {
+            let prefix = format!("{}{}", commented, decor.prefix().unwrap_or_default());
+            decor.set_prefix(prefix);
+            return true;
+        }
+    }
+    // Otherwise, by the next table header of the document.
+    let next = position
+        .and_then(|position| next_table_position(doc.as_table(), position))
+        .and_then(|next| table_at_position_mut(doc.as_table_mut(), next));
+    if let Some(next) = next {
+        let decor = next.decor_mut();
+        let prefix = format!("{}{}", commented, decor.prefix().unwrap_or_default());
+        decor.set_prefix(prefix);
+        return true;
+    }
+    // Or by nothing at all.
+    let trailing = format!("{}{}", commented, doc.trailing());
+    doc.set_trailing(&trailing);
+    true
+}
+
+/// Whether `item` is a table with its own `[header]`.
+fn is_header_table(item: &Item) -> bool {
+    match item {
+        Item::Table(t) => !t.is_dotted(),
+        Item::ArrayOfTables(_) => true,
+        _ => false,
+    }
+}
+
+/// Whether `table` is rendered with a `[header]`, which its decor precedes.
+fn has_header(table: &Table) -> bool {
+    !table.is_implicit() && !table.is_dotted()
+}
+
+/// The smallest position after `position` of a table in `table` which has a
+/// header.
+fn next_table_position(table: &Table, position: usize) -> Option<usize> {
+    let own = table
+        .position()
+        .filter(|&p| p > position && has_header(table));
+    let nested = table.iter().filter_map(|(_, item)| match item {
+        Item::Table(t) => next_table_position(t, position),
+        Item::ArrayOfTables(a) => a
+            .iter()
+            .filter_map(|t| next_table_position(t, position))
+            .min(),
+        _ => None,
+    });
+    own.into_iter().chain(nested).min()
+}
+
+/// The table with a header at `position` in `table`.
+fn table_at_position_mut(table: &mut Table, position: usize) -> Option<&mut Table> {
+    if table.position() == Some(position) && has_header(table) {
+        return Some(table);
+    }
+    table.iter_mut().find_map(|(_, item)| match item {
+        Item::Table(t) => table_at_position_mut(t, position),
+        Item::ArrayOfTables(a) => a
+            .iter_mut()
+            .find_map(|t| table_at_position_mut(t, position)),
+        _ => None,
+    })
+}
@@ -46,6 +46,12 @@ struct Inner {
     features: Vec<InternedString>,
     // The presence of this information turns a dependency into an artifact dependency.
     artifact: Option<Artifact>,
+    // Whether the artifacts of this artifact dependency are also made
+    // available to dependents which use `artifact-env = "transitive"`.
+    reexport_artifact: bool,
+    // Whether the dependent also receives the artifacts this dependency
+    // re-exports, set with `artifact-env = "transitive"`.
+    transitive_artifact_env: bool,
 
     // This dependency should be used only for this platform.
     // `None` means *all platforms*.
@@ -169,6 +175,8 @@ impl Dependency {
                 platform: None,
                 explicit_name_in_toml: None,
                 artifact: None,
+                reexport_artifact: false,
+                transitive_artifact_env: false,
             }),
         }
     }
@@ -422,6 +430,30 @@ impl Dependency {
         self.inner.artifact.as_ref()
     }
 
+    /// Sets whether the artifacts of this artifact dependency are re-exported
+    /// to dependents, with `artifact-reexport = true`.
+    pub(crate) fn set_reexport_artifact(&mut self, reexport: bool) {
+        Rc::make_mut(&mut self.inner).reexport_artifact = reexport;
+    }
+
+    /// Returns `true` if this is an artifact dependency whose artifacts are
+    /// made available to dependents with `artifact-env = "transitive"`.
+    pub(crate) fn reexports_artifact(&self) -> bool {
+        self.inner.reexport_artifact
+    }
+
+    /// Sets whether the artifacts re-exported by this dependency are made
+    /// available to the dependent, with `artifact-env = "transitive"`.
+    pub(crate) fn set_transitive_artifact_env(&mut self, transitive: bool) {
+        Rc::make_mut(&mut self.inner).transitive_artifact_env = transitive;
+    }
+
+    /// Returns `true` if the dependent receives the artifacts re-exported by
+    /// this dependency.
+    pub(crate) fn has_transitive_artifact_env(&self) -> bool {
+        self.inner.transitive_artifact_env
+    }
+
     /// Dependencies are potential rust libs if they are not artifacts or they are an
     /// artifact which allows to be seen as library.
     /// Previously, every dependency was potentially seen as library.
@@ -587,7 +619,7 @@ impl fmt::Display for ArtifactKind {
 }
 
 impl ArtifactKind {
-    fn parse(kind: &str) -> CargoResult<Self> {
+    pub(crate) fn parse(kind: &str) -> CargoResult<Self> {
         Ok(match kind {
             "bin" => ArtifactKind::AllBinaries,
             "cdylib" => ArtifactKind::Cdylib,
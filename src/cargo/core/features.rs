@@ -441,6 +441,27 @@ features! {
     // Allow specifying rustflags directly in a profile
     (unstable, profile_rustflags, "", "reference/unstable.html#profile-rustflags-option"),
 
+    // Allow overriding profile settings for all packages from a registry
+    (unstable, profile_registry_packages, "", "reference/unstable.html#profile-registry-packages"),
+
+    // Allow declaring the expected custom cfgs of a package in the manifest
+    (unstable, package_check_cfg, "", "reference/unstable.html#package-check-cfg"),
+
+    // Allow declaring features which must not be enabled together
+    (unstable, feature_conflicts, "", "reference/unstable.html#feature-conflicts"),
+
+    // Allow `resolver = "3"`, resolving the features of each member on its own
+    (unstable, resolver_3, "", "reference/unstable.html#resolver-3"),
+
+    // Allow packages sharing a `links` key through `[links-overrides]`
+    (unstable, links_overrides, "", "reference/unstable.html#links-overrides"),
+
+    // Allow choosing the compiler of a package in a profile
+    (unstable, profile_rustc, "", "reference/unstable.html#profile-rustc-option"),
+
+    // Allow examples to have dev-dependencies of their own
+    (unstable, example_dependencies, "", "reference/unstable.html#example-dependencies"),
+
     // Allow specifying rustflags directly in a profile
     (stable, workspace_inheritance, "1.64", "reference/unstable.html#workspace-inheritance"),
 }
@@ -467,6 +488,9 @@ impl Features {
             ret.add(feature, config, warnings)?;
             ret.activated.push(feature.to_string());
         }
+        // `-Z codegen-backend` allows choosing a backend in the profiles of
+        // the configuration, which aren't tied to a manifest.
+        ret.codegen_backend |= config.cli_unstable().codegen_backend;
         Ok(ret)
     }
 
@@ -667,28 +691,65 @@ unstable_cli_options!(
     #[serde(deserialize_with = "deserialize_build_std")]
     build_std: Option<Vec<String>>  = ("Enable Cargo to compile the standard library itself as part of a crate graph compilation"),
     build_std_features: Option<Vec<String>>  = ("Configure features enabled for the standard library itself when building the standard library"),
+    build_progress: bool = ("Emit `build-progress` messages with `--message-format json`"),
+    cfg_json: bool = ("Provide the target cfg of build scripts as JSON in `CARGO_CFG_JSON`"),
     config_include: bool = ("Enable the `include` key in config files"),
     credential_process: bool = ("Add a config setting to fetch registry authentication tokens by calling an external process"),
     #[serde(deserialize_with = "deserialize_check_cfg")]
     check_cfg: Option<(/*features:*/ bool, /*well_known_names:*/ bool, /*well_known_values:*/ bool, /*output:*/ bool)> = ("Specify scope of compile-time checking of `cfg` names/values"),
+    doc_landing_page: bool = ("Generate a landing page linking to the documented workspace members"),
     doctest_in_workspace: bool = ("Compile doctests with paths relative to the workspace root"),
     doctest_xcompile: bool = ("Compile and run doctests for non-host target using runner config"),
     dual_proc_macros: bool = ("Build proc-macros for both the host and the target"),
     features: Option<Vec<String>>  = (HIDDEN),
+    grouped_output: bool = ("Group the diagnostics of each compiled crate into collapsible sections"),
     jobserver_per_rustc: bool = (HIDDEN),
+    machine_profiles: bool = ("Enable the `[machine-profiles]` config table and the `--machine-profile` flag"),
     minimal_versions: bool = ("Resolve minimal dependency versions instead of maximum"),
     mtime_on_use: bool = ("Configure Cargo to update the mtime of used files"),
     no_index_update: bool = ("Do not update the registry index even if the cache is outdated"),
     panic_abort_tests: bool = ("Enable support to run tests with -Cpanic=abort"),
+    package_default_features: bool = ("Allow `--features <package>(-default)` to disable the default features of one package"),
     host_config: bool = ("Enable the [host] section in the .cargo/config.toml file"),
+    script_output: bool = ("Enable the `build.script-output` config and `cargo report build-script-output`"),
+    check_build_sharing: bool = ("Share the metadata of libraries between `cargo check` and `cargo build`"),
+    build_executor: bool = ("Enable the `build.executor` config, which runs rustc through an external program"),
+    target_layout_v2: bool = ("Use shorter directory names in the target directory, and record its layout"),
+    fine_grained_locking: bool = ("Lock the units of a build instead of the whole profile directory, so more commands can run concurrently"),
+    global_target_dir: bool = ("Allow `build.target-dir = \"global\"` to keep build outputs in a per-user cache directory"),
+    artifact_manifest: bool = ("Write a `.artifacts.json` manifest of the final artifacts into the profile directory after a build"),
+    uplift: bool = ("Allow `build.uplift` to choose which files are hard-linked into the profile directory"),
+    incremental_dir: bool = ("Allow `build.incremental-dir` to keep a separate incremental cache for each git branch"),
+    required_toolchain: bool = ("Verify the active toolchain against `--require-toolchain`, `build.required-rust-version` or the toolchain file"),
+    global_rustc_info_cache: bool = ("Share the cached information about rustc and targets between workspaces in `$CARGO_HOME`"),
+    codegen_backend: bool = ("Allow the `codegen-backend` profile option without the cargo-feature, such as in profiles of the configuration"),
+    build_manifest: bool = ("Allow build scripts to declare their inputs and outputs with `cargo:build-manifest`"),
+    target_spec_path: bool = ("Provide the target specification to build scripts as a JSON file in `CARGO_TARGET_SPEC_PATH`"),
+    sysroot_provider: bool = ("Enable the `sysroot-provider` key in `[target]` tables"),
+    system_deps: bool = ("Probe the system libraries of `[package.metadata.system-deps]` with pkg-config before running build scripts"),
+    show_duplicate_warnings: bool = ("Show every instance of warnings emitted by several units"),
     sparse_registry: bool = ("Support plain-HTTP-based crate registries"),
     registry_auth: bool = ("Authentication for alternative registries"),
     target_applies_to_host: bool = ("Enable the `target-applies-to-host` key in the .cargo/config.toml file"),
+    target_runners: bool = ("Enable runner fallbacks and per-kind runners in `[target]` tables"),
     rustdoc_map: bool = ("Allow passing external documentation mappings to rustdoc"),
     separate_nightlies: bool = (HIDDEN),
     terminal_width: Option<Option<usize>>  = ("Provide a terminal width to rustc for error truncation"),
     publish_timeout: bool = ("Enable the `publish.timeout` key in .cargo/config.toml file"),
+    publish_verify_targets: bool = ("Enable the `publish.verify-targets` key in .cargo/config.toml file"),
+    script: bool = ("Enable `cargo script` to run single-file packages"),
+    semver_check: bool = ("Enable `cargo semver-check` and the check for breaking changes of `cargo publish`"),
+    http_retries: bool = ("Enable `http.retries`, backoff between retries, and retries of rate-limited requests"),
+    resumable_downloads: bool = ("Resume interrupted `.crate` downloads, and enable `http.max-download-rate`"),
+    git_shallow: bool = ("Fetch only the most recent history of git dependencies"),
+    patch_registry_version: bool = ("Allow `[patch]` entries selecting a version of the registry they patch"),
+    source_policy: bool = ("Enable the `[source-policy]` allowlist of registries and git repositories in .cargo/config.toml"),
+    package_epoch: bool = ("Use `package.epoch` or `SOURCE_DATE_EPOCH` as the modification time of the files in a `.crate`"),
+    publish_provenance: bool = ("Attach the `[publish.provenance]` config to published packages"),
+    package_vcs: bool = ("Check Mercurial and Jujutsu checkouts for uncommitted changes and honor `export-ignore` when packaging"),
+    alias_templates: bool = ("Allow aliases with argument placeholders and descriptions in the `[alias]` table"),
     unstable_options: bool = ("Allow the usage of unstable options"),
+    warnings: bool = ("Enable the `--warnings` flag and the `build.warnings` config"),
     skip_rustdoc_fingerprint: bool = (HIDDEN),
     rustdoc_scrape_examples: bool = ("Allows Rustdoc to scrape code examples from reverse-dependencies"),
 );
@@ -920,17 +981,54 @@ impl CliUnstable {
             "named-profiles" => stabilized_warn(k, "1.57", STABILIZED_NAMED_PROFILES),
             "binary-dep-depinfo" => self.binary_dep_depinfo = parse_empty(k, v)?,
             "bindeps" => self.bindeps = parse_empty(k, v)?,
+            "cfg-json" => self.cfg_json = parse_empty(k, v)?,
             "build-std" => {
                 self.build_std = Some(crate::core::compiler::standard_lib::parse_unstable_flag(v))
             }
             "build-std-features" => self.build_std_features = Some(parse_features(v)),
+            "build-progress" => self.build_progress = parse_empty(k, v)?,
             "doctest-xcompile" => self.doctest_xcompile = parse_empty(k, v)?,
+            "grouped-output" => self.grouped_output = parse_empty(k, v)?,
+            "doc-landing-page" => self.doc_landing_page = parse_empty(k, v)?,
             "doctest-in-workspace" => self.doctest_in_workspace = parse_empty(k, v)?,
             "panic-abort-tests" => self.panic_abort_tests = parse_empty(k, v)?,
+            "package-default-features" => self.package_default_features = parse_empty(k, v)?,
             "jobserver-per-rustc" => self.jobserver_per_rustc = parse_empty(k, v)?,
+            "machine-profiles" => self.machine_profiles = parse_empty(k, v)?,
             "host-config" => self.host_config = parse_empty(k, v)?,
+            "warnings" => self.warnings = parse_empty(k, v)?,
+            "script-output" => self.script_output = parse_empty(k, v)?,
+            "build-manifest" => self.build_manifest = parse_empty(k, v)?,
+            "check-build-sharing" => self.check_build_sharing = parse_empty(k, v)?,
+            "build-executor" => self.build_executor = parse_empty(k, v)?,
+            "target-layout-v2" => self.target_layout_v2 = parse_empty(k, v)?,
+            "global-target-dir" => self.global_target_dir = parse_empty(k, v)?,
+            "fine-grained-locking" => self.fine_grained_locking = parse_empty(k, v)?,
+            "artifact-manifest" => self.artifact_manifest = parse_empty(k, v)?,
+            "uplift" => self.uplift = parse_empty(k, v)?,
+            "incremental-dir" => self.incremental_dir = parse_empty(k, v)?,
+            "required-toolchain" => self.required_toolchain = parse_empty(k, v)?,
+            "global-rustc-info-cache" => self.global_rustc_info_cache = parse_empty(k, v)?,
+            "codegen-backend" => self.codegen_backend = parse_empty(k, v)?,
+            "target-spec-path" => self.target_spec_path = parse_empty(k, v)?,
+            "system-deps" => self.system_deps = parse_empty(k, v)?,
+            "sysroot-provider" => self.sysroot_provider = parse_empty(k, v)?,
+            "show-duplicate-warnings" => self.show_duplicate_warnings = parse_empty(k, v)?,
             "target-applies-to-host" => self.target_applies_to_host = parse_empty(k, v)?,
+            "target-runners" => self.target_runners = parse_empty(k, v)?,
             "publish-timeout" => self.publish_timeout = parse_empty(k, v)?,
+            "publish-verify-targets" => self.publish_verify_targets = parse_empty(k, v)?,
+            "script" => self.script = parse_empty(k, v)?,
+            "semver-check" => self.semver_check = parse_empty(k, v)?,
+            "alias-templates" => self.alias_templates = parse_empty(k, v)?,
+            "http-retries" => self.http_retries = parse_empty(k, v)?,
+            "resumable-downloads" => self.resumable_downloads = parse_empty(k, v)?,
+            "git-shallow" => self.git_shallow = parse_empty(k, v)?,
+            "patch-registry-version" => self.patch_registry_version = parse_empty(k, v)?,
+            "source-policy" => self.source_policy = parse_empty(k, v)?,
+            "package-epoch" => self.package_epoch = parse_empty(k, v)?,
+            "publish-provenance" => self.publish_provenance = parse_empty(k, v)?,
+            "package-vcs" => self.package_vcs = parse_empty(k, v)?,
             "features" => {
                 // `-Z features` has been stabilized since 1.51,
                 // but `-Z features=compare` is still allowed for convenience
@@ -985,19 +1083,23 @@ impl CliUnstable {
 
     /// Generates an error if `-Z unstable-options` was not used for a new,
     /// unstable command-line flag.
-    pub fn fail_if_stable_opt(&self, flag: &str, issue: u32) -> CargoResult<()> {
+    ///
+    /// `issue` is the tracking issue of the flag, if it has one.
+    pub fn fail_if_stable_opt(&self, flag: &str, issue: impl Into<Option<u32>>) -> CargoResult<()> {
         if !self.unstable_options {
-            let see = format!(
-                "See https://github.com/rust-lang/cargo/issues/{} for more \
-                 information about the `{}` flag.",
-                issue, flag
-            );
+            let see = match issue.into() {
+                Some(issue) => format!(
+                    "\nSee https://github.com/rust-lang/cargo/issues/{} for more \
+                     information about the `{}` flag.",
+                    issue, flag
+                ),
+                None => String::new(),
+            };
             // NOTE: a `config` isn't available here, check the channel directly
             let channel = channel();
             if channel == "nightly" || channel == "dev" {
                 bail!(
-                    "the `{}` flag is unstable, pass `-Z unstable-options` to enable it\n\
-                     {}",
+                    "the `{}` flag is unstable, pass `-Z unstable-options` to enable it{}",
                     flag,
                     see
                 );
@@ -1005,8 +1107,7 @@ impl CliUnstable {
                 bail!(
                     "the `{}` flag is unstable, and only available on the nightly channel \
                      of Cargo, but this is the `{}` channel\n\
-                     {}\n\
-                     {}",
+                     {}{}",
                     flag,
                     channel,
                     SEE_CHANNELS,
@@ -1019,24 +1120,28 @@ impl CliUnstable {
 
     /// Generates an error if `-Z unstable-options` was not used for a new,
     /// unstable subcommand.
+    ///
+    /// `issue` is the tracking issue of the command, if it has one.
     pub fn fail_if_stable_command(
         &self,
         config: &Config,
         command: &str,
-        issue: u32,
+        issue: impl Into<Option<u32>>,
     ) -> CargoResult<()> {
         if self.unstable_options {
             return Ok(());
         }
-        let see = format!(
-            "See https://github.com/rust-lang/cargo/issues/{} for more \
-            information about the `cargo {}` command.",
-            issue, command
-        );
+        let see = match issue.into() {
+            Some(issue) => format!(
+                "\nSee https://github.com/rust-lang/cargo/issues/{} for more \
+                information about the `cargo {}` command.",
+                issue, command
+            ),
+            None => String::new(),
+        };
         if config.nightly_features_allowed {
             bail!(
-                "the `cargo {}` command is unstable, pass `-Z unstable-options` to enable it\n\
-                 {}",
+                "the `cargo {}` command is unstable, pass `-Z unstable-options` to enable it{}",
                 command,
                 see
             );
@@ -1044,8 +1149,7 @@ impl CliUnstable {
             bail!(
                 "the `cargo {}` command is unstable, and only available on the \
                  nightly channel of Cargo, but this is the `{}` channel\n\
-                 {}\n\
-                 {}",
+                 {}{}",
                 command,
                 channel(),
                 SEE_CHANNELS,
@@ -13,7 +13,7 @@ use toml_edit::easy as toml;
 use url::Url;
 
 use crate::core::compiler::rustdoc::RustdocScrapeExamples;
-use crate::core::compiler::{CompileKind, CrateType};
+use crate::core::compiler::{BuildOutput, CompileKind, CrateType};
 use crate::core::resolver::ResolveBehavior;
 use crate::core::{Dependency, PackageId, PackageIdSpec, SourceId, Summary};
 use crate::core::{Edition, Feature, Features, WorkspaceConfig};
@@ -22,6 +22,17 @@ use crate::util::interning::InternedString;
 use crate::util::toml::{TomlManifest, TomlProfiles};
 use crate::util::{short_hash, Config, Filesystem};
 
+/// An entry of the `[links-overrides]` table of the workspace root, which
+/// allows several packages to use the same `links` key.
+#[derive(Clone, Debug)]
+pub enum LinksOverride {
+    /// Only the build script of the matching package runs, the build scripts
+    /// of the other packages with the `links` key are skipped.
+    Package(PackageIdSpec),
+    /// None of the build scripts run, this output is used for all of them.
+    Output(BuildOutput),
+}
+
 pub enum EitherManifest {
     Real(Manifest),
     Virtual(VirtualManifest),
@@ -64,6 +75,8 @@ pub struct Manifest {
     default_run: Option<String>,
     metabuild: Option<Vec<String>>,
     resolve_behavior: Option<ResolveBehavior>,
+    check_cfgs: Vec<String>,
+    links_overrides: BTreeMap<String, LinksOverride>,
 }
 
 /// When parsing `Cargo.toml`, some warnings should silenced
@@ -87,6 +100,7 @@ pub struct VirtualManifest {
     warnings: Warnings,
     features: Features,
     resolve_behavior: Option<ResolveBehavior>,
+    links_overrides: BTreeMap<String, LinksOverride>,
 }
 
 /// General metadata about a package which is just blindly uploaded to the
@@ -213,6 +227,8 @@ struct TargetInner {
     // compiler is handled elsewhere.
     src_path: TargetSourcePath,
     required_features: Option<Vec<String>>,
+    // Note that `scoped_dependencies` is used for the cargo-feature `example_dependencies`
+    scoped_dependencies: Vec<String>,
     tested: bool,
     benched: bool,
     doc: bool,
@@ -278,6 +294,9 @@ struct SerializedTarget<'a> {
     edition: &'a str,
     #[serde(rename = "required-features", skip_serializing_if = "Option::is_none")]
     required_features: Option<Vec<&'a str>>,
+    /// The dev-dependencies only available to this example.
+    #[serde(rename = "dependencies", skip_serializing_if = "<[_]>::is_empty")]
+    scoped_dependencies: &'a [String],
     /// Whether docs should be built for the target via `cargo doc`
     /// See <https://doc.rust-lang.org/cargo/commands/cargo-doc.html#target-selection>
     doc: bool,
@@ -303,6 +322,7 @@ impl ser::Serialize for Target {
             required_features: self
                 .required_features()
                 .map(|rf| rf.iter().map(|s| s.as_str()).collect()),
+            scoped_dependencies: self.scoped_dependencies(),
             doc: self.documented(),
             doctest: self.doctested() && self.doctestable(),
             test: self.tested(),
@@ -367,6 +387,7 @@ compact_debug! {
                 bin_name
                 src_path
                 required_features
+                scoped_dependencies
                 tested
                 benched
                 doc
@@ -405,6 +426,8 @@ impl Manifest {
         original: Rc<TomlManifest>,
         metabuild: Option<Vec<String>>,
         resolve_behavior: Option<ResolveBehavior>,
+        check_cfgs: Vec<String>,
+        links_overrides: BTreeMap<String, LinksOverride>,
     ) -> Manifest {
         Manifest {
             summary,
@@ -430,6 +453,8 @@ impl Manifest {
             default_run,
             metabuild,
             resolve_behavior,
+            check_cfgs,
+            links_overrides,
         }
     }
 
@@ -568,6 +593,15 @@ impl Manifest {
         self.default_run.as_deref()
     }
 
+    /// The `--check-cfg` specs declared in `[package.check-cfg]`.
+    pub fn check_cfgs(&self) -> &[String] {
+        &self.check_cfgs
+    }
+
+    pub fn links_overrides(&self) -> &BTreeMap<String, LinksOverride> {
+        &self.links_overrides
+    }
+
     pub fn metabuild(&self) -> Option<&Vec<String>> {
         self.metabuild.as_ref()
     }
@@ -589,6 +623,7 @@ impl VirtualManifest {
         profiles: Option<TomlProfiles>,
         features: Features,
         resolve_behavior: Option<ResolveBehavior>,
+        links_overrides: BTreeMap<String, LinksOverride>,
     ) -> VirtualManifest {
         VirtualManifest {
             replace,
@@ -598,6 +633,7 @@ impl VirtualManifest {
             warnings: Warnings::new(),
             features,
             resolve_behavior,
+            links_overrides,
         }
     }
 
@@ -609,6 +645,10 @@ impl VirtualManifest {
         &self.patch
     }
 
+    pub fn links_overrides(&self) -> &BTreeMap<String, LinksOverride> {
+        &self.links_overrides
+    }
+
     pub fn workspace_config(&self) -> &WorkspaceConfig {
         &self.workspace
     }
@@ -646,6 +686,7 @@ impl Target {
                 bin_name: None,
                 src_path,
                 required_features: None,
+                scoped_dependencies: Vec::new(),
                 doc: false,
                 doctest: false,
                 harness: true,
@@ -788,6 +829,11 @@ impl Target {
     pub fn required_features(&self) -> Option<&Vec<String>> {
         self.inner.required_features.as_ref()
     }
+    /// The names of the dev-dependencies of the package only available to
+    /// this target, set with the `dependencies` field of an example.
+    pub fn scoped_dependencies(&self) -> &[String] {
+        &self.inner.scoped_dependencies
+    }
     pub fn kind(&self) -> &TargetKind {
         &self.inner.kind
     }
@@ -958,6 +1004,10 @@ impl Target {
         Arc::make_mut(&mut self.inner).required_features = required_features;
         self
     }
+    pub fn set_scoped_dependencies(&mut self, scoped_dependencies: Vec<String>) -> &mut Target {
+        Arc::make_mut(&mut self.inner).scoped_dependencies = scoped_dependencies;
+        self
+    }
     pub fn binary_filename(&self) -> Option<String> {
         self.inner.bin_name.clone()
     }
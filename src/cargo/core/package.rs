@@ -2,7 +2,9 @@ use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
+use std::fs::{File, OpenOptions};
 use std::hash;
+use std::io::Write;
 use std::mem;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
@@ -10,6 +12,7 @@ use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use bytesize::ByteSize;
+use cargo_util::paths;
 use curl::easy::{Easy, HttpVersion};
 use curl::multi::{EasyHandle, Multi};
 use lazycell::LazyCell;
@@ -18,6 +21,7 @@ use semver::Version;
 use serde::Serialize;
 use toml_edit::easy as toml;
 
+use crate::core::compiler::artifact::match_artifacts_kind_with_targets;
 use crate::core::compiler::{CompileKind, RustcTargetData};
 use crate::core::dependency::DepKind;
 use crate::core::resolver::features::ForceAllTargets;
@@ -29,7 +33,9 @@ use crate::ops;
 use crate::util::config::PackageCacheLock;
 use crate::util::errors::{CargoResult, HttpNotSuccessful};
 use crate::util::interning::InternedString;
-use crate::util::network::Retry;
+use crate::util::network::retry::parse_retry_after;
+use crate::util::network::sleep::SleepTracker;
+use crate::util::network::{Retry, RetryResult};
 use crate::util::{self, internal, Config, Progress, ProgressStyle};
 
 pub const MANIFEST_PREAMBLE: &str = "\
@@ -319,8 +325,10 @@ pub struct Downloads<'a, 'cfg> {
     /// finished.
     pending: HashMap<usize, (Download<'cfg>, EasyHandle)>,
     /// Set of packages currently being downloaded. This should stay in sync
-    /// with `pending`.
+    /// with `pending` and `sleeping`.
     pending_ids: HashSet<PackageId>,
+    /// Downloads that have failed and are waiting to be retried.
+    sleeping: SleepTracker<(Download<'cfg>, Easy)>,
     /// The final result of each download. A pair `(token, result)`. This is a
     /// temporary holding area, needed because curl can report multiple
     /// downloads at once, but the main loop (`wait`) is written to only
@@ -378,6 +386,21 @@ struct Download<'cfg> {
     /// Actual downloaded data, updated throughout the lifetime of this download.
     data: RefCell<Vec<u8>>,
 
+    /// The delay requested by the `Retry-After` header of the response.
+    retry_after: Cell<Option<Duration>>,
+
+    /// The status code of the current response, set once its status line has
+    /// been received.
+    status: Cell<u32>,
+
+    /// The file the data is persisted to while it's being received, with
+    /// `-Zresumable-downloads`.
+    partial: Option<PartialDownload>,
+
+    /// The value of the `Authorization` header, kept to rebuild the headers
+    /// of a resumed request.
+    authorization: Option<String>,
+
     /// The URL that we're downloading from, cached here for error messages and
     /// reenqueuing.
     url: String,
@@ -397,6 +420,15 @@ struct Download<'cfg> {
     retry: Retry<'cfg>,
 }
 
+/// The `.partial` file of a download which can be resumed.
+struct PartialDownload {
+    path: PathBuf,
+    file: RefCell<File>,
+    /// The number of bytes which were already in the file when the current
+    /// request was sent, and which it asked the server to skip.
+    resume_from: Cell<u64>,
+}
+
 impl<'cfg> PackageSet<'cfg> {
     pub fn new(
         package_ids: &[PackageId],
@@ -444,6 +476,7 @@ impl<'cfg> PackageSet<'cfg> {
             next: 0,
             pending: HashMap::new(),
             pending_ids: HashSet::new(),
+            sleeping: SleepTracker::new(),
             results: Vec::new(),
             progress: RefCell::new(Some(Progress::with_style(
                 "Downloading",
@@ -492,6 +525,28 @@ impl<'cfg> PackageSet<'cfg> {
         target_data: &RustcTargetData<'cfg>,
         force_all_targets: ForceAllTargets,
     ) -> CargoResult<()> {
+        let to_download = PackageSet::accessible_package_ids(
+            resolve,
+            root_ids,
+            has_dev_units,
+            requested_kinds,
+            target_data,
+            force_all_targets,
+        )?;
+        self.get_many(to_download.into_iter())?;
+        Ok(())
+    }
+
+    /// Returns the packages reachable from `root_ids` with the given filters,
+    /// including the roots themselves.
+    fn accessible_package_ids(
+        resolve: &Resolve,
+        root_ids: &[PackageId],
+        has_dev_units: HasDevUnits,
+        requested_kinds: &[CompileKind],
+        target_data: &RustcTargetData<'_>,
+        force_all_targets: ForceAllTargets,
+    ) -> CargoResult<BTreeSet<PackageId>> {
         fn collect_used_deps(
             used: &mut BTreeSet<PackageId>,
             resolve: &Resolve,
@@ -529,11 +584,11 @@ impl<'cfg> PackageSet<'cfg> {
         // This is sorted by PackageId to get consistent behavior and error
         // messages for Cargo's testsuite. Perhaps there is a better ordering
         // that optimizes download time?
-        let mut to_download = BTreeSet::new();
+        let mut used = BTreeSet::new();
 
         for id in root_ids {
             collect_used_deps(
-                &mut to_download,
+                &mut used,
                 resolve,
                 *id,
                 has_dev_units,
@@ -542,7 +597,51 @@ impl<'cfg> PackageSet<'cfg> {
                 force_all_targets,
             )?;
         }
-        self.get_many(to_download.into_iter())?;
+        Ok(used)
+    }
+
+    /// Checks that the packages of artifact dependencies provide the
+    /// requested artifacts, so a missing binary is reported right after
+    /// resolution instead of while building the unit graph.
+    ///
+    /// All accessible packages must have been downloaded already.
+    pub(crate) fn check_artifact_dependencies(
+        &self,
+        resolve: &Resolve,
+        root_ids: &[PackageId],
+        has_dev_units: HasDevUnits,
+        requested_kinds: &[CompileKind],
+        target_data: &RustcTargetData<'_>,
+        force_all_targets: ForceAllTargets,
+    ) -> CargoResult<()> {
+        let used = PackageSet::accessible_package_ids(
+            resolve,
+            root_ids,
+            has_dev_units,
+            requested_kinds,
+            target_data,
+            force_all_targets,
+        )?;
+        for pkg_id in used {
+            let deps = PackageSet::filter_deps(
+                pkg_id,
+                resolve,
+                has_dev_units,
+                requested_kinds,
+                target_data,
+                force_all_targets,
+            );
+            for (dep_id, deps) in deps {
+                for dep in deps.iter().filter(|dep| dep.artifact().is_some()) {
+                    let dep_pkg = self.get_one(dep_id)?;
+                    match_artifacts_kind_with_targets(
+                        dep,
+                        dep_pkg.targets(),
+                        pkg_id.name().as_str(),
+                    )?;
+                }
+            }
+        }
         Ok(())
     }
 
@@ -703,7 +802,7 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
         let pkg = source
             .download(id)
             .with_context(|| "unable to get packages from source")?;
-        let (url, descriptor, authorization) = match pkg {
+        let (url, descriptor, authorization, partial) = match pkg {
             MaybePackage::Ready(pkg) => {
                 debug!("{} doesn't need a download", id);
                 assert!(slot.fill(pkg).is_ok());
@@ -713,7 +812,8 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
                 url,
                 descriptor,
                 authorization,
-            } => (url, descriptor, authorization),
+                partial,
+            } => (url, descriptor, authorization, partial),
         };
 
         // Ok we're going to download this crate, so let's set up all our
@@ -730,11 +830,31 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
         handle.url(&url)?;
         handle.follow_location(true)?; // follow redirects
 
-        // Add authorization header.
-        if let Some(authorization) = authorization {
-            let mut headers = curl::easy::List::new();
-            headers.append(&format!("Authorization: {}", authorization))?;
-            handle.http_headers(headers)?;
+        let partial = match partial {
+            Some(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .read(true)
+                    .append(true)
+                    .open(&path)
+                    .with_context(|| format!("failed to open `{}`", path.display()))?;
+                let resume_from = file.metadata()?.len();
+                if resume_from > 0 {
+                    debug!("resuming download of {} from byte {}", id, resume_from);
+                }
+                Some(PartialDownload {
+                    path,
+                    file: RefCell::new(file),
+                    resume_from: Cell::new(resume_from),
+                })
+            }
+            None => None,
+        };
+
+        // Add authorization and range headers.
+        let resume_from = partial.as_ref().map_or(0, |p| p.resume_from.get());
+        if authorization.is_some() || resume_from > 0 {
+            handle.http_headers(request_headers(authorization.as_deref(), resume_from)?)?;
         }
 
         // Enable HTTP/2 to be used as it'll allow true multiplexing which makes
@@ -766,16 +886,60 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
 
         handle.write_function(move |buf| {
             debug!("{} - {} bytes of data", token, buf.len());
-            tls::with(|downloads| {
+            let written = tls::with(|downloads| {
                 if let Some(downloads) = downloads {
-                    downloads.pending[&token]
-                        .0
-                        .data
-                        .borrow_mut()
-                        .extend_from_slice(buf);
+                    let dl = &downloads.pending[&token].0;
+                    dl.data.borrow_mut().extend_from_slice(buf);
+                    // Only the body of a successful response is part of the
+                    // `.crate` file.
+                    if let (Some(partial), 200 | 206) = (&dl.partial, dl.status.get()) {
+                        if let Err(e) = partial.file.borrow_mut().write_all(buf) {
+                            debug!("failed to write `{}`: {}", partial.path.display(), e);
+                            return 0;
+                        }
+                    }
                 }
+                buf.len()
             });
-            Ok(buf.len())
+            Ok(written)
+        })?;
+
+        handle.header_function(move |buf| {
+            let header = String::from_utf8_lossy(buf);
+            if let Some(status) = header.strip_prefix("HTTP/") {
+                let code = status
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|code| code.parse().ok())
+                    .unwrap_or(0);
+                return tls::with(|downloads| {
+                    let dl = match downloads {
+                        Some(downloads) => &downloads.pending[&token].0,
+                        None => return true,
+                    };
+                    dl.status.set(code);
+                    // The server ignored the `Range` header, and sends the
+                    // whole file again.
+                    match &dl.partial {
+                        Some(partial) if code == 200 && partial.resume_from.get() > 0 => {
+                            partial.resume_from.set(0);
+                            partial.file.borrow().set_len(0).is_ok()
+                        }
+                        _ => true,
+                    }
+                });
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("retry-after") {
+                    tls::with(|downloads| {
+                        if let Some(downloads) = downloads {
+                            let dl = &downloads.pending[&token].0;
+                            dl.retry_after.set(parse_retry_after(value));
+                        }
+                    });
+                }
+            }
+            true
         })?;
 
         handle.progress(true)?;
@@ -802,6 +966,10 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
         let dl = Download {
             token,
             data: RefCell::new(Vec::new()),
+            retry_after: Cell::new(None),
+            status: Cell::new(0),
+            partial,
+            authorization,
             id,
             url,
             descriptor,
@@ -819,7 +987,7 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
 
     /// Returns the number of crates that are still downloading.
     pub fn remaining(&self) -> usize {
-        self.pending.len()
+        self.pending.len() + self.sleeping.len()
     }
 
     /// Blocks the current thread waiting for a package to finish downloading.
@@ -831,8 +999,8 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
     ///
     /// This function will panic if there are no remaining downloads.
     pub fn wait(&mut self) -> CargoResult<&'a Package> {
-        let (dl, data) = loop {
-            assert_eq!(self.pending.len(), self.pending_ids.len());
+        let (mut dl, mut data) = loop {
+            assert_eq!(self.remaining(), self.pending_ids.len());
             let (token, result) = self.wait_for_curl()?;
             debug!("{} finished with {:?}", token, result);
 
@@ -849,56 +1017,82 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
             // then we wait for another request to finish.
             let ret = {
                 let timed_out = &dl.timed_out;
+                let retry_after = dl.retry_after.take();
+                let partial = &dl.partial;
                 let url = &dl.url;
-                dl.retry
-                    .r#try(|| {
-                        if let Err(e) = result {
-                            // If this error is "aborted by callback" then that's
-                            // probably because our progress callback aborted due to
-                            // a timeout. We'll find out by looking at the
-                            // `timed_out` field, looking for a descriptive message.
-                            // If one is found we switch the error code (to ensure
-                            // it's flagged as spurious) and then attach our extra
-                            // information to the error.
-                            if !e.is_aborted_by_callback() {
-                                return Err(e.into());
-                            }
+                dl.retry.r#try(|| {
+                    if let Err(e) = result {
+                        // If this error is "aborted by callback" then that's
+                        // probably because our progress callback aborted due to
+                        // a timeout. We'll find out by looking at the
+                        // `timed_out` field, looking for a descriptive message.
+                        // If one is found we switch the error code (to ensure
+                        // it's flagged as spurious) and then attach our extra
+                        // information to the error.
+                        if !e.is_aborted_by_callback() {
+                            return Err(e.into());
+                        }
 
-                            return Err(match timed_out.replace(None) {
-                                Some(msg) => {
-                                    let code = curl_sys::CURLE_OPERATION_TIMEDOUT;
-                                    let mut err = curl::Error::new(code);
-                                    err.set_extra(msg);
-                                    err
-                                }
-                                None => e,
+                        return Err(match timed_out.replace(None) {
+                            Some(msg) => {
+                                let code = curl_sys::CURLE_OPERATION_TIMEDOUT;
+                                let mut err = curl::Error::new(code);
+                                err.set_extra(msg);
+                                err
                             }
-                            .into());
+                            None => e,
                         }
+                        .into());
+                    }
 
-                        let code = handle.response_code()?;
-                        if code != 200 && code != 0 {
-                            let url = handle.effective_url()?.unwrap_or(url);
-                            return Err(HttpNotSuccessful {
-                                code,
-                                url: url.to_string(),
-                                body: data,
-                            }
-                            .into());
+                    let code = handle.response_code()?;
+                    if !matches!(code, 0 | 200 | 206) {
+                        // The partial file doesn't match the file on the
+                        // server anymore, the next attempt starts over.
+                        if let (416, Some(partial)) = (code, partial) {
+                            partial.file.borrow().set_len(0)?;
                         }
-                        Ok(data)
-                    })
-                    .with_context(|| format!("failed to download from `{}`", dl.url))?
+                        let url = handle.effective_url()?.unwrap_or(url);
+                        return Err(HttpNotSuccessful {
+                            code,
+                            url: url.to_string(),
+                            body: data,
+                            retry_after,
+                        }
+                        .into());
+                    }
+                    Ok(data)
+                })
             };
             match ret {
-                Some(data) => break (dl, data),
-                None => {
+                RetryResult::Success(data) => break (dl, data),
+                RetryResult::Err(e) => {
+                    return Err(e.context(format!("failed to download from `{}`", dl.url)))
+                }
+                RetryResult::Retry(sleep) => {
+                    debug!("download retry {} for {sleep:?}", dl.url);
+                    if let Some(partial) = &dl.partial {
+                        // Continue after the data received by this attempt.
+                        let resume_from = partial.file.borrow().metadata()?.len();
+                        partial.resume_from.set(resume_from);
+                        handle.http_headers(request_headers(
+                            dl.authorization.as_deref(),
+                            resume_from,
+                        )?)?;
+                    }
                     self.pending_ids.insert(dl.id);
-                    self.enqueue(dl, handle)?
+                    self.sleeping.push(sleep, (dl, handle));
                 }
             }
         };
 
+        // The partial file holds the data received by earlier attempts as well.
+        if let Some(PartialDownload { path, file, .. }) = dl.partial.take() {
+            drop(file);
+            data = paths::read_bytes(&path)?;
+            paths::remove_file(&path)?;
+        }
+
         // If the progress bar isn't enabled then we still want to provide some
         // semblance of progress of how we're downloading crates, and if the
         // progress bar is enabled this provides a good log of what's happening.
@@ -957,6 +1151,7 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
         self.next_speed_check_bytes_threshold
             .set(u64::from(self.timeout.low_speed_limit));
         dl.timed_out.set(None);
+        dl.status.set(0);
         dl.current.set(0);
         dl.total.set(0);
         self.pending.insert(dl.token, (dl, handle));
@@ -982,6 +1177,7 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
         // actually block waiting for I/O to happen, which we achieve with the
         // `wait` method on `multi`.
         loop {
+            self.add_sleepers()?;
             let n = tls::set(self, || {
                 self.set
                     .multi
@@ -1004,17 +1200,31 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
             if let Some(pair) = results.pop() {
                 break Ok(pair);
             }
-            assert!(!self.pending.is_empty());
-            let min_timeout = Duration::new(1, 0);
-            let timeout = self.set.multi.get_timeout()?.unwrap_or(min_timeout);
-            let timeout = timeout.min(min_timeout);
-            self.set
-                .multi
-                .wait(&mut [], timeout)
-                .with_context(|| "failed to wait on curl `Multi`")?;
+            assert_ne!(self.remaining(), 0);
+            if self.pending.is_empty() {
+                let delay = self.sleeping.time_to_next().unwrap();
+                debug!("sleeping main thread for {delay:?}");
+                std::thread::sleep(delay);
+            } else {
+                let min_timeout = Duration::new(1, 0);
+                let timeout = self.set.multi.get_timeout()?.unwrap_or(min_timeout);
+                let timeout = timeout.min(min_timeout);
+                self.set
+                    .multi
+                    .wait(&mut [], timeout)
+                    .with_context(|| "failed to wait on curl `Multi`")?;
+            }
         }
     }
 
+    /// Restarts the downloads whose retry delay has elapsed.
+    fn add_sleepers(&mut self) -> CargoResult<()> {
+        for (dl, handle) in self.sleeping.to_retry() {
+            self.enqueue(dl, handle)?;
+        }
+        Ok(())
+    }
+
     fn progress(&self, token: usize, total: u64, cur: u64) -> bool {
         let dl = &self.pending[&token].0;
         dl.total.set(total);
@@ -1111,6 +1321,19 @@ impl<'a, 'cfg> Downloads<'a, 'cfg> {
     }
 }
 
+/// The headers of a download request, which resumes a partial download with
+/// a `Range` header.
+fn request_headers(authorization: Option<&str>, resume_from: u64) -> CargoResult<curl::easy::List> {
+    let mut headers = curl::easy::List::new();
+    if let Some(authorization) = authorization {
+        headers.append(&format!("Authorization: {}", authorization))?;
+    }
+    if resume_from > 0 {
+        headers.append(&format!("Range: bytes={}-", resume_from))?;
+    }
+    Ok(headers)
+}
+
 #[derive(Copy, Clone)]
 enum WhyTick<'a> {
     DownloadStarted,
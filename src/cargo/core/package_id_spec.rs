@@ -6,11 +6,12 @@ use semver::Version;
 use serde::{de, ser};
 use url::Url;
 
-use crate::core::PackageId;
+use crate::core::dependency::{ArtifactKind, DepKind};
+use crate::core::{Dependency, PackageId};
 use crate::util::errors::CargoResult;
 use crate::util::interning::InternedString;
 use crate::util::lev_distance;
-use crate::util::{validate_package_name, IntoUrl, ToSemver};
+use crate::util::{validate_package_name, Config, IntoUrl, ToSemver};
 
 /// Some or all of the data required to identify a package:
 ///
@@ -26,6 +27,20 @@ pub struct PackageIdSpec {
     name: InternedString,
     version: Option<Version>,
     url: Option<Url>,
+    qualifiers: Option<SpecQualifiers>,
+}
+
+/// Qualifiers at the end of a `PackageIdSpec`, like `#dep-kind=build` or
+/// `#artifact=bin`, which select the packages that are depended on in a
+/// particular way.
+///
+/// They disambiguate between several versions of a package in the dependency
+/// graph, for example an artifact dependency on a newer version of a library
+/// which is also a normal dependency.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Default)]
+pub struct SpecQualifiers {
+    dep_kind: Option<DepKind>,
+    artifact: Option<ArtifactKind>,
 }
 
 impl PackageIdSpec {
@@ -78,9 +93,42 @@ impl PackageIdSpec {
             name: InternedString::new(name),
             version,
             url: None,
+            qualifiers: None,
         })
     }
 
+    /// Parses a spec string like [`PackageIdSpec::parse`], which may end in
+    /// [`SpecQualifiers`], like `foo@1.2.3#dep-kind=build,artifact=bin`.
+    ///
+    /// Qualifiers are only honored when looking packages up in a resolve
+    /// graph with [`Resolve::query_spec`], and require `-Z unstable-options`.
+    ///
+    /// [`Resolve::query_spec`]: crate::core::Resolve::query_spec
+    pub fn parse_qualified(spec: &str, config: &Config) -> CargoResult<PackageIdSpec> {
+        let parsed = PackageIdSpec::parse_with_qualifiers(spec)?;
+        if parsed.qualifiers.is_some() && !config.cli_unstable().unstable_options {
+            bail!(
+                "qualifiers in package ID specifications are unstable, \
+                 pass `-Z unstable-options` to enable them"
+            );
+        }
+        Ok(parsed)
+    }
+
+    /// Checks whether a spec string ends in qualifiers, without validating it.
+    pub fn is_qualified(spec: &str) -> bool {
+        split_qualifiers(spec).1.is_some()
+    }
+
+    fn parse_with_qualifiers(spec: &str) -> CargoResult<PackageIdSpec> {
+        let (base, qualifiers) = split_qualifiers(spec);
+        let mut parsed = PackageIdSpec::parse(base)?;
+        if let Some(qualifiers) = qualifiers {
+            parsed.qualifiers = Some(SpecQualifiers::parse(qualifiers)?);
+        }
+        Ok(parsed)
+    }
+
     /// Roughly equivalent to `PackageIdSpec::parse(spec)?.query(i)`
     pub fn query_str<I>(spec: &str, i: I) -> CargoResult<PackageId>
     where
@@ -101,6 +149,7 @@ impl PackageIdSpec {
             name: package_id.name(),
             version: Some(package_id.version().clone()),
             url: Some(package_id.source_id().url().clone()),
+            qualifiers: None,
         }
     }
 
@@ -148,6 +197,7 @@ impl PackageIdSpec {
             name,
             version,
             url: Some(url),
+            qualifiers: None,
         })
     }
 
@@ -167,6 +217,10 @@ impl PackageIdSpec {
         self.url = Some(url);
     }
 
+    pub fn qualifiers(&self) -> Option<&SpecQualifiers> {
+        self.qualifiers.as_ref()
+    }
+
     /// Checks whether the given `PackageId` matches the `PackageIdSpec`.
     pub fn matches(&self, package_id: PackageId) -> bool {
         if self.name() != package_id.name() {
@@ -214,6 +268,7 @@ impl PackageIdSpec {
                             name: self.name,
                             version: self.version.clone(),
                             url: None,
+                            qualifiers: None,
                         },
                         &mut suggestion,
                     );
@@ -224,6 +279,7 @@ impl PackageIdSpec {
                             name: self.name,
                             version: None,
                             url: None,
+                            qualifiers: None,
                         },
                         &mut suggestion,
                     );
@@ -279,6 +335,15 @@ impl PackageIdSpec {
     }
 }
 
+/// Splits the qualifiers off a spec string. URLs have a fragment with the
+/// name and version after a `#` too, but the qualifiers always contain a `=`.
+fn split_qualifiers(spec: &str) -> (&str, Option<&str>) {
+    match spec.rsplit_once('#') {
+        Some((base, qualifiers)) if qualifiers.contains('=') => (base, Some(qualifiers)),
+        _ => (spec, None),
+    }
+}
+
 impl fmt::Display for PackageIdSpec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut printed_name = false;
@@ -298,6 +363,91 @@ impl fmt::Display for PackageIdSpec {
         if let Some(ref v) = self.version {
             write!(f, "{}{}", if printed_name { "@" } else { "#" }, v)?;
         }
+        if let Some(ref qualifiers) = self.qualifiers {
+            write!(f, "#{}", qualifiers)?;
+        }
+        Ok(())
+    }
+}
+
+impl SpecQualifiers {
+    /// Parses a comma-separated list of `key=value` qualifiers.
+    fn parse(qualifiers: &str) -> CargoResult<SpecQualifiers> {
+        let mut ret = SpecQualifiers::default();
+        for qualifier in qualifiers.split(',') {
+            let (key, value) = qualifier.split_once('=').ok_or_else(|| {
+                anyhow::format_err!(
+                    "invalid qualifier `{}`, expected `<key>=<value>`",
+                    qualifier
+                )
+            })?;
+            let duplicate = match key {
+                "dep-kind" => {
+                    let kind = match value {
+                        "normal" => DepKind::Normal,
+                        "dev" => DepKind::Development,
+                        "build" => DepKind::Build,
+                        _ => bail!(
+                            "invalid dependency kind `{}`, \
+                             expected one of `normal`, `dev`, or `build`",
+                            value
+                        ),
+                    };
+                    ret.dep_kind.replace(kind).is_some()
+                }
+                "artifact" => ret.artifact.replace(ArtifactKind::parse(value)?).is_some(),
+                _ => bail!(
+                    "unknown qualifier `{}`, expected `dep-kind` or `artifact`",
+                    key
+                ),
+            };
+            if duplicate {
+                bail!("qualifier `{}` is specified more than once", key);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Checks whether a dependency edge is selected by these qualifiers.
+    pub fn matches(&self, dep: &Dependency) -> bool {
+        if let Some(kind) = self.dep_kind {
+            if dep.kind() != kind {
+                return false;
+            }
+        }
+        let want = match self.artifact {
+            Some(want) => want,
+            None => return true,
+        };
+        let kinds = match dep.artifact() {
+            Some(artifact) => artifact.kinds(),
+            None => return false,
+        };
+        kinds.iter().any(|&kind| match (want, kind) {
+            // `bin` also selects dependencies on single binaries, and
+            // `bin:<name>` dependencies on all of them.
+            (ArtifactKind::AllBinaries, ArtifactKind::SelectedBinary(_))
+            | (ArtifactKind::SelectedBinary(_), ArtifactKind::AllBinaries) => true,
+            (want, kind) => want == kind,
+        })
+    }
+}
+
+impl fmt::Display for SpecQualifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        if let Some(kind) = self.dep_kind {
+            let kind = match kind {
+                DepKind::Normal => "normal",
+                DepKind::Development => "dev",
+                DepKind::Build => "build",
+            };
+            write!(f, "dep-kind={}", kind)?;
+            sep = ",";
+        }
+        if let Some(artifact) = self.artifact {
+            write!(f, "{}artifact={}", sep, artifact)?;
+        }
         Ok(())
     }
 }
@@ -323,7 +473,8 @@ impl<'de> de::Deserialize<'de> for PackageIdSpec {
 
 #[cfg(test)]
 mod tests {
-    use super::PackageIdSpec;
+    use super::{PackageIdSpec, SpecQualifiers};
+    use crate::core::dependency::{ArtifactKind, DepKind};
     use crate::core::{PackageId, SourceId};
     use crate::util::interning::InternedString;
     use crate::util::ToSemver;
@@ -344,6 +495,7 @@ mod tests {
                 name: InternedString::new("foo"),
                 version: None,
                 url: Some(Url::parse("https://crates.io/foo").unwrap()),
+                qualifiers: None,
             },
             "https://crates.io/foo",
         );
@@ -353,6 +505,7 @@ mod tests {
                 name: InternedString::new("foo"),
                 version: Some("1.2.3".to_semver().unwrap()),
                 url: Some(Url::parse("https://crates.io/foo").unwrap()),
+                qualifiers: None,
             },
             "https://crates.io/foo#1.2.3",
         );
@@ -362,6 +515,7 @@ mod tests {
                 name: InternedString::new("bar"),
                 version: Some("1.2.3".to_semver().unwrap()),
                 url: Some(Url::parse("https://crates.io/foo").unwrap()),
+                qualifiers: None,
             },
             "https://crates.io/foo#bar@1.2.3",
         );
@@ -371,6 +525,7 @@ mod tests {
                 name: InternedString::new("bar"),
                 version: Some("1.2.3".to_semver().unwrap()),
                 url: Some(Url::parse("https://crates.io/foo").unwrap()),
+                qualifiers: None,
             },
             "https://crates.io/foo#bar@1.2.3",
         );
@@ -380,6 +535,7 @@ mod tests {
                 name: InternedString::new("foo"),
                 version: None,
                 url: None,
+                qualifiers: None,
             },
             "foo",
         );
@@ -389,6 +545,7 @@ mod tests {
                 name: InternedString::new("foo"),
                 version: Some("1.2.3".to_semver().unwrap()),
                 url: None,
+                qualifiers: None,
             },
             "foo@1.2.3",
         );
@@ -398,11 +555,54 @@ mod tests {
                 name: InternedString::new("foo"),
                 version: Some("1.2.3".to_semver().unwrap()),
                 url: None,
+                qualifiers: None,
             },
             "foo@1.2.3",
         );
     }
 
+    #[test]
+    fn qualified_parsing() {
+        #[track_caller]
+        fn ok(spec: &str, expected: SpecQualifiers, expected_rendered: &str) {
+            let parsed = PackageIdSpec::parse_with_qualifiers(spec).unwrap();
+            assert_eq!(parsed.qualifiers, Some(expected));
+            assert_eq!(parsed.to_string(), expected_rendered);
+        }
+
+        ok(
+            "foo#dep-kind=build",
+            SpecQualifiers {
+                dep_kind: Some(DepKind::Build),
+                artifact: None,
+            },
+            "foo#dep-kind=build",
+        );
+        ok(
+            "foo@1.2.3#artifact=bin:baz,dep-kind=dev",
+            SpecQualifiers {
+                dep_kind: Some(DepKind::Development),
+                artifact: Some(ArtifactKind::SelectedBinary(InternedString::new("baz"))),
+            },
+            "foo@1.2.3#dep-kind=dev,artifact=bin:baz",
+        );
+        ok(
+            "https://crates.io/foo#bar@1.2.3#artifact=cdylib",
+            SpecQualifiers {
+                dep_kind: None,
+                artifact: Some(ArtifactKind::Cdylib),
+            },
+            "https://crates.io/foo#bar@1.2.3#artifact=cdylib",
+        );
+
+        assert!(PackageIdSpec::parse("foo#dep-kind=build").is_err());
+        assert!(PackageIdSpec::parse_with_qualifiers("foo#dep-kind=").is_err());
+        assert!(PackageIdSpec::parse_with_qualifiers("foo#dep-kind=test").is_err());
+        assert!(PackageIdSpec::parse_with_qualifiers("foo#artifact=lib").is_err());
+        assert!(PackageIdSpec::parse_with_qualifiers("foo#kind=build").is_err());
+        assert!(PackageIdSpec::parse_with_qualifiers("foo#dep-kind=dev,dep-kind=build").is_err());
+    }
+
     #[test]
     fn bad_parsing() {
         assert!(PackageIdSpec::parse("baz:").is_err());
@@ -247,6 +247,7 @@ impl Profiles {
             Some(toml) => toml.merge(profile),
             None => maker.toml = Some(profile.clone()),
         };
+        maker.push_package_overrides(profile);
         Ok(maker)
     }
 
@@ -317,9 +318,19 @@ impl Profiles {
         result.root = for_unit_profile.root;
         result.debuginfo = for_unit_profile.debuginfo;
         result.opt_level = for_unit_profile.opt_level;
+        // Build scripts are told about the compiler of the package in `RUSTC`.
+        result.rustc = for_unit_profile.rustc;
         result
     }
 
+    /// Whether the `opt-level` of the build dependency `pkg_id` is set
+    /// explicitly, in `build-override` or a package override, instead of
+    /// defaulting to 0.
+    pub fn sets_build_opt_level(&self, pkg_id: PackageId, is_member: bool) -> bool {
+        let maker = self.get_profile_maker(self.requested_profile).unwrap();
+        maker.sets_build_opt_level(pkg_id, is_member)
+    }
+
     /// This returns the base profile. This is currently used for the
     /// `[Finished]` line. It is not entirely accurate, since it doesn't
     /// select for the package that was actually built.
@@ -344,21 +355,17 @@ impl Profiles {
         shell: &mut Shell,
         resolve: &Resolve,
     ) -> CargoResult<()> {
-        for (name, profile) in &self.by_name {
-            // If the user did not specify an override, skip this. This is here
-            // to avoid generating errors for inherited profiles which don't
-            // specify package overrides. The `by_name` profile has had the inherits
-            // chain merged, so we need to look at the original source to check
-            // if an override was specified.
-            if self
-                .original_profiles
-                .get(name)
-                .and_then(|orig| orig.package.as_ref())
-                .is_none()
-            {
-                continue;
-            }
-            let found = validate_packages_unique(resolve, name, &profile.toml)?;
+        for name in self.by_name.keys() {
+            // Package overrides are applied one profile of the inherits chain
+            // at a time, so only the overrides the user wrote for this profile
+            // need to be unique. A more specific override in an inheriting
+            // profile wins over the one it inherits, even if it is spelled
+            // differently.
+            let original = match self.original_profiles.get(name) {
+                Some(original) if original.package.is_some() => original,
+                _ => continue,
+            };
+            let found = validate_packages_unique(resolve, name, original)?;
             // We intentionally do not validate unmatched packages for config
             // profiles, in case they are defined in a central location. This
             // iterates over the manifest profiles only.
@@ -384,7 +391,10 @@ impl Profiles {
 /// The precedence of profiles are (first one wins):
 ///
 /// - Profiles in `.cargo/config` files (using same order as below).
-/// - `[profile.dev.package.name]` -- a named package.
+/// - `[profile.dev.package.name]` -- a named package. With `inherits`, the
+///   override of the inheriting profile wins over the inherited one.
+/// - `[profile.dev.registry-packages]` -- this only applies to packages from
+///   a registry.
 /// - `[profile.dev.package."*"]` -- this cannot apply to workspace members.
 /// - `[profile.dev.build-override]` -- this can only apply to `build.rs` scripts
 ///   and their dependencies.
@@ -400,6 +410,9 @@ struct ProfileMaker {
     /// `default` is used. Note that the built-in defaults for test/bench/doc
     /// always set this since they need to declare the `inherits` value.
     toml: Option<TomlProfile>,
+    /// The `[profile.*.package.<spec>]` overrides of each profile in the
+    /// inherits chain, starting with the root profile.
+    package_overrides: Vec<BTreeMap<ProfilePackageSpec, TomlProfile>>,
 }
 
 impl ProfileMaker {
@@ -407,7 +420,23 @@ impl ProfileMaker {
     ///
     /// Note that this does not process `inherits`, the caller is responsible for that.
     fn new(default: Profile, toml: Option<TomlProfile>) -> ProfileMaker {
-        ProfileMaker { default, toml }
+        let mut maker = ProfileMaker {
+            default,
+            toml: None,
+            package_overrides: Vec::new(),
+        };
+        if let Some(toml) = toml {
+            maker.push_package_overrides(&toml);
+            maker.toml = Some(toml);
+        }
+        maker
+    }
+
+    /// Adds the package overrides of the next profile in the inherits chain.
+    fn push_package_overrides(&mut self, profile: &TomlProfile) {
+        if let Some(package) = &profile.package {
+            self.package_overrides.push(package.clone());
+        }
     }
 
     /// Generates a new `Profile`.
@@ -443,20 +472,55 @@ impl ProfileMaker {
         // profiles, such as `[profile.release.build-override]` or
         // `[profile.release.package.foo]`
         if let Some(toml) = &self.toml {
-            merge_toml_overrides(pkg_id, is_member, is_for_host, &mut profile, toml);
+            merge_toml_overrides(
+                pkg_id,
+                is_member,
+                is_for_host,
+                &mut profile,
+                toml,
+                &self.package_overrides,
+            );
         }
         profile
     }
+
+    /// See [`Profiles::sets_build_opt_level`].
+    fn sets_build_opt_level(&self, pkg_id: PackageId, is_member: bool) -> bool {
+        let toml = match &self.toml {
+            Some(toml) => toml,
+            None => return false,
+        };
+        // The overrides only touch the `opt-level` if they set it.
+        let mut profile = Profile::default();
+        profile.opt_level = InternedString::new("");
+        merge_toml_overrides(
+            Some(pkg_id),
+            is_member,
+            true,
+            &mut profile,
+            toml,
+            &self.package_overrides,
+        );
+        !profile.opt_level.is_empty()
+    }
 }
 
 /// Merge package and build overrides from the given TOML profile into the given `Profile`.
+///
+/// Package specific overrides are taken from `package_overrides`, one profile
+/// of the inherits chain at a time.
 fn merge_toml_overrides(
     pkg_id: Option<PackageId>,
     is_member: bool,
     is_for_host: bool,
     profile: &mut Profile,
     toml: &TomlProfile,
+    package_overrides: &[BTreeMap<ProfilePackageSpec, TomlProfile>],
 ) {
+    // The `rustflags` of an override are appended to the ones of the profile
+    // itself instead of replacing them, with the most specific override
+    // winning over the others.
+    let profile_rustflags = std::mem::take(&mut profile.rustflags);
     if is_for_host {
         if let Some(build_override) = &toml.build_override {
             merge_profile(profile, build_override);
@@ -468,7 +532,15 @@ fn merge_toml_overrides(
                 merge_profile(profile, all);
             }
         }
-        if let Some(pkg_id) = pkg_id {
+    }
+    if let Some(pkg_id) = pkg_id {
+        if pkg_id.source_id().is_registry() {
+            if let Some(registry_packages) = &toml.registry_packages {
+                merge_profile(profile, registry_packages);
+            }
+        }
+
+        for overrides in package_overrides {
             let mut matches = overrides
                 .iter()
                 .filter_map(|(key, spec_profile)| match *key {
@@ -493,6 +565,8 @@ fn merge_toml_overrides(
             }
         }
     }
+    let override_rustflags = std::mem::replace(&mut profile.rustflags, profile_rustflags);
+    profile.rustflags.extend(override_rustflags);
 }
 
 /// Merge the given TOML profile into the given `Profile`.
@@ -546,6 +620,9 @@ fn merge_profile(profile: &mut Profile, toml: &TomlProfile) {
     if let Some(flags) = &toml.rustflags {
         profile.rustflags = flags.clone();
     }
+    if toml.rustc.is_some() {
+        profile.rustc = toml.rustc;
+    }
     profile.strip = match toml.strip {
         Some(StringOrBool::Bool(true)) => Strip::Named(InternedString::new("symbols")),
         None | Some(StringOrBool::Bool(false)) => Strip::None,
@@ -589,6 +666,9 @@ pub struct Profile {
     #[serde(skip_serializing_if = "Vec::is_empty")] // remove when `rustflags` is stablized
     // Note that `rustflags` is used for the cargo-feature `profile_rustflags`
     pub rustflags: Vec<InternedString>,
+    #[serde(skip_serializing_if = "Option::is_none")] // remove when `rustc` is stablized
+    // Note that `rustc` is used for the cargo-feature `profile_rustc`
+    pub rustc: Option<InternedString>,
 }
 
 impl Default for Profile {
@@ -609,6 +689,7 @@ impl Default for Profile {
             panic: PanicStrategy::Unwind,
             strip: Strip::None,
             rustflags: vec![],
+            rustc: None,
         }
     }
 }
@@ -637,6 +718,7 @@ compact_debug! {
                 panic
                 strip
                 rustflags
+                rustc
             )]
         }
     }
@@ -654,12 +736,22 @@ impl hash::Hash for Profile {
         H: hash::Hasher,
     {
         self.comparable().hash(state);
+        // Only hash `rustflags` when set, so that the metadata hash of every
+        // other unit stays the same.
+        if !self.rustflags.is_empty() {
+            self.rustflags.hash(state);
+        }
+        if let Some(rustc) = self.rustc {
+            rustc.hash(state);
+        }
     }
 }
 
 impl cmp::PartialEq for Profile {
     fn eq(&self, other: &Self) -> bool {
         self.comparable() == other.comparable()
+            && self.rustflags == other.rustflags
+            && self.rustc == other.rustc
     }
 }
 
@@ -1135,12 +1227,8 @@ fn get_config_profile(ws: &Workspace<'_>, name: &str) -> CargoResult<Option<Toml
 fn validate_packages_unique(
     resolve: &Resolve,
     name: &str,
-    toml: &Option<TomlProfile>,
+    toml: &TomlProfile,
 ) -> CargoResult<HashSet<PackageIdSpec>> {
-    let toml = match toml {
-        Some(ref toml) => toml,
-        None => return Ok(HashSet::new()),
-    };
     let overrides = match toml.package.as_ref() {
         Some(overrides) => overrides,
         None => return Ok(HashSet::new()),
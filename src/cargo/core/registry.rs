@@ -2,7 +2,9 @@ use std::collections::{HashMap, HashSet};
 use std::task::{ready, Poll};
 
 use crate::core::PackageSet;
-use crate::core::{Dependency, PackageId, QueryKind, Source, SourceId, SourceMap, Summary};
+use crate::core::{
+    Dependency, GitReference, PackageId, QueryKind, Source, SourceId, SourceMap, Summary,
+};
 use crate::sources::config::SourceConfigMap;
 use crate::util::errors::CargoResult;
 use crate::util::interning::InternedString;
@@ -359,7 +361,24 @@ impl<'cfg> PackageRegistry<'cfg> {
                     unlock_patches.push(((*orig_patch).clone(), unlock_id));
                 }
 
-                if *summary.package_id().source_id().canonical_url() == canonical {
+                // With `-Zpatch-registry-version` a patch can select a
+                // version of the registry it patches, to pin that version.
+                let pins_registry_version = summary.source_id().is_registry()
+                    && self
+                        .source_config
+                        .config()
+                        .cli_unstable()
+                        .patch_registry_version;
+                // With `-Zunstable-options` a git source can be patched with a
+                // `rev` of the same repository.
+                let pins_git_rev = matches!(
+                    summary.package_id().source_id().git_reference(),
+                    Some(GitReference::Rev(_))
+                ) && self.source_config.config().cli_unstable().unstable_options;
+                if *summary.package_id().source_id().canonical_url() == canonical
+                    && !pins_git_rev
+                    && !pins_registry_version
+                {
                     return Err(anyhow::anyhow!(
                         "patch for `{}` in `{}` points to the same source, but \
                         patches must point to different sources",
@@ -601,7 +620,13 @@ impl<'cfg> Registry for PackageRegistry<'cfg> {
             // locked dep basically just means a version constraint of `=a.b.c`,
             // and because patches take priority over the actual source then if
             // we have a candidate we're done.
-            if patches.len() == 1 && dep.is_locked() {
+            //
+            // The same goes for a patch selecting a version of the source it
+            // patches, since its purpose is to hide the other versions.
+            let pins_version = patches
+                .iter()
+                .any(|p| p.source_id().canonical_url() == dep.source_id().canonical_url());
+            if patches.len() == 1 && (dep.is_locked() || pins_version) {
                 let patch = patches.remove(0);
                 match override_summary {
                     Some(summary) => (summary, 1, Some(patch)),
@@ -7,8 +7,9 @@ use crate::util::interning::InternedString;
 use crate::util::Graph;
 use anyhow::format_err;
 use log::debug;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroU64;
+use std::rc::Rc;
 
 pub use super::encode::Metadata;
 pub use super::encode::{EncodableDependency, EncodablePackageId, EncodableResolve};
@@ -26,6 +27,8 @@ pub struct Context {
     pub resolve_features: im_rc::HashMap<PackageId, FeaturesSet>,
     /// get the package that will be linking to a native library by its links attribute
     pub links: im_rc::HashMap<InternedString, PackageId>,
+    /// `links` keys several packages may share, from `[links-overrides]`
+    pub shared_links: Rc<HashSet<InternedString>>,
     /// for each package the list of names it can see,
     /// then for each name the exact version that name represents and whether the name is public.
     pub public_dependency: Option<PublicDependency>,
@@ -78,11 +81,15 @@ impl PackageId {
 }
 
 impl Context {
-    pub fn new(check_public_visible_dependencies: bool) -> Context {
+    pub fn new(
+        check_public_visible_dependencies: bool,
+        shared_links: Rc<HashSet<InternedString>>,
+    ) -> Context {
         Context {
             age: 0,
             resolve_features: im_rc::HashMap::new(),
             links: im_rc::HashMap::new(),
+            shared_links,
             public_dependency: if check_public_visible_dependencies {
                 Some(PublicDependency::new())
             } else {
@@ -118,7 +125,7 @@ impl Context {
             }
             im_rc::hashmap::Entry::Vacant(v) => {
                 if let Some(link) = summary.links() {
-                    if self.links.insert(link, id).is_some() {
+                    if !self.shared_links.contains(&link) && self.links.insert(link, id).is_some() {
                         return Err(format_err!(
                             "Attempting to resolve a dependency with more than \
                              one crate with links={}.\nThis will not build as \
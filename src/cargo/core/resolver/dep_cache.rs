@@ -350,6 +350,7 @@ fn build_requirements<'a, 'b: 'a>(
             features,
             all_features,
             uses_default_features,
+            no_default_features_for: _,
         }) => {
             if *all_features {
                 for key in s.features().keys() {
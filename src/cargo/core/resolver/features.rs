@@ -41,7 +41,7 @@ use crate::core::compiler::{CompileKind, CompileTarget, RustcTargetData};
 use crate::core::dependency::{ArtifactTarget, DepKind, Dependency};
 use crate::core::resolver::types::FeaturesSet;
 use crate::core::resolver::{Resolve, ResolveBehavior};
-use crate::core::{FeatureValue, PackageId, PackageIdSpec, PackageSet, Workspace};
+use crate::core::{FeatureValue, PackageId, PackageIdSpec, PackageSet, Target, Workspace};
 use crate::util::interning::InternedString;
 use crate::util::CargoResult;
 use anyhow::bail;
@@ -62,10 +62,16 @@ pub struct ResolvedFeatures {
     /// The value is the `name_in_toml` of the dependencies.
     activated_dependencies: ActivateMap,
     opts: FeatureOpts,
+    /// Features resolved for each workspace member on its own, if members
+    /// are decoupled.
+    members: HashMap<PackageId, ResolvedFeatures>,
+    /// Features resolved for targets built with their `required-features`
+    /// enabled for them alone, with `--auto-required-features`.
+    targets: HashMap<(PackageId, Target), ResolvedFeatures>,
 }
 
 /// Options for how the feature resolver works.
-#[derive(Default)]
+#[derive(Default, Copy, Clone)]
 pub struct FeatureOpts {
     /// Build deps and proc-macros will not share features with other dep kinds,
     /// and so won't artifact targets.
@@ -80,6 +86,9 @@ pub struct FeatureOpts {
     ignore_inactive_targets: bool,
     /// If enabled, compare against old resolver (for testing).
     compare: bool,
+    /// Each workspace member gets its own feature set, features enabled by
+    /// one member are not unified into the dependencies of another.
+    decouple_members: bool,
 }
 
 /// Flag to indicate if Cargo is building *any* dev units (tests, examples, etc.).
@@ -183,7 +192,7 @@ impl FeatureOpts {
                         opts.ignore_inactive_targets = true;
                     }
                     "compare" => opts.compare = true,
-                    "ws" => unimplemented!(),
+                    "ws" => opts.decouple_members = true,
                     s => bail!("-Zfeatures flag `{}` is not supported", s),
                 }
             }
@@ -197,6 +206,9 @@ impl FeatureOpts {
             ResolveBehavior::V2 => {
                 enable(&vec!["all".to_string()]).unwrap();
             }
+            ResolveBehavior::V3 => {
+                enable(&vec!["all".to_string(), "ws".to_string()]).unwrap();
+            }
         }
         if let HasDevUnits::Yes = has_dev_units {
             // Dev deps cannot be decoupled when they are in use.
@@ -212,11 +224,12 @@ impl FeatureOpts {
     pub fn new_behavior(behavior: ResolveBehavior, has_dev_units: HasDevUnits) -> FeatureOpts {
         match behavior {
             ResolveBehavior::V1 => FeatureOpts::default(),
-            ResolveBehavior::V2 => FeatureOpts {
+            ResolveBehavior::V2 | ResolveBehavior::V3 => FeatureOpts {
                 decouple_host_deps: true,
                 decouple_dev_deps: has_dev_units == HasDevUnits::No,
                 ignore_inactive_targets: true,
                 compare: false,
+                decouple_members: behavior == ResolveBehavior::V3,
             },
         }
     }
@@ -254,6 +267,9 @@ pub struct CliFeatures {
     pub all_features: bool,
     /// Inverse of `--no-default-features` flag.
     pub uses_default_features: bool,
+    /// Packages whose default features are disabled with the `pkg(-default)`
+    /// syntax of the `--features` flag.
+    pub no_default_features_for: Rc<BTreeSet<InternedString>>,
 }
 
 impl CliFeatures {
@@ -263,7 +279,11 @@ impl CliFeatures {
         all_features: bool,
         uses_default_features: bool,
     ) -> CargoResult<CliFeatures> {
-        let features = Rc::new(CliFeatures::split_features(features));
+        let mut no_default_features_for = BTreeSet::new();
+        let features = Rc::new(CliFeatures::split_features(
+            features,
+            &mut no_default_features_for,
+        )?);
         // Some early validation to ensure correct syntax.
         for feature in features.iter() {
             match feature {
@@ -275,10 +295,23 @@ impl CliFeatures {
                         feature
                     );
                 }
-                FeatureValue::DepFeature { dep_feature, .. } => {
+                FeatureValue::DepFeature {
+                    dep_name,
+                    dep_feature,
+                    ..
+                } => {
                     if dep_feature.contains('/') {
                         bail!("multiple slashes in feature `{}` is not allowed", feature);
                     }
+                    if dep_feature == "default" && no_default_features_for.contains(dep_name) {
+                        bail!(
+                            "cannot both enable and disable the default features of `{}`\n\
+                             `{}` and `{}(-default)` were both passed to `--features`",
+                            dep_name,
+                            feature,
+                            dep_name
+                        );
+                    }
                 }
             }
         }
@@ -286,6 +319,7 @@ impl CliFeatures {
             features,
             all_features,
             uses_default_features,
+            no_default_features_for: Rc::new(no_default_features_for),
         })
     }
 
@@ -295,18 +329,44 @@ impl CliFeatures {
             features: Rc::new(BTreeSet::new()),
             all_features,
             uses_default_features: true,
+            no_default_features_for: Rc::new(BTreeSet::new()),
         }
     }
 
-    fn split_features(features: &[String]) -> BTreeSet<FeatureValue> {
-        features
+    /// Returns whether the default features of the given package are used.
+    pub fn uses_default_features_of(&self, pkg_name: InternedString) -> bool {
+        self.uses_default_features && !self.no_default_features_for.contains(&pkg_name)
+    }
+
+    /// Splits the values of `--features` into features, moving the names of
+    /// packages given with the `pkg(-default)` syntax to `no_default_features_for`.
+    fn split_features(
+        features: &[String],
+        no_default_features_for: &mut BTreeSet<InternedString>,
+    ) -> CargoResult<BTreeSet<FeatureValue>> {
+        let mut values = BTreeSet::new();
+        for s in features
             .iter()
             .flat_map(|s| s.split_whitespace())
             .flat_map(|s| s.split(','))
             .filter(|s| !s.is_empty())
-            .map(InternedString::new)
-            .map(FeatureValue::new)
-            .collect()
+        {
+            match s.split_once('(') {
+                Some((pkg_name, "-default)")) if !pkg_name.is_empty() => {
+                    no_default_features_for.insert(InternedString::new(pkg_name));
+                }
+                Some(_) => bail!(
+                    "invalid feature `{}`\n\
+                     only `<package>(-default)` may be used to disable the default \
+                     features of a package",
+                    s
+                ),
+                None => {
+                    values.insert(FeatureValue::new(InternedString::new(s)));
+                }
+            }
+        }
+        Ok(values)
     }
 }
 
@@ -349,6 +409,36 @@ impl ResolvedFeatures {
         self.activated_features_int(pkg_id, features_for).ok()
     }
 
+    /// Returns the features resolved for `member` on its own if workspace
+    /// members are decoupled, otherwise the features of the whole workspace.
+    pub fn for_member(&self, member: PackageId) -> &ResolvedFeatures {
+        self.members.get(&member).unwrap_or(self)
+    }
+
+    /// Returns `true` if each workspace member has its own feature set, see
+    /// [`ResolvedFeatures::for_member`].
+    pub fn decouples_members(&self) -> bool {
+        self.opts.decouple_members
+    }
+
+    /// Returns the features resolved for `target` of `pkg_id` if it has its
+    /// own feature set, see [`FeatureResolver::resolve_required_features`].
+    pub fn for_target(&self, pkg_id: PackageId, target: &Target) -> Option<&ResolvedFeatures> {
+        self.targets.get(&(pkg_id, target.clone()))
+    }
+
+    /// Returns the features to build `target` of `pkg_id` with, either its
+    /// own or the ones of [`ResolvedFeatures::for_member`].
+    pub fn for_root(&self, pkg_id: PackageId, target: &Target) -> &ResolvedFeatures {
+        self.for_target(pkg_id, target)
+            .unwrap_or_else(|| self.for_member(pkg_id))
+    }
+
+    /// Returns `true` if some targets have their own feature set.
+    pub fn decouples_targets(&self) -> bool {
+        !self.targets.is_empty()
+    }
+
     fn activated_features_int(
         &self,
         pkg_id: PackageId,
@@ -399,6 +489,20 @@ impl ResolvedFeatures {
 /// Key is `(pkg_id, for_host)`. Value is a set of features or dependencies removed.
 pub type DiffMap = BTreeMap<PackageFeaturesKey, BTreeSet<InternedString>>;
 
+/// What caused a feature or a package to be activated, recorded to explain
+/// how conflicting features came to be enabled together.
+#[derive(Copy, Clone, Debug)]
+enum Activator {
+    /// Requested for a workspace member, either on the command line or
+    /// through its default features.
+    Member,
+    /// Listed in a feature of the given package.
+    Feature(PackageId, FeaturesFor, InternedString),
+    /// The dependency declaration of the given package, either its
+    /// `features` list or its default features.
+    Dependency(PackageId, FeaturesFor),
+}
+
 /// The new feature resolver that [`resolve`]s your project.
 ///
 /// For more information, please see the [module-level documentation].
@@ -437,6 +541,10 @@ pub struct FeatureResolver<'a, 'cfg> {
     /// set of features to activate.
     deferred_weak_dependencies:
         HashMap<(PackageId, FeaturesFor, InternedString), HashSet<InternedString>>,
+    /// What first activated each feature of each package.
+    feature_activators: HashMap<(PackageId, FeaturesFor, InternedString), Activator>,
+    /// What first activated each package.
+    package_activators: HashMap<PackageFeaturesKey, Activator>,
 }
 
 impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
@@ -454,8 +562,66 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
     ) -> CargoResult<ResolvedFeatures> {
         use crate::util::profile;
         let _p = profile::start("resolve features");
+        let new_resolver = || {
+            FeatureResolver::new(
+                ws,
+                target_data,
+                resolve,
+                package_set,
+                requested_targets,
+                opts,
+            )
+        };
+        let member_features = ws.members_with_features(specs, cli_features)?;
+        let mut r = new_resolver();
+        for (member, cli_features) in &member_features {
+            r.activate_member(member.package_id(), cli_features)?;
+        }
+        log::debug!("features={:#?}", r.activated_features);
+        let mut members = HashMap::new();
+        if opts.decouple_members {
+            // The unified features are still used for everything which isn't
+            // built on behalf of a single member, like `cargo tree`.
+            for (member, cli_features) in &member_features {
+                let mut m = new_resolver();
+                m.activate_member(member.package_id(), cli_features)?;
+                m.check_conflicts()?;
+                members.insert(
+                    member.package_id(),
+                    ResolvedFeatures {
+                        activated_features: m.activated_features,
+                        activated_dependencies: m.activated_dependencies,
+                        opts,
+                        members: HashMap::new(),
+                        targets: HashMap::new(),
+                    },
+                );
+            }
+        } else {
+            r.check_conflicts()?;
+        }
+        if r.opts.compare {
+            r.compare();
+        }
+        Ok(ResolvedFeatures {
+            activated_features: r.activated_features,
+            activated_dependencies: r.activated_dependencies,
+            opts: r.opts,
+            members,
+            targets: HashMap::new(),
+        })
+    }
+
+    fn new(
+        ws: &'a Workspace<'cfg>,
+        target_data: &'a RustcTargetData<'cfg>,
+        resolve: &'a Resolve,
+        package_set: &'a PackageSet<'cfg>,
+        requested_targets: &'a [CompileKind],
+        opts: FeatureOpts,
+    ) -> FeatureResolver<'a, 'cfg> {
         let track_for_host = opts.decouple_host_deps || opts.ignore_inactive_targets;
-        let mut r = FeatureResolver {
+        FeatureResolver {
             ws,
             target_data,
             requested_targets,
@@ -467,46 +633,97 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
             processed_deps: HashSet::new(),
             track_for_host,
             deferred_weak_dependencies: HashMap::new(),
-        };
-        r.do_resolve(specs, cli_features)?;
-        log::debug!("features={:#?}", r.activated_features);
-        if r.opts.compare {
-            r.compare();
+            feature_activators: HashMap::new(),
+            package_activators: HashMap::new(),
         }
-        Ok(ResolvedFeatures {
-            activated_features: r.activated_features,
-            activated_dependencies: r.activated_dependencies,
-            opts: r.opts,
-        })
     }
 
-    /// Performs the process of resolving all features for the resolve graph.
-    fn do_resolve(
-        &mut self,
-        specs: &[PackageIdSpec],
-        cli_features: &CliFeatures,
+    /// Resolves the features of each of `targets` with its `required-features`
+    /// enabled, for `--auto-required-features`, and adds them to `resolved`.
+    ///
+    /// The required features are only enabled for the target itself, and the
+    /// dependencies it shares with the other targets, instead of being
+    /// unified into the features of the whole build. `member_features` are
+    /// the features requested for each member, as returned by
+    /// [`Workspace::members_with_features`].
+    pub fn resolve_required_features(
+        ws: &Workspace<'cfg>,
+        target_data: &RustcTargetData<'cfg>,
+        resolve: &Resolve,
+        package_set: &PackageSet<'cfg>,
+        requested_targets: &[CompileKind],
+        member_features: &[(PackageId, CliFeatures)],
+        targets: &[(PackageId, Target)],
+        resolved: &mut ResolvedFeatures,
     ) -> CargoResult<()> {
-        let member_features = self.ws.members_with_features(specs, cli_features)?;
-        for (member, cli_features) in &member_features {
-            let fvs = self.fvs_from_requested(member.package_id(), cli_features);
-            let fk = if self.track_for_host && self.is_proc_macro(member.package_id()) {
-                // Also activate for normal dependencies. This is needed if the
-                // proc-macro includes other targets (like binaries or tests),
-                // or running in `cargo test`. Note that in a workspace, if
-                // the proc-macro is selected on the command like (like with
-                // `--workspace`), this forces feature unification with normal
-                // dependencies. This is part of the bigger problem where
-                // features depend on which packages are built.
-                self.activate_pkg(member.package_id(), FeaturesFor::default(), &fvs)?;
-                FeaturesFor::HostDep
-            } else {
-                FeaturesFor::default()
-            };
-            self.activate_pkg(member.package_id(), fk, &fvs)?;
+        let opts = resolved.opts;
+        for (pkg_id, target) in targets {
+            let mut r = FeatureResolver::new(
+                ws,
+                target_data,
+                resolve,
+                package_set,
+                requested_targets,
+                opts,
+            );
+            for (member, cli_features) in member_features {
+                if member == pkg_id {
+                    let mut features = (*cli_features.features).clone();
+                    features.extend(
+                        target
+                            .required_features()
+                            .into_iter()
+                            .flatten()
+                            .map(|f| FeatureValue::new(InternedString::new(f))),
+                    );
+                    let cli_features = CliFeatures {
+                        features: Rc::new(features),
+                        ..cli_features.clone()
+                    };
+                    r.activate_member(*member, &cli_features)?;
+                } else if !opts.decouple_members {
+                    r.activate_member(*member, cli_features)?;
+                }
+            }
+            r.check_conflicts()?;
+            resolved.targets.insert(
+                (*pkg_id, target.clone()),
+                ResolvedFeatures {
+                    activated_features: r.activated_features,
+                    activated_dependencies: r.activated_dependencies,
+                    opts,
+                    members: HashMap::new(),
+                    targets: HashMap::new(),
+                },
+            );
         }
         Ok(())
     }
 
+    /// Activates the features requested for a workspace member, and
+    /// everything they transitively enable.
+    fn activate_member(
+        &mut self,
+        member: PackageId,
+        cli_features: &CliFeatures,
+    ) -> CargoResult<()> {
+        let fvs = self.fvs_from_requested(member, cli_features);
+        let fk = if self.track_for_host && self.is_proc_macro(member) {
+            // Also activate for normal dependencies. This is needed if the
+            // proc-macro includes other targets (like binaries or tests),
+            // or running in `cargo test`. Note that in a workspace, if
+            // the proc-macro is selected on the command like (like with
+            // `--workspace`), this forces feature unification with normal
+            // dependencies. This is part of the bigger problem where
+            // features depend on which packages are built.
+            self.activate_pkg(member, FeaturesFor::default(), &fvs, Activator::Member)?;
+            FeaturesFor::HostDep
+        } else {
+            FeaturesFor::default()
+        };
+        self.activate_pkg(member, fk, &fvs, Activator::Member)
+    }
+
     /// Activates [`FeatureValue`]s on the given package.
     ///
     /// This is the main entrance into the recursion of feature activation
@@ -516,8 +733,12 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
         pkg_id: PackageId,
         fk: FeaturesFor,
         fvs: &[FeatureValue],
+        by: Activator,
     ) -> CargoResult<()> {
         log::trace!("activate_pkg {} {}", pkg_id.name(), fk);
+        self.package_activators
+            .entry((pkg_id, fk.apply_opts(&self.opts)))
+            .or_insert(by);
         // Add an empty entry to ensure everything is covered. This is intended for
         // finding bugs where the resolver missed something it should have visited.
         // Remove this in the future if `activated_features` uses an empty default.
@@ -525,7 +746,7 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
             .entry((pkg_id, fk.apply_opts(&self.opts)))
             .or_insert_with(BTreeSet::new);
         for fv in fvs {
-            self.activate_fv(pkg_id, fk, fv)?;
+            self.activate_fv(pkg_id, fk, fv, by)?;
         }
         if !self.processed_deps.insert((pkg_id, fk)) {
             // Already processed dependencies. There's no need to process them
@@ -552,7 +773,8 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
                 }
                 // Recurse into the dependency.
                 let fvs = self.fvs_from_dependency(dep_pkg_id, dep);
-                self.activate_pkg(dep_pkg_id, dep_fk, &fvs)?;
+                let by = Activator::Dependency(pkg_id, fk);
+                self.activate_pkg(dep_pkg_id, dep_fk, &fvs, by)?;
             }
         }
         Ok(())
@@ -564,21 +786,22 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
         pkg_id: PackageId,
         fk: FeaturesFor,
         fv: &FeatureValue,
+        by: Activator,
     ) -> CargoResult<()> {
         log::trace!("activate_fv {} {} {}", pkg_id.name(), fk, fv);
         match fv {
             FeatureValue::Feature(f) => {
-                self.activate_rec(pkg_id, fk, *f)?;
+                self.activate_rec(pkg_id, fk, *f, by)?;
             }
             FeatureValue::Dep { dep_name } => {
-                self.activate_dependency(pkg_id, fk, *dep_name)?;
+                self.activate_dependency(pkg_id, fk, *dep_name, by)?;
             }
             FeatureValue::DepFeature {
                 dep_name,
                 dep_feature,
                 weak,
             } => {
-                self.activate_dep_feature(pkg_id, fk, *dep_name, *dep_feature, *weak)?;
+                self.activate_dep_feature(pkg_id, fk, *dep_name, *dep_feature, *weak, by)?;
             }
         }
         Ok(())
@@ -591,6 +814,7 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
         pkg_id: PackageId,
         fk: FeaturesFor,
         feature_to_enable: InternedString,
+        by: Activator,
     ) -> CargoResult<()> {
         log::trace!(
             "activate_rec {} {} feat={}",
@@ -606,6 +830,8 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
             // Already enabled.
             return Ok(());
         }
+        self.feature_activators
+            .insert((pkg_id, fk.apply_opts(&self.opts), feature_to_enable), by);
         let summary = self.resolve.summary(pkg_id);
         let feature_map = summary.features();
         let fvs = match feature_map.get(&feature_to_enable) {
@@ -622,8 +848,9 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
                 return Ok(());
             }
         };
+        let by = Activator::Feature(pkg_id, fk, feature_to_enable);
         for fv in fvs {
-            self.activate_fv(pkg_id, fk, fv)?;
+            self.activate_fv(pkg_id, fk, fv, by)?;
         }
         Ok(())
     }
@@ -634,6 +861,7 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
         pkg_id: PackageId,
         fk: FeaturesFor,
         dep_name: InternedString,
+        by: Activator,
     ) -> CargoResult<()> {
         // Mark this dependency as activated.
         let save_decoupled = fk.apply_opts(&self.opts);
@@ -661,11 +889,11 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
                             dep_feature
                         );
                         let fv = FeatureValue::new(*dep_feature);
-                        self.activate_fv(dep_pkg_id, dep_fk, &fv)?;
+                        self.activate_fv(dep_pkg_id, dep_fk, &fv, by)?;
                     }
                 }
                 let fvs = self.fvs_from_dependency(dep_pkg_id, dep);
-                self.activate_pkg(dep_pkg_id, dep_fk, &fvs)?;
+                self.activate_pkg(dep_pkg_id, dep_fk, &fvs, by)?;
             }
         }
         Ok(())
@@ -679,6 +907,7 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
         dep_name: InternedString,
         dep_feature: InternedString,
         weak: bool,
+        by: Activator,
     ) -> CargoResult<()> {
         for (dep_pkg_id, deps) in self.deps(pkg_id, fk) {
             for (dep, dep_fk) in deps {
@@ -712,17 +941,17 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
 
                     // Activate the dependency on self.
                     let fv = FeatureValue::Dep { dep_name };
-                    self.activate_fv(pkg_id, fk, &fv)?;
+                    self.activate_fv(pkg_id, fk, &fv, by)?;
                     if !weak {
                         // The old behavior before weak dependencies were
                         // added is to also enables a feature of the same
                         // name.
-                        self.activate_rec(pkg_id, fk, dep_name)?;
+                        self.activate_rec(pkg_id, fk, dep_name, by)?;
                     }
                 }
                 // Activate the feature on the dependency.
                 let fv = FeatureValue::new(dep_feature);
-                self.activate_fv(dep_pkg_id, dep_fk, &fv)?;
+                self.activate_fv(dep_pkg_id, dep_fk, &fv, by)?;
             }
         }
         Ok(())
@@ -913,6 +1142,94 @@ impl<'a, 'cfg> FeatureResolver<'a, 'cfg> {
         }
     }
 
+    /// Errors if features which a package declares as conflicting in
+    /// `[features.metadata]` ended up enabled together.
+    fn check_conflicts(&self) -> CargoResult<()> {
+        let mut keys: Vec<_> = self
+            .activated_features
+            .iter()
+            .filter(|(_, enabled)| enabled.len() > 1)
+            .map(|(key, _)| *key)
+            .collect();
+        keys.sort();
+        for (pkg_id, fk) in keys {
+            let enabled = &self.activated_features[&(pkg_id, fk)];
+            let pkg = self.package_set.get_one(pkg_id)?;
+            let metadata = match pkg.manifest().original().features_metadata() {
+                Some(metadata) => metadata,
+                None => continue,
+            };
+            for conflict in metadata.conflicts() {
+                let both: Vec<_> = conflict.iter().filter(|f| enabled.contains(*f)).collect();
+                if both.len() < 2 {
+                    continue;
+                }
+                let names: Vec<_> = both.iter().map(|f| format!("`{}`", f)).collect();
+                let (last, rest) = names.split_last().unwrap();
+                let mut msg = format!(
+                    "features {} and {} of package `{}` are mutually exclusive, \
+                     but {} are enabled",
+                    rest.join(", "),
+                    last,
+                    pkg_id,
+                    if both.len() == 2 {
+                        "both"
+                    } else {
+                        "all of them"
+                    }
+                );
+                if fk != FeaturesFor::NormalOrDev {
+                    msg.push_str(&format!(" for `{}`", fk));
+                }
+                for feature in both {
+                    msg.push_str(&format!("\n\n`{}` is enabled by:", feature));
+                    for line in self.activation_path(pkg_id, fk, *feature) {
+                        msg.push_str("\n  ");
+                        msg.push_str(&line);
+                    }
+                }
+                bail!(msg);
+            }
+        }
+        Ok(())
+    }
+
+    /// Describes the chain of activators which enabled `feature` of a package,
+    /// up to the workspace member it originates from.
+    fn activation_path(
+        &self,
+        pkg_id: PackageId,
+        fk: FeaturesFor,
+        feature: InternedString,
+    ) -> Vec<String> {
+        let mut path = Vec::new();
+        let mut current = pkg_id;
+        let mut next = self.feature_activators.get(&(pkg_id, fk, feature));
+        while let Some(by) = next {
+            match *by {
+                Activator::Member => {
+                    path.push(format!("requested for workspace member `{}`", current));
+                    break;
+                }
+                Activator::Feature(pkg_id, fk, feature) => {
+                    path.push(format!("feature `{}` of `{}`", feature, pkg_id));
+                    current = pkg_id;
+                    next =
+                        self.feature_activators
+                            .get(&(pkg_id, fk.apply_opts(&self.opts), feature));
+                }
+                Activator::Dependency(pkg_id, fk) => {
+                    path.push(format!("dependency declaration in `{}`", pkg_id));
+                    current = pkg_id;
+                    next = self
+                        .package_activators
+                        .get(&(pkg_id, fk.apply_opts(&self.opts)));
+                }
+            }
+        }
+        path
+    }
+
     fn is_proc_macro(&self, package_id: PackageId) -> bool {
         self.package_set
             .get_one(package_id)
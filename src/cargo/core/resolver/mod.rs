@@ -58,6 +58,7 @@ use crate::core::PackageIdSpec;
 use crate::core::{Dependency, PackageId, Registry, Summary};
 use crate::util::config::Config;
 use crate::util::errors::CargoResult;
+use crate::util::interning::InternedString;
 use crate::util::network::PollExt;
 use crate::util::profile;
 
@@ -71,7 +72,7 @@ pub use self::encode::Metadata;
 pub use self::encode::{EncodableDependency, EncodablePackageId, EncodableResolve};
 pub use self::errors::{ActivateError, ActivateResult, ResolveError};
 pub use self::features::{CliFeatures, ForceAllTargets, HasDevUnits};
-pub use self::resolve::{Resolve, ResolveVersion};
+pub use self::resolve::{checksum_mismatch, Resolve, ResolveVersion};
 pub use self::types::{ResolveBehavior, ResolveOpts};
 pub use self::version_prefs::{VersionOrdering, VersionPreferences};
 
@@ -120,6 +121,9 @@ mod version_prefs;
 ///
 ///     When we have a decision for how to implement is without breaking existing functionality
 ///     this flag can be removed.
+///
+/// * `shared_links` - the `links` keys which several packages may use, as
+///   designated by `[links-overrides]`.
 pub fn resolve(
     summaries: &[(Summary, ResolveOpts)],
     replacements: &[(PackageIdSpec, Dependency)],
@@ -127,6 +131,7 @@ pub fn resolve(
     version_prefs: &VersionPreferences,
     config: Option<&Config>,
     check_public_visible_dependencies: bool,
+    shared_links: &HashSet<InternedString>,
 ) -> CargoResult<Resolve> {
     let _p = profile::start("resolving");
     let minimal_versions = match config {
@@ -136,7 +141,10 @@ pub fn resolve(
     let mut registry =
         RegistryQueryer::new(registry, replacements, version_prefs, minimal_versions);
     let cx = loop {
-        let cx = Context::new(check_public_visible_dependencies);
+        let cx = Context::new(
+            check_public_visible_dependencies,
+            Rc::new(shared_links.clone()),
+        );
         let cx = activate_deps_loop(cx, &mut registry, summaries, config)?;
         if registry.reset_pending() {
             break cx;
@@ -3,7 +3,8 @@ use crate::core::dependency::DepKind;
 use crate::core::{Dependency, PackageId, PackageIdSpec, Summary, Target};
 use crate::util::errors::CargoResult;
 use crate::util::interning::InternedString;
-use crate::util::Graph;
+use crate::util::{lev_distance, Config, Graph};
+use anyhow::Context as _;
 use std::borrow::Borrow;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
@@ -82,6 +83,31 @@ pub enum ResolveVersion {
     V3,
 }
 
+impl ResolveVersion {
+    /// The number of this format, as accepted by
+    /// `cargo lock upgrade-format --to`.
+    pub fn number(self) -> u32 {
+        match self {
+            ResolveVersion::V1 => 1,
+            ResolveVersion::V2 => 2,
+            ResolveVersion::V3 => 3,
+        }
+    }
+
+    pub fn from_number(number: u32) -> CargoResult<ResolveVersion> {
+        match number {
+            1 => Ok(ResolveVersion::V1),
+            2 => Ok(ResolveVersion::V2),
+            3 => Ok(ResolveVersion::V3),
+            n => anyhow::bail!(
+                "lock file version `{}` is not supported, \
+                 expected one of 1, 2 or 3",
+                n
+            ),
+        }
+    }
+}
+
 impl Resolve {
     pub fn new(
         graph: Graph<PackageId, HashSet<Dependency>>,
@@ -160,70 +186,7 @@ impl Resolve {
                     continue;
                 }
 
-                // If the previous checksum wasn't calculated, the current
-                // checksum is `Some`. This may indicate that a source was
-                // erroneously replaced or was replaced with something that
-                // desires stronger checksum guarantees than can be afforded
-                // elsewhere.
-                if cksum.is_none() {
-                    anyhow::bail!(
-                        "\
-checksum for `{}` was not previously calculated, but a checksum could now \
-be calculated
-
-this could be indicative of a few possible situations:
-
-    * the source `{}` did not previously support checksums,
-      but was replaced with one that does
-    * newer Cargo implementations know how to checksum this source, but this
-      older implementation does not
-    * the lock file is corrupt
-",
-                        id,
-                        id.source_id()
-                    )
-
-                // If our checksum hasn't been calculated, then it could mean
-                // that future Cargo figured out how to checksum something or
-                // more realistically we were overridden with a source that does
-                // not have checksums.
-                } else if mine.is_none() {
-                    anyhow::bail!(
-                        "\
-checksum for `{}` could not be calculated, but a checksum is listed in \
-the existing lock file
-
-this could be indicative of a few possible situations:
-
-    * the source `{}` supports checksums,
-      but was replaced with one that doesn't
-    * the lock file is corrupt
-
-unable to verify that `{0}` is the same as when the lockfile was generated
-",
-                        id,
-                        id.source_id()
-                    )
-
-                // If the checksums aren't equal, and neither is None, then they
-                // must both be Some, in which case the checksum now differs.
-                // That's quite bad!
-                } else {
-                    anyhow::bail!(
-                        "\
-checksum for `{}` changed between lock files
-
-this could be indicative of a few possible errors:
-
-    * the lock file is corrupt
-    * a replacement source in use (e.g., a mirror) returned a different checksum
-    * the source itself may be corrupt in one way or another
-
-unable to verify that `{0}` is the same as when the lockfile was generated
-",
-                        id
-                    );
-                }
+                return Err(checksum_mismatch(*id, cksum, mine));
             }
         }
 
@@ -293,8 +256,46 @@ unable to verify that `{0}` is the same as when the lockfile was generated
         PackageIdSpec::query_str(spec, self.iter())
     }
 
+    /// Like [`Resolve::query`], but the spec may end in qualifiers, see
+    /// [`PackageIdSpec::parse_qualified`].
+    pub fn query_qualified(&self, spec: &str, config: &Config) -> CargoResult<PackageId> {
+        let parsed = PackageIdSpec::parse_qualified(spec, config).with_context(|| {
+            let suggestion = lev_distance::closest_msg(spec, self.iter(), |id| id.name().as_str());
+            format!("invalid package ID specification: `{}`{}", spec, suggestion)
+        })?;
+        self.query_spec(&parsed)
+    }
+
+    /// Finds the one package matching `spec`. If it has qualifiers, only the
+    /// packages depended on through a dependency matching them are considered.
+    pub fn query_spec(&self, spec: &PackageIdSpec) -> CargoResult<PackageId> {
+        let qualifiers = match spec.qualifiers() {
+            Some(qualifiers) => qualifiers,
+            None => return spec.query(self.iter()),
+        };
+        if !self.iter().any(|id| spec.matches(id)) {
+            return spec.query(self.iter());
+        }
+        let selected: HashSet<PackageId> = self
+            .iter()
+            .flat_map(|parent| self.deps(parent))
+            .filter(|(_, deps)| deps.iter().any(|dep| qualifiers.matches(dep)))
+            .map(|(id, _)| id)
+            .collect();
+        if !selected.iter().any(|&id| spec.matches(id)) {
+            anyhow::bail!(
+                "package ID specification `{}` did not match any packages\n\
+                 no dependency on `{}` matches `{}`",
+                spec,
+                spec.name(),
+                qualifiers
+            );
+        }
+        spec.query(self.iter().filter(|id| selected.contains(id)))
+    }
+
     pub fn specs_to_ids(&self, specs: &[PackageIdSpec]) -> CargoResult<Vec<PackageId>> {
-        specs.iter().map(|s| s.query(self.iter())).collect()
+        specs.iter().map(|s| self.query_spec(s)).collect()
     }
 
     pub fn unused_patches(&self) -> &[PackageId] {
@@ -305,6 +306,12 @@ unable to verify that `{0}` is the same as when the lockfile was generated
         &self.checksums
     }
 
+    /// Forgets the checksum recorded for `pkg`, so that a different one is
+    /// accepted by [`Resolve::merge_from`]. Returns the forgotten checksum.
+    pub fn forget_checksum(&mut self, pkg: PackageId) -> Option<String> {
+        self.checksums.remove(&pkg).flatten()
+    }
+
     pub fn metadata(&self) -> &Metadata {
         &self.metadata
     }
@@ -376,6 +383,79 @@ unable to verify that `{0}` is the same as when the lockfile was generated
     }
 }
 
+/// Builds the error reported when the checksum `previous` recorded for `id`
+/// (e.g., in a lock file) disagrees with the `current` one.
+pub fn checksum_mismatch(
+    id: PackageId,
+    previous: &Option<String>,
+    current: &Option<String>,
+) -> anyhow::Error {
+    // If the previous checksum wasn't calculated, the current
+    // checksum is `Some`. This may indicate that a source was
+    // erroneously replaced or was replaced with something that
+    // desires stronger checksum guarantees than can be afforded
+    // elsewhere.
+    if previous.is_none() {
+        anyhow::format_err!(
+            "\
+checksum for `{}` was not previously calculated, but a checksum could now \
+be calculated
+
+this could be indicative of a few possible situations:
+
+    * the source `{}` did not previously support checksums,
+      but was replaced with one that does
+    * newer Cargo implementations know how to checksum this source, but this
+      older implementation does not
+    * the lock file is corrupt
+",
+            id,
+            id.source_id()
+        )
+
+    // If our checksum hasn't been calculated, then it could mean
+    // that future Cargo figured out how to checksum something or
+    // more realistically we were overridden with a source that does
+    // not have checksums.
+    } else if current.is_none() {
+        anyhow::format_err!(
+            "\
+checksum for `{}` could not be calculated, but a checksum is listed in \
+the existing lock file
+
+this could be indicative of a few possible situations:
+
+    * the source `{}` supports checksums,
+      but was replaced with one that doesn't
+    * the lock file is corrupt
+
+unable to verify that `{0}` is the same as when the lockfile was generated
+",
+            id,
+            id.source_id()
+        )
+
+    // If the checksums aren't equal, and neither is None, then they
+    // must both be Some, in which case the checksum now differs.
+    // That's quite bad!
+    } else {
+        anyhow::format_err!(
+            "\
+checksum for `{}` changed between lock files
+
+this could be indicative of a few possible errors:
+
+    * the lock file is corrupt
+    * a replacement source in use (e.g., a mirror) returned a different checksum
+    * the source itself may be corrupt in one way or another
+
+unable to verify that `{0}` is the same as when the lockfile was generated
+",
+            id
+        )
+    }
+}
+
 impl PartialEq for Resolve {
     fn eq(&self, other: &Resolve) -> bool {
         macro_rules! compare {
@@ -105,6 +105,9 @@ pub enum ResolveBehavior {
     V1,
     /// V2 adds the new feature resolver.
     V2,
+    /// V3 additionally resolves the features of each workspace member on
+    /// its own.
+    V3,
 }
 
 impl ResolveBehavior {
@@ -112,8 +115,10 @@ impl ResolveBehavior {
         match resolver {
             "1" => Ok(ResolveBehavior::V1),
             "2" => Ok(ResolveBehavior::V2),
+            "3" => Ok(ResolveBehavior::V3),
             s => anyhow::bail!(
-                "`resolver` setting `{}` is not valid, valid options are \"1\" or \"2\"",
+                "`resolver` setting `{}` is not valid, valid options are \"1\" or \"2\", \
+                 or \"3\" with `cargo-features = [\"resolver-3\"]`",
                 s
             ),
         }
@@ -123,6 +128,7 @@ impl ResolveBehavior {
         match self {
             ResolveBehavior::V1 => "1",
             ResolveBehavior::V2 => "2",
+            ResolveBehavior::V3 => "3",
         }
         .to_owned()
     }
@@ -1,5 +1,6 @@
 use std::collections::hash_map::HashMap;
 use std::fmt;
+use std::path::PathBuf;
 use std::task::Poll;
 
 use crate::core::package::PackageSet;
@@ -125,6 +126,9 @@ pub enum MaybePackage {
         url: String,
         descriptor: String,
         authorization: Option<String>,
+        /// Where the downloaded data is persisted while it's being received,
+        /// to resume the download if it's interrupted.
+        partial: Option<PathBuf>,
     },
 }
 
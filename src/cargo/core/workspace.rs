@@ -17,14 +17,14 @@ use crate::core::registry::PackageRegistry;
 use crate::core::resolver::features::CliFeatures;
 use crate::core::resolver::ResolveBehavior;
 use crate::core::{Dependency, FeatureValue, PackageId, PackageIdSpec};
-use crate::core::{EitherManifest, Package, SourceId, VirtualManifest};
+use crate::core::{EitherManifest, LinksOverride, Package, SourceId, VirtualManifest};
 use crate::ops;
 use crate::sources::{PathSource, CRATES_IO_INDEX, CRATES_IO_REGISTRY};
 use crate::util::errors::{CargoResult, ManifestError};
 use crate::util::interning::InternedString;
 use crate::util::lev_distance;
 use crate::util::toml::{read_manifest, InheritableFields, TomlDependency, TomlProfiles};
-use crate::util::{config::ConfigRelativePath, Config, Filesystem, IntoUrl};
+use crate::util::{self, config::ConfigRelativePath, Config, Filesystem, IntoUrl};
 use cargo_util::paths;
 use cargo_util::paths::normalize_path;
 use pathdiff::diff_paths;
@@ -57,6 +57,10 @@ pub struct Workspace<'cfg> {
     // `None` if the default path of `root/target` should be used.
     target_dir: Option<Filesystem>,
 
+    // Whether `target_dir` is the directory of this workspace under
+    // `Config::global_target_dirs`.
+    global_target_dir: bool,
+
     // List of members in this workspace with a listing of all their manifest
     // paths. The packages themselves can be looked up through the `packages`
     // set above.
@@ -93,6 +97,10 @@ pub struct Workspace<'cfg> {
     // file. This is set for `cargo install` without `--locked`.
     ignore_lock: bool,
 
+    // The file name of the lock file in the workspace root, which is
+    // `Cargo.lock` unless another one is selected with `--lockfile`.
+    lock_file_name: String,
+
     /// The resolver behavior specified with the `resolver` field.
     resolve_behavior: ResolveBehavior,
 
@@ -196,6 +204,7 @@ impl<'cfg> Workspace<'cfg> {
         } else {
             ws.root_manifest = ws.find_root(manifest_path)?;
         }
+        ws.set_global_target_dir()?;
 
         ws.custom_metadata = ws
             .load_workspace_config()?
@@ -216,6 +225,7 @@ impl<'cfg> Workspace<'cfg> {
             },
             root_manifest: None,
             target_dir: None,
+            global_target_dir: false,
             members: Vec::new(),
             member_ids: HashSet::new(),
             default_members: Vec::new(),
@@ -223,6 +233,7 @@ impl<'cfg> Workspace<'cfg> {
             require_optional_deps: true,
             loaded_packages: RefCell::new(HashMap::new()),
             ignore_lock: false,
+            lock_file_name: String::from("Cargo.lock"),
             resolve_behavior: ResolveBehavior::V1,
             custom_metadata: None,
         }
@@ -237,6 +248,7 @@ impl<'cfg> Workspace<'cfg> {
         let mut ws = Workspace::new_default(current_manifest, config);
         ws.root_manifest = Some(root_path.join("Cargo.toml"));
         ws.target_dir = config.target_dir()?;
+        ws.set_global_target_dir()?;
         ws.packages
             .packages
             .insert(root_path, MaybePackage::Virtual(manifest));
@@ -269,11 +281,12 @@ impl<'cfg> Workspace<'cfg> {
         let id = package.package_id();
         let package = MaybePackage::Package(package);
         ws.packages.packages.insert(key.to_path_buf(), package);
-        ws.target_dir = if let Some(dir) = target_dir {
-            Some(dir)
+        if let Some(dir) = target_dir {
+            ws.target_dir = Some(dir);
         } else {
-            ws.config.target_dir()?
-        };
+            ws.target_dir = ws.config.target_dir()?;
+            ws.set_global_target_dir()?;
+        }
         ws.members.push(ws.current_manifest.clone());
         ws.member_ids.insert(id);
         ws.default_members.push(ws.current_manifest.clone());
@@ -281,6 +294,18 @@ impl<'cfg> Workspace<'cfg> {
         Ok(ws)
     }
 
+    /// Uses the directory of this workspace under
+    /// [`Config::global_target_dirs`] with `build.target-dir = "global"`, once
+    /// the root of the workspace is known.
+    fn set_global_target_dir(&mut self) -> CargoResult<()> {
+        if self.config.is_global_target_dir()? {
+            let hash = util::short_hash(&self.root());
+            self.target_dir = Some(self.config.global_target_dirs()?.join(hash));
+            self.global_target_dir = true;
+        }
+        Ok(())
+    }
+
     fn set_resolve_behavior(&mut self) {
         // - If resolver is specified in the workspace definition, use that.
         // - If the root package specifies the resolver, use that.
@@ -385,6 +410,12 @@ impl<'cfg> Workspace<'cfg> {
             .unwrap_or_else(|| Filesystem::new(self.root().join("target")))
     }
 
+    /// Whether the target directory is the directory of this workspace under
+    /// [`Config::global_target_dirs`], with `build.target-dir = "global"`.
+    pub fn is_global_target_dir(&self) -> bool {
+        self.global_target_dir
+    }
+
     /// Returns the root `[replace]` section of this workspace.
     ///
     /// This may be from a virtual crate or an actual crate.
@@ -451,6 +482,14 @@ impl<'cfg> Workspace<'cfg> {
     /// Returns the root `[patch]` section of this workspace.
     ///
     /// This may be from a virtual crate or an actual crate.
+    /// Returns the `[links-overrides]` table of the root manifest.
+    pub fn root_links_overrides(&self) -> &BTreeMap<String, LinksOverride> {
+        match self.root_maybe() {
+            MaybePackage::Package(p) => p.manifest().links_overrides(),
+            MaybePackage::Virtual(vm) => vm.links_overrides(),
+        }
+    }
+
     pub fn root_patch(&self) -> CargoResult<HashMap<Url, Vec<Dependency>>> {
         let from_manifest = match self.root_maybe() {
             MaybePackage::Package(p) => p.manifest().patch(),
@@ -582,6 +621,21 @@ impl<'cfg> Workspace<'cfg> {
         self
     }
 
+    /// The file name of the lock file, like `Cargo.lock`.
+    pub fn lock_file_name(&self) -> &str {
+        &self.lock_file_name
+    }
+
+    /// The path of the lock file in the workspace root.
+    pub fn lock_file_path(&self) -> PathBuf {
+        self.root().join(&self.lock_file_name)
+    }
+
+    pub fn set_lock_file_name(&mut self, name: String) -> &mut Workspace<'cfg> {
+        self.lock_file_name = name;
+        self
+    }
+
     pub fn custom_metadata(&self) -> Option<&toml::Value> {
         self.custom_metadata.as_ref()
     }
@@ -792,7 +846,7 @@ impl<'cfg> Workspace<'cfg> {
         self.is_virtual()
             || match self.resolve_behavior() {
                 ResolveBehavior::V1 => false,
-                ResolveBehavior::V2 => true,
+                ResolveBehavior::V2 | ResolveBehavior::V3 => true,
             }
     }
 
@@ -987,6 +1041,9 @@ impl<'cfg> Workspace<'cfg> {
                 if !manifest.patch().is_empty() {
                     emit_warning("patch")?;
                 }
+                if !manifest.links_overrides().is_empty() {
+                    emit_warning("links-overrides")?;
+                }
                 if let Some(behavior) = manifest.resolve_behavior() {
                     if behavior != self.resolve_behavior {
                         // Only warn if they don't match.
@@ -1072,6 +1129,7 @@ impl<'cfg> Workspace<'cfg> {
 
     pub fn set_target_dir(&mut self, target_dir: Filesystem) {
         self.target_dir = Some(target_dir);
+        self.global_target_dir = false;
     }
 
     /// Returns a Vec of `(&Package, RequestedFeatures)` tuples that
@@ -1098,11 +1156,35 @@ impl<'cfg> Workspace<'cfg> {
                 .map(|m| (m, CliFeatures::new_all(true)))
                 .collect());
         }
-        if self.allows_new_cli_feature_behavior() {
-            self.members_with_features_new(specs, cli_features)
+        if !cli_features.no_default_features_for.is_empty()
+            && !self.config().cli_unstable().package_default_features
+        {
+            bail!(
+                "the `<package>(-default)` syntax of `--features` is unstable, \
+                 pass `-Z package-default-features` to enable it"
+            );
+        }
+        let members = if self.allows_new_cli_feature_behavior() {
+            self.members_with_features_new(specs, cli_features)?
         } else {
-            Ok(self.members_with_features_old(specs, cli_features))
+            self.members_with_features_old(specs, cli_features)
+        };
+        for name in cli_features.no_default_features_for.iter() {
+            if !members.iter().any(|(member, _)| member.name() == *name) {
+                let mut selected: Vec<_> = members
+                    .iter()
+                    .map(|(member, _)| member.name().to_string())
+                    .collect();
+                selected.sort();
+                bail!(
+                    "`{}(-default)` does not match any package selected to be built\n\
+                     selected packages: {}",
+                    name,
+                    selected.join(", ")
+                );
+            }
         }
+        Ok(members)
     }
 
     /// Returns the requested features for the given member.
@@ -1113,7 +1195,10 @@ impl<'cfg> Workspace<'cfg> {
         found_features: &mut BTreeSet<FeatureValue>,
     ) -> CliFeatures {
         if cli_features.features.is_empty() {
-            return cli_features.clone();
+            return CliFeatures {
+                uses_default_features: cli_features.uses_default_features_of(member.name()),
+                ..cli_features.clone()
+            };
         }
 
         // Only include features this member defines.
@@ -1181,7 +1266,8 @@ impl<'cfg> Workspace<'cfg> {
         CliFeatures {
             features: Rc::new(features),
             all_features: cli_features.all_features,
-            uses_default_features: cli_features.uses_default_features,
+            uses_default_features: cli_features.uses_default_features_of(member.name()),
+            no_default_features_for: cli_features.no_default_features_for.clone(),
         }
     }
 
@@ -1393,7 +1479,8 @@ impl<'cfg> Workspace<'cfg> {
             // Do not allow any command-line flags (defaults only).
             if !(cli_features.features.is_empty()
                 && !cli_features.all_features
-                && cli_features.uses_default_features)
+                && cli_features.uses_default_features
+                && cli_features.no_default_features_for.is_empty())
             {
                 bail!("cannot specify features for packages outside of workspace");
             }
@@ -1467,7 +1554,9 @@ impl<'cfg> Workspace<'cfg> {
                         let feats = CliFeatures {
                             features: Rc::new(cwd_features.clone()),
                             all_features: cli_features.all_features,
-                            uses_default_features: cli_features.uses_default_features,
+                            uses_default_features: cli_features
+                                .uses_default_features_of(member.name()),
+                            no_default_features_for: cli_features.no_default_features_for.clone(),
                         };
                         Some((member, feats))
                     }
@@ -1489,8 +1578,13 @@ impl<'cfg> Workspace<'cfg> {
                                         .remove(member.name().as_str())
                                         .unwrap_or_default(),
                                 ),
-                                uses_default_features: true,
+                                uses_default_features: !cli_features
+                                    .no_default_features_for
+                                    .contains(&member.name()),
                                 all_features: cli_features.all_features,
+                                no_default_features_for: cli_features
+                                    .no_default_features_for
+                                    .clone(),
                             };
                             Some((member, feats))
                         } else {
@@ -0,0 +1,425 @@
+//! Implementation of `cargo changelog`.
+//!
+//! The changelog is drafted from two sources: the git history since a given
+//! revision, where each commit is attributed to the workspace members whose
+//! files it touches and categorized according to the [Conventional Commits]
+//! format, and the difference between the lock file at that revision and the
+//! current one, which is reported as dependency bumps of the members
+//! depending on the changed packages.
+//!
+//! [Conventional Commits]: https://www.conventionalcommits.org/
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use cargo_util::paths;
+use serde::Serialize;
+use toml_edit::easy as toml;
+
+use crate::core::{Package, PackageId, Workspace};
+use crate::util::errors::CargoResult;
+use crate::util::interning::InternedString;
+
+pub struct ChangelogOptions {
+    /// The git revision (usually a tag) to collect changes from.
+    pub since: String,
+    pub format: ChangelogFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangelogFormat {
+    Markdown,
+    Json,
+}
+
+impl ChangelogFormat {
+    pub const POSSIBLE_VALUES: [&'static str; 2] = ["markdown", "json"];
+}
+
+impl std::str::FromStr for ChangelogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> CargoResult<Self> {
+        match s {
+            "markdown" => Ok(ChangelogFormat::Markdown),
+            "json" => Ok(ChangelogFormat::Json),
+            f => anyhow::bail!("unknown changelog format `{}`", f),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Changelog {
+    since: String,
+    members: Vec<MemberChangelog>,
+}
+
+#[derive(Serialize)]
+struct MemberChangelog {
+    name: InternedString,
+    version: String,
+    id: PackageId,
+    changes: Vec<Change>,
+    dependencies: Vec<DependencyChange>,
+}
+
+#[derive(Serialize, Clone)]
+struct Change {
+    commit: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    scope: Option<String>,
+    breaking: bool,
+    description: String,
+}
+
+#[derive(Serialize, Clone)]
+struct DependencyChange {
+    name: String,
+    /// The versions locked at `since`, or `None` if the package was added.
+    from: Option<Vec<String>>,
+    /// The versions locked now, or `None` if the package was removed.
+    to: Option<Vec<String>>,
+}
+
+pub fn changelog(ws: &Workspace<'_>, opts: &ChangelogOptions) -> CargoResult<()> {
+    let repo = git2::Repository::discover(ws.root()).with_context(|| {
+        format!(
+            "`cargo changelog` requires the workspace at `{}` to be in a git repository",
+            ws.root().display()
+        )
+    })?;
+    let workdir = match repo.workdir() {
+        Some(workdir) => paths::normalize_path(workdir),
+        None => anyhow::bail!("`cargo changelog` does not support bare git repositories"),
+    };
+    let since = repo
+        .revparse_single(&opts.since)
+        .and_then(|obj| obj.peel_to_commit())
+        .with_context(|| format!("failed to find git revision `{}`", opts.since))?;
+
+    let mut members: Vec<&Package> = ws.members().collect();
+    members.sort_by_key(|pkg| pkg.name());
+    let member_dirs: Vec<PathBuf> = members
+        .iter()
+        .map(|pkg| relative_to(&workdir, pkg.root()))
+        .collect::<CargoResult<_>>()?;
+
+    let mut changes = vec![Vec::new(); members.len()];
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    revwalk.push_head()?;
+    revwalk.hide(since.id())?;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let touched = touched_members(&repo, &commit, &member_dirs)?;
+        if touched.is_empty() {
+            continue;
+        }
+        let change = parse_commit(&commit);
+        for idx in touched {
+            changes[idx].push(change.clone());
+        }
+    }
+
+    let lock_path = relative_to(&workdir, &ws.lock_file_path())?;
+    let old_lock = match since.tree()?.get_path(&lock_path) {
+        Ok(entry) => {
+            let blob = entry.to_object(&repo)?.peel_to_blob()?;
+            let contents = String::from_utf8_lossy(blob.content());
+            locked_versions(&contents)
+                .with_context(|| format!("failed to parse lock file at `{}`", opts.since))?
+        }
+        Err(_) => BTreeMap::new(),
+    };
+    let new_lock = match paths::read(&ws.lock_file_path()) {
+        Ok(contents) => locked_versions(&contents)?,
+        Err(_) => BTreeMap::new(),
+    };
+    let bumps = lock_diff(&old_lock, &new_lock);
+
+    let mut changelog = Changelog {
+        since: opts.since.clone(),
+        members: Vec::new(),
+    };
+    for (pkg, changes) in members.iter().zip(changes) {
+        let deps: BTreeSet<InternedString> = pkg
+            .dependencies()
+            .iter()
+            .map(|dep| dep.package_name())
+            .collect();
+        let dependencies: Vec<_> = bumps
+            .iter()
+            .filter(|bump| deps.contains(bump.name.as_str()))
+            .cloned()
+            .collect();
+        if changes.is_empty() && dependencies.is_empty() {
+            continue;
+        }
+        changelog.members.push(MemberChangelog {
+            name: pkg.name(),
+            version: pkg.version().to_string(),
+            id: pkg.package_id(),
+            changes,
+            dependencies,
+        });
+    }
+
+    let config = ws.config();
+    match opts.format {
+        ChangelogFormat::Json => config.shell().print_json(&changelog)?,
+        ChangelogFormat::Markdown => {
+            if changelog.members.is_empty() {
+                config.shell().status(
+                    "Changelog",
+                    format!("no changes to workspace members since `{}`", opts.since),
+                )?;
+            } else {
+                crate::drop_print!(config, "{}", to_markdown(&changelog));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns `path` relative to the root of the git working directory.
+pub(super) fn relative_to(workdir: &Path, path: &Path) -> CargoResult<PathBuf> {
+    let path = paths::normalize_path(path);
+    if let Ok(rel) = path.strip_prefix(workdir) {
+        return Ok(rel.to_path_buf());
+    }
+    // The working directory reported by git is canonicalized, while paths
+    // within the workspace may go through symlinks.
+    let canonical = path
+        .canonicalize()
+        .or_else(|_| -> std::io::Result<PathBuf> {
+            let parent = path.parent().unwrap_or(&path).canonicalize()?;
+            Ok(parent.join(path.file_name().unwrap_or_default()))
+        })
+        .ok();
+    let workdir = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_path_buf());
+    match canonical
+        .as_deref()
+        .and_then(|c| c.strip_prefix(&workdir).ok())
+    {
+        Some(rel) => Ok(rel.to_path_buf()),
+        None => anyhow::bail!(
+            "`{}` is outside of the git repository at `{}`",
+            path.display(),
+            workdir.display()
+        ),
+    }
+}
+
+/// Returns the indices of the members whose files are changed by `commit`.
+///
+/// Every changed path is attributed to the member with the most specific
+/// directory containing it, so a change in a nested member isn't also
+/// reported for the root package.
+fn touched_members(
+    repo: &git2::Repository,
+    commit: &git2::Commit<'_>,
+    member_dirs: &[PathBuf],
+) -> CargoResult<BTreeSet<usize>> {
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    let mut touched = BTreeSet::new();
+    for delta in diff.deltas() {
+        for path in [delta.old_file().path(), delta.new_file().path()]
+            .into_iter()
+            .flatten()
+        {
+            let owner = member_dirs
+                .iter()
+                .enumerate()
+                .filter(|(_, dir)| path.starts_with(dir))
+                .max_by_key(|(_, dir)| dir.components().count());
+            if let Some((idx, _)) = owner {
+                touched.insert(idx);
+            }
+        }
+    }
+    Ok(touched)
+}
+
+/// Parses the message of `commit` according to the Conventional Commits
+/// format, like `feat(parser)!: support comments`.
+///
+/// Messages not following the format are kept as is, without a type.
+fn parse_commit(commit: &git2::Commit<'_>) -> Change {
+    let message = commit.message().unwrap_or("");
+    let summary = message.lines().next().unwrap_or("").trim();
+    let breaking_footer = message
+        .lines()
+        .any(|line| line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"));
+    let mut change = Change {
+        commit: commit.id().to_string()[..10].to_string(),
+        kind: None,
+        scope: None,
+        breaking: breaking_footer,
+        description: summary.to_string(),
+    };
+
+    let (head, description) = match summary.split_once(": ") {
+        Some(parts) => parts,
+        None => return change,
+    };
+    let (head, bang) = match head.strip_suffix('!') {
+        Some(head) => (head, true),
+        None => (head, false),
+    };
+    let (kind, scope) = match head.split_once('(') {
+        Some((kind, scope)) => match scope.strip_suffix(')') {
+            Some(scope) if !scope.is_empty() => (kind, Some(scope)),
+            _ => return change,
+        },
+        None => (head, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+        return change;
+    }
+    change.kind = Some(kind.to_ascii_lowercase());
+    change.scope = scope.map(str::to_string);
+    change.breaking |= bang;
+    change.description = description.trim().to_string();
+    change
+}
+
+/// Collects the locked versions of every package in a lock file, keyed by
+/// package name. Packages without a source are workspace members or path
+/// dependencies and are skipped.
+fn locked_versions(contents: &str) -> CargoResult<BTreeMap<String, BTreeSet<String>>> {
+    let lock: toml::Value = toml::from_str(contents)?;
+    let mut versions: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let packages = lock.get("package").and_then(|p| p.as_array());
+    for pkg in packages.into_iter().flatten() {
+        if pkg.get("source").is_none() {
+            continue;
+        }
+        let name = pkg.get("name").and_then(|n| n.as_str());
+        let version = pkg.get("version").and_then(|v| v.as_str());
+        if let (Some(name), Some(version)) = (name, version) {
+            versions
+                .entry(name.to_string())
+                .or_default()
+                .insert(version.to_string());
+        }
+    }
+    Ok(versions)
+}
+
+fn lock_diff(
+    old: &BTreeMap<String, BTreeSet<String>>,
+    new: &BTreeMap<String, BTreeSet<String>>,
+) -> Vec<DependencyChange> {
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let from = old.get(name);
+            let to = new.get(name);
+            if from == to {
+                return None;
+            }
+            Some(DependencyChange {
+                name: name.clone(),
+                from: from.map(|v| v.iter().cloned().collect()),
+                to: to.map(|v| v.iter().cloned().collect()),
+            })
+        })
+        .collect()
+}
+
+fn to_markdown(changelog: &Changelog) -> String {
+    const SECTIONS: [(&str, &str); 4] = [
+        ("feat", "Features"),
+        ("fix", "Bug Fixes"),
+        ("perf", "Performance"),
+        ("", "Other Changes"),
+    ];
+
+    let mut out = String::new();
+    for member in &changelog.members {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        writeln!(out, "## {} {}", member.name, member.version).unwrap();
+
+        let entry = |change: &Change| {
+            let mut line = String::from("- ");
+            if let Some(scope) = &change.scope {
+                write!(line, "**{}:** ", scope).unwrap();
+            }
+            write!(line, "{} ({})", change.description, change.commit).unwrap();
+            line
+        };
+        let mut section = |title: &str, lines: Vec<String>| {
+            if !lines.is_empty() {
+                writeln!(out, "\n### {}\n", title).unwrap();
+                for line in lines {
+                    writeln!(out, "{}", line).unwrap();
+                }
+            }
+        };
+
+        section(
+            "Breaking Changes",
+            member
+                .changes
+                .iter()
+                .filter(|c| c.breaking)
+                .map(entry)
+                .collect(),
+        );
+        for (kind, title) in SECTIONS {
+            let known = |c: &&Change| {
+                SECTIONS
+                    .iter()
+                    .any(|(k, _)| !k.is_empty() && c.kind.as_deref() == Some(*k))
+            };
+            let lines = member
+                .changes
+                .iter()
+                .filter(|c| !c.breaking)
+                .filter(|c| {
+                    if kind.is_empty() {
+                        !known(c)
+                    } else {
+                        c.kind.as_deref() == Some(kind)
+                    }
+                })
+                .map(entry)
+                .collect();
+            section(title, lines);
+        }
+        section(
+            "Dependencies",
+            member
+                .dependencies
+                .iter()
+                .map(|dep| match (&dep.from, &dep.to) {
+                    (Some(from), Some(to)) => format!(
+                        "- Bump `{}` from {} to {}",
+                        dep.name,
+                        from.join(", "),
+                        to.join(", ")
+                    ),
+                    (None, Some(to)) => format!("- Add `{}` {}", dep.name, to.join(", ")),
+                    (Some(from), None) => {
+                        format!("- Remove `{}` {}", dep.name, from.join(", "))
+                    }
+                    (None, None) => unreachable!(),
+                })
+                .collect(),
+        );
+    }
+    out
+}
@@ -138,6 +138,16 @@ pub fn clean(ws: &Workspace<'_>, opts: &CleanOptions<'_>) -> CargoResult<()> {
         let pkg_dir = format!("{}-*", pkg.name());
         progress.on_cleaning_package(&pkg.name())?;
 
+        // With the v2 layout, the directories of the package are only known
+        // from the layout manifest.
+        for (_, layout) in &layouts_with_host {
+            for dir in layout.package_dirs(&pkg.name()) {
+                for parent in [layout.fingerprint(), layout.build(), layout.artifact()] {
+                    rm_rf(&parent.join(&dir), config, &mut progress)?;
+                }
+            }
+        }
+
         // Clean fingerprints.
         for (_, layout) in &layouts_with_host {
             let dir = escape_glob_path(layout.fingerprint())?;
@@ -304,6 +314,41 @@ fn rm_rf(path: &Path, config: &Config, progress: &mut dyn CleaningProgressBar) -
     Ok(())
 }
 
+/// Removes the global target directories (`build.target-dir = "global"`) of
+/// workspaces that no longer exist.
+pub fn clean_global_target_dirs(config: &Config) -> CargoResult<()> {
+    let dirs = config.global_target_dirs()?.into_path_unlocked();
+    let entries = match fs::read_dir(&dirs) {
+        Ok(entries) => entries.collect::<Result<Vec<_>, _>>()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read `{}`", dirs.display()));
+        }
+    };
+    let mut removed = 0;
+    for entry in entries {
+        let target_dir = entry.path();
+        // Directories without a recorded workspace weren't created by Cargo.
+        let root = match Layout::global_workspace_root(&target_dir) {
+            Some(root) => root,
+            None => continue,
+        };
+        if root.join("Cargo.toml").exists() {
+            continue;
+        }
+        clean_entire_folder(&target_dir, config)?;
+        removed += 1;
+    }
+    config.shell().status(
+        "Removed",
+        format!(
+            "{} global target director{}",
+            removed,
+            if removed == 1 { "y" } else { "ies" }
+        ),
+    )
+}
+
 fn clean_entire_folder(path: &Path, config: &Config) -> CargoResult<()> {
     let num_paths = walkdir::WalkDir::new(path).into_iter().count();
     let mut progress = CleaningFolderBar::new(config, num_paths);
@@ -40,13 +40,15 @@ use crate::core::compiler::unit_graph::{self, UnitDep, UnitGraph};
 use crate::core::compiler::{standard_lib, CrateType, TargetInfo};
 use crate::core::compiler::{BuildConfig, BuildContext, Compilation, Context};
 use crate::core::compiler::{CompileKind, CompileMode, CompileTarget, RustcTargetData, Unit};
-use crate::core::compiler::{DefaultExecutor, Executor, UnitInterner};
+use crate::core::compiler::{DefaultExecutor, Executor, RemoteExecutor, UnitInterner};
 use crate::core::dependency::DepKind;
 use crate::core::profiles::{Profiles, UnitFor};
-use crate::core::resolver::features::{self, CliFeatures, FeaturesFor};
+use crate::core::resolver::features::{
+    self, CliFeatures, FeatureOpts, FeatureResolver, FeaturesFor, ForceAllTargets,
+};
 use crate::core::resolver::{HasDevUnits, Resolve};
 use crate::core::{FeatureValue, Package, PackageSet, Shell, Summary, Target};
-use crate::core::{PackageId, SourceId, TargetKind, Workspace};
+use crate::core::{PackageId, PackageIdSpec, SourceId, TargetKind, Workspace};
 use crate::drop_println;
 use crate::ops;
 use crate::ops::resolve::WorkspaceResolve;
@@ -91,9 +93,16 @@ pub struct CompileOptions {
     /// Whether the `--document-private-items` flags was specified and should
     /// be forwarded to `rustdoc`.
     pub rustdoc_document_private_items: bool,
+    /// Whether `--include-private` was specified, forwarding
+    /// `--document-private-items` to `rustdoc` for workspace members only.
+    pub rustdoc_document_private_members: bool,
     /// Whether the build process should check the minimum Rust version
     /// defined in the cargo metadata for a crate.
     pub honor_rust_version: bool,
+    /// Whether `--auto-required-features` was specified, building targets
+    /// whose `required-features` aren't enabled with their own features
+    /// instead of skipping them.
+    pub auto_required_features: bool,
 }
 
 impl CompileOptions {
@@ -111,16 +120,22 @@ impl CompileOptions {
             target_rustc_args: None,
             target_rustc_crate_types: None,
             rustdoc_document_private_items: false,
+            rustdoc_document_private_members: false,
             honor_rust_version: true,
+            auto_required_features: false,
         })
     }
 }
 
 /// Compiles!
 ///
-/// This uses the [`DefaultExecutor`]. To use a custom [`Executor`], see [`compile_with_exec`].
+/// This uses the [`DefaultExecutor`], or the [`RemoteExecutor`] configured
+/// with `build.executor`. To use a custom [`Executor`], see [`compile_with_exec`].
 pub fn compile<'a>(ws: &Workspace<'a>, options: &CompileOptions) -> CargoResult<Compilation<'a>> {
-    let exec: Arc<dyn Executor> = Arc::new(DefaultExecutor);
+    let exec: Arc<dyn Executor> = match RemoteExecutor::from_config(ws.config())? {
+        Some(exec) => Arc::new(exec),
+        None => Arc::new(DefaultExecutor),
+    };
     compile_with_exec(ws, options, &exec)
 }
 
@@ -146,7 +161,18 @@ pub fn compile_ws<'a>(
     let interner = UnitInterner::new();
     let bcx = create_bcx(ws, options, &interner)?;
     if options.build_config.unit_graph {
-        unit_graph::emit_serialized_unit_graph(&bcx.roots, &bcx.unit_graph, ws.config())?;
+        // The build manifests are only known from a previous build.
+        let build_manifests = if ws.config().cli_unstable().build_manifest {
+            Context::new(&bcx)?.previous_build_manifests()?
+        } else {
+            HashMap::new()
+        };
+        unit_graph::emit_serialized_unit_graph(
+            &bcx.roots,
+            &bcx.unit_graph,
+            &build_manifests,
+            ws.config(),
+        )?;
         return Compilation::new(&bcx);
     }
     let _p = profile::start("compiling");
@@ -206,7 +232,9 @@ pub fn create_bcx<'a, 'cfg>(
         ref target_rustc_args,
         ref target_rustc_crate_types,
         rustdoc_document_private_items,
+        rustdoc_document_private_members,
         honor_rust_version,
+        auto_required_features,
     } = *options;
     let config = ws.config();
 
@@ -233,7 +261,10 @@ pub fn create_bcx<'a, 'cfg>(
     }
     config.validate_term_config()?;
 
-    let target_data = RustcTargetData::new(ws, &build_config.requested_kinds)?;
+    let mut target_data = RustcTargetData::new(ws, &build_config.requested_kinds)?;
+    if let Some(required_toolchain) = &build_config.required_toolchain {
+        required_toolchain.verify(&target_data.rustc)?;
+    }
 
     let specs = spec.to_package_id_specs(ws)?;
     let has_dev_units = {
@@ -257,27 +288,48 @@ pub fn create_bcx<'a, 'cfg>(
             HasDevUnits::No
         }
     };
+    // With `--auto-required-features`, the dependencies of every feature
+    // are resolved, so that targets can enable their required features on
+    // their own. The features are resolved again below.
+    let resolve_features = if auto_required_features {
+        CliFeatures::new_all(true)
+    } else {
+        cli_features.clone()
+    };
     let resolve = ops::resolve_ws_with_opts(
         ws,
         &target_data,
         &build_config.requested_kinds,
-        cli_features,
+        &resolve_features,
         &specs,
         has_dev_units,
-        crate::core::resolver::features::ForceAllTargets::No,
+        ForceAllTargets::No,
     )?;
     let WorkspaceResolve {
         mut pkg_set,
         workspace_resolve,
         targeted_resolve: resolve,
-        resolved_features,
+        mut resolved_features,
     } = resolve;
+    if auto_required_features {
+        resolved_features = resolve_required_features(
+            ws,
+            &target_data,
+            &resolve,
+            &pkg_set,
+            cli_features,
+            &specs,
+            &build_config.requested_kinds,
+            has_dev_units,
+        )?;
+    }
 
-    let std_resolve_features = if let Some(crates) = &config.cli_unstable().build_std {
-        let (std_package_set, std_resolve, std_features) =
-            standard_lib::resolve_std(ws, &target_data, &build_config, crates)?;
+    let std_resolve = if let Some(crates) = standard_lib::requested_std_crates(config)? {
+        let kinds = standard_lib::std_kinds(&target_data);
+        let (std_package_set, std_resolve) =
+            standard_lib::resolve_std(ws, &target_data, &build_config, &crates, &kinds)?;
         pkg_set.add_set(std_package_set);
-        Some((std_resolve, std_features))
+        Some(std_resolve)
     } else {
         None
     };
@@ -335,20 +387,11 @@ pub fn create_bcx<'a, 'cfg>(
     // assuming `--target $HOST` was specified. See
     // `rebuild_unit_graph_shared` for more on why this is done.
     let explicit_host_kind = CompileKind::Target(CompileTarget::new(&target_data.rustc.host)?);
-    let explicit_host_kinds: Vec<_> = build_config
-        .requested_kinds
-        .iter()
-        .map(|kind| match kind {
-            CompileKind::Host => explicit_host_kind,
-            CompileKind::Target(t) => CompileKind::Target(*t),
-        })
-        .collect();
 
-    // Passing `build_config.requested_kinds` instead of
-    // `explicit_host_kinds` here so that `generate_targets` can do
-    // its own special handling of `CompileKind::Host`. It will
-    // internally replace the host kind by the `explicit_host_kind`
-    // before setting as a unit.
+    // Passing `build_config.requested_kinds` here so that
+    // `generate_targets` can do its own special handling of
+    // `CompileKind::Host`. It will internally replace the host kind by
+    // the `explicit_host_kind` before setting as a unit.
     let mut units = generate_targets(
         ws,
         &to_builds,
@@ -402,13 +445,10 @@ pub fn create_bcx<'a, 'cfg>(
         Vec::new()
     };
 
-    let std_roots = if let Some(crates) = standard_lib::std_crates(config, Some(&units)) {
-        let (std_resolve, std_features) = std_resolve_features.as_ref().unwrap();
+    let std_roots = if let Some(crates) = standard_lib::std_crates(config, Some(&units))? {
         standard_lib::generate_std_roots(
             &crates,
-            std_resolve,
-            std_features,
-            &explicit_host_kinds,
+            std_resolve.as_ref().unwrap(),
             &pkg_set,
             interner,
             &profiles,
@@ -422,7 +462,7 @@ pub fn create_bcx<'a, 'cfg>(
         &pkg_set,
         &resolve,
         &resolved_features,
-        std_resolve_features.as_ref(),
+        std_resolve.as_ref(),
         &units,
         &scrape_units,
         &std_roots,
@@ -434,24 +474,27 @@ pub fn create_bcx<'a, 'cfg>(
 
     // TODO: In theory, Cargo should also dedupe the roots, but I'm uncertain
     // what heuristics to use in that case.
-    if build_config.mode == (CompileMode::Doc { deps: true }) {
+    if matches!(build_config.mode, CompileMode::Doc { deps: true, .. }) {
         remove_duplicate_doc(build_config, &units, &mut unit_graph);
     }
 
-    if build_config
+    let has_host_kind = build_config
         .requested_kinds
         .iter()
-        .any(CompileKind::is_host)
+        .any(CompileKind::is_host);
+    if has_host_kind
+        || resolved_features.decouples_members()
+        || resolved_features.decouples_targets()
     {
         // Rebuild the unit graph, replacing the explicit host targets with
         // CompileKind::Host, merging any dependencies shared with build
-        // dependencies.
+        // dependencies or with other workspace members.
         let new_graph = rebuild_unit_graph_shared(
             interner,
             unit_graph,
             &units,
             &scrape_units,
-            explicit_host_kind,
+            has_host_kind.then_some(explicit_host_kind),
         );
         // This would be nicer with destructuring assignment.
         units = new_graph.0;
@@ -459,6 +502,12 @@ pub fn create_bcx<'a, 'cfg>(
         unit_graph = new_graph.2;
     }
 
+    if config.cli_unstable().check_build_sharing {
+        share_check_units_with_build(&units, &mut unit_graph);
+    }
+
+    target_data.load_profile_rustcs(unit_graph.keys())?;
+
     let mut extra_compiler_args = HashMap::new();
     if let Some(args) = extra_args {
         if units.len() != 1 {
@@ -475,11 +524,15 @@ pub fn create_bcx<'a, 'cfg>(
     for unit in units
         .iter()
         .filter(|unit| unit.mode.is_doc() || unit.mode.is_doc_test())
-        .filter(|unit| rustdoc_document_private_items || unit.target.is_bin())
+        .filter(|unit| {
+            rustdoc_document_private_items
+                || unit.target.is_bin()
+                || (rustdoc_document_private_members && ws.is_member(&unit.pkg))
+        })
     {
-        // Add `--document-private-items` rustdoc flag if requested or if
-        // the target is a binary. Binary crates get their private items
-        // documented by default.
+        // Add `--document-private-items` rustdoc flag if requested, either
+        // for all packages or for workspace members, or if the target is a
+        // binary. Binary crates get their private items documented by default.
         let mut args = vec!["--document-private-items".into()];
         if unit.target.is_bin() {
             // This warning only makes sense if it's possible to document private items
@@ -494,20 +547,20 @@ pub fn create_bcx<'a, 'cfg>(
     }
 
     if honor_rust_version {
-        // Remove any pre-release identifiers for easier comparison
-        let current_version = &target_data.rustc.version;
-        let untagged_version = semver::Version::new(
-            current_version.major,
-            current_version.minor,
-            current_version.patch,
-        );
-
         for unit in unit_graph.keys() {
             let version = match unit.pkg.rust_version() {
                 Some(v) => v,
                 None => continue,
             };
 
+            // Remove any pre-release identifiers for easier comparison
+            let current_version = &target_data.rustc_for(unit).version;
+            let untagged_version = semver::Version::new(
+                current_version.major,
+                current_version.minor,
+                current_version.patch,
+            );
+
             let req = semver::VersionReq::parse(version).unwrap();
             if req.matches(&untagged_version) {
                 continue;
@@ -632,7 +685,9 @@ fn generate_targets(
 
         // No need to worry about build-dependencies, roots are never build dependencies.
         let features_for = FeaturesFor::from_for_host(target.proc_macro());
-        let features = resolved_features.activated_features(pkg.package_id(), features_for);
+        let features = resolved_features
+            .for_root(pkg.package_id(), target)
+            .activated_features(pkg.package_id(), features_for);
 
         // If `--target` has not been specified, then the unit
         // graph is built almost like if `--target $HOST` was
@@ -799,11 +854,18 @@ fn generate_targets(
                 Target::is_bin,
                 mode,
             )?);
+            // Examples with dev-dependencies of their own are only built
+            // when selected with `--example` or `--examples`.
+            let example_filter = if all_targets {
+                |t: &Target| t.is_example() && t.scoped_dependencies().is_empty()
+            } else {
+                Target::is_example
+            };
             proposals.extend(list_rule_targets(
                 packages,
                 examples,
                 "example",
-                Target::is_example,
+                example_filter,
                 mode,
             )?);
             proposals.extend(list_rule_targets(
@@ -897,8 +959,17 @@ fn generate_targets(
                     &mut config.shell(),
                 )?;
 
-                let features = features_map.entry(pkg).or_insert_with(|| {
-                    resolve_all_features(resolve, resolved_features, package_set, pkg.package_id())
+                // Targets built with their own features with
+                // `--auto-required-features` have them all available.
+                let own_features = resolved_features.for_target(pkg.package_id(), target);
+                let key = (pkg, own_features.map(|_| target));
+                let features = features_map.entry(key).or_insert_with(|| {
+                    resolve_all_features(
+                        resolve,
+                        own_features.unwrap_or(resolved_features),
+                        package_set,
+                        pkg.package_id(),
+                    )
                 });
                 rf.iter().filter(|f| !features.contains(*f)).collect()
             }
@@ -1102,6 +1173,64 @@ pub fn resolve_all_features(
     features
 }
 
+/// Resolves the features of the build for `--auto-required-features`, with
+/// each target of the selected packages whose `required-features` aren't
+/// enabled getting its own feature set with them enabled.
+///
+/// `resolve` must include the dependencies of every feature.
+fn resolve_required_features<'cfg>(
+    ws: &Workspace<'cfg>,
+    target_data: &RustcTargetData<'cfg>,
+    resolve: &Resolve,
+    package_set: &PackageSet<'cfg>,
+    cli_features: &CliFeatures,
+    specs: &[PackageIdSpec],
+    requested_kinds: &[CompileKind],
+    has_dev_units: HasDevUnits,
+) -> CargoResult<features::ResolvedFeatures> {
+    let opts = FeatureOpts::new(ws, has_dev_units, ForceAllTargets::No)?;
+    let mut resolved_features = FeatureResolver::resolve(
+        ws,
+        target_data,
+        resolve,
+        package_set,
+        cli_features,
+        specs,
+        requested_kinds,
+        opts,
+    )?;
+    let member_features = ws.members_with_features(specs, cli_features)?;
+    let mut targets = Vec::new();
+    for (member, _) in &member_features {
+        let pkg_id = member.package_id();
+        let features = resolve_all_features(resolve, &resolved_features, package_set, pkg_id);
+        for target in member.targets() {
+            let required_features = match target.required_features() {
+                Some(rf) => rf,
+                None => continue,
+            };
+            if !required_features.iter().all(|f| features.contains(f)) {
+                targets.push((pkg_id, target.clone()));
+            }
+        }
+    }
+    let member_features: Vec<_> = member_features
+        .into_iter()
+        .map(|(member, features)| (member.package_id(), features))
+        .collect();
+    FeatureResolver::resolve_required_features(
+        ws,
+        target_data,
+        resolve,
+        package_set,
+        requested_kinds,
+        &member_features,
+        &targets,
+        &mut resolved_features,
+    )?;
+    Ok(resolved_features)
+}
+
 /// Given a list of all targets for a package, filters out only the targets
 /// that are automatically included when the user doesn't specify any targets.
 fn filter_default_targets(targets: &[Target], mode: CompileMode) -> Vec<&Target> {
@@ -1109,7 +1238,7 @@ fn filter_default_targets(targets: &[Target], mode: CompileMode) -> Vec<&Target>
         CompileMode::Bench => targets.iter().filter(|t| t.benched()).collect(),
         CompileMode::Test => targets
             .iter()
-            .filter(|t| t.tested() || t.is_example())
+            .filter(|t| t.tested() || (t.is_example() && t.scoped_dependencies().is_empty()))
             .collect(),
         CompileMode::Build | CompileMode::Check { .. } => targets
             .iter()
@@ -1241,7 +1370,8 @@ fn filter_targets<'a>(
 ///
 /// This will translate any unit's `CompileKind::Target(host)` to
 /// `CompileKind::Host` if the kind is equal to `to_host`. This also handles
-/// generating the unit `dep_hash`, and merging shared units if possible.
+/// generating the unit `dep_hash`, and merging shared units if possible,
+/// including the units of workspace members with decoupled features.
 ///
 /// This is necessary because if normal dependencies used `CompileKind::Host`,
 /// there would be no way to distinguish those units from build-dependency
@@ -1262,7 +1392,7 @@ fn rebuild_unit_graph_shared(
     unit_graph: UnitGraph,
     roots: &[Unit],
     scrape_units: &[Unit],
-    to_host: CompileKind,
+    to_host: Option<CompileKind>,
 ) -> (Vec<Unit>, Vec<Unit>, UnitGraph) {
     let mut result = UnitGraph::new();
     // Map of the old unit to the new unit, used to avoid recursing into units
@@ -1295,7 +1425,7 @@ fn traverse_and_share(
     new_graph: &mut UnitGraph,
     unit_graph: &UnitGraph,
     unit: &Unit,
-    to_host: CompileKind,
+    to_host: Option<CompileKind>,
 ) -> Unit {
     if let Some(new_unit) = memo.get(unit) {
         // Already computed, no need to recompute.
@@ -1315,7 +1445,7 @@ fn traverse_and_share(
         })
         .collect();
     let new_dep_hash = dep_hash.finish();
-    let new_kind = if unit.kind == to_host {
+    let new_kind = if Some(unit.kind) == to_host {
         CompileKind::Host
     } else {
         unit.kind
@@ -1336,6 +1466,109 @@ fn traverse_and_share(
     new_unit
 }
 
+/// Replaces the units checking a library by the unit building the same
+/// library, if the graph has one (`-Zcheck-build-sharing`).
+///
+/// Both would write the same `.rmeta` file, see
+/// [`BuildContext::hashed_mode`]. This typically happens for a library used
+/// both as a normal dependency and as a build-dependency of `cargo check`.
+///
+/// This runs after [`rebuild_unit_graph_shared`], once normal and build
+/// dependencies for the host use the same `CompileKind`. Dependencies are
+/// visited first, so a checked library is only replaced if its own
+/// dependencies (after replacement) are the ones of the built library.
+/// Roots are left alone.
+///
+/// [`BuildContext::hashed_mode`]: crate::core::compiler::BuildContext::hashed_mode
+fn share_check_units_with_build(root_units: &[Unit], unit_graph: &mut UnitGraph) {
+    let mut builds: HashMap<(PackageId, CompileKind), Vec<Unit>> = HashMap::new();
+    for unit in unit_graph.keys() {
+        if unit.mode == CompileMode::Build && unit.target.is_lib() {
+            builds
+                .entry((unit.pkg.package_id(), unit.kind))
+                .or_default()
+                .push(unit.clone());
+        }
+    }
+
+    fn visit(
+        unit: &Unit,
+        graph: &UnitGraph,
+        root_units: &[Unit],
+        builds: &HashMap<(PackageId, CompileKind), Vec<Unit>>,
+        visited: &mut HashSet<Unit>,
+        replacements: &mut HashMap<Unit, Unit>,
+    ) {
+        if !visited.insert(unit.clone()) {
+            return;
+        }
+        for dep in &graph[unit] {
+            visit(&dep.unit, graph, root_units, builds, visited, replacements);
+        }
+        if !unit.is_shareable_check() || root_units.contains(unit) {
+            return;
+        }
+        let deps: HashSet<&Unit> = graph[unit]
+            .iter()
+            .map(|dep| replacements.get(&dep.unit).unwrap_or(&dep.unit))
+            .collect();
+        let build = builds
+            .get(&(unit.pkg.package_id(), unit.kind))
+            .into_iter()
+            .flatten()
+            .find(|build| {
+                build.target == unit.target
+                    && build.profile == unit.profile
+                    && build.features == unit.features
+                    && build.is_std == unit.is_std
+                    && build.artifact == unit.artifact
+                    && graph[*build]
+                        .iter()
+                        .map(|dep| &dep.unit)
+                        .collect::<HashSet<_>>()
+                        == deps
+            });
+        if let Some(build) = build {
+            replacements.insert(unit.clone(), build.clone());
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut replacements = HashMap::new();
+    let mut units: Vec<_> = unit_graph.keys().cloned().collect();
+    units.sort();
+    for unit in &units {
+        visit(
+            unit,
+            unit_graph,
+            root_units,
+            &builds,
+            &mut visited,
+            &mut replacements,
+        );
+    }
+    if replacements.is_empty() {
+        return;
+    }
+    for unit in replacements.keys() {
+        log::debug!(
+            "sharing the build of package {} target `{}` with check",
+            unit.pkg,
+            unit.target.name()
+        );
+        unit_graph.remove(unit);
+    }
+    for deps in unit_graph.values_mut() {
+        for dep in deps.iter_mut() {
+            if let Some(build) = replacements.get(&dep.unit) {
+                dep.unit = build.clone();
+            }
+        }
+        deps.sort();
+        deps.dedup();
+    }
+}
+
 /// Removes duplicate CompileMode::Doc units that would cause problems with
 /// filename collisions.
 ///
@@ -71,7 +71,7 @@ impl Packages {
                 let (mut patterns, packages) = opt_patterns_and_names(opt_in)?;
                 let mut specs = packages
                     .iter()
-                    .map(|p| PackageIdSpec::parse(p))
+                    .map(|p| PackageIdSpec::parse_qualified(p, ws.config()))
                     .collect::<CargoResult<Vec<_>>>()?;
                 if !patterns.is_empty() {
                     let matched_pkgs = ws
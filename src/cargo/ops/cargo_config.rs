@@ -1,12 +1,14 @@
 //! Implementation of `cargo config` subcommand.
 
-use crate::util::config::{Config, ConfigKey, ConfigValue as CV, Definition};
+use crate::util::config::{Config, ConfigKey, ConfigValue as CV, Definition, MACHINE_PROFILE_KEYS};
 use crate::util::errors::CargoResult;
 use crate::{drop_eprintln, drop_println};
-use anyhow::{bail, format_err, Error};
+use anyhow::{bail, format_err, Context as _, Error};
+use cargo_util::paths;
 use serde_json::json;
 use std::borrow::Cow;
 use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 pub enum ConfigFormat {
@@ -51,9 +53,9 @@ pub struct GetOptions<'a> {
 }
 
 pub fn get(config: &Config, opts: &GetOptions<'_>) -> CargoResult<()> {
-    if opts.show_origin && !matches!(opts.format, ConfigFormat::Toml) {
+    if opts.show_origin && matches!(opts.format, ConfigFormat::JsonValue) {
         bail!(
-            "the `{}` format does not support --show-origin, try the `toml` format instead",
+            "the `{}` format does not support --show-origin, try the `json` format instead",
             opts.format
         );
     }
@@ -65,10 +67,18 @@ pub fn get(config: &Config, opts: &GetOptions<'_>) -> CargoResult<()> {
         let cv = config
             .get_cv_with_env(&key)?
             .ok_or_else(|| format_err!("config value `{}` is not set", key))?;
+        let origins = if opts.show_origin {
+            Some(Origins {
+                config,
+                unmerged: config.load_values_unmerged()?,
+            })
+        } else {
+            None
+        };
         match opts.format {
-            ConfigFormat::Toml => print_toml(config, opts, &key, &cv),
-            ConfigFormat::Json => print_json(config, &key, &cv, true),
-            ConfigFormat::JsonValue => print_json(config, &key, &cv, false),
+            ConfigFormat::Toml => print_toml(config, opts, origins.as_ref(), &key, &cv),
+            ConfigFormat::Json => print_json(config, origins.as_ref(), &key, &cv, true),
+            ConfigFormat::JsonValue => print_json(config, None, &key, &cv, false),
         }
         if let Some(env) = maybe_env(config, &key, &cv) {
             match opts.format {
@@ -113,12 +123,77 @@ fn maybe_env<'config>(
     }
 }
 
-fn print_toml(config: &Config, opts: &GetOptions<'_>, key: &ConfigKey, cv: &CV) {
+/// Used with `--show-origin` to find the definitions that a merged value
+/// takes precedence over.
+struct Origins<'cfg> {
+    config: &'cfg Config,
+    /// The config files, in the order of precedence.
+    unmerged: Vec<CV>,
+}
+
+impl Origins<'_> {
+    /// Returns the definitions of `key` that are overridden by `def`, in
+    /// order of precedence.
+    ///
+    /// Only non-list values are considered, since lists are merged.
+    fn overridden(&self, key: &ConfigKey, def: &Definition) -> Vec<Definition> {
+        let mut result = Vec::new();
+        if let Definition::Cli(_) = def {
+            let env_key = key.as_env_key();
+            if self.config.env().contains_key(env_key) {
+                result.push(Definition::Environment(env_key.to_string()));
+            }
+        }
+        for file in &self.unmerged {
+            let mut cv = file;
+            for part in key.parts() {
+                match cv {
+                    CV::Table(map, _def) => match map.get(part) {
+                        Some(val) => cv = val,
+                        None => break,
+                    },
+                    _ => break,
+                }
+            }
+            match cv {
+                CV::Table(..) | CV::List(..) => {}
+                _ => {
+                    let other = cv.definition();
+                    let same_file = match (def, other) {
+                        (Definition::Path(a), Definition::Path(b)) => a == b,
+                        _ => false,
+                    };
+                    if !same_file {
+                        result.push(other.clone());
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+fn print_toml(
+    config: &Config,
+    opts: &GetOptions<'_>,
+    origins: Option<&Origins<'_>>,
+    key: &ConfigKey,
+    cv: &CV,
+) {
     let origin = |def: &Definition| -> String {
         if !opts.show_origin {
             return "".to_string();
         }
-        format!(" # {}", def)
+        let overridden = match origins {
+            Some(origins) => origins.overridden(key, def),
+            None => Vec::new(),
+        };
+        if overridden.is_empty() {
+            format!(" # {}", def)
+        } else {
+            let overridden: Vec<_> = overridden.iter().map(|def| def.to_string()).collect();
+            format!(" # {}, overriding {}", def, overridden.join(", "))
+        }
     };
     match cv {
         CV::Boolean(val, def) => drop_println!(config, "{} = {}{}", key, val, origin(def)),
@@ -155,7 +230,7 @@ fn print_toml(config: &Config, opts: &GetOptions<'_>, key: &ConfigKey, cv: &CV)
                 // push or push_sensitive shouldn't matter here, since this is
                 // not dealing with environment variables.
                 subkey.push(table_key);
-                print_toml(config, opts, &subkey, val);
+                print_toml(config, opts, origins, &subkey, val);
             }
         }
     }
@@ -183,9 +258,15 @@ fn print_json_env(config: &Config, env: &[(&String, &String)]) {
     }
 }
 
-fn print_json(config: &Config, key: &ConfigKey, cv: &CV, include_key: bool) {
+fn print_json(
+    config: &Config,
+    origins: Option<&Origins<'_>>,
+    key: &ConfigKey,
+    cv: &CV,
+    include_key: bool,
+) {
     let json_value = if key.is_root() || !include_key {
-        cv_to_json(cv)
+        cv_to_json(origins, key, cv)
     } else {
         let mut parts: Vec<_> = key.parts().collect();
         let last_part = parts.pop().unwrap();
@@ -196,25 +277,48 @@ fn print_json(config: &Config, key: &ConfigKey, cv: &CV, include_key: bool) {
             table[part] = json!({});
             table = table.get_mut(part).unwrap();
         }
-        table[last_part] = cv_to_json(cv);
+        table[last_part] = cv_to_json(origins, key, cv);
         root_table
     };
     drop_println!(config, "{}", serde_json::to_string(&json_value).unwrap());
 
     // Helper for recursively converting a CV to JSON.
-    fn cv_to_json(cv: &CV) -> serde_json::Value {
+    //
+    // With `--show-origin`, each value is replaced with an object holding the
+    // value and where it is defined.
+    fn cv_to_json(origins: Option<&Origins<'_>>, key: &ConfigKey, cv: &CV) -> serde_json::Value {
+        let with_origin = |val: serde_json::Value, def: &Definition| match origins {
+            Some(origins) => {
+                let mut entry = json!({ "value": val, "origin": def.to_string() });
+                let overridden = origins.overridden(key, def);
+                if !overridden.is_empty() {
+                    let overridden: Vec<_> = overridden.iter().map(|d| d.to_string()).collect();
+                    entry["overrides"] = json!(overridden);
+                }
+                entry
+            }
+            None => val,
+        };
         match cv {
-            CV::Boolean(val, _def) => json!(val),
-            CV::Integer(val, _def) => json!(val),
-            CV::String(val, _def) => json!(val),
+            CV::Boolean(val, def) => with_origin(json!(val), def),
+            CV::Integer(val, def) => with_origin(json!(val), def),
+            CV::String(val, def) => with_origin(json!(val), def),
             CV::List(vals, _def) => {
-                let jvals: Vec<_> = vals.iter().map(|(val, _def)| json!(val)).collect();
+                let jvals: Vec<_> = vals
+                    .iter()
+                    .map(|(val, def)| match origins {
+                        Some(_) => json!({ "value": val, "origin": def.to_string() }),
+                        None => json!(val),
+                    })
+                    .collect();
                 json!(jvals)
             }
             CV::Table(map, _def) => {
                 let mut table = json!({});
-                for (key, val) in map {
-                    table[key] = cv_to_json(val);
+                for (table_key, val) in map {
+                    let mut subkey = key.clone();
+                    subkey.push(table_key);
+                    table[table_key] = cv_to_json(origins, &subkey, val);
                 }
                 table
             }
@@ -225,7 +329,7 @@ fn print_json(config: &Config, key: &ConfigKey, cv: &CV, include_key: bool) {
 fn print_toml_unmerged(config: &Config, opts: &GetOptions<'_>, key: &ConfigKey) -> CargoResult<()> {
     let print_table = |cv: &CV| {
         drop_println!(config, "# {}", cv.definition());
-        print_toml(config, opts, &ConfigKey::new(), cv);
+        print_toml(config, opts, None, &ConfigKey::new(), cv);
         drop_println!(config, "");
     };
     // This removes entries from the given CV so that all that remains is the
@@ -309,3 +413,234 @@ fn print_toml_unmerged(config: &Config, opts: &GetOptions<'_>, key: &ConfigKey)
     }
     Ok(())
 }
+
+/// Implementation of `cargo config lint`.
+///
+/// Checks the `[machine-profiles]` tables for unknown keys, and shows where
+/// each value of the selected machine profile comes from.
+pub fn lint(config: &Config) -> CargoResult<()> {
+    if let Some(CV::Table(profiles, _def)) =
+        config.get_cv_with_env(&ConfigKey::from_str("machine-profiles"))?
+    {
+        let mut profiles: Vec<_> = profiles.into_iter().collect();
+        profiles.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, profile) in profiles {
+            let table = match profile {
+                CV::Table(table, _def) => table,
+                cv => {
+                    config.shell().warn(format!(
+                        "machine profile `{}` should be a table, but found {} in {}",
+                        name,
+                        cv.desc(),
+                        cv.definition()
+                    ))?;
+                    continue;
+                }
+            };
+            let mut keys: Vec<_> = table.iter().collect();
+            keys.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, value) in keys {
+                if !MACHINE_PROFILE_KEYS.iter().any(|(k, _)| k == key) {
+                    config.shell().warn(format!(
+                        "unknown key `{}` in machine profile `{}` ({})",
+                        key,
+                        name,
+                        value.definition()
+                    ))?;
+                }
+            }
+        }
+    }
+
+    let profile = match config.machine_profile() {
+        Some(profile) => profile,
+        None => {
+            drop_println!(config, "# no machine profile selected");
+            return Ok(());
+        }
+    };
+    drop_println!(
+        config,
+        "# machine profile `{}` selected by {}",
+        profile.name,
+        profile.selected_by
+    );
+    drop_println!(
+        config,
+        "# precedence: command-line flags > --config > environment variables \
+         > machine profile > config files"
+    );
+    for entry in &profile.entries {
+        let key = ConfigKey::from_str(&entry.key);
+        let env_key = key.as_env_key();
+        let shadowed_by = match &entry.shadowed_by {
+            Some(def) => Some(def.to_string()),
+            None if config.env().contains_key(env_key) => {
+                Some(format!("environment variable `{}`", env_key))
+            }
+            None => None,
+        };
+        let cv = config.get_cv_with_env(&key)?;
+        let value = match &cv {
+            Some(CV::Boolean(val, _def)) => val.to_string(),
+            Some(CV::Integer(val, _def)) => val.to_string(),
+            Some(CV::String(val, _def)) => toml_edit::Value::from(val).to_string(),
+            Some(CV::List(vals, _def)) => vals
+                .iter()
+                .map(|x| &x.0)
+                .collect::<toml_edit::Array>()
+                .to_string(),
+            Some(CV::Table(..)) | None => continue,
+        };
+        match shadowed_by {
+            Some(shadowed_by) => drop_println!(
+                config,
+                "{} = {} # {}, shadowing machine profile value from {}",
+                entry.key,
+                value,
+                shadowed_by,
+                entry.definition
+            ),
+            None => match &entry.overrides {
+                Some(overrides) => drop_println!(
+                    config,
+                    "{} = {} # {}, overriding {}",
+                    entry.key,
+                    value,
+                    entry.definition,
+                    overrides
+                ),
+                None => drop_println!(config, "{} = {} # {}", entry.key, value, entry.definition),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Which config file `cargo config set` edits.
+pub enum SetLocation {
+    /// The file that currently defines the key, or the config file in the
+    /// current directory if it is not defined in any file.
+    Auto,
+    /// The config file in `$CARGO_HOME`.
+    Global,
+    /// An explicit path to a config file.
+    File(PathBuf),
+}
+
+/// Options for `cargo config set`.
+pub struct SetOptions<'a> {
+    pub key: &'a str,
+    pub value: &'a str,
+    pub location: SetLocation,
+}
+
+/// Implementation of `cargo config set`.
+///
+/// Edits the config file in place with `toml_edit`, so that the formatting and
+/// comments of the rest of the file are preserved.
+pub fn set(config: &Config, opts: &SetOptions<'_>) -> CargoResult<()> {
+    let mut keys = toml_edit::Key::parse(opts.key)
+        .map_err(|e| format_err!("invalid config key `{}`: {}", opts.key, e))?;
+    let mut key = ConfigKey::new();
+    for k in &mut keys {
+        // Use the default whitespace around the key rather than what was
+        // parsed from the command line.
+        k.decor_mut().clear();
+        key.push(k.get());
+    }
+    let (last, parents) = keys.split_last().expect("parsed key is never empty");
+
+    let mut value = match opts.value.parse::<toml_edit::Value>() {
+        // Floats and datetimes are not supported by config, so treat them
+        // like any other unquoted string.
+        Ok(toml_edit::Value::Float(..)) | Ok(toml_edit::Value::Datetime(..)) | Err(_) => {
+            toml_edit::Value::from(opts.value)
+        }
+        Ok(value) => value,
+    };
+    value.decor_mut().clear();
+    let display_value = value.to_string();
+
+    let path = match &opts.location {
+        SetLocation::File(path) => config.cwd().join(path),
+        SetLocation::Global => {
+            let home = config.home().as_path_unlocked();
+            match config.get_file_path(home, "config", true)? {
+                Some(path) => path,
+                None => home.join("config.toml"),
+            }
+        }
+        SetLocation::Auto => match config
+            .get_cv_with_env(&key)?
+            .map(|cv| cv.definition().clone())
+        {
+            Some(Definition::Path(path)) => path,
+            _ => {
+                let dir = config.cwd().join(".cargo");
+                match config.get_file_path(&dir, "config", true)? {
+                    Some(path) => path,
+                    None => dir.join("config.toml"),
+                }
+            }
+        },
+    };
+
+    let contents = if path.exists() {
+        paths::read(&path)?
+    } else {
+        String::new()
+    };
+    let mut doc: toml_edit::Document = contents
+        .parse()
+        .with_context(|| format!("could not parse TOML configuration in `{}`", path.display()))?;
+
+    let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    let mut key_so_far = ConfigKey::new();
+    for part in parents {
+        key_so_far.push(part.get());
+        let item = table.entry_format(part).or_insert_with(|| {
+            let mut t = toml_edit::Table::new();
+            t.set_implicit(true);
+            toml_edit::Item::Table(t)
+        });
+        let type_name = item.type_name();
+        table = match item.as_table_like_mut() {
+            Some(t) => t,
+            None => bail!(
+                "expected table for configuration key `{}`, but found {} in {}",
+                key_so_far,
+                type_name,
+                path.display()
+            ),
+        };
+    }
+    match table.get_mut(last.get()) {
+        Some(item) if item.is_table_like() => bail!(
+            "configuration key `{}` is a table in {}, only values can be set",
+            key,
+            path.display()
+        ),
+        Some(item) => {
+            if let Some(old) = item.as_value() {
+                *value.decor_mut() = old.decor().clone();
+            }
+            *item = toml_edit::Item::Value(value);
+        }
+        None => {
+            table
+                .entry_format(last)
+                .or_insert(toml_edit::Item::Value(value));
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        paths::create_dir_all(parent)?;
+    }
+    paths::write(&path, doc.to_string())?;
+    config.shell().status(
+        "Setting",
+        format!("{} = {} in {}", key, display_value, path.display()),
+    )?;
+    Ok(())
+}
@@ -1,7 +1,11 @@
-use crate::core::{Shell, Workspace};
+use crate::core::compiler::{Compilation, CompileKind, CompileMode};
+use crate::core::{Package, PackageId, Shell, TargetKind, Workspace};
 use crate::ops;
 use crate::util::config::PathAndArgs;
 use crate::util::CargoResult;
+use cargo_util::paths;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
@@ -11,6 +15,8 @@ use std::process::Command;
 pub struct DocOptions {
     /// Whether to attempt to open the browser after compiling the docs
     pub open_result: bool,
+    /// Whether to write a `doc-manifest.json` describing the documented crates
+    pub output_manifest: bool,
     /// Options to pass through to the compiler
     pub compile_opts: ops::CompileOptions,
 }
@@ -19,16 +25,35 @@ pub struct DocOptions {
 pub fn doc(ws: &Workspace<'_>, options: &DocOptions) -> CargoResult<()> {
     let compilation = ops::compile(ws, &options.compile_opts)?;
 
+    if options.output_manifest {
+        write_doc_manifests(ws, &compilation)?;
+    }
+
+    let json = matches!(
+        options.compile_opts.build_config.mode,
+        CompileMode::Doc { json: true, .. }
+    );
+    let landing_pages = if ws.config().cli_unstable().doc_landing_page && !json {
+        write_landing_pages(ws, &compilation)?
+    } else {
+        BTreeMap::new()
+    };
+
     if options.open_result {
-        let name = &compilation
-            .root_crate_names
-            .get(0)
-            .ok_or_else(|| anyhow::anyhow!("no crates with documentation"))?;
         let kind = options.compile_opts.build_config.single_requested_kind()?;
-        let path = compilation.root_output[&kind]
-            .with_file_name("doc")
-            .join(&name)
-            .join("index.html");
+        let path = match landing_pages.get(&kind) {
+            Some(path) => path.clone(),
+            None => {
+                let name = &compilation
+                    .root_crate_names
+                    .get(0)
+                    .ok_or_else(|| anyhow::anyhow!("no crates with documentation"))?;
+                compilation.root_output[&kind]
+                    .with_file_name("doc")
+                    .join(&name)
+                    .join("index.html")
+            }
+        };
         if path.exists() {
             let config_browser = {
                 let cfg: Option<PathAndArgs> = ws.config().get("doc.browser")?;
@@ -44,6 +69,131 @@ pub fn doc(ws: &Workspace<'_>, options: &DocOptions) -> CargoResult<()> {
     Ok(())
 }
 
+/// The name of the file listing the documented crates, written to the doc
+/// directory of each compile kind.
+const DOC_MANIFEST: &str = "doc-manifest.json";
+
+#[derive(Serialize)]
+struct DocManifest {
+    /// The documented crates, keyed by crate name.
+    crates: BTreeMap<String, DocManifestCrate>,
+}
+
+#[derive(Serialize)]
+struct DocManifestCrate {
+    package_id: PackageId,
+    kind: TargetKind,
+    /// The directory containing the documentation of the crate.
+    root: PathBuf,
+    index: PathBuf,
+    member: bool,
+}
+
+fn write_doc_manifests(ws: &Workspace<'_>, compilation: &Compilation<'_>) -> CargoResult<()> {
+    let mut manifests: BTreeMap<CompileKind, DocManifest> = BTreeMap::new();
+    for output in &compilation.docs {
+        let unit = &output.unit;
+        let manifest = manifests.entry(unit.kind).or_insert_with(|| DocManifest {
+            crates: BTreeMap::new(),
+        });
+        // A binary with the same name as the library isn't documented, so
+        // names are unique except for colliding dependencies, which already
+        // cause a warning.
+        manifest
+            .crates
+            .entry(unit.target.crate_name())
+            .or_insert_with(|| DocManifestCrate {
+                package_id: unit.pkg.package_id(),
+                kind: unit.target.kind().clone(),
+                root: output.path.parent().unwrap().to_path_buf(),
+                index: output.path.clone(),
+                member: ws.is_member(&unit.pkg),
+            });
+    }
+
+    for (kind, manifest) in manifests {
+        let doc_dir = compilation.root_output[&kind].with_file_name("doc");
+        let mut json = serde_json::to_string_pretty(&manifest)?;
+        json.push('\n');
+        paths::write(doc_dir.join(DOC_MANIFEST), json)?;
+    }
+    Ok(())
+}
+
+/// Writes an `index.html` linking to the documentation of every documented
+/// workspace member, for each compile kind with more than one of them.
+///
+/// Returns the paths of the written pages.
+fn write_landing_pages(
+    ws: &Workspace<'_>,
+    compilation: &Compilation<'_>,
+) -> CargoResult<BTreeMap<CompileKind, PathBuf>> {
+    let mut members: BTreeMap<CompileKind, BTreeMap<String, &Package>> = BTreeMap::new();
+    for output in &compilation.docs {
+        let unit = &output.unit;
+        if !ws.is_member(&unit.pkg) {
+            continue;
+        }
+        members
+            .entry(unit.kind)
+            .or_default()
+            .entry(unit.target.crate_name())
+            .or_insert(&unit.pkg);
+    }
+
+    let title = ws
+        .root()
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "workspace".to_string());
+    let mut pages = BTreeMap::new();
+    for (kind, crates) in members {
+        if crates.len() < 2 {
+            continue;
+        }
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+        html.push_str("<meta charset=\"utf-8\">\n");
+        html.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+        html.push_str("</head>\n<body>\n");
+        html.push_str(&format!("<h1>{}</h1>\n<ul>\n", escape_html(&title)));
+        for (name, pkg) in crates {
+            html.push_str(&format!(
+                "<li><a href=\"{name}/index.html\">{name}</a> {}",
+                pkg.version(),
+                name = escape_html(&name),
+            ));
+            if let Some(description) = &pkg.manifest().metadata().description {
+                html.push_str(&format!(" &mdash; {}", escape_html(description.trim())));
+            }
+            html.push_str("</li>\n");
+        }
+        html.push_str("</ul>\n</body>\n</html>\n");
+
+        let path = compilation.root_output[&kind]
+            .with_file_name("doc")
+            .join("index.html");
+        paths::write(&path, html)?;
+        pages.insert(kind, path);
+    }
+    Ok(pages)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 fn open_docs(
     path: &Path,
     shell: &mut Shell,
@@ -68,8 +68,10 @@ pub fn fetch<'a>(
 
     // If -Zbuild-std was passed, download dependencies for the standard library.
     // We don't know ahead of time what jobs we'll be running, so tell `std_crates` that.
-    if let Some(crates) = standard_lib::std_crates(config, None) {
-        let (std_package_set, _, _) = standard_lib::resolve_std(ws, &data, &build_config, &crates)?;
+    if let Some(crates) = standard_lib::std_crates(config, None)? {
+        let kinds = standard_lib::std_kinds(&data);
+        let (std_package_set, _) =
+            standard_lib::resolve_std(ws, &data, &build_config, &crates, &kinds)?;
         packages.add_set(std_package_set);
     }
 
@@ -1,13 +1,19 @@
 use crate::core::registry::PackageRegistry;
 use crate::core::resolver::features::{CliFeatures, HasDevUnits};
-use crate::core::{PackageId, PackageIdSpec};
-use crate::core::{Resolve, SourceId, Workspace};
+use crate::core::{Dependency, GitReference, PackageId, PackageIdSpec};
+use crate::core::{Resolve, ResolveVersion, SourceId, Workspace};
 use crate::ops;
+use crate::sources::{RegistrySource, SourceConfigMap};
 use crate::util::config::Config;
+use crate::util::interning::InternedString;
 use crate::util::CargoResult;
 use anyhow::Context;
+use cargo_util::paths;
 use log::debug;
-use std::collections::{BTreeMap, HashSet};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use termcolor::Color::{self, Cyan, Green, Red};
 
 pub struct UpdateOptions<'a> {
@@ -17,6 +23,72 @@ pub struct UpdateOptions<'a> {
     pub aggressive: bool,
     pub dry_run: bool,
     pub workspace: bool,
+    /// Accept the checksums the registry index lists for the packages of
+    /// `to_update`, instead of updating them.
+    pub force_checksum: bool,
+    /// Print the changes of the lock file as JSON on stdout.
+    pub json: bool,
+}
+
+/// The changes of the lock file made by `cargo update`, as printed with
+/// `--message-format json`.
+#[derive(Serialize)]
+struct LockfileChanges {
+    dry_run: bool,
+    changes: Vec<LockfileChange>,
+}
+
+#[derive(Serialize)]
+struct LockfileChange {
+    name: InternedString,
+    /// The source of the package, without the revision of git sources.
+    source: String,
+    /// One of `add`, `remove`, `upgrade`, `downgrade`, or `update` for a new
+    /// revision of the same version.
+    action: &'static str,
+    old_version: Option<String>,
+    new_version: Option<String>,
+    old_id: Option<PackageId>,
+    new_id: Option<PackageId>,
+}
+
+impl LockfileChange {
+    fn new(old: Option<PackageId>, new: Option<PackageId>) -> LockfileChange {
+        let id = old.or(new).unwrap();
+        let action = match (old, new) {
+            (Some(old), Some(new)) if new.version() > old.version() => "upgrade",
+            (Some(old), Some(new)) if new.version() < old.version() => "downgrade",
+            (Some(_), Some(_)) => "update",
+            (Some(_), None) => "remove",
+            (None, _) => "add",
+        };
+        LockfileChange {
+            name: id.name(),
+            source: id.source_id().with_precise(None).as_url().to_string(),
+            action,
+            old_version: old.map(|id| id.version().to_string()),
+            new_version: new.map(|id| id.version().to_string()),
+            old_id: old,
+            new_id: new,
+        }
+    }
+}
+
+pub struct UpgradeFormatOptions<'a> {
+    pub config: &'a Config,
+    /// The format to migrate to, which may also be older than the current one.
+    pub to: ResolveVersion,
+    pub dry_run: bool,
+}
+
+pub struct LockFmtOptions<'a> {
+    pub config: &'a Config,
+    pub dry_run: bool,
+}
+
+pub struct LockDedupeOptions<'a> {
+    pub config: &'a Config,
+    pub dry_run: bool,
 }
 
 pub fn generate_lockfile(ws: &Workspace<'_>) -> CargoResult<()> {
@@ -48,10 +120,48 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
     // that we're synchronized against other Cargos.
     let _lock = ws.config().acquire_package_cache_lock()?;
 
+    if opts.force_checksum {
+        let previous_resolve = match ops::load_pkg_lockfile(ws)? {
+            Some(resolve) => resolve,
+            None => anyhow::bail!(
+                "no lock file found at `{}`, there are no checksums to update",
+                ws.lock_file_path().display()
+            ),
+        };
+        return force_checksums(ws, opts, previous_resolve);
+    }
+
     let previous_resolve = match ops::load_pkg_lockfile(ws)? {
         Some(resolve) => resolve,
         None => {
             match opts.precise {
+                None if opts.json => {
+                    // Every package of the new lock file is added.
+                    let mut registry = PackageRegistry::new(opts.config)?;
+                    let mut resolve = ops::resolve_with_previous(
+                        &mut registry,
+                        ws,
+                        &CliFeatures::new_all(true),
+                        HasDevUnits::Yes,
+                        None,
+                        None,
+                        &[],
+                        true,
+                    )?;
+                    let mut ids: Vec<_> = resolve.iter().collect();
+                    ids.sort();
+                    opts.config.shell().print_json(&LockfileChanges {
+                        dry_run: opts.dry_run,
+                        changes: ids
+                            .into_iter()
+                            .map(|id| LockfileChange::new(None, Some(id)))
+                            .collect(),
+                    })?;
+                    if !opts.dry_run {
+                        ops::write_pkg_lockfile(ws, &mut resolve)?;
+                    }
+                    return Ok(());
+                }
                 None => return generate_lockfile(ws),
 
                 // Precise option specified, so calculate a previous_resolve required
@@ -82,8 +192,31 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
         }
     } else {
         let mut sources = Vec::new();
+        // `Cargo.lock` doesn't record the kinds of the dependencies, which
+        // qualifiers in the specs select packages by, so resolve again for them.
+        let mut qualified_resolve = None;
         for name in opts.to_update.iter() {
-            let dep = previous_resolve.query(name)?;
+            let dep = if PackageIdSpec::is_qualified(name) {
+                if qualified_resolve.is_none() {
+                    let mut registry = PackageRegistry::new(opts.config)?;
+                    qualified_resolve = Some(ops::resolve_with_previous(
+                        &mut registry,
+                        ws,
+                        &CliFeatures::new_all(true),
+                        HasDevUnits::Yes,
+                        Some(&previous_resolve),
+                        None,
+                        &[],
+                        true,
+                    )?);
+                }
+                qualified_resolve
+                    .as_ref()
+                    .unwrap()
+                    .query_qualified(name, opts.config)?
+            } else {
+                previous_resolve.query(name)?
+            };
             if opts.aggressive {
                 fill_with_deps(&previous_resolve, dep, &mut to_avoid, &mut HashSet::new());
             } else {
@@ -127,12 +260,52 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
         true,
     )?;
 
-    // Summarize what is changing for the user.
+    let changes = summarize_changes(opts.config, &previous_resolve, &resolve)?;
+    if opts.json {
+        opts.config.shell().print_json(&LockfileChanges {
+            dry_run: opts.dry_run,
+            changes,
+        })?;
+    }
+    if opts.dry_run {
+        opts.config
+            .shell()
+            .warn("not updating lockfile due to dry run")?;
+    } else {
+        ops::write_pkg_lockfile(ws, &mut resolve)?;
+    }
+    return Ok(());
+
+    fn fill_with_deps<'a>(
+        resolve: &'a Resolve,
+        dep: PackageId,
+        set: &mut HashSet<PackageId>,
+        visited: &mut HashSet<PackageId>,
+    ) {
+        if !visited.insert(dep) {
+            return;
+        }
+        set.insert(dep);
+        for (dep, _) in resolve.deps_not_replaced(dep) {
+            fill_with_deps(resolve, dep, set, visited);
+        }
+    }
+}
+
+/// Prints the changes between `previous_resolve` and `resolve` for the user,
+/// and returns them.
+fn summarize_changes(
+    config: &Config,
+    previous_resolve: &Resolve,
+    resolve: &Resolve,
+) -> CargoResult<Vec<LockfileChange>> {
     let print_change = |status: &str, msg: String, color: Color| {
-        opts.config.shell().status_with_color(status, msg, color)
+        config.shell().status_with_color(status, msg, color)
     };
-    for (removed, added) in compare_dependency_graphs(&previous_resolve, &resolve) {
+    let mut changes = Vec::new();
+    for (removed, added) in compare_dependency_graphs(previous_resolve, resolve) {
         if removed.len() == 1 && added.len() == 1 {
+            changes.push(LockfileChange::new(Some(removed[0]), Some(added[0])));
             let msg = if removed[0].source_id().is_git() {
                 format!(
                     "{} -> #{}",
@@ -145,108 +318,545 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
             print_change("Updating", msg, Green)?;
         } else {
             for package in removed.iter() {
+                changes.push(LockfileChange::new(Some(*package), None));
                 print_change("Removing", format!("{}", package), Red)?;
             }
             for package in added.iter() {
+                changes.push(LockfileChange::new(None, Some(*package)));
                 print_change("Adding", format!("{}", package), Cyan)?;
             }
         }
     }
-    if opts.dry_run {
-        opts.config
-            .shell()
-            .warn("not updating lockfile due to dry run")?;
-    } else {
-        ops::write_pkg_lockfile(ws, &mut resolve)?;
+    Ok(changes)
+}
+
+fn compare_dependency_graphs(
+    previous_resolve: &Resolve,
+    resolve: &Resolve,
+) -> Vec<(Vec<PackageId>, Vec<PackageId>)> {
+    fn key(dep: PackageId) -> (&'static str, SourceId) {
+        (dep.name().as_str(), dep.source_id())
     }
-    return Ok(());
 
-    fn fill_with_deps<'a>(
-        resolve: &'a Resolve,
-        dep: PackageId,
-        set: &mut HashSet<PackageId>,
-        visited: &mut HashSet<PackageId>,
-    ) {
-        if !visited.insert(dep) {
-            return;
-        }
-        set.insert(dep);
-        for (dep, _) in resolve.deps_not_replaced(dep) {
-            fill_with_deps(resolve, dep, set, visited);
+    // Removes all package IDs in `b` from `a`. Note that this is somewhat
+    // more complicated because the equality for source IDs does not take
+    // precise versions into account (e.g., git shas), but we want to take
+    // that into account here.
+    fn vec_subtract(a: &[PackageId], b: &[PackageId]) -> Vec<PackageId> {
+        a.iter()
+            .filter(|a| {
+                // If this package ID is not found in `b`, then it's definitely
+                // in the subtracted set.
+                let i = match b.binary_search(a) {
+                    Ok(i) => i,
+                    Err(..) => return true,
+                };
+
+                // If we've found `a` in `b`, then we iterate over all instances
+                // (we know `b` is sorted) and see if they all have different
+                // precise versions. If so, then `a` isn't actually in `b` so
+                // we'll let it through.
+                //
+                // Note that we only check this for non-registry sources,
+                // however, as registries contain enough version information in
+                // the package ID to disambiguate.
+                if a.source_id().is_registry() {
+                    return false;
+                }
+                b[i..]
+                    .iter()
+                    .take_while(|b| a == b)
+                    .all(|b| a.source_id().precise() != b.source_id().precise())
+            })
+            .cloned()
+            .collect()
+    }
+
+    // Map `(package name, package source)` to `(removed versions, added versions)`.
+    let mut changes = BTreeMap::new();
+    let empty = (Vec::new(), Vec::new());
+    for dep in previous_resolve.iter() {
+        changes
+            .entry(key(dep))
+            .or_insert_with(|| empty.clone())
+            .0
+            .push(dep);
+    }
+    for dep in resolve.iter() {
+        changes
+            .entry(key(dep))
+            .or_insert_with(|| empty.clone())
+            .1
+            .push(dep);
+    }
+
+    for v in changes.values_mut() {
+        let (ref mut old, ref mut new) = *v;
+        old.sort();
+        new.sort();
+        let removed = vec_subtract(old, new);
+        let added = vec_subtract(new, old);
+        *old = removed;
+        *new = added;
+    }
+    debug!("{:#?}", changes);
+
+    changes.into_iter().map(|(_, v)| v).collect()
+}
+
+/// Replaces the checksums of the packages `opts.to_update` in the lock file
+/// with the ones currently listed in the registry index, which is the way to
+/// recover from a checksum that changed between lock files.
+///
+/// The packages stay locked to the same versions, but are downloaded again
+/// and verified against the new checksums before the lock file is written.
+/// Every replaced checksum is recorded in the `checksum-audit.log` file of
+/// the Cargo home directory.
+fn force_checksums(
+    ws: &Workspace<'_>,
+    opts: &UpdateOptions<'_>,
+    mut previous_resolve: Resolve,
+) -> CargoResult<()> {
+    let config = opts.config;
+    let mut ids = Vec::new();
+    for name in opts.to_update.iter() {
+        let id = previous_resolve.query(name)?;
+        if !id.source_id().is_registry() {
+            anyhow::bail!(
+                "cannot force the checksum of `{}`, only packages from registries have checksums",
+                id
+            );
         }
+        ids.push(id);
     }
+    let previous_checksums: Vec<_> = ids
+        .iter()
+        .map(|&id| previous_resolve.forget_checksum(id))
+        .collect();
 
-    fn compare_dependency_graphs(
-        previous_resolve: &Resolve,
-        resolve: &Resolve,
-    ) -> Vec<(Vec<PackageId>, Vec<PackageId>)> {
-        fn key(dep: PackageId) -> (&'static str, SourceId) {
-            (dep.name().as_str(), dep.source_id())
+    // Nothing is unlocked, the sources are only added to update the index.
+    let mut registry = PackageRegistry::new(config)?;
+    registry.add_sources(ids.iter().map(|id| id.source_id().with_precise(None)))?;
+    let mut resolve = ops::resolve_with_previous(
+        &mut registry,
+        ws,
+        &CliFeatures::new_all(true),
+        HasDevUnits::Yes,
+        Some(&previous_resolve),
+        None,
+        &[],
+        true,
+    )?;
+
+    // Make sure the downloads are checked against the new checksums, instead
+    // of using what's already cached.
+    let map = SourceConfigMap::new(config)?;
+    for &id in ids.iter() {
+        let replaced_id = map
+            .load(id.source_id(), &HashSet::new())?
+            .replaced_source_id();
+        if replaced_id.is_remote_registry() {
+            RegistrySource::evict_package(replaced_id, config, id)?;
         }
+    }
+    registry.get(&ids)?.get_many(ids.iter().copied())?;
 
-        // Removes all package IDs in `b` from `a`. Note that this is somewhat
-        // more complicated because the equality for source IDs does not take
-        // precise versions into account (e.g., git shas), but we want to take
-        // that into account here.
-        fn vec_subtract(a: &[PackageId], b: &[PackageId]) -> Vec<PackageId> {
-            a.iter()
-                .filter(|a| {
-                    // If this package ID is not found in `b`, then it's definitely
-                    // in the subtracted set.
-                    let i = match b.binary_search(a) {
-                        Ok(i) => i,
-                        Err(..) => return true,
-                    };
-
-                    // If we've found `a` in `b`, then we iterate over all instances
-                    // (we know `b` is sorted) and see if they all have different
-                    // precise versions. If so, then `a` isn't actually in `b` so
-                    // we'll let it through.
-                    //
-                    // Note that we only check this for non-registry sources,
-                    // however, as registries contain enough version information in
-                    // the package ID to disambiguate.
-                    if a.source_id().is_registry() {
-                        return false;
-                    }
-                    b[i..]
-                        .iter()
-                        .take_while(|b| a == b)
-                        .all(|b| a.source_id().precise() != b.source_id().precise())
-                })
-                .cloned()
-                .collect()
+    let mut entries = String::new();
+    for (&id, previous) in ids.iter().zip(previous_checksums) {
+        let checksum = resolve.checksums().get(&id).cloned().flatten();
+        if checksum == previous {
+            config.shell().status(
+                "Verified",
+                format!("{} still matches the checksum in the lock file", id),
+            )?;
+            continue;
         }
+        let short = |cksum: &Option<String>| match cksum {
+            Some(cksum) => cksum[..cksum.len().min(16)].to_string(),
+            None => "none".to_string(),
+        };
+        config.shell().status_with_color(
+            "Updating",
+            format!(
+                "checksum of {} ({} -> {})",
+                id,
+                short(&previous),
+                short(&checksum)
+            ),
+            Green,
+        )?;
+        let entry = ChecksumAuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            lockfile: ws.lock_file_path(),
+            package: id,
+            previous,
+            checksum,
+        };
+        entries.push_str(&serde_json::to_string(&entry)?);
+        entries.push('\n');
+    }
+
+    if opts.dry_run {
+        config
+            .shell()
+            .warn("not updating lockfile due to dry run")?;
+        return Ok(());
+    }
+    ops::write_pkg_lockfile(ws, &mut resolve)?;
+    if !entries.is_empty() {
+        let log = config.home().as_path_unlocked().join("checksum-audit.log");
+        paths::create_dir_all(log.parent().unwrap())?;
+        paths::append(&log, entries.as_bytes())?;
+    }
+    Ok(())
+}
 
-        // Map `(package name, package source)` to `(removed versions, added versions)`.
-        let mut changes = BTreeMap::new();
-        let empty = (Vec::new(), Vec::new());
-        for dep in previous_resolve.iter() {
-            changes
-                .entry(key(dep))
-                .or_insert_with(|| empty.clone())
-                .0
-                .push(dep);
+/// A line of the `checksum-audit.log` file, recording a checksum accepted
+/// with `cargo update --force-checksum`.
+#[derive(Serialize)]
+struct ChecksumAuditEntry {
+    /// Seconds since the Unix epoch.
+    timestamp: u64,
+    lockfile: PathBuf,
+    package: PackageId,
+    previous: Option<String>,
+    checksum: Option<String>,
+}
+
+fn load_existing_lockfile(ws: &Workspace<'_>) -> CargoResult<Resolve> {
+    match ops::load_pkg_lockfile(ws)? {
+        Some(previous) => Ok(previous),
+        None => anyhow::bail!(
+            "no lock file found at `{}`, run `cargo generate-lockfile` to create one",
+            ws.lock_file_path().display()
+        ),
+    }
+}
+
+/// Bails if `resolve` locks different packages than `previous`, which happens
+/// when the lock file is out of date. `action` describes what the caller is
+/// about to do with the lock file.
+fn ensure_locked_packages_unchanged(
+    ws: &Workspace<'_>,
+    previous: &Resolve,
+    resolve: &Resolve,
+    action: &str,
+) -> CargoResult<()> {
+    // Compare by source URL and precise revision only, as the git reference
+    // of `branch = "master"` sources isn't recorded in older formats.
+    let key = |id: PackageId| {
+        (
+            id.name(),
+            id.version().clone(),
+            id.source_id().url().clone(),
+            id.source_id().precise().map(str::to_string),
+        )
+    };
+    let previous_ids: HashSet<_> = previous.iter().map(key).collect();
+    let resolved_ids: HashSet<_> = resolve.iter().map(key).collect();
+    if previous_ids != resolved_ids {
+        anyhow::bail!(
+            "the lock file {} is out of date, so {} would also \
+             change the locked packages\n\
+             Make sure the lock file is up to date, for example by building the \
+             workspace, before {}.",
+            ws.lock_file_path().display(),
+            action,
+            action
+        );
+    }
+    Ok(())
+}
+
+/// Groups the packages of `resolve` which are locked in several versions from
+/// the same source, each group sorted by version.
+fn duplicate_packages(resolve: &Resolve) -> Vec<Vec<PackageId>> {
+    let mut groups: BTreeMap<_, Vec<PackageId>> = BTreeMap::new();
+    for id in resolve.iter() {
+        groups
+            .entry((id.name(), id.source_id()))
+            .or_default()
+            .push(id);
+    }
+    groups
+        .into_values()
+        .filter(|ids| ids.len() > 1)
+        .map(|mut ids| {
+            ids.sort_by(|a, b| a.version().cmp(b.version()));
+            ids
+        })
+        .collect()
+}
+
+/// Returns the packages of `duplicates` whose dependents all accept a higher
+/// version of the same package, which is locked as well.
+fn redundant_packages(resolve: &Resolve, duplicates: &[Vec<PackageId>]) -> HashSet<PackageId> {
+    let mut requirements: HashMap<PackageId, Vec<&Dependency>> = HashMap::new();
+    for id in resolve.iter() {
+        for (dep_id, deps) in resolve.deps_not_replaced(id) {
+            requirements.entry(dep_id).or_default().extend(deps);
         }
-        for dep in resolve.iter() {
-            changes
-                .entry(key(dep))
-                .or_insert_with(|| empty.clone())
-                .1
-                .push(dep);
+    }
+    let mut redundant = HashSet::new();
+    for ids in duplicates {
+        for (i, id) in ids.iter().enumerate() {
+            let reqs = match requirements.get(id) {
+                Some(reqs) => reqs,
+                None => continue,
+            };
+            if ids[i + 1..].iter().any(|higher| {
+                reqs.iter()
+                    .all(|dep| dep.version_req().matches_unlocked(higher.version()))
+            }) {
+                redundant.insert(*id);
+            }
         }
+    }
+    redundant
+}
 
-        for v in changes.values_mut() {
-            let (ref mut old, ref mut new) = *v;
-            old.sort();
-            new.sort();
-            let removed = vec_subtract(old, new);
-            let added = vec_subtract(new, old);
-            *old = removed;
-            *new = added;
-        }
-        debug!("{:#?}", changes);
+/// Rewrites `Cargo.lock` in the latest format with its canonical ordering and
+/// encoding, without changing the locked packages.
+pub fn format_lockfile(ws: &Workspace<'_>, opts: &LockFmtOptions<'_>) -> CargoResult<()> {
+    let previous = load_existing_lockfile(ws)?;
+    let from = previous.version();
+
+    let mut registry = PackageRegistry::new(opts.config)?;
+    let mut resolve = ops::resolve_with_previous(
+        &mut registry,
+        ws,
+        &CliFeatures::new_all(true),
+        HasDevUnits::Yes,
+        Some(&previous),
+        None,
+        &[],
+        true,
+    )?;
+    ensure_locked_packages_unchanged(ws, &previous, &resolve, "formatting it")?;
+
+    let to = ResolveVersion::default();
+    resolve.set_version(to);
+    let orig = paths::read(&ws.lock_file_path())?;
+    let out = ops::resolve_to_string(ws, &mut resolve)?;
+    let mut shell = opts.config.shell();
+    if orig == out {
+        shell.note(format!("{} is already formatted", ws.lock_file_name()))?;
+        return Ok(());
+    }
+
+    shell.status("Formatting", ws.lock_file_name())?;
+    if from != to {
+        shell.status_with_color(
+            "",
+            format!(
+                "- the format changes from version {} to version {}",
+                from.number(),
+                to.number()
+            ),
+            Color::Cyan,
+        )?;
+    }
+    if opts.dry_run {
+        shell.warn("not updating lock file due to dry run")?;
+        return Ok(());
+    }
+    drop(shell);
+
+    ops::lockfile::write_pkg_lockfile_exact(ws, &mut resolve)
+}
+
+/// Removes packages locked in several versions where the dependents of the
+/// lower versions also accept a higher one, and reports the duplicates which
+/// remain.
+///
+/// Only the redundant versions are resolved again while everything else
+/// stays locked, so that their dependents pick up the locked higher version.
+pub fn dedupe_lockfile(ws: &Workspace<'_>, opts: &LockDedupeOptions<'_>) -> CargoResult<()> {
+    let previous = load_existing_lockfile(ws)?;
+
+    let _lock = ws.config().acquire_package_cache_lock()?;
 
-        changes.into_iter().map(|(_, v)| v).collect()
+    // The lock file doesn't record the requirements of the dependencies, so
+    // resolve once without changes to learn which versions they accept.
+    let mut registry = PackageRegistry::new(opts.config)?;
+    let mut resolve = ops::resolve_with_previous(
+        &mut registry,
+        ws,
+        &CliFeatures::new_all(true),
+        HasDevUnits::Yes,
+        Some(&previous),
+        None,
+        &[],
+        true,
+    )?;
+    let duplicates = duplicate_packages(&resolve);
+    if duplicates.is_empty() {
+        opts.config.shell().note(format!(
+            "{} contains no duplicate packages",
+            ws.lock_file_name()
+        ))?;
+        return Ok(());
+    }
+
+    let to_avoid = redundant_packages(&resolve, &duplicates);
+    if !to_avoid.is_empty() {
+        let mut registry = PackageRegistry::new(opts.config)?;
+        resolve = ops::resolve_with_previous(
+            &mut registry,
+            ws,
+            &CliFeatures::new_all(true),
+            HasDevUnits::Yes,
+            Some(&previous),
+            Some(&to_avoid),
+            &[],
+            true,
+        )?;
+    }
+
+    summarize_changes(opts.config, &previous, &resolve)?;
+    for ids in duplicate_packages(&resolve) {
+        let versions: Vec<_> = ids.iter().map(|id| format!("v{}", id.version())).collect();
+        opts.config.shell().note(format!(
+            "`{}` is still locked in several versions: {}",
+            ids[0].name(),
+            versions.join(", ")
+        ))?;
     }
+    if opts.dry_run {
+        opts.config
+            .shell()
+            .warn("not updating lock file due to dry run")?;
+    } else {
+        ops::write_pkg_lockfile(ws, &mut resolve)?;
+    }
+    Ok(())
+}
+
+/// Explicitly migrates `Cargo.lock` to the format `opts.to`, instead of
+/// relying on the implicit upgrade whenever the lock file changes.
+///
+/// The lock file is resolved again, without updating anything, so that the
+/// encoding of git sources which differs between formats is derived from the
+/// manifests. Migrations losing information are refused.
+pub fn upgrade_lockfile_format(
+    ws: &Workspace<'_>,
+    opts: &UpgradeFormatOptions<'_>,
+) -> CargoResult<()> {
+    let previous = load_existing_lockfile(ws)?;
+    let from = previous.version();
+    let to = opts.to;
+
+    let mut registry = PackageRegistry::new(opts.config)?;
+    let mut resolve = ops::resolve_with_previous(
+        &mut registry,
+        ws,
+        &CliFeatures::new_all(true),
+        HasDevUnits::Yes,
+        Some(&previous),
+        None,
+        &[],
+        true,
+    )?;
+
+    ensure_locked_packages_unchanged(ws, &previous, &resolve, "changing its format")?;
+
+    let master_branch: Vec<_> = resolve
+        .iter()
+        .filter(|id| {
+            matches!(
+                id.source_id().git_reference(),
+                Some(GitReference::Branch(b)) if b == "master"
+            )
+        })
+        .map(|id| id.name())
+        .collect();
+    if from == ResolveVersion::V3 && to < ResolveVersion::V3 && !master_branch.is_empty() {
+        anyhow::bail!(
+            "cannot change the lock file to version {}, because it can't distinguish \
+             git dependencies on `branch = \"master\"` from those on the default branch\n\
+             The following packages use `branch = \"master\"`: {}",
+            to.number(),
+            master_branch.join(", ")
+        );
+    }
+
+    let mut shell = opts.config.shell();
+    if from == to {
+        shell.note(format!(
+            "{} is already at version {}",
+            ws.lock_file_name(),
+            to.number()
+        ))?;
+        return Ok(());
+    }
+
+    let verb = if from < to {
+        "Upgrading"
+    } else {
+        "Downgrading"
+    };
+    shell.status(
+        verb,
+        format!(
+            "{} from version {} to version {}",
+            ws.lock_file_name(),
+            from.number(),
+            to.number()
+        ),
+    )?;
+    let mut changes = Vec::new();
+    if (from == ResolveVersion::V1) != (to == ResolveVersion::V1) {
+        if from < to {
+            changes.push(
+                "checksums move from the `[metadata]` table into the `[[package]]` entries"
+                    .to_string(),
+            );
+            changes.push(
+                "dependencies are referred to by name only, unless several versions \
+                 or sources of a package are locked"
+                    .to_string(),
+            );
+        } else {
+            changes.push(
+                "checksums move from the `[[package]]` entries into the `[metadata]` table"
+                    .to_string(),
+            );
+            changes.push(
+                "dependencies are always referred to by name, version and source".to_string(),
+            );
+        }
+    }
+    if (from == ResolveVersion::V3) != (to == ResolveVersion::V3) {
+        if from < to {
+            changes.push("a `version = 3` marker is added to the top of the file".to_string());
+            if !master_branch.is_empty() {
+                changes.push(format!(
+                    "git dependencies on `branch = \"master\"` are recorded with their \
+                     branch: {}",
+                    master_branch.join(", ")
+                ));
+            }
+        } else {
+            changes.push("the `version = 3` marker is removed".to_string());
+        }
+    }
+    for change in changes {
+        shell.status_with_color("", format!("- {}", change), Color::Cyan)?;
+    }
+    if to < ResolveVersion::default() {
+        shell.warn(format!(
+            "Cargo will upgrade the lock file to version {} again the next time it changes",
+            ResolveVersion::default().number()
+        ))?;
+    }
+    if opts.dry_run {
+        shell.warn("not updating lock file due to dry run")?;
+        return Ok(());
+    }
+    drop(shell);
+
+    resolve.set_version(to);
+    ops::lockfile::write_pkg_lockfile_exact(ws, &mut resolve)
 }
@@ -9,12 +9,15 @@ use crate::ops::CompileFilter;
 use crate::ops::{common_for_install_and_uninstall::*, FilterRule};
 use crate::sources::{GitSource, PathSource, SourceConfigMap};
 use crate::util::errors::CargoResult;
-use crate::util::{Config, Filesystem, Rustc, ToSemver, VersionReqExt};
+use crate::util::{short_hash, Config, Filesystem, Rustc, ToSemver, VersionReqExt};
 use crate::{drop_println, ops};
 
 use anyhow::{bail, format_err, Context as _};
-use cargo_util::paths;
+use cargo_util::{paths, Sha256};
+use flate2::read::GzDecoder;
 use semver::VersionReq;
+use serde::Deserialize;
+use tar::{Archive, EntryType};
 use tempfile::Builder as TempFileBuilder;
 
 struct Transaction {
@@ -43,6 +46,8 @@ struct InstallablePackage<'cfg, 'a> {
     vers: Option<&'a str>,
     force: bool,
     no_track: bool,
+    reuse_target_dir: bool,
+    prefer_binary: bool,
 
     pkg: Package,
     ws: Workspace<'cfg>,
@@ -63,6 +68,9 @@ impl<'cfg, 'a> InstallablePackage<'cfg, 'a> {
         opts: &'a ops::CompileOptions,
         force: bool,
         no_track: bool,
+        use_project_config: bool,
+        reuse_target_dir: bool,
+        prefer_binary: bool,
         needs_update_if_source_is_index: bool,
     ) -> CargoResult<Option<InstallablePackage<'cfg, 'a>>> {
         if let Some(name) = krate {
@@ -167,7 +175,8 @@ impl<'cfg, 'a> InstallablePackage<'cfg, 'a> {
             }
         };
 
-        let (ws, rustc, target) = make_ws_rustc_target(config, opts, &source_id, pkg.clone())?;
+        let (ws, rustc, target) =
+            make_ws_rustc_target(config, opts, &source_id, pkg.clone(), use_project_config)?;
         // If we're installing in --locked mode and there's no `Cargo.lock` published
         // ie. the bin was published before https://github.com/rust-lang/cargo/pull/7026
         if config.locked() && !ws.root().join("Cargo.lock").exists() {
@@ -222,6 +231,8 @@ impl<'cfg, 'a> InstallablePackage<'cfg, 'a> {
             vers,
             force,
             no_track,
+            reuse_target_dir,
+            prefer_binary,
 
             pkg,
             ws,
@@ -248,6 +259,118 @@ impl<'cfg, 'a> InstallablePackage<'cfg, 'a> {
         Ok(Some(ip))
     }
 
+    /// Downloads the pre-built binaries the package references for the
+    /// target in `[package.metadata.binaries.<target>]`, and unpacks them
+    /// into `dir`.
+    ///
+    /// Returns `None` when there are none which can be used, in which case
+    /// the package is built from source. A checksum mismatch is an error.
+    fn fetch_prebuilt(&self, dir: &Path) -> CargoResult<Option<Vec<(String, PathBuf)>>> {
+        let mut shell = self.config.shell();
+        let cli_features = &self.opts.cli_features;
+        if self.source_id.is_path()
+            || self.opts.filter.is_specific()
+            || !cli_features.features.is_empty()
+            || cli_features.all_features
+            || !cli_features.uses_default_features
+            || self.opts.build_config.requested_profile != "release"
+        {
+            shell.note(
+                "pre-built binaries are only used for packages from a registry or git \
+                 repository with the default features and profile, building from source",
+            )?;
+            return Ok(None);
+        }
+        let entry = match self
+            .pkg
+            .manifest()
+            .custom_metadata()
+            .and_then(|metadata| metadata.get("binaries"))
+            .and_then(|binaries| binaries.get(&self.target))
+        {
+            Some(entry) => entry.clone(),
+            None => {
+                shell.note(format!(
+                    "`{}` has no pre-built binaries for `{}`, building from source",
+                    self.pkg, self.target
+                ))?;
+                return Ok(None);
+            }
+        };
+        let entry: PrebuiltBinaries = entry.try_into().with_context(|| {
+            format!(
+                "failed to parse `package.metadata.binaries.{}` of `{}`",
+                self.target, self.pkg
+            )
+        })?;
+
+        shell.status("Downloading", format!("pre-built binaries of {}", self.pkg))?;
+        drop(shell);
+        let data = match download(self.config, &entry.url) {
+            Ok(data) => data,
+            Err(e) => {
+                self.config.shell().warn(format!(
+                    "failed to download the pre-built binaries of `{}` from `{}`, \
+                     building from source\n\nCaused by:\n  {}",
+                    self.pkg, entry.url, e
+                ))?;
+                return Ok(None);
+            }
+        };
+        let actual = Sha256::new().update(&data).finish_hex();
+        if actual != entry.checksum {
+            bail!(
+                "checksum of the pre-built binaries of `{}` downloaded from `{}` \
+                 does not match\nexpected: {}\nactual:   {}",
+                self.pkg,
+                entry.url,
+                entry.checksum,
+                actual
+            );
+        }
+
+        let expected = exe_names(&self.pkg, &self.opts.filter);
+        let mut binaries = Vec::new();
+        let mut archive = Archive::new(GzDecoder::new(&data[..]));
+        for file in archive.entries()? {
+            let mut file = file?;
+            // Only plain files, so links can't point the binaries elsewhere.
+            if file.header().entry_type() != EntryType::Regular {
+                continue;
+            }
+            let name = match file.path()?.file_name().and_then(|name| name.to_str()) {
+                Some(name) if expected.contains(name) => name.to_string(),
+                _ => continue,
+            };
+            let path = dir.join(&name);
+            file.unpack(&path)?;
+            binaries.push((name, path));
+        }
+        if binaries.len() != expected.len() {
+            self.config.shell().warn(format!(
+                "the pre-built binaries of `{}` downloaded from `{}` don't contain \
+                 all executables of the package, building from source",
+                self.pkg, entry.url
+            ))?;
+            return Ok(None);
+        }
+        Ok(Some(binaries))
+    }
+
+    /// The target directory shared by all installs with the same toolchain,
+    /// used with `--reuse-target-dir`.
+    ///
+    /// Dependencies are only rebuilt when their unit metadata changes (crate
+    /// version, features, profile, ...), so reinstalling, or installing
+    /// another tool with common dependencies, picks up where earlier installs
+    /// left off. The build directory lock serializes concurrent installs.
+    fn shared_target_dir(&self) -> Filesystem {
+        self.config
+            .home()
+            .join("install-target")
+            .join(short_hash(&self.rustc.verbose_version))
+    }
+
     fn no_track_duplicates(&self, dst: &Path) -> CargoResult<BTreeMap<String, Option<PackageId>>> {
         // Helper for --no-track flag to make sure it doesn't overwrite anything.
         let duplicates: BTreeMap<String, Option<PackageId>> =
@@ -281,7 +404,9 @@ impl<'cfg, 'a> InstallablePackage<'cfg, 'a> {
         let mut td_opt = None;
         let mut needs_cleanup = false;
         if !self.source_id.is_path() {
-            let target_dir = if let Some(dir) = self.config.target_dir()? {
+            let target_dir = if self.reuse_target_dir {
+                self.shared_target_dir()
+            } else if let Some(dir) = self.config.target_dir()? {
                 dir
             } else if let Ok(td) = TempFileBuilder::new().prefix("cargo-install").tempdir() {
                 let p = td.path().to_owned();
@@ -296,32 +421,48 @@ impl<'cfg, 'a> InstallablePackage<'cfg, 'a> {
 
         self.check_yanked_install()?;
 
-        let exec: Arc<dyn Executor> = Arc::new(DefaultExecutor);
-        let compile = ops::compile_ws(&self.ws, self.opts, &exec).with_context(|| {
-            if let Some(td) = td_opt.take() {
-                // preserve the temporary directory, so the user can inspect it
-                td.into_path();
-            }
+        let prebuilt_dir;
+        let prebuilt = if self.prefer_binary {
+            prebuilt_dir = TempFileBuilder::new().prefix("cargo-install").tempdir()?;
+            self.fetch_prebuilt(prebuilt_dir.path())?
+        } else {
+            None
+        };
+        let compile;
+        let mut binaries: Vec<(&str, &Path)> = match &prebuilt {
+            Some(prebuilt) => prebuilt
+                .iter()
+                .map(|(name, path)| (name.as_str(), path.as_path()))
+                .collect(),
+            None => {
+                let exec: Arc<dyn Executor> = Arc::new(DefaultExecutor);
+                compile = ops::compile_ws(&self.ws, self.opts, &exec).with_context(|| {
+                    if let Some(td) = td_opt.take() {
+                        // preserve the temporary directory, so the user can inspect it
+                        let _ = td.keep();
+                    }
 
-            format!(
-                "failed to compile `{}`, intermediate artifacts can be \
-                 found at `{}`",
-                self.pkg,
-                self.ws.target_dir().display()
-            )
-        })?;
-        let mut binaries: Vec<(&str, &Path)> = compile
-            .binaries
-            .iter()
-            .map(|UnitOutput { path, .. }| {
-                let name = path.file_name().unwrap();
-                if let Some(s) = name.to_str() {
-                    Ok((s, path.as_ref()))
-                } else {
-                    bail!("Binary `{:?}` name can't be serialized into string", name)
-                }
-            })
-            .collect::<CargoResult<_>>()?;
+                    format!(
+                        "failed to compile `{}`, intermediate artifacts can be \
+                         found at `{}`",
+                        self.pkg,
+                        self.ws.target_dir().display()
+                    )
+                })?;
+                compile
+                    .binaries
+                    .iter()
+                    .map(|UnitOutput { path, .. }| {
+                        let name = path.file_name().unwrap();
+                        if let Some(s) = name.to_str() {
+                            Ok((s, path.as_ref()))
+                        } else {
+                            bail!("Binary `{:?}` name can't be serialized into string", name)
+                        }
+                    })
+                    .collect::<CargoResult<_>>()?
+            }
+        };
         if binaries.is_empty() {
             // Cargo already warns the user if they use a target specifier that matches nothing,
             // but we want to error if the user asked for a _particular_ binary to be installed,
@@ -390,7 +531,8 @@ impl<'cfg, 'a> InstallablePackage<'cfg, 'a> {
         for &(bin, src) in binaries.iter() {
             let dst = staging_dir.path().join(bin);
             // Try to move if `target_dir` is transient.
-            if !self.source_id.is_path() && fs::rename(src, &dst).is_ok() {
+            if !self.source_id.is_path() && !self.reuse_target_dir && fs::rename(src, &dst).is_ok()
+            {
                 continue;
             }
             paths::copy(src, &dst)?;
@@ -539,6 +681,36 @@ impl<'cfg, 'a> InstallablePackage<'cfg, 'a> {
     }
 }
 
+/// An entry of `[package.metadata.binaries]`, a `.tar.gz` archive with the
+/// executables of a package for one target.
+#[derive(Deserialize)]
+struct PrebuiltBinaries {
+    url: String,
+    /// The SHA-256 checksum of the archive.
+    checksum: String,
+}
+
+/// Downloads `url` into memory.
+fn download(config: &Config, url: &str) -> CargoResult<Vec<u8>> {
+    let mut handle = config.http()?.borrow_mut();
+    handle.get(true)?;
+    handle.url(url)?;
+    handle.follow_location(true)?;
+    let mut body = Vec::new();
+    let mut transfer = handle.transfer();
+    transfer.write_function(|data| {
+        body.extend_from_slice(data);
+        Ok(data.len())
+    })?;
+    transfer.perform()?;
+    drop(transfer);
+    // `file://` URLs don't have a response code.
+    match handle.response_code()? {
+        0 | 200 => Ok(body),
+        code => bail!("failed to get `{}`, got status code {}", url, code),
+    }
+}
+
 pub fn install(
     config: &Config,
     root: Option<&str>,
@@ -548,6 +720,9 @@ pub fn install(
     opts: &ops::CompileOptions,
     force: bool,
     no_track: bool,
+    use_project_config: bool,
+    reuse_target_dir: bool,
+    prefer_binary: bool,
 ) -> CargoResult<()> {
     let root = resolve_root(root, config)?;
     let dst = root.join("bin").into_path_unlocked();
@@ -560,7 +735,20 @@ pub fn install(
             .map(|(k, v)| (Some(k), v))
             .unwrap_or((None, None));
         let installable_pkg = InstallablePackage::new(
-            config, root, map, krate, source_id, from_cwd, vers, opts, force, no_track, true,
+            config,
+            root,
+            map,
+            krate,
+            source_id,
+            from_cwd,
+            vers,
+            opts,
+            force,
+            no_track,
+            use_project_config,
+            reuse_target_dir,
+            prefer_binary,
+            true,
         )?;
         let mut installed_anything = true;
         if let Some(installable_pkg) = installable_pkg {
@@ -590,6 +778,9 @@ pub fn install(
                     opts,
                     force,
                     no_track,
+                    use_project_config,
+                    reuse_target_dir,
+                    prefer_binary,
                     !did_update,
                 ) {
                     Ok(Some(installable_pkg)) => {
@@ -712,7 +903,7 @@ where
     // best-effort check to see if we can avoid hitting the network.
     if let Ok(pkg) = select_dep_pkg(source, dep, config, false) {
         let (_ws, rustc, target) =
-            make_ws_rustc_target(config, opts, &source.source_id(), pkg.clone())?;
+            make_ws_rustc_target(config, opts, &source.source_id(), pkg.clone(), false)?;
         if let Ok(true) = is_installed(&pkg, config, opts, &rustc, &target, root, dst, force) {
             return Ok(Some(pkg));
         }
@@ -725,13 +916,16 @@ fn make_ws_rustc_target<'cfg>(
     opts: &ops::CompileOptions,
     source_id: &SourceId,
     pkg: Package,
+    use_project_config: bool,
 ) -> CargoResult<(Workspace<'cfg>, Rustc, String)> {
     let mut ws = if source_id.is_git() || source_id.is_path() {
         Workspace::new(pkg.manifest_path(), config)?
     } else {
         Workspace::ephemeral(pkg, config, None, false)?
     };
-    ws.set_ignore_lock(config.lock_update_allowed());
+    // With `--use-project-config`, the lockfile is handled the same way as
+    // `cargo build` does, instead of resolving from scratch.
+    ws.set_ignore_lock(config.lock_update_allowed() && !use_project_config);
     ws.set_require_optional_deps(false);
 
     let rustc = config.load_global_rustc(Some(&ws))?;
@@ -792,10 +986,25 @@ fn parse_semver_flag(v: &str) -> CargoResult<VersionReq> {
 }
 
 /// Display a list of installed binaries.
-pub fn install_list(dst: Option<&str>, config: &Config) -> CargoResult<()> {
+/// Prints the installed packages and their binaries.
+///
+/// With `orphans`, only packages installed with `--path` whose source
+/// directory no longer exists are listed.
+pub fn install_list(dst: Option<&str>, orphans: bool, config: &Config) -> CargoResult<()> {
     let root = resolve_root(dst, config)?;
     let tracker = InstallTracker::load(config, &root)?;
+    let is_orphan = |pkg_id: &PackageId| {
+        pkg_id.source_id().is_path()
+            && pkg_id
+                .source_id()
+                .url()
+                .to_file_path()
+                .map_or(false, |path| !path.exists())
+    };
     for (k, v) in tracker.all_installed_bins() {
+        if orphans && !is_orphan(k) {
+            continue;
+        }
         drop_println!(config, "{}:", k);
         for bin in v {
             drop_println!(config, "    {}", bin);
@@ -1,6 +1,7 @@
-use crate::core::{Edition, Shell, Workspace};
+use crate::core::{find_workspace_root, Edition, GitReference, Shell, Workspace};
+use crate::sources::git::GitRemote;
 use crate::util::errors::CargoResult;
-use crate::util::{existing_vcs_repo, FossilRepo, GitRepo, HgRepo, PijulRepo};
+use crate::util::{existing_vcs_repo, FossilRepo, GitRepo, HgRepo, IntoUrl, PijulRepo};
 use crate::util::{restricted_names, Config};
 use anyhow::{anyhow, Context as _};
 use cargo_util::paths;
@@ -58,6 +59,11 @@ pub struct NewOptions {
     pub name: Option<String>,
     pub edition: Option<String>,
     pub registry: Option<String>,
+    /// Directory or git URL of a template to create the package from.
+    pub template: Option<String>,
+    /// Whether the package must be added to the members of the workspace
+    /// it's in.
+    pub member: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -96,6 +102,8 @@ struct MkOptions<'a> {
     bin: bool,
     edition: Option<&'a str>,
     registry: Option<&'a str>,
+    template: Option<&'a str>,
+    member: bool,
 }
 
 impl NewOptions {
@@ -124,6 +132,8 @@ impl NewOptions {
             name,
             edition,
             registry,
+            template: None,
+            member: false,
         };
         Ok(opts)
     }
@@ -436,6 +446,7 @@ pub fn new(opts: &NewOptions, config: &Config) -> CargoResult<()> {
     }
 
     check_path(path, &mut config.shell())?;
+    check_member(opts, config)?;
 
     let is_bin = opts.kind.is_bin();
 
@@ -450,6 +461,8 @@ pub fn new(opts: &NewOptions, config: &Config) -> CargoResult<()> {
         bin: is_bin,
         edition: opts.edition.as_deref(),
         registry: opts.registry.as_deref(),
+        template: opts.template.as_deref(),
+        member: opts.member,
     };
 
     mk(config, &mkopts).with_context(|| {
@@ -475,6 +488,7 @@ pub fn init(opts: &NewOptions, config: &Config) -> CargoResult<NewProjectKind> {
     }
 
     check_path(path, &mut config.shell())?;
+    check_member(opts, config)?;
 
     let name = get_name(path, opts)?;
 
@@ -512,41 +526,7 @@ pub fn init(opts: &NewOptions, config: &Config) -> CargoResult<NewProjectKind> {
 
     check_name(name, opts.name.is_none(), has_bin, &mut config.shell())?;
 
-    let mut version_control = opts.version_control;
-
-    if version_control == None {
-        let mut num_detected_vsces = 0;
-
-        if path.join(".git").exists() {
-            version_control = Some(VersionControl::Git);
-            num_detected_vsces += 1;
-        }
-
-        if path.join(".hg").exists() {
-            version_control = Some(VersionControl::Hg);
-            num_detected_vsces += 1;
-        }
-
-        if path.join(".pijul").exists() {
-            version_control = Some(VersionControl::Pijul);
-            num_detected_vsces += 1;
-        }
-
-        if path.join(".fossil").exists() {
-            version_control = Some(VersionControl::Fossil);
-            num_detected_vsces += 1;
-        }
-
-        // if none exists, maybe create git, like in `cargo new`
-
-        if num_detected_vsces > 1 {
-            anyhow::bail!(
-                "more than one of .hg, .git, .pijul, .fossil configurations \
-                 found and the ignore file can't be filled in as \
-                 a result. specify --vcs to override detection"
-            );
-        }
-    }
+    let version_control = detect_vcs(path, opts.version_control)?;
 
     let mkopts = MkOptions {
         version_control,
@@ -556,6 +536,8 @@ pub fn init(opts: &NewOptions, config: &Config) -> CargoResult<NewProjectKind> {
         source_files: src_paths_types,
         edition: opts.edition.as_deref(),
         registry: opts.registry.as_deref(),
+        template: opts.template.as_deref(),
+        member: opts.member,
     };
 
     mk(config, &mkopts).with_context(|| {
@@ -568,6 +550,137 @@ pub fn init(opts: &NewOptions, config: &Config) -> CargoResult<NewProjectKind> {
     Ok(kind)
 }
 
+/// The version control system of the directory at `path`, unless `requested`.
+fn detect_vcs(
+    path: &Path,
+    requested: Option<VersionControl>,
+) -> CargoResult<Option<VersionControl>> {
+    if requested.is_some() {
+        return Ok(requested);
+    }
+    let mut version_control = None;
+    let mut num_detected_vsces = 0;
+
+    if path.join(".git").exists() {
+        version_control = Some(VersionControl::Git);
+        num_detected_vsces += 1;
+    }
+
+    if path.join(".hg").exists() {
+        version_control = Some(VersionControl::Hg);
+        num_detected_vsces += 1;
+    }
+
+    if path.join(".pijul").exists() {
+        version_control = Some(VersionControl::Pijul);
+        num_detected_vsces += 1;
+    }
+
+    if path.join(".fossil").exists() {
+        version_control = Some(VersionControl::Fossil);
+        num_detected_vsces += 1;
+    }
+
+    // if none exists, maybe create git, like in `cargo new`
+
+    if num_detected_vsces > 1 {
+        anyhow::bail!(
+            "more than one of .hg, .git, .pijul, .fossil configurations \
+             found and the ignore file can't be filled in as \
+             a result. specify --vcs to override detection"
+        );
+    }
+
+    Ok(version_control)
+}
+
+/// Creates a virtual workspace in the directory of `opts`, with the packages
+/// already in it as members. Returns the paths of the members.
+pub fn init_workspace(opts: &NewOptions, config: &Config) -> CargoResult<Vec<String>> {
+    let path = &opts.path;
+    let manifest_path = path.join("Cargo.toml");
+    if manifest_path.exists() {
+        anyhow::bail!("`cargo init` cannot be run on existing Cargo packages")
+    }
+    check_path(path, &mut config.shell())?;
+
+    let members = find_packages(path)?;
+    let vcs = choose_vcs(config, path, detect_vcs(path, opts.version_control)?)?;
+    init_vcs(path, vcs, config)?;
+    let mut ignore = IgnoreList::new();
+    ignore.push("/target", "^target/", "target");
+    write_ignore_file(path, &ignore, vcs)?;
+
+    let members_list = if members.is_empty() {
+        "[]".to_string()
+    } else {
+        let entries: String = members
+            .iter()
+            .map(|member| format!("    {},\n", toml::Value::String(member.clone())))
+            .collect();
+        format!("[\n{}]", entries)
+    };
+    paths::write(
+        &manifest_path,
+        format!(
+            r#"[workspace]
+members = {}
+resolver = "2"
+
+# See more keys and their definitions at https://doc.rust-lang.org/cargo/reference/workspaces.html
+"#,
+            members_list
+        ),
+    )?;
+
+    if !members.is_empty() {
+        if let Err(e) = Workspace::new(&manifest_path, config) {
+            crate::display_warning_with_error(
+                "compiling this new workspace may not work due to invalid \
+                 configuration of its members",
+                &e,
+                &mut config.shell(),
+            );
+        }
+    }
+    Ok(members)
+}
+
+/// The relative paths of the packages in `root`, not looking into hidden
+/// directories, target directories or packages.
+fn find_packages(root: &Path) -> CargoResult<Vec<String>> {
+    let mut packages: Vec<PathBuf> = Vec::new();
+    let walker = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(3)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            !name.starts_with('.') && name != "target"
+        });
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_dir() || !entry.path().join("Cargo.toml").is_file() {
+            continue;
+        }
+        if packages.iter().any(|pkg| entry.path().starts_with(pkg)) {
+            continue;
+        }
+        packages.push(entry.path().to_path_buf());
+    }
+    packages
+        .iter()
+        .map(|pkg| {
+            let relative = pkg.strip_prefix(root)?;
+            Ok(relative
+                .to_str()
+                .ok_or_else(|| anyhow!("the path `{}` is not valid UTF-8", pkg.display()))?
+                .replace('\\', "/"))
+        })
+        .collect()
+}
+
 /// IgnoreList
 struct IgnoreList {
     /// git like formatted entries
@@ -735,10 +848,26 @@ fn init_vcs(path: &Path, vcs: VersionControl, config: &Config) -> CargoResult<()
     Ok(())
 }
 
+/// The version control system to initialize for a new package at `path`.
+fn choose_vcs(
+    config: &Config,
+    path: &Path,
+    requested: Option<VersionControl>,
+) -> CargoResult<VersionControl> {
+    let cfg = config.get::<CargoNewConfig>("cargo-new")?;
+    Ok(requested.unwrap_or_else(|| {
+        let in_existing_vcs = existing_vcs_repo(path.parent().unwrap_or(path), config.cwd());
+        match (cfg.version_control, in_existing_vcs) {
+            (None, false) => VersionControl::Git,
+            (Some(opt), false) => opt,
+            (_, true) => VersionControl::NoVcs,
+        }
+    }))
+}
+
 fn mk(config: &Config, opts: &MkOptions<'_>) -> CargoResult<()> {
     let path = opts.path;
     let name = opts.name;
-    let cfg = config.get::<CargoNewConfig>("cargo-new")?;
 
     // Using the push method with multiple arguments ensures that the entries
     // for all mutually-incompatible VCS in terms of syntax are in sync.
@@ -748,16 +877,15 @@ fn mk(config: &Config, opts: &MkOptions<'_>) -> CargoResult<()> {
         ignore.push("/Cargo.lock", "^Cargo.lock$", "Cargo.lock");
     }
 
-    let vcs = opts.version_control.unwrap_or_else(|| {
-        let in_existing_vcs = existing_vcs_repo(path.parent().unwrap_or(path), config.cwd());
-        match (cfg.version_control, in_existing_vcs) {
-            (None, false) => VersionControl::Git,
-            (Some(opt), false) => opt,
-            (_, true) => VersionControl::NoVcs,
-        }
-    });
+    let vcs = choose_vcs(config, path, opts.version_control)?;
 
     init_vcs(path, vcs, config)?;
+    if let Some(template) = opts.template {
+        // The ignore file of the template is amended rather than replaced.
+        apply_template(config, template, opts)?;
+        write_ignore_file(path, &ignore, vcs)?;
+        return check_workspace(config, path, opts.member);
+    }
     write_ignore_file(path, &ignore, vcs)?;
 
     let mut cargotoml_path_specifier = String::new();
@@ -868,7 +996,28 @@ mod tests {
         }
     }
 
-    if let Err(e) = Workspace::new(&path.join("Cargo.toml"), config) {
+    check_workspace(config, path, opts.member)
+}
+
+/// Warns if the new package at `path` doesn't fit in the workspace it's in.
+///
+/// With `--member` or `-Z unstable-options`, a package which isn't a member
+/// of the workspace yet is first added to its `members`.
+fn check_workspace(config: &Config, path: &Path, member: bool) -> CargoResult<()> {
+    let manifest_path = path.join("Cargo.toml");
+    if member {
+        add_to_workspace(config, &manifest_path)?;
+    } else if config.cli_unstable().unstable_options {
+        if let Err(e) = add_to_workspace(config, &manifest_path) {
+            crate::display_warning_with_error(
+                "failed to add the new package to the members of its workspace",
+                &e,
+                &mut config.shell(),
+            );
+        }
+    }
+
+    if let Err(e) = Workspace::new(&manifest_path, config) {
         crate::display_warning_with_error(
             "compiling this new package may not work due to invalid \
              workspace configuration",
@@ -879,3 +1028,171 @@ mod tests {
 
     Ok(())
 }
+
+/// Fails before creating anything if the package must be added to a
+/// workspace with `--member` but isn't in one.
+fn check_member(opts: &NewOptions, config: &Config) -> CargoResult<()> {
+    if opts.member && find_workspace_root(&opts.path.join("Cargo.toml"), config)?.is_none() {
+        anyhow::bail!(
+            "`--member` was passed, but no workspace containing `{}` was found\n\n\
+             Use `cargo init --workspace` to create one",
+            opts.path.display()
+        )
+    }
+    Ok(())
+}
+
+/// Adds the package at `manifest_path` to the `members` of the workspace it's
+/// in, unless it's already a member or excluded.
+fn add_to_workspace(config: &Config, manifest_path: &Path) -> CargoResult<()> {
+    let root_manifest = match find_workspace_root(manifest_path, config)? {
+        Some(root_manifest) => root_manifest,
+        None => return Ok(()),
+    };
+    if root_manifest == manifest_path {
+        return Ok(());
+    }
+    let ws = Workspace::new(&root_manifest, config)?;
+    if ws
+        .members()
+        .any(|member| member.manifest_path() == manifest_path)
+    {
+        return Ok(());
+    }
+
+    let root_dir = root_manifest.parent().unwrap();
+    let member = manifest_path
+        .parent()
+        .unwrap()
+        .strip_prefix(root_dir)?
+        .to_str()
+        .ok_or_else(|| anyhow!("the path of the new package is not valid UTF-8"))?
+        .replace('\\', "/");
+    let mut document: toml_edit::Document = paths::read(&root_manifest)?.parse()?;
+    let members = document["workspace"]
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("`workspace` is not a table"))?
+        .entry("members")
+        .or_insert_with(|| toml_edit::value(toml_edit::Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("`workspace.members` is not an array"))?;
+    members.push(member.as_str());
+    paths::write(&root_manifest, document.to_string())?;
+
+    config.shell().status(
+        "Adding",
+        format!(
+            "`{}` as a member of the workspace at `{}`",
+            member,
+            root_dir.display()
+        ),
+    )
+}
+
+/// Creates the package of `opts` from the files of `template`, a directory or
+/// the URL of a git repository.
+///
+/// The `{{name}}`, `{{crate_name}}`, `{{authors}}` and `{{edition}}`
+/// placeholders are substituted in the names and UTF-8 contents of the files.
+/// Files which already exist in the package, like the sources found by
+/// `cargo init`, are kept.
+fn apply_template(config: &Config, template: &str, opts: &MkOptions<'_>) -> CargoResult<()> {
+    let local = config.cwd().join(template);
+    let _checkout;
+    let template_dir = if local.is_dir() {
+        local
+    } else if let Ok(url) = template.into_url() {
+        let tmp = tempfile::Builder::new()
+            .prefix("cargo-template")
+            .tempdir()?;
+        config
+            .shell()
+            .status("Updating", format!("git repository `{}`", url))?;
+        let remote = GitRemote::new(&url);
+        let (db, rev) = remote
+            .checkout(
+                &tmp.path().join("db"),
+                None,
+                &GitReference::DefaultBranch,
+                None,
+                config,
+            )
+            .with_context(|| format!("failed to fetch the template `{}`", url))?;
+        let dir = tmp.path().join("checkout");
+        db.copy_to(rev, &dir, config, &url)?;
+        _checkout = tmp;
+        dir
+    } else {
+        anyhow::bail!(
+            "the template `{}` is neither a directory nor the URL of a git repository",
+            template
+        )
+    };
+    if !template_dir.join("Cargo.toml").is_file() {
+        anyhow::bail!(
+            "the template `{}` has no `Cargo.toml` at its root",
+            template
+        );
+    }
+
+    let edition = match opts.edition {
+        Some(edition) => edition.to_string(),
+        None => Edition::LATEST_STABLE.to_string(),
+    };
+    let vars = [
+        ("{{name}}", opts.name.to_string()),
+        ("{{crate_name}}", opts.name.replace('-', "_")),
+        ("{{authors}}", discover_author().unwrap_or_default()),
+        ("{{edition}}", edition),
+    ];
+    let substitute = |text: &str| {
+        vars.iter().fold(text.to_string(), |text, (var, value)| {
+            text.replace(var, value)
+        })
+    };
+
+    for entry in walkdir::WalkDir::new(&template_dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git")
+    {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(&template_dir)?;
+        let dest = opts.path.join(substitute(&relative.to_string_lossy()));
+        if dest.exists() {
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            paths::create_dir_all(parent)?;
+        }
+        let contents = paths::read_bytes(entry.path())?;
+        match String::from_utf8(contents) {
+            Ok(text) => paths::write(&dest, substitute(&text))?,
+            Err(e) => paths::write(&dest, e.into_bytes())?,
+        }
+    }
+    Ok(())
+}
+
+/// The author of new packages, as `Name <email>`, from the environment or the
+/// git configuration.
+fn discover_author() -> Option<String> {
+    let git_config = git2::Config::open_default().ok();
+    let var = |names: &[&str], git_key: &str| {
+        names
+            .iter()
+            .find_map(|name| std::env::var(name).ok())
+            .or_else(|| git_config.as_ref()?.get_string(git_key).ok())
+            .filter(|value| !value.trim().is_empty())
+    };
+    let name = var(&["CARGO_NAME", "GIT_AUTHOR_NAME"], "user.name")?;
+    Some(
+        match var(&["CARGO_EMAIL", "GIT_AUTHOR_EMAIL", "EMAIL"], "user.email") {
+            Some(email) => format!("{} <{}>", name.trim(), email.trim()),
+            None => name.trim().to_string(),
+        },
+    )
+}
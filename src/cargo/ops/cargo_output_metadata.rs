@@ -1,14 +1,14 @@
-use crate::core::compiler::{CompileKind, RustcTargetData};
+use crate::core::compiler::{CompileKind, CompileMode, Context, RustcTargetData, UnitInterner};
 use crate::core::dependency::DepKind;
 use crate::core::package::SerializedPackage;
 use crate::core::resolver::{features::CliFeatures, HasDevUnits, Resolve};
-use crate::core::{Dependency, Package, PackageId, Workspace};
-use crate::ops::{self, Packages};
+use crate::core::{Dependency, FeatureValue, Package, PackageId, Workspace};
+use crate::ops::{self, CompileOptions, Packages};
 use crate::util::interning::InternedString;
 use crate::util::CargoResult;
 use cargo_platform::Platform;
 use serde::Serialize;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::PathBuf;
 use toml_edit::easy as toml;
 
@@ -19,6 +19,12 @@ pub struct OutputMetadataOptions {
     pub no_deps: bool,
     pub version: u32,
     pub filter_platforms: Vec<String>,
+    /// Include the `features` section, describing the feature graph of every
+    /// package.
+    pub features_graph: bool,
+    /// Include the `build_scripts` section, with the `OUT_DIR` of every build
+    /// script which has already run.
+    pub build_scripts: bool,
 }
 
 /// Loads the manifest, resolves the dependencies of the package to the concrete
@@ -32,12 +38,18 @@ pub fn output_metadata(ws: &Workspace<'_>, opt: &OutputMetadataOptions) -> Cargo
             VERSION
         );
     }
-    let (packages, resolve) = if opt.no_deps {
+    let (packages, resolve, features) = if opt.no_deps {
         let packages = ws.members().map(|pkg| pkg.serialized()).collect();
-        (packages, None)
+        (packages, None, None)
     } else {
-        let (packages, resolve) = build_resolve_graph(ws, opt)?;
-        (packages, Some(resolve))
+        let (packages, resolve, features) = build_resolve_graph(ws, opt)?;
+        (packages, Some(resolve), features)
+    };
+
+    let build_scripts = if opt.build_scripts {
+        Some(previous_build_scripts(ws, opt)?)
+    } else {
+        None
     };
 
     Ok(ExportInfo {
@@ -48,6 +60,8 @@ pub fn output_metadata(ws: &Workspace<'_>, opt: &OutputMetadataOptions) -> Cargo
         version: VERSION,
         workspace_root: ws.root().to_path_buf(),
         metadata: ws.custom_metadata().cloned(),
+        features,
+        build_scripts,
     })
 }
 
@@ -63,6 +77,10 @@ pub struct ExportInfo {
     version: u32,
     workspace_root: PathBuf,
     metadata: Option<toml::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    features: Option<Vec<PackageFeatures>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_scripts: Option<Vec<BuildScriptInfo>>,
 }
 
 #[derive(Serialize)]
@@ -92,6 +110,52 @@ struct DepKindInfo {
     target: Option<Platform>,
 }
 
+/// The feature graph of a package, shown with `--features-graph`.
+#[derive(Serialize)]
+struct PackageFeatures {
+    id: PackageId,
+    features: BTreeMap<InternedString, FeatureInfo>,
+}
+
+#[derive(Serialize)]
+struct FeatureInfo {
+    /// The values of the feature in the `[features]` table.
+    enables: Vec<String>,
+    activated: bool,
+    activated_by: BTreeSet<FeatureActivator>,
+}
+
+/// What activated a feature in the current resolution.
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum FeatureActivator {
+    /// A feature of a package, either the same package, or one depending
+    /// on it with `dep/feature` values.
+    Feature {
+        package: PackageId,
+        feature: InternedString,
+    },
+    /// The declaration of a dependency on the package, through its
+    /// `features` or `default-features`.
+    Dependency {
+        package: PackageId,
+        name: InternedString,
+    },
+    /// The feature flags passed on the command-line.
+    CommandLine,
+}
+
+/// A build script which has run in a previous build, shown with
+/// `--build-scripts`.
+#[derive(Serialize)]
+struct BuildScriptInfo {
+    id: PackageId,
+    out_dir: PathBuf,
+    /// The files the script declares to generate in `OUT_DIR` with
+    /// `cargo:build-manifest`, which don't necessarily exist.
+    generated: Vec<PathBuf>,
+}
+
 impl From<&Dependency> for DepKindInfo {
     fn from(dep: &Dependency) -> DepKindInfo {
         DepKindInfo {
@@ -105,7 +169,11 @@ impl From<&Dependency> for DepKindInfo {
 fn build_resolve_graph(
     ws: &Workspace<'_>,
     metadata_opts: &OutputMetadataOptions,
-) -> CargoResult<(Vec<SerializedPackage>, MetadataResolve)> {
+) -> CargoResult<(
+    Vec<SerializedPackage>,
+    MetadataResolve,
+    Option<Vec<PackageFeatures>>,
+)> {
     // TODO: Without --filter-platform, features are being resolved for `host` only.
     // How should this work?
     let requested_kinds =
@@ -151,6 +219,17 @@ fn build_resolve_graph(
             &requested_kinds,
         );
     }
+    let features = if metadata_opts.features_graph {
+        Some(build_features_graph(
+            ws,
+            &node_map,
+            &ws_resolve.targeted_resolve,
+            &package_map,
+            &metadata_opts.cli_features,
+        ))
+    } else {
+        None
+    };
     // Get a Vec of Packages.
     let actual_packages = package_map
         .into_iter()
@@ -162,7 +241,146 @@ fn build_resolve_graph(
         nodes: node_map.into_iter().map(|(_pkg_id, node)| node).collect(),
         root: ws.current_opt().map(|pkg| pkg.package_id()),
     };
-    Ok((actual_packages, mr))
+    Ok((actual_packages, mr, features))
+}
+
+/// Builds the feature graph of the packages in `node_map`, recording which
+/// feature values, dependency declarations and command-line flags activated
+/// each feature.
+fn build_features_graph(
+    ws: &Workspace<'_>,
+    node_map: &BTreeMap<PackageId, MetadataResolveNode>,
+    resolve: &Resolve,
+    package_map: &BTreeMap<PackageId, Package>,
+    cli_features: &CliFeatures,
+) -> Vec<PackageFeatures> {
+    let normalize_id = |id| -> PackageId { *package_map.get_key_value(&id).unwrap().0 };
+    let mut activators: HashMap<(PackageId, InternedString), BTreeSet<FeatureActivator>> =
+        HashMap::new();
+    let mut activate = |pkg_id: PackageId, feature: InternedString, by: FeatureActivator| {
+        activators.entry((pkg_id, feature)).or_default().insert(by);
+    };
+
+    for member in ws.members() {
+        let member_id = member.package_id();
+        let feature_map = member.summary().features();
+        if cli_features.all_features {
+            for feature in feature_map.keys() {
+                activate(member_id, *feature, FeatureActivator::CommandLine);
+            }
+        }
+        if cli_features.uses_default_features {
+            activate(
+                member_id,
+                InternedString::new("default"),
+                FeatureActivator::CommandLine,
+            );
+        }
+        for fv in cli_features.features.iter() {
+            match fv {
+                FeatureValue::Feature(feature) => {
+                    activate(member_id, *feature, FeatureActivator::CommandLine)
+                }
+                FeatureValue::Dep { .. } => {}
+                FeatureValue::DepFeature {
+                    dep_name,
+                    dep_feature,
+                    ..
+                } => {
+                    for (dep_id, _) in deps_named(resolve, member_id, *dep_name) {
+                        activate(dep_id, *dep_feature, FeatureActivator::CommandLine);
+                    }
+                }
+            }
+        }
+    }
+
+    for &pkg_id in node_map.keys() {
+        let package = normalize_id(pkg_id);
+        for (dep_id, deps) in resolve.deps(pkg_id) {
+            if !node_map.contains_key(&dep_id) {
+                continue;
+            }
+            for dep in deps {
+                let by = || FeatureActivator::Dependency {
+                    package,
+                    name: dep.name_in_toml(),
+                };
+                if dep.uses_default_features() {
+                    activate(dep_id, InternedString::new("default"), by());
+                }
+                for feature in dep.features() {
+                    activate(dep_id, *feature, by());
+                }
+            }
+        }
+
+        let feature_map = resolve.summary(pkg_id).features();
+        for &feature in resolve.features(pkg_id) {
+            let by = || FeatureActivator::Feature { package, feature };
+            for fv in feature_map.get(&feature).into_iter().flatten() {
+                match fv {
+                    FeatureValue::Feature(enabled) => activate(pkg_id, *enabled, by()),
+                    FeatureValue::Dep { .. } => {}
+                    FeatureValue::DepFeature {
+                        dep_name,
+                        dep_feature,
+                        weak,
+                    } => {
+                        // Enabling a feature of an optional dependency also
+                        // enables the implicit feature of that dependency.
+                        if !weak && feature_map.contains_key(dep_name) {
+                            activate(pkg_id, *dep_name, by());
+                        }
+                        for (dep_id, _) in deps_named(resolve, pkg_id, *dep_name) {
+                            activate(dep_id, *dep_feature, by());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    node_map
+        .keys()
+        .map(|&pkg_id| {
+            let activated = resolve.features(pkg_id);
+            let features = resolve
+                .summary(pkg_id)
+                .features()
+                .iter()
+                .map(|(name, fvs)| {
+                    let is_activated = activated.contains(name);
+                    let activated_by = if is_activated {
+                        activators.remove(&(pkg_id, *name)).unwrap_or_default()
+                    } else {
+                        BTreeSet::new()
+                    };
+                    let info = FeatureInfo {
+                        enables: fvs.iter().map(|fv| fv.to_string()).collect(),
+                        activated: is_activated,
+                        activated_by,
+                    };
+                    (*name, info)
+                })
+                .collect();
+            PackageFeatures {
+                id: normalize_id(pkg_id),
+                features,
+            }
+        })
+        .collect()
+}
+
+/// Returns the dependencies of `pkg_id` named `dep_name` in its manifest.
+fn deps_named(
+    resolve: &Resolve,
+    pkg_id: PackageId,
+    dep_name: InternedString,
+) -> impl Iterator<Item = (PackageId, &HashSet<Dependency>)> + '_ {
+    resolve
+        .deps(pkg_id)
+        .filter(move |(_, deps)| deps.iter().any(|dep| dep.name_in_toml() == dep_name))
 }
 
 fn build_resolve_graph_r(
@@ -244,3 +462,28 @@ fn build_resolve_graph_r(
         );
     }
 }
+
+/// Finds the `OUT_DIR` of each build script run by a previous `cargo build`
+/// with the same features, without building anything.
+fn previous_build_scripts(
+    ws: &Workspace<'_>,
+    opt: &OutputMetadataOptions,
+) -> CargoResult<Vec<BuildScriptInfo>> {
+    let mut options = CompileOptions::new(ws.config(), CompileMode::Build)?;
+    options.cli_features = opt.cli_features.clone();
+    let interner = UnitInterner::new();
+    let bcx = ops::create_bcx(ws, &options, &interner)?;
+    let mut build_scripts: Vec<_> = Context::new(&bcx)?
+        .previous_build_script_out_dirs()?
+        .into_iter()
+        .map(|(unit, out_dir, manifest)| BuildScriptInfo {
+            id: unit.pkg.package_id(),
+            generated: manifest
+                .map(|m| m.outputs.iter().map(|path| out_dir.join(path)).collect())
+                .unwrap_or_default(),
+            out_dir,
+        })
+        .collect();
+    build_scripts.sort_by(|a, b| (a.id, &a.out_dir).cmp(&(b.id, &b.out_dir)));
+    Ok(build_scripts)
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::SeekFrom;
@@ -9,17 +9,20 @@ use std::task::Poll;
 
 use crate::core::compiler::{BuildConfig, CompileMode, DefaultExecutor, Executor};
 use crate::core::resolver::CliFeatures;
-use crate::core::{Feature, Shell, Verbosity, Workspace};
+use crate::core::{Dependency, Feature, Shell, Verbosity, Workspace};
 use crate::core::{Package, PackageId, PackageSet, Resolve, SourceId};
-use crate::sources::PathSource;
+use crate::sources::{PathSource, SourceConfigMap, CRATES_IO_REGISTRY};
 use crate::util::errors::CargoResult;
+use crate::util::interning::InternedString;
 use crate::util::toml::TomlManifest;
 use crate::util::{self, human_readable_bytes, restricted_names, Config, FileLock};
 use crate::{drop_println, ops};
 use anyhow::Context as _;
-use cargo_util::paths;
+use cargo_util::{paths, ProcessBuilder};
+use crates_io::NewCrateProvenance;
 use flate2::read::GzDecoder;
 use flate2::{Compression, GzBuilder};
+use ignore::gitignore::GitignoreBuilder;
 use log::debug;
 use serde::Serialize;
 use tar::{Archive, Builder, EntryType, Header, HeaderMode};
@@ -27,8 +30,15 @@ use tar::{Archive, Builder, EntryType, Header, HeaderMode};
 pub struct PackageOpts<'cfg> {
     pub config: &'cfg Config,
     pub list: bool,
+    pub list_format: ListFormat,
+    /// Instead of packaging, print how the files differ from this version
+    /// published in the registry.
+    pub diff: Option<String>,
     pub check_metadata: bool,
     pub allow_dirty: bool,
+    /// Glob patterns, relative to the package root, of files whose
+    /// uncommitted changes don't make the working directory dirty.
+    pub allow_dirty_paths: Vec<String>,
     pub verify: bool,
     pub jobs: Option<i32>,
     pub keep_going: bool,
@@ -37,8 +47,18 @@ pub struct PackageOpts<'cfg> {
     pub cli_features: CliFeatures,
 }
 
+/// The format used by `cargo package --list`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListFormat {
+    /// One archived path per line.
+    Human,
+    /// A JSON object per package describing every archived file.
+    Json,
+}
+
 const ORIGINAL_MANIFEST_FILE: &str = "Cargo.toml.orig";
 const VCS_INFO_FILE: &str = ".cargo_vcs_info.json";
+const PROVENANCE_FILE: &str = ".cargo_provenance.json";
 
 struct ArchiveFile {
     /// The relative path in the archive (not including the top-level package
@@ -64,6 +84,53 @@ enum GeneratedFile {
     Lockfile,
     /// Adds a `.cargo_vcs_info.json` file if in a (clean) git repo.
     VcsInfo(VcsInfo),
+    /// Adds a `.cargo_provenance.json` file with `-Zpublish-provenance`.
+    Provenance(NewCrateProvenance),
+}
+
+/// A package as printed by `cargo package --list --message-format json`.
+#[derive(Serialize)]
+struct ListedPackage {
+    name: InternedString,
+    version: String,
+    id: PackageId,
+    files: Vec<ListedFile>,
+}
+
+#[derive(Serialize)]
+struct ListedFile {
+    /// The relative path in the archive.
+    path: String,
+    /// Either `source` for files copied from disk, or `generated`.
+    kind: &'static str,
+    /// What produced a generated file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generator: Option<&'static str>,
+    /// The uncompressed size in bytes.
+    size: u64,
+    /// Whether the modification time is replaced by a fixed one in the
+    /// archive, which is currently true for every file.
+    mtime_normalized: bool,
+    #[serde(flatten)]
+    rule: InclusionRule,
+}
+
+/// Why a file ended up in the archive.
+#[derive(Serialize)]
+#[serde(tag = "rule", rename_all = "kebab-case")]
+enum InclusionRule {
+    /// Matched by this pattern of `package.include`.
+    Include { pattern: String },
+    /// Not excluded by `package.exclude` or VCS ignore files.
+    Default,
+    /// The original `Cargo.toml`, which is always included.
+    Manifest,
+    /// Added because `package.license-file` points to it.
+    LicenseFile,
+    /// Added because `package.readme` points to it.
+    Readme,
+    /// Generated by Cargo.
+    Generated,
 }
 
 #[derive(Serialize)]
@@ -97,29 +164,56 @@ pub fn package_one(
              the exclude list will be ignored",
         )?;
     }
-    let src_files = src.list_files(pkg)?;
+    let mut src_files = src.list_files(pkg)?;
+    if config.cli_unstable().package_vcs {
+        src_files = remove_export_ignored(pkg, src_files)?;
+    }
 
-    // Check (git) repository state, getting the current commit hash if not
+    // Check repository state, getting the current (git) commit hash if not
     // dirty.
     let vcs_info = if !opts.allow_dirty {
         // This will error if a dirty repo is found.
-        check_repo_state(pkg, &src_files, config)?
+        check_repo_state(pkg, &src_files, &opts.allow_dirty_paths, config)?
     } else {
         None
     };
 
-    let ar_files = build_ar_list(ws, pkg, src_files, vcs_info)?;
+    let listed_src_files = if opts.list && opts.list_format == ListFormat::Json {
+        src_files.clone()
+    } else {
+        Vec::new()
+    };
+    let provenance = provenance(pkg, config)?;
+    let ar_files = build_ar_list(ws, pkg, src_files, vcs_info, provenance)?;
 
     let filecount = ar_files.len();
 
     if opts.list {
-        for ar_file in ar_files {
-            drop_println!(config, "{}", ar_file.rel_str);
+        match opts.list_format {
+            ListFormat::Human => {
+                for ar_file in ar_files {
+                    drop_println!(config, "{}", ar_file.rel_str);
+                }
+            }
+            ListFormat::Json => {
+                let listed = ListedPackage {
+                    name: pkg.name(),
+                    version: pkg.version().to_string(),
+                    id: pkg.package_id(),
+                    files: list_files_json(ws, pkg, &ar_files, &listed_src_files)?,
+                };
+                config.shell().print_json(&listed)?;
+            }
         }
 
         return Ok(None);
     }
 
+    if let Some(version) = &opts.diff {
+        diff_published(ws, pkg, &ar_files, version)?;
+        return Ok(None);
+    }
+
     // Check that the package dependencies are safe to deploy.
     for dep in pkg.dependencies() {
         super::check_dep_has_version(dep, false)?;
@@ -194,8 +288,11 @@ pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option
             &PackageOpts {
                 config: opts.config,
                 list: opts.list,
+                list_format: opts.list_format,
+                diff: opts.diff.clone(),
                 check_metadata: opts.check_metadata,
                 allow_dirty: opts.allow_dirty,
+                allow_dirty_paths: opts.allow_dirty_paths.clone(),
                 verify: opts.verify,
                 jobs: opts.jobs,
                 keep_going: opts.keep_going,
@@ -205,12 +302,12 @@ pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option
             },
         )?;
 
-        if !opts.list {
+        if !opts.list && opts.diff.is_none() {
             dsts.push(result.unwrap());
         }
     }
 
-    if opts.list {
+    if opts.list || opts.diff.is_some() {
         // We're just listing, so there's no file output
         Ok(None)
     } else {
@@ -224,6 +321,7 @@ fn build_ar_list(
     pkg: &Package,
     src_files: Vec<PathBuf>,
     vcs_info: Option<VcsInfo>,
+    provenance: Option<NewCrateProvenance>,
 ) -> CargoResult<Vec<ArchiveFile>> {
     let mut result = Vec::new();
     let root = pkg.root();
@@ -250,10 +348,15 @@ fn build_ar_list(
                 });
             }
             "Cargo.lock" => continue,
-            VCS_INFO_FILE | ORIGINAL_MANIFEST_FILE => anyhow::bail!(
-                "invalid inclusion of reserved file name {} in package source",
-                rel_str
-            ),
+            // The provenance file name is only reserved when it's generated.
+            VCS_INFO_FILE | PROVENANCE_FILE | ORIGINAL_MANIFEST_FILE
+                if rel_str != PROVENANCE_FILE || provenance.is_some() =>
+            {
+                anyhow::bail!(
+                    "invalid inclusion of reserved file name {} in package source",
+                    rel_str
+                )
+            }
             _ => {
                 result.push(ArchiveFile {
                     rel_path,
@@ -277,6 +380,13 @@ fn build_ar_list(
             contents: FileContents::Generated(GeneratedFile::VcsInfo(vcs_info)),
         });
     }
+    if let Some(provenance) = provenance {
+        result.push(ArchiveFile {
+            rel_path: PathBuf::from(PROVENANCE_FILE),
+            rel_str: PROVENANCE_FILE.to_string(),
+            contents: FileContents::Generated(GeneratedFile::Provenance(provenance)),
+        });
+    }
     if let Some(license_file) = &pkg.manifest().metadata().license_file {
         let license_path = Path::new(license_file);
         let abs_file_path = paths::normalize_path(&pkg.root().join(license_path));
@@ -368,6 +478,187 @@ fn check_for_file_and_add(
     Ok(())
 }
 
+/// Describes the files of the archive for `cargo package --list --message-format json`.
+///
+/// `src_files` are the files found by listing the package source, which is
+/// used to tell them apart from files added for `license-file` or `readme`.
+fn list_files_json(
+    ws: &Workspace<'_>,
+    pkg: &Package,
+    ar_files: &[ArchiveFile],
+    src_files: &[PathBuf],
+) -> CargoResult<Vec<ListedFile>> {
+    let root = pkg.root();
+    let include_patterns = pkg
+        .manifest()
+        .include()
+        .iter()
+        .filter(|pattern| !pattern.starts_with('!'))
+        .map(|pattern| {
+            let mut builder = GitignoreBuilder::new(root);
+            builder.add_line(None, pattern)?;
+            Ok((pattern, builder.build()?))
+        })
+        .collect::<CargoResult<Vec<_>>>()?;
+    let license_path = pkg
+        .manifest()
+        .metadata()
+        .license_file
+        .as_ref()
+        .map(|license_file| paths::normalize_path(&root.join(license_file)));
+
+    let mut files = Vec::with_capacity(ar_files.len());
+    for ar_file in ar_files {
+        let (kind, generator, size, rule) = match &ar_file.contents {
+            FileContents::OnDisk(disk_path) => {
+                let size = fs::metadata(disk_path)
+                    .with_context(|| {
+                        format!("could not learn metadata for: `{}`", disk_path.display())
+                    })?
+                    .len();
+                let rule = if ar_file.rel_str == ORIGINAL_MANIFEST_FILE {
+                    InclusionRule::Manifest
+                } else if src_files.contains(disk_path) {
+                    include_patterns
+                        .iter()
+                        .find(|(_, matcher)| {
+                            matcher
+                                .matched_path_or_any_parents(&ar_file.rel_path, false)
+                                .is_ignore()
+                        })
+                        .map(|(pattern, _)| InclusionRule::Include {
+                            pattern: pattern.to_string(),
+                        })
+                        .unwrap_or(InclusionRule::Default)
+                } else if license_path.as_ref() == Some(disk_path) {
+                    InclusionRule::LicenseFile
+                } else {
+                    InclusionRule::Readme
+                };
+                ("source", None, size, rule)
+            }
+            FileContents::Generated(generated_kind) => {
+                let generator = match generated_kind {
+                    GeneratedFile::Manifest => "manifest-rewrite",
+                    GeneratedFile::Lockfile => "lockfile",
+                    GeneratedFile::VcsInfo(_) => "vcs-info",
+                    GeneratedFile::Provenance(_) => "provenance",
+                };
+                let size = generate_file(ws, pkg, generated_kind)?.len() as u64;
+                ("generated", Some(generator), size, InclusionRule::Generated)
+            }
+        };
+        files.push(ListedFile {
+            path: ar_file.rel_str.clone(),
+            kind,
+            generator,
+            size,
+            mtime_normalized: true,
+            rule,
+        });
+    }
+    Ok(files)
+}
+
+/// Prints the files which were added (`A`), deleted (`D`) or modified (`M`)
+/// compared to the `.crate` of `version` published in the registry of `pkg`.
+///
+/// The registry is the only one listed in `package.publish`, or crates.io.
+fn diff_published(
+    ws: &Workspace<'_>,
+    pkg: &Package,
+    ar_files: &[ArchiveFile],
+    version: &str,
+) -> CargoResult<()> {
+    let config = ws.config();
+    let source_id = match pkg.publish().as_deref() {
+        Some([registry]) if registry != CRATES_IO_REGISTRY => {
+            SourceId::alt_registry(config, registry)?
+        }
+        _ => SourceId::crates_io(config)?,
+    };
+    let mut source = SourceConfigMap::new(config)?.load(source_id, &HashSet::new())?;
+    let dep = Dependency::parse(pkg.name(), Some(&format!("={}", version)), source_id)?;
+    let published =
+        super::common_for_install_and_uninstall::select_dep_pkg(&mut source, dep, config, false)?;
+    config.shell().status(
+        "Comparing",
+        format!("{} against v{}", pkg.package_id(), published.version()),
+    )?;
+
+    // The published files, from the directory the `.crate` was unpacked to.
+    let mut published_files = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(published.root()) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_path = entry.path().strip_prefix(published.root()).unwrap();
+        let rel_str = rel_path.to_string_lossy().replace('\\', "/");
+        // Written by Cargo after unpacking.
+        if rel_str != ".cargo-ok" {
+            published_files.insert(rel_str, entry.into_path());
+        }
+    }
+
+    let mut changes = Vec::new();
+    for ar_file in ar_files {
+        let rel_str = ar_file.rel_str.replace('\\', "/");
+        match published_files.remove(&rel_str) {
+            Some(published_path) => {
+                let contents = match &ar_file.contents {
+                    FileContents::OnDisk(disk_path) => paths::read_bytes(disk_path)?,
+                    FileContents::Generated(kind) => generate_file(ws, pkg, kind)?.into_bytes(),
+                };
+                if contents != paths::read_bytes(&published_path)? {
+                    changes.push((rel_str, 'M'));
+                }
+            }
+            None => changes.push((rel_str, 'A')),
+        }
+    }
+    changes.extend(published_files.into_keys().map(|rel_str| (rel_str, 'D')));
+    changes.sort_unstable();
+    for (rel_str, change) in changes {
+        drop_println!(config, "{} {}", change, rel_str);
+    }
+    Ok(())
+}
+
+/// Returns the provenance of `pkg` from the `[publish.provenance]` config,
+/// which is both embedded in the `.crate` file and sent to the registry, or
+/// `None` without `-Zpublish-provenance` or the config.
+pub(crate) fn provenance(
+    pkg: &Package,
+    config: &Config,
+) -> CargoResult<Option<NewCrateProvenance>> {
+    let provenance = match config.publish_provenance_config()? {
+        Some(provenance) => provenance,
+        None => return Ok(None),
+    };
+    let vcs_commit = match provenance.commit {
+        Some(commit) => Some(commit),
+        None => git2::Repository::discover(pkg.root())
+            .and_then(|repo| Ok(repo.head()?.peel_to_commit()?.id().to_string()))
+            .ok(),
+    };
+    Ok(Some(NewCrateProvenance {
+        vcs_commit,
+        ci_run_url: provenance.ci_run_url,
+        builder: provenance.builder,
+    }))
+}
+
+/// Returns the contents of a file generated by Cargo.
+fn generate_file(ws: &Workspace<'_>, pkg: &Package, kind: &GeneratedFile) -> CargoResult<String> {
+    Ok(match kind {
+        GeneratedFile::Manifest => pkg.to_registry_toml(ws)?,
+        GeneratedFile::Lockfile => build_lock(ws, pkg)?,
+        GeneratedFile::VcsInfo(s) => serde_json::to_string_pretty(s)?,
+        GeneratedFile::Provenance(p) => serde_json::to_string_pretty(p)?,
+    })
+}
+
 /// Construct `Cargo.lock` for the package to be published.
 fn build_lock(ws: &Workspace<'_>, orig_pkg: &Package) -> CargoResult<String> {
     let config = ws.config();
@@ -444,15 +735,160 @@ fn check_metadata(pkg: &Package, config: &Config) -> CargoResult<()> {
     Ok(())
 }
 
-/// Checks if the package source is in a *git* DVCS repository. If *git*, and
-/// the source is *dirty* (e.g., has uncommitted changes) then `bail!` with an
-/// informative message. Otherwise return the sha1 hash of the current *HEAD*
-/// commit, or `None` if no repo is found.
+/// Removes the files marked with the `export-ignore` git attribute, which
+/// `git archive` leaves out as well.
+fn remove_export_ignored(pkg: &Package, src_files: Vec<PathBuf>) -> CargoResult<Vec<PathBuf>> {
+    let repo = match git2::Repository::discover(pkg.root()) {
+        Ok(repo) => repo,
+        Err(_) => return Ok(src_files),
+    };
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir.to_path_buf(),
+        None => return Ok(src_files),
+    };
+    let mut kept = Vec::with_capacity(src_files.len());
+    for file in src_files {
+        // The manifest is always packaged.
+        if file == pkg.manifest_path() {
+            kept.push(file);
+            continue;
+        }
+        let rel_path = match file.strip_prefix(&workdir) {
+            Ok(rel_path) => rel_path,
+            Err(_) => {
+                kept.push(file);
+                continue;
+            }
+        };
+        // The attribute may be set on the file or any directory containing it.
+        let mut ignored = false;
+        for path in rel_path.ancestors().filter(|p| !p.as_os_str().is_empty()) {
+            let value =
+                repo.get_attr(path, "export-ignore", git2::AttrCheckFlags::FILE_THEN_INDEX)?;
+            if let git2::AttrValue::True = git2::AttrValue::from_string(value) {
+                ignored = true;
+                break;
+            }
+        }
+        if ignored {
+            debug!("export-ignore: {}", rel_path.display());
+        } else {
+            kept.push(file);
+        }
+    }
+    Ok(kept)
+}
+
+/// Version control systems other than git which `-Z package-vcs` asks for
+/// uncommitted changes.
+#[derive(Clone, Copy)]
+enum OtherVcs {
+    Mercurial,
+    Jujutsu,
+}
+
+impl OtherVcs {
+    fn name(self) -> &'static str {
+        match self {
+            OtherVcs::Mercurial => "Mercurial",
+            OtherVcs::Jujutsu => "Jujutsu",
+        }
+    }
+}
+
+/// Finds the closest Mercurial or Jujutsu checkout containing `path`, unless
+/// a git repository is closer. A Jujutsu checkout colocated with git wins.
+fn find_other_vcs(path: &Path) -> Option<(OtherVcs, PathBuf)> {
+    for dir in path.ancestors() {
+        if dir.join(".jj").is_dir() {
+            return Some((OtherVcs::Jujutsu, dir.to_path_buf()));
+        }
+        if dir.join(".hg").is_dir() {
+            return Some((OtherVcs::Mercurial, dir.to_path_buf()));
+        }
+        if dir.join(".git").exists() {
+            return None;
+        }
+    }
+    None
+}
+
+/// Returns the package files, relative to the package root, which are
+/// `dirty_files` or inside of them, skipping those matching `allow_dirty_paths`.
+fn dirty_src_files(
+    p: &Package,
+    src_files: &[PathBuf],
+    dirty_files: &[PathBuf],
+    allow_dirty_paths: &[glob::Pattern],
+) -> Vec<String> {
+    let match_opts = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    // This is a lazy n^2 check, but seems fine with thousands of files.
+    src_files
+        .iter()
+        .filter(|src_file| dirty_files.iter().any(|path| src_file.starts_with(path)))
+        .map(|path| path.strip_prefix(p.root()).unwrap_or(path))
+        .filter(|rel_path| {
+            let rel_str = rel_path.to_string_lossy().replace('\\', "/");
+            !allow_dirty_paths
+                .iter()
+                .any(|pat| pat.matches_with(&rel_str, match_opts))
+        })
+        .map(|rel_path| rel_path.display().to_string())
+        .collect()
+}
+
+fn bail_dirty(dirty_src_files: &[String], vcs: &str) -> CargoResult<()> {
+    anyhow::bail!(
+        "{} files in the working directory contain changes that were \
+         not yet committed into {}:\n\n{}\n\n\
+         to proceed despite this and include the uncommitted changes, pass the `--allow-dirty` flag",
+        dirty_src_files.len(),
+        vcs,
+        dirty_src_files.join("\n")
+    )
+}
+
+/// Checks if the package source is in a DVCS repository. If it is, and the
+/// source is *dirty* (e.g., has uncommitted changes) then `bail!` with an
+/// informative message. Otherwise for *git* return the sha1 hash of the
+/// current *HEAD* commit, or `None` if no git repo is found.
+///
+/// Mercurial and Jujutsu checkouts are only looked at with `-Z package-vcs`.
+/// If they are colocated with git, git is checked as well.
 fn check_repo_state(
     p: &Package,
     src_files: &[PathBuf],
+    allow_dirty_paths: &[String],
     config: &Config,
 ) -> CargoResult<Option<VcsInfo>> {
+    let allow_dirty_paths = allow_dirty_paths
+        .iter()
+        .map(|glob| {
+            glob::Pattern::new(glob)
+                .with_context(|| format!("invalid `--allow-dirty-paths` glob `{}`", glob))
+        })
+        .collect::<CargoResult<Vec<_>>>()?;
+
+    if config.cli_unstable().package_vcs {
+        if let Some((vcs, root)) = find_other_vcs(p.root()) {
+            debug!("found a {} repo at {:?}", vcs.name(), root);
+            let dirty_files = other_vcs_dirty_files(vcs, &root)?;
+            let dirty_src_files = dirty_src_files(p, src_files, &dirty_files, &allow_dirty_paths);
+            if !dirty_src_files.is_empty() {
+                bail_dirty(&dirty_src_files, vcs.name())?;
+            }
+            // `.cargo_vcs_info.json` only records git commits, so take them
+            // from a colocated git repository, whose `HEAD` a Jujutsu
+            // checkout keeps at the parent of its working-copy commit.
+            if !root.join(".git").exists() {
+                return Ok(None);
+            }
+        }
+    }
+
     if let Ok(repo) = git2::Repository::discover(p.root()) {
         if let Some(workdir) = repo.workdir() {
             debug!("found a git repo at {:?}", workdir);
@@ -470,7 +906,7 @@ fn check_repo_state(
                         .unwrap_or("")
                         .replace("\\", "/");
                     return Ok(Some(VcsInfo {
-                        git: git(p, src_files, &repo)?,
+                        git: git(p, src_files, &allow_dirty_paths, &repo)?,
                         path_in_vcs,
                     }));
                 }
@@ -493,7 +929,12 @@ fn check_repo_state(
     // directory is dirty or not, thus we have to assume that it's clean.
     return Ok(None);
 
-    fn git(p: &Package, src_files: &[PathBuf], repo: &git2::Repository) -> CargoResult<GitVcsInfo> {
+    fn git(
+        p: &Package,
+        src_files: &[PathBuf],
+        allow_dirty_paths: &[glob::Pattern],
+        repo: &git2::Repository,
+    ) -> CargoResult<GitVcsInfo> {
         // This is a collection of any dirty or untracked files. This covers:
         // - new/modified/deleted/renamed/type change (index or worktree)
         // - untracked files (which are "new" worktree files)
@@ -506,32 +947,63 @@ fn check_repo_state(
         status_submodules(repo, &mut dirty_files)?;
 
         // Find the intersection of dirty in git, and the src_files that would
-        // be packaged. This is a lazy n^2 check, but seems fine with
-        // thousands of files.
-        let dirty_src_files: Vec<String> = src_files
-            .iter()
-            .filter(|src_file| dirty_files.iter().any(|path| src_file.starts_with(path)))
-            .map(|path| {
-                path.strip_prefix(p.root())
-                    .unwrap_or(path)
-                    .display()
-                    .to_string()
-            })
-            .collect();
-        if dirty_src_files.is_empty() {
-            let rev_obj = repo.revparse_single("HEAD")?;
-            Ok(GitVcsInfo {
-                sha1: rev_obj.id().to_string(),
-            })
-        } else {
-            anyhow::bail!(
-                "{} files in the working directory contain changes that were \
-                 not yet committed into git:\n\n{}\n\n\
-                 to proceed despite this and include the uncommitted changes, pass the `--allow-dirty` flag",
-                dirty_src_files.len(),
-                dirty_src_files.join("\n")
+        // be packaged.
+        let dirty_src_files = dirty_src_files(p, src_files, &dirty_files, allow_dirty_paths);
+        if !dirty_src_files.is_empty() {
+            bail_dirty(&dirty_src_files, "git")?;
+        }
+        let rev_obj = repo.revparse_single("HEAD")?;
+        Ok(GitVcsInfo {
+            sha1: rev_obj.id().to_string(),
+        })
+    }
+
+    // Helper to ask `hg` or `jj` for the changed, new and (for Mercurial)
+    // ignored files of the checkout at `root`.
+    fn other_vcs_dirty_files(vcs: OtherVcs, root: &Path) -> CargoResult<Vec<PathBuf>> {
+        let mut cmd = match vcs {
+            OtherVcs::Mercurial => {
+                let mut cmd = ProcessBuilder::new("hg");
+                cmd.env("HGPLAIN", "1")
+                    .args(&["status", "--modified", "--added", "--removed"])
+                    .args(&["--deleted", "--unknown", "--ignored", "--print0"]);
+                cmd
+            }
+            OtherVcs::Jujutsu => {
+                let mut cmd = ProcessBuilder::new("jj");
+                cmd.args(&["diff", "--summary", "--no-pager", "--color", "never"]);
+                cmd
+            }
+        };
+        cmd.cwd(root);
+        let output = cmd.exec_with_output().with_context(|| {
+            format!(
+                "failed to retrieve {} status from repo {}",
+                vcs.name(),
+                root.display()
             )
+        })?;
+        let stdout = String::from_utf8(output.stdout)
+            .with_context(|| format!("{} status is not valid utf-8", vcs.name()))?;
+        let separator = match vcs {
+            OtherVcs::Mercurial => '\0',
+            OtherVcs::Jujutsu => '\n',
+        };
+        let mut dirty_files = Vec::new();
+        for entry in stdout.split(separator) {
+            // Each entry is a one letter status, a space and the path
+            // relative to the root of the checkout.
+            let (status, path) = match entry.split_once(' ') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            if status == "I" && path.ends_with("Cargo.lock") {
+                // It is OK to include Cargo.lock even if it is ignored.
+                continue;
+            }
+            dirty_files.push(root.join(path));
         }
+        Ok(dirty_files)
     }
 
     // Helper to collect dirty statuses for a single repo.
@@ -604,6 +1076,7 @@ fn tar(
     // Put all package files into a compressed archive.
     let mut ar = Builder::new(encoder);
     let config = ws.config();
+    let epoch = archive_epoch(config)?;
 
     let base_name = format!("{}-{}", pkg.name(), pkg.version());
     let base_path = Path::new(&base_name);
@@ -629,6 +1102,9 @@ fn tar(
                     format!("could not learn metadata for: `{}`", disk_path.display())
                 })?;
                 header.set_metadata_in_mode(&metadata, HeaderMode::Deterministic);
+                if let Some(epoch) = epoch {
+                    header.set_mtime(epoch);
+                }
                 header.set_cksum();
                 ar.append_data(&mut header, &ar_path, &mut file)
                     .with_context(|| {
@@ -637,16 +1113,18 @@ fn tar(
                 uncompressed_size += metadata.len() as u64;
             }
             FileContents::Generated(generated_kind) => {
-                let contents = match generated_kind {
-                    GeneratedFile::Manifest => pkg.to_registry_toml(ws)?,
-                    GeneratedFile::Lockfile => build_lock(ws, pkg)?,
-                    GeneratedFile::VcsInfo(ref s) => serde_json::to_string_pretty(s)?,
-                };
+                let contents = generate_file(ws, pkg, &generated_kind)?;
                 header.set_entry_type(EntryType::file());
                 header.set_mode(0o644);
                 header.set_size(contents.len() as u64);
                 // use something nonzero to avoid rust-lang/cargo#9512
-                header.set_mtime(1);
+                header.set_mtime(epoch.unwrap_or(1));
+                if epoch.is_some() {
+                    // Fill in the owner like `HeaderMode::Deterministic` does,
+                    // instead of leaving the fields empty.
+                    header.set_uid(0);
+                    header.set_gid(0);
+                }
                 header.set_cksum();
                 ar.append_data(&mut header, &ar_path, contents.as_bytes())
                     .with_context(|| format!("could not archive source file `{}`", rel_str))?;
@@ -660,6 +1138,31 @@ fn tar(
     Ok(uncompressed_size)
 }
 
+/// Returns the modification time given to every file in the archive with
+/// `-Z package-epoch`, from `package.epoch` or else `SOURCE_DATE_EPOCH`.
+///
+/// Without it, files from disk get the fixed time of
+/// [`HeaderMode::Deterministic`], and generated files a time of `1`.
+fn archive_epoch(config: &Config) -> CargoResult<Option<u64>> {
+    if !config.cli_unstable().package_epoch {
+        return Ok(None);
+    }
+    let epoch = match config.get::<Option<u64>>("package.epoch")? {
+        Some(epoch) => epoch,
+        None => match config.env().get("SOURCE_DATE_EPOCH") {
+            Some(epoch) => epoch.trim().parse().map_err(|_| {
+                anyhow::format_err!(
+                    "`SOURCE_DATE_EPOCH` must be a number of seconds since the Unix epoch, found `{}`",
+                    epoch
+                )
+            })?,
+            None => return Ok(None),
+        },
+    };
+    // use something nonzero to avoid rust-lang/cargo#9512
+    Ok(Some(epoch.max(1)))
+}
+
 /// Generate warnings when packaging Cargo.lock, and the resolve have changed.
 fn compare_resolve(
     config: &Config,
@@ -852,14 +1355,56 @@ fn run_verify(
                 required_features_filterable: true,
             },
             target_rustdoc_args: None,
-            target_rustc_args: rustc_args,
+            target_rustc_args: rustc_args.clone(),
             target_rustc_crate_types: None,
             rustdoc_document_private_items: false,
+            rustdoc_document_private_members: false,
             honor_rust_version: true,
+            auto_required_features: false,
         },
         &exec,
     )?;
 
+    // Additionally check the package for the configured targets, to catch
+    // platform-specific breakage.
+    let verify_targets = if config.cli_unstable().publish_verify_targets {
+        let targets: Option<Vec<String>> = config.get("publish.verify-targets")?;
+        targets.unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    if !verify_targets.is_empty() {
+        config.shell().status(
+            "Verifying",
+            format!("{} for {}", pkg, verify_targets.join(", ")),
+        )?;
+        ops::compile_with_exec(
+            &ws,
+            &ops::CompileOptions {
+                build_config: BuildConfig::new(
+                    config,
+                    opts.jobs,
+                    opts.keep_going,
+                    &verify_targets,
+                    CompileMode::Check { test: false },
+                )?,
+                cli_features: opts.cli_features.clone(),
+                spec: ops::Packages::Packages(Vec::new()),
+                filter: ops::CompileFilter::Default {
+                    required_features_filterable: true,
+                },
+                target_rustdoc_args: None,
+                target_rustc_args: rustc_args,
+                target_rustc_crate_types: None,
+                rustdoc_document_private_items: false,
+                rustdoc_document_private_members: false,
+                honor_rust_version: true,
+                auto_required_features: false,
+            },
+            &exec,
+        )?;
+    }
+
     // Check that `build.rs` didn't modify any files in the `src` directory.
     let ws_fingerprint = hash_all(&dst)?;
     if pkg_fingerprint != ws_fingerprint {
@@ -9,6 +9,11 @@ pub fn pkgid(ws: &Workspace<'_>, spec: Option<&str>) -> CargoResult<PackageIdSpe
     };
 
     let pkgid = match spec {
+        // `Cargo.lock` doesn't record the kinds of the dependencies, which
+        // qualifiers in the spec select packages by, so resolve again for them.
+        Some(spec) if PackageIdSpec::is_qualified(spec) => {
+            ops::resolve_ws(ws)?.1.query_qualified(spec, ws.config())?
+        }
         Some(spec) => PackageIdSpec::query_str(spec, resolve.iter())?,
         None => ws.current()?.package_id(),
     };
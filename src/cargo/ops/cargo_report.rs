@@ -0,0 +1,665 @@
+//! Implementation of the reports of `cargo report` which are computed from
+//! the workspace or read from the target directory, rather than recorded by
+//! a previous build like the future-incompat reports.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::task::Poll;
+
+use semver::Version;
+use serde::Serialize;
+
+use crate::core::compiler::size_report::SizeReport;
+use crate::core::dependency::{ArtifactKind, DepKind};
+use crate::core::{Dependency, PackageId, PackageIdSpec, QueryKind, SourceId, Workspace};
+use crate::ops;
+use crate::sources::{SourceConfigMap, CRATES_IO_INDEX};
+use crate::util::errors::CargoResult;
+use crate::util::CanonicalUrl;
+use crate::{drop_print, drop_println};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Human,
+    Json,
+}
+
+impl ReportFormat {
+    pub const POSSIBLE_VALUES: [&'static str; 2] = ["human", "json"];
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> CargoResult<Self> {
+        match s {
+            "human" => Ok(ReportFormat::Human),
+            "json" => Ok(ReportFormat::Json),
+            f => anyhow::bail!("unknown report format `{}`", f),
+        }
+    }
+}
+
+/// How a package is reached from the workspace members.
+///
+/// A path through the dependency graph is classified by its first edge
+/// which isn't a normal dependency, since removing that edge is what would
+/// drop the package from the path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Reach {
+    Normal,
+    Build,
+    Dev,
+    Artifact(ArtifactKind),
+}
+
+impl fmt::Display for Reach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reach::Normal => f.write_str("normal"),
+            Reach::Build => f.write_str("build"),
+            Reach::Dev => f.write_str("dev"),
+            Reach::Artifact(kind) => write!(f, "artifact ({})", kind),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DepKindsJson {
+    packages: Vec<PackageDepKinds>,
+}
+
+#[derive(Serialize)]
+struct PackageDepKinds {
+    id: PackageId,
+    /// Any of `normal`, `build`, `dev` and `artifact`.
+    kinds: Vec<&'static str>,
+    artifact_kinds: Vec<ArtifactKind>,
+}
+
+/// Reports through which kinds of dependencies every package of the lock
+/// file is reachable from the workspace members.
+pub fn report_dep_kinds(ws: &Workspace<'_>, format: ReportFormat) -> CargoResult<()> {
+    let (_, resolve) = ops::resolve_ws(ws)?;
+
+    let members: HashSet<PackageId> = ws.members().map(|m| m.package_id()).collect();
+    let mut reached: BTreeMap<PackageId, BTreeSet<Reach>> = BTreeMap::new();
+    let mut visited = HashSet::new();
+    let mut queue: VecDeque<(PackageId, Reach)> =
+        members.iter().map(|id| (*id, Reach::Normal)).collect();
+    while let Some((pkg_id, reach)) = queue.pop_front() {
+        if !visited.insert((pkg_id, reach)) {
+            continue;
+        }
+        for (dep_id, deps) in resolve.deps(pkg_id) {
+            for dep in deps {
+                // Only the dev-dependencies of the members are ever built.
+                if dep.kind() == DepKind::Development && !members.contains(&pkg_id) {
+                    continue;
+                }
+                let mut edges = Vec::new();
+                match dep.artifact() {
+                    Some(artifact) => {
+                        edges.extend(artifact.kinds().iter().map(|k| Reach::Artifact(*k)));
+                        if artifact.is_lib() {
+                            edges.push(edge_reach(dep.kind()));
+                        }
+                    }
+                    None => edges.push(edge_reach(dep.kind())),
+                }
+                for edge in edges {
+                    let dep_reach = if reach == Reach::Normal { edge } else { reach };
+                    reached.entry(dep_id).or_default().insert(dep_reach);
+                    queue.push_back((dep_id, dep_reach));
+                }
+            }
+        }
+    }
+
+    let config = ws.config();
+    match format {
+        ReportFormat::Json => {
+            let packages = reached
+                .into_iter()
+                .map(|(id, reaches)| {
+                    let mut kinds = BTreeSet::new();
+                    let mut artifact_kinds = Vec::new();
+                    for reach in reaches {
+                        kinds.insert(match reach {
+                            Reach::Normal => "normal",
+                            Reach::Build => "build",
+                            Reach::Dev => "dev",
+                            Reach::Artifact(kind) => {
+                                artifact_kinds.push(kind);
+                                "artifact"
+                            }
+                        });
+                    }
+                    PackageDepKinds {
+                        id,
+                        kinds: kinds.into_iter().collect(),
+                        artifact_kinds,
+                    }
+                })
+                .collect();
+            config.shell().print_json(&DepKindsJson { packages })?;
+        }
+        ReportFormat::Human => {
+            let names: Vec<String> = reached
+                .keys()
+                .map(|id| format!("{} v{}", id.name(), id.version()))
+                .collect();
+            let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+            let mut not_normal = 0;
+            for (name, reaches) in names.iter().zip(reached.values()) {
+                if !reaches.contains(&Reach::Normal) {
+                    not_normal += 1;
+                }
+                drop_print!(config, "{:<width$}  ", name, width = width);
+                let reaches: Vec<String> = reaches.iter().map(|r| r.to_string()).collect();
+                drop_println!(config, "{}", reaches.join(", "));
+            }
+            if not_normal > 0 {
+                config.shell().note(format!(
+                    "{} of {} packages are only reachable through build, dev or artifact dependencies",
+                    not_normal,
+                    reached.len()
+                ))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn edge_reach(kind: DepKind) -> Reach {
+    match kind {
+        DepKind::Normal => Reach::Normal,
+        DepKind::Build => Reach::Build,
+        DepKind::Development => Reach::Dev,
+    }
+}
+
+#[derive(Serialize)]
+struct UnusedPatchesJson {
+    unused_patches: Vec<UnusedPatch>,
+}
+
+#[derive(Serialize)]
+struct UnusedPatch {
+    id: PackageId,
+    /// The source which is patched, as written in `[patch]`.
+    patched_source: String,
+    #[serde(flatten)]
+    reason: UnusedReason,
+}
+
+/// Why a `[patch]` entry didn't end up in the crate graph.
+#[derive(Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum UnusedReason {
+    /// Nothing depends on a package of that name from the patched source.
+    NameMismatch {
+        /// The sources the package is used from instead.
+        used_from: Vec<String>,
+    },
+    /// The version of the patch doesn't match any requirement on it.
+    VersionMismatch { requirements: Vec<Requirement> },
+    /// The version of the patch matches a requirement, but another version
+    /// was selected, like a newer one or one locked in `Cargo.lock`.
+    AlreadySatisfied { used_instead: PackageId },
+}
+
+#[derive(Serialize)]
+struct Requirement {
+    req: String,
+    required_by: PackageId,
+}
+
+/// Reports the `[patch]` entries which weren't used, and why each of them
+/// didn't match.
+pub fn report_unused_patches(ws: &Workspace<'_>, format: ReportFormat) -> CargoResult<()> {
+    let (_, resolve) = ops::resolve_ws(ws)?;
+
+    let mut patched_urls = Vec::new();
+    for (url, deps) in ws.root_patch()? {
+        let canonical = CanonicalUrl::new(&url)?;
+        for dep in deps {
+            patched_urls.push((dep, url.clone(), canonical.clone()));
+        }
+    }
+
+    let mut unused_patches = Vec::new();
+    for &unused in resolve.unused_patches() {
+        let patches = patched_urls.iter().filter(|(dep, ..)| {
+            dep.package_name() == unused.name()
+                && dep.source_id().canonical_url() == unused.source_id().canonical_url()
+        });
+        for (_, url, canonical) in patches {
+            let mut edges = Vec::new();
+            for pkg_id in resolve.iter() {
+                for (dep_id, deps) in resolve.deps(pkg_id) {
+                    edges.extend(
+                        deps.iter()
+                            .filter(|dep| {
+                                dep.package_name() == unused.name()
+                                    && dep.source_id().canonical_url() == canonical
+                            })
+                            .map(|dep| (pkg_id, dep, dep_id)),
+                    );
+                }
+            }
+
+            let reason = match edges
+                .iter()
+                .find(|(_, dep, _)| dep.version_req().matches_unlocked(unused.version()))
+            {
+                Some(&(_, _, used_instead)) => UnusedReason::AlreadySatisfied { used_instead },
+                None if edges.is_empty() => {
+                    let used_from: BTreeSet<String> = resolve
+                        .iter()
+                        .filter(|id| id.name() == unused.name())
+                        .map(|id| id.source_id().display_registry_name())
+                        .collect();
+                    UnusedReason::NameMismatch {
+                        used_from: used_from.into_iter().collect(),
+                    }
+                }
+                None => UnusedReason::VersionMismatch {
+                    requirements: edges
+                        .iter()
+                        .map(|(required_by, dep, _)| Requirement {
+                            req: dep.version_req().to_string(),
+                            required_by: *required_by,
+                        })
+                        .collect(),
+                },
+            };
+            let patched_source = if url.as_str() == CRATES_IO_INDEX {
+                "crates-io".to_string()
+            } else {
+                url.to_string()
+            };
+            unused_patches.push(UnusedPatch {
+                id: unused,
+                patched_source,
+                reason,
+            });
+        }
+    }
+
+    let config = ws.config();
+    match format {
+        ReportFormat::Json => {
+            config
+                .shell()
+                .print_json(&UnusedPatchesJson { unused_patches })?;
+        }
+        ReportFormat::Human => {
+            if unused_patches.is_empty() {
+                config.shell().note("all patches are used")?;
+            }
+            for patch in &unused_patches {
+                let name = patch.id.name();
+                drop_println!(
+                    config,
+                    "Patch `{}` for `{}` is unused:",
+                    patch.id,
+                    patch.patched_source
+                );
+                match &patch.reason {
+                    UnusedReason::NameMismatch { used_from } => {
+                        drop_println!(
+                            config,
+                            "  no package depends on `{}` from `{}`",
+                            name,
+                            patch.patched_source
+                        );
+                        if !used_from.is_empty() {
+                            drop_println!(
+                                config,
+                                "  `{}` is used from: {}",
+                                name,
+                                used_from.join(", ")
+                            );
+                        }
+                    }
+                    UnusedReason::VersionMismatch { requirements } => {
+                        drop_println!(
+                            config,
+                            "  version {} doesn't match any requirement on `{}`:",
+                            patch.id.version(),
+                            name
+                        );
+                        for req in requirements {
+                            drop_println!(
+                                config,
+                                "    `{}` required by {} v{}",
+                                req.req,
+                                req.required_by.name(),
+                                req.required_by.version()
+                            );
+                        }
+                    }
+                    UnusedReason::AlreadySatisfied { used_instead } => {
+                        drop_println!(
+                            config,
+                            "  the requirements on `{}` are already satisfied by `{}`, \
+                             which is used instead",
+                            name,
+                            used_instead
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct OutdatedJson {
+    packages: Vec<OutdatedPackage>,
+}
+
+#[derive(Serialize)]
+struct OutdatedPackage {
+    id: PackageId,
+    /// The newest version matching all the requirements on the package, if
+    /// it's newer than the locked one.
+    compatible: Option<Version>,
+    /// The newest version published, if it's newer than the locked one.
+    latest: Option<Version>,
+    /// Whether a workspace member depends on the package itself.
+    direct: bool,
+    /// The workspace members depending on the package, directly or not.
+    members: Vec<PackageId>,
+    /// The requirements which don't match `latest`.
+    held_back_by: Vec<Requirement>,
+}
+
+/// Reports the packages of the lock file from registries which have newer
+/// versions, compatible with the requirements on them or not.
+///
+/// The versions are read from the index, updated first unless offline.
+/// Yanked versions are ignored, as are pre-releases unless the locked version
+/// is one.
+pub fn report_outdated(ws: &Workspace<'_>, format: ReportFormat) -> CargoResult<()> {
+    let config = ws.config();
+    let (_, resolve) = ops::resolve_ws(ws)?;
+
+    let members: Vec<PackageId> = ws.members().map(|m| m.package_id()).collect();
+    let mut reaching_members: HashMap<PackageId, BTreeSet<PackageId>> = HashMap::new();
+    for &member in &members {
+        let mut queue = VecDeque::from([member]);
+        let mut visited = HashSet::new();
+        while let Some(pkg_id) = queue.pop_front() {
+            for (dep_id, _) in resolve.deps(pkg_id) {
+                if visited.insert(dep_id) {
+                    reaching_members.entry(dep_id).or_default().insert(member);
+                    queue.push_back(dep_id);
+                }
+            }
+        }
+    }
+
+    let mut by_source: BTreeMap<SourceId, Vec<PackageId>> = BTreeMap::new();
+    for pkg_id in resolve.iter() {
+        if pkg_id.source_id().is_registry() {
+            by_source
+                .entry(pkg_id.source_id())
+                .or_default()
+                .push(pkg_id);
+        }
+    }
+
+    let mut outdated = Vec::new();
+    {
+        let _lock = config.acquire_package_cache_lock()?;
+        let source_config = SourceConfigMap::new(config)?;
+        for (source_id, pkg_ids) in by_source {
+            let mut source = source_config.load(source_id, &HashSet::new())?;
+            if !config.offline() {
+                source.invalidate_cache();
+            }
+            for pkg_id in pkg_ids {
+                let dep = Dependency::parse(pkg_id.name(), None, source_id)?;
+                let summaries = loop {
+                    match source.query_vec(&dep, QueryKind::Exact)? {
+                        Poll::Ready(summaries) => break summaries,
+                        Poll::Pending => source.block_until_ready()?,
+                    }
+                };
+                let current = pkg_id.version();
+                let mut versions: Vec<&Version> = summaries
+                    .iter()
+                    .map(|s| s.version())
+                    .filter(|v| *v > current && (v.pre.is_empty() || !current.pre.is_empty()))
+                    .collect();
+                versions.sort();
+
+                let edges: Vec<(PackageId, &Dependency)> = resolve
+                    .iter()
+                    .flat_map(|parent| {
+                        resolve
+                            .deps(parent)
+                            .filter(move |(dep_id, _)| *dep_id == pkg_id)
+                            .flat_map(move |(_, deps)| deps.iter().map(move |dep| (parent, dep)))
+                    })
+                    .collect();
+                let latest = versions.last().map(|v| (*v).clone());
+                let compatible = versions
+                    .iter()
+                    .rev()
+                    .find(|v| {
+                        edges
+                            .iter()
+                            .all(|(_, dep)| dep.version_req().matches_unlocked(v))
+                    })
+                    .map(|v| (*v).clone());
+                let latest = match latest {
+                    Some(latest) => latest,
+                    None => continue,
+                };
+                let held_back_by = edges
+                    .iter()
+                    .filter(|(_, dep)| !dep.version_req().matches_unlocked(&latest))
+                    .map(|(parent, dep)| Requirement {
+                        req: dep.version_req().to_string(),
+                        required_by: *parent,
+                    })
+                    .collect();
+                // The latest version is only reported when it's incompatible.
+                let latest = if compatible.as_ref() == Some(&latest) {
+                    None
+                } else {
+                    Some(latest)
+                };
+                outdated.push(OutdatedPackage {
+                    id: pkg_id,
+                    latest,
+                    compatible,
+                    direct: edges.iter().any(|(parent, _)| members.contains(parent)),
+                    members: reaching_members
+                        .remove(&pkg_id)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect(),
+                    held_back_by,
+                });
+            }
+        }
+    }
+    outdated.sort_by_key(|pkg| (!pkg.direct, pkg.id));
+
+    match format {
+        ReportFormat::Json => {
+            config
+                .shell()
+                .print_json(&OutdatedJson { packages: outdated })?;
+        }
+        ReportFormat::Human => {
+            if outdated.is_empty() {
+                config.shell().note("all dependencies are up to date")?;
+            }
+            for pkg in &outdated {
+                let mut newer = Vec::new();
+                if let Some(compatible) = &pkg.compatible {
+                    newer.push(format!("{} is compatible", compatible));
+                }
+                if let Some(latest) = &pkg.latest {
+                    newer.push(format!("{} is the latest", latest));
+                }
+                let members: Vec<_> = pkg.members.iter().map(|m| m.name().as_str()).collect();
+                drop_println!(
+                    config,
+                    "{} v{} ({} dependency of {}): {}",
+                    pkg.id.name(),
+                    pkg.id.version(),
+                    if pkg.direct { "direct" } else { "transitive" },
+                    members.join(", "),
+                    newer.join(", ")
+                );
+                for req in &pkg.held_back_by {
+                    drop_println!(
+                        config,
+                        "  `{}` required by {} v{}",
+                        req.req,
+                        req.required_by.name(),
+                        req.required_by.version()
+                    );
+                }
+            }
+            if outdated.iter().any(|pkg| pkg.compatible.is_some()) {
+                config.shell().note(
+                    "run `cargo update` to update the dependencies to their compatible versions",
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the output saved by the last runs of the build scripts of the
+/// packages named by `specs`, or of all workspace members, oldest first.
+pub fn report_build_script_output(ws: &Workspace<'_>, specs: &[String]) -> CargoResult<()> {
+    let names = if specs.is_empty() {
+        ws.members().map(|pkg| pkg.name().to_string()).collect()
+    } else {
+        specs
+            .iter()
+            .map(|spec| Ok(PackageIdSpec::parse(spec)?.name().to_string()))
+            .collect::<CargoResult<BTreeSet<_>>>()?
+    };
+
+    let target_dir = ws.target_dir().into_path_unlocked();
+    let mut runs = Vec::new();
+    for build_dir in build_dirs(&target_dir) {
+        let entries = match fs::read_dir(&build_dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let output = dir.join("output");
+            let dir_name = entry.file_name().to_string_lossy().into_owned();
+            // Run directories are named `<package>-<metadata hash>`.
+            let name = match dir_name.rsplit_once('-') {
+                Some((name, hash)) if hash.len() == 16 && names.contains(name) => name,
+                _ => continue,
+            };
+            if let Ok(modified) = fs::metadata(&output).and_then(|m| m.modified()) {
+                runs.push((modified, name.to_string(), dir));
+            }
+        }
+    }
+    if runs.is_empty() {
+        anyhow::bail!(
+            "no build script output found for {}\n\
+             Build scripts save their output when they are run by a build.",
+            names
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    runs.sort();
+
+    for (modified, name, dir) in runs {
+        drop_println!(
+            ws.config(),
+            "{} ({}), run at {}",
+            name,
+            dir.display(),
+            humantime::format_rfc3339_seconds(modified)
+        );
+        print_output_section(ws, "stdout", &dir.join("output"));
+        print_output_section(ws, "stderr", &dir.join("stderr"));
+    }
+    Ok(())
+}
+
+/// Prints the sizes of the binaries recorded by the last build with
+/// `--timings=size`, of all packages or of the ones matching `specs`.
+pub fn report_size(ws: &Workspace<'_>, specs: &[String], format: ReportFormat) -> CargoResult<()> {
+    let dir = ws.target_dir().into_path_unlocked().join("cargo-timings");
+    let mut report = match SizeReport::load(&dir)? {
+        Some(report) => report,
+        None => anyhow::bail!(
+            "no size report found in `{}`\n\
+             Build with `--timings=size -Z unstable-options` to record the sizes of the binaries.",
+            dir.display()
+        ),
+    };
+    if !specs.is_empty() {
+        let specs = specs
+            .iter()
+            .map(|spec| PackageIdSpec::parse(spec))
+            .collect::<CargoResult<Vec<_>>>()?;
+        report.retain_packages(|id| specs.iter().any(|spec| spec.matches(id)));
+    }
+    match format {
+        ReportFormat::Human => drop_print!(ws.config(), "{}", report.render_human()),
+        ReportFormat::Json => {
+            drop_println!(ws.config(), "{}", serde_json::to_string_pretty(&report)?)
+        }
+    }
+    Ok(())
+}
+
+/// The `build` directories of all profiles and targets in `target_dir`.
+fn build_dirs(target_dir: &Path) -> Vec<PathBuf> {
+    let subdirs = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.is_dir())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut build_dirs = Vec::new();
+    // `<profile>/build`, and `<target triple>/<profile>/build`.
+    for dir in subdirs(target_dir) {
+        build_dirs.push(dir.join("build"));
+        build_dirs.extend(subdirs(&dir).into_iter().map(|d| d.join("build")));
+    }
+    build_dirs.retain(|d| d.is_dir());
+    build_dirs
+}
+
+fn print_output_section(ws: &Workspace<'_>, section: &str, path: &Path) {
+    let contents = fs::read(path).unwrap_or_default();
+    let contents = String::from_utf8_lossy(&contents);
+    if contents.trim().is_empty() {
+        return;
+    }
+    drop_println!(ws.config(), "--- {}", section);
+    drop_print!(ws.config(), "{}", contents);
+    if !contents.ends_with('\n') {
+        drop_println!(ws.config());
+    }
+}
@@ -2,7 +2,7 @@ use std::ffi::OsString;
 use std::iter;
 use std::path::Path;
 
-use crate::core::compiler::UnitOutput;
+use crate::core::compiler::{RunnerKind, UnitOutput};
 use crate::core::{TargetKind, Workspace};
 use crate::ops;
 use crate::util::CargoResult;
@@ -92,7 +92,12 @@ pub fn run(
         Err(_) => path.to_path_buf(),
     };
     let pkg = bins[0].0;
-    let mut process = compile.target_process(exe, unit.kind, pkg, *script_meta)?;
+    let runner_kind = if unit.target.is_example() {
+        RunnerKind::Example
+    } else {
+        RunnerKind::Bin
+    };
+    let mut process = compile.target_process(exe, unit.kind, runner_kind, pkg, *script_meta)?;
     process.args(args).cwd(config.cwd());
 
     config.shell().status("Running", process.to_string())?;
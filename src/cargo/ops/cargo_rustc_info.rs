@@ -0,0 +1,106 @@
+//! Implementation of `cargo rustc-info`.
+//!
+//! Prints what Cargo learned about `rustc` and the targets it builds for, the
+//! same [`RustcTargetData`] a build uses, so that external tools don't need
+//! to query rustc themselves and risk disagreeing with Cargo, for example
+//! because they don't apply `RUSTFLAGS` or the `[target]` config the same way.
+//! The output of rustc is cached in `.rustc_info.json` as for builds.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::core::compiler::{CompileKind, RustcTargetData, TargetInfo};
+use crate::core::Workspace;
+use crate::util::errors::CargoResult;
+use crate::util::interning::InternedString;
+use crate::util::Rustc;
+
+pub struct RustcInfoOptions {
+    /// The targets to describe, in addition to the host. Defaults to
+    /// `build.target`, or the host.
+    pub targets: Vec<String>,
+}
+
+/// The version of the JSON output, bumped on incompatible changes.
+const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct RustcInfo {
+    version: u32,
+    rustc: RustcDescription,
+    host: TargetDescription,
+    targets: Vec<TargetDescription>,
+}
+
+#[derive(Serialize)]
+struct RustcDescription {
+    path: PathBuf,
+    wrapper: Option<PathBuf>,
+    workspace_wrapper: Option<PathBuf>,
+    version: String,
+    verbose_version: String,
+    host: InternedString,
+}
+
+#[derive(Serialize)]
+struct TargetDescription {
+    triple: String,
+    cfg: serde_json::Value,
+    target_spec: serde_json::Value,
+    sysroot: PathBuf,
+    sysroot_host_libdir: PathBuf,
+    sysroot_target_libdir: PathBuf,
+    rustflags: Vec<String>,
+    rustdocflags: Vec<String>,
+}
+
+pub fn rustc_info(ws: &Workspace<'_>, opts: &RustcInfoOptions) -> CargoResult<()> {
+    // The cache of the output of rustc lives in the target directory, which
+    // may not exist yet if nothing was built.
+    ws.target_dir().create_dir()?;
+    let requested_kinds = CompileKind::from_requested_targets(ws.config(), &opts.targets)?;
+    let target_data = RustcTargetData::new(ws, &requested_kinds)?;
+    let rustc = &target_data.rustc;
+
+    let info = RustcInfo {
+        version: VERSION,
+        rustc: RustcDescription {
+            path: rustc.path.clone(),
+            wrapper: rustc.wrapper.clone(),
+            workspace_wrapper: rustc.workspace_wrapper.clone(),
+            version: rustc.version.to_string(),
+            verbose_version: rustc.verbose_version.clone(),
+            host: rustc.host,
+        },
+        host: describe(
+            rustc,
+            CompileKind::Host,
+            target_data.info(CompileKind::Host),
+        )?,
+        targets: requested_kinds
+            .iter()
+            .map(|&kind| describe(rustc, kind, target_data.info(kind)))
+            .collect::<CargoResult<_>>()?,
+    };
+    crate::drop_println!(ws.config(), "{}", serde_json::to_string_pretty(&info)?);
+    Ok(())
+}
+
+fn describe(rustc: &Rustc, kind: CompileKind, info: &TargetInfo) -> CargoResult<TargetDescription> {
+    let triple = match kind {
+        CompileKind::Host => rustc.host.to_string(),
+        CompileKind::Target(target) => target.rustc_target().to_string(),
+    };
+    let spec = info.target_spec_json(rustc, kind)?;
+    Ok(TargetDescription {
+        triple,
+        cfg: info.cfg_json()?,
+        target_spec: serde_json::from_str(&spec)?,
+        sysroot: info.sysroot.clone(),
+        sysroot_host_libdir: info.sysroot_host_libdir.clone(),
+        sysroot_target_libdir: info.sysroot_target_libdir.clone(),
+        rustflags: info.rustflags.clone(),
+        rustdocflags: info.rustdocflags.clone(),
+    })
+}
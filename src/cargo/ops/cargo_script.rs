@@ -0,0 +1,244 @@
+//! Implementation of `cargo script`, enabled with `-Z script`.
+//!
+//! A script is a single `.rs` file, optionally starting with a shebang line,
+//! which may embed its manifest in a `cargo` code block of its inner doc
+//! comments:
+//!
+//! ```text
+//! #!/usr/bin/env cargo script
+//! //! ```cargo
+//! //! [dependencies]
+//! //! regex = "1"
+//! //! ```
+//! fn main() {}
+//! ```
+//!
+//! Cargo generates a package with the script as its only binary in a
+//! directory of `$CARGO_HOME/script`, one per script, which keeps the
+//! lockfile and the target directory of the script between runs.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as _};
+use cargo_util::paths;
+use toml_edit::easy as toml;
+
+use crate::util::errors::CargoResult;
+use crate::util::{short_hash, Config};
+
+/// Tables of the embedded manifest which would conflict with the package
+/// generated for the script.
+const RESERVED_TABLES: &[&str] = &["workspace", "lib", "bin", "example", "test", "bench"];
+
+/// Generates the package of the script at `script`, and returns the path of
+/// its manifest.
+pub fn prepare_script(config: &Config, script: &Path) -> CargoResult<PathBuf> {
+    let script = config.cwd().join(script);
+    let script = script
+        .canonicalize()
+        .with_context(|| format!("failed to find the script `{}`", script.display()))?;
+    if script.is_dir() {
+        bail!(
+            "`{}` is a directory, expected a script file",
+            script.display()
+        );
+    }
+    let contents = paths::read(&script)?;
+    let name = package_name(&script);
+    let embedded = embedded_manifest(&contents)
+        .with_context(|| format!("failed to read the manifest of `{}`", script.display()))?;
+    let manifest = generate_manifest(&name, &script, &embedded)
+        .with_context(|| format!("failed to read the manifest of `{}`", script.display()))?;
+
+    let dir = config
+        .home()
+        .join("script")
+        .join(format!("{}-{}", name, short_hash(&script)))
+        .into_path_unlocked();
+    paths::create_dir_all(&dir)?;
+    let manifest_path = dir.join("Cargo.toml");
+    // Avoid touching the manifest when nothing changed.
+    paths::write_if_changed(&manifest_path, manifest)?;
+    Ok(manifest_path)
+}
+
+/// The name of the package of a script, after the name of its file.
+fn package_name(script: &Path) -> String {
+    let stem = script
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("script-{}", name)
+    }
+}
+
+/// Extracts the contents of the `cargo` code block in the leading inner doc
+/// comments of a script, if any.
+fn embedded_manifest(contents: &str) -> CargoResult<String> {
+    let mut lines = contents.lines().peekable();
+    if let Some(line) = lines.peek() {
+        if line.starts_with("#!") && !line.starts_with("#![") {
+            lines.next();
+        }
+    }
+    let doc_lines = lines.map_while(|line| {
+        let line = line.trim_start();
+        line.strip_prefix("//!")
+            .map(|doc| doc.strip_prefix(' ').unwrap_or(doc))
+    });
+
+    let mut manifest = None::<String>;
+    for line in doc_lines {
+        match &mut manifest {
+            None => {
+                if line.trim_end() == "```cargo" {
+                    manifest = Some(String::new());
+                }
+            }
+            Some(manifest) => {
+                if line.trim_end() == "```" {
+                    return Ok(manifest.clone());
+                }
+                manifest.push_str(line);
+                manifest.push('\n');
+            }
+        }
+    }
+    if manifest.is_some() {
+        bail!("the `cargo` code block of the doc comments is not closed");
+    }
+    Ok(String::new())
+}
+
+/// Generates the manifest of the package of a script from its embedded
+/// manifest.
+fn generate_manifest(name: &str, script: &Path, embedded: &str) -> CargoResult<String> {
+    let mut manifest: toml::value::Table = toml::from_str(embedded)?;
+    if let Some(table) = RESERVED_TABLES.iter().find(|t| manifest.contains_key(**t)) {
+        bail!("`{}` is not allowed in the manifest of a script", table);
+    }
+
+    let package = manifest
+        .entry("package")
+        .or_insert_with(|| toml::Value::Table(Default::default()));
+    let package = match package.as_table_mut() {
+        Some(package) => package,
+        None => bail!("`package` must be a table"),
+    };
+    let defaults = [
+        ("name", toml::Value::from(name)),
+        ("version", toml::Value::from("0.0.0")),
+        ("edition", toml::Value::from("2021")),
+        ("publish", toml::Value::from(false)),
+    ];
+    for (key, value) in defaults {
+        package.entry(key).or_insert(value);
+    }
+    for key in ["autobins", "autoexamples", "autotests", "autobenches"] {
+        package.insert(key.to_string(), toml::Value::from(false));
+    }
+    package.insert("build".to_string(), toml::Value::from(false));
+    let bin_name = package["name"].as_str().unwrap_or(name).to_string();
+
+    let script_dir = script.parent().unwrap();
+    absolutize_dependency_paths(&mut manifest, script_dir);
+    if let Some(targets) = manifest.get_mut("target").and_then(|t| t.as_table_mut()) {
+        for (_, platform) in targets.iter_mut() {
+            if let Some(platform) = platform.as_table_mut() {
+                absolutize_dependency_paths(platform, script_dir);
+            }
+        }
+    }
+
+    let mut bin = toml::value::Table::new();
+    bin.insert("name".to_string(), toml::Value::from(bin_name));
+    bin.insert(
+        "path".to_string(),
+        toml::Value::from(script.to_string_lossy().into_owned()),
+    );
+    manifest.insert(
+        "bin".to_string(),
+        toml::Value::Array(vec![toml::Value::Table(bin)]),
+    );
+    // The package is its own workspace, even if `$CARGO_HOME` is within
+    // another one.
+    manifest.insert(
+        "workspace".to_string(),
+        toml::Value::Table(Default::default()),
+    );
+    Ok(toml::to_string(&manifest)?)
+}
+
+/// Makes the relative `path` of dependencies relative to the directory of the
+/// script rather than the directory of its generated package.
+fn absolutize_dependency_paths(table: &mut toml::value::Table, script_dir: &Path) {
+    for kind in [
+        "dependencies",
+        "dev-dependencies",
+        "dev_dependencies",
+        "build-dependencies",
+        "build_dependencies",
+    ] {
+        let deps = match table.get_mut(kind).and_then(|deps| deps.as_table_mut()) {
+            Some(deps) => deps,
+            None => continue,
+        };
+        for (_, dep) in deps.iter_mut() {
+            let path = match dep.get_mut("path") {
+                Some(toml::Value::String(path)) => path,
+                _ => continue,
+            };
+            if Path::new(path).is_relative() {
+                *path = script_dir.join(&*path).to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_manifests() {
+        let script = "#!/usr/bin/env cargo script\n\
+                      //! A script.\n\
+                      //!\n\
+                      //! ```cargo\n\
+                      //! [dependencies]\n\
+                      //! bar = \"1\"\n\
+                      //! ```\n\
+                      fn main() {}\n";
+        assert_eq!(
+            embedded_manifest(script).unwrap(),
+            "[dependencies]\nbar = \"1\"\n"
+        );
+        assert_eq!(embedded_manifest("fn main() {}").unwrap(), "");
+        // Only the leading doc comments are searched.
+        assert_eq!(
+            embedded_manifest("fn main() {}\n//! ```cargo\n//! [package]\n//! ```\n").unwrap(),
+            ""
+        );
+        assert!(embedded_manifest("//! ```cargo\n//! [package]\n").is_err());
+    }
+
+    #[test]
+    fn package_names() {
+        assert_eq!(package_name(Path::new("/s/hello.rs")), "hello");
+        assert_eq!(package_name(Path::new("/s/my script.rs")), "my-script");
+        assert_eq!(package_name(Path::new("/s/2fast.rs")), "script-2fast");
+    }
+}
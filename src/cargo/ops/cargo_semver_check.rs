@@ -0,0 +1,351 @@
+//! Implementation of `cargo semver-check`, which is also run by
+//! `cargo publish` with `-Zsemver-check`.
+//!
+//! The rustdoc JSON of the library of a package is built twice: once for the
+//! current sources, and once for a baseline, which is either a version
+//! published in a registry or a git revision of the workspace. Both files are
+//! passed to the program configured as `semver-check.backend`, which decides
+//! whether the changes between them are breaking.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::task::Poll;
+
+use anyhow::{bail, Context as _};
+use cargo_util::{paths, ProcessBuilder};
+
+use crate::core::compiler::CompileMode;
+use crate::core::source::Source;
+use crate::core::{Dependency, Package, QueryKind, SourceId, Workspace};
+use crate::ops::{self, CompileFilter, CompileOptions, Packages};
+use crate::sources::SourceConfigMap;
+use crate::util::config::{Config, PathAndArgs};
+use crate::util::errors::CargoResult;
+
+/// The version or revision the current sources are compared to.
+pub enum SemverBaseline {
+    /// The newest version published in the registry which is older than
+    /// the current version.
+    Latest,
+    /// A version published in the registry.
+    Version(String),
+    /// A git revision of the workspace.
+    Rev(String),
+}
+
+pub struct SemverCheckOptions {
+    pub to_check: Packages,
+    pub baseline: SemverBaseline,
+    /// The registry of the published versions, crates.io if `None`.
+    pub registry: Option<String>,
+}
+
+/// The rustdoc JSON of the baseline.
+struct BaselineDoc {
+    /// Describes the baseline in messages, like `v0.1.0`.
+    description: String,
+    json: PathBuf,
+}
+
+/// Main method for `cargo semver-check`.
+pub fn semver_check(ws: &Workspace<'_>, opts: &SemverCheckOptions) -> CargoResult<()> {
+    let config = ws.config();
+    let specs = opts.to_check.to_package_id_specs(ws)?;
+    if specs.len() > 1 {
+        bail!("the `-p` argument must be specified to select a single package to check")
+    }
+    if Packages::Default == opts.to_check && ws.is_virtual() {
+        bail!("the `-p` argument must be specified in the root of a virtual workspace")
+    }
+    let pkg_id = specs[0].query(ws.members().map(|p| p.package_id()))?;
+    let pkg = ws.members().find(|p| p.package_id() == pkg_id).unwrap();
+    if !pkg.targets().iter().any(|t| t.is_lib()) {
+        bail!(
+            "`{}` does not have a library target, only libraries can be semver-checked",
+            pkg.name()
+        )
+    }
+    let backend = backend(config)?;
+
+    let current = build_doc_json(ws, pkg)?;
+    let baseline = match &opts.baseline {
+        SemverBaseline::Rev(rev) => rev_baseline(ws, pkg, rev)?,
+        SemverBaseline::Latest | SemverBaseline::Version(_) => {
+            let source_id = match &opts.registry {
+                Some(registry) => SourceId::alt_registry(config, registry)?,
+                None => SourceId::crates_io(config)?,
+            };
+            let mut source = SourceConfigMap::new(config)?.load(source_id, &HashSet::new())?;
+            let version = match &opts.baseline {
+                SemverBaseline::Version(version) => version.clone(),
+                _ => match latest_published(config, &mut source, source_id, pkg)? {
+                    Some(version) => version.to_string(),
+                    None => bail!(
+                        "no version of `{}` older than {} is published in {}",
+                        pkg.name(),
+                        pkg.version(),
+                        source_id.display_registry_name()
+                    ),
+                },
+            };
+            registry_baseline(ws, pkg, source, source_id, &version)?
+        }
+    };
+
+    config.shell().status(
+        "Comparing",
+        format!("{} against {}", pkg.package_id(), baseline.description),
+    )?;
+    if run_backend(config, &backend, &baseline.json, &current)? {
+        bail!(
+            "breaking changes detected in `{}` compared to {}",
+            pkg.name(),
+            baseline.description
+        )
+    }
+    Ok(())
+}
+
+/// Checks `pkg` for breaking changes compared to its newest version in the
+/// registry `registry_src` before it is published there.
+///
+/// Nothing is checked if `pkg` isn't a library, has never been published,
+/// or its version isn't semver-compatible with the published one anyway.
+pub(crate) fn check_before_publish(
+    ws: &Workspace<'_>,
+    pkg: &Package,
+    registry_src: SourceId,
+    allow_breaking: bool,
+) -> CargoResult<()> {
+    let config = ws.config();
+    if !pkg.targets().iter().any(|t| t.is_lib()) {
+        return Ok(());
+    }
+    let backend = backend(config)?;
+    let mut source = SourceConfigMap::empty(config)?.load(registry_src, &HashSet::new())?;
+    let version = match latest_published(config, &mut source, registry_src, pkg)? {
+        Some(version) => version,
+        None => return Ok(()),
+    };
+    if !is_semver_compatible(&version, pkg.version()) {
+        return Ok(());
+    }
+
+    let current = build_doc_json(ws, pkg)?;
+    let baseline = registry_baseline(ws, pkg, source, registry_src, &version.to_string())?;
+    config.shell().status(
+        "Comparing",
+        format!("{} against {}", pkg.package_id(), baseline.description),
+    )?;
+    if !run_backend(config, &backend, &baseline.json, &current)? {
+        return Ok(());
+    }
+    if allow_breaking {
+        config.shell().warn(format!(
+            "breaking changes detected in `{}` compared to {}, \
+             publishing anyway because of `--allow-breaking`",
+            pkg.name(),
+            baseline.description
+        ))?;
+        return Ok(());
+    }
+    bail!(
+        "breaking changes detected in `{}` compared to {}\n\n\
+         Bump the version to {} to publish them, \
+         or pass `--allow-breaking` to publish anyway.",
+        pkg.name(),
+        baseline.description,
+        next_breaking_version(&version)
+    )
+}
+
+/// Returns the configured semver-checking program.
+fn backend(config: &Config) -> CargoResult<PathAndArgs> {
+    match config.get::<Option<PathAndArgs>>("semver-check.backend")? {
+        Some(backend) => Ok(backend),
+        None => bail!(
+            "no semver checker is configured\n\n\
+             Set `semver-check.backend` in the Cargo configuration to the program \
+             comparing the rustdoc JSON of two versions of a library."
+        ),
+    }
+}
+
+/// Runs the semver checker, returning whether it reported breaking changes.
+///
+/// The checker is called with the rustdoc JSON of the baseline and of the
+/// current sources as its last two arguments. It exits with status 0 when
+/// the changes are compatible and with status 1 when they are breaking.
+fn run_backend(
+    config: &Config,
+    backend: &PathAndArgs,
+    baseline: &Path,
+    current: &Path,
+) -> CargoResult<bool> {
+    let mut cmd = ProcessBuilder::new(backend.path.resolve_program(config));
+    cmd.args(&backend.args).arg(baseline).arg(current);
+    let status = cmd.status()?;
+    match status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => bail!("semver checker `{}` failed ({})", cmd, status),
+    }
+}
+
+/// Builds the rustdoc JSON of the library of `pkg`, returning its path.
+fn build_doc_json(ws: &Workspace<'_>, pkg: &Package) -> CargoResult<PathBuf> {
+    let mut opts = CompileOptions::new(
+        ws.config(),
+        CompileMode::Doc {
+            deps: false,
+            json: true,
+        },
+    )?;
+    opts.spec = Packages::Packages(vec![pkg.name().to_string()]);
+    opts.filter = CompileFilter::lib_only();
+    let compilation = ops::compile(ws, &opts)?;
+
+    let kind = opts.build_config.single_requested_kind()?;
+    let lib = pkg.targets().iter().find(|t| t.is_lib()).unwrap();
+    Ok(compilation.root_output[&kind]
+        .with_file_name("doc-json")
+        .join(format!("{}.json", lib.crate_name())))
+}
+
+/// Returns the newest version of `pkg` published in `source` which is older
+/// than the current version, ignoring pre-releases and yanked versions.
+fn latest_published(
+    config: &Config,
+    source: &mut Box<dyn Source + '_>,
+    source_id: SourceId,
+    pkg: &Package,
+) -> CargoResult<Option<semver::Version>> {
+    let _lock = config.acquire_package_cache_lock()?;
+    let query = Dependency::parse(pkg.name(), None, source_id)?;
+    let summaries = loop {
+        match source.query_vec(&query, QueryKind::Exact)? {
+            Poll::Ready(summaries) => break summaries,
+            Poll::Pending => source.block_until_ready()?,
+        }
+    };
+    Ok(summaries
+        .iter()
+        .map(|s| s.version())
+        .filter(|v| v.pre.is_empty() && *v < pkg.version())
+        .max()
+        .cloned())
+}
+
+/// Downloads `version` of `pkg` from the registry and builds its rustdoc
+/// JSON in the `semver-check` directory of the target directory.
+fn registry_baseline(
+    ws: &Workspace<'_>,
+    pkg: &Package,
+    mut source: Box<dyn Source + '_>,
+    source_id: SourceId,
+    version: &str,
+) -> CargoResult<BaselineDoc> {
+    let config = ws.config();
+    let dep = Dependency::parse(pkg.name(), Some(&format!("={}", version)), source_id)?;
+    let baseline_pkg =
+        super::common_for_install_and_uninstall::select_dep_pkg(&mut source, dep, config, false)?;
+    let description = format!("v{}", baseline_pkg.version());
+    let target_dir = ws.target_dir().join("semver-check");
+    let baseline_ws = Workspace::ephemeral(baseline_pkg.clone(), config, Some(target_dir), false)?;
+    let json = build_doc_json(&baseline_ws, &baseline_pkg)?;
+    Ok(BaselineDoc { description, json })
+}
+
+/// Exports the git revision `rev` of the repository containing the workspace
+/// to the `semver-check` directory of the target directory, and builds the
+/// rustdoc JSON of `pkg` from there.
+fn rev_baseline(ws: &Workspace<'_>, pkg: &Package, rev: &str) -> CargoResult<BaselineDoc> {
+    let config = ws.config();
+    let repo = git2::Repository::discover(ws.root()).with_context(|| {
+        format!(
+            "`--baseline-rev` requires the workspace at `{}` to be in a git repository",
+            ws.root().display()
+        )
+    })?;
+    let workdir = match repo.workdir() {
+        Some(workdir) => paths::normalize_path(workdir),
+        None => bail!("`--baseline-rev` does not support bare git repositories"),
+    };
+    let commit = repo
+        .revparse_single(rev)
+        .and_then(|obj| obj.peel_to_commit())
+        .with_context(|| format!("failed to find git revision `{}`", rev))?;
+
+    let target_dir = ws.target_dir().join("semver-check");
+    let export = target_dir.join("baseline-src").into_path_unlocked();
+    if export.exists() {
+        paths::remove_dir_all(&export)?;
+    }
+    let mut blobs = Vec::new();
+    commit
+        .tree()?
+        .walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    blobs.push((format!("{}{}", dir, name), entry.id()));
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+    for (path, id) in blobs {
+        let path = export.join(path);
+        paths::create_dir_all(path.parent().unwrap())?;
+        paths::write(&path, repo.find_blob(id)?.content())?;
+    }
+
+    let manifest_path = export.join(super::cargo_changelog::relative_to(
+        &workdir,
+        pkg.manifest_path(),
+    )?);
+    if !manifest_path.exists() {
+        bail!(
+            "package `{}` does not exist at revision `{}`",
+            pkg.name(),
+            rev
+        )
+    }
+    let mut baseline_ws = Workspace::new(&manifest_path, config)?;
+    baseline_ws.set_target_dir(target_dir);
+    let baseline_pkg = match baseline_ws.members().find(|p| p.name() == pkg.name()) {
+        Some(baseline_pkg) => baseline_pkg.clone(),
+        None => bail!(
+            "package `{}` does not exist at revision `{}`",
+            pkg.name(),
+            rev
+        ),
+    };
+    let json = build_doc_json(&baseline_ws, &baseline_pkg)?;
+    Ok(BaselineDoc {
+        description: format!("revision `{}`", rev),
+        json,
+    })
+}
+
+/// Whether `new` may be published as a compatible update of `old`.
+fn is_semver_compatible(old: &semver::Version, new: &semver::Version) -> bool {
+    if old.major != new.major {
+        false
+    } else if old.major != 0 {
+        true
+    } else if old.minor != new.minor {
+        false
+    } else {
+        old.minor != 0 || old.patch == new.patch
+    }
+}
+
+/// The smallest version which is allowed to break compatibility with `old`.
+fn next_breaking_version(old: &semver::Version) -> semver::Version {
+    if old.major != 0 {
+        semver::Version::new(old.major + 1, 0, 0)
+    } else if old.minor != 0 {
+        semver::Version::new(0, old.minor + 1, 0)
+    } else {
+        semver::Version::new(0, 0, old.patch + 1)
+    }
+}
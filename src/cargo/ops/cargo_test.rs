@@ -1,4 +1,6 @@
-use crate::core::compiler::{Compilation, CompileKind, Doctest, Metadata, Unit, UnitOutput};
+use crate::core::compiler::{
+    Compilation, CompileKind, Doctest, Metadata, RunnerKind, Unit, UnitOutput,
+};
 use crate::core::shell::Verbosity;
 use crate::core::{TargetKind, Workspace};
 use crate::ops;
@@ -67,7 +69,7 @@ pub fn run_tests(ws: &Workspace<'_>, options: &TestOptions, test_args: &[&str])
 
     if options.no_run {
         if !options.compile_opts.build_config.emit_json() {
-            display_no_run_information(ws, test_args, &compilation, "unittests")?;
+            display_no_run_information(ws, test_args, &compilation, TestKind::Test, "unittests")?;
         }
         return Ok(());
     }
@@ -87,7 +89,7 @@ pub fn run_benches(ws: &Workspace<'_>, options: &TestOptions, args: &[&str]) ->
 
     if options.no_run {
         if !options.compile_opts.build_config.emit_json() {
-            display_no_run_information(ws, args, &compilation, "benches")?;
+            display_no_run_information(ws, args, &compilation, TestKind::Bench, "benches")?;
         }
         return Ok(());
     }
@@ -134,6 +136,7 @@ fn run_unit_tests(
             script_meta,
             test_args,
             compilation,
+            test_kind,
             "unittests",
         )?;
         config
@@ -233,7 +236,9 @@ fn run_doc_tests(
         if doctest_xcompile {
             p.arg("-Zunstable-options");
             p.arg("--enable-per-target-ignores");
-            if let Some((runtool, runtool_args)) = compilation.target_runner(unit.kind) {
+            if let Some((runtool, runtool_args)) =
+                compilation.target_runner(unit.kind, RunnerKind::Test)?
+            {
                 p.arg("--runtool").arg(runtool);
                 for arg in runtool_args {
                     p.arg("--runtool-arg").arg(arg);
@@ -299,6 +304,7 @@ fn display_no_run_information(
     ws: &Workspace<'_>,
     test_args: &[&str],
     compilation: &Compilation<'_>,
+    test_kind: TestKind,
     exec_type: &str,
 ) -> CargoResult<()> {
     let config = ws.config();
@@ -317,6 +323,7 @@ fn display_no_run_information(
             script_meta,
             test_args,
             compilation,
+            test_kind,
             exec_type,
         )?;
         config
@@ -343,6 +350,7 @@ fn cmd_builds(
     script_meta: &Option<Metadata>,
     test_args: &[&str],
     compilation: &Compilation<'_>,
+    test_kind: TestKind,
     exec_type: &str,
 ) -> CargoResult<(String, ProcessBuilder)> {
     let test_path = unit.target.src_path().path().unwrap();
@@ -365,7 +373,12 @@ fn cmd_builds(
         ),
     };
 
-    let mut cmd = compilation.target_process(path, unit.kind, &unit.pkg, *script_meta)?;
+    let runner_kind = match test_kind {
+        TestKind::Bench => RunnerKind::Bench,
+        TestKind::Test | TestKind::Doctest => RunnerKind::Test,
+    };
+    let mut cmd =
+        compilation.target_process(path, unit.kind, runner_kind, &unit.pkg, *script_meta)?;
     cmd.args(test_args);
     if unit.target.harness() && config.shell().verbosity() == Verbosity::Quiet {
         cmd.arg("--quiet");
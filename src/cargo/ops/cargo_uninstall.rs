@@ -1,14 +1,16 @@
 use crate::core::PackageId;
-use crate::core::{PackageIdSpec, SourceId};
+use crate::core::{PackageIdSpec, SourceId, Workspace};
 use crate::ops::common_for_install_and_uninstall::*;
 use crate::sources::PathSource;
 use crate::util::errors::CargoResult;
+use crate::util::important_paths::find_root_manifest_for_wd;
 use crate::util::Config;
 use crate::util::Filesystem;
 use anyhow::bail;
 use cargo_util::paths;
 use std::collections::BTreeSet;
 use std::env;
+use std::path::Path;
 
 pub fn uninstall(
     root: Option<&str>,
@@ -81,6 +83,36 @@ pub fn uninstall_one(
     uninstall_pkgid(root, tracker, pkgid, bins, config)
 }
 
+/// Uninstalls every package which was installed with `--path` from within
+/// the workspace containing `path`.
+pub fn uninstall_workspace(root: Option<&str>, path: &Path, config: &Config) -> CargoResult<()> {
+    let root = resolve_root(root, config)?;
+    let ws = Workspace::new(&find_root_manifest_for_wd(path)?, config)?;
+    let pkgids: Vec<PackageId> = InstallTracker::load(config, &root)?
+        .all_installed_bins()
+        .map(|(pkgid, _)| *pkgid)
+        .filter(|pkgid| {
+            pkgid.source_id().is_path()
+                && pkgid
+                    .source_id()
+                    .url()
+                    .to_file_path()
+                    .map_or(false, |path| path.starts_with(ws.root()))
+        })
+        .collect();
+    if pkgids.is_empty() {
+        bail!(
+            "no packages are installed from the workspace at `{}`",
+            ws.root().display()
+        );
+    }
+    for pkgid in pkgids {
+        let tracker = InstallTracker::load(config, &root)?;
+        uninstall_pkgid(&root, tracker, pkgid, &[], config)?;
+    }
+    Ok(())
+}
+
 fn uninstall_cwd(root: &Filesystem, bins: &[String], config: &Config) -> CargoResult<()> {
     let tracker = InstallTracker::load(config, root)?;
     let source_id = SourceId::for_path(config.cwd())?;
@@ -0,0 +1,153 @@
+//! Implementation of `cargo verify-mirror`.
+//!
+//! Every package of the lock file which comes from a replaced source (e.g., a
+//! mirror configured through `[source]`) is queried from both the replacement
+//! and the original source, and the checksums of the three (lock file,
+//! replacement and upstream) are compared with each other. This reports the
+//! same divergences `Resolve::merge_from` would stumble upon when the lock
+//! file is regenerated, but before anything is built from the mirror.
+
+use std::collections::{BTreeMap, HashSet};
+use std::task::Poll;
+
+use crate::core::resolver::checksum_mismatch;
+use crate::core::source::{QueryKind, Source};
+use crate::core::{Dependency, PackageId, SourceId, Workspace};
+use crate::ops;
+use crate::sources::SourceConfigMap;
+use crate::util::errors::CargoResult;
+
+pub fn verify_mirror(ws: &Workspace<'_>) -> CargoResult<()> {
+    let config = ws.config();
+    let resolve = match ops::load_pkg_lockfile(ws)? {
+        Some(resolve) => resolve,
+        None => anyhow::bail!(
+            "no Cargo.lock found for `{}`, generate it with `cargo generate-lockfile` first",
+            ws.root_manifest().display()
+        ),
+    };
+
+    let mut by_source: BTreeMap<SourceId, Vec<PackageId>> = BTreeMap::new();
+    for id in resolve.iter() {
+        by_source.entry(id.source_id()).or_default().push(id);
+    }
+
+    let _lock = config.acquire_package_cache_lock()?;
+    let map = SourceConfigMap::new(config)?;
+    // Only the built-in replacements (such as the sparse protocol for
+    // crates.io) are taken into account to find upstream.
+    let upstream_map = SourceConfigMap::empty(config)?;
+    let mut verified = 0;
+    let mut errors = Vec::new();
+    for (source_id, ids) in by_source {
+        // Locked versions may have been yanked since, they still need to be
+        // found in both sources.
+        let yanked_whitelist: HashSet<PackageId> = ids.iter().copied().collect();
+        let mut mirror = map.load(source_id, &yanked_whitelist)?;
+        let mut upstream = upstream_map.load(source_id, &yanked_whitelist)?;
+        if mirror.replaced_source_id() == upstream.replaced_source_id() {
+            continue;
+        }
+        mirror.invalidate_cache();
+        upstream.invalidate_cache();
+
+        config.shell().status(
+            "Verifying",
+            format!(
+                "{} against `{}`",
+                mirror.describe(),
+                source_id.display_registry_name()
+            ),
+        )?;
+        for id in ids {
+            let replacement = match query_checksum(&mut mirror, id)? {
+                Some(cksum) => cksum,
+                None => {
+                    errors.push(anyhow::format_err!(
+                        "`{}` is listed in the lock file, but could not be found in the \
+                         replacement source {}",
+                        id,
+                        mirror.replaced_source_id()
+                    ));
+                    continue;
+                }
+            };
+            if let Some(locked) = resolve.checksums().get(&id) {
+                if *locked != replacement {
+                    errors.push(checksum_mismatch(id, locked, &replacement));
+                    continue;
+                }
+            }
+            match query_checksum(&mut upstream, id)? {
+                Some(original) if original == replacement => verified += 1,
+                Some(_) => errors.push(anyhow::format_err!(
+                    "\
+checksum for `{}` differs between `{}` and its replacement {}
+
+this could be indicative of a few possible errors:
+
+    * the replacement source is not an exact mirror, some changes were made
+    * either source may be corrupt in one way or another
+",
+                    id,
+                    source_id.display_registry_name(),
+                    mirror.replaced_source_id()
+                )),
+                None => errors.push(anyhow::format_err!(
+                    "`{}` is provided by the replacement source {}, but could not be \
+                     found in `{}`",
+                    id,
+                    mirror.replaced_source_id(),
+                    source_id.display_registry_name()
+                )),
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        if verified == 0 {
+            config
+                .shell()
+                .note("no package of the lock file comes from a replaced source")?;
+        } else {
+            config.shell().status(
+                "Verified",
+                format!(
+                    "{} package{}",
+                    verified,
+                    if verified == 1 { "" } else { "s" }
+                ),
+            )?;
+        }
+        return Ok(());
+    }
+    let count = errors.len();
+    for error in errors {
+        crate::display_error(&error, &mut config.shell());
+    }
+    anyhow::bail!(
+        "{} package{} diverged from upstream",
+        count,
+        if count == 1 { "" } else { "s" }
+    )
+}
+
+/// Returns the checksum `source` reports for exactly `id`, or `None` when
+/// the source doesn't have that package.
+fn query_checksum(
+    source: &mut Box<dyn Source + '_>,
+    id: PackageId,
+) -> CargoResult<Option<Option<String>>> {
+    let mut dep = Dependency::new_override(id.name(), id.source_id());
+    dep.lock_to(id);
+    let summaries = loop {
+        match source.query_vec(&dep, QueryKind::Exact)? {
+            Poll::Ready(summaries) => break summaries,
+            Poll::Pending => source.block_until_ready()?,
+        }
+    };
+    Ok(summaries
+        .into_iter()
+        .find(|s| s.package_id() == id)
+        .map(|s| s.checksum().map(str::to_string)))
+}
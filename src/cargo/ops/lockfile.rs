@@ -1,6 +1,7 @@
 use std::io::prelude::*;
 
 use crate::core::{resolver, Resolve, ResolveVersion, Workspace};
+use crate::ops::resolve::PatchLockChanges;
 use crate::util::errors::CargoResult;
 use crate::util::toml as cargo_toml;
 use crate::util::Filesystem;
@@ -9,12 +10,12 @@ use anyhow::Context as _;
 use toml_edit::easy as toml;
 
 pub fn load_pkg_lockfile(ws: &Workspace<'_>) -> CargoResult<Option<Resolve>> {
-    if !ws.root().join("Cargo.lock").exists() {
+    if !ws.lock_file_path().exists() {
         return Ok(None);
     }
 
     let root = Filesystem::new(ws.root().to_path_buf());
-    let mut f = root.open_ro("Cargo.lock", ws.config(), "Cargo.lock file")?;
+    let mut f = root.open_ro(ws.lock_file_name(), ws.config(), "Cargo.lock file")?;
 
     let mut s = String::new();
     f.read_to_string(&mut s)
@@ -36,6 +37,17 @@ pub fn resolve_to_string(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoResu
 }
 
 pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoResult<()> {
+    write_pkg_lockfile_patched(ws, resolve, &PatchLockChanges::default())
+}
+
+/// Like [`write_pkg_lockfile`], but `patch_changes` are the entries of the
+/// lock file which are replaced by a `[patch]`. These may be updated even
+/// with `--locked` if `--allow-patch-update` was passed.
+pub(crate) fn write_pkg_lockfile_patched(
+    ws: &Workspace<'_>,
+    resolve: &mut Resolve,
+    patch_changes: &PatchLockChanges,
+) -> CargoResult<()> {
     let (orig, mut out, ws_root) = resolve_to_string_orig(ws, resolve);
 
     // If the lock file contents haven't changed so don't rewrite it. This is
@@ -46,20 +58,34 @@ pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoRes
         }
     }
 
-    if !ws.config().lock_update_allowed() {
+    let only_patches = !patch_changes.changes.is_empty() && !patch_changes.has_other_changes;
+    if !ws.config().lock_update_allowed() && !(only_patches && ws.config().allow_patch_update()) {
         let flag = if ws.config().locked() {
             "--locked"
         } else {
             "--frozen"
         };
-        anyhow::bail!(
+        let mut msg = format!(
             "the lock file {} needs to be updated but {} was passed to prevent this\n\
              If you want to try to generate the lock file without accessing the network, \
              remove the {} flag and use --offline instead.",
-            ws.root().to_path_buf().join("Cargo.lock").display(),
+            ws.lock_file_path().display(),
             flag,
             flag
         );
+        if !patch_changes.changes.is_empty() {
+            msg.push_str("\n\nThe following lock file entries are replaced by a `[patch]`:");
+            for change in &patch_changes.changes {
+                msg.push_str(&format!("\n  {}", change));
+            }
+            if only_patches {
+                msg.push_str(&format!(
+                    "\nTo update only these entries, pass `--allow-patch-update` along with {}.",
+                    flag
+                ));
+            }
+        }
+        anyhow::bail!(msg);
     }
 
     // While we're updating the lock file anyway go ahead and update its
@@ -73,14 +99,45 @@ pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoRes
     }
 
     // Ok, if that didn't work just write it out
+    write_lockfile_contents(ws, &ws_root, &out)
+}
+
+/// Like [`write_pkg_lockfile`], but keeps the [`Resolve::version`] of
+/// `resolve` instead of upgrading older formats to the default one.
+///
+/// This is used to explicitly migrate the format of a lock file.
+pub(crate) fn write_pkg_lockfile_exact(
+    ws: &Workspace<'_>,
+    resolve: &mut Resolve,
+) -> CargoResult<()> {
+    let (orig, out, ws_root) = resolve_to_string_orig(ws, resolve);
+    if orig.as_deref() == Some(out.as_str()) {
+        return Ok(());
+    }
+    if !ws.config().lock_update_allowed() {
+        let flag = if ws.config().locked() {
+            "--locked"
+        } else {
+            "--frozen"
+        };
+        anyhow::bail!(
+            "the lock file {} needs to be updated but {} was passed to prevent this",
+            ws.lock_file_path().display(),
+            flag
+        );
+    }
+    write_lockfile_contents(ws, &ws_root, &out)
+}
+
+fn write_lockfile_contents(ws: &Workspace<'_>, ws_root: &Filesystem, out: &str) -> CargoResult<()> {
     ws_root
-        .open_rw("Cargo.lock", ws.config(), "Cargo.lock file")
+        .open_rw(ws.lock_file_name(), ws.config(), "Cargo.lock file")
         .and_then(|mut f| {
             f.file().set_len(0)?;
             f.write_all(out.as_bytes())?;
             Ok(())
         })
-        .with_context(|| format!("failed to write {}", ws.root().join("Cargo.lock").display()))?;
+        .with_context(|| format!("failed to write {}", ws.lock_file_path().display()))?;
     Ok(())
 }
 
@@ -90,7 +147,7 @@ fn resolve_to_string_orig(
 ) -> (Option<String>, String, Filesystem) {
     // Load the original lock file if it exists.
     let ws_root = Filesystem::new(ws.root().to_path_buf());
-    let orig = ws_root.open_ro("Cargo.lock", ws.config(), "Cargo.lock file");
+    let orig = ws_root.open_ro(ws.lock_file_name(), ws.config(), "Cargo.lock file");
     let orig = orig.and_then(|mut f| {
         let mut s = String::new();
         f.read_to_string(&mut s)?;
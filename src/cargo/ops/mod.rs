@@ -1,6 +1,7 @@
 use crate::sources::CRATES_IO_DOMAIN;
 
-pub use self::cargo_clean::{clean, CleanOptions};
+pub use self::cargo_changelog::{changelog, ChangelogFormat, ChangelogOptions};
+pub use self::cargo_clean::{clean, clean_global_target_dirs, CleanOptions};
 pub use self::cargo_compile::{
     compile, compile_with_exec, compile_ws, create_bcx, print, resolve_all_features, CompileOptions,
 };
@@ -10,29 +11,44 @@ pub use self::cargo_fetch::{fetch, FetchOptions};
 pub use self::cargo_generate_lockfile::generate_lockfile;
 pub use self::cargo_generate_lockfile::update_lockfile;
 pub use self::cargo_generate_lockfile::UpdateOptions;
+pub use self::cargo_generate_lockfile::{dedupe_lockfile, LockDedupeOptions};
+pub use self::cargo_generate_lockfile::{format_lockfile, LockFmtOptions};
+pub use self::cargo_generate_lockfile::{upgrade_lockfile_format, UpgradeFormatOptions};
 pub use self::cargo_install::{install, install_list};
-pub use self::cargo_new::{init, new, NewOptions, VersionControl};
+pub use self::cargo_new::{init, init_workspace, new, NewOptions, VersionControl};
 pub use self::cargo_output_metadata::{output_metadata, ExportInfo, OutputMetadataOptions};
-pub use self::cargo_package::{check_yanked, package, package_one, PackageOpts};
+pub use self::cargo_package::{
+    check_yanked, package, package_one, ListFormat as PackageListFormat, PackageOpts,
+};
 pub use self::cargo_pkgid::pkgid;
 pub use self::cargo_read_manifest::{read_package, read_packages};
 pub use self::cargo_run::run;
+pub use self::cargo_rustc_info::{rustc_info, RustcInfoOptions};
+pub use self::cargo_script::prepare_script;
+pub use self::cargo_semver_check::{semver_check, SemverBaseline, SemverCheckOptions};
 pub use self::cargo_test::{run_benches, run_tests, TestOptions};
-pub use self::cargo_uninstall::uninstall;
+pub use self::cargo_uninstall::{uninstall, uninstall_workspace};
+pub use self::cargo_verify_mirror::verify_mirror;
 pub use self::fix::{fix, fix_exec_rustc, fix_get_proxy_lock_addr, FixOptions};
 pub use self::lockfile::{load_pkg_lockfile, resolve_to_string, write_pkg_lockfile};
 pub use self::registry::HttpTimeout;
 pub use self::registry::{configure_http_handle, http_handle, http_handle_and_timeout};
-pub use self::registry::{modify_owners, yank, OwnersOptions, PublishOpts};
-pub use self::registry::{needs_custom_http_transport, registry_login, registry_logout, search};
+pub use self::registry::{info, InfoFormat, InfoOptions};
+pub use self::registry::{
+    modify_owners, yank, OwnersListFormat, OwnersOptions, PublishOpts, YankOptions,
+};
+pub use self::registry::{
+    needs_custom_http_transport, registry_login, registry_logout, search, SearchFormat,
+};
 pub use self::registry::{publish, RegistryCredentialConfig};
 pub use self::resolve::{
     add_overrides, get_resolved_packages, resolve_with_previous, resolve_ws, resolve_ws_with_opts,
     WorkspaceResolve,
 };
-pub use self::vendor::{vendor, VendorOptions};
+pub use self::vendor::{vendor, verify as verify_vendor, VendorOptions};
 
 pub mod cargo_add;
+mod cargo_changelog;
 mod cargo_clean;
 mod cargo_compile;
 pub mod cargo_config;
@@ -46,9 +62,14 @@ mod cargo_package;
 mod cargo_pkgid;
 mod cargo_read_manifest;
 pub mod cargo_remove;
+pub mod cargo_report;
 mod cargo_run;
+mod cargo_rustc_info;
+mod cargo_script;
+mod cargo_semver_check;
 mod cargo_test;
 mod cargo_uninstall;
+mod cargo_verify_mirror;
 mod common_for_install_and_uninstall;
 mod fix;
 mod lockfile;
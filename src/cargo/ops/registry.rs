@@ -14,9 +14,11 @@ use crates_io::{self, NewCrate, NewCrateDependency, Registry};
 use curl::easy::{Easy, InfoType, SslOpt, SslVersion};
 use log::{log, Level};
 use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
+use serde::Serialize;
 use termcolor::Color::Green;
 use termcolor::ColorSpec;
 
+use crate::core::compiler::artifact::match_artifacts_kind_with_targets;
 use crate::core::dependency::DepKind;
 use crate::core::dependency::Dependency;
 use crate::core::manifest::ManifestMetadata;
@@ -31,7 +33,9 @@ use crate::util::auth::{self, AuthorizationError};
 use crate::util::config::{Config, SslVersionConfig, SslVersionConfigRange};
 use crate::util::errors::CargoResult;
 use crate::util::important_paths::find_root_manifest_for_wd;
-use crate::util::{truncate_with_ellipsis, IntoUrl};
+use crate::util::interning::InternedString;
+use crate::util::network::Retry;
+use crate::util::{truncate_with_ellipsis, IntoUrl, OptVersionReq};
 use crate::{drop_print, drop_println, version};
 
 /// Registry settings loaded from config files.
@@ -81,6 +85,7 @@ pub struct PublishOpts<'cfg> {
     pub index: Option<String>,
     pub verify: bool,
     pub allow_dirty: bool,
+    pub allow_dirty_paths: Vec<String>,
     pub jobs: Option<i32>,
     pub keep_going: bool,
     pub to_publish: ops::Packages,
@@ -88,6 +93,8 @@ pub struct PublishOpts<'cfg> {
     pub dry_run: bool,
     pub registry: Option<String>,
     pub cli_features: CliFeatures,
+    /// Publish even if the semver check detects breaking changes.
+    pub allow_breaking: bool,
 }
 
 pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
@@ -158,6 +165,15 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
         !opts.dry_run,
     )?;
     verify_dependencies(pkg, &registry, reg_ids.original)?;
+    verify_artifact_dependencies(ws, pkg)?;
+    if opts.config.cli_unstable().semver_check {
+        super::cargo_semver_check::check_before_publish(
+            ws,
+            pkg,
+            reg_ids.replacement,
+            opts.allow_breaking,
+        )?;
+    }
 
     // Prepare a tarball, with a non-suppressible warning if metadata
     // is missing since this is being put online.
@@ -168,8 +184,11 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
             config: opts.config,
             verify: opts.verify,
             list: false,
+            list_format: ops::PackageListFormat::Human,
+            diff: None,
             check_metadata: true,
             allow_dirty: opts.allow_dirty,
+            allow_dirty_paths: opts.allow_dirty_paths.clone(),
             to_package: ops::Packages::Default,
             targets: opts.targets.clone(),
             jobs: opts.jobs,
@@ -242,6 +261,45 @@ fn verify_dependencies(
     Ok(())
 }
 
+/// Checks that the artifacts requested by the artifact dependencies of `pkg`
+/// are provided by the packages they resolve to, which are the published
+/// versions for registry dependencies. Otherwise every user of the published
+/// package would only find out when building it.
+///
+/// Dependencies which can't be downloaded aren't checked.
+fn verify_artifact_dependencies(ws: &Workspace<'_>, pkg: &Package) -> CargoResult<()> {
+    if pkg
+        .dependencies()
+        .iter()
+        .all(|dep| dep.artifact().is_none())
+    {
+        return Ok(());
+    }
+    let (pkg_set, resolve) = ops::resolve_ws(ws)?;
+    for (dep_id, deps) in resolve.deps(pkg.package_id()) {
+        for dep in deps.iter().filter(|dep| dep.artifact().is_some()) {
+            let dep_pkg = match pkg_set.get_one(dep_id) {
+                Ok(dep_pkg) => dep_pkg,
+                Err(e) => {
+                    log::debug!("skipping artifact check of {}: {:?}", dep_id, e);
+                    continue;
+                }
+            };
+            match_artifacts_kind_with_targets(dep, dep_pkg.targets(), pkg.name().as_str())
+                .with_context(|| {
+                    format!(
+                        "failed to verify the artifact dependency `{}` against {}\n\n\
+                         fix its `artifact` key, or depend on a version of `{}` which provides the artifact",
+                        dep.name_in_toml(),
+                        dep_id,
+                        dep_id.name()
+                    )
+                })?;
+        }
+    }
+    Ok(())
+}
+
 fn transmit(
     config: &Config,
     pkg: &Package,
@@ -337,30 +395,29 @@ fn transmit(
         None => BTreeMap::new(),
     };
 
-    let warnings = registry
-        .publish(
-            &NewCrate {
-                name: pkg.name().to_string(),
-                vers: pkg.version().to_string(),
-                deps,
-                features: string_features,
-                authors: authors.clone(),
-                description: description.clone(),
-                homepage: homepage.clone(),
-                documentation: documentation.clone(),
-                keywords: keywords.clone(),
-                categories: categories.clone(),
-                readme: readme_content,
-                readme_file: readme.clone(),
-                repository: repository.clone(),
-                license: license.clone(),
-                license_file: license_file.clone(),
-                badges: badges.clone(),
-                links: links.clone(),
-            },
-            tarball,
-        )
-        .with_context(|| format!("failed to publish to registry at {}", registry.host()))?;
+    let host = registry.host().to_string();
+    let new_crate = NewCrate {
+        name: pkg.name().to_string(),
+        vers: pkg.version().to_string(),
+        deps,
+        features: string_features,
+        authors: authors.clone(),
+        description: description.clone(),
+        homepage: homepage.clone(),
+        documentation: documentation.clone(),
+        keywords: keywords.clone(),
+        categories: categories.clone(),
+        readme: readme_content,
+        readme_file: readme.clone(),
+        repository: repository.clone(),
+        license: license.clone(),
+        license_file: license_file.clone(),
+        badges: badges.clone(),
+        links: links.clone(),
+        provenance: super::cargo_package::provenance(pkg, config)?,
+    };
+    let warnings = call_api(config, false, || registry.publish(&new_crate, tarball))
+        .with_context(|| format!("failed to publish to registry at {}", host))?;
 
     if !warnings.invalid_categories.is_empty() {
         let msg = format!(
@@ -567,6 +624,11 @@ pub fn configure_http_handle(config: &Config, handle: &mut Easy) -> CargoResult<
     if let Some(check) = http.check_revoke {
         handle.ssl_options(SslOpt::new().no_revoke(!check))?;
     }
+    if config.cli_unstable().resumable_downloads {
+        if let Some(rate) = http.max_download_rate {
+            handle.max_recv_speed(rate)?;
+        }
+    }
 
     if let Some(user_agent) = &http.user_agent {
         handle.useragent(user_agent)?;
@@ -823,24 +885,46 @@ pub fn registry_logout(config: &Config, reg: Option<&str>) -> CargoResult<()> {
 }
 
 pub struct OwnersOptions {
-    pub krate: Option<String>,
+    /// The crates to manage the owners of. When empty, the current package
+    /// is used, or all publishable workspace members with `workspace`.
+    pub krates: Vec<String>,
+    pub workspace: bool,
     pub token: Option<String>,
     pub index: Option<String>,
     pub to_add: Option<Vec<String>>,
     pub to_remove: Option<Vec<String>>,
     pub list: bool,
+    pub list_format: OwnersListFormat,
     pub registry: Option<String>,
 }
 
+/// The output format of `cargo owner --list`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OwnersListFormat {
+    /// One owner per line.
+    Human,
+    /// One JSON object per crate.
+    Json,
+}
+
+/// The owners of a crate, as printed by `cargo owner --list` in JSON.
+#[derive(Serialize)]
+struct CrateOwners<'a> {
+    name: &'a str,
+    registry: &'a str,
+    owners: Vec<Owner<'a>>,
+}
+
+#[derive(Serialize)]
+struct Owner<'a> {
+    id: u32,
+    login: &'a str,
+    name: Option<&'a str>,
+    email: Option<&'a str>,
+}
+
 pub fn modify_owners(config: &Config, opts: &OwnersOptions) -> CargoResult<()> {
-    let name = match opts.krate {
-        Some(ref name) => name.clone(),
-        None => {
-            let manifest_path = find_root_manifest_for_wd(config.cwd())?;
-            let ws = Workspace::new(&manifest_path, config)?;
-            ws.current()?.package_id().name().to_string()
-        }
-    };
+    let names = owners_crate_names(config, opts)?;
 
     let (mut registry, _) = registry(
         config,
@@ -850,105 +934,574 @@ pub fn modify_owners(config: &Config, opts: &OwnersOptions) -> CargoResult<()> {
         true,
         true,
     )?;
+    let host = registry.host().to_string();
+
+    for name in &names {
+        if let Some(ref v) = opts.to_add {
+            let v = v.iter().map(|s| &s[..]).collect::<Vec<_>>();
+            let msg = call_api(config, true, || registry.add_owners(name, &v))
+                .map_err(|e| api_unsupported_error(e, &host, "managing owners"))
+                .with_context(|| {
+                    format!(
+                        "failed to invite owners to crate `{}` on registry at {}",
+                        name, host
+                    )
+                })?;
+
+            config.shell().status("Owner", msg)?;
+        }
 
-    if let Some(ref v) = opts.to_add {
-        let v = v.iter().map(|s| &s[..]).collect::<Vec<_>>();
-        let msg = registry.add_owners(&name, &v).with_context(|| {
-            format!(
-                "failed to invite owners to crate `{}` on registry at {}",
-                name,
-                registry.host()
-            )
-        })?;
+        if let Some(ref v) = opts.to_remove {
+            let v = v.iter().map(|s| &s[..]).collect::<Vec<_>>();
+            config
+                .shell()
+                .status("Owner", format!("removing {:?} from crate {}", v, name))?;
+            call_api(config, true, || registry.remove_owners(name, &v))
+                .map_err(|e| api_unsupported_error(e, &host, "managing owners"))
+                .with_context(|| {
+                    format!(
+                        "failed to remove owners from crate `{}` on registry at {}",
+                        name, host
+                    )
+                })?;
+        }
 
-        config.shell().status("Owner", msg)?;
+        if opts.list {
+            let owners = call_api(config, true, || registry.list_owners(name))
+                .map_err(|e| api_unsupported_error(e, &host, "managing owners"))
+                .with_context(|| {
+                    format!(
+                        "failed to list owners of crate `{}` on registry at {}",
+                        name, host
+                    )
+                })?;
+            match opts.list_format {
+                OwnersListFormat::Human => {
+                    if names.len() > 1 {
+                        drop_println!(config, "{}:", name);
+                    }
+                    let indent = if names.len() > 1 { "  " } else { "" };
+                    for owner in owners.iter() {
+                        drop_print!(config, "{}{}", indent, owner.login);
+                        match (owner.name.as_ref(), owner.email.as_ref()) {
+                            (Some(name), Some(email)) => {
+                                drop_println!(config, " ({} <{}>)", name, email)
+                            }
+                            (Some(s), None) | (None, Some(s)) => drop_println!(config, " ({})", s),
+                            (None, None) => drop_println!(config),
+                        }
+                    }
+                }
+                OwnersListFormat::Json => {
+                    let owners = owners
+                        .iter()
+                        .map(|owner| Owner {
+                            id: owner.id,
+                            login: &owner.login,
+                            name: owner.name.as_deref(),
+                            email: owner.email.as_deref(),
+                        })
+                        .collect();
+                    config.shell().print_json(&CrateOwners {
+                        name,
+                        registry: &host,
+                        owners,
+                    })?;
+                }
+            }
+        }
     }
 
-    if let Some(ref v) = opts.to_remove {
-        let v = v.iter().map(|s| &s[..]).collect::<Vec<_>>();
-        config
-            .shell()
-            .status("Owner", format!("removing {:?} from crate {}", v, name))?;
-        registry.remove_owners(&name, &v).with_context(|| {
-            format!(
-                "failed to remove owners from crate `{}` on registry at {}",
-                name,
-                registry.host()
-            )
-        })?;
+    Ok(())
+}
+
+/// Returns the names of the crates `cargo owner` works on.
+fn owners_crate_names(config: &Config, opts: &OwnersOptions) -> CargoResult<Vec<String>> {
+    if !opts.krates.is_empty() {
+        let mut seen = HashSet::new();
+        return Ok(opts
+            .krates
+            .iter()
+            .filter(|name| seen.insert(name.as_str()))
+            .cloned()
+            .collect());
+    }
+    let manifest_path = find_root_manifest_for_wd(config.cwd())?;
+    let ws = Workspace::new(&manifest_path, config)?;
+    if !opts.workspace {
+        return Ok(vec![ws.current()?.package_id().name().to_string()]);
+    }
+    let names: Vec<_> = ws
+        .members()
+        .filter(|pkg| !matches!(pkg.publish(), Some(registries) if registries.is_empty()))
+        .map(|pkg| pkg.name().to_string())
+        .collect();
+    if names.is_empty() {
+        bail!(
+            "no publishable packages in the workspace at `{}`",
+            ws.root().display()
+        );
     }
+    Ok(names)
+}
 
-    if opts.list {
-        let owners = registry.list_owners(&name).with_context(|| {
-            format!(
-                "failed to list owners of crate `{}` on registry at {}",
-                name,
-                registry.host()
-            )
-        })?;
-        for owner in owners.iter() {
-            drop_print!(config, "{}", owner.login);
-            match (owner.name.as_ref(), owner.email.as_ref()) {
-                (Some(name), Some(email)) => drop_println!(config, " ({} <{}>)", name, email),
-                (Some(s), None) | (None, Some(s)) => drop_println!(config, " ({})", s),
-                (None, None) => drop_println!(config),
+pub struct InfoOptions {
+    pub krate: String,
+    /// The version to show, either an exact version or a version requirement
+    /// matched against the published versions. The latest version is shown
+    /// when `None`.
+    pub version: Option<String>,
+    pub index: Option<String>,
+    pub registry: Option<String>,
+    pub format: InfoFormat,
+}
+
+/// The output format of `cargo info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InfoFormat {
+    Human,
+    Json,
+}
+
+/// The information about a crate, as printed by `cargo info` in JSON.
+#[derive(Serialize)]
+struct CrateInfo<'a> {
+    name: &'a str,
+    version: String,
+    yanked: bool,
+    rust_version: Option<&'a str>,
+    description: Option<&'a str>,
+    homepage: Option<&'a str>,
+    documentation: Option<&'a str>,
+    repository: Option<&'a str>,
+    downloads: Option<u64>,
+    features: BTreeMap<&'a str, Vec<String>>,
+    dependencies: Vec<CrateInfoDependency<'a>>,
+    versions: Vec<CrateInfoVersion<'a>>,
+}
+
+#[derive(Serialize)]
+struct CrateInfoDependency<'a> {
+    name: &'a str,
+    /// The name of the package, if the dependency is renamed.
+    package: Option<&'a str>,
+    req: String,
+    kind: DepKind,
+    optional: bool,
+    target: Option<String>,
+    features: Vec<&'a str>,
+    default_features: bool,
+}
+
+#[derive(Serialize)]
+struct CrateInfoVersion<'a> {
+    version: String,
+    yanked: bool,
+    rust_version: Option<&'a str>,
+}
+
+/// Shows the information about a crate from the index of its registry, along
+/// with the details the web API of the registry provides, if it has one.
+pub fn info(config: &Config, opts: &InfoOptions) -> CargoResult<()> {
+    let source_ids = get_source_id(config, opts.index.as_deref(), opts.registry.as_deref())?;
+    let name = InternedString::new(&opts.krate);
+    let mut summaries = {
+        let _lock = config.acquire_package_cache_lock()?;
+        let mut src = RegistrySource::remote(source_ids.replacement, &HashSet::new(), config)?;
+        loop {
+            match src.summaries(name, &OptVersionReq::Any)? {
+                Poll::Ready(summaries) => break summaries,
+                Poll::Pending => src.block_until_ready()?,
             }
         }
+    };
+    if summaries.is_empty() {
+        bail!("could not find `{}` in {}", opts.krate, source_ids.original);
     }
+    summaries.sort_by(|a, b| b.summary.version().cmp(a.summary.version()));
+
+    let selected = match &opts.version {
+        Some(spec) => {
+            let req = match spec.parse::<semver::Version>() {
+                Ok(version) => OptVersionReq::exact(&version),
+                Err(_) => match spec.parse::<semver::VersionReq>() {
+                    Ok(req) => OptVersionReq::Req(req),
+                    Err(_) => bail!("invalid version `{}` of `{}`", spec, opts.krate),
+                },
+            };
+            let mut matching = summaries
+                .iter()
+                .filter(|s| req.matches(s.summary.version()));
+            let first = matching.clone().next();
+            match matching.find(|s| !s.yanked).or(first) {
+                Some(summary) => summary,
+                None => bail!(
+                    "no versions of `{}` match `{}` in {}",
+                    opts.krate,
+                    spec,
+                    source_ids.original
+                ),
+            }
+        }
+        // The latest stable version which isn't yanked, falling back to
+        // pre-releases and yanked versions.
+        None => summaries
+            .iter()
+            .find(|s| !s.yanked && s.summary.version().pre.is_empty())
+            .or_else(|| summaries.iter().find(|s| !s.yanked))
+            .unwrap_or(&summaries[0]),
+    };
+
+    let metadata = crate_metadata(config, opts);
+    let summary = &selected.summary;
+    let mut dependencies: Vec<_> = summary.dependencies().iter().collect();
+    dependencies.sort_by_key(|dep| (dep.kind(), dep.name_in_toml()));
+    let info = CrateInfo {
+        name: &opts.krate,
+        version: summary.version().to_string(),
+        yanked: selected.yanked,
+        rust_version: selected.rust_version.as_deref(),
+        description: metadata.as_ref().and_then(|m| m.description.as_deref()),
+        homepage: metadata.as_ref().and_then(|m| m.homepage.as_deref()),
+        documentation: metadata.as_ref().and_then(|m| m.documentation.as_deref()),
+        repository: metadata.as_ref().and_then(|m| m.repository.as_deref()),
+        downloads: metadata.as_ref().and_then(|m| m.downloads),
+        features: summary
+            .features()
+            .iter()
+            .map(|(name, values)| {
+                (
+                    name.as_str(),
+                    values.iter().map(|value| value.to_string()).collect(),
+                )
+            })
+            .collect(),
+        dependencies: dependencies
+            .into_iter()
+            .map(|dep| CrateInfoDependency {
+                name: dep.name_in_toml().as_str(),
+                package: dep
+                    .explicit_name_in_toml()
+                    .map(|_| dep.package_name().as_str()),
+                req: dep.version_req().to_string(),
+                kind: dep.kind(),
+                optional: dep.is_optional(),
+                target: dep.platform().map(|platform| platform.to_string()),
+                features: dep.features().iter().map(|f| f.as_str()).collect(),
+                default_features: dep.uses_default_features(),
+            })
+            .collect(),
+        versions: summaries
+            .iter()
+            .map(|s| CrateInfoVersion {
+                version: s.summary.version().to_string(),
+                yanked: s.yanked,
+                rust_version: s.rust_version.as_deref(),
+            })
+            .collect(),
+    };
 
+    match opts.format {
+        InfoFormat::Json => config.shell().print_json(&info)?,
+        InfoFormat::Human => print_crate_info(config, &info),
+    }
     Ok(())
 }
 
-pub fn yank(
-    config: &Config,
-    krate: Option<String>,
-    version: Option<String>,
-    token: Option<String>,
-    index: Option<String>,
-    undo: bool,
-    reg: Option<String>,
-) -> CargoResult<()> {
-    let name = match krate {
-        Some(name) => name,
+/// Reads the details of a crate which aren't in the index from the web API
+/// of its registry.
+///
+/// They are optional, so registries without an API, or which don't know the
+/// crate, are ignored.
+fn crate_metadata(config: &Config, opts: &InfoOptions) -> Option<crates_io::CrateMetadata> {
+    if config.offline() {
+        return None;
+    }
+    let (mut registry, _) = match registry(
+        config,
+        None,
+        opts.index.as_deref(),
+        opts.registry.as_deref(),
+        false,
+        false,
+    ) {
+        Ok(registry) => registry,
+        Err(e) => {
+            log::debug!(
+                "not reading the crate metadata from the registry API: {:?}",
+                e
+            );
+            return None;
+        }
+    };
+    let host = registry.host().to_string();
+    match call_api(config, true, || registry.crate_metadata(&opts.krate)) {
+        Ok(metadata) => Some(metadata),
+        Err(e) => {
+            let not_found = match e.downcast_ref::<crates_io::ResponseError>() {
+                Some(crates_io::ResponseError::Code { code, .. }) => {
+                    matches!(code, 404 | 405 | 501)
+                }
+                // Like registries serving their API from `file://` URLs.
+                Some(crates_io::ResponseError::Curl(e)) => e.is_file_couldnt_read_file(),
+                _ => false,
+            };
+            if !not_found {
+                let _ = config.shell().warn(format!(
+                    "failed to read the details of `{}` from the registry at {}: {}",
+                    opts.krate, host, e
+                ));
+            }
+            None
+        }
+    }
+}
+
+fn print_crate_info(config: &Config, info: &CrateInfo<'_>) {
+    let yanked = if info.yanked { " (yanked)" } else { "" };
+    drop_println!(config, "{}@{}{}", info.name, info.version, yanked);
+    if let Some(description) = info.description {
+        drop_println!(config, "{}", description.trim());
+    }
+    let fields = [
+        ("rust-version", info.rust_version.map(str::to_string)),
+        ("homepage", info.homepage.map(str::to_string)),
+        ("documentation", info.documentation.map(str::to_string)),
+        ("repository", info.repository.map(str::to_string)),
+        ("downloads", info.downloads.map(|d| d.to_string())),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            drop_println!(config, "{}: {}", key, value);
+        }
+    }
+
+    if !info.features.is_empty() {
+        drop_println!(config, "features:");
+        for (name, values) in &info.features {
+            drop_println!(config, "  {} = [{}]", name, values.join(", "));
+        }
+    }
+
+    let kinds = [
+        (DepKind::Normal, "dependencies"),
+        (DepKind::Build, "build-dependencies"),
+        (DepKind::Development, "dev-dependencies"),
+    ];
+    for (kind, table) in kinds {
+        let mut deps = info.dependencies.iter().filter(|dep| dep.kind == kind);
+        let first = match deps.next() {
+            Some(dep) => dep,
+            None => continue,
+        };
+        drop_println!(config, "{}:", table);
+        for dep in std::iter::once(first).chain(deps) {
+            let mut notes = Vec::new();
+            if let Some(package) = dep.package {
+                notes.push(format!("package `{}`", package));
+            }
+            if dep.optional {
+                notes.push("optional".to_string());
+            }
+            if let Some(target) = &dep.target {
+                notes.push(format!("for `{}`", target));
+            }
+            if notes.is_empty() {
+                drop_println!(config, "  {} {}", dep.name, dep.req);
+            } else {
+                drop_println!(config, "  {} {} ({})", dep.name, dep.req, notes.join(", "));
+            }
+        }
+    }
+
+    drop_println!(config, "versions:");
+    for version in &info.versions {
+        let yanked = if version.yanked { " (yanked)" } else { "" };
+        drop_println!(config, "  {}{}", version.version, yanked);
+    }
+}
+
+/// Explains failures of registries which don't implement a part of the web
+/// API, like `what` = "managing owners", rather than showing their raw
+/// response.
+fn api_unsupported_error(err: anyhow::Error, host: &str, what: &str) -> anyhow::Error {
+    match err.downcast_ref::<crates_io::ResponseError>() {
+        Some(crates_io::ResponseError::Code { code, .. }) if matches!(code, 404 | 405 | 501) => {
+            format_err!(
+                "the registry at {} does not support {}\n\n\
+                 The API of the registry responded with HTTP status {}.",
+                host,
+                what,
+                code
+            )
+        }
+        _ => err,
+    }
+}
+
+pub struct YankOptions {
+    pub krate: Option<String>,
+    /// The versions to yank, either exact versions or version requirements
+    /// matching any number of published versions.
+    pub versions: Vec<String>,
+    pub token: Option<String>,
+    pub index: Option<String>,
+    pub undo: bool,
+    pub registry: Option<String>,
+    /// The reason for the yank, sent to registries which record it.
+    pub reason: Option<String>,
+    pub dry_run: bool,
+}
+
+pub fn yank(config: &Config, opts: &YankOptions) -> CargoResult<()> {
+    let name = match &opts.krate {
+        Some(name) => name.clone(),
         None => {
             let manifest_path = find_root_manifest_for_wd(config.cwd())?;
             let ws = Workspace::new(&manifest_path, config)?;
             ws.current()?.package_id().name().to_string()
         }
     };
-    let version = match version {
-        Some(v) => v,
-        None => bail!("a version must be specified to yank"),
-    };
+    if opts.versions.is_empty() {
+        bail!("a version must be specified to yank");
+    }
 
-    let (mut registry, _) = registry(
+    let (mut registry, source_ids) = registry(
         config,
-        token.as_deref(),
-        index.as_deref(),
-        reg.as_deref(),
+        opts.token.as_deref(),
+        opts.index.as_deref(),
+        opts.registry.as_deref(),
         true,
         true,
     )?;
 
-    let package_spec = format!("{}@{}", name, version);
-    if undo {
-        config.shell().status("Unyank", package_spec)?;
-        registry.unyank(&name, &version).with_context(|| {
-            format!(
-                "failed to undo a yank from the registry at {}",
-                registry.host()
-            )
-        })?;
-    } else {
-        config.shell().status("Yank", package_spec)?;
-        registry
-            .yank(&name, &version)
-            .with_context(|| format!("failed to yank from the registry at {}", registry.host()))?;
+    let versions = resolve_yank_versions(config, source_ids.replacement, &name, opts)?;
+    let host = registry.host().to_string();
+
+    if versions.len() > 1 && !opts.dry_run {
+        let action = if opts.undo { "Unyank" } else { "Yank" };
+        drop_println!(config, "{} the following versions of `{}`:", action, name);
+        for version in &versions {
+            drop_println!(config, "  {}", version);
+        }
+        drop_print!(config, "Continue? [y/N] ");
+        let mut line = String::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .with_context(|| "failed to read stdin")?;
+        if !matches!(line.trim(), "y" | "Y" | "yes") {
+            bail!("{} aborted", action.to_lowercase());
+        }
+    }
+
+    for version in &versions {
+        let package_spec = format!("{}@{}", name, version);
+        if opts.undo {
+            config.shell().status("Unyank", package_spec)?;
+            if opts.dry_run {
+                continue;
+            }
+            call_api(config, true, || registry.unyank(&name, version))
+                .with_context(|| format!("failed to undo a yank from the registry at {}", host))?;
+        } else {
+            config.shell().status("Yank", package_spec)?;
+            if opts.dry_run {
+                continue;
+            }
+            call_api(config, true, || {
+                registry.yank_with_reason(&name, version, opts.reason.as_deref())
+            })
+            .with_context(|| format!("failed to yank from the registry at {}", host))?;
+        }
+    }
+
+    if opts.dry_run {
+        let action = if opts.undo { "unyank" } else { "yank" };
+        config
+            .shell()
+            .warn(format!("aborting {} due to dry run", action))?;
     }
 
     Ok(())
 }
 
+/// Resolves the versions of `name` to yank, or to unyank with `--undo`.
+///
+/// Exact versions are used as given. Version requirements are matched
+/// against the versions published in the index which aren't yanked yet, or
+/// are yanked for `--undo`.
+fn resolve_yank_versions(
+    config: &Config,
+    source_id: SourceId,
+    name: &str,
+    opts: &YankOptions,
+) -> CargoResult<Vec<String>> {
+    let mut versions = Vec::new();
+    let mut src = None;
+    for spec in &opts.versions {
+        if spec.parse::<semver::Version>().is_ok() {
+            versions.push(spec.clone());
+            continue;
+        }
+        let req = match spec.parse::<semver::VersionReq>() {
+            Ok(req) => OptVersionReq::Req(req),
+            // Leave it to the registry to reject invalid versions.
+            Err(_) => {
+                versions.push(spec.clone());
+                continue;
+            }
+        };
+        let _lock = config.acquire_package_cache_lock()?;
+        let src = match &mut src {
+            Some(src) => src,
+            None => src.insert(RegistrySource::remote(source_id, &HashSet::new(), config)?),
+        };
+        let mut matching = loop {
+            match src.versions(InternedString::new(name), &req)? {
+                Poll::Ready(versions) => break versions,
+                Poll::Pending => src.block_until_ready()?,
+            }
+        };
+        matching.retain(|(_, yanked)| *yanked == opts.undo);
+        if matching.is_empty() {
+            bail!(
+                "no {} versions of `{}` match `{}`",
+                if opts.undo { "yanked" } else { "published" },
+                name,
+                spec
+            );
+        }
+        matching.sort();
+        versions.extend(matching.into_iter().map(|(v, _)| v.to_string()));
+    }
+    let mut seen = HashSet::new();
+    versions.retain(|v| seen.insert(v.clone()));
+    Ok(versions)
+}
+
+/// Calls the registry API with `f`, retrying spurious failures with
+/// `-Zhttp-retries`.
+///
+/// Calls which aren't `idempotent` are only retried when the request
+/// certainly wasn't processed by the registry.
+fn call_api<T>(
+    config: &Config,
+    idempotent: bool,
+    mut f: impl FnMut() -> CargoResult<T>,
+) -> CargoResult<T> {
+    if !config.cli_unstable().http_retries {
+        return f();
+    }
+    let retry = Retry::new(config)?;
+    let retry = if idempotent {
+        retry
+    } else {
+        retry.non_idempotent()
+    };
+    retry.run(f)
+}
+
 /// Gets the SourceId for an index or registry setting.
 ///
 /// The `index` and `reg` values are from the command-line or config settings.
@@ -1007,21 +1560,71 @@ struct RegistrySourceIds {
     replacement: SourceId,
 }
 
+/// The output format of `cargo search`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchFormat {
+    /// One line per crate, with its latest version and description.
+    Human,
+    /// A JSON object with all the search results.
+    Json,
+}
+
+/// The search results, as printed by `cargo search` in JSON.
+#[derive(Serialize)]
+struct SearchResults<'a> {
+    crates: Vec<SearchResult<'a>>,
+    /// The number of crates matching the query, including the ones beyond
+    /// the limit.
+    total: u32,
+}
+
+#[derive(Serialize)]
+struct SearchResult<'a> {
+    name: &'a str,
+    max_version: &'a str,
+    description: Option<&'a str>,
+    downloads: Option<u64>,
+    recent_downloads: Option<u64>,
+}
+
 pub fn search(
     query: &str,
     config: &Config,
     index: Option<String>,
     limit: u32,
     reg: Option<String>,
+    sort: Option<&str>,
+    format: SearchFormat,
 ) -> CargoResult<()> {
     let (mut registry, source_ids) =
         registry(config, None, index.as_deref(), reg.as_deref(), false, false)?;
-    let (crates, total_crates) = registry.search(query, limit).with_context(|| {
-        format!(
-            "failed to retrieve search results from the registry at {}",
-            registry.host()
-        )
-    })?;
+    let host = registry.host().to_string();
+    let (crates, total_crates) =
+        call_api(config, true, || registry.search_sorted(query, limit, sort))
+            .map_err(|e| api_unsupported_error(e, &host, "searching crates"))
+            .with_context(|| {
+                format!(
+                    "failed to retrieve search results from the registry at {}",
+                    host
+                )
+            })?;
+
+    if format == SearchFormat::Json {
+        let crates = crates
+            .iter()
+            .map(|krate| SearchResult {
+                name: &krate.name,
+                max_version: &krate.max_version,
+                description: krate.description.as_deref(),
+                downloads: krate.downloads,
+                recent_downloads: krate.recent_downloads,
+            })
+            .collect();
+        return config.shell().print_json(&SearchResults {
+            crates,
+            total: total_crates,
+        });
+    }
 
     let names = crates
         .iter()
@@ -22,12 +22,15 @@ use crate::core::summary::Summary;
 use crate::core::Feature;
 use crate::core::{GitReference, PackageId, PackageIdSpec, PackageSet, SourceId, Workspace};
 use crate::ops;
-use crate::sources::PathSource;
+use crate::sources::{PathSource, CRATES_IO_INDEX};
 use crate::util::errors::CargoResult;
+use crate::util::interning::InternedString;
 use crate::util::{profile, CanonicalUrl};
 use anyhow::Context as _;
 use log::{debug, trace};
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use url::Url;
 
 /// Result for `resolve_ws_with_opts`.
 pub struct WorkspaceResolve<'cfg> {
@@ -157,6 +160,15 @@ pub fn resolve_ws_with_opts<'cfg>(
         force_all_targets,
     )?;
 
+    pkg_set.check_artifact_dependencies(
+        &resolved_with_overrides,
+        &member_ids,
+        has_dev_units,
+        requested_targets,
+        target_data,
+        force_all_targets,
+    )?;
+
     let feature_opts = FeatureOpts::new(ws, has_dev_units, force_all_targets)?;
     let resolved_features = FeatureResolver::resolve(
         ws,
@@ -204,11 +216,118 @@ fn resolve_with_registry<'cfg>(
     )?;
 
     if !ws.is_ephemeral() && ws.require_optional_deps() {
-        ops::write_pkg_lockfile(ws, &mut resolve)?;
+        // Patching lock file entries is only possible with
+        // `-Zunstable-options`, so don't look for them otherwise.
+        let patch_changes = match &prev {
+            Some(prev) if ws.config().cli_unstable().unstable_options => {
+                patch_lock_changes(prev, &resolve, registry)?
+            }
+            _ => PatchLockChanges::default(),
+        };
+        ops::lockfile::write_pkg_lockfile_patched(ws, &mut resolve, &patch_changes)?;
+        if ws.config().allow_patch_update() {
+            for change in &patch_changes.changes {
+                ws.config().shell().status("Patching", change)?;
+            }
+        }
     }
     Ok(resolve)
 }
 
+/// A `Cargo.lock` entry which is replaced by the package of a `[patch]`.
+pub(crate) struct PatchLockChange {
+    /// The entry of the previous lock file.
+    from: PackageId,
+    /// The patch replacing it.
+    to: PackageId,
+    /// The key of the `[patch]` table, like `crates-io`.
+    patch_key: String,
+}
+
+impl fmt::Display for PatchLockChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> v{} ({}), patched by `[patch.{}]`",
+            self.from,
+            self.to.version(),
+            self.to.source_id(),
+            self.patch_key
+        )
+    }
+}
+
+/// The lock file entries which changed because of a `[patch]`.
+#[derive(Default)]
+pub(crate) struct PatchLockChanges {
+    pub changes: Vec<PatchLockChange>,
+    /// Whether the lock file has other changes as well, which are not
+    /// allowed by `--allow-patch-update`.
+    pub has_other_changes: bool,
+}
+
+/// Compares the previous lock file with the new resolve to find the entries
+/// which were replaced by a `[patch]`, like a git dependency that is patched
+/// to a different commit than the one locked.
+fn patch_lock_changes(
+    previous: &Resolve,
+    resolve: &Resolve,
+    registry: &PackageRegistry<'_>,
+) -> CargoResult<PatchLockChanges> {
+    // Package IDs don't compare the precise git revision, which is exactly
+    // what changes when patching a git dependency to another commit.
+    let key = |id: PackageId| (id, id.source_id().precise().map(str::to_string));
+    let previous_ids: HashSet<_> = previous.iter().map(key).collect();
+    let new_ids: HashSet<_> = resolve.iter().map(key).collect();
+    let mut removed: Vec<_> = previous
+        .iter()
+        .filter(|id| !new_ids.contains(&key(*id)))
+        .collect();
+    let added: Vec<_> = resolve
+        .iter()
+        .filter(|id| !previous_ids.contains(&key(*id)))
+        .collect();
+    removed.sort();
+
+    let crates_io = CanonicalUrl::new(&Url::parse(CRATES_IO_INDEX)?)?;
+    let mut changes = PatchLockChanges::default();
+    for to in added {
+        let patched_url = registry.patches().iter().find_map(|(url, summaries)| {
+            summaries
+                .iter()
+                .any(|summary| key(summary.package_id()) == key(to))
+                .then(|| url)
+        });
+        let patched_url = match patched_url {
+            Some(url) => url,
+            None => {
+                changes.has_other_changes = true;
+                continue;
+            }
+        };
+        let patch_key = if *patched_url == crates_io {
+            "crates-io".to_string()
+        } else {
+            format!("\"{}\"", patched_url.raw_canonicalized_url())
+        };
+        let (replaced, rest): (Vec<_>, Vec<_>) = removed
+            .into_iter()
+            .partition(|from| from.name() == to.name());
+        removed = rest;
+        changes
+            .changes
+            .extend(replaced.into_iter().map(|from| PatchLockChange {
+                from,
+                to,
+                patch_key: patch_key.clone(),
+            }));
+    }
+    if !removed.is_empty() {
+        changes.has_other_changes = true;
+    }
+    Ok(changes)
+}
+
 /// Resolves all dependencies for a package using an optional previous instance
 /// of resolve to guide the resolution process.
 ///
@@ -460,6 +579,10 @@ pub fn resolve_with_previous<'cfg>(
         ws.unstable_features()
             .require(Feature::public_dependency())
             .is_ok(),
+        &ws.root_links_overrides()
+            .keys()
+            .map(|links| InternedString::new(links))
+            .collect(),
     )?;
     let patches: Vec<_> = registry
         .patches()
@@ -473,11 +596,109 @@ pub fn resolve_with_previous<'cfg>(
     }
 
     if let Some(previous) = previous {
-        resolved.merge_from(previous)?;
+        if let Err(e) = resolved.merge_from(previous) {
+            let changed = previous.checksums().iter().find(
+                |(id, cksum)| matches!(resolved.checksums().get(id), Some(mine) if mine != *cksum),
+            );
+            match changed {
+                Some((id, _)) if ws.config().nightly_features_allowed => anyhow::bail!(
+                    "{}\n\
+                     If the new checksum is expected, it can be accepted with \
+                     `cargo update -Z unstable-options --force-checksum -p {}@{}`",
+                    e,
+                    id.name(),
+                    id.version()
+                ),
+                _ => return Err(e),
+            }
+        }
     }
+    check_source_policy(ws, &resolved)?;
     Ok(resolved)
 }
 
+/// Verifies that the sources of all packages in `resolve` are allowed by the
+/// `[source-policy]` configuration, so that nothing is fetched from an
+/// unexpected registry or git repository even if the lock file says so.
+fn check_source_policy(ws: &Workspace<'_>, resolve: &Resolve) -> CargoResult<()> {
+    let config = ws.config();
+    let policy = match config.source_policy_config()? {
+        Some(policy) => policy,
+        None => return Ok(()),
+    };
+    let registries = policy
+        .registries
+        .unwrap_or_else(|| vec![String::from("crates-io")])
+        .iter()
+        .map(|name| {
+            if name == "crates-io" {
+                SourceId::crates_io(config)
+            } else {
+                SourceId::alt_registry(config, name)
+            }
+        })
+        .collect::<CargoResult<Vec<_>>>()
+        .with_context(|| "failed to load the registries of `source-policy.registries`")?;
+    let git = policy.git.unwrap_or_default();
+
+    let mut denied = Vec::new();
+    for id in resolve.iter() {
+        let source_id = id.source_id();
+        let allowed = if source_id.is_path() {
+            true
+        } else if source_id.is_git() {
+            git.iter()
+                .any(|allowed| git_url_allowed(source_id.url(), allowed))
+        } else if source_id.is_crates_io() {
+            registries.iter().any(|r| r.is_crates_io())
+        } else {
+            registries
+                .iter()
+                .any(|r| r.canonical_url() == source_id.canonical_url())
+        };
+        if !allowed {
+            denied.push(id);
+        }
+    }
+    if denied.is_empty() {
+        return Ok(());
+    }
+    denied.sort();
+    let mut msg = String::from(
+        "the sources of the following packages are not allowed by the `[source-policy]` \
+         configuration:",
+    );
+    for id in denied {
+        msg.push_str(&format!(
+            "\n  {} v{} from {}",
+            id.name(),
+            id.version(),
+            id.source_id()
+        ));
+    }
+    anyhow::bail!(msg)
+}
+
+/// Whether the git repository at `url` matches an entry of `source-policy.git`,
+/// which is either a URL prefix, or a host optionally followed by the first
+/// segments of the path.
+fn git_url_allowed(url: &Url, allowed: &str) -> bool {
+    if allowed.contains("://") {
+        let prefix = allowed.trim_end_matches('/');
+        return url.as_str() == prefix || url.as_str().starts_with(&format!("{}/", prefix));
+    }
+    let (host, prefix) = allowed.split_once('/').unwrap_or((allowed, ""));
+    if url.host_str() != Some(host) {
+        return false;
+    }
+    let prefix = prefix.trim_matches('/');
+    let path = url.path().trim_start_matches('/');
+    prefix.is_empty()
+        || path == prefix
+        || path.starts_with(&format!("{}/", prefix))
+        || path == format!("{}.git", prefix)
+}
+
 /// Read the `paths` configuration variable to discover all path overrides that
 /// have been configured.
 pub fn add_overrides<'a>(
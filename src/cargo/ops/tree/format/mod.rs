@@ -14,6 +14,9 @@ enum Chunk {
     Repository,
     Features,
     LibName,
+    Source,
+    Checksum,
+    RustVersion,
 }
 
 pub struct Pattern(Vec<Chunk>);
@@ -26,10 +29,13 @@ impl Pattern {
             let chunk = match raw {
                 RawChunk::Text(text) => Chunk::Raw(text.to_owned()),
                 RawChunk::Argument("p") => Chunk::Package,
-                RawChunk::Argument("l") => Chunk::License,
+                RawChunk::Argument("l") | RawChunk::Argument("license") => Chunk::License,
                 RawChunk::Argument("r") => Chunk::Repository,
                 RawChunk::Argument("f") => Chunk::Features,
                 RawChunk::Argument("lib") => Chunk::LibName,
+                RawChunk::Argument("source") => Chunk::Source,
+                RawChunk::Argument("checksum") => Chunk::Checksum,
+                RawChunk::Argument("rust-version") => Chunk::RustVersion,
                 RawChunk::Argument(a) => {
                     bail!("unsupported pattern `{}`", a);
                 }
@@ -111,6 +117,19 @@ impl<'a> fmt::Display for Display<'a> {
                                 write!(fmt, "{}", target.crate_name())?;
                             }
                         }
+                        Chunk::Source => {
+                            write!(fmt, "{}", package.package_id().source_id().as_url())?;
+                        }
+                        Chunk::Checksum => {
+                            if let Some(checksum) = package.summary().checksum() {
+                                write!(fmt, "{}", checksum)?;
+                            }
+                        }
+                        Chunk::RustVersion => {
+                            if let Some(rust_version) = package.rust_version() {
+                                write!(fmt, "{}", rust_version)?;
+                            }
+                        }
                     }
                 }
             }
@@ -24,7 +24,8 @@ pub enum RawChunk<'a> {
 /// `Cargo.toml`.
 ///
 /// Substitutions are alphabetic characters between curly braces, like `{p}`
-/// or `{foo}`. The actual interpretation of these are done in the `Pattern`
+/// or `{foo}`, which may be followed by digits and dashes, like
+/// `{rust-version}`. The actual interpretation of these are done in the `Pattern`
 /// struct.
 ///
 /// Bare curly braces can be included in the output with double braces like
@@ -67,7 +68,7 @@ impl<'a> Parser<'a> {
 
         loop {
             match self.it.peek() {
-                Some(&(_, ch)) if ch.is_alphanumeric() => {
+                Some(&(_, ch)) if ch.is_alphanumeric() || ch == '-' => {
                     self.it.next();
                 }
                 Some(&(end, _)) => return &self.s[start..end],
@@ -5,10 +5,12 @@ use crate::core::compiler::{CompileKind, RustcTargetData};
 use crate::core::dependency::DepKind;
 use crate::core::resolver::features::{CliFeatures, FeaturesFor, ResolvedFeatures};
 use crate::core::resolver::Resolve;
+use crate::core::Dependency;
 use crate::core::{FeatureMap, FeatureValue, Package, PackageId, PackageIdSpec, Workspace};
 use crate::util::interning::InternedString;
 use crate::util::CargoResult;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
 pub enum Node {
@@ -58,6 +60,72 @@ impl Edges {
     }
 }
 
+/// Why a package depends on another one, as shown by `--explain-edges`.
+///
+/// This is derived from the dependency declarations and the features the
+/// feature resolver activated on the dependent package.
+#[derive(Clone)]
+pub struct EdgeReason {
+    /// The kind of the dependency declarations behind the edge.
+    kind: DepKind,
+    /// For optional dependencies, the activated features of the dependent
+    /// package that enable the dependency, along with the feature value
+    /// doing so (like `dep:foo` or `foo/bar`).
+    enabled_by: Vec<(InternedString, String)>,
+    /// The features enabled on the dependency through this edge, along with
+    /// the feature of the dependent package they come from, if they aren't
+    /// part of the dependency declaration.
+    features: Vec<(InternedString, Option<(InternedString, String)>)>,
+}
+
+impl EdgeReason {
+    fn new(kind: DepKind) -> EdgeReason {
+        EdgeReason {
+            kind,
+            enabled_by: Vec::new(),
+            features: Vec::new(),
+        }
+    }
+
+    fn add_feature(&mut self, name: InternedString, from: Option<(InternedString, String)>) {
+        if !self.features.iter().any(|(n, f)| *n == name && *f == from) {
+            self.features.push((name, from));
+            self.features.sort_unstable();
+        }
+    }
+}
+
+impl fmt::Display for EdgeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            DepKind::Normal => "normal",
+            DepKind::Development => "dev",
+            DepKind::Build => "build",
+        };
+        f.write_str(kind)?;
+        if !self.enabled_by.is_empty() {
+            let enabled_by: Vec<_> = self
+                .enabled_by
+                .iter()
+                .map(|(feature, value)| format!("\"{}\" ({})", feature, value))
+                .collect();
+            write!(f, "; enabled by {}", enabled_by.join(", "))?;
+        }
+        if !self.features.is_empty() {
+            let features: Vec<_> = self
+                .features
+                .iter()
+                .map(|(name, from)| match from {
+                    Some((feature, value)) => format!("{} ({} in \"{}\")", name, value, feature),
+                    None => name.to_string(),
+                })
+                .collect();
+            write!(f, "; features: {}", features.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 /// A graph of dependencies.
 pub struct Graph<'a> {
     nodes: Vec<Node>,
@@ -79,6 +147,10 @@ pub struct Graph<'a> {
     /// Key is the index of a package node, value is a map of dep_name to a
     /// set of `(pkg_node_index, is_optional)`.
     dep_name_map: HashMap<usize, HashMap<InternedString, HashSet<(usize, bool)>>>,
+    /// Why each package edge exists, keyed by the indexes of the dependent
+    /// and the dependency package, and the kind of the edge. Only filled with
+    /// `--explain-edges`.
+    edge_reasons: HashMap<(usize, usize, DepKind), EdgeReason>,
 }
 
 impl<'a> Graph<'a> {
@@ -90,6 +162,7 @@ impl<'a> Graph<'a> {
             package_map,
             cli_features: HashSet::new(),
             dep_name_map: HashMap::new(),
+            edge_reasons: HashMap::new(),
         }
     }
 
@@ -155,6 +228,12 @@ impl<'a> Graph<'a> {
         }
     }
 
+    /// Returns why the package `from` depends on the package `to` through
+    /// an edge of the given kind, if recorded with `--explain-edges`.
+    pub fn edge_reason(&self, from: usize, to: usize, kind: DepKind) -> Option<&EdgeReason> {
+        self.edge_reasons.get(&(from, to, kind))
+    }
+
     /// Returns `true` if the given feature node index is a feature enabled
     /// via the command-line.
     pub fn is_cli_feature(&self, index: usize) -> bool {
@@ -188,6 +267,13 @@ impl<'a> Graph<'a> {
                 for edge_index in edge_indexes {
                     let new_to_index = visit(graph, new_graph, remap, *edge_index);
                     new_graph.edges[new_from].add_edge(*edge_kind, new_to_index);
+                    if let EdgeKind::Dep(kind) = edge_kind {
+                        if let Some(reason) = graph.edge_reasons.get(&(index, *edge_index, *kind)) {
+                            new_graph
+                                .edge_reasons
+                                .insert((new_from, new_to_index, *kind), reason.clone());
+                        }
+                    }
                 }
             }
             new_from
@@ -402,6 +488,20 @@ fn add_pkg(
                 requested_kind,
                 opts,
             );
+            if opts.explain_edges {
+                let reason = graph
+                    .edge_reasons
+                    .entry((from_index, dep_index, dep.kind()))
+                    .or_insert_with(|| EdgeReason::new(dep.kind()));
+                explain_edge(
+                    reason,
+                    dep,
+                    resolve,
+                    resolved_features,
+                    package_id,
+                    features_for,
+                );
+            }
             if opts.graph_features {
                 // Add the dependency node with feature nodes in-between.
                 dep_name_map
@@ -445,6 +545,54 @@ fn add_pkg(
     from_index
 }
 
+/// Records in `reason` why `package_id` uses the dependency `dep`.
+fn explain_edge(
+    reason: &mut EdgeReason,
+    dep: &Dependency,
+    resolve: &Resolve,
+    resolved_features: &ResolvedFeatures,
+    package_id: PackageId,
+    features_for: FeaturesFor,
+) {
+    if dep.uses_default_features() {
+        reason.add_feature(InternedString::new("default"), None);
+    }
+    for feature in dep.features() {
+        reason.add_feature(*feature, None);
+    }
+
+    let dep_name = dep.name_in_toml();
+    let feature_map = resolve.summary(package_id).features();
+    for feature in resolved_features.activated_features(package_id, features_for) {
+        let fvs = match feature_map.get(&feature) {
+            Some(fvs) => fvs,
+            None => continue,
+        };
+        for fv in fvs {
+            match fv {
+                FeatureValue::Dep { dep_name: name } if *name == dep_name => {
+                    if dep.is_optional() {
+                        reason.enabled_by.push((feature, fv.to_string()));
+                    }
+                }
+                FeatureValue::DepFeature {
+                    dep_name: name,
+                    dep_feature,
+                    weak,
+                } if *name == dep_name => {
+                    if dep.is_optional() && !weak {
+                        reason.enabled_by.push((feature, fv.to_string()));
+                    }
+                    reason.add_feature(*dep_feature, Some((feature, fv.to_string())));
+                }
+                _ => {}
+            }
+        }
+    }
+    reason.enabled_by.sort_unstable();
+    reason.enabled_by.dedup();
+}
+
 /// Adds a feature node between two nodes.
 ///
 /// That is, it adds the following:
@@ -49,6 +49,9 @@ pub struct TreeOptions {
     pub max_display_depth: u32,
     /// Excludes proc-macro dependencies.
     pub no_proc_macro: bool,
+    /// Annotates the edges of an inverted tree with the dependency kinds and
+    /// features behind them.
+    pub explain_edges: bool,
 }
 
 #[derive(PartialEq)]
@@ -183,7 +186,7 @@ pub fn build_and_print(ws: &Workspace<'_>, opts: &TreeOptions) -> CargoResult<()
     } else {
         opts.invert
             .iter()
-            .map(|p| PackageIdSpec::parse(p))
+            .map(|p| PackageIdSpec::parse_qualified(p, ws.config()))
             .collect::<CargoResult<Vec<PackageIdSpec>>>()?
     };
     let root_ids = ws_resolve.targeted_resolve.specs_to_ids(&root_specs)?;
@@ -261,6 +264,7 @@ fn print(
             config,
             graph,
             root_index,
+            None,
             &format,
             symbols,
             pkgs_to_prune,
@@ -282,6 +286,7 @@ fn print_node<'a>(
     config: &Config,
     graph: &'a Graph<'_>,
     node_index: usize,
+    parent_edge: Option<(usize, &EdgeKind)>,
     format: &Pattern,
     symbols: &Symbols,
     pkgs_to_prune: &[PackageIdSpec],
@@ -325,7 +330,21 @@ fn print_node<'a>(
     } else {
         " (*)"
     };
-    drop_println!(config, "{}{}", format.display(graph, node_index), star);
+    // In an inverted tree the printed node depends on its parent.
+    let reason = match parent_edge {
+        Some((parent, EdgeKind::Dep(kind))) => graph
+            .edge_reason(node_index, parent, *kind)
+            .map(|reason| format!(" [{}]", reason))
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+    drop_println!(
+        config,
+        "{}{}{}",
+        format.display(graph, node_index),
+        reason,
+        star
+    );
 
     if !new || in_cycle {
         return;
@@ -435,6 +454,7 @@ fn print_dependencies<'a>(
             config,
             graph,
             *dependency,
+            Some((node_index, kind)),
             format,
             symbols,
             pkgs_to_prune,
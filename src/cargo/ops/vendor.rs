@@ -1,12 +1,12 @@
 use crate::core::shell::Verbosity;
-use crate::core::{GitReference, Workspace};
+use crate::core::{GitReference, Package, Workspace};
 use crate::ops;
 use crate::sources::path::PathSource;
 use crate::sources::CRATES_IO_REGISTRY;
 use crate::util::{CargoResult, Config};
 use anyhow::{bail, Context as _};
 use cargo_util::{paths, Sha256};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::{self, File, OpenOptions};
@@ -19,6 +19,34 @@ pub struct VendorOptions<'a> {
     pub versioned_dirs: bool,
     pub destination: &'a Path,
     pub extra: Vec<PathBuf>,
+    /// Leave out the tests, benches and examples of the vendored packages.
+    pub prune: bool,
+    /// Normalize file permissions and record the vendored packages in a
+    /// [`VendorManifest`], so the result can be checked with [`verify`].
+    pub reproducible: bool,
+}
+
+/// The name of the file recording the contents of a reproducible vendor
+/// directory.
+const VENDOR_MANIFEST: &str = ".vendor-manifest.json";
+
+/// The integrity manifest of a vendor directory created with
+/// `--reproducible`.
+#[derive(Serialize, Deserialize)]
+struct VendorManifest {
+    version: u32,
+    /// Whether tests, benches and examples were pruned.
+    prune: bool,
+    /// The vendored packages, keyed by their directory name.
+    packages: BTreeMap<String, VendoredPackage>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VendoredPackage {
+    id: String,
+    /// The sha256 of the `.cargo-checksum.json` of the package, which in turn
+    /// records the checksums of all its files.
+    checksum: String,
 }
 
 pub fn vendor(ws: &Workspace<'_>, opts: &VendorOptions<'_>) -> CargoResult<()> {
@@ -190,6 +218,7 @@ fn sync(
     }
 
     let mut sources = BTreeSet::new();
+    let mut vendored = BTreeMap::new();
     let mut tmp_buf = [0; 64 * 1024];
     for (id, pkg) in ids.iter() {
         // Next up, copy it to the vendor directory
@@ -211,7 +240,9 @@ fn sync(
         let dst = canonical_destination.join(&dst_name);
         to_remove.remove(&dst);
         let cksum = dst.join(".cargo-checksum.json");
-        if dir_has_version_suffix && cksum.exists() {
+        // A previous run may have used different `--prune` or
+        // `--reproducible` settings, so always re-copy with those.
+        if dir_has_version_suffix && cksum.exists() && !opts.prune && !opts.reproducible {
             // Always re-copy directory without version suffix in case the version changed
             continue;
         }
@@ -223,18 +254,43 @@ fn sync(
 
         let _ = fs::remove_dir_all(&dst);
         let pathsource = PathSource::new(src, id.source_id(), config);
-        let paths = pathsource.list_files(pkg)?;
+        let mut paths = pathsource.list_files(pkg)?;
+        if opts.prune {
+            paths = prune_targets(pkg, src, paths);
+        }
         let mut map = BTreeMap::new();
-        cp_sources(src, &paths, &dst, &mut map, &mut tmp_buf)
+        cp_sources(src, &paths, &dst, &mut map, &mut tmp_buf, opts.reproducible)
             .with_context(|| format!("failed to copy over vendored sources for: {}", id))?;
+        if opts.prune {
+            let manifest_path = dst.join("Cargo.toml");
+            let manifest = prune_manifest(&paths::read(&manifest_path)?)
+                .with_context(|| format!("failed to prune the manifest of {}", id))?;
+            paths::write(&manifest_path, &manifest)?;
+            let mut sha = Sha256::new();
+            sha.update(manifest.as_bytes());
+            map.insert("Cargo.toml".to_string(), sha.finish_hex());
+        }
 
         // Finally, emit the metadata about this package
         let json = serde_json::json!({
             "package": checksums.get(id),
             "files": map,
-        });
-
-        paths::write(&cksum, json.to_string())?;
+        })
+        .to_string();
+
+        paths::write(&cksum, &json)?;
+        if opts.reproducible {
+            normalize_permissions(&cksum, false)?;
+            let mut sha = Sha256::new();
+            sha.update(json.as_bytes());
+            vendored.insert(
+                dst_name,
+                VendoredPackage {
+                    id: id.to_string(),
+                    checksum: sha.finish_hex(),
+                },
+            );
+        }
     }
 
     for path in to_remove {
@@ -245,6 +301,22 @@ fn sync(
         }
     }
 
+    let manifest_path = canonical_destination.join(VENDOR_MANIFEST);
+    if opts.reproducible && !vendored.is_empty() {
+        let manifest = VendorManifest {
+            version: 1,
+            prune: opts.prune,
+            packages: vendored,
+        };
+        let mut json = serde_json::to_string_pretty(&manifest)?;
+        json.push('\n');
+        paths::write(&manifest_path, json)?;
+        normalize_permissions(&manifest_path, false)?;
+    } else if manifest_path.exists() {
+        // The manifest would no longer describe the vendored packages.
+        paths::remove_file(&manifest_path)?;
+    }
+
     // add our vendored source
     let mut config = BTreeMap::new();
 
@@ -312,12 +384,223 @@ fn sync(
     Ok(VendorConfig { source: config })
 }
 
+/// Leaves out the files of the tests, benches and examples of `pkg`, which
+/// are never built when it is used as a dependency.
+fn prune_targets(pkg: &Package, src: &Path, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let pruned_dirs = ["tests", "benches", "examples"].map(|dir| src.join(dir));
+    let pruned_targets: HashSet<&Path> = pkg
+        .targets()
+        .iter()
+        .filter(|t| t.is_test() || t.is_bench() || t.is_example())
+        .filter_map(|t| t.src_path().path())
+        .collect();
+    paths
+        .into_iter()
+        .filter(|p| {
+            !pruned_targets.contains(p.as_path())
+                && !pruned_dirs.iter().any(|dir| p.starts_with(dir))
+        })
+        .collect()
+}
+
+/// Removes the tests, benches and examples left out by [`prune_targets`]
+/// from a manifest, and disables their discovery, so that the pruned package
+/// still loads.
+fn prune_manifest(contents: &str) -> CargoResult<String> {
+    let mut manifest: toml_edit::Document = contents.parse()?;
+    for key in ["test", "bench", "example"] {
+        manifest.remove(key);
+    }
+    for key in ["package", "project"] {
+        if let Some(package) = manifest.get_mut(key).and_then(|p| p.as_table_like_mut()) {
+            for auto in ["autotests", "autobenches", "autoexamples"] {
+                package.insert(auto, toml_edit::value(false));
+            }
+        }
+    }
+    Ok(manifest.to_string())
+}
+
+/// Sets the permissions of a vendored file to `0o755` if it is executable and
+/// `0o644` otherwise, so they don't depend on the umask or on how the package
+/// was extracted.
+fn normalize_permissions(path: &Path, executable: bool) -> CargoResult<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = if executable { 0o755 } else { 0o644 };
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .with_context(|| format!("failed to set permissions of {:?}", path))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, executable);
+    Ok(())
+}
+
+/// Checks that the vendor directory at `destination` is unmodified, without
+/// resolving or downloading anything.
+///
+/// Every file of every vendored package must match the checksum recorded in
+/// its `.cargo-checksum.json`, and no files may have been added. If the
+/// directory was created with `--reproducible`, the set of packages and the
+/// file permissions must match the [`VendorManifest`] as well.
+pub fn verify(config: &Config, destination: &Path) -> CargoResult<()> {
+    if !destination.is_dir() {
+        bail!(
+            "vendor directory `{}` does not exist",
+            destination.display()
+        );
+    }
+    let manifest_path = destination.join(VENDOR_MANIFEST);
+    let manifest: Option<VendorManifest> = if manifest_path.exists() {
+        let contents = paths::read(&manifest_path)?;
+        Some(
+            serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse {}", manifest_path.display()))?,
+        )
+    } else {
+        None
+    };
+
+    let mut dirs = BTreeSet::new();
+    for entry in destination.read_dir()? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with('.') && entry.file_type()?.is_dir() {
+            dirs.insert(name);
+        }
+    }
+
+    let mut problems = Vec::new();
+    for dir in &dirs {
+        let recorded = match &manifest {
+            Some(manifest) => match manifest.packages.get(dir) {
+                Some(pkg) => Some(pkg),
+                None => {
+                    problems.push(format!("`{}` is not listed in {}", dir, VENDOR_MANIFEST));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        verify_package(&destination.join(dir), dir, recorded, &mut problems)?;
+    }
+    if let Some(manifest) = &manifest {
+        for dir in manifest.packages.keys() {
+            if !dirs.contains(dir) {
+                problems.push(format!("`{}` is missing", dir));
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!(
+            "vendor directory `{}` failed verification:\n  {}",
+            destination.display(),
+            problems.join("\n  ")
+        );
+    }
+    config.shell().status(
+        "Verified",
+        format!(
+            "{} vendored package{} in `{}`",
+            dirs.len(),
+            if dirs.len() == 1 { "" } else { "s" },
+            destination.display()
+        ),
+    )?;
+    Ok(())
+}
+
+fn verify_package(
+    dir: &Path,
+    name: &str,
+    recorded: Option<&VendoredPackage>,
+    problems: &mut Vec<String>,
+) -> CargoResult<()> {
+    #[derive(Deserialize)]
+    struct Checksums {
+        files: BTreeMap<String, String>,
+    }
+
+    let cksum_path = dir.join(".cargo-checksum.json");
+    let contents = match fs::read(&cksum_path) {
+        Ok(contents) => contents,
+        Err(_) => {
+            problems.push(format!("`{}/.cargo-checksum.json` is missing", name));
+            return Ok(());
+        }
+    };
+    if let Some(recorded) = recorded {
+        let mut sha = Sha256::new();
+        sha.update(&contents);
+        if sha.finish_hex() != recorded.checksum {
+            problems.push(format!("`{}/.cargo-checksum.json` has been modified", name));
+        }
+    }
+    let checksums: Checksums = serde_json::from_slice(&contents)
+        .with_context(|| format!("failed to parse {}", cksum_path.display()))?;
+
+    let mut buf = [0; 64 * 1024];
+    for (file, expected) in &checksums.files {
+        let path = dir.join(file);
+        let mut f = match File::open(&path) {
+            Ok(f) => f,
+            Err(_) => {
+                problems.push(format!("`{}/{}` is missing", name, file));
+                continue;
+            }
+        };
+        let mut sha = Sha256::new();
+        loop {
+            let n = f
+                .read(&mut buf)
+                .with_context(|| format!("failed to read from {:?}", path))?;
+            if n == 0 {
+                break;
+            }
+            sha.update(&buf[..n]);
+        }
+        if &sha.finish_hex() != expected {
+            problems.push(format!("`{}/{}` has been modified", name, file));
+        }
+        #[cfg(unix)]
+        if recorded.is_some() {
+            use std::os::unix::fs::MetadataExt;
+            let mode = f.metadata()?.mode() & 0o777;
+            if mode != 0o644 && mode != 0o755 {
+                problems.push(format!(
+                    "`{}/{}` has unnormalized permissions {:o}",
+                    name, file, mode
+                ));
+            }
+        }
+    }
+
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir).unwrap();
+        let relative = relative.to_string_lossy().replace("\\", "/");
+        if relative != ".cargo-checksum.json" && !checksums.files.contains_key(&relative) {
+            problems.push(format!(
+                "`{}/{}` is not part of the package",
+                name, relative
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn cp_sources(
     src: &Path,
     paths: &[PathBuf],
     dst: &Path,
     cksums: &mut BTreeMap<String, String>,
     tmp_buf: &mut [u8],
+    normalize: bool,
 ) -> CargoResult<()> {
     for p in paths {
         let relative = p.strip_prefix(&src).unwrap();
@@ -353,13 +636,18 @@ fn cp_sources(
 
         paths::create_dir_all(dst.parent().unwrap())?;
 
-        let cksum = copy_and_checksum(p, &dst, tmp_buf)?;
+        let cksum = copy_and_checksum(p, &dst, tmp_buf, normalize)?;
         cksums.insert(relative.to_str().unwrap().replace("\\", "/"), cksum);
     }
     Ok(())
 }
 
-fn copy_and_checksum(src_path: &Path, dst_path: &Path, buf: &mut [u8]) -> CargoResult<String> {
+fn copy_and_checksum(
+    src_path: &Path,
+    dst_path: &Path,
+    buf: &mut [u8],
+    normalize: bool,
+) -> CargoResult<String> {
     let mut src = File::open(src_path).with_context(|| format!("failed to open {:?}", src_path))?;
     let mut dst_opts = OpenOptions::new();
     dst_opts.write(true).create(true).truncate(true);
@@ -374,6 +662,16 @@ fn copy_and_checksum(src_path: &Path, dst_path: &Path, buf: &mut [u8]) -> CargoR
     let mut dst = dst_opts
         .open(dst_path)
         .with_context(|| format!("failed to create {:?}", dst_path))?;
+    if normalize {
+        #[cfg(unix)]
+        let executable = {
+            use std::os::unix::fs::MetadataExt;
+            src.metadata()?.mode() & 0o111 != 0
+        };
+        #[cfg(not(unix))]
+        let executable = false;
+        normalize_permissions(dst_path, executable)?;
+    }
     // Not going to bother setting mode on pre-existing files, since there
     // shouldn't be any under normal conditions.
     let mut cksum = Sha256::new();
@@ -95,7 +95,7 @@ impl GitRemote {
         // populated the database with the latest version of `reference`, so
         // return that database and the rev we resolve to.
         if let Some(mut db) = db {
-            fetch(&mut db.repo, self.url.as_str(), reference, cargo_config)
+            self.fetch_into(&mut db.repo, reference, locked_rev, cargo_config)
                 .context(format!("failed to fetch into: {}", into.display()))?;
             match locked_rev {
                 Some(rev) => {
@@ -119,7 +119,7 @@ impl GitRemote {
         }
         paths::create_dir_all(into)?;
         let mut repo = init(into, true)?;
-        fetch(&mut repo, self.url.as_str(), reference, cargo_config)
+        self.fetch_into(&mut repo, reference, locked_rev, cargo_config)
             .context(format!("failed to clone into: {}", into.display()))?;
         let rev = match locked_rev {
             Some(rev) => rev,
@@ -136,6 +136,30 @@ impl GitRemote {
         ))
     }
 
+    /// Fetches `reference` into the database `repo`, only with its most
+    /// recent history if shallow fetches are enabled. The full history is
+    /// fetched if that doesn't contain `locked_rev`.
+    fn fetch_into(
+        &self,
+        repo: &mut git2::Repository,
+        reference: &GitReference,
+        locked_rev: Option<git2::Oid>,
+        cargo_config: &Config,
+    ) -> CargoResult<()> {
+        let depth = shallow_fetch_depth(cargo_config)?;
+        fetch(repo, self.url.as_str(), reference, depth, cargo_config)?;
+        if let (Some(_), Some(rev)) = (depth, locked_rev) {
+            if repo.find_commit(rev).is_err() {
+                debug!(
+                    "{} is missing from the shallow fetch, fetching all history",
+                    rev
+                );
+                fetch(repo, self.url.as_str(), reference, None, cargo_config)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn db_at(&self, db_path: &Path) -> CargoResult<GitDatabase> {
         let repo = git2::Repository::open(db_path)?;
         Ok(GitDatabase {
@@ -430,7 +454,7 @@ impl<'a> GitCheckout<'a> {
             cargo_config
                 .shell()
                 .status("Updating", format!("git submodule `{}`", url))?;
-            fetch(&mut repo, &url, &reference, cargo_config).with_context(|| {
+            fetch(&mut repo, &url, &reference, None, cargo_config).with_context(|| {
                 format!(
                     "failed to fetch submodule `{}` from {}",
                     child.name().unwrap_or(""),
@@ -773,10 +797,29 @@ pub fn with_fetch_options(
     })
 }
 
+/// The number of commits to fetch of git dependencies with `-Zgit-shallow`,
+/// or `None` to fetch their full history.
+fn shallow_fetch_depth(config: &Config) -> CargoResult<Option<u32>> {
+    if !config.cli_unstable().git_shallow {
+        return Ok(None);
+    }
+    match config.net_config()?.git_fetch_depth {
+        Some(0) => Ok(None),
+        depth => Ok(Some(depth.unwrap_or(1))),
+    }
+}
+
+/// Fetches `reference` from `url` into `repo`.
+///
+/// With a `depth`, only that many commits of the history of `reference` are
+/// fetched, which requires the `git` CLI. If the shallow history doesn't
+/// contain `reference`, like a `rev` which isn't the tip of any branch, the
+/// full history is fetched instead.
 pub fn fetch(
     repo: &mut git2::Repository,
     url: &str,
     reference: &GitReference,
+    depth: Option<u32>,
     config: &Config,
 ) -> CargoResult<()> {
     if config.frozen() {
@@ -846,6 +889,20 @@ pub fn fetch(
         }
     }
 
+    // `libgit2` can neither fetch a shallow history, nor turn a shallow
+    // repository into a complete one, so both are left to the `git` CLI.
+    if depth.is_some() || repo.is_shallow() {
+        fetch_with_cli(repo, url, &refspecs, tags, depth, config)?;
+        if depth.is_some() && reference.resolve(repo).is_err() {
+            debug!(
+                "{:?} is missing from the shallow fetch, fetching all history",
+                reference
+            );
+            fetch_with_cli(repo, url, &refspecs, tags, None, config)?;
+        }
+        return Ok(());
+    }
+
     // Unfortunately `libgit2` is notably lacking in the realm of authentication
     // when compared to the `git` command line. As a result, allow an escape
     // hatch for users that would prefer to use `git`-the-CLI for fetching
@@ -853,7 +910,7 @@ pub fn fetch(
     // flavors of authentication possible while also still giving us all the
     // speed and portability of using `libgit2`.
     if let Some(true) = config.net_config()?.git_fetch_with_cli {
-        return fetch_with_cli(repo, url, &refspecs, tags, config);
+        return fetch_with_cli(repo, url, &refspecs, tags, None, config);
     }
 
     debug!("doing a fetch for {}", url);
@@ -907,6 +964,7 @@ fn fetch_with_cli(
     url: &str,
     refspecs: &[String],
     tags: bool,
+    depth: Option<u32>,
     config: &Config,
 ) -> CargoResult<()> {
     let mut cmd = ProcessBuilder::new("git");
@@ -914,6 +972,15 @@ fn fetch_with_cli(
     if tags {
         cmd.arg("--tags");
     }
+    match depth {
+        Some(depth) => {
+            cmd.arg(format!("--depth={}", depth));
+        }
+        None if repo.is_shallow() => {
+            cmd.arg("--unshallow");
+        }
+        None => {}
+    }
     cmd.arg("--force") // handle force pushes
         .arg("--update-head-ok") // see discussion in #2078
         .arg(url)
@@ -21,6 +21,10 @@ pub(super) fn filename(pkg: PackageId) -> String {
     format!("{}-{}.crate", pkg.name(), pkg.version())
 }
 
+fn partial_filename(pkg: PackageId) -> String {
+    format!("{}.partial", filename(pkg))
+}
+
 pub(super) fn download(
     cache_path: &Filesystem,
     config: &Config,
@@ -76,10 +80,21 @@ pub(super) fn download(
         None
     };
 
+    // The data of the download is persisted next to the `.crate` file while
+    // it's received, so a later attempt can pick up where this one stopped.
+    let partial = if config.cli_unstable().resumable_downloads {
+        cache_path.create_dir()?;
+        let partial = cache_path.join(partial_filename(pkg));
+        Some(config.assert_package_cache_locked(&partial).to_path_buf())
+    } else {
+        None
+    };
+
     Ok(MaybeLock::Download {
         url,
         descriptor: pkg.to_string(),
         authorization: authorization,
+        partial,
     })
 }
 
@@ -8,11 +8,13 @@ use crate::sources::registry::download;
 use crate::sources::registry::MaybeLock;
 use crate::sources::registry::{LoadResponse, RegistryConfig, RegistryData};
 use crate::util::errors::{CargoResult, HttpNotSuccessful};
-use crate::util::network::Retry;
+use crate::util::network::retry::parse_retry_after;
+use crate::util::network::sleep::SleepTracker;
+use crate::util::network::{Retry, RetryResult};
 use crate::util::{auth, Config, Filesystem, IntoUrl, Progress, ProgressStyle};
 use anyhow::Context;
 use cargo_util::paths;
-use curl::easy::{HttpVersion, List};
+use curl::easy::{Easy, HttpVersion, List};
 use curl::multi::{EasyHandle, Multi};
 use log::{debug, trace};
 use std::cell::RefCell;
@@ -28,6 +30,7 @@ use url::Url;
 // HTTP headers
 const ETAG: &'static str = "etag";
 const LAST_MODIFIED: &'static str = "last-modified";
+const RETRY_AFTER: &'static str = "retry-after";
 const WWW_AUTHENTICATE: &'static str = "www-authenticate";
 const IF_NONE_MATCH: &'static str = "if-none-match";
 const IF_MODIFIED_SINCE: &'static str = "if-modified-since";
@@ -98,8 +101,10 @@ pub struct Downloads<'cfg> {
     /// finished.
     pending: HashMap<usize, (Download<'cfg>, EasyHandle)>,
     /// Set of paths currently being downloaded.
-    /// This should stay in sync with `pending`.
+    /// This should stay in sync with `pending` and `sleeping`.
     pending_paths: HashSet<PathBuf>,
+    /// Downloads that have failed and are waiting to be retried.
+    sleeping: SleepTracker<(Download<'cfg>, Easy)>,
     /// The final result of each download.
     results: HashMap<PathBuf, CargoResult<CompletedDownload>>,
     /// The next ID to use for creating a token (see `Download::token`).
@@ -136,6 +141,7 @@ struct Headers {
     last_modified: Option<String>,
     etag: Option<String>,
     www_authenticate: Vec<String>,
+    retry_after: Option<Duration>,
 }
 
 enum StatusCode {
@@ -184,6 +190,7 @@ impl<'cfg> HttpRegistry<'cfg> {
                 next: 0,
                 pending: HashMap::new(),
                 pending_paths: HashSet::new(),
+                sleeping: SleepTracker::new(),
                 results: HashMap::new(),
                 progress: RefCell::new(Some(Progress::with_style(
                     "Fetch",
@@ -243,7 +250,7 @@ impl<'cfg> HttpRegistry<'cfg> {
 
     fn handle_completed_downloads(&mut self) -> CargoResult<()> {
         assert_eq!(
-            self.downloads.pending.len(),
+            self.downloads.pending.len() + self.downloads.sleeping.len(),
             self.downloads.pending_paths.len()
         );
 
@@ -280,24 +287,24 @@ impl<'cfg> HttpRegistry<'cfg> {
                             code,
                             url: url.to_owned(),
                             body: data,
+                            retry_after: download.header_map.borrow().retry_after,
                         }
                         .into());
                     }
                 };
                 Ok((data, code))
             }) {
-                Ok(Some((data, code))) => Ok(CompletedDownload {
+                RetryResult::Success((data, code)) => Ok(CompletedDownload {
                     response_code: code,
                     data,
                     header_map: download.header_map.take(),
                 }),
-                Ok(None) => {
-                    // retry the operation
-                    let handle = self.multi.add(handle)?;
-                    self.downloads.pending.insert(token, (download, handle));
+                RetryResult::Retry(sleep) => {
+                    debug!("download retry {:?} for {sleep:?}", download.path);
+                    self.downloads.sleeping.push(sleep, (download, handle));
                     continue;
                 }
-                Err(e) => Err(e),
+                RetryResult::Err(e) => Err(e),
             };
 
             assert!(self.downloads.pending_paths.remove(&download.path));
@@ -310,6 +317,16 @@ impl<'cfg> HttpRegistry<'cfg> {
         Ok(())
     }
 
+    /// Restarts the downloads whose retry delay has elapsed.
+    fn add_sleepers(&mut self) -> CargoResult<()> {
+        for (dl, handle) in self.downloads.sleeping.to_retry() {
+            let mut handle = self.multi.add(handle)?;
+            handle.set_token(dl.token)?;
+            self.downloads.pending.insert(dl.token, (dl, handle));
+        }
+        Ok(())
+    }
+
     fn full_url(&self, path: &Path) -> String {
         // self.url always ends with a slash.
         format!("{}{}", self.url, path.display())
@@ -520,6 +537,7 @@ impl<'cfg> RegistryData for HttpRegistry<'cfg> {
                         code: 401,
                         body: result.data,
                         url: self.full_url(path),
+                        retry_after: None,
                     }
                     .into());
                     if self.auth_required {
@@ -637,6 +655,7 @@ impl<'cfg> RegistryData for HttpRegistry<'cfg> {
                             LAST_MODIFIED => header_map.last_modified = Some(value.to_string()),
                             ETAG => header_map.etag = Some(value.to_string()),
                             WWW_AUTHENTICATE => header_map.www_authenticate.push(value.to_string()),
+                            RETRY_AFTER => header_map.retry_after = parse_retry_after(value),
                             _ => {}
                         }
                     }
@@ -715,6 +734,7 @@ impl<'cfg> RegistryData for HttpRegistry<'cfg> {
 
         loop {
             self.handle_completed_downloads()?;
+            self.add_sleepers()?;
 
             let remaining_in_multi = tls::set(&self.downloads, || {
                 self.multi
@@ -723,19 +743,28 @@ impl<'cfg> RegistryData for HttpRegistry<'cfg> {
             })?;
             trace!("{} transfers remaining", remaining_in_multi);
 
-            if remaining_in_multi == 0 {
+            if remaining_in_multi + self.downloads.sleeping.len() as u32 == 0 {
                 return Ok(());
             }
 
-            // We have no more replies to provide the caller with,
-            // so we need to wait until cURL has something new for us.
-            let timeout = self
-                .multi
-                .get_timeout()?
-                .unwrap_or_else(|| Duration::new(1, 0));
-            self.multi
-                .wait(&mut [], timeout)
-                .with_context(|| "failed to wait on curl `Multi`")?;
+            if self.downloads.pending.is_empty() {
+                let delay = self.downloads.sleeping.time_to_next().unwrap();
+                debug!("sleeping main thread for {delay:?}");
+                std::thread::sleep(delay);
+            } else {
+                // We have no more replies to provide the caller with,
+                // so we need to wait until cURL has something new for us.
+                let mut timeout = self
+                    .multi
+                    .get_timeout()?
+                    .unwrap_or_else(|| Duration::new(1, 0));
+                if let Some(next) = self.downloads.sleeping.time_to_next() {
+                    timeout = timeout.min(next);
+                }
+                self.multi
+                    .wait(&mut [], timeout)
+                    .with_context(|| "failed to wait on curl `Multi`")?;
+            }
         }
     }
 }
@@ -233,9 +233,12 @@ enum MaybeIndexSummary {
 /// A parsed representation of a summary from the index.
 ///
 /// In addition to a full `Summary` we have information on whether it is `yanked`.
+#[derive(Clone)]
 pub struct IndexSummary {
     pub summary: Summary,
     pub yanked: bool,
+    /// The `rust-version` of the package, if the index records it.
+    pub rust_version: Option<InternedString>,
     /// Schema version, see [`RegistryPackage`].
     v: u32,
 }
@@ -839,6 +842,7 @@ impl IndexSummary {
             features2,
             yanked,
             links,
+            rust_version,
             v,
         } = serde_json::from_slice(line)?;
         let v = v.unwrap_or(1);
@@ -858,6 +862,7 @@ impl IndexSummary {
         Ok(IndexSummary {
             summary,
             yanked: yanked.unwrap_or(false),
+            rust_version,
             v,
         })
     }
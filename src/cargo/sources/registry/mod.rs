@@ -167,7 +167,7 @@ use std::path::{Path, PathBuf};
 use std::task::Poll;
 
 use anyhow::Context as _;
-use cargo_util::paths::exclude_from_backups_and_indexing;
+use cargo_util::paths::{self, exclude_from_backups_and_indexing};
 use flate2::read::GzDecoder;
 use log::debug;
 use semver::Version;
@@ -287,6 +287,11 @@ pub struct RegistryPackage<'a> {
     /// Added early 2018 (see <https://github.com/rust-lang/cargo/pull/4978>),
     /// can be `None` if published before then.
     links: Option<InternedString>,
+    /// The minimum supported Rust version, from `package.rust-version`.
+    ///
+    /// Only informational, Cargo doesn't use it when resolving. `None` if the
+    /// registry doesn't record it.
+    rust_version: Option<InternedString>,
     /// The schema version for this entry.
     ///
     /// If this is None, it defaults to version 1. Entries with unknown
@@ -532,11 +537,13 @@ pub enum MaybeLock {
     /// The `.crate` file is not downloaded, here's the URL to download it from.
     ///
     /// `descriptor` is just a text string to display to the user of what is
-    /// being downloaded.
+    /// being downloaded. `partial` is the file holding the data of an earlier
+    /// interrupted download, with `-Zresumable-downloads`.
     Download {
         url: String,
         descriptor: String,
         authorization: Option<String>,
+        partial: Option<PathBuf>,
     },
 }
 
@@ -546,6 +553,8 @@ mod index;
 mod local;
 mod remote;
 
+pub use self::index::IndexSummary;
+
 fn short_name(id: SourceId) -> String {
     let hash = hex::short_hash(&id);
     let ident = id.url().host_str().unwrap_or("").to_string();
@@ -603,6 +612,32 @@ impl<'cfg> RegistrySource<'cfg> {
         }
     }
 
+    /// Removes the downloaded `.crate` file and the unpacked sources of `pkg`
+    /// from the caches of the remote registry `source_id`, so that the
+    /// package is downloaded and verified again the next time it's needed.
+    pub fn evict_package(source_id: SourceId, config: &Config, pkg: PackageId) -> CargoResult<()> {
+        assert!(source_id.is_remote_registry());
+        let name = short_name(source_id);
+        let crate_file = config
+            .registry_cache_path()
+            .join(&name)
+            .join(download::filename(pkg));
+        let crate_file = config.assert_package_cache_locked(&crate_file);
+        if crate_file.exists() {
+            paths::remove_file(crate_file)?;
+        }
+        let unpacked = config.registry_source_path().join(&name).join(format!(
+            "{}-{}",
+            pkg.name(),
+            pkg.version()
+        ));
+        let unpacked = config.assert_package_cache_locked(&unpacked);
+        if unpacked.exists() {
+            paths::remove_dir_all(unpacked)?;
+        }
+        Ok(())
+    }
+
     /// Decode the configuration stored within the registry.
     ///
     /// This requires that the index has been at least checked out.
@@ -610,6 +645,39 @@ impl<'cfg> RegistrySource<'cfg> {
         self.ops.config()
     }
 
+    /// Returns the index entries of the package `name` matching `req`.
+    ///
+    /// Unlike queries through [`Source`], yanked versions are always
+    /// included.
+    pub fn summaries(
+        &mut self,
+        name: InternedString,
+        req: &OptVersionReq,
+    ) -> Poll<CargoResult<Vec<IndexSummary>>> {
+        self.index
+            .summaries(name, req, &mut *self.ops)
+            .map_ok(|summaries| summaries.cloned().collect())
+    }
+
+    /// Returns the versions of the package `name` matching `req`, along with
+    /// whether they are yanked.
+    ///
+    /// Unlike queries through [`Source`], yanked versions are always
+    /// included.
+    pub fn versions(
+        &mut self,
+        name: InternedString,
+        req: &OptVersionReq,
+    ) -> Poll<CargoResult<Vec<(Version, bool)>>> {
+        self.index
+            .summaries(name, req, &mut *self.ops)
+            .map_ok(|summaries| {
+                summaries
+                    .map(|s| (s.summary.version().clone(), s.yanked))
+                    .collect()
+            })
+    }
+
     /// Unpacks a downloaded package into a location where it's ready to be
     /// compiled.
     ///
@@ -800,10 +868,12 @@ impl<'cfg> Source for RegistrySource<'cfg> {
                 url,
                 descriptor,
                 authorization,
+                partial,
             } => Ok(MaybePackage::Download {
                 url,
                 descriptor,
                 authorization,
+                partial,
             }),
         }
     }
@@ -1,4 +1,4 @@
-use crate::core::compiler::{BuildConfig, MessageFormat, TimingOutput};
+use crate::core::compiler::{BuildConfig, MessageFormat, TimingOutput, UnusedDepsMode};
 use crate::core::resolver::CliFeatures;
 use crate::core::{Edition, Workspace};
 use crate::ops::{CompileFilter, CompileOptions, NewOptions, Packages, VersionControl};
@@ -6,6 +6,7 @@ use crate::util::important_paths::find_root_manifest_for_wd;
 use crate::util::interning::InternedString;
 use crate::util::restricted_names::is_glob_pattern;
 use crate::util::toml::{StringOrVec, TomlProfile};
+use crate::util::toolchain::{RequiredToolchain, ToolchainSource};
 use crate::util::validate_package_name;
 use crate::util::{
     print_available_benches, print_available_binaries, print_available_examples,
@@ -169,10 +170,37 @@ pub trait CommandExt: Sized {
         )
     }
 
+    fn arg_machine_profile(self) -> Self {
+        self._arg(
+            opt(
+                "machine-profile",
+                "Apply the named machine profile from config (unstable)",
+            )
+            .value_name("NAME"),
+        )
+    }
+
     fn arg_manifest_path(self) -> Self {
         self._arg(opt("manifest-path", "Path to Cargo.toml").value_name("PATH"))
     }
 
+    fn arg_lockfile(self) -> Self {
+        self._arg(
+            opt(
+                "lockfile",
+                "Use the lock file Cargo.<NAME>.lock instead of Cargo.lock (unstable)",
+            )
+            .value_name("NAME"),
+        )
+    }
+
+    fn arg_allow_patch_update(self) -> Self {
+        self._arg(flag(
+            "allow-patch-update",
+            "Allow Cargo.lock entries replaced by [patch] to be updated (unstable)",
+        ))
+    }
+
     fn arg_message_format(self) -> Self {
         self._arg(multi_opt("message-format", "FMT", "Error format"))
     }
@@ -214,6 +242,17 @@ pub trait CommandExt: Sized {
             )
             .value_name("NAME"),
         )
+        ._arg(
+            opt(
+                "template",
+                "Create the package from a template directory or git repository (unstable)",
+            )
+            .value_name("PATH|URL"),
+        )
+        ._arg(flag(
+            "member",
+            "Add the package to the members of the workspace it's in (unstable)",
+        ))
     }
 
     fn arg_index(self) -> Self {
@@ -231,6 +270,23 @@ pub trait CommandExt: Sized {
         ))
     }
 
+    fn arg_auto_required_features(self) -> Self {
+        self._arg(flag(
+            "auto-required-features",
+            "Build targets with their required features enabled instead of skipping them (unstable)",
+        ))
+    }
+
+    fn arg_require_toolchain(self) -> Self {
+        self._arg(
+            opt(
+                "require-toolchain",
+                "Fail unless the active toolchain is TOOLCHAIN (unstable)",
+            )
+            .value_name("TOOLCHAIN"),
+        )
+    }
+
     fn arg_future_incompat_report(self) -> Self {
         self._arg(flag(
             "future-incompat-report",
@@ -238,6 +294,29 @@ pub trait CommandExt: Sized {
         ))
     }
 
+    fn arg_warn_unused_dependencies(self) -> Self {
+        self._arg(
+            optional_opt(
+                "warn-unused-dependencies",
+                "Report dependencies no target used (unstable): warn (default) or fix",
+            )
+            .value_name("MODE")
+            .value_parser(["warn", "fix"])
+            .require_equals(true),
+        )
+    }
+
+    fn arg_warnings(self) -> Self {
+        self._arg(
+            opt(
+                "warnings",
+                "How to handle warnings of workspace members (unstable): warn, allow or deny",
+            )
+            .value_name("MODE")
+            .value_parser(["warn", "allow", "deny"]),
+        )
+    }
+
     fn arg_quiet(self) -> Self {
         self._arg(flag("quiet", "Do not print cargo log messages").short('q'))
     }
@@ -246,7 +325,7 @@ pub trait CommandExt: Sized {
         self._arg(
             optional_opt(
                 "timings",
-                "Timing output formats (unstable) (comma separated): html, json",
+                "Timing output formats (unstable) (comma separated): html, json, size",
             )
             .value_name("FMTS")
             .require_equals(true),
@@ -361,6 +440,9 @@ pub trait ArgMatchesExt {
         if config.cli_unstable().avoid_dev_deps {
             ws.set_require_optional_deps(false);
         }
+        if let Some(name) = config.lockfile() {
+            ws.set_lock_file_name(format!("Cargo.{}.lock", name));
+        }
         Ok(ws)
     }
 
@@ -468,6 +550,7 @@ pub trait ArgMatchesExt {
             short: false,
             ansi: false,
             render_diagnostics: false,
+            v2: false,
         };
         let two_kinds_of_msg_format_err = "cannot specify two kinds of `message-format` arguments";
         for fmt in self._values_of("message-format") {
@@ -480,6 +563,20 @@ pub trait ArgMatchesExt {
                         }
                         message_format = Some(default_json);
                     }
+                    "json-v2" => {
+                        config
+                            .cli_unstable()
+                            .fail_if_stable_opt("--message-format=json-v2", None)?;
+                        if message_format.is_some() {
+                            bail!(two_kinds_of_msg_format_err);
+                        }
+                        message_format = Some(MessageFormat::Json {
+                            short: false,
+                            ansi: false,
+                            render_diagnostics: false,
+                            v2: true,
+                        });
+                    }
                     "human" => {
                         if message_format.is_some() {
                             bail!(two_kinds_of_msg_format_err);
@@ -556,6 +653,12 @@ pub trait ArgMatchesExt {
                                 .fail_if_stable_opt("--timings=json", 7405)?;
                             TimingOutput::Json
                         }
+                        "size" => {
+                            config
+                                .cli_unstable()
+                                .fail_if_stable_opt("--timings=size", None)?;
+                            TimingOutput::Size
+                        }
                         s => bail!("invalid timings output specifier: `{}`", s),
                     };
                     build_config.timing_outputs.push(timing_output);
@@ -566,6 +669,36 @@ pub trait ArgMatchesExt {
             }
         }
 
+        if self._contains("warn-unused-dependencies") {
+            config
+                .cli_unstable()
+                .fail_if_stable_opt("--warn-unused-dependencies", None)?;
+            build_config.unused_dependencies = match self._value_of("warn-unused-dependencies") {
+                Some("fix") => Some(UnusedDepsMode::Fix),
+                _ => Some(UnusedDepsMode::Warn),
+            };
+        }
+
+        if let Some(toolchain) = self._value_of("require-toolchain") {
+            if !config.cli_unstable().required_toolchain {
+                bail!(
+                    "the `--require-toolchain` flag is unstable, \
+                     pass `-Z required-toolchain` to enable it"
+                );
+            }
+            build_config.required_toolchain = Some(RequiredToolchain {
+                spec: toolchain.parse()?,
+                source: ToolchainSource::Cli,
+            });
+        }
+
+        if let Some(warnings) = self._value_of("warnings") {
+            if !config.cli_unstable().warnings {
+                bail!("the `--warnings` flag is unstable, pass `-Z warnings` to enable it");
+            }
+            build_config.warnings = warnings.parse()?;
+        }
+
         if build_config.keep_going {
             config
                 .cli_unstable()
@@ -602,9 +735,17 @@ pub trait ArgMatchesExt {
             target_rustc_args: None,
             target_rustc_crate_types: None,
             rustdoc_document_private_items: false,
+            rustdoc_document_private_members: false,
             honor_rust_version: !self.flag("ignore-rust-version"),
+            auto_required_features: self.flag("auto-required-features"),
         };
 
+        if opts.auto_required_features {
+            config
+                .cli_unstable()
+                .fail_if_stable_opt("--auto-required-features", None)?;
+        }
+
         if let Some(ws) = workspace {
             self.check_optional_opts(ws, &opts)?;
         } else if self.is_present_with_zero_values("package") {
@@ -653,7 +794,7 @@ pub trait ArgMatchesExt {
             "none" => VersionControl::NoVcs,
             vcs => panic!("Impossible vcs: {:?}", vcs),
         });
-        NewOptions::new(
+        let mut opts = NewOptions::new(
             vcs,
             self.flag("bin"),
             self.flag("lib"),
@@ -661,7 +802,18 @@ pub trait ArgMatchesExt {
             self._value_of("name").map(|s| s.to_string()),
             self._value_of("edition").map(|s| s.to_string()),
             self.registry(config)?,
-        )
+        )?;
+        opts.template = self._value_of("template").map(|s| s.to_string());
+        if opts.template.is_some() {
+            config
+                .cli_unstable()
+                .fail_if_stable_opt("--template", None)?;
+        }
+        opts.member = self.flag("member");
+        if opts.member {
+            config.cli_unstable().fail_if_stable_opt("--member", None)?;
+        }
+        Ok(opts)
     }
 
     fn registry(&self, config: &Config) -> CargoResult<Option<String>> {
@@ -795,7 +947,14 @@ pub fn ignore_unknown<T: Default>(r: Result<T, clap::parser::MatchesError>) -> T
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 pub enum CommandInfo {
-    BuiltIn { about: Option<String> },
-    External { path: PathBuf },
-    Alias { target: StringOrVec },
+    BuiltIn {
+        about: Option<String>,
+    },
+    External {
+        path: PathBuf,
+    },
+    Alias {
+        target: StringOrVec,
+        description: Option<String>,
+    },
 }
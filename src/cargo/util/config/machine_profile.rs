@@ -0,0 +1,215 @@
+//! Support for `[machine-profiles]`, named bundles of build settings that are
+//! layered on top of the config files.
+//!
+//! A machine profile is selected with `--machine-profile` or the
+//! `build.machine-profile` config value (`CARGO_BUILD_MACHINE_PROFILE`). Its
+//! values take precedence over config files, but not over `--config` or
+//! environment variables. Command-line flags such as `--jobs` still win over
+//! all of these.
+
+use super::{Config, ConfigKey, Definition, OptValue, Value, CV};
+use crate::util::CargoResult;
+use anyhow::bail;
+use std::collections::hash_map::Entry::{Occupied, Vacant};
+use std::collections::HashMap;
+
+/// Keys allowed in a machine profile, and the config keys they are applied to.
+pub const MACHINE_PROFILE_KEYS: &[(&str, &str)] = &[
+    ("jobs", "build.jobs"),
+    ("incremental", "build.incremental"),
+    ("profile", "profile"),
+];
+
+/// A machine profile that has been applied to the config values.
+#[derive(Debug)]
+pub struct MachineProfile {
+    /// Name of the profile, the key in `[machine-profiles]`.
+    pub name: String,
+    /// Where `build.machine-profile` was set.
+    pub selected_by: Definition,
+    /// Every value the profile set, sorted by key.
+    pub entries: Vec<MachineProfileEntry>,
+}
+
+/// A single value set by a machine profile.
+#[derive(Debug)]
+pub struct MachineProfileEntry {
+    /// The config key the value was applied to, such as `build.jobs`.
+    pub key: String,
+    /// Where the machine profile value is defined.
+    pub definition: Definition,
+    /// The config file value this replaced, if any.
+    pub overrides: Option<Definition>,
+    /// The `--config` value that was kept instead of this one, if any.
+    pub shadowed_by: Option<Definition>,
+}
+
+/// Loads the selected machine profile, with its values moved to the config
+/// keys they apply to.
+///
+/// Returns `None` if no machine profile is selected, or if the unstable
+/// feature is not enabled.
+pub(super) fn load_machine_profile(
+    config: &Config,
+) -> CargoResult<Option<(Value<String>, HashMap<String, CV>)>> {
+    let selected = match config.get::<OptValue<String>>("build.machine-profile")? {
+        Some(selected) => selected,
+        None => return Ok(None),
+    };
+    if !config.cli_unstable().machine_profiles {
+        return Ok(None);
+    }
+
+    let mut key = ConfigKey::from_str("machine-profiles");
+    key.push(&selected.val);
+    let table = match config.get_cv(&key)? {
+        Some(CV::Table(table, _def)) => table,
+        Some(cv) => bail!(
+            "expected a table for `{}`, but found {} in {}",
+            key,
+            cv.desc(),
+            cv.definition()
+        ),
+        None => {
+            let available = match config.get_cv(&ConfigKey::from_str("machine-profiles"))? {
+                Some(CV::Table(profiles, _def)) => {
+                    let mut names: Vec<_> = profiles.into_keys().collect();
+                    names.sort();
+                    names
+                }
+                _ => Vec::new(),
+            };
+            let available = if available.is_empty() {
+                "no machine profiles are defined".to_string()
+            } else {
+                format!("available machine profiles: {}", available.join(", "))
+            };
+            bail!(
+                "machine profile `{}` is not defined (selected by {})\n{}",
+                selected.val,
+                selected.definition,
+                available
+            );
+        }
+    };
+
+    // Move each value to where it lives in the regular config structure, so
+    // `jobs` becomes `build.jobs` and so on.
+    let mut overlay = HashMap::new();
+    for (name, value) in table {
+        let target = match MACHINE_PROFILE_KEYS.iter().find(|(k, _)| *k == name) {
+            Some((_, target)) => *target,
+            None => bail!(
+                "unknown key `{}` in machine profile `{}` ({})\n\
+                 supported keys are: {}",
+                name,
+                selected.val,
+                value.definition(),
+                MACHINE_PROFILE_KEYS
+                    .iter()
+                    .map(|(k, _)| format!("`{}`", k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let mut parts: Vec<_> = target.split('.').collect();
+        let last = parts.pop().unwrap();
+        let mut table = &mut overlay;
+        for part in parts {
+            let entry = table
+                .entry(part.to_string())
+                .or_insert_with(|| CV::Table(HashMap::new(), value.definition().clone()));
+            table = match entry {
+                CV::Table(table, _def) => table,
+                _ => unreachable!(),
+            };
+        }
+        table.insert(last.to_string(), value);
+    }
+    Ok(Some((selected, overlay)))
+}
+
+/// Applies a machine profile loaded by [`load_machine_profile`] to `values`.
+pub(super) fn apply_machine_profile(
+    values: &mut HashMap<String, CV>,
+    selected: Value<String>,
+    overlay: HashMap<String, CV>,
+) -> CargoResult<MachineProfile> {
+    let mut entries = Vec::new();
+    overlay_values(values, overlay, "", &mut entries)?;
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(MachineProfile {
+        name: selected.val,
+        selected_by: selected.definition,
+        entries,
+    })
+}
+
+/// Recursively replaces the values in `dst` with those from `src`, keeping
+/// any value that was set with `--config`.
+fn overlay_values(
+    dst: &mut HashMap<String, CV>,
+    src: HashMap<String, CV>,
+    prefix: &str,
+    entries: &mut Vec<MachineProfileEntry>,
+) -> CargoResult<()> {
+    for (name, new) in src {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match dst.entry(name) {
+            Vacant(entry) => match new {
+                CV::Table(new, def) => {
+                    if let CV::Table(table, _def) = entry.insert(CV::Table(HashMap::new(), def)) {
+                        overlay_values(table, new, &key, entries)?;
+                    }
+                }
+                new => {
+                    entries.push(MachineProfileEntry {
+                        key,
+                        definition: new.definition().clone(),
+                        overrides: None,
+                        shadowed_by: None,
+                    });
+                    entry.insert(new);
+                }
+            },
+            Occupied(mut entry) => match (entry.get_mut(), new) {
+                (CV::Table(old, _def), CV::Table(new, _new_def)) => {
+                    overlay_values(old, new, &key, entries)?;
+                }
+                (old @ CV::Table(..), new) | (old, new @ CV::Table(..)) => {
+                    bail!(
+                        "failed to apply machine profile value `{}` from {}: \
+                         expected {}, but found {}",
+                        key,
+                        new.definition(),
+                        old.desc(),
+                        new.desc()
+                    );
+                }
+                (old, new) => {
+                    if let Definition::Cli(_) = old.definition() {
+                        entries.push(MachineProfileEntry {
+                            key,
+                            definition: new.definition().clone(),
+                            overrides: None,
+                            shadowed_by: Some(old.definition().clone()),
+                        });
+                    } else {
+                        entries.push(MachineProfileEntry {
+                            key,
+                            definition: new.definition().clone(),
+                            overrides: Some(old.definition().clone()),
+                            shadowed_by: None,
+                        });
+                        *old = new;
+                    }
+                }
+            },
+        }
+    }
+    Ok(())
+}
@@ -54,7 +54,7 @@ use std::cell::{RefCell, RefMut};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::{HashMap, HashSet};
 use std::env;
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::fs::{self, File};
 use std::io::prelude::*;
@@ -67,6 +67,7 @@ use std::time::Instant;
 
 use self::ConfigValue as CV;
 use crate::core::compiler::rustdoc::RustdocExternMap;
+use crate::core::compiler::{ScriptOutput, UpliftKind, WarningHandling};
 use crate::core::shell::Verbosity;
 use crate::core::{features, CliUnstable, Shell, SourceId, Workspace, WorkspaceRootConfig};
 use crate::ops;
@@ -92,6 +93,9 @@ pub use value::{Definition, OptValue, Value};
 mod key;
 pub use key::ConfigKey;
 
+mod machine_profile;
+pub use machine_profile::{MachineProfile, MachineProfileEntry, MACHINE_PROFILE_KEYS};
+
 mod path;
 pub use path::{ConfigRelativePath, PathAndArgs};
 
@@ -136,6 +140,50 @@ enum WhyLoad {
     FileDiscovery,
 }
 
+/// Tracks config files loaded through [`config-include`].
+///
+/// A file included more than once (for example, a shared file included by two
+/// other includes) is only loaded the first time it is reached, so its values
+/// are not merged twice. Including a file that is still being loaded is a cycle.
+///
+/// [`config-include`]: https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#config-include
+#[derive(Default)]
+struct IncludeSeen {
+    /// Every file loaded so far.
+    loaded: HashSet<PathBuf>,
+    /// The files whose includes are currently being loaded, outermost first.
+    stack: Vec<PathBuf>,
+}
+
+impl IncludeSeen {
+    /// Records that `path` is about to be loaded.
+    ///
+    /// Returns `false` if it has already been loaded.
+    fn insert(&mut self, path: &Path) -> CargoResult<bool> {
+        if self.stack.iter().any(|p| p == path) {
+            bail!(
+                "config `include` cycle detected with path `{}`",
+                path.display()
+            );
+        }
+        Ok(self.loaded.insert(path.to_path_buf()))
+    }
+
+    /// Marks the start of loading the includes of the file where `def` is
+    /// defined. Must be paired with [`IncludeSeen::pop`].
+    fn push(&mut self, def: &Definition) {
+        if let Definition::Path(p) | Definition::Cli(Some(p)) = def {
+            self.stack.push(p.clone());
+        }
+    }
+
+    fn pop(&mut self, def: &Definition) {
+        if let Definition::Path(_) | Definition::Cli(Some(_)) = def {
+            self.stack.pop();
+        }
+    }
+}
+
 /// Configuration information for cargo. This is not specific to a build, it is information
 /// relating to cargo itself.
 #[derive(Debug)]
@@ -166,6 +214,12 @@ pub struct Config {
     /// `locked` is set if we should not update lock files. If the lock file
     /// is missing, or needs to be updated, an error is produced.
     locked: bool,
+    /// `allow_patch_update` lets lock file entries which are replaced by a
+    /// `[patch]` be updated, even with `locked` or `frozen`.
+    allow_patch_update: bool,
+    /// `lockfile` is the name selected with `--lockfile`, which makes
+    /// workspaces use `Cargo.<name>.lock` instead of `Cargo.lock`.
+    lockfile: Option<String>,
     /// `offline` is set if we should never access the network, but otherwise
     /// continue operating if possible.
     offline: bool,
@@ -206,6 +260,11 @@ pub struct Config {
     doc_extern_map: LazyCell<RustdocExternMap>,
     progress_config: ProgressConfig,
     env_config: LazyCell<EnvConfig>,
+    /// The machine profile applied on top of the config files, if any.
+    machine_profile: Option<MachineProfile>,
+    /// Whether `build.machine-profile` was set with the `--machine-profile`
+    /// flag, instead of `--config`.
+    machine_profile_arg: bool,
     /// This should be false if:
     /// - this is an artifact of the rustc distribution process for "stable" or for "beta"
     /// - this is an `#[test]` that does not opt in with `enable_nightly_features`
@@ -280,6 +339,8 @@ impl Config {
             extra_verbose: false,
             frozen: false,
             locked: false,
+            allow_patch_update: false,
+            lockfile: None,
             offline: false,
             jobserver: unsafe {
                 if GLOBAL_JOBSERVER.is_null() {
@@ -308,6 +369,8 @@ impl Config {
             doc_extern_map: LazyCell::new(),
             progress_config: ProgressConfig::default(),
             env_config: LazyCell::new(),
+            machine_profile: None,
+            machine_profile_arg: false,
             nightly_features_allowed: matches!(&*features::channel(), "nightly" | "dev"),
             ws_roots: RefCell::new(HashMap::new()),
         }
@@ -386,6 +449,19 @@ impl Config {
                 .join(".rustc_info.json")
                 .into_path_unlocked()
         });
+        self.load_rustc(
+            self.get_tool("rustc", &self.build_config()?.rustc),
+            cache_location,
+        )
+    }
+
+    /// Loads the `rustc` at `path`, run through the same wrappers as the
+    /// global one.
+    ///
+    /// `cache_location` should only be the cache of the target directory for
+    /// the global `rustc`, as that cache only holds the output of one
+    /// compiler at a time.
+    pub fn load_rustc(&self, path: PathBuf, cache_location: Option<PathBuf>) -> CargoResult<Rustc> {
         let wrapper = self.maybe_get_tool("rustc_wrapper", &self.build_config()?.rustc_wrapper);
         let rustc_workspace_wrapper = self.maybe_get_tool(
             "rustc_workspace_wrapper",
@@ -393,7 +469,7 @@ impl Config {
         );
 
         Rustc::new(
-            self.get_tool("rustc", &self.build_config()?.rustc),
+            path,
             wrapper,
             rustc_workspace_wrapper,
             &self
@@ -407,6 +483,11 @@ impl Config {
             } else {
                 None
             },
+            if self.cache_rustc_info && self.cli_unstable().global_rustc_info_cache {
+                Some(self.home().join(".rustc_info-v2").into_path_unlocked())
+            } else {
+                None
+            },
         )
     }
 
@@ -523,6 +604,8 @@ impl Config {
         self.values.replace(values);
         self.merge_cli_args()?;
         self.load_unstable_flags_from_config()?;
+        self.machine_profile = None;
+        self.apply_machine_profile()?;
         Ok(())
     }
 
@@ -550,6 +633,11 @@ impl Config {
 
             Ok(Some(Filesystem::new(self.cwd.join(dir))))
         } else if let Some(val) = &self.build_config()?.target_dir {
+            if self.is_global_target_dir()? {
+                // The directory depends on the workspace, see
+                // `Workspace::target_dir`.
+                return Ok(None);
+            }
             let path = val.resolve_path(self);
 
             // Check if the target directory is set to an empty string in the config.toml file.
@@ -566,6 +654,41 @@ impl Config {
         }
     }
 
+    /// Whether `build.target-dir` is set to `"global"`, placing the outputs of
+    /// each workspace in its own directory under [`Config::global_target_dirs`]
+    /// (`-Z global-target-dir`).
+    ///
+    /// `--target-dir` and `CARGO_TARGET_DIR` take precedence over this mode.
+    pub fn is_global_target_dir(&self) -> CargoResult<bool> {
+        if !self.cli_unstable().global_target_dir
+            || self.target_dir.is_some()
+            || self.env.contains_key("CARGO_TARGET_DIR")
+        {
+            return Ok(false);
+        }
+        Ok(match &self.build_config()?.target_dir {
+            Some(val) => val.raw_value() == "global",
+            None => false,
+        })
+    }
+
+    /// The directory holding the target directories of workspaces using
+    /// `build.target-dir = "global"`.
+    ///
+    /// This is `$XDG_CACHE_HOME/cargo/targets`, or `~/.cache/cargo/targets`
+    /// if `XDG_CACHE_HOME` isn't set.
+    pub fn global_target_dirs(&self) -> CargoResult<Filesystem> {
+        let cache = match self.env.get("XDG_CACHE_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => home::home_dir()
+                .ok_or_else(|| {
+                    anyhow!("could not find the home directory for the global target directory")
+                })?
+                .join(".cache"),
+        };
+        Ok(Filesystem::new(cache.join("cargo").join("targets")))
+    }
+
     /// Get a configuration value by key.
     ///
     /// This does NOT look at environment variables. See `get_cv_with_env` for
@@ -790,7 +913,7 @@ impl Config {
         })
     }
 
-    fn string_to_path(&self, value: &str, definition: &Definition) -> PathBuf {
+    pub(crate) fn string_to_path(&self, value: &str, definition: &Definition) -> PathBuf {
         let is_path = value.contains('/') || (cfg!(windows) && value.contains('\\'));
         if is_path {
             definition.root(self).join(value)
@@ -1005,6 +1128,7 @@ impl Config {
         self.target_dir = cli_target_dir;
 
         self.load_unstable_flags_from_config()?;
+        self.apply_machine_profile()?;
 
         Ok(())
     }
@@ -1028,6 +1152,65 @@ impl Config {
         Ok(())
     }
 
+    /// Applies the machine profile selected with `build.machine-profile` on
+    /// top of the loaded config values.
+    ///
+    /// This is a no-op if a machine profile has already been applied.
+    fn apply_machine_profile(&mut self) -> CargoResult<()> {
+        if self.machine_profile.is_some() {
+            return Ok(());
+        }
+        // Without the unstable flag only a selection on the command-line
+        // matters (to report an error), so don't load config files early.
+        if !self.cli_unstable().machine_profiles {
+            if self.cli_config.is_none() {
+                return Ok(());
+            }
+            let selected = match self.cli_args_as_table()? {
+                CV::Table(mut table, _) => match table.remove("build") {
+                    Some(CV::Table(mut build, _)) => build.remove("machine-profile"),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match selected {
+                Some(cv) => match cv.definition() {
+                    Definition::Cli(Some(path)) => bail!(
+                        "`build.machine-profile` in `{}` is unstable, \
+                         pass `-Z machine-profiles` to enable it",
+                        path.display()
+                    ),
+                    _ if self.machine_profile_arg => bail!(
+                        "the `--machine-profile` flag is unstable, \
+                         pass `-Z machine-profiles` to enable it"
+                    ),
+                    _ => bail!(
+                        "`build.machine-profile` passed with `--config` is unstable, \
+                         pass `-Z machine-profiles` to enable it"
+                    ),
+                },
+                None => return Ok(()),
+            }
+        }
+        if let Some((selected, overlay)) = machine_profile::load_machine_profile(self)? {
+            let values = self.values_mut()?;
+            let profile = machine_profile::apply_machine_profile(values, selected, overlay)?;
+            self.machine_profile = Some(profile);
+        }
+        Ok(())
+    }
+
+    /// The machine profile that was applied, if any.
+    pub fn machine_profile(&self) -> Option<&MachineProfile> {
+        self.machine_profile.as_ref()
+    }
+
+    /// Records that `build.machine-profile` is set with the
+    /// `--machine-profile` flag, before calling [`Config::configure`].
+    pub fn set_machine_profile_arg(&mut self) {
+        self.machine_profile_arg = true;
+    }
+
     pub fn cli_unstable(&self) -> &CliUnstable {
         &self.unstable_flags
     }
@@ -1056,6 +1239,39 @@ impl Config {
         !self.frozen && !self.locked
     }
 
+    pub fn allow_patch_update(&self) -> bool {
+        self.allow_patch_update
+    }
+
+    pub fn set_allow_patch_update(&mut self, allow: bool) {
+        self.allow_patch_update = allow;
+    }
+
+    pub fn lockfile(&self) -> Option<&str> {
+        self.lockfile.as_deref()
+    }
+
+    pub fn set_lockfile(&mut self, name: Option<String>) {
+        self.lockfile = name;
+    }
+
+    /// The `[source-policy]` table, which is only read with `-Zsource-policy`.
+    pub fn source_policy_config(&self) -> CargoResult<Option<SourcePolicyConfig>> {
+        if !self.cli_unstable().source_policy {
+            return Ok(None);
+        }
+        self.get("source-policy")
+    }
+
+    /// The `[publish.provenance]` table, which is only read with
+    /// `-Zpublish-provenance`.
+    pub fn publish_provenance_config(&self) -> CargoResult<Option<PublishProvenanceConfig>> {
+        if !self.cli_unstable().publish_provenance {
+            return Ok(None);
+        }
+        self.get("publish.provenance")
+    }
+
     /// Loads configuration from the filesystem.
     pub fn load_values(&self) -> CargoResult<HashMap<String, ConfigValue>> {
         self.load_values_from(&self.cwd)
@@ -1066,10 +1282,13 @@ impl Config {
     /// This is primarily crafted for `cargo config` command.
     pub(crate) fn load_values_unmerged(&self) -> CargoResult<Vec<ConfigValue>> {
         let mut result = Vec::new();
-        let mut seen = HashSet::new();
+        let mut seen = IncludeSeen::default();
         let home = self.home_path.clone().into_path_unlocked();
         self.walk_tree(&self.cwd, &home, |path| {
-            let mut cv = self._load_file(path, &mut seen, false, WhyLoad::FileDiscovery)?;
+            let mut cv = match self._load_file(path, &mut seen, false, WhyLoad::FileDiscovery)? {
+                Some(cv) => cv,
+                None => return Ok(()),
+            };
             if self.cli_unstable().config_include {
                 self.load_unmerged_include(&mut cv, &mut seen, &mut result)?;
             }
@@ -1086,19 +1305,23 @@ impl Config {
     fn load_unmerged_include(
         &self,
         cv: &mut CV,
-        seen: &mut HashSet<PathBuf>,
+        seen: &mut IncludeSeen,
         output: &mut Vec<CV>,
     ) -> CargoResult<()> {
         let includes = self.include_paths(cv, false)?;
+        seen.push(cv.definition());
         for (path, abs_path, def) in includes {
-            let mut cv = self
+            let cv = self
                 ._load_file(&abs_path, seen, false, WhyLoad::FileDiscovery)
                 .with_context(|| {
                     format!("failed to load config include `{}` from `{}`", path, def)
                 })?;
-            self.load_unmerged_include(&mut cv, seen, output)?;
-            output.push(cv);
+            if let Some(mut cv) = cv {
+                self.load_unmerged_include(&mut cv, seen, output)?;
+                output.push(cv);
+            }
         }
+        seen.pop(cv.definition());
         Ok(())
     }
 
@@ -1128,7 +1351,13 @@ impl Config {
     ///
     /// This is used during config file discovery.
     fn load_file(&self, path: &Path) -> CargoResult<ConfigValue> {
-        self._load_file(path, &mut HashSet::new(), true, WhyLoad::FileDiscovery)
+        let cv = self._load_file(
+            path,
+            &mut IncludeSeen::default(),
+            true,
+            WhyLoad::FileDiscovery,
+        )?;
+        Ok(cv.expect("first file loaded is never a duplicate"))
     }
 
     /// Loads a config value from a path with options.
@@ -1136,22 +1365,21 @@ impl Config {
     /// This is actual implementation of loading a config value from a path.
     ///
     /// * `includes` determines whether to load configs from [`config-include`].
-    /// * `seen` is used to check for cyclic includes.
+    /// * `seen` is used to check for cyclic includes. Returns `None` if the
+    ///   file was already loaded.
     /// * `why_load` tells why a config is being loaded.
     ///
     /// [`config-include`]: https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#config-include
     fn _load_file(
         &self,
         path: &Path,
-        seen: &mut HashSet<PathBuf>,
+        seen: &mut IncludeSeen,
         includes: bool,
         why_load: WhyLoad,
-    ) -> CargoResult<ConfigValue> {
-        if !seen.insert(path.to_path_buf()) {
-            bail!(
-                "config `include` cycle detected with path `{}`",
-                path.display()
-            );
+    ) -> CargoResult<Option<ConfigValue>> {
+        let path = &paths::normalize_path(path);
+        if !seen.insert(path)? {
+            return Ok(None);
         }
         let contents = fs::read_to_string(path)
             .with_context(|| format!("failed to read configuration file `{}`", path.display()))?;
@@ -1169,9 +1397,9 @@ impl Config {
             )
         })?;
         if includes {
-            self.load_includes(value, seen, why_load)
+            self.load_includes(value, seen, why_load).map(Some)
         } else {
-            Ok(value)
+            Ok(Some(value))
         }
     }
 
@@ -1184,7 +1412,7 @@ impl Config {
     fn load_includes(
         &self,
         mut value: CV,
-        seen: &mut HashSet<PathBuf>,
+        seen: &mut IncludeSeen,
         why_load: WhyLoad,
     ) -> CargoResult<CV> {
         // Get the list of files to load.
@@ -1193,15 +1421,22 @@ impl Config {
         if !self.cli_unstable().config_include {
             return Ok(value);
         }
-        // Accumulate all values here.
+        // Accumulate all values here. Includes are merged left to right, so
+        // later includes override earlier ones, and the values of the file
+        // itself override all of them.
         let mut root = CV::Table(HashMap::new(), value.definition().clone());
+        seen.push(value.definition());
         for (path, abs_path, def) in includes {
             self._load_file(&abs_path, seen, true, why_load)
-                .and_then(|include| root.merge(include, true))
+                .and_then(|include| match include {
+                    Some(include) => root.merge(include, true),
+                    None => Ok(()),
+                })
                 .with_context(|| {
                     format!("failed to load config include `{}` from `{}`", path, def)
                 })?;
         }
+        seen.pop(value.definition());
         root.merge(value, true)?;
         Ok(root)
     }
@@ -1254,7 +1489,7 @@ impl Config {
             Some(cli_args) => cli_args,
             None => return Ok(loaded_args),
         };
-        let mut seen = HashSet::new();
+        let mut seen = IncludeSeen::default();
         for arg in cli_args {
             let arg_as_path = self.cwd.join(arg);
             let tmp_table = if !arg.is_empty() && arg_as_path.exists() {
@@ -1265,8 +1500,14 @@ impl Config {
                         anyhow::format_err!("config path {:?} is not utf-8", arg_as_path)
                     })?
                     .to_string();
-                self._load_file(&self.cwd().join(&str_path), &mut seen, true, WhyLoad::Cli)
-                    .with_context(|| format!("failed to load config from `{}`", str_path))?
+                let cv = self
+                    ._load_file(&self.cwd().join(&str_path), &mut seen, true, WhyLoad::Cli)
+                    .with_context(|| format!("failed to load config from `{}`", str_path))?;
+                match cv {
+                    Some(cv) => cv,
+                    // Already loaded by an earlier `--config` argument.
+                    None => continue,
+                }
             } else {
                 // We only want to allow "dotted key" (see https://toml.io/en/v1.0.0#keys)
                 // expressions followed by a value that's not an "inline table"
@@ -1364,7 +1605,7 @@ impl Config {
                     .with_context(|| format!("failed to convert --config argument `{arg}`"))?
             };
             let tmp_table = self
-                .load_includes(tmp_table, &mut HashSet::new(), WhyLoad::Cli)
+                .load_includes(tmp_table, &mut IncludeSeen::default(), WhyLoad::Cli)
                 .with_context(|| "failed to load --config include".to_string())?;
             loaded_args
                 .merge(tmp_table, true)
@@ -1402,7 +1643,7 @@ impl Config {
     /// Both 'config.toml' and 'credentials.toml' should be valid with or without extension.
     /// When both exist, we want to prefer the one without an extension for
     /// backwards compatibility, but warn the user appropriately.
-    fn get_file_path(
+    pub(crate) fn get_file_path(
         &self,
         dir: &Path,
         filename_without_extension: &str,
@@ -1442,6 +1683,24 @@ impl Config {
         }
     }
 
+    /// The config files discovered from the current directory and the home
+    /// directory, from the highest to the lowest priority.
+    ///
+    /// This doesn't include the files given with `--config`, or included with
+    /// `include`.
+    pub fn config_files(&self) -> CargoResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        self.walk_tree(
+            &self.cwd,
+            &self.home_path.clone().into_path_unlocked(),
+            |path| {
+                files.push(path.to_path_buf());
+                Ok(())
+            },
+        )?;
+        Ok(files)
+    }
+
     fn walk_tree<F>(&self, pwd: &Path, home: &Path, mut walk: F) -> CargoResult<()>
     where
         F: FnMut(&Path) -> CargoResult<()>,
@@ -1644,6 +1903,20 @@ impl Config {
             .try_borrow_with(|| self.get::<EnvConfig>("env"))
     }
 
+    /// Returns the values from the `[env]` table that are set in the
+    /// environment of processes spawned by Cargo.
+    ///
+    /// A value is only included if it is forced, or if the variable is not
+    /// already set in Cargo's own environment.
+    pub fn env_config_overrides(&self) -> CargoResult<HashMap<String, OsString>> {
+        Ok(self
+            .env_config()?
+            .iter()
+            .filter(|(key, value)| value.is_force() || env::var_os(key).is_none())
+            .map(|(key, value)| (key.clone(), value.resolve(self).into_owned()))
+            .collect())
+    }
+
     /// This is used to validate the `term` table has valid syntax.
     ///
     /// This is necessary because loading the term settings happens very
@@ -2227,6 +2500,11 @@ pub struct CargoHttpConfig {
     pub debug: Option<bool>,
     pub multiplexing: Option<bool>,
     pub ssl_version: Option<SslVersionConfig>,
+    pub retries: Option<u32>,
+    /// The delay before the first retry, in milliseconds.
+    pub retry_backoff: Option<u64>,
+    /// The maximum speed of each transfer, in bytes per second.
+    pub max_download_rate: Option<u64>,
 }
 
 #[derive(Debug, Default, Deserialize, PartialEq)]
@@ -2287,6 +2565,36 @@ pub struct CargoNetConfig {
     pub retry: Option<u32>,
     pub offline: Option<bool>,
     pub git_fetch_with_cli: Option<bool>,
+    /// The number of commits fetched of git dependencies with
+    /// `-Zgit-shallow`, where `0` fetches their full history.
+    pub git_fetch_depth: Option<u32>,
+}
+
+/// The `[source-policy]` table, which restricts the sources packages may come
+/// from with `-Zsource-policy`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SourcePolicyConfig {
+    /// The names of the allowed registries, only `crates-io` if unset.
+    pub registries: Option<Vec<String>>,
+    /// The allowed git repositories, either a host optionally followed by a
+    /// path prefix like `github.com/rust-lang`, or a URL prefix. No git
+    /// repositories are allowed if unset.
+    pub git: Option<Vec<String>>,
+}
+
+/// The `[publish.provenance]` table, which describes where and how packages
+/// are published with `-Zpublish-provenance`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PublishProvenanceConfig {
+    /// The commit the package is published from, the `HEAD` of the git
+    /// repository containing it if unset.
+    pub commit: Option<String>,
+    /// A link to the CI run publishing the package.
+    pub ci_run_url: Option<String>,
+    /// Who or what publishes the package, like the name of a CI workflow.
+    pub builder: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -2306,6 +2614,42 @@ pub struct CargoBuildConfig {
     pub rustc: Option<ConfigRelativePath>,
     pub rustdoc: Option<ConfigRelativePath>,
     pub out_dir: Option<ConfigRelativePath>,
+    pub machine_profile: Option<String>,
+    pub warnings: Option<WarningHandling>,
+    pub script_output: Option<ScriptOutput>,
+    pub std: Option<BuildStdConfig>,
+    pub executor: Option<PathAndArgs>,
+    pub uplift: Option<Vec<UpliftKind>>,
+    pub required_rust_version: Option<Value<String>>,
+    pub incremental_dir: Option<IncrementalDir>,
+}
+
+/// Configuration for `build.incremental-dir`, which decides whether the
+/// incremental compilation cache of rustc is shared by all git branches.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IncrementalDir {
+    /// A single cache in `incremental/`.
+    Shared,
+    /// A cache in `incremental/branches/<branch>/` for each branch checked
+    /// out in the git repository of the workspace.
+    PerBranch,
+}
+
+/// Configuration for `build.std`, building the standard library.
+///
+/// ```toml
+/// [build]
+/// std = { crates = ["core", "alloc"], features = ["compiler-builtins-mem"] }
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildStdConfig {
+    /// The standard library crates to build, `std` if not set.
+    pub crates: Option<Vec<String>>,
+    /// The features of the standard library, unless the `[target]` table
+    /// sets `std-features`.
+    pub features: Option<Vec<String>>,
 }
 
 /// Configuration for `build.target`.
@@ -12,6 +12,14 @@ use toml_edit::easy as toml;
 #[derive(Debug, Deserialize)]
 pub struct TargetCfgConfig {
     pub runner: OptValue<PathAndArgs>,
+    #[serde(rename = "test-runner")]
+    pub test_runner: OptValue<PathAndArgs>,
+    #[serde(rename = "bench-runner")]
+    pub bench_runner: OptValue<PathAndArgs>,
+    #[serde(rename = "example-runner")]
+    pub example_runner: OptValue<PathAndArgs>,
+    #[serde(rename = "runner-fallbacks")]
+    pub runner_fallbacks: OptValue<Vec<String>>,
     pub rustflags: OptValue<StringList>,
     // This is here just to ignore fields from normal `TargetConfig` because
     // all `[target]` tables are getting deserialized, whether they start with
@@ -35,6 +43,10 @@ pub struct TargetConfig {
     /// running its build script and instead use the given output from the
     /// config file.
     pub links_overrides: BTreeMap<String, BuildOutput>,
+    /// Command providing the sysroot to build for this target with.
+    pub sysroot_provider: OptValue<PathAndArgs>,
+    /// Features of the standard library when building it for this target.
+    pub std_features: OptValue<StringList>,
 }
 
 /// Loads all of the `target.'cfg()'` tables.
@@ -98,6 +110,8 @@ pub(super) fn load_host_triple(config: &Config, triple: &str) -> CargoResult<Tar
             rustflags: None,
             linker: None,
             links_overrides: BTreeMap::new(),
+            sysroot_provider: None,
+            std_features: None,
         })
     }
 }
@@ -117,6 +131,9 @@ fn load_config_table(config: &Config, prefix: &str) -> CargoResult<TargetConfig>
     let runner: OptValue<PathAndArgs> = config.get(&format!("{}.runner", prefix))?;
     let rustflags: OptValue<StringList> = config.get(&format!("{}.rustflags", prefix))?;
     let linker: OptValue<ConfigRelativePath> = config.get(&format!("{}.linker", prefix))?;
+    let sysroot_provider: OptValue<PathAndArgs> =
+        config.get(&format!("{}.sysroot-provider", prefix))?;
+    let std_features: OptValue<StringList> = config.get(&format!("{}.std-features", prefix))?;
     // Links do not support environment variables.
     let target_key = ConfigKey::from_str(prefix);
     let links_overrides = match config.get_table(&target_key)? {
@@ -128,6 +145,8 @@ fn load_config_table(config: &Config, prefix: &str) -> CargoResult<TargetConfig>
         rustflags,
         linker,
         links_overrides,
+        sysroot_provider,
+        std_features,
     })
 }
 
@@ -147,6 +166,8 @@ fn parse_links_overrides(
         match lib_name.as_str() {
             // `ar` is a historical thing.
             "ar" | "linker" | "runner" | "rustflags" => continue,
+            "test-runner" | "bench-runner" | "example-runner" | "runner-fallbacks" => continue,
+            "sysroot-provider" | "std-features" => continue,
             _ => {}
         }
         let mut output = BuildOutput::default();
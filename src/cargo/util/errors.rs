@@ -13,6 +13,8 @@ pub struct HttpNotSuccessful {
     pub code: u32,
     pub url: String,
     pub body: Vec<u8>,
+    /// The delay requested by the `Retry-After` header of the response.
+    pub retry_after: Option<std::time::Duration>,
 }
 
 impl fmt::Display for HttpNotSuccessful {
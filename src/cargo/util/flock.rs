@@ -175,8 +175,7 @@ impl Filesystem {
             path.as_ref(),
             OpenOptions::new().read(true).write(true).create(true),
             State::Exclusive,
-            config,
-            msg,
+            &|| blocking_status(config, msg),
         )
     }
 
@@ -197,8 +196,38 @@ impl Filesystem {
             path.as_ref(),
             OpenOptions::new().read(true),
             State::Shared,
-            config,
-            msg,
+            &|| blocking_status(config, msg),
+        )
+    }
+
+    /// Opens `path` with an exclusive lock, or a shared one if `exclusive` is
+    /// false, creating the file if it doesn't already exist.
+    ///
+    /// Instead of printing to a `Config`, `on_block` is called if the process
+    /// must block waiting for the lock, so this can be used from the threads
+    /// running the jobs of a build.
+    pub fn open_with<P>(
+        &self,
+        path: P,
+        exclusive: bool,
+        on_block: &dyn Fn() -> CargoResult<()>,
+    ) -> CargoResult<FileLock>
+    where
+        P: AsRef<Path>,
+    {
+        let state = if exclusive {
+            State::Exclusive
+        } else {
+            State::Shared
+        };
+        if let Some(parent) = self.root.join(path.as_ref()).parent() {
+            paths::create_dir_all(parent)?;
+        }
+        self.open(
+            path.as_ref(),
+            OpenOptions::new().read(true).write(true).create(true),
+            state,
+            on_block,
         )
     }
 
@@ -207,8 +236,7 @@ impl Filesystem {
         path: &Path,
         opts: &OpenOptions,
         state: State,
-        config: &Config,
-        msg: &str,
+        on_block: &dyn Fn() -> CargoResult<()>,
     ) -> CargoResult<FileLock> {
         let path = self.root.join(path);
 
@@ -228,12 +256,12 @@ impl Filesystem {
             .with_context(|| format!("failed to open: {}", path.display()))?;
         match state {
             State::Exclusive => {
-                acquire(config, msg, &path, &|| try_lock_exclusive(&f), &|| {
+                acquire(on_block, &path, &|| try_lock_exclusive(&f), &|| {
                     lock_exclusive(&f)
                 })?;
             }
             State::Shared => {
-                acquire(config, msg, &path, &|| try_lock_shared(&f), &|| {
+                acquire(on_block, &path, &|| try_lock_shared(&f), &|| {
                     lock_shared(&f)
                 })?;
             }
@@ -259,6 +287,12 @@ impl PartialEq<Filesystem> for Path {
     }
 }
 
+/// Prints the status shown while waiting for the lock on `msg`.
+fn blocking_status(config: &Config, msg: &str) -> CargoResult<()> {
+    let msg = format!("waiting for file lock on {}", msg);
+    config.shell().status_with_color("Blocking", &msg, Cyan)
+}
+
 /// Acquires a lock on a file in a "nice" manner.
 ///
 /// Almost all long-running blocking actions in Cargo have a status message
@@ -269,14 +303,13 @@ impl PartialEq<Filesystem> for Path {
 /// This function will acquire the lock on a `path`, printing out a nice message
 /// to the console if we have to wait for it. It will first attempt to use `try`
 /// to acquire a lock on the crate, and in the case of contention it will emit a
-/// status message based on `msg` to `config`'s shell, and then use `block` to
-/// block waiting to acquire a lock.
+/// status message with `on_block`, and then use `block` to block waiting to
+/// acquire a lock.
 ///
 /// Returns an error if the lock could not be acquired or if any error other
 /// than a contention error happens.
 fn acquire(
-    config: &Config,
-    msg: &str,
+    on_block: &dyn Fn() -> CargoResult<()>,
     path: &Path,
     lock_try: &dyn Fn() -> io::Result<()>,
     lock_block: &dyn Fn() -> io::Result<()>,
@@ -311,8 +344,7 @@ fn acquire(
             }
         }
     }
-    let msg = format!("waiting for file lock on {}", msg);
-    config.shell().status_with_color("Blocking", &msg, Cyan)?;
+    on_block()?;
 
     lock_block().with_context(|| format!("failed to lock file: {}", path.display()))?;
     return Ok(());
@@ -43,3 +43,15 @@ pub fn find_project_manifest_exact(pwd: &Path, file: &str) -> CargoResult<PathBu
         anyhow::bail!("Could not find `{}` in `{}`", file, pwd.display())
     }
 }
+
+/// Finds the toolchain file rustup uses in `cwd`: the first `rust-toolchain`
+/// or `rust-toolchain.toml` found in `cwd` or a parent directory. Like
+/// rustup, `rust-toolchain` is preferred if both exist.
+pub fn find_toolchain_file(cwd: &Path) -> Option<PathBuf> {
+    paths::ancestors(cwd, None).find_map(|current| {
+        ["rust-toolchain", "rust-toolchain.toml"]
+            .iter()
+            .map(|name| current.join(name))
+            .find(|path| path.is_file())
+    })
+}
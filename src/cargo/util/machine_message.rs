@@ -1,10 +1,18 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use serde::ser;
 use serde::Serialize;
 use serde_json::{self, json, value::RawValue};
 
-use crate::core::{compiler::CompileMode, PackageId, Target};
+use crate::core::compiler::{CompileMode, MessageFormat};
+use crate::core::{PackageId, Target};
+
+/// The schema of the messages emitted with `--message-format json-v2`.
+///
+/// It must be bumped whenever a field of a message is removed or changes its
+/// meaning. Adding fields, or new kinds of messages, is backwards compatible.
+pub const SCHEMA_V2: &str = "cargo-message/2";
 
 pub trait Message: ser::Serialize {
     fn reason(&self) -> &str;
@@ -15,6 +23,28 @@ pub trait Message: ser::Serialize {
         let reason = json!(self.reason());
         format!("{{\"reason\":{},{}", reason, &json[1..])
     }
+
+    /// Serializes the message for `--message-format json-v2`, where every
+    /// message starts with the schema and the version of Cargo.
+    fn to_json_v2_string(&self) -> String {
+        let json = serde_json::to_string(self).unwrap();
+        assert!(json.starts_with("{\""));
+        format!(
+            "{{\"schema\":{},\"cargo_version\":{},\"reason\":{},{}",
+            json!(SCHEMA_V2),
+            json!(crate::version().version),
+            json!(self.reason()),
+            &json[1..]
+        )
+    }
+
+    /// Serializes the message for one of the JSON message formats.
+    fn to_json_string_for(&self, format: MessageFormat) -> String {
+        match format {
+            MessageFormat::Json { v2: true, .. } => self.to_json_v2_string(),
+            _ => self.to_json_string(),
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -77,6 +107,22 @@ impl<'a> Message for BuildScript<'a> {
     }
 }
 
+/// The environment variables pointing at the outputs of artifact
+/// dependencies, which are set for a compiler or build script invocation.
+#[derive(Serialize)]
+pub struct ArtifactDepEnv<'a> {
+    pub package_id: PackageId,
+    pub target: &'a Target,
+    pub mode: CompileMode,
+    pub env: BTreeMap<String, String>,
+}
+
+impl<'a> Message for ArtifactDepEnv<'a> {
+    fn reason(&self) -> &str {
+        "artifact-dep-env"
+    }
+}
+
 #[derive(Serialize)]
 pub struct TimingInfo<'a> {
     pub package_id: PackageId,
@@ -85,6 +131,17 @@ pub struct TimingInfo<'a> {
     pub duration: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rmeta_time: Option<f64>,
+    /// The maximum resident set size of the process, in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rss: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_time: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_read: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_written: Option<u64>,
 }
 
 impl<'a> Message for TimingInfo<'a> {
@@ -93,6 +150,34 @@ impl<'a> Message for TimingInfo<'a> {
     }
 }
 
+#[derive(Serialize)]
+pub struct UnusedDependency<'a> {
+    pub package_id: PackageId,
+    pub manifest_path: &'a Path,
+    pub dependency: &'a str,
+    pub table: &'a str,
+}
+
+impl<'a> Message for UnusedDependency<'a> {
+    fn reason(&self) -> &str {
+        "unused-dependency"
+    }
+}
+
+#[derive(Serialize)]
+pub struct BuildProgress {
+    pub total: usize,
+    pub finished: usize,
+    pub active: Vec<String>,
+    pub eta_secs: Option<u64>,
+}
+
+impl Message for BuildProgress {
+    fn reason(&self) -> &str {
+        "build-progress"
+    }
+}
+
 #[derive(Serialize)]
 pub struct BuildFinished {
     pub success: bool,
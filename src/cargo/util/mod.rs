@@ -61,6 +61,7 @@ mod semver_ext;
 pub mod to_semver;
 pub mod toml;
 pub mod toml_mut;
+pub mod toolchain;
 mod vcs;
 mod workspace;
 
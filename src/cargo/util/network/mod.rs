@@ -0,0 +1,22 @@
+//! Utilities for networking.
+
+use std::task::Poll;
+
+pub mod retry;
+pub mod sleep;
+
+pub use self::retry::{with_retry, Retry, RetryResult};
+
+pub trait PollExt<T> {
+    fn expect(self, msg: &str) -> T;
+}
+
+impl<T> PollExt<T> for Poll<T> {
+    #[track_caller]
+    fn expect(self, msg: &str) -> T {
+        match self {
+            Poll::Ready(val) => val,
+            Poll::Pending => panic!("{}", msg),
+        }
+    }
+}
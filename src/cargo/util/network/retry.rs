@@ -0,0 +1,380 @@
+//! Utilities for retrying a network operation.
+//!
+//! Some network errors are considered "spurious", meaning it is not a real
+//! error (such as a 404 not found) and is likely a transient error (like a
+//! bad network connection) that we can hope will resolve itself shortly. The
+//! [`Retry`] type offers a way to repeatedly perform some kind of network
+//! operation with a delay if it detects one of these possibly transient
+//! errors.
+//!
+//! The number of retries comes from the `net.retry` config value. With
+//! `-Zhttp-retries`, `http.retries` takes precedence over it, retries are
+//! delayed with an exponential backoff starting at `http.retry-backoff`
+//! milliseconds, responses with the status 429 (Too Many Requests) are
+//! retried, and their `Retry-After` header is honored.
+
+use anyhow::Error;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::util::errors::{CargoResult, HttpNotSuccessful};
+use crate::util::Config;
+
+/// State for managing retrying a network operation.
+pub struct Retry<'a> {
+    config: &'a Config,
+    /// The number of failed attempts that have been done so far.
+    retries: u32,
+    /// The maximum number of times the operation should be retried.
+    max_retries: u32,
+    /// The delay before the first retry, or `None` if retries aren't
+    /// delayed, which is the case without `-Zhttp-retries`.
+    backoff: Option<Duration>,
+    /// Whether the operation can be repeated without changing its outcome.
+    ///
+    /// Operations which aren't, like publishing a crate, are only retried
+    /// when the request certainly didn't reach the server.
+    idempotent: bool,
+}
+
+/// The result of attempting some operation via [`Retry::try`].
+pub enum RetryResult<T> {
+    /// The operation was successful.
+    ///
+    /// The wrapped value is the return value of the callback function.
+    Success(T),
+    /// The operation was an error, and it should not be tried again.
+    Err(anyhow::Error),
+    /// The operation failed, and should be tried again in the future.
+    ///
+    /// The wrapped value is how long to wait before trying again.
+    Retry(Duration),
+}
+
+/// The maximum delay between two retries computed from the backoff.
+const MAX_RETRY_SLEEP: Duration = Duration::from_secs(10);
+
+/// The longest `Retry-After` a server may ask for before Cargo gives up
+/// instead of waiting.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+impl<'a> Retry<'a> {
+    pub fn new(config: &'a Config) -> CargoResult<Retry<'a>> {
+        let net_retry = config.net_config()?.retry;
+        let (max_retries, backoff) = if config.cli_unstable().http_retries {
+            let http = config.http_config()?;
+            (
+                http.retries.or(net_retry).unwrap_or(2),
+                Some(Duration::from_millis(http.retry_backoff.unwrap_or(500))),
+            )
+        } else {
+            (net_retry.unwrap_or(2), None)
+        };
+        Ok(Retry {
+            config,
+            retries: 0,
+            max_retries,
+            backoff,
+            idempotent: true,
+        })
+    }
+
+    /// Only retries on errors which guarantee that the request wasn't
+    /// processed by the server, for operations which can't be repeated.
+    pub fn non_idempotent(mut self) -> Retry<'a> {
+        self.idempotent = false;
+        self
+    }
+
+    /// Calls `callback` until it succeeds or fails with an error which
+    /// shouldn't be retried, sleeping between the attempts.
+    pub fn run<T>(mut self, mut callback: impl FnMut() -> CargoResult<T>) -> CargoResult<T> {
+        loop {
+            match self.r#try(&mut callback) {
+                RetryResult::Success(r) => return Ok(r),
+                RetryResult::Err(e) => return Err(e),
+                RetryResult::Retry(delay) => std::thread::sleep(delay),
+            }
+        }
+    }
+
+    /// Calls the given callback, and returns a [`RetryResult`] which
+    /// indicates whether or not this needs to be called again at some point
+    /// in the future to retry the operation if it failed.
+    pub fn r#try<T>(&mut self, f: impl FnOnce() -> CargoResult<T>) -> RetryResult<T> {
+        match f() {
+            Err(e) if self.retries < self.max_retries => {
+                let delay = match self.spurious(&e) {
+                    Some(delay) => delay,
+                    None => return RetryResult::Err(e),
+                };
+                let msg = format!(
+                    "spurious network error ({} tries remaining): {}",
+                    self.max_retries - self.retries,
+                    e.root_cause(),
+                );
+                if let Err(e) = self.config.shell().warn(msg) {
+                    return RetryResult::Err(e);
+                }
+                self.retries += 1;
+                RetryResult::Retry(delay)
+            }
+            Err(e) => RetryResult::Err(e),
+            Ok(r) => RetryResult::Success(r),
+        }
+    }
+
+    /// Returns how long to wait before retrying after the error `err`, or
+    /// `None` if it shouldn't be retried.
+    fn spurious(&self, err: &Error) -> Option<Duration> {
+        let backoff = match self.backoff {
+            Some(backoff) => backoff,
+            None => return (self.idempotent && maybe_spurious(err)).then(|| Duration::ZERO),
+        };
+        let retry_after = if is_rate_limited(err) {
+            match retry_after(err) {
+                Some(after) if after > MAX_RETRY_AFTER => return None,
+                after => after,
+            }
+        } else if self.idempotent && maybe_spurious(err) || is_connect_error(err) {
+            None
+        } else {
+            return None;
+        };
+        Some(retry_after.unwrap_or_else(|| {
+            let exponential = backoff.saturating_mul(2u32.saturating_pow(self.retries));
+            let jitter = backoff.mul_f64(random_fraction());
+            (exponential + jitter).min(MAX_RETRY_SLEEP)
+        }))
+    }
+}
+
+/// A random number in `[0, 1)`, to spread retries of concurrent requests.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn maybe_spurious(err: &Error) -> bool {
+    if let Some(git_err) = err.downcast_ref::<git2::Error>() {
+        match git_err.class() {
+            git2::ErrorClass::Net
+            | git2::ErrorClass::Os
+            | git2::ErrorClass::Zlib
+            | git2::ErrorClass::Http => return true,
+            _ => (),
+        }
+    }
+    if let Some(curl_err) = curl_error(err) {
+        if curl_err.is_couldnt_connect()
+            || curl_err.is_couldnt_resolve_proxy()
+            || curl_err.is_couldnt_resolve_host()
+            || curl_err.is_operation_timedout()
+            || curl_err.is_recv_error()
+            || curl_err.is_send_error()
+            || curl_err.is_http2_error()
+            || curl_err.is_http2_stream_error()
+            || curl_err.is_ssl_connect_error()
+            || curl_err.is_partial_file()
+        {
+            return true;
+        }
+    }
+    if let Some(code) = http_code(err) {
+        if 500 <= code && code < 600 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Whether `err` is a failure to connect to the server, meaning that the
+/// request was never received.
+fn is_connect_error(err: &Error) -> bool {
+    curl_error(err).map_or(false, |e| {
+        e.is_couldnt_connect() || e.is_couldnt_resolve_proxy() || e.is_couldnt_resolve_host()
+    })
+}
+
+/// Whether `err` is a response with the status 429 (Too Many Requests).
+fn is_rate_limited(err: &Error) -> bool {
+    http_code(err) == Some(429)
+}
+
+fn curl_error(err: &Error) -> Option<&curl::Error> {
+    err.chain().find_map(|e| {
+        e.downcast_ref::<curl::Error>().or_else(|| {
+            match e.downcast_ref::<crates_io::ResponseError>() {
+                Some(crates_io::ResponseError::Curl(e)) => Some(e),
+                _ => None,
+            }
+        })
+    })
+}
+
+/// The status code of an unsuccessful HTTP response.
+fn http_code(err: &Error) -> Option<u32> {
+    err.chain().find_map(|e| {
+        if let Some(not_200) = e.downcast_ref::<HttpNotSuccessful>() {
+            return Some(not_200.code);
+        }
+        match e.downcast_ref::<crates_io::ResponseError>() {
+            Some(crates_io::ResponseError::Code { code, .. })
+            | Some(crates_io::ResponseError::Api { code, .. }) => Some(*code),
+            _ => None,
+        }
+    })
+}
+
+/// The delay requested by the `Retry-After` header of an unsuccessful HTTP
+/// response.
+///
+/// Only a number of seconds is supported, not an HTTP date.
+fn retry_after(err: &Error) -> Option<Duration> {
+    err.chain().find_map(|e| {
+        if let Some(not_200) = e.downcast_ref::<HttpNotSuccessful>() {
+            return not_200.retry_after;
+        }
+        match e.downcast_ref::<crates_io::ResponseError>() {
+            Some(crates_io::ResponseError::Code { headers, .. }) => headers.iter().find_map(|h| {
+                let (name, value) = h.split_once(':')?;
+                if name.trim().eq_ignore_ascii_case("retry-after") {
+                    parse_retry_after(value)
+                } else {
+                    None
+                }
+            }),
+            _ => None,
+        }
+    })
+}
+
+/// Parses the value of a `Retry-After` header given in seconds.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Wrapper method for network call retry logic.
+///
+/// Retry counts provided by Config object `net.retry`. Config shell outputs
+/// a warning on per retry, and the thread sleeps between retries with
+/// `-Zhttp-retries`.
+///
+/// Closure must return a `CargoResult`.
+///
+/// # Examples
+///
+/// ```
+/// # use crate::cargo::util::{CargoResult, Config};
+/// # let download_something = || return Ok(());
+/// # let config = Config::default().unwrap();
+/// use cargo::util::network;
+/// let cargo_result = network::with_retry(&config, || download_something());
+/// ```
+pub fn with_retry<T, F>(config: &Config, mut callback: F) -> CargoResult<T>
+where
+    F: FnMut() -> CargoResult<T>,
+{
+    Retry::new(config)?.run(&mut callback)
+}
+
+#[test]
+fn with_retry_repeats_the_call_then_works() {
+    use crate::core::Shell;
+
+    //Error HTTP codes (5xx) are considered maybe_spurious and will prompt retry
+    let error1 = HttpNotSuccessful {
+        code: 501,
+        url: "Uri".to_string(),
+        body: Vec::new(),
+        retry_after: None,
+    }
+    .into();
+    let error2 = HttpNotSuccessful {
+        code: 502,
+        url: "Uri".to_string(),
+        body: Vec::new(),
+        retry_after: None,
+    }
+    .into();
+    let mut results: Vec<CargoResult<()>> = vec![Ok(()), Err(error1), Err(error2)];
+    let config = Config::default().unwrap();
+    *config.shell() = Shell::from_write(Box::new(Vec::new()));
+    let result = with_retry(&config, || results.pop().unwrap());
+    assert!(result.is_ok())
+}
+
+#[test]
+fn with_retry_finds_nested_spurious_errors() {
+    use crate::core::Shell;
+
+    //Error HTTP codes (5xx) are considered maybe_spurious and will prompt retry
+    //String error messages are not considered spurious
+    let error1 = anyhow::Error::from(HttpNotSuccessful {
+        code: 501,
+        url: "Uri".to_string(),
+        body: Vec::new(),
+        retry_after: None,
+    });
+    let error1 = anyhow::Error::from(error1.context("A non-spurious wrapping err"));
+    let error2 = anyhow::Error::from(HttpNotSuccessful {
+        code: 502,
+        url: "Uri".to_string(),
+        body: Vec::new(),
+        retry_after: None,
+    });
+    let error2 = anyhow::Error::from(error2.context("A second chained error"));
+    let mut results: Vec<CargoResult<()>> = vec![Ok(()), Err(error1), Err(error2)];
+    let config = Config::default().unwrap();
+    *config.shell() = Shell::from_write(Box::new(Vec::new()));
+    let result = with_retry(&config, || results.pop().unwrap());
+    assert!(result.is_ok())
+}
+
+#[test]
+fn curle_http2_stream_is_spurious() {
+    let code = curl_sys::CURLE_HTTP2_STREAM;
+    let err = curl::Error::new(code);
+    assert!(maybe_spurious(&err.into()));
+}
+
+#[test]
+fn backoff_grows_and_honors_retry_after() {
+    let config = Config::default().unwrap();
+    let mut retry = Retry {
+        config: &config,
+        retries: 0,
+        max_retries: 3,
+        backoff: Some(Duration::from_millis(100)),
+        idempotent: true,
+    };
+    let error = |code, retry_after| {
+        anyhow::Error::from(HttpNotSuccessful {
+            code,
+            url: "Uri".to_string(),
+            body: Vec::new(),
+            retry_after,
+        })
+    };
+
+    let first = retry.spurious(&error(503, None)).unwrap();
+    assert!(first >= Duration::from_millis(100) && first < Duration::from_millis(200));
+    retry.retries = 2;
+    let third = retry.spurious(&error(503, None)).unwrap();
+    assert!(third >= Duration::from_millis(400) && third < Duration::from_millis(500));
+
+    let after = Some(Duration::from_secs(3));
+    assert_eq!(retry.spurious(&error(429, after)), after);
+    assert_eq!(
+        retry.spurious(&error(429, Some(Duration::from_secs(3600)))),
+        None
+    );
+
+    // Only failures to connect are retried for non-idempotent operations.
+    let mut retry = retry.non_idempotent();
+    retry.retries = 0;
+    assert_eq!(retry.spurious(&error(503, None)), None);
+    let err = curl::Error::new(curl_sys::CURLE_COULDNT_CONNECT);
+    assert!(retry.spurious(&err.into()).is_some());
+}
@@ -1,4 +1,5 @@
 use std::collections::hash_map::HashMap;
+use std::collections::HashSet;
 use std::env;
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
@@ -37,12 +38,17 @@ impl Rustc {
     ///
     /// If successful this function returns a description of the compiler along
     /// with a list of its capabilities.
+    ///
+    /// The outputs of rustc are cached in `cache_location`, and also in the
+    /// directory `global_cache_location` shared with other workspaces if
+    /// `Some`, see [`GlobalCache`].
     pub fn new(
         path: PathBuf,
         wrapper: Option<PathBuf>,
         workspace_wrapper: Option<PathBuf>,
         rustup_rustc: &Path,
         cache_location: Option<PathBuf>,
+        global_cache_location: Option<PathBuf>,
     ) -> CargoResult<Rustc> {
         let _p = profile::start("Rustc::new");
 
@@ -52,6 +58,7 @@ impl Rustc {
             &path,
             rustup_rustc,
             cache_location,
+            global_cache_location,
         );
 
         let mut cmd = ProcessBuilder::new(&path);
@@ -148,6 +155,7 @@ struct Cache {
     cache_location: Option<PathBuf>,
     dirty: bool,
     data: CacheData,
+    global: Option<GlobalCache>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -157,7 +165,7 @@ struct CacheData {
     successes: HashMap<u64, bool>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct Output {
     success: bool,
     status: String,
@@ -173,56 +181,78 @@ impl Cache {
         rustc: &Path,
         rustup_rustc: &Path,
         cache_location: Option<PathBuf>,
+        global_cache_location: Option<PathBuf>,
     ) -> Cache {
-        match (
-            cache_location,
-            rustc_fingerprint(wrapper, workspace_wrapper, rustc, rustup_rustc),
-        ) {
-            (Some(cache_location), Ok(rustc_fingerprint)) => {
-                let empty = CacheData {
-                    rustc_fingerprint,
-                    outputs: HashMap::new(),
-                    successes: HashMap::new(),
-                };
-                let mut dirty = true;
-                let data = match read(&cache_location) {
-                    Ok(data) => {
-                        if data.rustc_fingerprint == rustc_fingerprint {
-                            debug!("reusing existing rustc info cache");
-                            dirty = false;
-                            data
-                        } else {
-                            debug!("different compiler, creating new rustc info cache");
-                            empty
-                        }
-                    }
-                    Err(e) => {
-                        debug!("failed to read rustc info cache: {}", e);
-                        empty
+        let rustc_fingerprint =
+            match rustc_fingerprint(wrapper, workspace_wrapper, rustc, rustup_rustc) {
+                Ok(rustc_fingerprint)
+                    if cache_location.is_some() || global_cache_location.is_some() =>
+                {
+                    rustc_fingerprint
+                }
+                fingerprint => {
+                    if let Err(e) = fingerprint {
+                        warn!("failed to calculate rustc fingerprint: {}", e);
                     }
-                };
-                return Cache {
-                    cache_location: Some(cache_location),
-                    dirty,
-                    data,
-                };
-
-                fn read(path: &Path) -> CargoResult<CacheData> {
-                    let json = paths::read(path)?;
-                    Ok(serde_json::from_str(&json)?)
+                    debug!("rustc info cache disabled");
+                    return Cache {
+                        cache_location: None,
+                        dirty: false,
+                        data: CacheData::default(),
+                        global: None,
+                    };
                 }
-            }
-            (_, fingerprint) => {
-                if let Err(e) = fingerprint {
-                    warn!("failed to calculate rustc fingerprint: {}", e);
+            };
+        let global = global_cache_location.and_then(|root| {
+            match rustc_id(wrapper, workspace_wrapper, rustc) {
+                Ok(id) => Some(GlobalCache::new(&root, id, rustc_fingerprint)),
+                Err(e) => {
+                    warn!(
+                        "failed to identify rustc for the global rustc info cache: {}",
+                        e
+                    );
+                    None
                 }
-                debug!("rustc info cache disabled");
-                Cache {
+            }
+        });
+        let empty = CacheData {
+            rustc_fingerprint,
+            outputs: HashMap::new(),
+            successes: HashMap::new(),
+        };
+        let cache_location = match cache_location {
+            Some(cache_location) => cache_location,
+            None => {
+                return Cache {
                     cache_location: None,
                     dirty: false,
-                    data: CacheData::default(),
+                    data: empty,
+                    global,
                 }
             }
+        };
+        let mut dirty = true;
+        let data = match read(&cache_location) {
+            Ok(data) => {
+                if data.rustc_fingerprint == rustc_fingerprint {
+                    debug!("reusing existing rustc info cache");
+                    dirty = false;
+                    data
+                } else {
+                    debug!("different compiler, creating new rustc info cache");
+                    empty
+                }
+            }
+            Err(e) => {
+                debug!("failed to read rustc info cache: {}", e);
+                empty
+            }
+        };
+        Cache {
+            cache_location: Some(cache_location),
+            dirty,
+            data,
+            global,
         }
     }
 
@@ -236,6 +266,15 @@ impl Cache {
             debug!("rustc info cache hit");
         } else {
             debug!("rustc info cache miss");
+            if let Some(global) = &mut self.global {
+                if let Some(output) = global.data(&target_key(cmd)).outputs.get(&key) {
+                    debug!("global rustc info cache hit");
+                    self.data.outputs.insert(key, output.clone());
+                    self.dirty = true;
+                }
+            }
+        }
+        if !self.data.outputs.contains_key(&key) {
             debug!("running {}", cmd);
             let output = cmd.output()?;
             let stdout = String::from_utf8(output.stdout)
@@ -259,6 +298,9 @@ impl Cache {
                 },
             );
             self.dirty = true;
+            if let Some(global) = &mut self.global {
+                global.insert(&target_key(cmd), key, self.data.outputs[&key].clone());
+            }
         }
         let output = &self.data.outputs[&key];
         if output.success {
@@ -291,6 +333,158 @@ impl Drop for Cache {
     }
 }
 
+fn read(path: &Path) -> CargoResult<CacheData> {
+    let json = paths::read(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// A second level of [`Cache`] in `$CARGO_HOME/.rustc_info-v2`, shared by
+/// all workspaces, so that a new target directory doesn't need to query rustc
+/// for every target again. Enabled with `-Z global-rustc-info-cache`.
+///
+/// The outputs are stored in a file per target in a directory per compiler,
+/// `<rustc id>/<target>.json`. The id of a compiler only depends on its path
+/// and rustup toolchain, see [`rustc_id`], while each file records the
+/// fingerprint of the compiler which produced its outputs. When the compiler
+/// changes, for example after `rustup update`, the whole directory is
+/// removed rather than keeping outputs that will never be used again.
+#[derive(Debug)]
+struct GlobalCache {
+    dir: PathBuf,
+    rustc_fingerprint: u64,
+    /// The outputs of each target which was looked up, read lazily.
+    targets: HashMap<String, CacheData>,
+    /// The targets with new outputs, written back on drop.
+    dirty: HashSet<String>,
+}
+
+impl GlobalCache {
+    fn new(root: &Path, rustc_id: u64, rustc_fingerprint: u64) -> GlobalCache {
+        GlobalCache {
+            dir: root.join(format!("{:016x}", rustc_id)),
+            rustc_fingerprint,
+            targets: HashMap::new(),
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// The outputs of `target`, read from disk the first time.
+    fn data(&mut self, target: &str) -> &mut CacheData {
+        if !self.targets.contains_key(target) {
+            let path = self.dir.join(format!("{}.json", target));
+            let data = match read(&path) {
+                Ok(data) if data.rustc_fingerprint == self.rustc_fingerprint => {
+                    debug!("reusing global rustc info cache for `{}`", target);
+                    data
+                }
+                Ok(_) => {
+                    debug!("different compiler, invalidating global rustc info cache");
+                    if let Err(e) = paths::remove_dir_all(&self.dir) {
+                        warn!("failed to remove global rustc info cache: {}", e);
+                    }
+                    // What was read before is still valid, but its file is
+                    // gone now.
+                    self.dirty.extend(self.targets.keys().cloned());
+                    CacheData {
+                        rustc_fingerprint: self.rustc_fingerprint,
+                        ..CacheData::default()
+                    }
+                }
+                Err(e) => {
+                    debug!("failed to read global rustc info cache: {}", e);
+                    CacheData {
+                        rustc_fingerprint: self.rustc_fingerprint,
+                        ..CacheData::default()
+                    }
+                }
+            };
+            self.targets.insert(target.to_string(), data);
+        }
+        self.targets.get_mut(target).unwrap()
+    }
+
+    fn insert(&mut self, target: &str, key: u64, output: Output) {
+        self.data(target).outputs.insert(key, output);
+        self.dirty.insert(target.to_string());
+    }
+
+    fn write(&self, target: &str) -> CargoResult<()> {
+        paths::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string(&self.targets[target])?;
+        // Other Cargo processes may read the file at the same time, so it's
+        // replaced at once rather than written in place.
+        let mut tmp = tempfile::NamedTempFile::new_in(&self.dir)?;
+        std::io::Write::write_all(&mut tmp, json.as_bytes())?;
+        tmp.persist(self.dir.join(format!("{}.json", target)))?;
+        Ok(())
+    }
+}
+
+impl Drop for GlobalCache {
+    fn drop(&mut self) {
+        for target in &self.dirty {
+            match self.write(target) {
+                Ok(()) => info!("updated global rustc info cache for `{}`", target),
+                Err(e) => warn!("failed to update global rustc info cache: {}", e),
+            }
+        }
+    }
+}
+
+/// The name of the file of [`GlobalCache`] holding the output of `cmd`,
+/// after the target passed to rustc with `--target`, or `host` without.
+///
+/// Custom targets, which are paths to a JSON file, are named after the hash
+/// of their path.
+fn target_key(cmd: &ProcessBuilder) -> String {
+    let mut args = cmd.get_args().map(|arg| arg.to_string_lossy());
+    let mut target = None;
+    while let Some(arg) = args.next() {
+        if arg == "--target" {
+            target = args.next();
+        } else if let Some(value) = arg.strip_prefix("--target=") {
+            target = Some(value.to_string().into());
+        }
+    }
+    match target {
+        None => "host".to_string(),
+        Some(target)
+            if !target.ends_with(".json")
+                && target
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') =>
+        {
+            target.into_owned()
+        }
+        Some(target) => {
+            let mut hasher = StableHasher::new();
+            target.hash(&mut hasher);
+            format!("custom-{:016x}", hasher.finish())
+        }
+    }
+}
+
+/// Identifies a compiler for [`GlobalCache`]: like [`rustc_fingerprint`],
+/// but without the modification times, which change when the toolchain is
+/// updated in place.
+fn rustc_id(
+    wrapper: Option<&Path>,
+    workspace_wrapper: Option<&Path>,
+    rustc: &Path,
+) -> CargoResult<u64> {
+    let mut hasher = StableHasher::new();
+    paths::resolve_executable(rustc)?.hash(&mut hasher);
+    if let Some(wrapper) = wrapper {
+        paths::resolve_executable(wrapper)?.hash(&mut hasher);
+    }
+    if let Some(workspace_wrapper) = workspace_wrapper {
+        paths::resolve_executable(workspace_wrapper)?.hash(&mut hasher);
+    }
+    env::var("RUSTUP_HOME").ok().hash(&mut hasher);
+    env::var("RUSTUP_TOOLCHAIN").ok().hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
 fn rustc_fingerprint(
     wrapper: Option<&Path>,
     workspace_wrapper: Option<&Path>,
@@ -90,6 +90,15 @@ impl OptVersionReq {
             }
         }
     }
+
+    /// Whether `version` matches the requirement as it's written, even if it
+    /// has been locked to the version in `Cargo.lock` since.
+    pub fn matches_unlocked(&self, version: &Version) -> bool {
+        match self {
+            OptVersionReq::Any => true,
+            OptVersionReq::Req(req) | OptVersionReq::Locked(_, req) => req.matches(version),
+        }
+    }
 }
 
 impl Display for OptVersionReq {
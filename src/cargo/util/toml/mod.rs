@@ -17,9 +17,9 @@ use serde::{Deserialize, Serialize};
 use toml_edit::easy as toml;
 use url::Url;
 
-use crate::core::compiler::{CompileKind, CompileTarget};
+use crate::core::compiler::{BuildOutput, CompileKind, CompileTarget, LinkType};
 use crate::core::dependency::{Artifact, ArtifactTarget, DepKind};
-use crate::core::manifest::{ManifestMetadata, TargetSourcePath, Warnings};
+use crate::core::manifest::{LinksOverride, ManifestMetadata, TargetSourcePath, Warnings};
 use crate::core::resolver::ResolveBehavior;
 use crate::core::{
     find_workspace_root, resolve_relative_path, Dependency, Manifest, PackageId, Summary, Target,
@@ -296,6 +296,8 @@ impl<'de, P: Deserialize<'de> + Clone> de::Deserialize<'de> for TomlDependency<P
                         artifact: details.artifact,
                         lib: details.lib,
                         target: details.target,
+                        artifact_reexport: details.artifact_reexport,
+                        artifact_env: details.artifact_env,
                     }))
                 }
             }
@@ -345,6 +347,8 @@ pub struct IntermediateDependency<P = String> {
     artifact: Option<StringOrVec>,
     lib: Option<bool>,
     target: Option<String>,
+    artifact_reexport: Option<bool>,
+    artifact_env: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -387,6 +391,12 @@ pub struct DetailedTomlDependency<P: Clone = String> {
     lib: Option<bool>,
     /// A platform name, like `x86_64-apple-darwin`
     target: Option<String>,
+    /// If set, the artifacts are also available to dependents of this
+    /// package which use `artifact-env = "transitive"`
+    artifact_reexport: Option<bool>,
+    /// Either `direct` or `transitive`, to also receive the artifacts the
+    /// dependency re-exports
+    artifact_env: Option<String>,
 }
 
 // Explicit implementation so we avoid pulling in P: Default
@@ -410,6 +420,8 @@ impl<P: Clone> Default for DetailedTomlDependency<P> {
             artifact: Default::default(),
             lib: Default::default(),
             target: Default::default(),
+            artifact_reexport: Default::default(),
+            artifact_env: Default::default(),
         }
     }
 }
@@ -434,14 +446,141 @@ pub struct TomlManifest {
     build_dependencies: Option<BTreeMap<String, TomlDependency>>,
     #[serde(rename = "build_dependencies")]
     build_dependencies2: Option<BTreeMap<String, TomlDependency>>,
-    features: Option<BTreeMap<InternedString, Vec<InternedString>>>,
+    features: Option<TomlFeatures>,
     target: Option<BTreeMap<String, TomlPlatform>>,
     replace: Option<BTreeMap<String, TomlDependency>>,
     patch: Option<BTreeMap<String, BTreeMap<String, TomlDependency>>>,
+    links_overrides: Option<BTreeMap<String, BTreeMap<String, toml::Value>>>,
     workspace: Option<TomlWorkspace>,
     badges: Option<MaybeWorkspace<BTreeMap<String, BTreeMap<String, String>>>>,
 }
 
+/// The `[features]` table.
+///
+/// A `metadata` entry which is a table is not a feature but holds
+/// [`TomlFeaturesMetadata`], a `metadata` entry which is a list is still a
+/// regular feature.
+#[derive(Clone, Debug, Default)]
+pub struct TomlFeatures {
+    features: BTreeMap<InternedString, Vec<InternedString>>,
+    metadata: Option<TomlFeaturesMetadata>,
+}
+
+/// The `[features.metadata]` table, requires the `feature-conflicts`
+/// cargo feature.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct TomlFeaturesMetadata {
+    /// Sets of features of which at most one may be enabled at a time.
+    conflicts: Option<Vec<Vec<InternedString>>>,
+}
+
+impl TomlFeaturesMetadata {
+    pub fn conflicts(&self) -> &[Vec<InternedString>] {
+        self.conflicts.as_deref().unwrap_or_default()
+    }
+}
+
+enum TomlFeatureOrMetadata {
+    Feature(Vec<InternedString>),
+    Metadata(TomlFeaturesMetadata),
+}
+
+impl<'de> de::Deserialize<'de> for TomlFeatureOrMetadata {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = TomlFeatureOrMetadata;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a list of features or a table")
+            }
+
+            fn visit_seq<V>(self, v: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::SeqAccess<'de>,
+            {
+                let seq = de::value::SeqAccessDeserializer::new(v);
+                Vec::deserialize(seq).map(TomlFeatureOrMetadata::Feature)
+            }
+
+            fn visit_map<V>(self, v: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::MapAccess<'de>,
+            {
+                let map = de::value::MapAccessDeserializer::new(v);
+                TomlFeaturesMetadata::deserialize(map).map(TomlFeatureOrMetadata::Metadata)
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for TomlFeatures {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = TomlFeatures;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a table of features")
+            }
+
+            fn visit_map<V>(self, mut v: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::MapAccess<'de>,
+            {
+                let mut features = TomlFeatures::default();
+                while let Some(name) = v.next_key::<InternedString>()? {
+                    if name == "metadata" {
+                        match v.next_value()? {
+                            TomlFeatureOrMetadata::Feature(fvs) => {
+                                features.features.insert(name, fvs);
+                            }
+                            TomlFeatureOrMetadata::Metadata(metadata) => {
+                                features.metadata = Some(metadata);
+                            }
+                        }
+                    } else {
+                        features.features.insert(name, v.next_value()?);
+                    }
+                }
+                Ok(features)
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+impl ser::Serialize for TomlFeatures {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        use ser::SerializeMap;
+
+        let len = self.features.len() + usize::from(self.metadata.is_some());
+        let mut map = serializer.serialize_map(Some(len))?;
+        for (name, fvs) in &self.features {
+            map.serialize_entry(name, fvs)?;
+        }
+        if let Some(metadata) = &self.metadata {
+            map.serialize_entry("metadata", metadata)?;
+        }
+        map.end()
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, Default)]
 pub struct TomlProfiles(BTreeMap<InternedString, TomlProfile>);
 
@@ -544,9 +683,14 @@ pub struct TomlProfile {
     pub strip: Option<StringOrBool>,
     // Note that `rustflags` is used for the cargo-feature `profile_rustflags`
     pub rustflags: Option<Vec<InternedString>>,
+    // Note that `rustc` is used for the cargo-feature `profile_rustc`
+    pub rustc: Option<InternedString>,
     // These two fields must be last because they are sub-tables, and TOML
     // requires all non-tables to be listed first.
     pub package: Option<BTreeMap<ProfilePackageSpec, TomlProfile>>,
+    // Note that `registry-packages` is used for the cargo-feature
+    // `profile-registry-packages`
+    pub registry_packages: Option<Box<TomlProfile>>,
     pub build_override: Option<Box<TomlProfile>>,
 }
 
@@ -608,6 +752,11 @@ impl TomlProfile {
                 profile.validate_profile(&format!("{name}.package.{override_name}"), features)?;
             }
         }
+        if let Some(ref profile) = self.registry_packages {
+            features.require(Feature::profile_registry_packages())?;
+            profile.validate_override("registry-packages")?;
+            profile.validate_profile(&format!("{name}.registry-packages"), features)?;
+        }
 
         // Profile name validation
         Self::validate_name(name)?;
@@ -753,9 +902,13 @@ impl TomlProfile {
     fn validate_profile(&self, name: &str, features: &Features) -> CargoResult<()> {
         if let Some(codegen_backend) = &self.codegen_backend {
             features.require(Feature::codegen_backend())?;
-            if codegen_backend.contains(|c: char| !c.is_ascii_alphanumeric() && c != '_') {
+            let is_path = codegen_backend.contains(|c| c == '/' || c == '\\');
+            if !is_path
+                && codegen_backend.contains(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            {
                 bail!(
-                    "`profile.{}.codegen-backend` setting of `{}` is not a valid backend name.",
+                    "`profile.{}.codegen-backend` setting of `{}` is not a valid backend name \
+                     or path.",
                     name,
                     codegen_backend,
                 );
@@ -764,6 +917,9 @@ impl TomlProfile {
         if self.rustflags.is_some() {
             features.require(Feature::profile_rustflags())?;
         }
+        if self.rustc.is_some() {
+            features.require(Feature::profile_rustc())?;
+        }
         Ok(())
     }
 
@@ -775,6 +931,9 @@ impl TomlProfile {
         if self.build_override.is_some() {
             bail!("build-override profiles cannot be nested");
         }
+        if self.registry_packages.is_some() {
+            bail!("registry-packages profiles cannot be nested");
+        }
         if self.panic.is_some() {
             bail!("`panic` may not be specified in a `{}` profile", which)
         }
@@ -837,6 +996,10 @@ impl TomlProfile {
             self.rustflags = Some(v.clone());
         }
 
+        if let Some(v) = profile.rustc {
+            self.rustc = Some(v);
+        }
+
         if let Some(other_package) = &profile.package {
             match &mut self.package {
                 Some(self_package) => {
@@ -853,6 +1016,13 @@ impl TomlProfile {
             }
         }
 
+        if let Some(other_rp) = &profile.registry_packages {
+            match &mut self.registry_packages {
+                Some(self_rp) => self_rp.merge(other_rp),
+                None => self.registry_packages = Some(other_rp.clone()),
+            }
+        }
+
         if let Some(other_bo) = &profile.build_override {
             match &mut self.build_override {
                 Some(self_bo) => self_bo.merge(other_bo),
@@ -1108,11 +1278,77 @@ pub struct TomlPackage {
     repository: Option<MaybeWorkspace<String>>,
     resolver: Option<String>,
 
+    // Provided by the `package-check-cfg` cargo-feature.
+    check_cfg: Option<TomlCheckCfg>,
+
     // Note that this field must come last due to the way toml serialization
     // works which requires tables to be emitted after all values.
     metadata: Option<toml::Value>,
 }
 
+/// The `[package.check-cfg]` table, declaring the custom cfgs a package
+/// expects to be set.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct TomlCheckCfg {
+    /// Cfg names that may be set without a value, like `cfg(has_simd)`.
+    names: Option<Vec<String>>,
+    /// Cfg names along with all the values they may be set to, like
+    /// `cfg(backend = "vulkan")`.
+    values: Option<BTreeMap<String, Vec<String>>>,
+}
+
+impl TomlCheckCfg {
+    /// Validates the table and renders it into the specs passed to
+    /// `--check-cfg`.
+    fn to_check_cfgs(&self) -> CargoResult<Vec<String>> {
+        let validate_name = |name: &str| -> CargoResult<()> {
+            let mut chars = name.chars();
+            let valid = match chars.next() {
+                Some(c) => {
+                    (c.is_alphabetic() || c == '_')
+                        && chars.all(|c| c.is_alphanumeric() || c == '_')
+                }
+                None => false,
+            };
+            if !valid {
+                bail!(
+                    "invalid cfg name `{}` in `package.check-cfg`, \
+                     expected an identifier",
+                    name
+                );
+            }
+            Ok(())
+        };
+
+        let mut check_cfgs = Vec::new();
+        if let Some(names) = self.names.as_ref().filter(|names| !names.is_empty()) {
+            for name in names {
+                validate_name(name)?;
+            }
+            check_cfgs.push(format!("names({})", names.join(", ")));
+        }
+        for (name, values) in self.values.iter().flatten() {
+            validate_name(name)?;
+            let mut spec = format!("values({}", name);
+            for value in values {
+                if value.contains(|c| c == '"' || c == '\\') {
+                    bail!(
+                        "invalid value `{}` for cfg `{}` in `package.check-cfg`, \
+                         values may not contain quotes or backslashes",
+                        value,
+                        name
+                    );
+                }
+                spec.push_str(&format!(", \"{}\"", value));
+            }
+            spec.push(')');
+            check_cfgs.push(spec);
+        }
+        Ok(check_cfgs)
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TomlWorkspace {
     members: Option<Vec<String>>,
@@ -1486,6 +1722,7 @@ impl TomlManifest {
             },
             replace: None,
             patch: None,
+            links_overrides: None,
             workspace: None,
             badges: self.badges.clone(),
             cargo_features: self.cargo_features.clone(),
@@ -1707,6 +1944,14 @@ impl TomlManifest {
             features.require(Feature::metabuild())?;
         }
 
+        let check_cfgs = match &package.check_cfg {
+            Some(check_cfg) => {
+                features.require(Feature::package_check_cfg())?;
+                check_cfg.to_check_cfgs()?
+            }
+            None => Vec::new(),
+        };
+
         let resolve_behavior = match (
             package.resolver.as_ref(),
             me.workspace.as_ref().and_then(|ws| ws.resolver.as_ref()),
@@ -1717,6 +1962,9 @@ impl TomlManifest {
                 bail!("cannot specify `resolver` field in both `[workspace]` and `[package]`")
             }
         };
+        if resolve_behavior == Some(ResolveBehavior::V3) {
+            features.require(Feature::resolver_3())?;
+        }
 
         // If we have no lib at all, use the inferred lib, if available.
         // If we have a lib with a path, we're done.
@@ -1899,6 +2147,7 @@ impl TomlManifest {
         };
         let replace = me.replace(&mut cx)?;
         let patch = me.patch(&mut cx)?;
+        let links_overrides = me.links_overrides(&features)?;
 
         {
             let mut names_sources = BTreeMap::new();
@@ -1934,7 +2183,10 @@ impl TomlManifest {
             config,
             pkgid,
             deps,
-            me.features.as_ref().unwrap_or(&empty_features),
+            me.features
+                .as_ref()
+                .map(|f| &f.features)
+                .unwrap_or(&empty_features),
             package.links.as_deref(),
         )?;
 
@@ -2079,6 +2331,27 @@ impl TomlManifest {
             )
         }
 
+        if let Some(metadata) = me.features.as_ref().and_then(|f| f.metadata.as_ref()) {
+            features.require(Feature::feature_conflicts())?;
+            for conflict in metadata.conflicts() {
+                if conflict.len() < 2 {
+                    bail!(
+                        "a set of conflicting features needs at least two features, found {:?}",
+                        conflict
+                    );
+                }
+                for name in conflict {
+                    if !summary.features().contains_key(name) {
+                        bail!(
+                            "feature `{}` is listed in `features.metadata.conflicts`, \
+                             but no such feature exists",
+                            name
+                        );
+                    }
+                }
+            }
+        }
+
         if let Some(run) = &package.default_run {
             if !targets
                 .iter()
@@ -2123,6 +2396,7 @@ impl TomlManifest {
             target,
             replace: me.replace.clone(),
             patch: me.patch.clone(),
+            links_overrides: me.links_overrides.clone(),
             workspace: me.workspace.clone(),
             badges: me
                 .badges
@@ -2152,6 +2426,8 @@ impl TomlManifest {
             Rc::new(resolved_toml),
             package.metabuild.clone().map(|sov| sov.0),
             resolve_behavior,
+            check_cfgs,
+            links_overrides,
         );
         if package.license_file.is_some() && package.license.is_some() {
             manifest.warnings_mut().add_warning(
@@ -2242,6 +2518,7 @@ impl TomlManifest {
             };
             (me.replace(&mut cx)?, me.patch(&mut cx)?)
         };
+        let links_overrides = me.links_overrides(&features)?;
         let profiles = me.profile.clone();
         if let Some(profiles) = &profiles {
             profiles.validate(&features, &mut warnings)?;
@@ -2252,6 +2529,9 @@ impl TomlManifest {
             .and_then(|ws| ws.resolver.as_deref())
             .map(|r| ResolveBehavior::from_manifest(r))
             .transpose()?;
+        if resolve_behavior == Some(ResolveBehavior::V3) {
+            features.require(Feature::resolver_3())?;
+        }
         let workspace_config = match me.workspace {
             Some(ref toml_config) => {
                 let mut inheritable = toml_config.package.clone().unwrap_or_default();
@@ -2283,11 +2563,45 @@ impl TomlManifest {
                 profiles,
                 features,
                 resolve_behavior,
+                links_overrides,
             ),
             nested_paths,
         ))
     }
 
+    /// Parses the `[links-overrides]` table, which requires the
+    /// `links-overrides` cargo feature.
+    fn links_overrides(&self, features: &Features) -> CargoResult<BTreeMap<String, LinksOverride>> {
+        let table = match &self.links_overrides {
+            Some(table) => table,
+            None => return Ok(BTreeMap::new()),
+        };
+        features.require(Feature::links_overrides())?;
+        let mut overrides = BTreeMap::new();
+        for (links, entry) in table {
+            let whence = format!("`links-overrides.{}`", links);
+            let links_override = match entry.get("package") {
+                Some(package) => {
+                    if entry.len() != 1 {
+                        bail!(
+                            "{} cannot specify build script output together with `package`",
+                            whence
+                        );
+                    }
+                    let spec = package
+                        .as_str()
+                        .ok_or_else(|| anyhow!("{}.package must be a string", whence))?;
+                    LinksOverride::Package(PackageIdSpec::parse(spec).with_context(|| {
+                        format!("invalid package ID specification in {}", whence)
+                    })?)
+                }
+                None => LinksOverride::Output(links_override_output(&whence, entry)?),
+            };
+            overrides.insert(links.clone(), links_override);
+        }
+        Ok(overrides)
+    }
+
     fn replace(&self, cx: &mut Context<'_, '_>) -> CargoResult<Vec<(PackageIdSpec, Dependency)>> {
         if self.patch.is_some() && self.replace.is_some() {
             bail!("cannot specify both [replace] and [patch]");
@@ -2380,8 +2694,71 @@ impl TomlManifest {
     }
 
     pub fn features(&self) -> Option<&BTreeMap<InternedString, Vec<InternedString>>> {
-        self.features.as_ref()
+        self.features.as_ref().map(|f| &f.features)
+    }
+
+    pub fn features_metadata(&self) -> Option<&TomlFeaturesMetadata> {
+        self.features.as_ref().and_then(|f| f.metadata.as_ref())
+    }
+}
+
+/// Converts an entry of `[links-overrides]` which doesn't designate a package
+/// into the build script output to use instead, like the `links` overrides of
+/// the `[target]` config table.
+fn links_override_output(
+    whence: &str,
+    entry: &BTreeMap<String, toml::Value>,
+) -> CargoResult<BuildOutput> {
+    let strings = |key: &str, value: &toml::Value| -> CargoResult<Vec<String>> {
+        value
+            .as_array()
+            .and_then(|list| {
+                list.iter()
+                    .map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .ok_or_else(|| anyhow!("{}.{} must be a list of strings", whence, key))
+    };
+    let string = |key: &str, value: &toml::Value| -> CargoResult<String> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("{}.{} must be a string", whence, key))
+    };
+    let mut output = BuildOutput::default();
+    for (key, value) in entry {
+        match key.as_str() {
+            "rustc-flags" => {
+                let flags = string(key, value)?;
+                let (paths, links) = BuildOutput::parse_rustc_flags(&flags, whence)?;
+                output.library_paths.extend(paths);
+                output.library_links.extend(links);
+            }
+            "rustc-link-lib" => output.library_links.extend(strings(key, value)?),
+            "rustc-link-search" => output
+                .library_paths
+                .extend(strings(key, value)?.into_iter().map(PathBuf::from)),
+            "rustc-link-arg" => output.linker_args.extend(
+                strings(key, value)?
+                    .into_iter()
+                    .map(|arg| (LinkType::All, arg)),
+            ),
+            "rustc-cfg" => output.cfgs.extend(strings(key, value)?),
+            "rustc-env" => {
+                let table = value
+                    .as_table()
+                    .ok_or_else(|| anyhow!("{}.{} must be a table", whence, key))?;
+                for (name, val) in table {
+                    output.env.push((name.clone(), string(name, val)?));
+                }
+            }
+            "warning" | "rerun-if-changed" | "rerun-if-env-changed" => {
+                bail!("`{}` is not supported in build script overrides", key);
+            }
+            _ => output.metadata.push((key.clone(), string(key, value)?)),
+        }
     }
+    Ok(output)
 }
 
 fn inheritable_from_path(
@@ -2805,10 +3182,14 @@ impl<P: ResolveToPath + Clone> DetailedTomlDependency<P> {
             } else {
                 bail!("`artifact = …` requires `-Z bindeps` ({})", name_in_toml);
             }
-        } else if self.lib.is_some() || self.target.is_some() {
+            if let Some(reexport) = self.artifact_reexport {
+                dep.set_reexport_artifact(reexport);
+            }
+        } else if self.lib.is_some() || self.target.is_some() || self.artifact_reexport.is_some() {
             for (is_set, specifier) in [
                 (self.lib.is_some(), "lib"),
                 (self.target.is_some(), "target"),
+                (self.artifact_reexport.is_some(), "artifact-reexport"),
             ] {
                 if !is_set {
                     continue;
@@ -2820,6 +3201,26 @@ impl<P: ResolveToPath + Clone> DetailedTomlDependency<P> {
                 )
             }
         }
+        if let Some(artifact_env) = &self.artifact_env {
+            if !cx.config.cli_unstable().bindeps {
+                bail!(
+                    "`artifact-env = …` requires `-Z bindeps` ({})",
+                    name_in_toml
+                );
+            }
+            match artifact_env.as_str() {
+                "direct" => {}
+                "transitive" => {
+                    dep.set_transitive_artifact_env(true);
+                }
+                other => bail!(
+                    "invalid value `{}` for `artifact-env` of dependency `{}`, \
+                     expected `direct` or `transitive`",
+                    other,
+                    name_in_toml
+                ),
+            }
+        }
         Ok(dep)
     }
 }
@@ -2887,6 +3288,8 @@ struct TomlTarget {
     proc_macro_raw2: Option<bool>,
     harness: Option<bool>,
     required_features: Option<Vec<String>>,
+    // Note that `dependencies` is used for the cargo-feature `example_dependencies`
+    dependencies: Option<Vec<String>>,
     edition: Option<String>,
 }
 
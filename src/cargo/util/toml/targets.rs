@@ -122,6 +122,8 @@ pub fn targets(
             edition,
         ));
     }
+    validate_scoped_dependencies(features, manifest)?;
+
     if let Some(metabuild) = metabuild {
         // Verify names match available build deps.
         let bdeps = manifest.build_dependencies.as_ref();
@@ -415,6 +417,7 @@ fn clean_examples(
             edition,
         );
         configure(&toml, &mut target)?;
+        target.set_scoped_dependencies(toml.dependencies.clone().unwrap_or_default());
         result.push(target);
     }
 
@@ -800,6 +803,58 @@ fn validate_unique_names(targets: &[TomlTarget], target_kind: &str) -> CargoResu
     Ok(())
 }
 
+/// Checks the `dependencies` field of targets, which is only allowed for
+/// examples and must name dev-dependencies of the package.
+fn validate_scoped_dependencies(features: &Features, manifest: &TomlManifest) -> CargoResult<()> {
+    let others = [
+        ("lib", manifest.lib.iter().collect::<Vec<_>>()),
+        ("bin", manifest.bin.iter().flatten().collect()),
+        ("test", manifest.test.iter().flatten().collect()),
+        ("bench", manifest.bench.iter().flatten().collect()),
+    ];
+    for (kind, targets) in others {
+        if let Some(target) = targets.iter().find(|t| t.dependencies.is_some()) {
+            anyhow::bail!(
+                "the `dependencies` field of {} target `{}` is only supported for examples",
+                kind,
+                target.name()
+            );
+        }
+    }
+
+    let dev_deps = manifest
+        .dev_dependencies
+        .iter()
+        .chain(manifest.dev_dependencies2.iter())
+        .chain(manifest.target.iter().flat_map(|platforms| {
+            platforms.values().flat_map(|platform| {
+                platform
+                    .dev_dependencies
+                    .iter()
+                    .chain(platform.dev_dependencies2.iter())
+            })
+        }))
+        .flat_map(|deps| deps.keys())
+        .collect::<Vec<_>>();
+    for example in manifest.example.iter().flatten() {
+        let names = match &example.dependencies {
+            Some(names) => names,
+            None => continue,
+        };
+        features.require(Feature::example_dependencies())?;
+        for name in names {
+            if !dev_deps.contains(&name) {
+                anyhow::bail!(
+                    "dependency `{}` of example `{}` must be specified in `dev-dependencies`",
+                    name,
+                    example.name()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
 fn configure(toml: &TomlTarget, target: &mut Target) -> CargoResult<()> {
     let t2 = target.clone();
     target
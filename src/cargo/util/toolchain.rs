@@ -0,0 +1,327 @@
+//! Verification of the active toolchain against a required one, enabled with
+//! `-Z required-toolchain`.
+//!
+//! The requirement is the name of a toolchain as understood by rustup, and is
+//! taken from `--require-toolchain`, `build.required-rust-version`, or the
+//! `rust-toolchain.toml` file of the current directory, in that order.
+//! Usually rustup already selects the toolchain of the file when running
+//! Cargo through its proxy, but not when it's overridden with
+//! `cargo +<toolchain>` or `RUSTUP_TOOLCHAIN`, or Cargo is run without rustup,
+//! and a mismatch is only noticed when the build fails in confusing ways.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Context as _};
+use cargo_util::paths;
+use serde::Deserialize;
+use toml_edit::easy as toml;
+
+use crate::util::config::Definition;
+use crate::util::important_paths::find_toolchain_file;
+use crate::util::{CargoResult, Config, Rustc};
+
+/// A toolchain as named by rustup, like `stable`, `1.65.0` or
+/// `nightly-2022-10-01-x86_64-unknown-linux-gnu`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ToolchainSpec {
+    name: String,
+    channel: Channel,
+    /// The date of a dated nightly or beta toolchain, like `2022-10-01`.
+    date: Option<String>,
+    host: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Channel {
+    Stable,
+    Beta,
+    Nightly,
+    /// A stable release, of any patch version if it's `None`.
+    Version(u64, u64, Option<u64>),
+}
+
+impl FromStr for ToolchainSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> CargoResult<ToolchainSpec> {
+        let unrecognized = || {
+            anyhow::format_err!(
+                "unrecognized toolchain `{}`, expected a channel like `stable` or \
+                 `nightly-2022-10-01`, or a version like `1.65.0`",
+                name
+            )
+        };
+        let (channel, rest) = match name.split_once('-') {
+            Some((channel, rest)) => (channel, Some(rest)),
+            None => (name, None),
+        };
+        let channel = match channel {
+            "stable" => Channel::Stable,
+            "beta" => Channel::Beta,
+            "nightly" => Channel::Nightly,
+            version => {
+                let parts = version
+                    .split('.')
+                    .map(|part| part.parse::<u64>().map_err(|_| unrecognized()))
+                    .collect::<CargoResult<Vec<_>>>()?;
+                match parts[..] {
+                    [major, minor] => Channel::Version(major, minor, None),
+                    [major, minor, patch] => Channel::Version(major, minor, Some(patch)),
+                    _ => return Err(unrecognized()),
+                }
+            }
+        };
+        let (date, host) = match rest {
+            Some(rest) if is_date(rest.get(..10).unwrap_or_default()) => {
+                match rest[10..].strip_prefix('-') {
+                    Some(host) => (Some(rest[..10].to_string()), Some(host)),
+                    None if rest.len() == 10 => (Some(rest.to_string()), None),
+                    None => return Err(unrecognized()),
+                }
+            }
+            rest => (None, rest),
+        };
+        if date.is_some() && matches!(channel, Channel::Version(..)) {
+            return Err(unrecognized());
+        }
+        if host == Some("") {
+            return Err(unrecognized());
+        }
+        Ok(ToolchainSpec {
+            name: name.to_string(),
+            channel,
+            date,
+            host: host.map(str::to_string),
+        })
+    }
+}
+
+impl fmt::Display for ToolchainSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl ToolchainSpec {
+    /// Whether `rustc` is an instance of this toolchain.
+    ///
+    /// Nightly and beta toolchains are dated after the day they were built,
+    /// while rustc reports the date of the last commit, usually the day
+    /// before, so both are accepted.
+    fn matches(&self, rustc: &Rustc) -> bool {
+        let version = &rustc.version;
+        let pre = version.pre.as_str();
+        let channel_matches = match self.channel {
+            Channel::Stable => pre.is_empty(),
+            Channel::Beta => pre.starts_with("beta"),
+            // Locally built compilers report `-dev` and act like nightly.
+            Channel::Nightly => pre.starts_with("nightly") || pre.starts_with("dev"),
+            Channel::Version(major, minor, patch) => {
+                pre.is_empty()
+                    && version.major == major
+                    && version.minor == minor
+                    && patch.map_or(true, |patch| version.patch == patch)
+            }
+        };
+        let date_matches = match &self.date {
+            Some(date) => rustc
+                .verbose_version
+                .lines()
+                .find_map(|line| line.strip_prefix("commit-date: "))
+                .map_or(false, |commit_date| {
+                    commit_date == date || next_day(commit_date).as_deref() == Some(date)
+                }),
+            None => true,
+        };
+        let host_matches = self.host.as_deref().map_or(true, |host| rustc.host == host);
+        channel_matches && date_matches && host_matches
+    }
+}
+
+/// Where the required toolchain comes from.
+#[derive(Clone, Debug)]
+pub enum ToolchainSource {
+    /// `--require-toolchain`.
+    Cli,
+    /// `build.required-rust-version`.
+    Config(Definition),
+    /// The channel of a toolchain file.
+    File(PathBuf),
+}
+
+/// The toolchain a build requires, see the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct RequiredToolchain {
+    pub spec: ToolchainSpec,
+    pub source: ToolchainSource,
+}
+
+impl RequiredToolchain {
+    /// The toolchain required by `build.required-rust-version`, or else the
+    /// toolchain file found from the current directory.
+    ///
+    /// A toolchain file naming a toolchain which can't be verified, like a
+    /// custom one linked with `rustup toolchain link`, only causes a warning.
+    pub fn from_config(config: &Config) -> CargoResult<Option<RequiredToolchain>> {
+        if let Some(value) = &config.build_config()?.required_rust_version {
+            let spec = value.val.parse().with_context(|| {
+                format!(
+                    "failed to parse `build.required-rust-version` in {}",
+                    value.definition
+                )
+            })?;
+            return Ok(Some(RequiredToolchain {
+                spec,
+                source: ToolchainSource::Config(value.definition.clone()),
+            }));
+        }
+        let path = match find_toolchain_file(config.cwd()) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let channel = match read_toolchain_file(&path)? {
+            Some(channel) => channel,
+            None => return Ok(None),
+        };
+        match channel.parse() {
+            Ok(spec) => Ok(Some(RequiredToolchain {
+                spec,
+                source: ToolchainSource::File(path),
+            })),
+            Err(e) => {
+                config.shell().warn(format!(
+                    "the toolchain of `{}` can't be verified: {}",
+                    path.display(),
+                    e
+                ))?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Fails with an explanation if `rustc` isn't the required toolchain.
+    pub fn verify(&self, rustc: &Rustc) -> CargoResult<()> {
+        if self.spec.matches(rustc) {
+            return Ok(());
+        }
+        let active = rustc.verbose_version.lines().next().unwrap_or_default();
+        let source = match &self.source {
+            ToolchainSource::Cli => "`--require-toolchain`".to_string(),
+            ToolchainSource::Config(definition) => {
+                format!("`build.required-rust-version` in {}", definition)
+            }
+            ToolchainSource::File(path) => format!("`{}`", path.display()),
+        };
+        let mut msg = format!(
+            "the active toolchain, {}, is not the toolchain `{}` required by {}",
+            active, self.spec, source
+        );
+        if let ToolchainSource::File(_) = self.source {
+            msg.push_str(
+                "\nrustup selects the toolchain of this file when Cargo is run through its \
+                 proxy, unless it's overridden with `cargo +<toolchain>` or `RUSTUP_TOOLCHAIN`",
+            );
+        }
+        bail!(
+            "{}\nTry `rustup toolchain install {}`, then run Cargo with `cargo +{}`",
+            msg,
+            self.spec,
+            self.spec
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct ToolchainFile {
+    toolchain: ToolchainTable,
+}
+
+#[derive(Deserialize)]
+struct ToolchainTable {
+    channel: Option<String>,
+}
+
+/// Reads the channel of the toolchain file at `path`, in the format of
+/// `rust-toolchain.toml`, or the legacy format of a file with only the name
+/// of the toolchain. Returns `None` if it names no channel, like a file
+/// with the `path` of a custom toolchain.
+pub fn read_toolchain_file(path: &Path) -> CargoResult<Option<String>> {
+    let contents = paths::read(path)?;
+    let trimmed = contents.trim();
+    if !trimmed.is_empty() && !trimmed.contains(|c| c == '=' || c == '[' || c == '\n') {
+        return Ok(Some(trimmed.to_string()));
+    }
+    let file: ToolchainFile = toml::from_str(&contents)
+        .with_context(|| format!("failed to parse toolchain file `{}`", path.display()))?;
+    Ok(file.toolchain.channel)
+}
+
+/// Whether `s` looks like a date, `YYYY-MM-DD`.
+fn is_date(s: &str) -> bool {
+    s.len() == 10
+        && s.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// The day after `date`, a `YYYY-MM-DD` date.
+fn next_day(date: &str) -> Option<String> {
+    if !is_date(date) {
+        return None;
+    }
+    let year: u32 = date[..4].parse().ok()?;
+    let month: u32 = date[5..7].parse().ok()?;
+    let day: u32 = date[8..].parse().ok()?;
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    let (year, month, day) = if day < days_in_month {
+        (year, month, day + 1)
+    } else if month < 12 {
+        (year, month + 1, 1)
+    } else {
+        (year + 1, 1, 1)
+    };
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_toolchain_spec() {
+        let spec: ToolchainSpec = "nightly-2022-10-01-x86_64-unknown-linux-gnu"
+            .parse()
+            .unwrap();
+        assert_eq!(spec.channel, Channel::Nightly);
+        assert_eq!(spec.date.as_deref(), Some("2022-10-01"));
+        assert_eq!(spec.host.as_deref(), Some("x86_64-unknown-linux-gnu"));
+
+        let spec: ToolchainSpec = "1.65".parse().unwrap();
+        assert_eq!(spec.channel, Channel::Version(1, 65, None));
+        let spec: ToolchainSpec = "stable-aarch64-apple-darwin".parse().unwrap();
+        assert_eq!(spec.channel, Channel::Stable);
+        assert_eq!(spec.host.as_deref(), Some("aarch64-apple-darwin"));
+
+        for invalid in ["my-toolchain", "1", "1.65.0-2022-10-01", "nightly-", "1.x"] {
+            assert!(invalid.parse::<ToolchainSpec>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn next_days() {
+        assert_eq!(next_day("2022-10-01").as_deref(), Some("2022-10-02"));
+        assert_eq!(next_day("2022-09-30").as_deref(), Some("2022-10-01"));
+        assert_eq!(next_day("2020-02-28").as_deref(), Some("2020-02-29"));
+        assert_eq!(next_day("2022-02-28").as_deref(), Some("2022-03-01"));
+        assert_eq!(next_day("2022-12-31").as_deref(), Some("2023-01-01"));
+    }
+}
@@ -611,6 +611,87 @@ fn build_script_with_bin_artifacts() {
     assert_artifact_executable_output(&p, "debug", "bar", "bar");
 }
 
+#[cargo_test]
+fn transitive_artifact_env() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.0"
+                authors = []
+                resolver = "2"
+
+                [dependencies]
+                bundle = { path = "bundle", artifact-env = "transitive" }
+
+                [build-dependencies]
+                bundle = { path = "bundle", artifact-env = "transitive" }
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"pub const TOOL: &str = env!("CARGO_BIN_FILE_TOOL");"#,
+        )
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    let tool = std::env::var("CARGO_BIN_FILE_TOOL").expect("CARGO_BIN_FILE_TOOL");
+                    assert!(std::path::Path::new(&tool).is_file());
+                    assert!(std::env::var("CARGO_BIN_FILE_INTERNAL").is_err());
+                }
+            "#,
+        )
+        .file(
+            "bundle/Cargo.toml",
+            r#"
+                [package]
+                name = "bundle"
+                version = "0.0.0"
+                authors = []
+
+                [dependencies]
+                tool = { path = "../tool", artifact = "bin", artifact-reexport = true }
+                internal = { path = "../internal", artifact = "bin" }
+            "#,
+        )
+        .file("bundle/src/lib.rs", "")
+        .file("tool/Cargo.toml", &basic_bin_manifest("tool"))
+        .file("tool/src/main.rs", "fn main() {}")
+        .file("internal/Cargo.toml", &basic_bin_manifest("internal"))
+        .file("internal/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("check -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_contains("[COMPILING] tool v0.5.0 ([CWD]/tool)")
+        .with_stderr_contains("[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]")
+        .run();
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+
+            [dependencies]
+            bundle = { path = "bundle", artifact-env = "all" }
+        "#,
+    );
+    p.cargo("check -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_status(101)
+        .with_stderr_contains(
+            "  invalid value `all` for `artifact-env` of dependency `bundle`, \
+             expected `direct` or `transitive`",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn build_script_with_bin_artifact_and_lib_false() {
     let p = project()
@@ -960,7 +1041,56 @@ fn disallow_using_example_binaries_as_artifacts() {
     p.cargo("build -Z bindeps")
         .masquerade_as_nightly_cargo(&["bindeps"])
         .with_status(101)
-        .with_stderr(r#"[ERROR] dependency `bar` in package `foo` requires a `bin:one-example` artifact to be present."#)
+        .with_stderr(
+            "\
+[ERROR] dependency `bar` in package `foo` requires a `bin:one-example` artifact to be present.
+Package `bar` provides the binaries: `bar`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn missing_binary_artifact_is_reported_after_resolution() {
+    Package::new("baz", "0.1.0")
+        .file("src/main.rs", "fn main() {}")
+        .file("src/bin/baz-cli.rs", "fn main() {}")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.0"
+                authors = []
+                resolver = "2"
+
+                [dependencies]
+                bar = { path = "bar/" }
+
+                [build-dependencies]
+                baz = { version = "0.1", artifact = "bin:baz-tool" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    // `cargo tree` doesn't build a unit graph, but still reports the error.
+    p.cargo("tree -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] baz v0.1.0 (registry `dummy-registry`)
+[ERROR] dependency `baz` in package `foo` requires a `bin:baz-tool` artifact to be present.
+Package `baz` provides the binaries: `baz`, `baz-cli`
+",
+        )
         .run();
 }
 
@@ -1073,6 +1203,63 @@ fn build_script_deps_adopt_specified_target_unconditionally() {
         .run();
 }
 
+#[cargo_test]
+fn build_script_deps_adopt_specified_fake_target() {
+    // Like `build_script_deps_adopt_specified_target_unconditionally`, but
+    // with a fake target so it doesn't need a cross toolchain.
+    let target = cross_compile::fake_target("aarch64-fake-linux-gnu", cross_compile::FakeStd::Full);
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                [package]
+                name = "foo"
+                version = "0.0.0"
+                authors = []
+                resolver = "2"
+
+                [build-dependencies.bar]
+                path = "bar/"
+                artifact = "bin"
+                target = "{}"
+            "#,
+                target.triple()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            &r#"
+                fn main() {
+                    let bar: std::path::PathBuf = std::env::var("CARGO_BIN_FILE_BAR").expect("CARGO_BIN_FILE_BAR").into();
+                    assert!(&bar.is_file());
+                    let target_dir = std::path::Path::new("target").join("__TRIPLE__");
+                    assert!(bar.components().collect::<Vec<_>>().windows(2).any(|w| {
+                        std::path::Path::new(w[0].as_os_str()).join(w[1].as_os_str()) == target_dir
+                    }), "{} should be in {}", bar.display(), target_dir.display());
+                }
+            "#
+            .replace("__TRIPLE__", target.triple()),
+        )
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    cross_compile::use_fake_targets(&mut p.cargo("check -v -Z bindeps"), &[&target])
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_contains(format!(
+            "[RUNNING] `[..]rustc --crate-name bar bar/src/main.rs [..]--target {} [..]",
+            target.triple()
+        ))
+        .with_stderr_does_not_contain(format!(
+            "[RUNNING] `[..]rustc --crate-name foo [..]--target {} [..]",
+            target.triple()
+        ))
+        .run();
+    assert!(!target.out_dir(&p, "debug").join("bar").exists());
+}
+
 /// inverse RFC-3176
 #[cargo_test]
 fn build_script_deps_adopt_do_not_allow_multiple_targets_under_different_name_and_same_version() {
@@ -1384,6 +1571,264 @@ fn profile_override_basic() {
         .run();
 }
 
+#[cargo_test]
+fn profile_override_custom_inherits_chain() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = { path = "bar", artifact = "bin" }
+
+                [profile.release.package.bar]
+                codegen-units = 3
+
+                [profile.release-lto]
+                inherits = "release"
+                lto = true
+
+                [profile.ci]
+                inherits = "release-lto"
+                opt-level = 2
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -v -Z bindeps --profile ci")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_contains(
+            "[RUNNING] `rustc --crate-name bar bar/src/main.rs [..]-C opt-level=2 -C lto [..]-C codegen-units=3 [..]`",
+        )
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-C opt-level=2 [..]`")
+        .with_stderr_contains("[FINISHED] ci [optimized] target(s) in [..]")
+        .run();
+}
+
+#[cargo_test]
+fn build_script_artifact_profile_mismatch_warning() {
+    let manifest = |profile: &str| {
+        format!(
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [build-dependencies]
+                bar = {{ path = "bar", artifact = "cdylib" }}
+                {}
+            "#,
+            profile
+        )
+    };
+    let p = project()
+        .file("Cargo.toml", &manifest(""))
+        .file(
+            "build.rs",
+            r#"fn main() { assert!(std::env::var("CARGO_CDYLIB_FILE_BAR").is_ok()); }"#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "bar/Cargo.toml",
+            r#"
+                [package]
+                name = "bar"
+                version = "0.5.0"
+                authors = []
+
+                [lib]
+                crate-type = ["cdylib"]
+            "#,
+        )
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    // The dev profile and its build-override agree on the opt-level.
+    p.cargo("build -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_does_not_contain("[WARNING] [..]")
+        .run();
+
+    p.cargo("build --release -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_unordered(
+            "\
+[WARNING] artifact dependency `bar v0.5.0` of the build script of `foo v0.0.1` is built with \
+opt-level=0, but the `release` profile uses opt-level=3
+Dependencies of build scripts use the `[profile.release.build-override]` settings. \
+To build this dependency with opt-level=3, add `opt-level = 3` to `[profile.release.package.bar]`, \
+or set another `opt-level` there to silence this warning.
+[COMPILING] bar v0.5.0 ([CWD]/bar)
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] release [..]
+",
+        )
+        .run();
+
+    p.change_file(
+        "Cargo.toml",
+        &manifest(
+            r#"
+                [profile.release.package.bar]
+                opt-level = 3
+            "#,
+        ),
+    );
+    p.cargo("build --release -v -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_does_not_contain("[WARNING] [..]")
+        .with_stderr_contains(
+            "[RUNNING] `rustc --crate-name bar bar/src/lib.rs [..] -C opt-level=3 [..]`",
+        )
+        .run();
+
+    // Setting the build-override opt-level explicitly opts out of the warning.
+    p.change_file(
+        "Cargo.toml",
+        &manifest(
+            r#"
+                [profile.release.package.bar]
+                opt-level = 0
+            "#,
+        ),
+    );
+    p.cargo("build --release -v -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_does_not_contain("[WARNING] [..]")
+        .with_stderr_does_not_contain("[RUNNING] `rustc --crate-name bar [..] -C opt-level=3 [..]`")
+        .run();
+}
+
+#[cargo_test]
+fn build_script_generator_artifact_is_quiet() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [build-dependencies]
+                bar = { path = "bar", artifact = "bin" }
+            "#,
+        )
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    let bar = std::env::var("CARGO_BIN_FILE_BAR").unwrap();
+                    assert!(std::process::Command::new(bar).status().unwrap().success());
+                }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    // A binary run by the build script doesn't need to be optimized.
+    p.cargo("build --release -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_unordered(
+            "\
+[COMPILING] bar v0.5.0 ([CWD]/bar)
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] release [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn build_script_artifact_uses_validation() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [build-dependencies]
+                bar = { path = "bar", artifact = "bin" }
+            "#,
+        )
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    println!("cargo:artifact-uses=bar:bin");
+                    println!("cargo:artifact-uses=bar:staticlib");
+                    std::env::var("CARGO_STATICLIB_FILE_BAR").unwrap();
+                }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] the build script of `foo v0.0.1 ([CWD])` uses the `staticlib` artifact of `bar`, \
+but the manifest only requests `bin`
+change the dependency on `bar` in [CWD]/Cargo.toml to use `artifact = [\"bin\", \"staticlib\"]`
+
+Caused by:
+  failed to run custom build command for `foo v0.0.1 ([CWD])`
+",
+        )
+        .run();
+
+    // A script that handles the missing artifact itself still gets the error.
+    p.change_file(
+        "build.rs",
+        r#"
+            fn main() {
+                println!("cargo:artifact-uses=baz:cdylib");
+            }
+        "#,
+    );
+    p.cargo("build -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] the build script of `foo v0.0.1 ([CWD])` uses the `cdylib` artifact of `baz`, \
+but `baz` is not an artifact build-dependency
+add `baz = { ..., artifact = \"cdylib\" }` to `[build-dependencies]` in `[CWD]/Cargo.toml`
+",
+        )
+        .run();
+
+    p.change_file(
+        "build.rs",
+        r#"
+            fn main() {
+                println!("cargo:artifact-uses=bar:bin:bar");
+                std::env::var("CARGO_BIN_FILE_BAR").unwrap();
+            }
+        "#,
+    );
+    p.cargo("build -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .run();
+}
+
 #[cargo_test]
 fn dependencies_of_dependencies_work_in_artifacts() {
     Package::new("baz", "1.0.0")
@@ -1691,7 +2136,11 @@ fn resolver_2_build_dep_without_lib() {
 
 #[cargo_test]
 fn check_missing_crate_type_in_package_fails() {
-    for crate_type in &["cdylib", "staticlib", "bin"] {
+    for (crate_type, note) in [
+        ("cdylib", ""),
+        ("staticlib", ""),
+        ("bin", "Package `bar` does not provide any binaries.\n"),
+    ] {
         let p = project()
             .file(
                 "Cargo.toml",
@@ -1715,9 +2164,10 @@ fn check_missing_crate_type_in_package_fails() {
         p.cargo("check -Z bindeps")
             .masquerade_as_nightly_cargo(&["bindeps"])
             .with_status(101)
-            .with_stderr(
-                "[ERROR] dependency `bar` in package `foo` requires a `[..]` artifact to be present.",
-            )
+            .with_stderr(&format!(
+                "[ERROR] dependency `bar` in package `foo` requires a `{}` artifact to be present.\n{}",
+                crate_type, note
+            ))
             .run();
     }
 }
@@ -1875,8 +2325,30 @@ fn publish_artifact_dep() {
     // HACK below allows us to use a local registry
     let registry = registry::init();
 
-    Package::new("bar", "1.0.0").publish();
-    Package::new("baz", "1.0.0").publish();
+    // The published packages provide the artifacts requested by `foo`.
+    Package::new("bar", "1.0.0")
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    Package::new("baz", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "baz"
+                version = "1.0.0"
+
+                [lib]
+                crate-type = ["cdylib", "staticlib"]
+
+                [[bin]]
+                name = "a"
+                path = "src/main.rs"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
 
     let p = project()
         .file(
@@ -1915,9 +2387,13 @@ fn publish_artifact_dep() {
     p.cargo("publish -Z bindeps --no-verify")
         .replace_crates_io(registry.index_url())
         .masquerade_as_nightly_cargo(&["bindeps"])
-        .with_stderr(
+        .with_stderr_unordered(
             "\
 [UPDATING] [..]
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v1.0.0
+[DOWNLOADING] crates ...
+[DOWNLOADED] baz v1.0.0
 [PACKAGING] foo v0.1.0 [..]
 [PACKAGED] [..]
 [UPLOADING] foo v0.1.0 [..]
@@ -2002,6 +2478,60 @@ target = "target""#,
     );
 }
 
+#[cargo_test]
+fn publish_artifact_dep_missing_artifact() {
+    // HACK below allows us to use a local registry
+    let registry = registry::init();
+
+    Package::new("bar", "1.0.0")
+        .file("src/lib.rs", "")
+        .file("src/bin/b.rs", "fn main() {}")
+        .file("src/bin/c.rs", "fn main() {}")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+            license = "MIT"
+            description = "foo"
+            documentation = "foo"
+            homepage = "foo"
+            repository = "foo"
+            resolver = "2"
+
+            [dependencies]
+            bar = { version = "1.0", artifact = "bin:a" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("publish -Z bindeps --no-verify")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] [..]
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v1.0.0
+[ERROR] failed to verify the artifact dependency `bar` against bar v1.0.0
+
+fix its `artifact` key, or depend on a version of `bar` which provides the artifact
+
+Caused by:
+  dependency `bar` in package `foo` requires a `bin:a` artifact to be present.
+  Package `bar` provides the binaries: `b`, `c`
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn doc_lib_true() {
     let p = project()
@@ -2121,6 +2651,49 @@ fn rustdoc_works_on_libs_with_artifacts_and_lib_false() {
     );
 }
 
+#[cargo_test]
+fn doc_manifest_omits_artifact_only_deps() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+                resolver = "2"
+
+                [dependencies.bar]
+                path = "bar"
+                artifact = "bin"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo(
+        "doc --workspace --no-deps --include-private --doc-manifest -Z bindeps -Z unstable-options",
+    )
+    .masquerade_as_nightly_cargo(&["bindeps", "unstable-options"])
+    .with_stderr(
+        "\
+[COMPILING] bar v0.5.0 ([CWD]/bar)
+[DOCUMENTING] foo v0.0.1 ([CWD])
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]
+",
+    )
+    .run();
+
+    let manifest = p.read_file("target/doc/doc-manifest.json");
+    assert!(manifest.contains(r#""foo": {"#));
+    assert!(
+        !manifest.contains(r#""bar": {"#),
+        "bar is not a lib dependency and thus remains undocumented"
+    );
+}
+
 fn assert_artifact_executable_output(
     p: &Project,
     target_name: &str,
@@ -2342,3 +2915,112 @@ fn calc_bin_artifact_fingerprint() {
         )
         .run();
 }
+
+#[cfg(unix)]
+#[cargo_test]
+fn lib_true_pipelines_artifact() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+                resolver = "2"
+
+                [dependencies.bar]
+                path = "bar"
+                artifact = "bin"
+                lib = true
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"pub fn foo() -> &'static str { bar::bar(); env!("CARGO_BIN_FILE_BAR") }"#,
+        )
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .file("bar/src/main.rs", "fn main() { bar::bar() }")
+        // Holds the binary of `bar` back until `foo` is compiled.
+        .executable(
+            "rustc-wrapper",
+            r#"#!/bin/sh
+                case "$*" in
+                    *"--crate-name bar "*"--crate-type bin"*)
+                        i=0
+                        while ! ls target/debug/deps/libfoo-* >/dev/null 2>&1; do
+                            i=$((i + 1))
+                            if [ $i -gt 600 ]; then
+                                echo "foo wasn't compiled before the binary of bar" >&2
+                                exit 1
+                            fi
+                            sleep 0.1
+                        done
+                        ;;
+                esac
+                exec "$@"
+            "#,
+        )
+        .build();
+
+    p.cargo("build -j2 -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .env("RUSTC_WRAPPER", p.root().join("rustc-wrapper"))
+        .run();
+    assert!(p.glob("target/debug/deps/artifact/bar-*/bin/bar*").count() > 0);
+
+    p.cargo("build -v -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .env("RUSTC_WRAPPER", p.root().join("rustc-wrapper"))
+        .with_stderr_unordered(
+            "\
+[FRESH] bar v0.0.1 ([CWD]/bar)
+[FRESH] foo v0.0.1 ([CWD])
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn uplift_artifact_deps() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.0"
+                authors = []
+
+                [dependencies]
+                bar = { path = "bar/", artifact = "bin" }
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", &basic_bin_manifest("bar"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                uplift = ["bins", "artifact-deps"]
+            "#,
+        )
+        .build();
+
+    // `build.uplift` is ignored without `-Z uplift`.
+    p.cargo("build -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .run();
+    assert!(p.bin("foo").is_file());
+    assert!(!p.bin("bar").is_file());
+
+    p.cargo("build -Z bindeps -Z uplift")
+        .masquerade_as_nightly_cargo(&["bindeps", "uplift"])
+        .run();
+    assert!(p.bin("foo").is_file());
+    assert!(p.bin("bar").is_file());
+    assert_artifact_executable_output(&p, "debug", "bar", "bar");
+}
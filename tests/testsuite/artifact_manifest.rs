@@ -0,0 +1,143 @@
+//! Tests for `-Z artifact-manifest`.
+
+use cargo_test_support::compare::find_json_mismatch;
+use cargo_test_support::{basic_bin_manifest, basic_manifest, project};
+
+fn artifact_paths(manifest: &serde_json::Value) -> Vec<String> {
+    manifest["artifacts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["path"].as_str().unwrap().replace('\\', "/"))
+        .collect()
+}
+
+#[cargo_test]
+fn requires_nightly() {
+    let p = project().file("src/main.rs", "fn main() {}").build();
+
+    p.cargo("build -Z artifact-manifest")
+        .with_status(101)
+        .with_stderr_contains("[..]-Z` flag is only accepted on the nightly channel[..]")
+        .run();
+
+    p.cargo("build").run();
+    assert!(!p.build_dir().join("debug/.artifacts.json").exists());
+}
+
+#[cargo_test]
+fn binary() {
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -Z artifact-manifest")
+        .masquerade_as_nightly_cargo(&["artifact-manifest"])
+        .run();
+    let expected = serde_json::from_str(
+        r#"
+            {
+                "version": 1,
+                "artifacts": [
+                    {
+                        "path": "[..]/target/debug/foo[EXE]",
+                        "package_id": "foo 0.5.0 ([..])",
+                        "target": {
+                            "kind": ["bin"],
+                            "crate_types": ["bin"],
+                            "name": "foo",
+                            "src_path": "[..]/src/main.rs",
+                            "edition": "2015",
+                            "doc": true,
+                            "doctest": false,
+                            "test": true
+                        },
+                        "mode": "build",
+                        "features": [],
+                        "hash": "[..]",
+                        "flavor": "normal",
+                        "artifact_dep": false
+                    }
+                ]
+            }
+        "#,
+    )
+    .unwrap();
+    let actual = serde_json::from_str(&p.read_file("target/debug/.artifacts.json")).unwrap();
+    if cfg!(target_os = "linux") {
+        find_json_mismatch(&expected, &actual, None).unwrap();
+    }
+}
+
+#[cargo_test]
+fn test_executables() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file("tests/t.rs", "")
+        .build();
+
+    p.cargo("test --no-run -Z artifact-manifest")
+        .masquerade_as_nightly_cargo(&["artifact-manifest"])
+        .run();
+    let actual: serde_json::Value =
+        serde_json::from_str(&p.read_file("target/debug/.artifacts.json")).unwrap();
+    let paths = artifact_paths(&actual);
+    assert!(paths.iter().any(|p| p.contains("/target/debug/deps/t-")));
+    assert!(paths.iter().any(|p| p.contains("/target/debug/deps/foo-")));
+
+    // Each build replaces the manifest.
+    p.cargo("build -Z artifact-manifest")
+        .masquerade_as_nightly_cargo(&["artifact-manifest"])
+        .run();
+    let actual: serde_json::Value =
+        serde_json::from_str(&p.read_file("target/debug/.artifacts.json")).unwrap();
+    let paths = artifact_paths(&actual);
+    assert!(!paths.iter().any(|p| p.contains("/deps/")), "{:?}", paths);
+    assert!(paths
+        .iter()
+        .any(|p| p.ends_with("/target/debug/libfoo.rlib")));
+}
+
+#[cargo_test]
+fn artifact_dep() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = { path = "bar", artifact = "bin" }
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.5.0"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -Z bindeps -Z artifact-manifest")
+        .masquerade_as_nightly_cargo(&["bindeps", "artifact-manifest"])
+        .run();
+    let actual: serde_json::Value =
+        serde_json::from_str(&p.read_file("target/debug/.artifacts.json")).unwrap();
+    let artifacts = actual["artifacts"].as_array().unwrap();
+    let bar = artifacts
+        .iter()
+        .find(|a| a["package_id"].as_str().unwrap().starts_with("bar "))
+        .unwrap();
+    assert_eq!(bar["artifact_dep"], true);
+    let path = bar["path"].as_str().unwrap().replace('\\', "/");
+    assert!(
+        path.contains("/target/debug/deps/artifact/bar-"),
+        "{}",
+        path
+    );
+    assert!(path.contains(bar["hash"].as_str().unwrap()), "{}", path);
+    assert!(std::path::Path::new(&path).exists());
+    assert!(artifact_paths(&actual)
+        .iter()
+        .any(|p| p.ends_with("/target/debug/foo") || p.ends_with("/target/debug/foo.exe")));
+}
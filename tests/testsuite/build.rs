@@ -135,6 +135,69 @@ fn incremental_config() {
         .run();
 }
 
+#[cargo_test]
+fn incremental_dir_per_branch() {
+    let p = project()
+        .file("src/main.rs", "fn main() {}")
+        .file(".git/HEAD", "ref: refs/heads/main\n")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                incremental-dir = "per-branch"
+            "#,
+        )
+        .build();
+
+    // The config is ignored without the unstable flag.
+    p.cargo("build -v")
+        .env("CARGO_INCREMENTAL", "1")
+        .with_stderr_contains(
+            "[RUNNING] `rustc [..] -C incremental=[..]/target/debug/incremental -L [..]`",
+        )
+        .run();
+
+    // Moving the incremental cache doesn't rebuild anything by itself.
+    p.cargo("build -v -Zincremental-dir")
+        .masquerade_as_nightly_cargo(&["incremental-dir"])
+        .env("CARGO_INCREMENTAL", "1")
+        .with_stderr_contains("[FRESH] foo v0.0.1 ([CWD])")
+        .run();
+    p.change_file("src/main.rs", "fn main() { println!(); }");
+    p.cargo("build -v -Zincremental-dir")
+        .masquerade_as_nightly_cargo(&["incremental-dir"])
+        .env("CARGO_INCREMENTAL", "1")
+        .with_stderr_contains(
+            "[RUNNING] `rustc [..] -C incremental=[..]/target/debug/incremental/branches/main -L [..]`",
+        )
+        .run();
+    assert!(p
+        .root()
+        .join("target/debug/incremental/branches/main")
+        .is_dir());
+
+    p.change_file(".git/HEAD", "ref: refs/heads/feature/x\n");
+    p.change_file("src/main.rs", "fn main() {}");
+    p.cargo("build -v -Zincremental-dir")
+        .masquerade_as_nightly_cargo(&["incremental-dir"])
+        .env("CARGO_INCREMENTAL", "1")
+        .with_stderr_contains(
+            "[RUNNING] `rustc [..] -C incremental=[..]/target/debug/incremental/branches/feature_x -L [..]`",
+        )
+        .run();
+
+    // A detached `HEAD` uses the shared directory.
+    p.change_file(".git/HEAD", "0123456789abcdef0123456789abcdef01234567\n");
+    p.change_file("src/main.rs", "fn main() { println!(); }");
+    p.cargo("build -v -Zincremental-dir")
+        .masquerade_as_nightly_cargo(&["incremental-dir"])
+        .env("CARGO_INCREMENTAL", "1")
+        .with_stderr_contains(
+            "[RUNNING] `rustc [..] -C incremental=[..]/target/debug/incremental -L [..]`",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn cargo_compile_with_workspace_excluded() {
     let p = project().file("src/main.rs", "fn main() {}").build();
@@ -5214,6 +5277,138 @@ fn uplift_pdb_of_bin_on_windows() {
     assert!(!p.target_debug_dir().join("d.pdb").exists());
 }
 
+#[cargo_test]
+#[cfg(target_os = "linux")]
+fn uplift_dwp_of_bin_on_linux() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [lib]
+                crate-type = ["rlib", "dylib"]
+
+                [profile.dev]
+                split-debuginfo = "packed"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "fn main() { panic!(); }")
+        .file("src/bin/foo-bar.rs", "fn main() { panic!(); }")
+        .file("examples/c.rs", "fn main() { panic!(); }")
+        .file("tests/d.rs", "fn main() { panic!(); }")
+        .build();
+
+    p.cargo("build --bins --examples --tests --message-format json")
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "compiler-artifact",
+                    "package_id": "foo 0.0.1 ([..])",
+                    "manifest_path": "[..]",
+                    "target": {
+                        "kind": ["bin"],
+                        "crate_types": ["bin"],
+                        "name": "foo",
+                        "src_path": "[..]/src/main.rs",
+                        "edition": "2015",
+                        "doc": true,
+                        "doctest": false,
+                        "test": true
+                    },
+                    "profile": "{...}",
+                    "features": [],
+                    "filenames": ["[..]/target/debug/foo", "[..]/target/debug/foo.dwp"],
+                    "executable": "[..]/target/debug/foo",
+                    "fresh": false
+                }
+            "#,
+        )
+        .run();
+    assert!(p.target_debug_dir().join("foo.dwp").is_file());
+    assert!(p.target_debug_dir().join("foo-bar.dwp").is_file());
+    assert!(p.target_debug_dir().join("libfoo.so.dwp").is_file());
+    assert!(p.target_debug_dir().join("examples/c.dwp").is_file());
+    assert!(!p.target_debug_dir().join("c.dwp").exists());
+    assert!(!p.target_debug_dir().join("d.dwp").exists());
+
+    // Without split debug info, there is nothing to uplift.
+    p.change_file("src/main.rs", "fn main() {}");
+    p.cargo("build --bin foo --message-format json")
+        .env("CARGO_PROFILE_DEV_SPLIT_DEBUGINFO", "off")
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "compiler-artifact",
+                    "package_id": "foo 0.0.1 ([..])",
+                    "manifest_path": "[..]",
+                    "target": "{...}",
+                    "profile": "{...}",
+                    "features": [],
+                    "filenames": ["[..]/target/debug/foo"],
+                    "executable": "[..]/target/debug/foo",
+                    "fresh": false
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn uplift_config() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "fn main() {}")
+        .file("examples/ex.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                uplift = ["bins"]
+            "#,
+        )
+        .build();
+
+    p.cargo("build --lib --bins --examples -Z uplift")
+        .masquerade_as_nightly_cargo(&["uplift"])
+        .run();
+    assert!(p.bin("foo").is_file());
+    assert!(!p.target_debug_dir().join("libfoo.rlib").exists());
+    assert!(!p.bin("examples/ex").exists());
+    assert!(p.glob("target/debug/examples/ex-*").count() > 0);
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build]
+            uplift = ["libs", "examples"]
+        "#,
+    );
+    p.cargo("clean").run();
+    p.cargo("build --lib --bins --examples -Z uplift")
+        .masquerade_as_nightly_cargo(&["uplift"])
+        .run();
+    assert!(!p.bin("foo").exists());
+    assert!(p.target_debug_dir().join("libfoo.rlib").is_file());
+    assert!(p.bin("examples/ex").is_file());
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build]
+            uplift = ["tests"]
+        "#,
+    );
+    p.cargo("build -Z uplift")
+        .masquerade_as_nightly_cargo(&["uplift"])
+        .with_status(101)
+        .with_stderr_contains("[..]unknown variant `tests`, expected one of [..]")
+        .run();
+}
+
 // Ensure that `cargo build` chooses the correct profile for building
 // targets based on filters (assuming `--profile` is not specified).
 #[cargo_test]
@@ -5527,6 +5722,39 @@ fn target_filters_workspace() {
         .run();
 }
 
+#[cargo_test]
+fn target_filters_workspace_glob() {
+    let ws = project()
+        .at("ws")
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["a", "b"]
+            "#,
+        )
+        .file("a/Cargo.toml", &basic_manifest("a", "0.1.0"))
+        .file("a/src/bin/tool-fmt.rs", "fn main() {}")
+        .file("a/src/bin/server.rs", "fn main() {}")
+        .file("b/Cargo.toml", &basic_manifest("b", "0.1.0"))
+        .file("b/src/bin/tool-lint.rs", "fn main() {}")
+        .file("b/examples/tool-demo.rs", "fn main() {}")
+        .build();
+
+    // A pattern selects the matching targets of every member.
+    ws.cargo("build -v --bin 'tool-*'")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name tool_fmt [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name tool_lint [..]")
+        .with_stderr_does_not_contain("[..]server[..]")
+        .with_stderr_does_not_contain("[..]tool_demo[..]")
+        .run();
+
+    ws.cargo("build -v --bin 'tool-*' --example 'tool-*'")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name tool_demo [..]")
+        .with_stderr_does_not_contain("[..]server[..]")
+        .run();
+}
+
 #[cargo_test]
 fn target_filters_workspace_not_found() {
     let ws = project()
@@ -0,0 +1,308 @@
+//! Tests for the `build.executor` config (`-Z build-executor`).
+
+use cargo_test_support::{basic_manifest, paths, project};
+use std::path::PathBuf;
+
+/// Builds an executor running the invocations locally, and logging the
+/// targets it compiles to the file in `EXECUTOR_LOG`.
+fn local_executor() -> PathBuf {
+    let p = project()
+        .at(paths::root().join("executor"))
+        .file("Cargo.toml", &basic_manifest("executor", "1.0.0"))
+        .file(
+            "src/main.rs",
+            r##"
+                use std::io::{Read, Write};
+                use std::process::Command;
+
+                enum Value {
+                    Null,
+                    Str(String),
+                    Arr(Vec<Value>),
+                    Obj(Vec<(String, Value)>),
+                    Other,
+                }
+
+                impl Value {
+                    fn get(&self, key: &str) -> &Value {
+                        match self {
+                            Value::Obj(fields) => &fields.iter().find(|f| f.0 == key).unwrap().1,
+                            _ => panic!("not an object"),
+                        }
+                    }
+
+                    fn str(&self) -> &str {
+                        match self {
+                            Value::Str(s) => s,
+                            _ => panic!("not a string"),
+                        }
+                    }
+                }
+
+                fn parse(s: &[u8], i: &mut usize) -> Value {
+                    match s[*i] {
+                        b'"' => Value::Str(parse_str(s, i)),
+                        b'[' | b'{' => {
+                            let object = s[*i] == b'{';
+                            let mut items = Vec::new();
+                            *i += 1;
+                            while s[*i] != b']' && s[*i] != b'}' {
+                                if s[*i] == b',' {
+                                    *i += 1;
+                                }
+                                let key = if object {
+                                    let key = parse_str(s, i);
+                                    *i += 1; // `:`
+                                    key
+                                } else {
+                                    String::new()
+                                };
+                                items.push((key, parse(s, i)));
+                            }
+                            *i += 1;
+                            if object {
+                                Value::Obj(items)
+                            } else {
+                                Value::Arr(items.into_iter().map(|item| item.1).collect())
+                            }
+                        }
+                        _ => {
+                            let start = *i;
+                            while !b",]}".contains(&s[*i]) {
+                                *i += 1;
+                            }
+                            if &s[start..*i] == b"null" { Value::Null } else { Value::Other }
+                        }
+                    }
+                }
+
+                fn parse_str(s: &[u8], i: &mut usize) -> String {
+                    let mut out = Vec::new();
+                    *i += 1;
+                    while s[*i] != b'"' {
+                        if s[*i] == b'\\' {
+                            *i += 1;
+                            match s[*i] {
+                                b'n' => out.push(b'\n'),
+                                b't' => out.push(b'\t'),
+                                b'u' => {
+                                    let hex = std::str::from_utf8(&s[*i + 1..*i + 5]).unwrap();
+                                    let c = char::from_u32(u32::from_str_radix(hex, 16).unwrap());
+                                    out.extend(c.unwrap().to_string().bytes());
+                                    *i += 4;
+                                }
+                                c => out.push(c),
+                            }
+                        } else {
+                            out.push(s[*i]);
+                        }
+                        *i += 1;
+                    }
+                    *i += 1;
+                    String::from_utf8(out).unwrap()
+                }
+
+                fn escape(s: &str) -> String {
+                    let mut out = String::new();
+                    for c in s.chars() {
+                        match c {
+                            '"' => out.push_str("\\\""),
+                            '\\' => out.push_str("\\\\"),
+                            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                            c => out.push(c),
+                        }
+                    }
+                    out
+                }
+
+                fn main() {
+                    let mut input = String::new();
+                    std::io::stdin().read_to_string(&mut input).unwrap();
+                    let request = parse(input.trim().as_bytes(), &mut 0);
+
+                    let mut log = std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(std::env::var("EXECUTOR_LOG").unwrap())
+                        .unwrap();
+                    writeln!(log, "{} {}", request.get("target").str(), request.get("mode").str())
+                        .unwrap();
+
+                    let mut cmd = Command::new(request.get("program").str());
+                    if let Value::Arr(args) = request.get("args") {
+                        cmd.args(args.iter().map(Value::str));
+                    }
+                    if let Value::Obj(env) = request.get("env") {
+                        for (key, value) in env {
+                            match value {
+                                Value::Null => cmd.env_remove(key),
+                                value => cmd.env(key, value.str()),
+                            };
+                        }
+                    }
+                    if let Value::Str(cwd) = request.get("cwd") {
+                        cmd.current_dir(cwd);
+                    }
+                    let output = cmd.output().unwrap();
+                    for (reason, out) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+                        for line in String::from_utf8_lossy(out).lines() {
+                            println!(r#"{{"reason":"{}","line":"{}"}}"#, reason, escape(line));
+                        }
+                    }
+                    println!(r#"{{"reason":"exit","code":{}}}"#, output.status.code().unwrap());
+                }
+            "##,
+        )
+        .build();
+    p.cargo("build").run();
+    p.bin("executor")
+}
+
+#[cargo_test]
+fn build_with_executor() {
+    let executor = local_executor();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::bar()); }"#,
+        )
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file(
+            "bar/src/lib.rs",
+            r#"pub fn bar() -> &'static str { "remote" }"#,
+        )
+        .build();
+
+    p.cargo("run -Zbuild-executor")
+        .masquerade_as_nightly_cargo(&["build-executor"])
+        .env("CARGO_BUILD_EXECUTOR", &executor)
+        .env("EXECUTOR_LOG", p.root().join("executor.log"))
+        .with_stdout("remote")
+        .with_stderr(
+            "\
+[COMPILING] bar v0.1.0 ([CWD]/bar)
+[COMPILING] foo v0.1.0 ([CWD])
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]
+[RUNNING] `target/debug/foo[EXE]`",
+        )
+        .run();
+    assert_eq!(p.read_file("executor.log"), "bar build\nfoo build\n");
+
+    // Up-to-date units aren't handed to the executor.
+    p.cargo("build -Zbuild-executor")
+        .masquerade_as_nightly_cargo(&["build-executor"])
+        .env("CARGO_BUILD_EXECUTOR", &executor)
+        .env("EXECUTOR_LOG", p.root().join("executor.log"))
+        .with_stderr("[FINISHED] [..]")
+        .run();
+    assert_eq!(p.read_file("executor.log"), "bar build\nfoo build\n");
+}
+
+#[cargo_test]
+fn executor_compile_error() {
+    let executor = local_executor();
+    let p = project().file("src/lib.rs", "pub fn foo() { bar }").build();
+
+    p.cargo("check -Zbuild-executor")
+        .masquerade_as_nightly_cargo(&["build-executor"])
+        .env("CARGO_BUILD_EXECUTOR", &executor)
+        .env("EXECUTOR_LOG", p.root().join("executor.log"))
+        .with_status(101)
+        .with_stderr_contains("error[E0425]: cannot find value `bar` in this scope")
+        .with_stderr_contains("[ERROR] could not compile `foo` due to previous error")
+        .run();
+    assert_eq!(p.read_file("executor.log"), "foo check\n");
+}
+
+/// A package whose request is larger than a pipe buffer, because of its
+/// description, so the executor can exit before reading all of it.
+fn large_request_project(executor: &str) -> cargo_test_support::Project {
+    project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+                    description = "{}"
+                "#,
+                "x".repeat(1 << 20)
+            ),
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            &format!("build.executor = {}", executor),
+        )
+        .build()
+}
+
+#[cargo_test]
+fn executor_invalid_message() {
+    let p = large_request_project("['echo', 'hello']");
+
+    p.cargo("check -Zbuild-executor")
+        .masquerade_as_nightly_cargo(&["build-executor"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[CHECKING] foo v0.0.1 ([CWD])
+[ERROR] could not compile `foo`
+
+Caused by:
+  build executor failed to run `rustc --crate-name foo [..]`
+
+Caused by:
+  invalid message from the build executor: `hello`
+
+Caused by:
+  [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn executor_exits_without_reading_request() {
+    let p = large_request_project("['sh', '-c', 'exit 3']");
+
+    p.cargo("check -v -Zbuild-executor")
+        .masquerade_as_nightly_cargo(&["build-executor"])
+        .with_status(101)
+        .with_stderr_contains(
+            "  process didn't exit successfully: `sh -c 'exit 3'` (exit status: 3)",
+        )
+        .with_stderr_does_not_contain("[..]never executed[..]")
+        .run();
+}
+
+#[cargo_test]
+fn executor_requires_nightly() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(".cargo/config.toml", "build.executor = 'executor'")
+        .build();
+
+    p.cargo("check")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `build.executor` config requires `-Z build-executor`
+See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#build-executor \
+for more information about the `build.executor` config.
+",
+        )
+        .run();
+}
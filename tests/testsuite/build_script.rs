@@ -959,6 +959,135 @@ also links to native library `a`
         .run();
 }
 
+fn links_manifest(name: &str, links: &str) -> String {
+    format!(
+        r#"
+            [package]
+            name = "{name}"
+            version = "0.5.0"
+            links = "{links}"
+        "#
+    )
+}
+
+#[cargo_test]
+fn links_overrides_package() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["links-overrides"]
+
+                [package]
+                name = "foo"
+                version = "0.5.0"
+
+                [dependencies]
+                a-sys = { path = "a-sys" }
+                b-sys = { path = "b-sys" }
+
+                [links-overrides]
+                z = { package = "a-sys" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("a-sys/Cargo.toml", &links_manifest("a-sys", "z"))
+        .file("a-sys/src/lib.rs", "")
+        .file(
+            "a-sys/build.rs",
+            r#"fn main() { println!("cargo:warning=a-sys build script"); }"#,
+        )
+        .file("b-sys/Cargo.toml", &links_manifest("b-sys", "z"))
+        .file("b-sys/src/lib.rs", "")
+        .file(
+            "b-sys/build.rs",
+            r#"fn main() { panic!("should not run"); }"#,
+        )
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo(&["links-overrides"])
+        .with_stderr_unordered(
+            "\
+[COMPILING] a-sys v0.5.0 ([..])
+[COMPILING] b-sys v0.5.0 ([..])
+[COMPILING] foo v0.5.0 ([..])
+[WARNING] a-sys build script
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn links_overrides_output() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["links-overrides"]
+
+                [package]
+                name = "foo"
+                version = "0.5.0"
+
+                [dependencies]
+                a-sys = { path = "a-sys" }
+                b-sys = { path = "b-sys" }
+
+                [links-overrides.z]
+                rustc-cfg = ["shared_z"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("a-sys/Cargo.toml", &links_manifest("a-sys", "z"))
+        .file(
+            "a-sys/src/lib.rs",
+            r#"#[cfg(not(shared_z))] compile_error!("missing cfg");"#,
+        )
+        .file(
+            "a-sys/build.rs",
+            r#"fn main() { panic!("should not run"); }"#,
+        )
+        .file("b-sys/Cargo.toml", &links_manifest("b-sys", "z"))
+        .file(
+            "b-sys/src/lib.rs",
+            r#"#[cfg(not(shared_z))] compile_error!("missing cfg");"#,
+        )
+        .file(
+            "b-sys/build.rs",
+            r#"fn main() { panic!("should not run"); }"#,
+        )
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo(&["links-overrides"])
+        .run();
+}
+
+#[cargo_test]
+fn links_overrides_requires_nightly() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.5.0"
+
+                [links-overrides]
+                z = { package = "foo" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .with_status(101)
+        .with_stderr_contains("[..]feature `links-overrides` is required[..]")
+        .run();
+}
+
 #[cargo_test]
 fn links_duplicates_deep_dependency() {
     // this tests that the links_duplicates are caught at resolver time
@@ -1188,6 +1317,56 @@ fn links_passes_env_vars() {
     p.cargo("build -v").run();
 }
 
+#[cargo_test]
+fn links_passes_artifact_uses_without_bindeps() {
+    // `cargo:artifact-uses` is only an instruction with `-Zbindeps`.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.5.0"
+                authors = []
+                build = "build.rs"
+
+                [dependencies.a]
+                path = "a"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"
+                use std::env;
+                fn main() {
+                    assert_eq!(env::var("DEP_FOO_ARTIFACT_USES").unwrap(), "bar");
+                }
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.5.0"
+                authors = []
+                links = "foo"
+                build = "build.rs"
+            "#,
+        )
+        .file("a/src/lib.rs", "")
+        .file(
+            "a/build.rs",
+            r#"fn main() { println!("cargo:artifact-uses=bar"); }"#,
+        )
+        .build();
+
+    p.cargo("build")
+        .with_stderr_contains("[WARNING] cargo:artifact-uses requires -Zbindeps flag")
+        .run();
+}
+
 #[cargo_test]
 fn only_rerun_build_script() {
     let p = project()
@@ -3948,6 +4127,200 @@ fn cfg_env_vars_available() {
     p.cargo("bench").run();
 }
 
+#[cargo_test]
+fn cfg_json_available() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r##"
+                use std::env;
+
+                fn main() {
+                    let json = match env::var("CARGO_CFG_JSON") {
+                        Ok(json) => json,
+                        Err(_) => return println!("cargo:warning=no CARGO_CFG_JSON"),
+                    };
+                    assert!(json.starts_with(r#"{"names":["#), "{}", json);
+                    let family = if cfg!(unix) { "unix" } else { "windows" };
+                    assert!(json.contains(&format!(r#""{}""#, family)), "{}", json);
+                    assert!(
+                        json.contains(&format!(r#""target_family":["{}""#, family)),
+                        "{}",
+                        json
+                    );
+                    assert!(json.contains(r#""my_key":["#), "{}", json);
+                    assert!(json.contains(r#""a""#) && json.contains(r#""b""#), "{}", json);
+                    assert!(json.contains(r#""my_name""#), "{}", json);
+                    assert!(!json.contains("debug_assertions"), "{}", json);
+                }
+            "##,
+        )
+        .build();
+
+    p.cargo("build")
+        .env(
+            "RUSTFLAGS",
+            r#"--cfg my_name --cfg my_key="a" --cfg my_key="b""#,
+        )
+        .with_stderr_contains("[WARNING] no CARGO_CFG_JSON")
+        .run();
+
+    p.cargo("build -Zcfg-json")
+        .masquerade_as_nightly_cargo(&["cfg-json"])
+        .env("CARGO_TARGET_DIR", "target-cfg-json")
+        .env(
+            "RUSTFLAGS",
+            r#"--cfg my_name --cfg my_key="a" --cfg my_key="b""#,
+        )
+        .with_stderr_does_not_contain("[WARNING] [..]")
+        .run();
+}
+
+#[cargo_test]
+fn target_spec_path() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r##"
+                use std::env;
+
+                fn main() {
+                    let path = match env::var("CARGO_TARGET_SPEC_PATH") {
+                        Ok(path) => path,
+                        Err(_) => return println!("cargo:warning=no CARGO_TARGET_SPEC_PATH"),
+                    };
+                    let spec = std::fs::read_to_string(&path).unwrap();
+                    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+                    let os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+                    let width = env::var("CARGO_CFG_TARGET_POINTER_WIDTH").unwrap();
+                    assert!(spec.contains(&format!(r#""arch": "{}""#, arch)), "{}", spec);
+                    assert!(spec.contains(&format!(r#""os": "{}""#, os)), "{}", spec);
+                    assert!(
+                        spec.contains(&format!(r#""target-pointer-width": {}"#, width))
+                            || spec.contains(&format!(r#""target-pointer-width": "{}""#, width)),
+                        "{}",
+                        spec
+                    );
+                    assert!(spec.contains(r#""llvm-target": "#), "{}", spec);
+                }
+            "##,
+        )
+        .build();
+
+    p.cargo("build")
+        .with_stderr_contains("[WARNING] no CARGO_TARGET_SPEC_PATH")
+        .run();
+
+    p.cargo("build -Ztarget-spec-path")
+        .masquerade_as_nightly_cargo(&["target-spec-path"])
+        .env("CARGO_TARGET_DIR", "target-spec")
+        .with_stderr_does_not_contain("[WARNING] [..]")
+        .run();
+}
+
+#[cfg(unix)]
+#[cargo_test]
+fn system_deps() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [package.metadata.system-deps]
+                zlib = "1.2"
+                ssl = { name = "openssl", optional = true }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"
+                use std::env;
+
+                fn main() {
+                    let version = match env::var("CARGO_SYSTEM_DEP_ZLIB_VERSION") {
+                        Ok(version) => version,
+                        Err(_) => return println!("cargo:warning=zlib not probed"),
+                    };
+                    assert_eq!(version, "1.2.13");
+                    assert_eq!(env::var("CARGO_SYSTEM_DEP_ZLIB_LIBS").unwrap(), "z");
+                    assert_eq!(
+                        env::var("CARGO_SYSTEM_DEP_ZLIB_LINK_PATHS").unwrap(),
+                        "/opt/zlib/lib"
+                    );
+                    assert_eq!(
+                        env::var("CARGO_SYSTEM_DEP_ZLIB_INCLUDE_PATHS").unwrap(),
+                        "/opt/zlib/include"
+                    );
+                    assert!(env::var("CARGO_SYSTEM_DEP_SSL_VERSION").is_err());
+                }
+            "#,
+        )
+        .executable(
+            "pkg-config",
+            r#"#!/bin/sh
+                case "$*" in
+                    "--atleast-version=1.2 zlib") ;;
+                    "--modversion zlib") echo 1.2.13 ;;
+                    "--libs --cflags zlib") echo "-L/opt/zlib/lib -lz -I/opt/zlib/include" ;;
+                    *) echo "Package $* was not found" >&2; exit 1 ;;
+                esac
+            "#,
+        )
+        .build();
+
+    p.cargo("build")
+        .with_stderr_contains("[WARNING] zlib not probed")
+        .run();
+
+    p.cargo("build -v -Zsystem-deps")
+        .masquerade_as_nightly_cargo(&["system-deps"])
+        .env("CARGO_TARGET_DIR", "target-system-deps")
+        .env("PKG_CONFIG", p.root().join("pkg-config"))
+        .with_stderr_does_not_contain("[WARNING] [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-L native=/opt/zlib/lib -l z`")
+        .run();
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [package.metadata.system-deps]
+            zlib = "1.3"
+        "#,
+    );
+    p.cargo("build -Zsystem-deps")
+        .masquerade_as_nightly_cargo(&["system-deps"])
+        .env("CARGO_TARGET_DIR", "target-system-deps")
+        .env("PKG_CONFIG", p.root().join("pkg-config"))
+        .with_status(101)
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+error: failed to find the system library `zlib` required by `foo v0.0.1 ([CWD])`
+
+Caused by:
+  `zlib` must be at least version 1.3 (`zlib` in `package.metadata.system-deps`)
+
+Caused by:
+  process didn't exit successfully: [..]
+  --- stderr
+  Package --atleast-version=1.3 zlib was not found
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn switch_features_rerun() {
     let p = project()
@@ -4950,3 +5323,198 @@ fn custom_build_closes_stdin() {
         .build();
     p.cargo("build").run();
 }
+
+#[cargo_test]
+fn script_output_always_and_never() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    println!("cargo:rerun-if-env-changed=FAIL");
+                    println!("cargo:rustc-cfg=from_script");
+                    eprintln!("message on stderr");
+                    if std::env::var("FAIL").is_ok() {
+                        std::process::exit(1);
+                    }
+                }
+            "#,
+        )
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                script-output = "always"
+            "#,
+        )
+        .build();
+
+    // The config is ignored without `-Z script-output`.
+    p.cargo("check")
+        .with_stderr_does_not_contain("[..]message on stderr")
+        .run();
+
+    p.cargo("check -Zscript-output")
+        .masquerade_as_nightly_cargo(&["script-output"])
+        .env("FAIL", "0")
+        .with_status(101)
+        .with_stdout(
+            "\
+[foo 0.0.1] cargo:rerun-if-env-changed=FAIL
+[foo 0.0.1] cargo:rustc-cfg=from_script",
+        )
+        .with_stderr_contains("[foo 0.0.1] message on stderr")
+        .run();
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build]
+            script-output = "never"
+        "#,
+    );
+    p.cargo("check -Zscript-output")
+        .masquerade_as_nightly_cargo(&["script-output"])
+        .env("FAIL", "0")
+        .with_status(101)
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[ERROR] failed to run custom build command for `foo v0.0.1 ([CWD])`
+
+Caused by:
+  process didn't exit successfully: `[..]build-script-build` (exit [..]: 1)
+",
+        )
+        .run();
+
+    // The output of the failed run is saved nonetheless.
+    p.cargo("report build-script-output")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo report build-script-output` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+    p.cargo("report build-script-output -p foo -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+foo ([CWD]/target/debug/build/foo-[..]), run at [..]Z
+--- stdout
+cargo:rerun-if-env-changed=FAIL
+cargo:rustc-cfg=from_script
+--- stderr
+message on stderr
+",
+        )
+        .run();
+
+    p.cargo("report build-script-output -p bar -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] no build script output found for `bar`
+Build scripts save their output when they are run by a build.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn build_manifest() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file("input.txt", "1")
+        .file(
+            "build.rs",
+            r##"
+                use std::path::Path;
+
+                fn main() {
+                    let out_dir = std::env::var("OUT_DIR").unwrap();
+                    let out_dir = Path::new(&out_dir);
+                    std::fs::write(
+                        out_dir.join("manifest.json"),
+                        r#"{"inputs": ["input.txt"], "env": ["BUILD_MODE"], "outputs": ["gen.rs"]}"#,
+                    )
+                    .unwrap();
+                    if std::env::var("SKIP_GEN").is_err() {
+                        std::fs::write(out_dir.join("gen.rs"), "").unwrap();
+                    }
+                    println!("cargo:build-manifest=manifest.json");
+                }
+            "##,
+        )
+        .build();
+
+    p.cargo("check")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[WARNING] cargo:build-manifest requires -Zbuild-manifest flag
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.cargo("check -Zbuild-manifest -v")
+        .masquerade_as_nightly_cargo(&["build-manifest"])
+        .with_stderr_contains("[RUNNING] `[..]/build-script-build`")
+        .run();
+    p.cargo("check -Zbuild-manifest -v")
+        .masquerade_as_nightly_cargo(&["build-manifest"])
+        .with_stderr_contains("[FRESH] foo v0.0.1 ([CWD])")
+        .run();
+
+    // Declared inputs, env vars and outputs all rerun the script.
+    sleep_ms(1000);
+    p.change_file("input.txt", "2");
+    p.cargo("check -Zbuild-manifest -v")
+        .masquerade_as_nightly_cargo(&["build-manifest"])
+        .with_stderr_contains("[RUNNING] `[..]/build-script-build`")
+        .run();
+    p.cargo("check -Zbuild-manifest -v")
+        .masquerade_as_nightly_cargo(&["build-manifest"])
+        .env("BUILD_MODE", "release")
+        .with_stderr_contains("[RUNNING] `[..]/build-script-build`")
+        .run();
+    let gen = glob::glob(
+        &p.root()
+            .join("target/debug/build/foo-*/out/gen.rs")
+            .to_string_lossy(),
+    )
+    .unwrap()
+    .next()
+    .unwrap()
+    .unwrap();
+    fs::remove_file(&gen).unwrap();
+    p.cargo("check -Zbuild-manifest -v")
+        .masquerade_as_nightly_cargo(&["build-manifest"])
+        .env("BUILD_MODE", "release")
+        .with_stderr_contains("[RUNNING] `[..]/build-script-build`")
+        .run();
+    assert!(gen.exists());
+
+    p.cargo("build --unit-graph -Zunstable-options -Zbuild-manifest")
+        .masquerade_as_nightly_cargo(&["unit-graph", "build-manifest"])
+        .with_stdout_contains(
+            r#"[..]"build_manifest":{"inputs":["input.txt"],"env":["BUILD_MODE"],"outputs":["gen.rs"]}[..]"#,
+        )
+        .run();
+
+    fs::remove_file(&gen).unwrap();
+    p.cargo("check -Zbuild-manifest")
+        .masquerade_as_nightly_cargo(&["build-manifest"])
+        .env("BUILD_MODE", "release")
+        .env("SKIP_GEN", "1")
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] build script of `foo v0.0.1 ([CWD])` declared the output `gen.rs` \
+             in its build manifest, but did not create it",
+        )
+        .run();
+}
@@ -0,0 +1,119 @@
+//! Tests for `--warnings` and `build.warnings`.
+
+use cargo_test_support::{basic_manifest, project, Project};
+
+fn warning_project() -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+
+                [workspace]
+                exclude = ["bar"]
+            "#,
+        )
+        .file("src/lib.rs", "fn unused_foo() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "fn unused_bar() {}")
+        .build()
+}
+
+#[cargo_test]
+fn deny() {
+    let p = warning_project();
+
+    p.cargo("check -Zwarnings --warnings=deny")
+        .masquerade_as_nightly_cargo(&["warnings"])
+        .with_status(101)
+        .with_stderr_contains("[WARNING] `bar` (lib) generated 1 warning")
+        .with_stderr_contains("[WARNING] function `unused_foo` is never used")
+        .with_stderr_contains(
+            "[ERROR] could not compile `foo` because warnings are denied (1 warning emitted)",
+        )
+        .run();
+
+    p.cargo("check -Zwarnings --warnings=warn")
+        .masquerade_as_nightly_cargo(&["warnings"])
+        .with_stderr_contains("[WARNING] `foo` (lib) generated 1 warning")
+        .run();
+
+    // Denied warnings of fresh units fail the build as well, switching the
+    // handling doesn't need a rebuild.
+    p.cargo("check -Zwarnings --warnings=deny")
+        .masquerade_as_nightly_cargo(&["warnings"])
+        .with_status(101)
+        .with_stderr_does_not_contain("[CHECKING] [..]")
+        .with_stderr_contains(
+            "[ERROR] could not compile `foo` because warnings are denied (1 warning emitted)",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn allow() {
+    let p = warning_project();
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build]
+            warnings = "allow"
+        "#,
+    );
+
+    p.cargo("check -Zwarnings")
+        .masquerade_as_nightly_cargo(&["warnings"])
+        .with_stderr(
+            "\
+[CHECKING] bar v0.1.0 ([..])
+[WARNING] function `unused_bar` is never used
+[..]
+[..]
+[..]
+[..]
+[..]
+[..]
+
+[WARNING] `bar` (lib) generated 1 warning
+[CHECKING] foo v0.1.0 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // The flag overrides the config.
+    p.cargo("check -Zwarnings --warnings=deny")
+        .masquerade_as_nightly_cargo(&["warnings"])
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] could not compile `foo` because warnings are denied (1 warning emitted)",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn requires_unstable() {
+    let p = warning_project();
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build]
+            warnings = "deny"
+        "#,
+    );
+
+    // The config is ignored on stable.
+    p.cargo("check")
+        .with_stderr_contains("[WARNING] `foo` (lib) generated 1 warning")
+        .run();
+
+    p.cargo("check --warnings=deny")
+        .with_status(101)
+        .with_stderr("[ERROR] the `--warnings` flag is unstable, pass `-Z warnings` to enable it")
+        .run();
+}
@@ -432,3 +432,142 @@ To pass the arguments to the subcommand, remove `--`
         )
         .run();
 }
+
+#[cargo_test]
+fn alias_template_requires_z_flag() {
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config",
+            r#"
+                [alias.b-bin]
+                command = "build --bin {1}"
+            "#,
+        )
+        .build();
+
+    p.cargo("b-bin foo")
+        .masquerade_as_nightly_cargo(&["alias-templates"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the table form of alias `b-bin` is unstable, \
+             pass `-Z alias-templates` to enable it",
+        )
+        .run();
+
+    // String aliases are unaffected.
+    p.cargo("build -v")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]")
+        .run();
+}
+
+#[cargo_test]
+fn alias_template_placeholders() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [features]
+                extra = []
+            "#,
+        )
+        .file("src/bin/a.rs", "fn main() {}")
+        .file(
+            "src/bin/b.rs",
+            r#"#[cfg(not(feature = "extra"))] compile_error!("no extra"); fn main() {}"#,
+        )
+        .file(
+            ".cargo/config",
+            r#"
+                [alias.b-bin]
+                command = ["build", "--bin", "{1}", "--features={2}"]
+                description = "Builds one binary with a feature"
+            "#,
+        )
+        .build();
+
+    p.cargo("-Zalias-templates b-bin b extra -v")
+        .masquerade_as_nightly_cargo(&["alias-templates"])
+        .with_stderr(
+            "\
+[COMPILING] foo v0.1.0 ([CWD])
+[RUNNING] `rustc --crate-name b [..]--cfg 'feature=\"extra\"'[..]
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.cargo("-Zalias-templates b-bin a")
+        .masquerade_as_nightly_cargo(&["alias-templates"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] alias `b-bin` requires 2 arguments, but 1 was given
+
+Run `cargo help b-bin` for more information.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn alias_template_from_config() {
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config",
+            r#"
+                [unstable]
+                alias-templates = true
+
+                [alias.c-as]
+                command = "check --message-format {1}"
+            "#,
+        )
+        .build();
+
+    p.cargo("c-as short -v")
+        .masquerade_as_nightly_cargo(&["alias-templates"])
+        .with_stderr_contains("[RUNNING] `rustc [..]--error-format=json[..]")
+        .run();
+}
+
+#[cargo_test]
+fn alias_template_help_and_list() {
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config",
+            r#"
+                [alias]
+                plain = "build --release"
+
+                [alias.b-bin]
+                command = "build --bin {1}"
+                description = "Builds the binary given as first argument."
+            "#,
+        )
+        .build();
+
+    p.cargo("help b-bin")
+        .with_stdout(
+            "\
+`b-bin` is aliased to `build --bin {1}`
+
+Builds the binary given as first argument.
+",
+        )
+        .run();
+
+    p.cargo("--list")
+        .with_stdout_contains("    b-bin                Builds the binary given as first argument.")
+        .with_stdout_contains("    plain                alias: build --release")
+        .run();
+}
@@ -296,7 +296,10 @@ fn show_origin_toml_cli() {
         .cwd(&sub_folder.parent().unwrap())
         .masquerade_as_nightly_cargo(&["cargo-config"])
         .env("CARGO_BUILD_JOBS", "1")
-        .with_stdout("build.jobs = 123 # --config cli option")
+        .with_stdout(
+            "build.jobs = 123 # --config cli option, \
+             overriding environment variable `CARGO_BUILD_JOBS`, [ROOT]/home/.cargo/config.toml",
+        )
         .with_stderr("")
         .run();
 
@@ -324,11 +327,177 @@ build.rustflags = [
 #[cargo_test]
 fn show_origin_json() {
     let sub_folder = common_setup();
-    cargo_process("config get --show-origin --format=json -Zunstable-options")
+    cargo_process("config get --show-origin --format=json build -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_json(
+            r#"
+            {
+              "build": {
+                "jobs": {"value": 99, "origin": "[ROOT]/home/.cargo/config.toml"},
+                "rustflags": [
+                  {"value": "--flag-directory", "origin": "[ROOT]/foo/.cargo/config.toml"},
+                  {"value": "--flag-global", "origin": "[ROOT]/home/.cargo/config.toml"}
+                ]
+              }
+            }
+            "#,
+        )
+        .with_stderr("")
+        .run();
+
+    cargo_process("config get --show-origin --format=json build.jobs -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .env("CARGO_BUILD_JOBS", "100")
+        .with_json(
+            r#"
+            {
+              "build": {
+                "jobs": {
+                  "value": 100,
+                  "origin": "environment variable `CARGO_BUILD_JOBS`",
+                  "overrides": ["[ROOT]/home/.cargo/config.toml"]
+                }
+              }
+            }
+            "#,
+        )
+        .with_stderr("")
+        .run();
+
+    cargo_process("config get --show-origin --format=json-value build.jobs -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_status(101)
+        .with_stderr(
+            "error: the `json-value` format does not support --show-origin, \
+             try the `json` format instead",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn show_origin_toml_overrides() {
+    let sub_folder = common_setup();
+    write_config_at(
+        sub_folder.join("config.toml"),
+        "
+        [build]
+        jobs = 4
+        ",
+    );
+    cargo_process("config get --show-origin build.jobs -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stdout(
+            "build.jobs = 4 # [ROOT]/foo/.cargo/config.toml, \
+             overriding [ROOT]/home/.cargo/config.toml",
+        )
+        .with_stderr("")
+        .run();
+}
+
+#[cargo_test]
+fn set_existing_file() {
+    let sub_folder = common_setup();
+    let home_config = paths::home().join(".cargo/config.toml");
+    // The key is defined in the home config, so that is the file edited.
+    cargo_process("config set build.jobs 8 -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stderr("[SETTING] build.jobs = 8 in [ROOT]/home/.cargo/config.toml")
+        .run();
+    let contents = fs::read_to_string(&home_config).unwrap();
+    assert!(contents.contains("jobs = 8\n"), "{}", contents);
+    assert!(contents.contains("# How unknown keys are handled."));
+
+    // New keys go to the config in the current directory, with a table header
+    // created as needed.
+    cargo_process("config set profile.release.lto thin -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stderr("[SETTING] profile.release.lto = \"thin\" in [ROOT]/foo/.cargo/config.toml")
+        .run();
+    cargo_process("config set -Zunstable-options")
+        .arg("target.'cfg(unix)'.rustflags")
+        .arg("[\"-Cfoo\"]")
         .cwd(&sub_folder.parent().unwrap())
         .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stderr(
+            "[SETTING] target.\"cfg(unix)\".rustflags = [\"-Cfoo\"] in [ROOT]/foo/.cargo/config.toml",
+        )
+        .run();
+    cargo_process("config get --show-origin -Zunstable-options")
+        .arg("target.cfg(unix)")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stdout(
+            "\
+target.\"cfg(unix)\".rustflags = [
+    \"-Cfoo\", # [ROOT]/foo/.cargo/config.toml
+]
+",
+        )
+        .run();
+    assert_eq!(
+        fs::read_to_string(sub_folder.join("config.toml")).unwrap(),
+        "
+        [alias]
+        sub-example = [\"sub\", \"example\"]
+        [build]
+        rustflags = [\"--flag-directory\"]
+
+[profile.release]
+lto = \"thin\"
+
+[target.'cfg(unix)']
+rustflags = [\"-Cfoo\"]
+        "
+    );
+}
+
+#[cargo_test]
+fn set_file_and_global() {
+    let dir = paths::root().join("foo");
+    fs::create_dir_all(&dir).unwrap();
+    cargo_process("config set --global net.git-fetch-with-cli true -Zunstable-options")
+        .cwd(&dir)
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stderr("[SETTING] net.git-fetch-with-cli = true in [ROOT]/home/.cargo/config.toml")
+        .run();
+    cargo_process("config set --file custom.toml build.jobs 2 -Zunstable-options")
+        .cwd(&dir)
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stderr("[SETTING] build.jobs = 2 in [ROOT]/foo/custom.toml")
+        .run();
+    assert_eq!(
+        fs::read_to_string(paths::home().join(".cargo/config.toml")).unwrap(),
+        "[net]\ngit-fetch-with-cli = true\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join("custom.toml")).unwrap(),
+        "[build]\njobs = 2\n"
+    );
+
+    // Tables cannot be replaced with a value.
+    cargo_process("config set --file custom.toml build 2 -Zunstable-options")
+        .cwd(&dir)
+        .masquerade_as_nightly_cargo(&["cargo-config"])
         .with_status(101)
-        .with_stderr("error: the `json` format does not support --show-origin, try the `toml` format instead")
+        .with_stderr(
+            "[ERROR] configuration key `build` is a table in [ROOT]/foo/custom.toml, \
+             only values can be set",
+        )
+        .run();
+    cargo_process("config set --file custom.toml build.jobs.x 2 -Zunstable-options")
+        .cwd(&dir)
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] expected table for configuration key `build.jobs`, \
+             but found integer in [ROOT]/foo/custom.toml",
+        )
         .run();
 }
 
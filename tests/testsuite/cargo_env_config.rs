@@ -153,3 +153,133 @@ fn env_no_override() {
         .with_stdout_contains("CARGO_PKG_NAME:unchanged")
         .run();
 }
+
+#[cargo_test]
+fn env_changes_trigger_rebuild() {
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file(
+            "src/main.rs",
+            r#"
+        fn main() {
+            println!( "compile-time:{}", env!("ENV_TEST_REBUILD") );
+        }
+        "#,
+        )
+        .file(
+            ".cargo/config",
+            r#"
+                [env]
+                ENV_TEST_REBUILD = "first"
+            "#,
+        )
+        .build();
+
+    p.cargo("run")
+        .with_stdout("compile-time:first")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.5.0 ([CWD])
+[FINISHED] [..]
+[RUNNING] `target/debug/foo[EXE]`
+",
+        )
+        .run();
+
+    // An unchanged value does not trigger a rebuild.
+    p.cargo("build").with_stderr("[FINISHED] [..]").run();
+
+    p.change_file(
+        ".cargo/config",
+        r#"
+            [env]
+            ENV_TEST_REBUILD = "second"
+        "#,
+    );
+    p.cargo("run")
+        .with_stdout("compile-time:second")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.5.0 ([CWD])
+[FINISHED] [..]
+[RUNNING] `target/debug/foo[EXE]`
+",
+        )
+        .run();
+
+    // A value from the environment wins unless `force` is set.
+    p.cargo("run")
+        .env("ENV_TEST_REBUILD", "from-env")
+        .with_stdout("compile-time:from-env")
+        .run();
+
+    p.change_file(
+        ".cargo/config",
+        r#"
+            [env]
+            ENV_TEST_REBUILD = { value = "forced", force = true }
+        "#,
+    );
+    p.cargo("run")
+        .env("ENV_TEST_REBUILD", "from-env")
+        .with_stdout("compile-time:forced")
+        .run();
+    p.cargo("build")
+        .env("ENV_TEST_REBUILD", "from-env")
+        .with_stderr("[FINISHED] [..]")
+        .run();
+}
+
+#[cargo_test]
+fn env_rerun_if_env_changed() {
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            "build.rs",
+            r#"
+        fn main() {
+            println!("cargo:rerun-if-env-changed=ENV_TEST_BUILD_SCRIPT");
+            println!("cargo:warning=value:{}", std::env::var("ENV_TEST_BUILD_SCRIPT").unwrap());
+        }
+        "#,
+        )
+        .file(
+            ".cargo/config",
+            r#"
+                [env]
+                ENV_TEST_BUILD_SCRIPT = { value = "build.rs", relative = true }
+            "#,
+        )
+        .build();
+
+    p.cargo("build")
+        .with_stderr_contains("[WARNING] value:[..]/foo/build.rs")
+        .run();
+    // The warning is replayed from the cached output.
+    p.cargo("build")
+        .with_stderr(
+            "\
+[WARNING] value:[..]/foo/build.rs
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.change_file(
+        ".cargo/config",
+        r#"
+            [env]
+            ENV_TEST_BUILD_SCRIPT = "changed"
+        "#,
+    );
+    p.cargo("build")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.5.0 ([CWD])
+[WARNING] value:changed
+[FINISHED] [..]
+",
+        )
+        .run();
+}
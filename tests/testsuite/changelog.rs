@@ -0,0 +1,189 @@
+//! Tests for the `cargo changelog` command.
+
+use cargo_test_support::registry::Package;
+use cargo_test_support::{git, project};
+
+/// Stages all files and commits them with the given message.
+fn commit(repo: &git2::Repository, message: &str) {
+    git::add(repo);
+    let tree = repo
+        .find_tree(repo.index().unwrap().write_tree().unwrap())
+        .unwrap();
+    let sig = repo.signature().unwrap();
+    let parent = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent])
+        .unwrap();
+}
+
+#[cargo_test]
+fn requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("changelog --since v0.1.0")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo changelog` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn conventional_commits_and_lock_bumps() {
+    Package::new("dep", "0.1.0").publish();
+    Package::new("other", "1.0.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [workspace]
+                members = ["bar"]
+
+                [dependencies]
+                dep = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "bar/Cargo.toml",
+            r#"
+                [package]
+                name = "bar"
+                version = "0.2.0"
+
+                [dependencies]
+                other = "1.0"
+            "#,
+        )
+        .file("bar/src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    let repo = git::init(&p.root());
+    git::add(&repo);
+    git::commit(&repo);
+    git::tag(&repo, "v0.1.0");
+
+    p.change_file("src/lib.rs", "pub fn parse() {}");
+    commit(&repo, "feat(parser): support comments");
+    p.change_file("bar/src/lib.rs", "pub fn bar() {}");
+    commit(&repo, "fix: don't crash on empty input");
+    p.change_file("bar/src/lib.rs", "pub fn baz() {}");
+    commit(
+        &repo,
+        "refactor!: rename `bar`\n\nBREAKING CHANGE: `bar` is now called `baz`",
+    );
+    p.change_file("src/lib.rs", "pub fn parse() {}\n");
+    commit(&repo, "Tweak formatting");
+
+    Package::new("dep", "0.1.1").publish();
+    p.cargo("update -p dep").run();
+    commit(&repo, "chore: update dependencies");
+
+    p.cargo("changelog --since v0.1.0 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+## bar 0.2.0
+
+### Breaking Changes
+
+- rename `bar` ([..])
+
+### Bug Fixes
+
+- don't crash on empty input ([..])
+
+## foo 0.1.0
+
+### Features
+
+- **parser:** support comments ([..])
+
+### Other Changes
+
+- update dependencies ([..])
+- Tweak formatting ([..])
+
+### Dependencies
+
+- Bump `dep` from 0.1.0 to 0.1.1
+",
+        )
+        .run();
+
+    p.cargo("changelog --since v0.1.0 --format json -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+  "since": "v0.1.0",
+  "members": [
+    {
+      "name": "bar",
+      "version": "0.2.0",
+      "id": "bar 0.2.0 (path+file://[..])",
+      "changes": [
+        {
+          "commit": "{...}",
+          "type": "refactor",
+          "scope": null,
+          "breaking": true,
+          "description": "rename `bar`"
+        },
+        {
+          "commit": "{...}",
+          "type": "fix",
+          "scope": null,
+          "breaking": false,
+          "description": "don't crash on empty input"
+        }
+      ],
+      "dependencies": []
+    },
+    {
+      "name": "foo",
+      "version": "0.1.0",
+      "id": "foo 0.1.0 (path+file://[..])",
+      "changes": [
+        {
+          "commit": "{...}",
+          "type": "chore",
+          "scope": null,
+          "breaking": false,
+          "description": "update dependencies"
+        },
+        {
+          "commit": "{...}",
+          "type": null,
+          "scope": null,
+          "breaking": false,
+          "description": "Tweak formatting"
+        },
+        {
+          "commit": "{...}",
+          "type": "feat",
+          "scope": "parser",
+          "breaking": false,
+          "description": "support comments"
+        }
+      ],
+      "dependencies": [
+        {
+          "name": "dep",
+          "from": ["0.1.0"],
+          "to": ["0.1.1"]
+        }
+      ]
+    }
+  ]
+}
+"#,
+        )
+        .run();
+}
@@ -1447,3 +1447,112 @@ fn check_fixable_mixed() {
         .with_stderr_contains("[..] (run `cargo fix --bench \"bench\"` to apply 1 suggestion)")
         .run();
 }
+
+#[cargo_test]
+fn check_build_sharing_reuses_build() {
+    Package::new("bar", "1.0.0")
+        .file("src/lib.rs", "pub fn bar() {}")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "pub fn foo() { bar::bar(); }")
+        .build();
+
+    p.cargo("build -Zcheck-build-sharing")
+        .masquerade_as_nightly_cargo(&["check-build-sharing"])
+        .with_stderr_contains("[COMPILING] bar v1.0.0")
+        .run();
+    p.cargo("check -v -Zcheck-build-sharing")
+        .masquerade_as_nightly_cargo(&["check-build-sharing"])
+        .with_stderr(
+            "\
+[FRESH] bar v1.0.0
+[FRESH] foo v0.1.0 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn check_build_sharing_build_after_check() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/main.rs", "fn main() { bar::bar(); }")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .build();
+
+    p.cargo("check -Zcheck-build-sharing")
+        .masquerade_as_nightly_cargo(&["check-build-sharing"])
+        .run();
+    // The rlib still has to be produced, `check` only wrote the metadata.
+    p.cargo("build -Zcheck-build-sharing")
+        .masquerade_as_nightly_cargo(&["check-build-sharing"])
+        .with_stderr(
+            "\
+[COMPILING] bar v0.1.0 ([CWD]/bar)
+[COMPILING] foo v0.1.0 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+    p.cargo("check -v -Zcheck-build-sharing")
+        .masquerade_as_nightly_cargo(&["check-build-sharing"])
+        .with_stderr_contains("[FRESH] bar v0.1.0 ([CWD]/bar)")
+        .run();
+}
+
+#[cargo_test]
+fn check_build_sharing_build_dependency() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+
+                [build-dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/lib.rs", "pub fn foo() { bar::bar(); }")
+        .file("build.rs", "fn main() { bar::bar(); }")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .build();
+
+    p.cargo("check -v -Zcheck-build-sharing")
+        .masquerade_as_nightly_cargo(&["check-build-sharing"])
+        .with_stderr_does_not_contain("[..]collision[..]")
+        // `bar` is only built, not checked as well.
+        .with_stderr_line_without(
+            &["[RUNNING] `rustc --crate-name bar"],
+            &["--emit=dep-info,metadata "],
+        )
+        .run();
+}
@@ -586,3 +586,107 @@ fn config_invalid() {
         .with_status(101)
         .run();
 }
+
+#[cargo_test(nightly, reason = "--check-cfg is unstable")]
+fn manifest_check_cfg() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["package-check-cfg"]
+
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [package.check-cfg]
+                names = ["has_simd"]
+                values = { backend = ["gl", "vulkan"] }
+
+                [features]
+                f_a = []
+            "#,
+        )
+        .file("src/lib.rs", "#[allow(dead_code)] fn foo() {}")
+        .build();
+
+    p.cargo("test -v --doc")
+        .masquerade_as_nightly_cargo(&["package-check-cfg"])
+        .with_stderr_contains(x!("rustc" => "names" of "has_simd"))
+        .with_stderr_contains(x!("rustc" => "values" of "backend" with "gl" "vulkan"))
+        .with_stderr_contains(x!("rustc" => "values" of "feature" with "f_a"))
+        .with_stderr_contains(x!("rustdoc" => "names" of "has_simd"))
+        .with_stderr_contains(x!("rustdoc" => "values" of "backend" with "gl" "vulkan"))
+        .run();
+}
+
+#[cargo_test]
+fn manifest_check_cfg_requires_feature() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [package.check-cfg]
+                names = ["has_simd"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["package-check-cfg"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  feature `package-check-cfg` is required
+
+  The package requires the Cargo feature called `package-check-cfg`, \
+  but that feature is not stabilized in this version of Cargo (1.[..]).
+  Consider adding `cargo-features = [\"package-check-cfg\"]` to the top of Cargo.toml \
+  (above the [package] table) to tell Cargo you are opting in to use this unstable feature.
+  See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#package-check-cfg \
+  for more information about the status of this feature.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn manifest_check_cfg_invalid_name() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["package-check-cfg"]
+
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [package.check-cfg]
+                values = { "target-backend" = ["gl"] }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["package-check-cfg"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  invalid cfg name `target-backend` in `package.check-cfg`, expected an identifier
+",
+        )
+        .run();
+}
@@ -525,6 +525,35 @@ fn package_cleans_all_the_things() {
     assert_all_clean(&p.build_dir());
 }
 
+#[cargo_test]
+fn package_cleans_split_debuginfo() {
+    // -p cleans the separate debug info of every kind of split debug info.
+    for split_debuginfo in &["packed", "unpacked"] {
+        let p = project()
+            .file(
+                "Cargo.toml",
+                r#"
+                [package]
+                name = "foo-bar"
+                version = "0.1.0"
+
+                [lib]
+                crate-type = ["rlib", "dylib"]
+                "#,
+            )
+            .file("src/lib.rs", "")
+            .file("src/main.rs", "fn main() {}")
+            .file("tests/foo-test.rs", "")
+            .build();
+        p.cargo("build --all-targets")
+            .env("CARGO_PROFILE_DEV_SPLIT_DEBUGINFO", split_debuginfo)
+            .env("CARGO_PROFILE_TEST_SPLIT_DEBUGINFO", split_debuginfo)
+            .run();
+        p.cargo("clean -p foo-bar").run();
+        assert_all_clean(&p.build_dir());
+    }
+}
+
 // Ensures that all files for the package have been deleted.
 #[track_caller]
 fn assert_all_clean(build_dir: &Path) {
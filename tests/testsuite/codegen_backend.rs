@@ -0,0 +1,201 @@
+//! Tests for the `codegen-backend` profile option.
+
+use cargo_test_support::paths::CargoPathExt;
+use cargo_test_support::{basic_bin_manifest, project, Project};
+
+/// Builds a `RUSTC_WRAPPER` dropping `-Z codegen-backend`, which needs a
+/// nightly rustc and the backend itself.
+fn backend_stripping_wrapper() -> Project {
+    let wrapper = project()
+        .at("wrapper")
+        .file("Cargo.toml", &basic_bin_manifest("wrapper"))
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() {
+                    let mut args = std::env::args_os().skip(1);
+                    let mut cmd = std::process::Command::new(args.next().unwrap());
+                    let mut prev = None;
+                    for arg in args {
+                        if arg.to_str().unwrap().starts_with("codegen-backend=") {
+                            prev = None;
+                            continue;
+                        }
+                        cmd.args(prev.replace(arg));
+                    }
+                    cmd.args(prev);
+                    std::process::exit(cmd.status().unwrap().code().unwrap());
+                }
+            "#,
+        )
+        .build();
+    wrapper.cargo("build").run();
+    wrapper
+}
+
+#[cargo_test]
+fn named_backend() {
+    let wrapper = backend_stripping_wrapper();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["codegen-backend"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [profile.dev]
+                codegen-backend = "cranelift"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build -v")
+        .masquerade_as_nightly_cargo(&["codegen-backend"])
+        .env("RUSTC_WRAPPER", wrapper.bin("wrapper"))
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[RUNNING] `[..]wrapper[EXE] rustc --crate-name foo [..]-Z codegen-backend=cranelift [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // Artifacts of each backend are kept apart.
+    p.cargo("build -v --release")
+        .masquerade_as_nightly_cargo(&["codegen-backend"])
+        .env("RUSTC_WRAPPER", wrapper.bin("wrapper"))
+        .with_stderr_does_not_contain("[..]codegen-backend[..]")
+        .run();
+}
+
+#[cargo_test]
+fn backend_path() {
+    let wrapper = backend_stripping_wrapper();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["codegen-backend"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [profile.dev]
+                codegen-backend = "backend/librustc_codegen_mine.so"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("backend/librustc_codegen_mine.so", "")
+        .build();
+
+    p.cargo("build -v")
+        .masquerade_as_nightly_cargo(&["codegen-backend"])
+        .env("RUSTC_WRAPPER", wrapper.bin("wrapper"))
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[RUNNING] `[..] -Z codegen-backend=[CWD]/backend/librustc_codegen_mine.so [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo(&["codegen-backend"])
+        .env("RUSTC_WRAPPER", wrapper.bin("wrapper"))
+        .with_stderr("[FINISHED] [..]")
+        .run();
+
+    // Rebuilding the backend rebuilds what it compiled.
+    p.root()
+        .join("backend/librustc_codegen_mine.so")
+        .move_into_the_future();
+    p.cargo("build")
+        .masquerade_as_nightly_cargo(&["codegen-backend"])
+        .env("RUSTC_WRAPPER", wrapper.bin("wrapper"))
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn invalid_backend_name() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["codegen-backend"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [profile.dev]
+                codegen-backend = "my backend"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo(&["codegen-backend"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[CWD]/Cargo.toml`
+
+Caused by:
+  `profile.dev.codegen-backend` setting of `my backend` is not a valid backend name or path.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn config_profile() {
+    let wrapper = backend_stripping_wrapper();
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [profile.dev]
+                codegen-backend = "cranelift"
+            "#,
+        )
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo(&["codegen-backend"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] config profile `dev` is not valid (defined in `[CWD]/.cargo/config.toml`)
+
+Caused by:
+  feature `codegen-backend` is required",
+        )
+        .run();
+
+    p.cargo("build -v -Zcodegen-backend")
+        .masquerade_as_nightly_cargo(&["codegen-backend"])
+        .env("RUSTC_WRAPPER", wrapper.bin("wrapper"))
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([CWD])
+[RUNNING] `[..]wrapper[EXE] rustc --crate-name foo [..]-Z codegen-backend=cranelift [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+}
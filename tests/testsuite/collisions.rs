@@ -97,26 +97,25 @@ This may become a hard error in the future; see <https://github.com/rust-lang/ca
     ignore = "--out-dir and examples are currently broken on MSVC and apple"
 )]
 fn collision_export() {
-    // `--out-dir` combines some things which can cause conflicts.
+    // `--out-dir` copies bins and examples into separate directories, so
+    // they don't conflict.
     let p = project()
         .file("Cargo.toml", &basic_manifest("foo", "1.0.0"))
         .file("examples/foo.rs", "fn main() {}")
         .file("src/main.rs", "fn main() {}")
         .build();
 
-    // -j1 to avoid issues with two processes writing to the same file at the
-    // same time.
-    p.cargo("build -j1 --out-dir=out -Z unstable-options --bins --examples")
-        .masquerade_as_nightly_cargo(&["out-dir"])
-        .with_stderr_contains("\
-[WARNING] `--out-dir` filename collision.
-The example target `foo` in package `foo v1.0.0 ([..]/foo)` has the same output filename as the bin target `foo` in package `foo v1.0.0 ([..]/foo)`.
-Colliding filename is: [..]/foo/out/foo[EXE]
-The exported filenames should be unique.
-Consider changing their names to be unique or compiling them separately.
-This may become a hard error in the future; see <https://github.com/rust-lang/cargo/issues/6313>.
-")
+    p.cargo("build -j1 --out-dir=out --bins --examples")
+        .with_stderr_does_not_contain("[WARNING] `--out-dir` filename collision.")
         .run();
+    assert!(p
+        .root()
+        .join(format!("out/bin/foo{}", env::consts::EXE_SUFFIX))
+        .is_file());
+    assert!(p
+        .root()
+        .join(format!("out/examples/foo{}", env::consts::EXE_SUFFIX))
+        .is_file());
 }
 
 #[cargo_test]
@@ -1492,14 +1492,17 @@ fn all_profile_options() {
         inherits: Some(InternedString::new("debug")),
         strip: Some(toml::StringOrBool::String("symbols".to_string())),
         package: None,
+        registry_packages: None,
         build_override: None,
         rustflags: None,
+        rustc: None,
     };
     let mut overrides = BTreeMap::new();
     let key = toml::ProfilePackageSpec::Spec(PackageIdSpec::parse("foo").unwrap());
     overrides.insert(key, base_settings.clone());
     let profile = toml::TomlProfile {
         build_override: Some(Box::new(base_settings.clone())),
+        registry_packages: Some(Box::new(base_settings.clone())),
         package: Some(overrides),
         ..base_settings
     };
@@ -166,6 +166,97 @@ Caused by:
     );
 }
 
+#[cargo_test]
+fn cycle_through_relative_path() {
+    // Paths are normalized before checking for a cycle.
+    write_config_at(".cargo/config", "include='shared/one'");
+    write_config_at(".cargo/shared/one", "include='../config'");
+    let config = ConfigBuilder::new()
+        .unstable_flag("config-include")
+        .build_err();
+    assert_error(
+        config.unwrap_err(),
+        "\
+could not load Cargo configuration
+
+Caused by:
+  failed to load config include `shared/one` from `[..]/.cargo/config`
+
+Caused by:
+  failed to load config include `../config` from `[..]/.cargo/shared/one`
+
+Caused by:
+  config `include` cycle detected with path `[..]/.cargo/config`",
+    );
+}
+
+#[cargo_test]
+fn diamond() {
+    // A file included through two paths is only merged once, the first time
+    // it is reached.
+    write_config_at(
+        ".cargo/config",
+        "
+        include = ['one', 'two']
+        ",
+    );
+    write_config_at(
+        ".cargo/one",
+        "
+        include = 'shared/common'
+        from-one = 1
+        ",
+    );
+    write_config_at(
+        ".cargo/two",
+        "
+        include = 'shared/common'
+        primary = 2
+        ",
+    );
+    write_config_at(
+        ".cargo/shared/common",
+        "
+        primary = 0
+        flags = ['common']
+        ",
+    );
+    let config = ConfigBuilder::new().unstable_flag("config-include").build();
+    assert_eq!(config.get::<i32>("from-one").unwrap(), 1);
+    assert_eq!(config.get::<i32>("primary").unwrap(), 2);
+    assert_eq!(config.get::<Vec<String>>("flags").unwrap(), ["common"]);
+}
+
+#[cargo_test]
+fn nested_relative_to_including_file() {
+    // Nested includes are relative to the file that includes them, and merge
+    // depth first.
+    write_config_at(
+        ".cargo/config",
+        "
+        include = ['../shared/base.toml', 'local.toml']
+        ",
+    );
+    write_config_at(
+        "shared/base.toml",
+        "
+        include = 'defaults/jobs.toml'
+        key = 'base'
+        ",
+    );
+    write_config_at(
+        "shared/defaults/jobs.toml",
+        "
+        key = 'defaults'
+        jobs = 4
+        ",
+    );
+    write_config_at(".cargo/local.toml", "jobs = 8");
+    let config = ConfigBuilder::new().unstable_flag("config-include").build();
+    assert_eq!(config.get::<String>("key").unwrap(), "base");
+    assert_eq!(config.get::<i32>("jobs").unwrap(), 8);
+}
+
 #[cargo_test]
 fn cli_include() {
     // Using --config with include.
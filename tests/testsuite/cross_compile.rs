@@ -2,8 +2,10 @@
 //!
 //! See `cargo_test_support::cross_compile` for more detail.
 
-use cargo_test_support::rustc_host;
+use cargo_test_support::cross_compile::FakeStd;
 use cargo_test_support::{basic_bin_manifest, basic_manifest, cross_compile, project};
+use cargo_test_support::{paths, rustc_host};
+use std::fs;
 
 #[cargo_test]
 fn simple_cross() {
@@ -1340,3 +1342,145 @@ fn doctest_xcompile_linker() {
         ))
         .run();
 }
+
+#[cargo_test]
+fn fake_target_layout_and_env() {
+    let target = cross_compile::fake_target("x86_64-fake-linux-gnu", FakeStd::Full);
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    println!("cargo:warning=TARGET={}", std::env::var("TARGET").unwrap());
+                    println!("cargo:warning=HOST={}", std::env::var("HOST").unwrap());
+                }
+            "#,
+        )
+        .file("src/main.rs", r#"fn main() { println!("hello"); }"#)
+        .build();
+
+    cross_compile::use_fake_targets(
+        p.cargo("build -v --target").arg(target.triple()),
+        &[&target],
+    )
+    .with_stderr_contains(format!(
+        "[RUNNING] `[..]rustc --crate-name foo src/main.rs [..]--target {} [..]",
+        target.triple()
+    ))
+    .with_stderr_contains(format!("warning: TARGET={}", target.triple()))
+    .with_stderr_contains(format!("warning: HOST={}", rustc_host()))
+    .run();
+
+    assert!(target.bin(&p, "foo").is_file());
+    assert!(!p.bin("foo").is_file());
+
+    cross_compile::use_fake_targets(p.cargo("run --target").arg(target.triple()), &[&target])
+        .env(
+            &format!("CARGO_TARGET_{}_RUNNER", target.env_name()),
+            cargo_test_support::tools::echo_wrapper(),
+        )
+        .with_stdout("hello")
+        .with_stderr_contains(format!(
+            "WRAPPER CALLED: target/{}/debug/foo[EXE]",
+            target.triple()
+        ))
+        .run();
+}
+
+#[cargo_test]
+fn fake_target_without_std() {
+    let target = cross_compile::fake_target("thumbv7em-fake-none-eabi", FakeStd::Core);
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/lib.rs", "pub fn f() -> String { String::new() }")
+        .build();
+
+    // Host builds still have `std`.
+    p.cargo("build").run();
+
+    cross_compile::use_fake_targets(p.cargo("build --target").arg(target.triple()), &[&target])
+        .with_status(101)
+        .with_stderr_contains("error[E0463]: can't find crate for `std`")
+        .run();
+
+    p.change_file("src/lib.rs", "#![no_std]\npub fn f() -> u32 { 42 }");
+    cross_compile::use_fake_targets(p.cargo("build --target").arg(target.triple()), &[&target])
+        .run();
+    assert!(target.out_dir(&p, "debug").join("libfoo.rlib").is_file());
+}
+
+#[cfg(unix)]
+#[cargo_test]
+fn sysroot_provider() {
+    let target = rustc_host();
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.0.1"))
+        .file("src/lib.rs", "pub fn f() -> String { String::new() }")
+        .executable(
+            "provide-sysroot",
+            r#"#!/bin/sh
+                set -e
+                echo "$CARGO_SYSROOT_TARGET $CARGO_RUSTC_VERSION" >> "$(dirname "$0")/provided"
+                mkdir -p "$CARGO_SYSROOT_DIR/lib/rustlib"
+                cp -r "$("$RUSTC" --print sysroot)/lib/rustlib/$CARGO_SYSROOT_TARGET" \
+                    "$CARGO_SYSROOT_DIR/lib/rustlib/"
+            "#,
+        )
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                    [target.{}]
+                    sysroot-provider = "./provide-sysroot"
+                "#,
+                target
+            ),
+        )
+        .build();
+
+    p.cargo("build -v -Zsysroot-provider --target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo(&["sysroot-provider"])
+        .with_stderr_contains(format!("[PROVIDING] sysroot for `{}`", target))
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]--sysroot=[..]/sysroots/[..]`")
+        .run();
+    let provided = p.read_file("provided");
+    assert_eq!(provided.lines().count(), 1);
+    assert!(provided.starts_with(&format!("{} 1.", target)));
+
+    // The provided sysroot is reused.
+    p.cargo("build -v -Zsysroot-provider --target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo(&["sysroot-provider"])
+        .with_stderr_does_not_contain("[PROVIDING] [..]")
+        .with_stderr_contains("[FRESH] foo [..]")
+        .run();
+
+    // Another provider command provides the sysroot again.
+    p.change_file(
+        ".cargo/config.toml",
+        &format!(
+            r#"
+                [target.{}]
+                sysroot-provider = ["./provide-sysroot", "--refresh"]
+            "#,
+            target
+        ),
+    );
+    p.cargo("build -Zsysroot-provider --target")
+        .arg(&target)
+        .masquerade_as_nightly_cargo(&["sysroot-provider"])
+        .with_stderr(format!(
+            "\
+[PROVIDING] sysroot for `{}`
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+            target
+        ))
+        .run();
+    assert_eq!(p.read_file("provided").lines().count(), 2);
+    let sysroots = paths::home().join(".cargo/sysroots").join(&target);
+    assert_eq!(fs::read_dir(sysroots).unwrap().count(), 1);
+}
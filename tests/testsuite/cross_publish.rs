@@ -117,3 +117,49 @@ fn publish_with_target() {
         )
         .run();
 }
+
+#[cargo_test]
+fn verify_targets_catch_platform_errors() {
+    if cross_compile::disabled() {
+        return;
+    }
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.0"
+                license = "MIT"
+                description = "foo"
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            &format!(
+                r#"
+                    #[cfg(target_arch = "{}")]
+                    compile_error!("unsupported architecture");
+                "#,
+                cross_compile::alternate_arch()
+            ),
+        )
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                "[publish]\nverify-targets = [\"{}\"]\n",
+                cross_compile::alternate()
+            ),
+        )
+        .build();
+
+    p.cargo("package --no-metadata -Zpublish-verify-targets")
+        .masquerade_as_nightly_cargo(&["publish-verify-targets"])
+        .with_status(101)
+        .with_stderr_contains(&format!(
+            "[VERIFYING] foo v0.0.0 ([CWD]) for {}",
+            cross_compile::alternate()
+        ))
+        .with_stderr_contains("error: unsupported architecture")
+        .run();
+}
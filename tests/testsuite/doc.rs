@@ -1306,6 +1306,75 @@ fn doc_workspace_open_help_message() {
         .run();
 }
 
+#[cargo_test]
+fn doc_workspace_open_landing_page() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["foo", "bar"]
+            "#,
+        )
+        .file(
+            "foo/Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                description = "Does <foo> & more"
+            "#,
+        )
+        .file("foo/src/lib.rs", "")
+        .file(
+            "bar/Cargo.toml",
+            r#"
+                [package]
+                name = "bar"
+                version = "0.2.0"
+
+                [lib]
+                name = "barlib"
+            "#,
+        )
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("doc --workspace --open -Zdoc-landing-page")
+        .masquerade_as_nightly_cargo(&["doc-landing-page"])
+        .env("BROWSER", tools::echo())
+        .with_stderr_contains("[..] Opening [CWD]/target/doc/index.html")
+        .with_stdout_contains("[CWD]/target/doc/index.html")
+        .run();
+
+    assert_eq!(
+        p.read_file("target/doc/index.html"),
+        "\
+<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>foo</title>
+</head>
+<body>
+<h1>foo</h1>
+<ul>
+<li><a href=\"barlib/index.html\">barlib</a> 0.2.0</li>
+<li><a href=\"foo/index.html\">foo</a> 0.1.0 &mdash; Does &lt;foo&gt; &amp; more</li>
+</ul>
+</body>
+</html>
+"
+    );
+
+    // A single member is opened directly.
+    p.cargo("doc -p foo --open -Zdoc-landing-page")
+        .masquerade_as_nightly_cargo(&["doc-landing-page"])
+        .env("BROWSER", tools::echo())
+        .with_stderr_contains("[..] Opening [CWD]/target/doc/foo/index.html")
+        .run();
+}
+
 #[cargo_test(nightly, reason = "-Zextern-html-root-url is unstable")]
 fn doc_extern_map_local() {
     let p = project()
@@ -1597,6 +1666,116 @@ fn doc_private_ws() {
         .run();
 }
 
+#[cargo_test]
+fn doc_include_private_members_only() {
+    Package::new("dep", "1.0.0")
+        .file("src/lib.rs", "mod private { fn private_item() {} }")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.0.1"
+
+                [dependencies]
+                dep = "1.0"
+            "#,
+        )
+        .file("a/src/lib.rs", "mod private { fn private_item() {} }")
+        .file("b/Cargo.toml", &basic_manifest("b", "0.0.1"))
+        .file("b/src/lib.rs", "fn p2() {}")
+        .build();
+
+    p.cargo("doc --workspace --include-private")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--include-private` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("doc --workspace --include-private -Zunstable-options -v")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains(
+            "[RUNNING] `rustdoc [..] a/src/lib.rs [..]--document-private-items[..]",
+        )
+        .with_stderr_contains(
+            "[RUNNING] `rustdoc [..] b/src/lib.rs [..]--document-private-items[..]",
+        )
+        .with_stderr_line_without(
+            &["[RUNNING] `rustdoc [..]--crate-name dep"],
+            &["--document-private-items"],
+        )
+        .run();
+    assert!(p.root().join("target/doc/a/private/index.html").is_file());
+    assert!(p.root().join("target/doc/dep/index.html").is_file());
+    assert!(!p.root().join("target/doc/dep/private/index.html").exists());
+}
+
+#[cargo_test]
+fn doc_manifest() {
+    Package::new("dep", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                dep = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("src/bin/foo-cli.rs", "fn main() {}")
+        .build();
+
+    p.cargo("doc --doc-manifest -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .run();
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&p.read_file("target/doc/doc-manifest.json")).unwrap();
+    let root = p.root();
+    let expected = serde_json::json!({
+        "crates": {
+            "dep": {
+                "package_id": "dep 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                "kind": ["lib"],
+                "root": root.join("target/doc/dep"),
+                "index": root.join("target/doc/dep/index.html"),
+                "member": false,
+            },
+            "foo": {
+                "package_id": format!("foo 0.0.1 (path+{})", p.url()),
+                "kind": ["lib"],
+                "root": root.join("target/doc/foo"),
+                "index": root.join("target/doc/foo/index.html"),
+                "member": true,
+            },
+            "foo_cli": {
+                "package_id": format!("foo 0.0.1 (path+{})", p.url()),
+                "kind": ["bin"],
+                "root": root.join("target/doc/foo_cli"),
+                "index": root.join("target/doc/foo_cli/index.html"),
+                "member": true,
+            },
+        }
+    });
+    assert_eq!(manifest, expected);
+}
+
 const BAD_INTRA_LINK_LIB: &str = r#"
 #![deny(broken_intra_doc_links)]
 
@@ -2501,3 +2680,80 @@ fn link_to_private_item() {
         )
         .run();
 }
+
+#[cargo_test]
+fn doc_json_output_format_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("doc --output-format json")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--output-format` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test(nightly, reason = "rustdoc --output-format json is unstable")]
+fn doc_json_output_format() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/lib.rs", "pub fn foo() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .build();
+
+    p.cargo("doc --output-format json -Zunstable-options -v")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains(
+            "[RUNNING] `rustdoc [..]--crate-name foo [..]-o [CWD]/target/doc-json \
+             -Zunstable-options --output-format=json[..]",
+        )
+        .run();
+    assert!(p.root().join("target/doc-json/foo.json").is_file());
+    assert!(p.root().join("target/doc-json/bar.json").is_file());
+    assert!(!p.root().join("target/doc").exists());
+
+    p.cargo("doc --output-format json -Zunstable-options --message-format json")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "compiler-artifact",
+                    "package_id": "foo 0.1.0 [..]",
+                    "manifest_path": "[..]",
+                    "target": "{...}",
+                    "profile": "{...}",
+                    "features": [],
+                    "filenames": ["[..]/target/doc-json/foo.json"],
+                    "executable": null,
+                    "fresh": true
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn doc_json_output_format_conflicts_with_open() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("doc --output-format json --open -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(1)
+        .with_stderr_contains(
+            "[ERROR] the argument '--output-format <FMT>' cannot be used with '--open'",
+        )
+        .run();
+}
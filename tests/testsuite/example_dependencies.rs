@@ -0,0 +1,177 @@
+//! Tests for the `dependencies` field of examples.
+
+use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_manifest, project};
+
+#[cargo_test]
+fn gated() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dev-dependencies]
+                bar = { path = "bar" }
+
+                [[example]]
+                name = "ex"
+                dependencies = ["bar"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("examples/ex.rs", "fn main() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("check --examples")
+        .masquerade_as_nightly_cargo(&["example-dependencies"])
+        .with_status(101)
+        .with_stderr_contains("[..]feature `example-dependencies` is required")
+        .run();
+}
+
+#[cargo_test]
+fn scoped_to_example() {
+    Package::new("heavy", "1.0.0")
+        .file("src/lib.rs", "pub fn render() {}")
+        .publish();
+    Package::new("light", "1.0.0")
+        .file("src/lib.rs", "pub fn help() {}")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["example-dependencies"]
+
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2018"
+
+                [dev-dependencies]
+                heavy = "1.0"
+                light = "1.0"
+
+                [[example]]
+                name = "gui"
+                dependencies = ["heavy"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "examples/gui.rs",
+            "fn main() { heavy::render(); light::help(); }",
+        )
+        .file("examples/cli.rs", "fn main() { light::help(); }")
+        .file("tests/t.rs", "#[test] fn t() { light::help(); }")
+        .build();
+
+    // Neither `cargo test` nor `--all-targets` build the example or its
+    // dependencies.
+    p.cargo("test --no-run")
+        .masquerade_as_nightly_cargo(&["example-dependencies"])
+        .with_stderr_does_not_contain("[COMPILING] heavy [..]")
+        .with_stderr_contains("[COMPILING] light v1.0.0")
+        .run();
+    p.cargo("check --all-targets -v")
+        .masquerade_as_nightly_cargo(&["example-dependencies"])
+        .with_stderr_does_not_contain("[CHECKING] heavy [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name cli [..]")
+        .with_stderr_does_not_contain("[RUNNING] `rustc --crate-name gui [..]")
+        .run();
+
+    // Other examples can't use them.
+    p.change_file("examples/cli.rs", "fn main() { heavy::render(); }");
+    p.cargo("check --example cli")
+        .masquerade_as_nightly_cargo(&["example-dependencies"])
+        .with_status(101)
+        .with_stderr_contains("[..]heavy[..]")
+        .with_stderr_does_not_contain("[CHECKING] heavy v1.0.0")
+        .run();
+    p.change_file("examples/cli.rs", "fn main() { light::help(); }");
+
+    p.cargo("build --example gui")
+        .masquerade_as_nightly_cargo(&["example-dependencies"])
+        .with_stderr_contains("[COMPILING] heavy v1.0.0")
+        .with_stderr_contains("[FINISHED] [..]")
+        .run();
+    p.cargo("check --examples -v")
+        .masquerade_as_nightly_cargo(&["example-dependencies"])
+        .with_stderr_contains("[RUNNING] `rustc --crate-name gui [..]--extern heavy=[..]")
+        .with_stderr_line_without(
+            &["[RUNNING] `rustc --crate-name cli [..]"],
+            &["--extern heavy"],
+        )
+        .run();
+}
+
+#[cargo_test]
+fn must_be_dev_dependency() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["example-dependencies"]
+
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+
+                [[example]]
+                name = "ex"
+                dependencies = ["bar"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("examples/ex.rs", "fn main() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["example-dependencies"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]/foo/Cargo.toml`
+
+Caused by:
+  dependency `bar` of example `ex` must be specified in `dev-dependencies`
+",
+        )
+        .run();
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            cargo-features = ["example-dependencies"]
+
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dev-dependencies]
+            bar = { path = "bar" }
+
+            [[bin]]
+            name = "foo"
+            path = "src/lib.rs"
+            dependencies = ["bar"]
+        "#,
+    );
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["example-dependencies"])
+        .with_status(101)
+        .with_stderr_contains(
+            "  the `dependencies` field of bin target `foo` is only supported for examples",
+        )
+        .run();
+}
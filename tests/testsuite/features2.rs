@@ -1272,7 +1272,7 @@ fn resolver_bad_setting() {
 error: failed to parse manifest at `[..]/foo/Cargo.toml`
 
 Caused by:
-  `resolver` setting `foo` is not valid, valid options are \"1\" or \"2\"
+  `resolver` setting `foo` is not valid, valid options are \"1\" or \"2\", or \"3\" with `cargo-features = [\"resolver-3\"]`
 ",
         )
         .run();
@@ -2543,3 +2543,259 @@ fn dep_with_optional_host_deps_activated() {
         )
         .run();
 }
+
+#[cargo_test]
+fn feature_conflicts() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                backend = { path = "backend", features = ["a"] }
+                bar = { path = "bar" }
+
+                [features]
+                b = ["bar/b"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "bar/Cargo.toml",
+            r#"
+                [package]
+                name = "bar"
+                version = "0.1.0"
+
+                [dependencies]
+                backend = { path = "../backend" }
+
+                [features]
+                b = ["backend/b"]
+            "#,
+        )
+        .file("bar/src/lib.rs", "")
+        .file(
+            "backend/Cargo.toml",
+            r#"
+                cargo-features = ["feature-conflicts"]
+
+                [package]
+                name = "backend"
+                version = "0.1.0"
+
+                [features]
+                a = []
+                b = []
+
+                [features.metadata]
+                conflicts = [["a", "b"]]
+            "#,
+        )
+        .file("backend/src/lib.rs", "")
+        .build();
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["feature-conflicts"])
+        .with_stderr(
+            "\
+[CHECKING] backend v0.1.0 ([..])
+[CHECKING] bar v0.1.0 ([..])
+[CHECKING] foo v0.1.0 ([..])
+[FINISHED] dev [..]
+",
+        )
+        .run();
+
+    p.cargo("check --features b")
+        .masquerade_as_nightly_cargo(&["feature-conflicts"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] features `a` and `b` of package `backend v0.1.0 ([CWD]/backend)` are mutually exclusive, but both are enabled
+
+`a` is enabled by:
+  dependency declaration in `foo v0.1.0 ([CWD])`
+  requested for workspace member `foo v0.1.0 ([CWD])`
+
+`b` is enabled by:
+  feature `b` of `bar v0.1.0 ([CWD]/bar)`
+  feature `b` of `foo v0.1.0 ([CWD])`
+  requested for workspace member `foo v0.1.0 ([CWD])`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn feature_conflicts_unknown_feature() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["feature-conflicts"]
+
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [features]
+                a = []
+
+                [features.metadata]
+                conflicts = [["a", "c"]]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["feature-conflicts"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  feature `c` is listed in `features.metadata.conflicts`, but no such feature exists
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn resolver_3_decouples_members() {
+    // Each member gets the features it asks for, `a` doesn't get `std`
+    // from the others.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["resolver-3"]
+
+                [workspace]
+                members = ["a", "b", "c"]
+                resolver = "3"
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [dependencies]
+                common = { path = "../common", default-features = false }
+            "#,
+        )
+        .file("a/src/lib.rs", "const _: () = assert!(!common::STD);")
+        .file(
+            "b/Cargo.toml",
+            r#"
+                [package]
+                name = "b"
+                version = "0.1.0"
+
+                [dependencies]
+                common = { path = "../common" }
+            "#,
+        )
+        .file("b/src/lib.rs", "const _: () = assert!(common::STD);")
+        .file(
+            "c/Cargo.toml",
+            r#"
+                [package]
+                name = "c"
+                version = "0.1.0"
+
+                [dependencies]
+                common = { path = "../common" }
+            "#,
+        )
+        .file("c/src/lib.rs", "const _: () = assert!(common::STD);")
+        .file(
+            "common/Cargo.toml",
+            r#"
+                [package]
+                name = "common"
+                version = "0.1.0"
+
+                [features]
+                default = ["std"]
+                std = []
+            "#,
+        )
+        .file(
+            "common/src/lib.rs",
+            r#"
+                pub const STD: bool = cfg!(feature = "std");
+            "#,
+        )
+        .build();
+
+    p.cargo("check --workspace")
+        .masquerade_as_nightly_cargo(&["resolver-3"])
+        .with_stderr_unordered(
+            "\
+[CHECKING] common v0.1.0 ([..]/common)
+[CHECKING] a v0.1.0 ([..]/a)
+[CHECKING] b v0.1.0 ([..]/b)
+[CHECKING] c v0.1.0 ([..]/c)
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [workspace]
+            members = ["a", "b", "c"]
+            resolver = "2"
+        "#,
+    );
+    p.cargo("check --workspace")
+        .with_status(101)
+        .with_stderr_contains("[..]assertion failed: !common::STD[..]")
+        .run();
+}
+
+#[cargo_test]
+fn resolver_3_requires_feature() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                resolver = "3"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["resolver-3"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  feature `resolver-3` is required
+
+  The package requires the Cargo feature called `resolver-3`, \
+  but that feature is not stabilized in this version of Cargo (1.[..]).
+  Consider adding `cargo-features = [\"resolver-3\"]` to the top of Cargo.toml \
+  (above the [package] table) to tell Cargo you are opting in to use this unstable feature.
+  See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#resolver-3 \
+  for more information about the status of this feature.
+",
+        )
+        .run();
+}
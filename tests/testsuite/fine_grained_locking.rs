@@ -0,0 +1,194 @@
+//! Tests for locking the units of a build (`-Z fine-grained-locking`).
+
+use std::io::{BufRead, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Stdio};
+use std::thread;
+
+use cargo::util::Filesystem;
+use cargo_test_support::{basic_manifest, execs, project, Project};
+
+/// A workspace with `slow`, whose build script waits until the connection to
+/// the address in `ADDR` is closed, and `fast`.
+fn slow_project() -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["slow", "fast"]
+            "#,
+        )
+        .file("slow/Cargo.toml", &basic_manifest("slow", "0.1.0"))
+        .file("slow/src/lib.rs", "")
+        .file(
+            "slow/build.rs",
+            r#"
+                use std::io::Read;
+                use std::net::TcpStream;
+                use std::time::Duration;
+
+                fn main() {
+                    if let Ok(addr) = std::env::var("ADDR") {
+                        let mut conn = TcpStream::connect(addr).unwrap();
+                        conn.set_read_timeout(Some(Duration::from_secs(60))).unwrap();
+                        let _ = conn.read(&mut [0]);
+                    }
+                }
+            "#,
+        )
+        .file("fast/Cargo.toml", &basic_manifest("fast", "0.1.0"))
+        .file("fast/src/lib.rs", "")
+        .build()
+}
+
+/// Starts building `slow`, and returns once its build script runs.
+fn start_slow_build(p: &Project) -> (Child, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut build = p
+        .cargo("build -p slow -Zfine-grained-locking")
+        .masquerade_as_nightly_cargo(&["fine-grained-locking"])
+        .env("ADDR", listener.local_addr().unwrap().to_string())
+        .build_command();
+    build.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let build = build.spawn().unwrap();
+    let (conn, _) = listener.accept().unwrap();
+    (build, conn)
+}
+
+#[cargo_test]
+fn other_commands_run_concurrently() {
+    let p = slow_project();
+    let (build, conn) = start_slow_build(&p);
+
+    p.cargo("check -p fast -Zfine-grained-locking")
+        .masquerade_as_nightly_cargo(&["fine-grained-locking"])
+        .with_stderr(
+            "\
+[CHECKING] fast v0.1.0 ([CWD]/fast)
+[FINISHED] [..]
+",
+        )
+        .run();
+    p.cargo("doc -p fast -Zfine-grained-locking")
+        .masquerade_as_nightly_cargo(&["fine-grained-locking"])
+        .with_stderr(
+            "\
+[DOCUMENTING] fast v0.1.0 ([CWD]/fast)
+[FINISHED] [..]
+",
+        )
+        .run();
+    p.cargo("build -p fast --release -Zfine-grained-locking")
+        .masquerade_as_nightly_cargo(&["fine-grained-locking"])
+        .with_stderr(
+            "\
+[COMPILING] fast v0.1.0 ([CWD]/fast)
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    drop(conn);
+    execs().run_output(&build.wait_with_output().unwrap());
+}
+
+#[cargo_test]
+fn same_unit_waits() {
+    let p = slow_project();
+    let (build, conn) = start_slow_build(&p);
+
+    let mut other = p
+        .cargo("build -p slow -Zfine-grained-locking")
+        .masquerade_as_nightly_cargo(&["fine-grained-locking"])
+        .build_command();
+    other.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut other = other.spawn().unwrap();
+
+    // Wait for the other build to block on the build script, then let the
+    // first one finish.
+    let stderr = BufReader::new(other.stderr.take().unwrap());
+    let mut lines = stderr.lines().map(Result::unwrap);
+    lines
+        .by_ref()
+        .find(|line| line.contains("Blocking waiting for file lock on `slow v0.1.0`"))
+        .expect("the build should block");
+    let rest = thread::spawn(move || lines.collect::<Vec<_>>());
+    drop(conn);
+    execs().run_output(&build.wait_with_output().unwrap());
+
+    let output = other.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let rest = rest.join().unwrap();
+    assert!(rest.last().unwrap().contains("Finished"), "{:?}", rest);
+}
+
+#[cargo_test]
+fn default_locking_waits() {
+    let p = slow_project();
+    let (build, conn) = start_slow_build(&p);
+
+    // A build without the flag locks the entire profile directory.
+    let mut other = p.cargo("check -p fast").build_command();
+    other.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut other = other.spawn().unwrap();
+    let stderr = BufReader::new(other.stderr.take().unwrap());
+    let mut lines = stderr.lines().map(Result::unwrap);
+    lines
+        .by_ref()
+        .find(|line| line.contains("Blocking waiting for file lock on build directory"))
+        .expect("the build should block");
+    let rest = thread::spawn(move || lines.count());
+    drop(conn);
+    execs().run_output(&build.wait_with_output().unwrap());
+    assert!(other.wait().unwrap().success());
+    rest.join().unwrap();
+}
+
+#[cargo_test]
+fn uplift_waits_for_other_features() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [features]
+                a = []
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+    p.cargo("build -Zfine-grained-locking")
+        .masquerade_as_nightly_cargo(&["fine-grained-locking"])
+        .run();
+
+    // Builds with different features are different units, but uplift their
+    // binary to the same `target/debug/foo`, so hold the lock another build
+    // takes for that.
+    let lock = Filesystem::new(p.target_debug_dir())
+        .open_with(".cargo-uplift-lock", true, &|| Ok(()))
+        .unwrap();
+    let mut other = p
+        .cargo("build --features a -Zfine-grained-locking")
+        .masquerade_as_nightly_cargo(&["fine-grained-locking"])
+        .build_command();
+    other.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut other = other.spawn().unwrap();
+    let stderr = BufReader::new(other.stderr.take().unwrap());
+    let mut lines = stderr.lines().map(Result::unwrap);
+    lines
+        .by_ref()
+        .find(|line| line.contains("Blocking waiting for file lock on build directory"))
+        .expect("the build should block");
+    let rest = thread::spawn(move || lines.count());
+    drop(lock);
+    assert!(other.wait().unwrap().success());
+    rest.join().unwrap();
+
+    assert!(p.bin("foo").is_file());
+    let depinfo = p.read_file("target/debug/foo.d");
+    assert!(depinfo.starts_with(&format!("{}:", p.bin("foo").display())));
+}
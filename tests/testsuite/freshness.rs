@@ -2663,3 +2663,154 @@ fn changing_linker() {
         )
         .run();
 }
+
+#[cargo_test]
+fn corrupt_fingerprint_files() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("build").run();
+    let fingerprint = |pattern: &str| {
+        p.glob(format!("target/debug/.fingerprint/{}", pattern))
+            .next()
+            .unwrap()
+            .unwrap()
+    };
+    // A partially written hash and JSON.
+    fs::write(fingerprint("bar-*/lib-bar"), b"\0\0\0").unwrap();
+    fs::write(fingerprint("bar-*/lib-bar.json"), b"{\"rustc\":").unwrap();
+    // A truncated dep-info file.
+    let dep_info = fingerprint("foo-*/dep-lib-foo");
+    let len = fs::metadata(&dep_info).unwrap().len();
+    fs::OpenOptions::new()
+        .write(true)
+        .open(&dep_info)
+        .unwrap()
+        .set_len(len / 2)
+        .unwrap();
+
+    p.cargo("build")
+        .with_stderr(
+            "\
+[WARNING] found corrupt fingerprint files for `bar v0.1.0`, `foo v0.1.0`, rebuilding them
+The target directory may have been left behind by a crash or a full disk.
+[COMPILING] bar v0.1.0 ([..])
+[COMPILING] foo v0.1.0 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.cargo("build -v")
+        .with_stderr(
+            "\
+[FRESH] bar v0.1.0 ([..])
+[FRESH] foo v0.1.0 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+    // The files are written through temporary files, which don't stay around.
+    assert_eq!(p.glob("target/debug/.fingerprint/*/.tmp*").count(), 0);
+}
+
+#[cargo_test]
+fn empty_fingerprint_files() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("build").run();
+    // The fingerprint files aren't synced to disk, so a power loss can leave
+    // them empty.
+    for path in p.glob("target/debug/.fingerprint/foo-*/*") {
+        let path = path.unwrap();
+        if path.file_name().unwrap() != "invoked.timestamp" {
+            fs::write(path, b"").unwrap();
+        }
+    }
+
+    p.cargo("build")
+        .with_stderr(
+            "\
+[WARNING] found corrupt fingerprint files for `foo v0.0.1`, rebuilding them
+The target directory may have been left behind by a crash or a full disk.
+[COMPILING] foo v0.0.1 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+    p.cargo("build -v")
+        .with_stderr(
+            "\
+[FRESH] foo v0.0.1 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+#[cfg(target_os = "linux")]
+fn missing_split_debuginfo() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [profile.dev]
+                split-debuginfo = "packed"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build").run();
+    let dwp = p
+        .glob("target/debug/deps/foo-*.dwp")
+        .next()
+        .unwrap()
+        .unwrap();
+    p.cargo("build").with_stderr("[FINISHED] [..]").run();
+
+    fs::remove_file(&dwp).unwrap();
+    p.cargo("build")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+    assert!(dwp.is_file());
+
+    // Without debug info, there is no `.dwp` to miss.
+    p.cargo("build")
+        .env("RUSTFLAGS", "-C debuginfo=0")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+    p.cargo("build")
+        .env("RUSTFLAGS", "-C debuginfo=0")
+        .with_stderr("[FINISHED] [..]")
+        .run();
+}
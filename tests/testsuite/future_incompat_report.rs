@@ -389,3 +389,77 @@ with_updates v1.0.0 has the following newer versions available: 1.0.1, 1.0.2, 3.
         .with_stdout_contains(update_message)
         .run()
 }
+
+#[cargo_test]
+fn diff_since() {
+    let p = simple_project();
+    // Reports as they were saved by builds before and after an update of
+    // the lock file.
+    let report = |id: u32, timestamp: u64, lockfile: &str, lints: &str| {
+        format!(
+            r#"{{"id":{},"suggestion_message":"","per_package":{{}},"timestamp":{},"lockfile":"{}","lints":{}}}"#,
+            id, timestamp, lockfile, lints
+        )
+    };
+    p.change_file(
+        "target/.future-incompat-report.json",
+        &format!(
+            r#"{{"version":0,"next_id":4,"reports":[{},{},{}]}}"#,
+            report(1, 1640995200, "a", r#"{"bar@1.0.0":["lint_a"]}"#),
+            report(2, 1648771200, "a", r#"{"bar@1.0.0":["lint_a"]}"#),
+            report(
+                3,
+                1656633600,
+                "b",
+                r#"{"bar@1.1.0":["lint_a","lint_b"],"baz@0.1.0":["lint_c"]}"#
+            ),
+        ),
+    );
+
+    p.cargo("report future-incompatibilities --diff-since lock")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--diff-since` flag is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("report future-incompatibilities --diff-since lock -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+The following future incompatibilities were introduced in report 3 since report 2 (saved 2022-04-01T00:00:00Z):
+  - bar@1.1.0 (updated from 1.0.0): lint_b
+  - baz@0.1.0: lint_c
+",
+        )
+        .run();
+
+    p.cargo("report future-incompatibilities --id 2 --diff-since 2022-02-01 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "No future incompatibilities were introduced in report 2 \
+             since report 1 (saved 2022-01-01T00:00:00Z).",
+        )
+        .run();
+
+    p.cargo("report future-incompatibilities --diff-since 2021-12-01 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] could not find a report older than report 3 saved before that date
+Only the last 5 reports are kept
+",
+        )
+        .run();
+
+    p.cargo("report future-incompatibilities --diff-since yesterday -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] invalid value `yesterday` for `--diff-since`, \
+             expected `lock` or a date like `2022-11-30`",
+        )
+        .run();
+}
@@ -218,3 +218,116 @@ fn duplicate_entries_in_lockfile() {
         )
         .run();
 }
+
+#[cargo_test]
+fn named_lockfile_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("generate-lockfile --lockfile msrv")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--lockfile` flag is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("generate-lockfile --lockfile ../msrv -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] invalid lock file name `../msrv`, only letters, numbers, `-` and `_` \
+             are allowed",
+        )
+        .run();
+
+    // Only the commands reading or writing the lock file accept the flag.
+    p.cargo("verify-project --lockfile msrv -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(1)
+        .with_stderr_contains("error: unexpected argument '--lockfile' found")
+        .run();
+}
+
+#[cargo_test]
+fn named_lockfile() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("generate-lockfile --lockfile msrv -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .run();
+    assert!(p.root().join("Cargo.msrv.lock").is_file());
+    assert!(!p.root().join("Cargo.lock").exists());
+
+    // Subcommands of `cargo lock` take the flag as well.
+    p.cargo("lock fmt --lockfile msrv -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .run();
+    assert!(!p.root().join("Cargo.lock").exists());
+
+    Package::new("bar", "0.1.1").publish();
+    p.cargo("generate-lockfile").run();
+    let lock = p.read_file("Cargo.lock");
+    assert!(lock.contains("version = \"0.1.1\""));
+    let msrv_lock = p.read_file("Cargo.msrv.lock");
+    assert!(msrv_lock.contains("version = \"0.1.0\""));
+
+    p.cargo("check --locked --lockfile msrv -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v0.1.0 (registry `dummy-registry`)
+[CHECKING] bar v0.1.0
+[CHECKING] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+    assert_eq!(msrv_lock, p.read_file("Cargo.msrv.lock"));
+
+    // The named lock file is verified against the same manifests.
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            bar = "0.1.1"
+        "#,
+    );
+    p.cargo("check --locked --lockfile msrv -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the lock file [CWD]/Cargo.msrv.lock needs to be updated but --locked \
+             was passed to prevent this",
+        )
+        .run();
+    p.cargo("update --lockfile msrv -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[UPDATING] bar v0.1.0 -> v0.1.1
+",
+        )
+        .run();
+    assert_eq!(lock, p.read_file("Cargo.lock"));
+}
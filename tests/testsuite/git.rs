@@ -3652,3 +3652,142 @@ fn cleans_temp_pack_files() {
     p.cargo("generate-lockfile").run();
     assert!(!tmp_path.exists());
 }
+
+/// Whether the database of the only git dependency is shallow.
+fn git_db_is_shallow() -> bool {
+    let db = paths::home().join(".cargo/git/db");
+    let db = t!(t!(fs::read_dir(&db)).next().unwrap()).path();
+    db.join("shallow").exists()
+}
+
+#[cargo_test(requires_git)]
+fn shallow_fetch() {
+    let (git_project, repo) = git::new_repo("dep1", |project| {
+        project
+            .file("Cargo.toml", &basic_lib_manifest("dep1"))
+            .file("src/lib.rs", r#"pub fn f() { println!("one"); }"#)
+    });
+    git_project.change_file("src/lib.rs", r#"pub fn f() { println!("two"); }"#);
+    git::add(&repo);
+    git::commit(&repo);
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+                    edition = "2018"
+
+                    [dependencies]
+                    dep1 = {{ git = "{}" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/main.rs", "fn main() { dep1::f(); }")
+        .build();
+
+    p.cargo("build -v -Zgit-shallow")
+        .masquerade_as_nightly_cargo(&["git-shallow"])
+        .with_stderr_contains("[RUNNING] `git fetch [..]--depth=1 [..]`")
+        .run();
+    p.rename_run("foo", "foo1").with_stdout("two").run();
+    assert!(git_db_is_shallow());
+
+    // A depth of `0` fetches the full history, which also completes the
+    // shallow database.
+    p.cargo("update -v -Zgit-shallow")
+        .masquerade_as_nightly_cargo(&["git-shallow"])
+        .env("CARGO_NET_GIT_FETCH_DEPTH", "0")
+        .with_stderr_contains("[RUNNING] `git fetch [..]--unshallow [..]`")
+        .run();
+    assert!(!git_db_is_shallow());
+}
+
+#[cargo_test(requires_git)]
+fn shallow_fetch_falls_back_to_full_history() {
+    let (git_project, repo) = git::new_repo("dep1", |project| {
+        project
+            .file("Cargo.toml", &basic_lib_manifest("dep1"))
+            .file("src/lib.rs", r#"pub fn f() { println!("one"); }"#)
+    });
+    let first = repo.head().unwrap().target().unwrap();
+    git_project.change_file("src/lib.rs", r#"pub fn f() { println!("two"); }"#);
+    git::add(&repo);
+    git::commit(&repo);
+
+    // A `rev` which isn't the tip of a branch isn't part of the shallow
+    // history.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+                    edition = "2018"
+
+                    [dependencies]
+                    dep1 = {{ git = "{}", rev = "{}" }}
+                "#,
+                git_project.url(),
+                first
+            ),
+        )
+        .file("src/main.rs", "fn main() { dep1::f(); }")
+        .build();
+
+    p.cargo("build -v -Zgit-shallow")
+        .masquerade_as_nightly_cargo(&["git-shallow"])
+        .with_stderr_contains("[RUNNING] `git fetch [..]--depth=1 [..]`")
+        .with_stderr_contains("[RUNNING] `git fetch [..]--unshallow [..]`")
+        .run();
+    p.rename_run("foo", "foo1").with_stdout("one").run();
+    assert!(!git_db_is_shallow());
+}
+
+#[cargo_test(requires_git)]
+fn shallow_fetch_of_locked_rev() {
+    let (git_project, repo) = git::new_repo("dep1", |project| {
+        project
+            .file("Cargo.toml", &basic_lib_manifest("dep1"))
+            .file("src/lib.rs", r#"pub fn f() { println!("one"); }"#)
+    });
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+                    edition = "2018"
+
+                    [dependencies]
+                    dep1 = {{ git = "{}" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/main.rs", "fn main() { dep1::f(); }")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    // The branch moved on, but the lock file still points to the first
+    // commit, which the shallow fetch of a fresh database misses.
+    git_project.change_file("src/lib.rs", r#"pub fn f() { println!("two"); }"#);
+    git::add(&repo);
+    git::commit(&repo);
+    paths::home().join(".cargo/git").rm_rf();
+
+    p.cargo("build -v -Zgit-shallow")
+        .masquerade_as_nightly_cargo(&["git-shallow"])
+        .with_stderr_contains("[RUNNING] `git fetch [..]--depth=1 [..]`")
+        .with_stderr_contains("[RUNNING] `git fetch [..]--unshallow [..]`")
+        .run();
+    p.rename_run("foo", "foo1").with_stdout("one").run();
+}
@@ -0,0 +1,176 @@
+//! Tests for `build.target-dir = "global"` (`-Z global-target-dir`).
+
+use cargo_test_support::{basic_manifest, paths, project, Project};
+use std::path::PathBuf;
+
+fn global_project(name: &str) -> Project {
+    project()
+        .at(name)
+        .file("Cargo.toml", &basic_manifest(name, "0.1.0"))
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config.toml", "build.target-dir = 'global'")
+        .build()
+}
+
+fn global_target_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<_> = paths::home()
+        .join(".cache/cargo/targets")
+        .read_dir()
+        .map(|entries| entries.map(|e| e.unwrap().path()).collect())
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+#[cargo_test]
+fn global_target_dir() {
+    let p = global_project("foo");
+
+    p.cargo("build -Zglobal-target-dir")
+        .masquerade_as_nightly_cargo(&["global-target-dir"])
+        .run();
+    assert!(!p.root().join("target").exists());
+    assert!(!p.root().join("global").exists());
+
+    let dirs = global_target_dirs();
+    assert_eq!(dirs.len(), 1);
+    let target_dir = &dirs[0];
+    assert!(target_dir.join("debug/foo").is_file());
+    assert_eq!(
+        std::fs::read_to_string(target_dir.join(".cargo-workspace")).unwrap(),
+        p.root().to_str().unwrap()
+    );
+
+    p.cargo("metadata --format-version=1 -Zglobal-target-dir")
+        .masquerade_as_nightly_cargo(&["global-target-dir"])
+        .with_stdout_contains(format!(
+            r#"[..]"target_directory":"{}"[..]"#,
+            target_dir.display()
+        ))
+        .run();
+
+    p.cargo("build -Zglobal-target-dir")
+        .masquerade_as_nightly_cargo(&["global-target-dir"])
+        .with_stderr("[FINISHED] [..]")
+        .run();
+
+    // `--target-dir` and `CARGO_TARGET_DIR` take precedence.
+    p.cargo("build -Zglobal-target-dir")
+        .masquerade_as_nightly_cargo(&["global-target-dir"])
+        .env("CARGO_TARGET_DIR", "out")
+        .run();
+    assert!(p.root().join("out/debug/foo").is_file());
+}
+
+#[cargo_test]
+fn global_target_dir_requires_nightly() {
+    let p = global_project("foo");
+
+    // Without the flag, `global` is a directory like any other.
+    p.cargo("build").run();
+    assert!(p.root().join("global/debug/foo").is_file());
+    assert!(global_target_dirs().is_empty());
+}
+
+#[cargo_test]
+fn workspaces_are_isolated() {
+    let foo = global_project("foo");
+    let bar = global_project("bar");
+
+    for p in [&foo, &bar] {
+        p.cargo("build -Zglobal-target-dir")
+            .masquerade_as_nightly_cargo(&["global-target-dir"])
+            .run();
+    }
+    let dirs = global_target_dirs();
+    assert_eq!(dirs.len(), 2);
+    assert_eq!(
+        dirs.iter()
+            .filter(|d| d.join("debug/foo").is_file())
+            .count(),
+        1
+    );
+    assert_eq!(
+        dirs.iter()
+            .filter(|d| d.join("debug/bar").is_file())
+            .count(),
+        1
+    );
+
+    // Building from a member uses the directory of the workspace root.
+    foo.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [workspace]
+            members = ["member"]
+        "#,
+    );
+    foo.change_file("member/Cargo.toml", &basic_manifest("member", "0.1.0"));
+    foo.change_file("member/src/main.rs", "fn main() {}");
+    foo.cargo("build -Zglobal-target-dir")
+        .cwd("member")
+        .masquerade_as_nightly_cargo(&["global-target-dir"])
+        .run();
+    assert_eq!(global_target_dirs(), dirs);
+    assert_eq!(
+        dirs.iter()
+            .filter(|d| d.join("debug/member").is_file())
+            .count(),
+        1
+    );
+
+    // `cargo clean` only removes the directory of its workspace.
+    foo.cargo("clean -Zglobal-target-dir")
+        .masquerade_as_nightly_cargo(&["global-target-dir"])
+        .run();
+    let remaining = global_target_dirs();
+    assert_eq!(remaining.len(), 1);
+    assert!(remaining[0].join("debug/bar").is_file());
+}
+
+#[cargo_test]
+fn clean_gc() {
+    let foo = global_project("foo");
+    let bar = global_project("bar");
+
+    for p in [&foo, &bar] {
+        p.cargo("build -Zglobal-target-dir")
+            .masquerade_as_nightly_cargo(&["global-target-dir"])
+            .run();
+    }
+    assert_eq!(global_target_dirs().len(), 2);
+
+    bar.cargo("clean --gc -Zglobal-target-dir")
+        .masquerade_as_nightly_cargo(&["global-target-dir"])
+        .with_stderr("[..]Removed 0 global target directories")
+        .run();
+
+    std::fs::remove_dir_all(bar.root()).unwrap();
+    foo.cargo("clean --gc -Zglobal-target-dir")
+        .masquerade_as_nightly_cargo(&["global-target-dir"])
+        .with_stderr("[..]Removed 1 global target directory")
+        .run();
+    let remaining = global_target_dirs();
+    assert_eq!(remaining.len(), 1);
+    assert!(remaining[0].join("debug/foo").is_file());
+}
+
+#[cargo_test]
+fn clean_gc_requires_nightly() {
+    let p = global_project("foo");
+
+    p.cargo("clean --gc")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--gc` flag requires `-Z global-target-dir`
+See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#global-target-dir \
+for more information about the `--gc` flag.
+",
+        )
+        .run();
+}
@@ -0,0 +1,157 @@
+//! Tests for the `cargo info` command.
+
+use cargo_test_support::cargo_process;
+use cargo_test_support::registry::{self, Dependency, Package, RegistryBuilder, Response};
+
+fn publish_foo() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("foo", "0.1.0").rust_version("1.56").publish();
+    Package::new("foo", "0.2.0")
+        .rust_version("1.60")
+        .dep("bar", "1.0")
+        .add_dep(Dependency::new("baz", "0.1").optional(true))
+        .dev_dep("bar", "1.0")
+        .feature("default", &["std"])
+        .feature("std", &[])
+        .publish();
+    Package::new("foo", "0.3.0").yanked(true).publish();
+    Package::new("foo", "0.4.0-alpha.1").publish();
+}
+
+#[cargo_test]
+fn requires_unstable_options() {
+    let registry = registry::init();
+
+    cargo_process("info foo")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo info` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn latest_version() {
+    let registry = registry::init();
+    publish_foo();
+
+    cargo_process("info -Zunstable-options foo")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+foo@0.2.0
+rust-version: 1.60
+features:
+  baz = [dep:baz]
+  default = [std]
+  std = []
+dependencies:
+  bar ^1.0
+  baz ^0.1 (optional)
+dev-dependencies:
+  bar ^1.0
+versions:
+  0.4.0-alpha.1
+  0.3.0 (yanked)
+  0.2.0
+  0.1.0
+",
+        )
+        .with_stderr("[UPDATING] crates.io index")
+        .run();
+}
+
+#[cargo_test]
+fn selected_version() {
+    let registry = registry::init();
+    publish_foo();
+
+    cargo_process("info -Zunstable-options foo@0.3.0")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout_contains("foo@0.3.0 (yanked)")
+        .run();
+
+    cargo_process("info -Zunstable-options foo@<0.2")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout_contains("foo@0.1.0\nrust-version: 1.56\nversions:")
+        .run();
+
+    cargo_process("info -Zunstable-options foo@2")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr_contains("[ERROR] no versions of `foo` match `2` in registry `crates-io`")
+        .run();
+
+    cargo_process("info -Zunstable-options missing")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr_contains("[ERROR] could not find `missing` in registry `crates-io`")
+        .run();
+}
+
+#[cargo_test]
+fn json_with_api_metadata() {
+    let registry = RegistryBuilder::new()
+        .http_api()
+        .add_responder("/api/v1/crates/foo", |_, _| Response {
+            code: 200,
+            headers: vec![],
+            body: br#"{
+                "crate": {
+                    "description": "A foo",
+                    "homepage": null,
+                    "documentation": "https://docs.rs/foo",
+                    "repository": "https://github.com/foo/foo",
+                    "downloads": 1234
+                }
+            }"#
+            .to_vec(),
+        })
+        .build();
+    Package::new("foo", "0.1.0")
+        .rust_version("1.56")
+        .target_dep("bar", "1.0", "cfg(unix)")
+        .publish();
+
+    cargo_process("info -Zunstable-options foo --message-format json")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+    "name": "foo",
+    "version": "0.1.0",
+    "yanked": false,
+    "rust_version": "1.56",
+    "description": "A foo",
+    "homepage": null,
+    "documentation": "https://docs.rs/foo",
+    "repository": "https://github.com/foo/foo",
+    "downloads": 1234,
+    "features": {},
+    "dependencies": [
+        {
+            "name": "bar",
+            "package": null,
+            "req": "^1.0",
+            "kind": null,
+            "optional": false,
+            "target": "cfg(unix)",
+            "features": [],
+            "default_features": true
+        }
+    ],
+    "versions": [{"version": "0.1.0", "yanked": false, "rust_version": "1.56"}]
+}
+"#,
+        )
+        .run();
+}
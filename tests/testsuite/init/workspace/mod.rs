@@ -0,0 +1,39 @@
+use cargo_test_support::compare::assert_ui;
+use cargo_test_support::prelude::*;
+use cargo_test_support::Project;
+
+use cargo_test_support::curr_dir;
+
+#[cargo_test]
+fn workspace() {
+    let project = Project::from_template(curr_dir!().join("in"));
+    let project_root = &project.root();
+
+    snapbox::cmd::Command::cargo_ui()
+        .arg_line("init --workspace")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .current_dir(project_root)
+        .assert()
+        .code(101)
+        .stderr_eq(
+            "error: the `--workspace` flag is unstable, pass `-Z unstable-options` to enable it\n",
+        );
+
+    snapbox::cmd::Command::cargo_ui()
+        .arg_line("init -Zunstable-options --workspace --vcs none")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .current_dir(project_root)
+        .assert()
+        .success()
+        .stdout_matches_path(curr_dir!().join("stdout.log"))
+        .stderr_matches_path(curr_dir!().join("stderr.log"));
+
+    assert_ui().subset_matches(curr_dir!().join("out"), project_root);
+    assert!(!project_root.join(".gitignore").is_file());
+
+    snapbox::cmd::Command::cargo_ui()
+        .current_dir(project_root)
+        .arg("build")
+        .assert()
+        .success();
+}
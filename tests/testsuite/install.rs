@@ -7,15 +7,16 @@ use cargo_test_support::cross_compile;
 use cargo_test_support::git;
 use cargo_test_support::registry::{self, registry_path, Package};
 use cargo_test_support::{
-    basic_manifest, cargo_process, no_such_file_err_msg, project, project_in, symlink_supported, t,
+    basic_manifest, cargo_process, no_such_file_err_msg, project, project_in, rustc_host,
+    symlink_supported, t,
 };
 
 use cargo_test_support::install::{
-    assert_has_installed_exe, assert_has_not_installed_exe, cargo_home,
+    assert_has_installed_exe, assert_has_not_installed_exe, cargo_home, exe,
 };
 use cargo_test_support::paths::{self, CargoPathExt};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn pkg(name: &str, vers: &str) {
     Package::new(name, vers)
@@ -1152,6 +1153,68 @@ Caused by:
         .run();
 }
 
+#[cargo_test]
+fn uninstall_workspace_and_list_orphans() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file("a/Cargo.toml", &basic_manifest("a", "0.1.0"))
+        .file("a/src/main.rs", "fn main() {}")
+        .file("b/Cargo.toml", &basic_manifest("b", "0.1.0"))
+        .file("b/src/main.rs", "fn main() {}")
+        .build();
+    let other = project_in("other")
+        .file("Cargo.toml", &basic_manifest("other", "0.1.0"))
+        .file("src/main.rs", "fn main() {}")
+        .build();
+    p.cargo("install --path a").run();
+    p.cargo("install --path b").run();
+    other.cargo("install --path .").run();
+
+    p.cargo("uninstall --workspace")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--workspace` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+    p.cargo("uninstall --workspace -Zunstable-options")
+        .cwd(p.root().join("b"))
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(&format!(
+            "\
+[REMOVING] {home}/bin/a[EXE]
+[REMOVING] {home}/bin/b[EXE]",
+            home = cargo_home().display()
+        ))
+        .run();
+    assert_has_not_installed_exe(cargo_home(), "a");
+    assert_has_not_installed_exe(cargo_home(), "b");
+    assert_has_installed_exe(cargo_home(), "other");
+
+    p.cargo("install --path a").run();
+    cargo_process("install --list --orphans -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout("")
+        .run();
+    other.root().rm_rf();
+    cargo_process("install --list --orphans -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+other v0.1.0 ([..]/other/foo):
+    other[EXE]
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn do_not_rebuilds_on_local_install() {
     let p = project().file("src/main.rs", "fn main() {}").build();
@@ -1611,6 +1674,274 @@ dependencies = [
     p.cargo("install --path . --locked").run();
 }
 
+#[cargo_test]
+fn install_path_use_project_config() {
+    // `--use-project-config` uses Cargo.lock like `cargo build` does, without
+    // requiring `--locked`, and picks up the project's config.
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.1.1")
+        .file("src/lib.rs", "not rust")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"
+                extern crate bar;
+                #[cfg(not(from_config))]
+                compile_error!("missing rustflags from config");
+                fn main() {}
+            "#,
+        )
+        .file(
+            ".cargo/config.toml",
+            "build.rustflags = ['--cfg', 'from_config']",
+        )
+        .file(
+            "Cargo.lock",
+            r#"
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+"#,
+        )
+        .build();
+
+    p.cargo("install --path . --use-project-config")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--use-project-config` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+    p.cargo("install --use-project-config")
+        .with_status(1)
+        .with_stderr_contains("[..]--path <PATH>[..]")
+        .run();
+
+    p.cargo("install --path . --use-project-config -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains("[COMPILING] bar v0.1.0")
+        .with_stderr_contains("[INSTALLED] package `foo v0.1.0 ([CWD])` (executable `foo[EXE]`)")
+        .run();
+    assert_has_installed_exe(cargo_home(), "foo");
+}
+
+#[cargo_test]
+fn install_reuse_target_dir() {
+    Package::new("dep", "1.0.0").publish();
+    Package::new("foo", "0.1.0")
+        .dep("dep", "1.0")
+        .file("src/main.rs", "extern crate dep; fn main() {}")
+        .publish();
+    Package::new("bar", "0.1.0")
+        .dep("dep", "1.0")
+        .file("src/main.rs", "extern crate dep; fn main() {}")
+        .publish();
+
+    cargo_process("install foo --reuse-target-dir")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--reuse-target-dir` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    cargo_process("install foo --reuse-target-dir -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains("[COMPILING] dep v1.0.0")
+        .with_stderr_contains("[INSTALLED] package `foo v0.1.0` (executable `foo[EXE]`)")
+        .run();
+    assert_has_installed_exe(cargo_home(), "foo");
+    let shared: Vec<_> = fs::read_dir(cargo_home().join("install-target"))
+        .unwrap()
+        .collect();
+    assert_eq!(shared.len(), 1);
+
+    // Another tool depending on `dep` doesn't rebuild it.
+    cargo_process("install bar --reuse-target-dir -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_does_not_contain("[COMPILING] dep v1.0.0")
+        .with_stderr_contains("[COMPILING] bar v0.1.0")
+        .run();
+    assert_has_installed_exe(cargo_home(), "bar");
+
+    // Reinstalling doesn't rebuild anything.
+    cargo_process("install foo --force --reuse-target-dir -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_does_not_contain("[COMPILING] [..]")
+        .with_stderr_contains("[REPLACING] [..]foo[EXE]")
+        .run();
+}
+
+#[cargo_test]
+fn install_prefer_binary() {
+    let archive = paths::root().join("foo.tar.gz");
+    {
+        let encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&archive).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut ar = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(8);
+        header.set_mode(0o755);
+        header.set_cksum();
+        ar.append_data(
+            &mut header,
+            format!("foo{}", env::consts::EXE_SUFFIX),
+            &b"prebuilt"[..],
+        )
+        .unwrap();
+        ar.into_inner().unwrap().finish().unwrap();
+    }
+    // An archive whose binary is a link instead of a file.
+    let link_archive = paths::root().join("link.tar.gz");
+    {
+        let encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&link_archive).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut ar = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        header.set_mode(0o755);
+        ar.append_link(
+            &mut header,
+            format!("foo{}", env::consts::EXE_SUFFIX),
+            "/etc/passwd",
+        )
+        .unwrap();
+        ar.into_inner().unwrap().finish().unwrap();
+    }
+    let checksum = |archive: &Path| {
+        cargo_util::Sha256::new()
+            .update(&fs::read(archive).unwrap())
+            .finish_hex()
+    };
+    let manifest = |version: &str, archive: &Path, checksum: &str| {
+        format!(
+            r#"
+                [package]
+                name = "foo"
+                version = "{}"
+
+                [package.metadata.binaries.{}]
+                url = "{}"
+                checksum = "{}"
+            "#,
+            version,
+            rustc_host(),
+            url::Url::from_file_path(archive).unwrap(),
+            checksum
+        )
+    };
+    Package::new("foo", "0.1.0")
+        .file(
+            "Cargo.toml",
+            &manifest("0.1.0", &archive, &checksum(&archive)),
+        )
+        .file("src/main.rs", r#"compile_error!("built from source");"#)
+        .publish();
+    Package::new("foo", "0.2.0")
+        .file("Cargo.toml", &manifest("0.2.0", &archive, "0000"))
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    Package::new("foo", "0.3.0")
+        .file(
+            "Cargo.toml",
+            &manifest("0.3.0", &link_archive, &checksum(&link_archive)),
+        )
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    pkg("bar", "0.1.0");
+
+    cargo_process("install foo --prefer-binary")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--prefer-binary` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    cargo_process("install foo@0.1.0 --prefer-binary -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] foo v0.1.0 (registry `dummy-registry`)
+[INSTALLING] foo v0.1.0
+[DOWNLOADING] pre-built binaries of foo v0.1.0
+[INSTALLING] [CWD]/home/.cargo/bin/foo[EXE]
+[INSTALLED] package `foo v0.1.0` (executable `foo[EXE]`)
+[WARNING] be sure to add `[..]` to your PATH to be able to run the installed binaries
+",
+        )
+        .run();
+    let installed = cargo_home().join("bin").join(exe("foo"));
+    assert_eq!(fs::read(&installed).unwrap(), b"prebuilt");
+
+    // A mismatching checksum is an error rather than a reason to build from
+    // source.
+    cargo_process("install foo@0.2.0 --prefer-binary -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] checksum of the pre-built binaries of `foo v0.2.0` downloaded from `file://[..]/foo.tar.gz` does not match
+expected: 0000
+actual:   [..]",
+        )
+        .run();
+
+    // Links aren't unpacked, so the binary is built from source.
+    cargo_process("install foo@0.3.0 --force --prefer-binary -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains(
+            "[WARNING] the pre-built binaries of `foo v0.3.0` downloaded from \
+             `file://[..]/link.tar.gz` don't contain all executables of the package, \
+             building from source",
+        )
+        .with_stderr_contains("[COMPILING] foo v0.3.0")
+        .run();
+    assert!(!fs::symlink_metadata(&installed)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+
+    cargo_process("install bar --prefer-binary -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains(
+            "[NOTE] `bar v0.1.0` has no pre-built binaries for `[..]`, building from source",
+        )
+        .with_stderr_contains("[COMPILING] bar v0.1.0")
+        .run();
+    assert_has_installed_exe(cargo_home(), "bar");
+}
+
 #[cargo_test]
 fn lock_file_path_deps_ok() {
     Package::new("bar", "0.1.0").publish();
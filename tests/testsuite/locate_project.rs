@@ -1,6 +1,6 @@
 //! Tests for the `cargo locate-project` command.
 
-use cargo_test_support::project;
+use cargo_test_support::{basic_manifest, project};
 
 #[cargo_test]
 fn simple() {
@@ -74,3 +74,74 @@ fn workspace() {
         .with_json(outer_manifest)
         .run();
 }
+
+#[cargo_test]
+fn details() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file("a/Cargo.toml", &basic_manifest("a", "0.1.0"))
+        .file("a/src/lib.rs", "")
+        .file("b/Cargo.toml", &basic_manifest("b", "0.1.0"))
+        .file("b/src/lib.rs", "")
+        .file("rust-toolchain.toml", "[toolchain]\nchannel = \"stable\"")
+        .file(".cargo/config.toml", "build.target-dir = 'out'")
+        .file("a/.cargo/config", "")
+        .build();
+
+    p.cargo("locate-project --details -Zunstable-options")
+        .cwd("a")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+                {
+                    "root": "[ROOT]/foo/a/Cargo.toml",
+                    "workspace_root": "[ROOT]/foo/Cargo.toml",
+                    "members": ["[ROOT]/foo/a/Cargo.toml", "[ROOT]/foo/b/Cargo.toml"],
+                    "toolchain_file": "[ROOT]/foo/rust-toolchain.toml",
+                    "config_files": [
+                        "[ROOT]/foo/a/.cargo/config",
+                        "[ROOT]/foo/.cargo/config.toml"
+                    ],
+                    "target_directory": "[ROOT]/foo/out"
+                }
+            "#,
+        )
+        .run();
+
+    p.cargo("locate-project --workspace --details -Zunstable-options")
+        .cwd("b")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+                {
+                    "root": "[ROOT]/foo/Cargo.toml",
+                    "workspace_root": "[ROOT]/foo/Cargo.toml",
+                    "members": "{...}",
+                    "toolchain_file": "[ROOT]/foo/rust-toolchain.toml",
+                    "config_files": "{...}",
+                    "target_directory": "[ROOT]/foo/out"
+                }
+            "#,
+        )
+        .run();
+
+    p.cargo("locate-project --details --message-format plain -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr("[ERROR] the `--details` flag requires `--message-format json`")
+        .run();
+
+    p.cargo("locate-project --details")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--details` flag is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
@@ -888,3 +888,439 @@ perhaps a crate was updated and forgotten to be re-vendored?
         )
         .run();
 }
+
+#[cargo_test]
+fn upgrade_format_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("lock upgrade-format")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo lock` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn upgrade_format_v1_to_v3() {
+    let cksum = Package::new("bar", "0.1.0").publish();
+
+    let old_lockfile = format!(
+        r#"[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+
+[metadata]
+"checksum bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)" = "{}"
+"#,
+        cksum,
+    );
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "0.1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("Cargo.lock", &old_lockfile)
+        .build();
+
+    p.cargo("lock upgrade-format --dry-run -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[UPGRADING] Cargo.lock from version 1 to version 3
+             - checksums move from the `[metadata]` table into the `[[package]]` entries
+             - dependencies are referred to by name only, unless several versions \
+or sources of a package are locked
+             - a `version = 3` marker is added to the top of the file
+[WARNING] not updating lock file due to dry run
+",
+        )
+        .run();
+    assert_match_exact(&old_lockfile, &p.read_lockfile());
+
+    p.cargo("lock upgrade-format -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains("[UPGRADING] Cargo.lock from version 1 to version 3")
+        .run();
+    let expected = format!(
+        r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{}"
+
+[[package]]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar",
+]
+"#,
+        cksum
+    );
+    assert_match_exact(&expected, &p.read_lockfile());
+
+    p.cargo("lock upgrade-format -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains("[NOTE] Cargo.lock is already at version 3")
+        .run();
+}
+
+#[cargo_test]
+fn downgrade_format_v3_to_v1() {
+    let cksum = Package::new("bar", "0.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "0.1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    p.cargo("lock upgrade-format --to 1 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[DOWNGRADING] Cargo.lock from version 3 to version 1
+             - checksums move from the `[[package]]` entries into the `[metadata]` table
+             - dependencies are always referred to by name, version and source
+             - the `version = 3` marker is removed
+[WARNING] Cargo will upgrade the lock file to version 3 again the next time it changes
+",
+        )
+        .run();
+    let expected = format!(
+        r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+
+[metadata]
+"checksum bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)" = "{}"
+"#,
+        cksum
+    );
+    assert_match_exact(&expected, &p.read_lockfile());
+
+    // The downgraded lock file is still accepted as is.
+    p.cargo("build --locked").run();
+}
+
+#[cargo_test]
+fn downgrade_format_refused_for_master_branch() {
+    let git_project = git::new("dep1", |project| {
+        project
+            .file("Cargo.toml", &basic_lib_manifest("dep1"))
+            .file("src/lib.rs", "")
+    });
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+
+                    [dependencies]
+                    dep1 = {{ git = '{}', branch = 'master' }}
+                "#,
+                git_project.url(),
+            ),
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    p.cargo("lock upgrade-format --to 2 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] cannot change the lock file to version 2, because it can't distinguish \
+git dependencies on `branch = \"master\"` from those on the default branch
+The following packages use `branch = \"master\"`: dep1
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn upgrade_format_refused_for_outdated_lockfile() {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("baz", "0.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "0.1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            bar = "0.1.0"
+            baz = "0.1.0"
+        "#,
+    );
+
+    p.cargo("lock upgrade-format --to 2 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[ERROR] the lock file [..]Cargo.lock is out of date, so changing its format would \
+also change the locked packages
+Make sure the lock file is up to date, for example by building the workspace, \
+before changing its format.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn fmt_lockfile() {
+    let cksum = Package::new("bar", "0.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "0.1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "Cargo.lock",
+            &format!(
+                r#"[[package]]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar",
+]
+
+[[package]]
+checksum = "{}"
+name = "bar"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+version = "0.1.0"
+"#,
+                cksum
+            ),
+        )
+        .build();
+
+    p.cargo("lock fmt --dry-run -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[FORMATTING] Cargo.lock
+             - the format changes from version 2 to version 3
+[WARNING] not updating lock file due to dry run
+",
+        )
+        .run();
+    assert!(!p.read_lockfile().starts_with('#'));
+
+    p.cargo("lock fmt -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains("[FORMATTING] Cargo.lock")
+        .run();
+    let expected = format!(
+        r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{}"
+
+[[package]]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar",
+]
+"#,
+        cksum
+    );
+    assert_match_exact(&expected, &p.read_lockfile());
+
+    p.cargo("lock fmt -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr("[NOTE] Cargo.lock is already formatted")
+        .run();
+}
+
+#[cargo_test]
+fn dedupe_lockfile() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "0.1.0")
+        .dep("bar", ">=1.0, <3")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                baz = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("bar", "2.0.0").publish();
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            bar = "2"
+            baz = "0.1"
+        "#,
+    );
+    p.cargo("fetch").run();
+    let lockfile = p.read_lockfile();
+    assert!(lockfile.contains("\"bar 1.0.0\""));
+    assert!(lockfile.contains("\"bar 2.0.0\""));
+
+    p.cargo("lock dedupe --dry-run -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[REMOVING] bar v1.0.0
+[WARNING] not updating lock file due to dry run
+",
+        )
+        .run();
+    assert_match_exact(&lockfile, &p.read_lockfile());
+
+    p.cargo("lock dedupe -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[REMOVING] bar v1.0.0
+",
+        )
+        .run();
+    assert!(!p.read_lockfile().contains("1.0.0"));
+
+    p.cargo("lock dedupe -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[NOTE] Cargo.lock contains no duplicate packages
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn dedupe_lockfile_reports_remaining_duplicates() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("bar", "2.0.0").publish();
+    Package::new("baz", "0.1.0").dep("bar", "1").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "2"
+                baz = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    let lockfile = p.read_lockfile();
+
+    p.cargo("lock dedupe -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "[NOTE] `bar` is still locked in several versions: v1.0.0, v2.0.0
+",
+        )
+        .run();
+    assert_match_exact(&lockfile, &p.read_lockfile());
+}
@@ -0,0 +1,238 @@
+//! Tests for `[machine-profiles]` in config and `--machine-profile`.
+
+use cargo_test_support::{basic_lib_manifest, basic_manifest, project};
+
+fn machine_profile_project() -> cargo_test_support::Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [profile.dev]
+                opt-level = 1
+
+                [machine-profiles.laptop]
+                jobs = 2
+
+                [machine-profiles.laptop.profile.dev]
+                opt-level = 2
+
+                [machine-profiles.ci-large.profile.dev.package.bar]
+                opt-level = 3
+
+                [machine-profiles.ci-large.profile.release]
+                lto = true
+            "#,
+        )
+        .build()
+}
+
+#[cargo_test]
+fn requires_unstable_flag() {
+    let p = machine_profile_project();
+
+    p.cargo("build --machine-profile laptop")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--machine-profile` flag is unstable, pass `-Z machine-profiles` to enable it
+",
+        )
+        .run();
+
+    // A selection from config is ignored without the flag.
+    p.cargo("build -v")
+        .env("CARGO_BUILD_MACHINE_PROFILE", "laptop")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-C opt-level=1 [..]")
+        .run();
+}
+
+#[cargo_test]
+fn per_package_overrides() {
+    let p = machine_profile_project();
+
+    p.cargo("build -v -Zmachine-profiles --machine-profile ci-large")
+        .masquerade_as_nightly_cargo(&["machine-profiles"])
+        .with_stderr_contains("[RUNNING] `rustc --crate-name bar [..]-C opt-level=3 [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-C opt-level=1 [..]")
+        .run();
+
+    p.cargo("build --release -v -Zmachine-profiles --machine-profile ci-large")
+        .masquerade_as_nightly_cargo(&["machine-profiles"])
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-C linker-plugin-lto [..]")
+        .run();
+}
+
+#[cargo_test]
+fn selected_from_env() {
+    let p = machine_profile_project();
+
+    p.cargo("build -v -Zmachine-profiles")
+        .masquerade_as_nightly_cargo(&["machine-profiles"])
+        .env("CARGO_BUILD_MACHINE_PROFILE", "laptop")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-C opt-level=2 [..]")
+        .run();
+}
+
+#[cargo_test]
+fn precedence() {
+    let p = machine_profile_project();
+
+    // `--config` wins over the machine profile.
+    p.cargo("build -v -Zmachine-profiles --machine-profile laptop")
+        .arg("--config")
+        .arg("profile.dev.opt-level=3")
+        .masquerade_as_nightly_cargo(&["machine-profiles"])
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-C opt-level=3 [..]")
+        .run();
+
+    // So do environment variables.
+    p.cargo("build -v -Zmachine-profiles --machine-profile laptop")
+        .env("CARGO_PROFILE_DEV_OPT_LEVEL", "0")
+        .masquerade_as_nightly_cargo(&["machine-profiles"])
+        .with_stderr_does_not_contain("[..]-C opt-level=[..]")
+        .run();
+}
+
+#[cargo_test]
+fn undefined_profile() {
+    let p = machine_profile_project();
+
+    p.cargo("build -Zmachine-profiles --machine-profile desktop")
+        .masquerade_as_nightly_cargo(&["machine-profiles"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] machine profile `desktop` is not defined (selected by --config cli option)
+available machine profiles: ci-large, laptop
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn unknown_key() {
+    let p = project()
+        .file("Cargo.toml", &basic_lib_manifest("foo"))
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [machine-profiles.laptop]
+                linker = "cc"
+            "#,
+        )
+        .build();
+
+    p.cargo("build -Zmachine-profiles --machine-profile laptop")
+        .masquerade_as_nightly_cargo(&["machine-profiles"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] unknown key `linker` in machine profile `laptop` ([..]/foo/.cargo/config.toml)
+supported keys are: `jobs`, `incremental`, `profile`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn config_lint() {
+    let p = machine_profile_project();
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build]
+            jobs = 8
+
+            [machine-profiles.laptop]
+            jobs = 2
+            incremental = false
+
+            [machine-profiles.laptop.profile.dev]
+            opt-level = 2
+
+            [machine-profiles.ci-large]
+            linker = "cc"
+        "#,
+    );
+
+    p.cargo("config lint -Zunstable-options -Zmachine-profiles")
+        .masquerade_as_nightly_cargo(&["cargo-config", "machine-profiles"])
+        .with_stdout("# no machine profile selected")
+        .with_stderr(
+            "\
+[WARNING] unknown key `linker` in machine profile `ci-large` ([..]/foo/.cargo/config.toml)
+",
+        )
+        .run();
+
+    p.cargo("config lint -Zunstable-options -Zmachine-profiles")
+        .masquerade_as_nightly_cargo(&["cargo-config", "machine-profiles"])
+        .arg("--config")
+        .arg("build.incremental=true")
+        .env("CARGO_BUILD_MACHINE_PROFILE", "laptop")
+        .env("CARGO_PROFILE_DEV_OPT_LEVEL", "1")
+        .with_stdout(
+            "\
+# machine profile `laptop` selected by environment variable `CARGO_BUILD_MACHINE_PROFILE`
+# precedence: command-line flags > --config > environment variables > machine profile > config files
+build.incremental = true # --config cli option, shadowing machine profile value from [..]/foo/.cargo/config.toml
+build.jobs = 2 # [..]/foo/.cargo/config.toml, overriding [..]/foo/.cargo/config.toml
+profile.dev.opt-level = 1 # environment variable `CARGO_PROFILE_DEV_OPT_LEVEL`, shadowing machine profile value from [..]/foo/.cargo/config.toml
+",
+        )
+        .with_stderr(
+            "\
+[WARNING] unknown key `linker` in machine profile `ci-large` ([..]/foo/.cargo/config.toml)
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn requires_unstable_flag_from_config_cli() {
+    let p = machine_profile_project();
+    p.change_file(
+        "machine-profile-laptop.toml",
+        "build.machine-profile = \"laptop\"",
+    );
+
+    p.cargo("build --config machine-profile-laptop.toml")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `build.machine-profile` in `[CWD]/machine-profile-laptop.toml` is unstable, \
+pass `-Z machine-profiles` to enable it
+",
+        )
+        .run();
+
+    // Only the key matters, not the name of the file.
+    p.change_file("machine-profile-jobs.toml", "build.jobs = 1");
+    p.cargo("build --config machine-profile-jobs.toml").run();
+
+    p.cargo("build --config")
+        .arg("build.machine-profile='laptop'")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `build.machine-profile` passed with `--config` is unstable, \
+pass `-Z machine-profiles` to enable it
+",
+        )
+        .run();
+}
@@ -9,15 +9,18 @@ extern crate cargo_test_macro;
 mod advanced_env;
 mod alt_registry;
 mod artifact_dep;
+mod artifact_manifest;
 mod bad_config;
 mod bad_manifest_path;
 mod bench;
 mod binary_name;
 mod build;
+mod build_executor;
 mod build_plan;
 mod build_script;
 mod build_script_env;
 mod build_script_extra_link_arg;
+mod build_warnings;
 mod cache_messages;
 mod cargo_add;
 mod cargo_alias_config;
@@ -28,9 +31,11 @@ mod cargo_features;
 mod cargo_remove;
 mod cargo_targets;
 mod cfg;
+mod changelog;
 mod check;
 mod check_cfg;
 mod clean;
+mod codegen_backend;
 mod collisions;
 mod concurrent;
 mod config;
@@ -48,10 +53,12 @@ mod doc;
 mod docscrape;
 mod edition;
 mod error;
+mod example_dependencies;
 mod features;
 mod features2;
 mod features_namespaced;
 mod fetch;
+mod fine_grained_locking;
 mod fix;
 mod freshness;
 mod future_incompat_report;
@@ -60,7 +67,9 @@ mod git;
 mod git_auth;
 mod git_gc;
 mod glob_targets;
+mod global_target_dir;
 mod help;
+mod info;
 mod inheritable_workspace_fields;
 mod init;
 mod install;
@@ -73,6 +82,7 @@ mod lockfile_compat;
 mod login;
 mod logout;
 mod lto;
+mod machine_profiles;
 mod member_discovery;
 mod member_errors;
 mod message_format;
@@ -109,25 +119,34 @@ mod registry;
 mod registry_auth;
 mod rename_deps;
 mod replace;
+mod report;
 mod required_features;
+mod required_toolchain;
 mod run;
 mod rust_version;
 mod rustc;
+mod rustc_info;
 mod rustc_info_cache;
 mod rustdoc;
 mod rustdoc_extern_html;
 mod rustdocflags;
 mod rustflags;
+mod script;
 mod search;
+mod semver_check;
 mod shell_quoting;
+mod source_policy;
 mod source_replacement;
 mod standard_lib;
+mod target_layout;
+mod target_runners;
 mod test;
 mod timings;
 mod tool_paths;
 mod tree;
 mod tree_graph_features;
 mod unit_graph;
+mod unused_dependencies;
 mod update;
 mod vendor;
 mod verify_project;
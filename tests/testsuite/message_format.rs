@@ -131,3 +131,226 @@ fn cargo_renders_doctests() {
         .with_stdout_contains("[..]src/lib.rs - bar (line 1)[..]")
         .run();
 }
+
+#[cargo_test]
+fn build_progress() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("check -j1 --message-format json -Zbuild-progress")
+        .masquerade_as_nightly_cargo(&["build-progress"])
+        .with_json_contains_unordered(
+            r#"
+                {"reason":"build-progress","total":2,"finished":0,"active":["bar"],"eta_secs":null}
+
+                {"reason":"build-progress","total":2,"finished":1,"active":["foo"],"eta_secs":"{...}"}
+
+                {"reason":"build-progress","total":2,"finished":2,"active":[],"eta_secs":"{...}"}
+            "#,
+        )
+        .run();
+
+    // Without `-Z build-progress`, no progress messages are emitted.
+    p.cargo("check --message-format json")
+        .with_stdout_does_not_contain("[..]build-progress[..]")
+        .run();
+}
+
+#[cargo_test]
+fn json_v2_requires_unstable_options() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.1.0"))
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check --message-format json-v2")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "error: the `--message-format=json-v2` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("check --message-format json,json-v2 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr("error: cannot specify two kinds of `message-format` arguments")
+        .run();
+}
+
+// The messages of `json-v2` are a stable interface, this guards their schema.
+#[cargo_test]
+fn json_v2_schema() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.1.0"))
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"fn main() { println!("cargo:rustc-cfg=xyz"); }"#,
+        )
+        .build();
+
+    let output = |fresh| {
+        r#"
+            {
+                "schema": "cargo-message/2",
+                "cargo_version": "[..]",
+                "reason": "compiler-artifact",
+                "package_id": "foo 0.1.0 ([..])",
+                "manifest_path": "[..]",
+                "target": {
+                    "kind": ["custom-build"],
+                    "crate_types": ["bin"],
+                    "doc": false,
+                    "doctest": false,
+                    "edition": "2015",
+                    "name": "build-script-build",
+                    "src_path": "[..]build.rs",
+                    "test": false
+                },
+                "profile": {
+                    "debug_assertions": true,
+                    "debuginfo": 2,
+                    "opt_level": "0",
+                    "overflow_checks": true,
+                    "test": false
+                },
+                "executable": null,
+                "features": [],
+                "filenames": "{...}",
+                "fresh": $FRESH
+            }
+
+            {
+                "schema": "cargo-message/2",
+                "cargo_version": "[..]",
+                "reason": "build-script-executed",
+                "package_id": "foo 0.1.0 ([..])",
+                "linked_libs": [],
+                "linked_paths": [],
+                "env": [],
+                "cfgs": ["xyz"],
+                "out_dir": "[..]target/debug/build/foo-[..]/out"
+            }
+
+            {
+                "schema": "cargo-message/2",
+                "cargo_version": "[..]",
+                "reason": "compiler-artifact",
+                "package_id": "foo 0.1.0 ([..])",
+                "manifest_path": "[..]",
+                "target": {
+                    "kind": ["lib"],
+                    "crate_types": ["lib"],
+                    "doc": true,
+                    "doctest": true,
+                    "edition": "2015",
+                    "name": "foo",
+                    "src_path": "[..]lib.rs",
+                    "test": true
+                },
+                "profile": {
+                    "debug_assertions": true,
+                    "debuginfo": 2,
+                    "opt_level": "0",
+                    "overflow_checks": true,
+                    "test": false
+                },
+                "executable": null,
+                "features": [],
+                "filenames": ["[..].rlib", "[..].rmeta"],
+                "fresh": $FRESH
+            }
+
+            {
+                "schema": "cargo-message/2",
+                "cargo_version": "[..]",
+                "reason": "build-finished",
+                "success": true
+            }
+        "#
+        .replace("$FRESH", fresh)
+    };
+
+    p.cargo("build --message-format json-v2 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(&output("false"))
+        .run();
+
+    p.cargo("build --message-format json-v2 -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(&output("true"))
+        .run();
+}
+
+#[cargo_test]
+fn json_v2_artifact_dep_env() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [build-dependencies]
+                bar = { path = "bar", artifact = "bin" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build --message-format json-v2 -Zunstable-options -Zbindeps")
+        .masquerade_as_nightly_cargo(&["unstable-options", "bindeps"])
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "schema": "cargo-message/2",
+                    "cargo_version": "[..]",
+                    "reason": "artifact-dep-env",
+                    "package_id": "foo 0.1.0 ([..])",
+                    "target": {
+                        "kind": ["custom-build"],
+                        "crate_types": ["bin"],
+                        "doc": false,
+                        "doctest": false,
+                        "edition": "2015",
+                        "name": "build-script-build",
+                        "src_path": "[..]build.rs",
+                        "test": false
+                    },
+                    "mode": "run-custom-build",
+                    "env": {
+                        "CARGO_BIN_DIR_BAR": "[..]/target/debug/deps/artifact/bar-[..]/bin",
+                        "CARGO_BIN_FILE_BAR": "[..]/target/debug/deps/artifact/bar-[..]/bin/bar[..]",
+                        "CARGO_BIN_FILE_BAR_bar": "[..]/target/debug/deps/artifact/bar-[..]/bin/bar[..]"
+                    }
+                }
+            "#,
+        )
+        .run();
+
+    // Without `json-v2`, the environment isn't reported.
+    p.cargo("build --message-format json -Zbindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stdout_does_not_contain("[..]artifact-dep-env[..]")
+        .run();
+}
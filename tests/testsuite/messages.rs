@@ -2,7 +2,7 @@
 //!
 //! Tests for message caching can be found in `cache_messages`.
 
-use cargo_test_support::{process, project, Project};
+use cargo_test_support::{basic_manifest, process, project, Project};
 use cargo_util::ProcessError;
 
 /// Captures the actual diagnostics displayed by rustc. This is done to avoid
@@ -76,6 +76,51 @@ warning: `foo` (lib test) generated 1 warning (1 duplicate)
         .run();
 }
 
+#[cargo_test]
+fn show_duplicate_warnings() {
+    let p = project()
+        .file(
+            "src/lib.rs",
+            r#"
+                pub fn foo() {
+                    let x = 1;
+                }
+            "#,
+        )
+        .build();
+    let rustc_message = raw_rustc_output(&p, "src/lib.rs", &[]);
+    p.cargo("test --no-run -j1")
+        .masquerade_as_nightly_cargo(&["show-duplicate-warnings"])
+        .with_stderr(&format!(
+            "\
+[COMPILING] foo [..]
+{}\
+warning: `foo` (lib) generated 1 warning (run `cargo fix --lib -p foo` to apply 1 suggestion)
+warning: `foo` (lib test) generated 1 warning (1 duplicate)
+[NOTE] 1 duplicate warning was not shown, pass `-Z show-duplicate-warnings` to show every instance
+[FINISHED] [..]
+[EXECUTABLE] unittests src/lib.rs (target/debug/deps/foo-[..][EXE])
+",
+            rustc_message
+        ))
+        .run();
+
+    p.cargo("test --no-run -j1 -Zshow-duplicate-warnings")
+        .masquerade_as_nightly_cargo(&["show-duplicate-warnings"])
+        .with_stderr(&format!(
+            "\
+{0}\
+warning: `foo` (lib) generated 1 warning (run `cargo fix --lib -p foo` to apply 1 suggestion)
+{0}\
+warning: `foo` (lib test) generated 1 warning (run `cargo fix --lib -p foo --tests` to apply 1 suggestion)
+[FINISHED] [..]
+[EXECUTABLE] unittests src/lib.rs (target/debug/deps/foo-[..][EXE])
+",
+            rustc_message
+        ))
+        .run();
+}
+
 #[cargo_test]
 fn deduplicate_messages_mismatched_warnings() {
     // One execution prints 1 warning, the other prints 2 where there is an overlap.
@@ -142,3 +187,63 @@ fn deduplicate_errors() {
         ))
         .run();
 }
+
+#[cargo_test]
+fn grouped_output() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/lib.rs", "fn unused_foo() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "fn unused_bar() {}")
+        .build();
+
+    p.cargo("check -Zgrouped-output")
+        .masquerade_as_nightly_cargo(&["grouped-output"])
+        .with_stderr(
+            "\
+[CHECKING] bar v0.1.0 ([..])
+::group::bar v0.1.0 (lib)
+[WARNING] function `unused_bar` is never used
+[..]
+[..]
+[..]
+[..]
+[..]
+[..]
+
+::endgroup::
+[WARNING] `bar` (lib) generated 1 warning
+[CHECKING] foo v0.1.0 ([..])
+::group::foo v0.1.0 (lib)
+[WARNING] function `unused_foo` is never used
+[..]
+[..]
+[..]
+[..]
+[..]
+[..]
+
+::endgroup::
+[WARNING] `foo` (lib) generated 1 warning
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // Replayed diagnostics are grouped as well.
+    p.cargo("check -Zgrouped-output")
+        .masquerade_as_nightly_cargo(&["grouped-output"])
+        .with_stderr_contains("::group::foo v0.1.0 (lib)")
+        .with_stderr_contains("::endgroup::")
+        .run();
+}
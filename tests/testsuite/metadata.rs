@@ -4055,3 +4055,228 @@ fn workspace_metadata_with_dependencies_no_deps_artifact() {
         )
         .run();
 }
+
+#[cargo_test]
+fn features_graph() {
+    Package::new("bar", "1.0.0")
+        .feature("default", &["std"])
+        .feature("std", &[])
+        .feature("alloc", &[])
+        .feature("serde", &[])
+        .publish();
+    Package::new("opt", "1.0.0").feature("serde", &[]).publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = { version = "1.0", features = ["alloc"] }
+                opt = { version = "1.0", optional = true }
+
+                [features]
+                default = ["json"]
+                json = ["dep:opt", "opt?/serde"]
+                unused = ["bar/serde"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("metadata --format-version 1 --features-graph")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--features-graph` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("metadata --format-version 1 --features-graph -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["features-graph"])
+        .with_json(
+            r#"
+            {
+              "packages": "{...}",
+              "workspace_members": "{...}",
+              "resolve": "{...}",
+              "target_directory": "[..]",
+              "version": 1,
+              "workspace_root": "[..]",
+              "metadata": null,
+              "features": [
+                {
+                  "id": "bar 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                  "features": {
+                    "alloc": {
+                      "activated": true,
+                      "activated_by": [
+                        {
+                          "kind": "dependency",
+                          "name": "bar",
+                          "package": "foo 0.1.0 (path+file://[..]/foo)"
+                        }
+                      ],
+                      "enables": []
+                    },
+                    "default": {
+                      "activated": true,
+                      "activated_by": [
+                        {
+                          "kind": "dependency",
+                          "name": "bar",
+                          "package": "foo 0.1.0 (path+file://[..]/foo)"
+                        }
+                      ],
+                      "enables": ["std"]
+                    },
+                    "serde": {
+                      "activated": false,
+                      "activated_by": [],
+                      "enables": []
+                    },
+                    "std": {
+                      "activated": true,
+                      "activated_by": [
+                        {
+                          "feature": "default",
+                          "kind": "feature",
+                          "package": "bar 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)"
+                        }
+                      ],
+                      "enables": []
+                    }
+                  }
+                },
+                {
+                  "id": "foo 0.1.0 (path+file://[..]/foo)",
+                  "features": {
+                    "default": {
+                      "activated": true,
+                      "activated_by": [{ "kind": "command-line" }],
+                      "enables": ["json"]
+                    },
+                    "json": {
+                      "activated": true,
+                      "activated_by": [
+                        {
+                          "feature": "default",
+                          "kind": "feature",
+                          "package": "foo 0.1.0 (path+file://[..]/foo)"
+                        }
+                      ],
+                      "enables": ["dep:opt", "opt?/serde"]
+                    },
+                    "unused": {
+                      "activated": false,
+                      "activated_by": [],
+                      "enables": ["bar/serde"]
+                    }
+                  }
+                },
+                {
+                  "id": "opt 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                  "features": {
+                    "serde": {
+                      "activated": true,
+                      "activated_by": [
+                        {
+                          "feature": "json",
+                          "kind": "feature",
+                          "package": "foo 0.1.0 (path+file://[..]/foo)"
+                        }
+                      ],
+                      "enables": []
+                    }
+                  }
+                }
+              ]
+            }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn build_scripts() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r##"
+                use std::path::Path;
+
+                fn main() {
+                    let out_dir = std::env::var("OUT_DIR").unwrap();
+                    let out_dir = Path::new(&out_dir);
+                    std::fs::write(
+                        out_dir.join("manifest.json"),
+                        r#"{"outputs": ["gen.rs"]}"#,
+                    )
+                    .unwrap();
+                    std::fs::write(out_dir.join("gen.rs"), "").unwrap();
+                    println!("cargo:build-manifest=manifest.json");
+                }
+            "##,
+        )
+        .build();
+
+    p.cargo("metadata --format-version 1 --build-scripts")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--build-scripts` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    // Nothing is listed before the build script has run.
+    p.cargo("metadata --format-version 1 --build-scripts -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+            {
+              "packages": "{...}",
+              "workspace_members": "{...}",
+              "resolve": "{...}",
+              "target_directory": "[..]",
+              "version": 1,
+              "workspace_root": "[..]",
+              "metadata": null,
+              "build_scripts": []
+            }
+            "#,
+        )
+        .run();
+    assert!(!p.root().join("target/debug/build").exists());
+
+    p.cargo("check -Zbuild-manifest")
+        .masquerade_as_nightly_cargo(&["build-manifest"])
+        .run();
+    p.cargo("metadata --format-version 1 --build-scripts -Zunstable-options -Zbuild-manifest")
+        .masquerade_as_nightly_cargo(&["unstable-options", "build-manifest"])
+        .with_json(
+            r#"
+            {
+              "packages": "{...}",
+              "workspace_members": "{...}",
+              "resolve": "{...}",
+              "target_directory": "[..]",
+              "version": 1,
+              "workspace_root": "[..]",
+              "metadata": null,
+              "build_scripts": [
+                {
+                  "id": "foo 0.0.1 (path+file://[..]/foo)",
+                  "out_dir": "[..]/foo/target/debug/build/foo-[..]/out",
+                  "generated": ["[..]/foo/target/debug/build/foo-[..]/out/gen.rs"]
+                }
+              ]
+            }
+            "#,
+        )
+        .run();
+}
@@ -543,3 +543,207 @@ It is recommended to use a different name to avoid problems.
         )
         .run();
 }
+
+#[cargo_test]
+fn template_requires_unstable_options() {
+    cargo_process("new foo --template tmpl")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--template` flag is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn template_from_path() {
+    let tmpl = paths::root().join("tmpl");
+    fs::create_dir_all(tmpl.join("src/bin")).unwrap();
+    fs::write(
+        tmpl.join("Cargo.toml"),
+        r#"
+            [package]
+            name = "{{name}}"
+            version = "0.1.0"
+            authors = ["{{authors}}"]
+            edition = "{{edition}}"
+        "#,
+    )
+    .unwrap();
+    fs::write(
+        tmpl.join("src/lib.rs"),
+        "pub const NAME: &str = \"{{name}}\";\n",
+    )
+    .unwrap();
+    fs::write(
+        tmpl.join("src/bin/{{name}}-cli.rs"),
+        "fn main() { println!(\"{}\", {{crate_name}}::NAME); }\n",
+    )
+    .unwrap();
+    fs::write(
+        tmpl.join("Cargo.lock"),
+        "version = 3\n\n[[package]]\nname = \"{{name}}\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(tmpl.join(".gitignore"), "/notes\n").unwrap();
+
+    cargo_process("new -Zunstable-options my-tool --template tmpl --edition 2018")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .env("CARGO_NAME", "Ferris")
+        .env("CARGO_EMAIL", "ferris@example.com")
+        .with_stderr("[CREATED] `my-tool` package from template `tmpl`")
+        .run();
+
+    let pkg = paths::root().join("my-tool");
+    let manifest = fs::read_to_string(pkg.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains(r#"name = "my-tool""#));
+    assert!(manifest.contains(r#"authors = ["Ferris <ferris@example.com>"]"#));
+    assert!(manifest.contains(r#"edition = "2018""#));
+    assert!(fs::read_to_string(pkg.join("Cargo.lock"))
+        .unwrap()
+        .contains(r#"name = "my-tool""#));
+    // The ignore file of the template is amended.
+    let ignore = fs::read_to_string(pkg.join(".gitignore")).unwrap();
+    assert!(ignore.starts_with("/notes\n"), "{}", ignore);
+    assert!(ignore.contains("/target"), "{}", ignore);
+
+    cargo_process("run --bin my-tool-cli")
+        .cwd(&pkg)
+        .with_stdout("my-tool")
+        .run();
+}
+
+#[cargo_test]
+fn template_from_git() {
+    let repo = cargo_test_support::git::repo(&paths::root().join("tmpl"))
+        .file(
+            "Cargo.toml",
+            "[package]\nname = \"{{name}}\"\nversion = \"0.1.0\"\n",
+        )
+        .file("src/main.rs", "fn main() {}\n")
+        .build();
+    let url = repo.url();
+
+    cargo_process(&format!("new -Zunstable-options foo --template {}", url))
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(&format!(
+            "\
+[UPDATING] git repository `{url}`
+[CREATED] `foo` package from template `{url}`
+",
+            url = url
+        ))
+        .run();
+
+    let pkg = paths::root().join("foo");
+    assert!(fs::read_to_string(pkg.join("Cargo.toml"))
+        .unwrap()
+        .contains(r#"name = "foo""#));
+    assert!(pkg.join("src/main.rs").is_file());
+
+    cargo_process("new -Zunstable-options bar --template no-such-template")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr_contains(
+            "  the template `no-such-template` is neither a directory nor the URL of a git \
+             repository",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn adds_member_to_workspace() {
+    let root = paths::root();
+    fs::create_dir_all(root.join("crates/a/src")).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[workspace]\n# The members.\nmembers = [\"crates/a\"]\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("crates/a/Cargo.toml"),
+        "[package]\nname = \"a\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(root.join("crates/a/src/lib.rs"), "").unwrap();
+
+    cargo_process("new -Zunstable-options --lib crates/b")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[ADDING] `crates/b` as a member of the workspace at `[ROOT]`
+[CREATED] library `crates/b` package
+",
+        )
+        .run();
+    assert_eq!(
+        fs::read_to_string(root.join("Cargo.toml")).unwrap(),
+        "[workspace]\n# The members.\nmembers = [\"crates/a\", \"crates/b\"]\n"
+    );
+
+    // Packages matched by a glob are already members.
+    fs::write(
+        root.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"crates/*\"]\n",
+    )
+    .unwrap();
+    cargo_process("new -Zunstable-options --lib crates/c")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr("[CREATED] library `crates/c` package")
+        .run();
+    assert_eq!(
+        fs::read_to_string(root.join("Cargo.toml")).unwrap(),
+        "[workspace]\nmembers = [\"crates/*\"]\n"
+    );
+
+    cargo_process("build").cwd(root.join("crates/b")).run();
+}
+
+#[cargo_test]
+fn new_member() {
+    let root = paths::root();
+    fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = []\n").unwrap();
+
+    cargo_process("new --member crates/a")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--member` flag is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    cargo_process("new -Zunstable-options --member --lib crates/a")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[ADDING] `crates/a` as a member of the workspace at `[ROOT]`
+[CREATED] library `crates/a` package
+",
+        )
+        .run();
+    assert_eq!(
+        fs::read_to_string(root.join("Cargo.toml")).unwrap(),
+        "[workspace]\nmembers = [\"crates/a\"]\n"
+    );
+
+    // Nothing is created outside of a workspace.
+    let outside = root.join("outside");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(
+        root.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"crates/a\"]\nexclude = [\"outside\"]\n",
+    )
+    .unwrap();
+    cargo_process("new -Zunstable-options --member outside/b")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `--member` was passed, but no workspace containing `[ROOT]/outside/b` was found
+
+Use `cargo init --workspace` to create one
+",
+        )
+        .run();
+    assert!(!outside.join("b").exists());
+}
@@ -1,5 +1,6 @@
 //! Tests for --out-dir flag.
 
+use cargo_test_support::compare::find_json_mismatch;
 use cargo_test_support::sleep_ms;
 use cargo_test_support::{basic_manifest, project};
 use std::env;
@@ -12,16 +13,13 @@ fn binary_with_debug() {
         .file("src/main.rs", r#"fn main() { println!("Hello, World!") }"#)
         .build();
 
-    p.cargo("build -Z unstable-options --out-dir out")
-        .masquerade_as_nightly_cargo(&["out-dir"])
-        .enable_mac_dsym()
-        .run();
+    p.cargo("build --out-dir out").enable_mac_dsym().run();
     check_dir_contents(
         &p.root().join("out"),
-        &["foo"],
-        &["foo", "foo.dSYM"],
-        &["foo.exe", "foo.pdb"],
-        &["foo.exe"],
+        &["bin/foo"],
+        &["bin/foo", "bin/foo.dSYM"],
+        &["bin/foo.exe", "bin/foo.pdb"],
+        &["bin/foo.exe"],
     );
 }
 
@@ -49,15 +47,13 @@ fn static_library_with_debug() {
         )
         .build();
 
-    p.cargo("build -Z unstable-options --out-dir out")
-        .masquerade_as_nightly_cargo(&["out-dir"])
-        .run();
+    p.cargo("build --out-dir out").run();
     check_dir_contents(
         &p.root().join("out"),
-        &["libfoo.a"],
-        &["libfoo.a"],
-        &["foo.lib"],
-        &["libfoo.a"],
+        &["staticlib/libfoo.a"],
+        &["staticlib/libfoo.a"],
+        &["staticlib/foo.lib"],
+        &["staticlib/libfoo.a"],
     );
 }
 
@@ -85,16 +81,18 @@ fn dynamic_library_with_debug() {
         )
         .build();
 
-    p.cargo("build -Z unstable-options --out-dir out")
-        .masquerade_as_nightly_cargo(&["out-dir"])
-        .enable_mac_dsym()
-        .run();
+    p.cargo("build --out-dir out").enable_mac_dsym().run();
     check_dir_contents(
         &p.root().join("out"),
-        &["libfoo.so"],
-        &["libfoo.dylib", "libfoo.dylib.dSYM"],
-        &["foo.dll", "foo.dll.exp", "foo.dll.lib", "foo.pdb"],
-        &["foo.dll", "libfoo.dll.a"],
+        &["cdylib/libfoo.so"],
+        &["cdylib/libfoo.dylib", "cdylib/libfoo.dylib.dSYM"],
+        &[
+            "cdylib/foo.dll",
+            "cdylib/foo.dll.exp",
+            "cdylib/foo.dll.lib",
+            "cdylib/foo.pdb",
+        ],
+        &["cdylib/foo.dll", "cdylib/libfoo.dll.a"],
     );
 }
 
@@ -121,15 +119,13 @@ fn rlib_with_debug() {
         )
         .build();
 
-    p.cargo("build -Z unstable-options --out-dir out")
-        .masquerade_as_nightly_cargo(&["out-dir"])
-        .run();
+    p.cargo("build --out-dir out").run();
     check_dir_contents(
         &p.root().join("out"),
-        &["libfoo.rlib"],
-        &["libfoo.rlib"],
-        &["libfoo.rlib"],
-        &["libfoo.rlib"],
+        &["lib/libfoo.rlib"],
+        &["lib/libfoo.rlib"],
+        &["lib/libfoo.rlib"],
+        &["lib/libfoo.rlib"],
     );
 }
 
@@ -165,16 +161,15 @@ fn include_only_the_binary_from_the_current_package() {
         .file("utils/src/lib.rs", "")
         .build();
 
-    p.cargo("build -Z unstable-options --bin foo --out-dir out")
-        .masquerade_as_nightly_cargo(&["out-dir"])
+    p.cargo("build --bin foo --out-dir out")
         .enable_mac_dsym()
         .run();
     check_dir_contents(
         &p.root().join("out"),
-        &["foo"],
-        &["foo", "foo.dSYM"],
-        &["foo.exe", "foo.pdb"],
-        &["foo.exe"],
+        &["bin/foo"],
+        &["bin/foo", "bin/foo.dSYM"],
+        &["bin/foo.exe", "bin/foo.pdb"],
+        &["bin/foo.exe"],
     );
 }
 
@@ -185,8 +180,7 @@ fn out_dir_is_a_file() {
         .file("out", "")
         .build();
 
-    p.cargo("build -Z unstable-options --out-dir out")
-        .masquerade_as_nightly_cargo(&["out-dir"])
+    p.cargo("build --out-dir out")
         .with_status(101)
         .with_stderr_contains("[ERROR] failed to create directory [..]")
         .run();
@@ -198,12 +192,10 @@ fn replaces_artifacts() {
         .file("src/main.rs", r#"fn main() { println!("foo") }"#)
         .build();
 
-    p.cargo("build -Z unstable-options --out-dir out")
-        .masquerade_as_nightly_cargo(&["out-dir"])
-        .run();
+    p.cargo("build --out-dir out").run();
     p.process(
         &p.root()
-            .join(&format!("out/foo{}", env::consts::EXE_SUFFIX)),
+            .join(&format!("out/bin/foo{}", env::consts::EXE_SUFFIX)),
     )
     .with_stdout("foo")
     .run();
@@ -211,12 +203,10 @@ fn replaces_artifacts() {
     sleep_ms(1000);
     p.change_file("src/main.rs", r#"fn main() { println!("bar") }"#);
 
-    p.cargo("build -Z unstable-options --out-dir out")
-        .masquerade_as_nightly_cargo(&["out-dir"])
-        .run();
+    p.cargo("build --out-dir out").run();
     p.process(
         &p.root()
-            .join(&format!("out/foo{}", env::consts::EXE_SUFFIX)),
+            .join(&format!("out/bin/foo{}", env::consts::EXE_SUFFIX)),
     )
     .with_stdout("bar")
     .run();
@@ -240,18 +230,17 @@ fn avoid_build_scripts() {
         .file("b/build.rs", r#"fn main() { println!("hello-build-b"); }"#)
         .build();
 
-    p.cargo("build -Z unstable-options --out-dir out -vv")
-        .masquerade_as_nightly_cargo(&["out-dir"])
+    p.cargo("build --out-dir out -vv")
         .enable_mac_dsym()
         .with_stdout_contains("[a 0.0.1] hello-build-a")
         .with_stdout_contains("[b 0.0.1] hello-build-b")
         .run();
     check_dir_contents(
         &p.root().join("out"),
-        &["a", "b"],
-        &["a", "a.dSYM", "b", "b.dSYM"],
-        &["a.exe", "a.pdb", "b.exe", "b.pdb"],
-        &["a.exe", "b.exe"],
+        &["bin/a", "bin/b"],
+        &["bin/a", "bin/a.dSYM", "bin/b", "bin/b.dSYM"],
+        &["bin/a.exe", "bin/a.pdb", "bin/b.exe", "bin/b.pdb"],
+        &["bin/a.exe", "bin/b.exe"],
     );
 }
 
@@ -268,16 +257,13 @@ fn cargo_build_out_dir() {
         )
         .build();
 
-    p.cargo("build -Z unstable-options")
-        .masquerade_as_nightly_cargo(&["out-dir"])
-        .enable_mac_dsym()
-        .run();
+    p.cargo("build").enable_mac_dsym().run();
     check_dir_contents(
         &p.root().join("out"),
-        &["foo"],
-        &["foo", "foo.dSYM"],
-        &["foo.exe", "foo.pdb"],
-        &["foo.exe"],
+        &["bin/foo"],
+        &["bin/foo", "bin/foo.dSYM"],
+        &["bin/foo.exe", "bin/foo.pdb"],
+        &["bin/foo.exe"],
     );
 }
 
@@ -300,18 +286,166 @@ fn check_dir_contents(
         expected_linux
     };
 
-    let actual = list_dir(out_dir);
+    // The manifest is checked by its own tests.
+    let mut actual = list_dir(out_dir, "");
+    actual.retain(|path| path != "manifest.json");
     let mut expected = expected.iter().map(|s| s.to_string()).collect::<Vec<_>>();
     expected.sort_unstable();
     assert_eq!(actual, expected);
 }
 
-fn list_dir(dir: &Path) -> Vec<String> {
+/// Lists the files in `dir` recursively, as paths relative to it. The
+/// `.dSYM` directories of macOS are listed like files.
+fn list_dir(dir: &Path, prefix: &str) -> Vec<String> {
     let mut res = Vec::new();
     for entry in fs::read_dir(dir).unwrap() {
         let entry = entry.unwrap();
-        res.push(entry.file_name().into_string().unwrap());
+        let name = entry.file_name().into_string().unwrap();
+        let path = format!("{}{}", prefix, name);
+        if entry.file_type().unwrap().is_dir() && !name.ends_with(".dSYM") {
+            res.extend(list_dir(&entry.path(), &format!("{}/", path)));
+        } else {
+            res.push(path);
+        }
     }
     res.sort_unstable();
     res
 }
+
+#[cargo_test]
+fn manifest() {
+    let p = project()
+        .file("src/main.rs", "fn main() {}")
+        .file("examples/ex.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build --out-dir out --examples --bins").run();
+    let expected = serde_json::from_str(
+        r#"
+            {
+                "version": 1,
+                "artifacts": [
+                    {
+                        "path": "bin/foo[EXE]",
+                        "source": "[..]/target/debug/deps/foo-[..][EXE]",
+                        "package_id": "foo 0.0.1 ([..])",
+                        "target": {
+                            "kind": ["bin"],
+                            "crate_types": ["bin"],
+                            "name": "foo",
+                            "src_path": "[..]/src/main.rs",
+                            "edition": "2015",
+                            "doc": true,
+                            "doctest": false,
+                            "test": true
+                        },
+                        "artifact_dep": false
+                    },
+                    {
+                        "path": "examples/ex[EXE]",
+                        "source": "[..]/target/debug/examples/ex-[..][EXE]",
+                        "package_id": "foo 0.0.1 ([..])",
+                        "target": {
+                            "kind": ["example"],
+                            "crate_types": ["bin"],
+                            "name": "ex",
+                            "src_path": "[..]/examples/ex.rs",
+                            "edition": "2015",
+                            "doc": false,
+                            "doctest": false,
+                            "test": false
+                        },
+                        "artifact_dep": false
+                    }
+                ]
+            }
+        "#,
+    )
+    .unwrap();
+    let actual = serde_json::from_str(&p.read_file("out/manifest.json")).unwrap();
+    if cfg!(target_os = "linux") {
+        find_json_mismatch(&expected, &actual, None).unwrap();
+    }
+
+    // Only the files copied by the last build are listed.
+    p.cargo("build --out-dir out --bins").run();
+    let actual: serde_json::Value =
+        serde_json::from_str(&p.read_file("out/manifest.json")).unwrap();
+    let paths: Vec<_> = actual["artifacts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["path"].as_str().unwrap())
+        .filter(|path| !path.ends_with(".pdb") && !path.ends_with(".dSYM"))
+        .collect();
+    assert_eq!(paths, [format!("bin/foo{}", env::consts::EXE_SUFFIX)]);
+}
+
+#[cargo_test]
+fn include_artifact_deps() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = { path = "bar", artifact = "bin" }
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build --include-artifact-deps -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_status(101)
+        .with_stderr("[ERROR] the `--include-artifact-deps` flag requires an `--out-dir`")
+        .run();
+
+    // Without the flag, only the artifacts of the package are copied.
+    p.cargo("build --out-dir out -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .enable_mac_dsym()
+        .run();
+    check_dir_contents(
+        &p.root().join("out"),
+        &["bin/foo"],
+        &["bin/foo", "bin/foo.dSYM"],
+        &["bin/foo.exe", "bin/foo.pdb"],
+        &["bin/foo.exe"],
+    );
+
+    p.cargo("build --out-dir out2 --include-artifact-deps -Z bindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .enable_mac_dsym()
+        .run();
+    check_dir_contents(
+        &p.root().join("out2"),
+        &["artifact-deps/bar/bin/bar", "bin/foo"],
+        &[
+            "artifact-deps/bar/bin/bar",
+            "artifact-deps/bar/bin/bar.dSYM",
+            "bin/foo",
+            "bin/foo.dSYM",
+        ],
+        &[
+            "artifact-deps/bar/bin/bar.exe",
+            "artifact-deps/bar/bin/bar.pdb",
+            "bin/foo.exe",
+            "bin/foo.pdb",
+        ],
+        &["artifact-deps/bar/bin/bar.exe", "bin/foo.exe"],
+    );
+    let manifest: serde_json::Value =
+        serde_json::from_str(&p.read_file("out2/manifest.json")).unwrap();
+    let bar = &manifest["artifacts"][0];
+    assert_eq!(
+        bar["package_id"].as_str().unwrap().split(' ').next(),
+        Some("bar")
+    );
+    assert_eq!(bar["artifact_dep"], true);
+}
@@ -3,8 +3,8 @@
 use std::fs;
 
 use cargo_test_support::paths::CargoPathExt;
-use cargo_test_support::project;
 use cargo_test_support::registry::{self, api_path};
+use cargo_test_support::{basic_manifest, project};
 
 fn setup(name: &str, content: Option<&str>) {
     let dir = api_path().join(format!("api/v1/crates/{}", name));
@@ -124,3 +124,144 @@ Caused by:
         )
         .run();
 }
+
+#[cargo_test]
+fn batches_require_unstable_options() {
+    let registry = registry::init();
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("owner -l foo bar")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `crate` argument given more than once is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("owner -l --message-format json")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--message-format` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn workspace_list_json() {
+    let registry = registry::init();
+    setup(
+        "foo",
+        Some(r#"{"users": [{"id": 70, "login": "github:rust-lang:core", "name": "Core"}]}"#),
+    );
+    setup(
+        "bar",
+        Some(r#"{"users": [{"id": 123, "login": "octocat", "email": "octo@cat"}]}"#),
+    );
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["foo", "bar", "private"]
+            "#,
+        )
+        .file("foo/Cargo.toml", &basic_manifest("foo", "0.1.0"))
+        .file("foo/src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "")
+        .file(
+            "private/Cargo.toml",
+            r#"
+                [package]
+                name = "private"
+                version = "0.1.0"
+                publish = false
+            "#,
+        )
+        .file("private/src/lib.rs", "")
+        .build();
+
+    p.cargo("owner -Zunstable-options --workspace -l --message-format json")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+    "name": "foo",
+    "registry": "file://[..]",
+    "owners": [{"id": 70, "login": "github:rust-lang:core", "name": "Core", "email": null}]
+}
+
+{
+    "name": "bar",
+    "registry": "file://[..]",
+    "owners": [{"id": 123, "login": "octocat", "name": null, "email": "octo@cat"}]
+}
+"#,
+        )
+        .run();
+
+    p.cargo("owner -Zunstable-options -l foo bar")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+foo:
+  github:rust-lang:core (Core)
+bar:
+  octocat (octo@cat)
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn add_to_several_crates() {
+    let registry = registry::RegistryBuilder::new().http_api().build();
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("owner -Zunstable-options foo bar -a octocat")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+       Owner completed!
+       Owner completed!
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn registry_without_owners_api() {
+    let registry = registry::RegistryBuilder::new()
+        .http_api()
+        .add_responder("/api/v1/crates/foo/owners", |req, server| {
+            server.not_found(req)
+        })
+        .build();
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("owner -l")
+        .replace_crates_io(registry.index_url())
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+[ERROR] failed to list owners of crate `foo` on registry at http://127.0.0.1:[..]
+
+Caused by:
+  the registry at http://127.0.0.1:[..] does not support managing owners
+
+  The API of the registry responded with HTTP status 404.
+",
+        )
+        .run();
+}
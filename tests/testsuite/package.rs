@@ -7,6 +7,7 @@ use cargo_test_support::{
     basic_manifest, cargo_process, git, path2url, paths, project, symlink_supported, t,
 };
 use flate2::read::GzDecoder;
+use std::env;
 use std::fs::{self, read_to_string, File};
 use std::path::Path;
 use tar::Archive;
@@ -329,6 +330,62 @@ in package source
         .run();
 }
 
+#[cargo_test]
+fn provenance_file_collision() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                description = "foo"
+                version = "0.0.1"
+                authors = []
+                license = "MIT"
+                documentation = "foo"
+                homepage = "foo"
+                repository = "foo"
+                include = ["src/main.rs", ".cargo_provenance.json"]
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo_provenance.json", "{}")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [publish.provenance]
+                commit = "0123456789abcdef"
+            "#,
+        )
+        .build();
+
+    // The name is only reserved when provenance is added to the package.
+    p.cargo("package --no-verify --allow-dirty").run();
+    validate_crate_contents(
+        File::open(&p.root().join("target/package/foo-0.0.1.crate")).unwrap(),
+        "foo-0.0.1.crate",
+        &[
+            ".cargo_provenance.json",
+            "Cargo.lock",
+            "Cargo.toml",
+            "Cargo.toml.orig",
+            "src/main.rs",
+        ],
+        &[(".cargo_provenance.json", "{}")],
+    );
+
+    p.cargo("package --no-verify --allow-dirty -Zpublish-provenance")
+        .masquerade_as_nightly_cargo(&["publish-provenance"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] invalid inclusion of reserved file name .cargo_provenance.json \
+in package source
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn path_dependency_no_version() {
     let p = project()
@@ -2178,6 +2235,96 @@ the `path` specification will be removed from the dependency declaration.
         .run();
 }
 
+#[cargo_test]
+fn list_json() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            description = "foo"
+            homepage = "foo"
+            license-file = "../LICENSE"
+            readme = "README.md"
+            include = ["src/**", "Cargo.toml"]
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file("README.md", "foo")
+        .file("../LICENSE", "license")
+        .build();
+
+    p.cargo("package --list --message-format json")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--message-format` flag is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("package --list --message-format json -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+            {
+              "name": "foo",
+              "version": "0.1.0",
+              "id": "foo 0.1.0 (path+file://[..]/foo)",
+              "files": [
+                {
+                  "path": "Cargo.lock",
+                  "kind": "generated",
+                  "generator": "lockfile",
+                  "size": "{...}",
+                  "mtime_normalized": true,
+                  "rule": "generated"
+                },
+                {
+                  "path": "Cargo.toml",
+                  "kind": "generated",
+                  "generator": "manifest-rewrite",
+                  "size": "{...}",
+                  "mtime_normalized": true,
+                  "rule": "generated"
+                },
+                {
+                  "path": "Cargo.toml.orig",
+                  "kind": "source",
+                  "size": "{...}",
+                  "mtime_normalized": true,
+                  "rule": "manifest"
+                },
+                {
+                  "path": "LICENSE",
+                  "kind": "source",
+                  "size": 7,
+                  "mtime_normalized": true,
+                  "rule": "license-file"
+                },
+                {
+                  "path": "README.md",
+                  "kind": "source",
+                  "size": 3,
+                  "mtime_normalized": true,
+                  "rule": "readme"
+                },
+                {
+                  "path": "src/main.rs",
+                  "kind": "source",
+                  "size": 12,
+                  "mtime_normalized": true,
+                  "rule": "include",
+                  "pattern": "src/**"
+                }
+              ]
+            }
+            "#,
+        )
+        .run();
+}
+
 #[cargo_test]
 fn long_file_names() {
     // Filenames over 100 characters require a GNU extension tarfile.
@@ -2285,6 +2432,67 @@ fn reproducible_output() {
     }
 }
 
+#[cargo_test]
+fn package_epoch() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                license = "MIT"
+                description = "foo"
+            "#,
+        )
+        .file("src/main.rs", r#"fn main() { println!("hello"); }"#)
+        .build();
+    let crate_path = p.root().join("target/package/foo-0.0.1.crate");
+    let check_mtimes = |expected: u64| {
+        let f = File::open(&crate_path).unwrap();
+        let mut archive = Archive::new(GzDecoder::new(f));
+        for ent in archive.entries().unwrap() {
+            let ent = ent.unwrap();
+            let header = ent.header();
+            assert_eq!(header.mtime().unwrap(), expected, "{:?}", ent.path());
+            assert_eq!(header.uid().unwrap(), 0);
+            assert_eq!(header.gid().unwrap(), 0);
+        }
+    };
+
+    p.cargo("package --no-verify -Zpackage-epoch")
+        .masquerade_as_nightly_cargo(&["package-epoch"])
+        .env("SOURCE_DATE_EPOCH", "1700000000")
+        .run();
+    check_mtimes(1700000000);
+    let first = fs::read(&crate_path).unwrap();
+
+    // The modification times on disk don't matter.
+    let main_rs = p.root().join("src/main.rs");
+    filetime::set_file_mtime(&main_rs, filetime::FileTime::from_unix_time(1, 0)).unwrap();
+    p.cargo("package --no-verify -Zpackage-epoch")
+        .masquerade_as_nightly_cargo(&["package-epoch"])
+        .env("SOURCE_DATE_EPOCH", "1700000000")
+        .run();
+    assert_eq!(first, fs::read(&crate_path).unwrap());
+
+    // The config wins over the environment.
+    p.cargo("package --no-verify -Zpackage-epoch --config package.epoch=1600000000")
+        .masquerade_as_nightly_cargo(&["package-epoch"])
+        .env("SOURCE_DATE_EPOCH", "1700000000")
+        .run();
+    check_mtimes(1600000000);
+
+    p.cargo("package --no-verify -Zpackage-epoch")
+        .masquerade_as_nightly_cargo(&["package-epoch"])
+        .env("SOURCE_DATE_EPOCH", "yesterday")
+        .with_status(101)
+        .with_stderr_contains(
+            "  `SOURCE_DATE_EPOCH` must be a number of seconds since the Unix epoch, found `yesterday`",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn package_with_resolver_and_metadata() {
     let p = project()
@@ -2762,3 +2970,372 @@ src/main.rs.bak
         ],
     );
 }
+
+#[cargo_test]
+fn verify_targets() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                license = "MIT"
+                description = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                "[publish]\nverify-targets = [\"{}\"]\n",
+                cargo_test_support::rustc_host()
+            ),
+        )
+        .build();
+
+    p.cargo("package --no-metadata -Zpublish-verify-targets")
+        .masquerade_as_nightly_cargo(&["publish-verify-targets"])
+        .with_stderr(&format!(
+            "\
+[PACKAGING] foo v0.0.1 ([CWD])
+[VERIFYING] foo v0.0.1 ([CWD])
+[COMPILING] foo v0.0.1 ([CWD]/target/package/foo-0.0.1)
+[FINISHED] dev [..]
+[VERIFYING] foo v0.0.1 ([CWD]) for {}
+[CHECKING] foo v0.0.1 ([CWD]/target/package/foo-0.0.1)
+[FINISHED] dev [..]
+[PACKAGED] [..]
+",
+            cargo_test_support::rustc_host()
+        ))
+        .run();
+
+    // The config key is ignored without `-Zpublish-verify-targets`.
+    p.cargo("package --no-metadata")
+        .with_stderr_does_not_contain("[CHECKING] [..]")
+        .run();
+}
+
+#[cargo_test]
+fn allow_dirty_paths() {
+    let (p, _repo) = git::new_repo("foo", |p| {
+        p.file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                description = "foo"
+                license = "foo"
+                documentation = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("CHANGELOG.md", "")
+    });
+    p.change_file("CHANGELOG.md", "## Unreleased");
+    p.change_file("src/lib.rs", "pub fn f() {}");
+    p.change_file("src/new.rs", "");
+
+    p.cargo("package --list --allow-dirty-paths CHANGELOG.md")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+error: the `--allow-dirty-paths` flag is unstable, pass `-Z unstable-options` to enable it
+",
+        )
+        .run();
+
+    p.cargo("package --list -Zunstable-options --allow-dirty-paths CHANGELOG.md")
+        .masquerade_as_nightly_cargo(&["allow-dirty-paths"])
+        .with_status(101)
+        .with_stderr(
+            "\
+error: 2 files in the working directory contain changes that were not yet committed into git:
+
+src/lib.rs
+src/new.rs
+
+to proceed despite this and include the uncommitted changes, pass the `--allow-dirty` flag
+",
+        )
+        .run();
+
+    p.cargo("package --list -Zunstable-options --allow-dirty-paths CHANGELOG.md --allow-dirty-paths src/*.rs")
+        .masquerade_as_nightly_cargo(&["allow-dirty-paths"])
+        .with_stderr("")
+        .with_stdout(
+            "\
+.cargo_vcs_info.json
+CHANGELOG.md
+Cargo.toml
+Cargo.toml.orig
+src/lib.rs
+src/new.rs
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn export_ignore() {
+    let (p, _repo) = git::new_repo("foo", |p| {
+        p.file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                description = "foo"
+                license = "foo"
+                documentation = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("docs/guide.md", "")
+        .file("logo.png", "")
+        .file(
+            ".gitattributes",
+            "docs export-ignore\n*.png export-ignore\n",
+        )
+    });
+
+    p.cargo("package --list")
+        .with_stdout(
+            "\
+.cargo_vcs_info.json
+.gitattributes
+Cargo.toml
+Cargo.toml.orig
+docs/guide.md
+logo.png
+src/lib.rs
+",
+        )
+        .run();
+
+    p.cargo("package --list -Zpackage-vcs")
+        .masquerade_as_nightly_cargo(&["package-vcs"])
+        .with_stdout(
+            "\
+.cargo_vcs_info.json
+.gitattributes
+Cargo.toml
+Cargo.toml.orig
+src/lib.rs
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn dirty_mercurial() {
+    // A stand-in for `hg status` which reports a modified, an unknown and an
+    // ignored file.
+    let hg = project()
+        .at("fake-hg")
+        .file("Cargo.toml", &basic_manifest("hg", "1.0.0"))
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() {
+                    assert_eq!(std::env::var("HGPLAIN").unwrap(), "1");
+                    print!("M src/lib.rs\0? CHANGELOG.md\0I Cargo.lock\0");
+                }
+            "#,
+        )
+        .build();
+    hg.cargo("build").run();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                description = "foo"
+                license = "foo"
+                documentation = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("CHANGELOG.md", "")
+        .file(".hg/requires", "")
+        .build();
+
+    let mut paths = vec![hg.target_debug_dir()];
+    paths.extend(env::split_paths(&env::var_os("PATH").unwrap_or_default()));
+    let path = env::join_paths(paths).unwrap();
+
+    // Without the flag only git is looked at.
+    p.cargo("package --list")
+        .env("PATH", &path)
+        .with_stdout(
+            "\
+CHANGELOG.md
+Cargo.toml
+Cargo.toml.orig
+src/lib.rs
+",
+        )
+        .run();
+
+    p.cargo("package --list -Zpackage-vcs")
+        .masquerade_as_nightly_cargo(&["package-vcs"])
+        .env("PATH", &path)
+        .with_status(101)
+        .with_stderr_contains(
+            "error: 2 files in the working directory contain changes that were not yet committed into Mercurial:",
+        )
+        .with_stderr_contains("CHANGELOG.md")
+        .with_stderr_contains("src/lib.rs")
+        .run();
+
+    p.cargo("package --list -Zpackage-vcs -Zunstable-options --allow-dirty-paths CHANGELOG.md")
+        .masquerade_as_nightly_cargo(&["package-vcs"])
+        .env("PATH", &path)
+        .with_status(101)
+        .with_stderr_contains("error: 1 files in the working directory contain changes that were not yet committed into Mercurial:")
+        .with_stderr_contains("src/lib.rs")
+        .run();
+}
+
+#[cargo_test]
+fn jujutsu_colocated_with_git() {
+    // A stand-in for `jj diff`, which reports the files in `JJ_DIFF`.
+    let jj = project()
+        .at("fake-jj")
+        .file("Cargo.toml", &basic_manifest("jj", "1.0.0"))
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() {
+                    print!("{}", std::env::var("JJ_DIFF").unwrap_or_default());
+                }
+            "#,
+        )
+        .build();
+    jj.cargo("build").run();
+
+    let (p, repo) = git::new_repo("foo", |p| {
+        p.file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                description = "foo"
+                license = "foo"
+                documentation = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(".gitignore", "/.jj\n")
+    });
+    fs::create_dir(p.root().join(".jj")).unwrap();
+
+    let mut paths = vec![jj.target_debug_dir()];
+    paths.extend(env::split_paths(&env::var_os("PATH").unwrap_or_default()));
+    let path = env::join_paths(paths).unwrap();
+
+    p.cargo("package --list -Zpackage-vcs")
+        .masquerade_as_nightly_cargo(&["package-vcs"])
+        .env("PATH", &path)
+        .env("JJ_DIFF", "M src/lib.rs\n")
+        .with_status(101)
+        .with_stderr_contains(
+            "error: 1 files in the working directory contain changes that were not yet committed into Jujutsu:",
+        )
+        .run();
+
+    // The commit is still recorded from git.
+    p.cargo("package --no-verify -Zpackage-vcs")
+        .masquerade_as_nightly_cargo(&["package-vcs"])
+        .env("PATH", &path)
+        .run();
+    let f = File::open(&p.root().join("target/package/foo-0.1.0.crate")).unwrap();
+    let vcs_contents = format!(
+        r#"{{
+  "git": {{
+    "sha1": "{}"
+  }},
+  "path_in_vcs": ""
+}}
+"#,
+        repo.revparse_single("HEAD").unwrap().id()
+    );
+    validate_crate_contents(
+        f,
+        "foo-0.1.0.crate",
+        &[
+            ".cargo_vcs_info.json",
+            ".gitignore",
+            "Cargo.toml",
+            "Cargo.toml.orig",
+            "src/lib.rs",
+        ],
+        &[(".cargo_vcs_info.json", &vcs_contents)],
+    );
+}
+
+#[cargo_test]
+fn diff_published() {
+    registry::init();
+    Package::new("foo", "0.1.0")
+        .file("src/lib.rs", "")
+        .file("src/old.rs", "")
+        .file("README.md", "# foo")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.2.0"
+                description = "foo"
+                license = "MIT"
+                documentation = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("src/new.rs", "")
+        .file("README.md", "# foo\n\nNow with more.")
+        .build();
+
+    p.cargo("package --diff 0.1.0")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+error: the `--diff` flag is unstable, pass `-Z unstable-options` to enable it
+",
+        )
+        .run();
+
+    p.cargo("package -Zunstable-options --diff 0.1.0")
+        .masquerade_as_nightly_cargo(&["package --diff"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] foo v0.1.0 (registry `dummy-registry`)
+[COMPARING] foo v0.2.0 ([CWD]) against v0.1.0
+",
+        )
+        .with_stdout(
+            "\
+M Cargo.toml
+A Cargo.toml.orig
+M README.md
+A src/new.rs
+D src/old.rs
+",
+        )
+        .run();
+    assert!(!p.root().join("target/package/foo-0.2.0.crate").exists());
+}
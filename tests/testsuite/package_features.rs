@@ -702,3 +702,174 @@ error: package ID specification `bar` did not match any packages
         .with_stderr("error: cannot specify features for packages outside of workspace")
         .run();
 }
+
+#[cargo_test]
+fn per_package_no_default_features() {
+    // --features 'member(-default)' only disables the defaults of that member.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b"]
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [features]
+                default = ["fa"]
+                fa = []
+            "#,
+        )
+        .file(
+            "a/src/lib.rs",
+            r#"
+                #[cfg(feature = "fa")]
+                compile_error!{"expected fa off"}
+            "#,
+        )
+        .file(
+            "b/Cargo.toml",
+            r#"
+                [package]
+                name = "b"
+                version = "0.1.0"
+
+                [features]
+                default = ["fb"]
+                fb = []
+                other = []
+            "#,
+        )
+        .file(
+            "b/src/lib.rs",
+            r#"
+                #[cfg(not(feature = "fb"))]
+                compile_error!{"expected fb on"}
+                #[cfg(not(feature = "other"))]
+                compile_error!{"expected other on"}
+            "#,
+        )
+        .build();
+
+    p.cargo("check --features a(-default)")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `<package>(-default)` syntax of `--features` is unstable, \
+pass `-Z package-default-features` to enable it
+",
+        )
+        .run();
+
+    p.cargo("check -Zpackage-default-features --features a(-default),b/other")
+        .masquerade_as_nightly_cargo(&["package-default-features"])
+        .with_stderr_unordered(
+            "\
+[CHECKING] a v0.1.0 [..]
+[CHECKING] b v0.1.0 [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.cargo("check -Zpackage-default-features -p b --features a(-default)")
+        .masquerade_as_nightly_cargo(&["package-default-features"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `a(-default)` does not match any package selected to be built
+selected packages: b
+",
+        )
+        .run();
+
+    p.cargo("check -Zpackage-default-features --features a(-default),a/default")
+        .masquerade_as_nightly_cargo(&["package-default-features"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] cannot both enable and disable the default features of `a`
+`a/default` and `a(-default)` were both passed to `--features`
+",
+        )
+        .run();
+
+    p.cargo("check -Zpackage-default-features --features a(-fa)")
+        .masquerade_as_nightly_cargo(&["package-default-features"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] invalid feature `a(-fa)`
+only `<package>(-default)` may be used to disable the default features of a package
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn resolver1_per_package_no_default_features() {
+    // `member(-default)` with resolver="1" for a member other than the current one.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [workspace]
+                members = ["member1"]
+
+                [features]
+                default = ["f"]
+                f = []
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+                #[cfg(not(feature = "f"))]
+                compile_error!{"expected f on"}
+            "#,
+        )
+        .file(
+            "member1/Cargo.toml",
+            r#"
+                [package]
+                name = "member1"
+                version = "0.1.0"
+
+                [features]
+                default = ["m1-feature"]
+                m1-feature = []
+            "#,
+        )
+        .file(
+            "member1/src/main.rs",
+            r#"
+                fn main() {
+                    if cfg!(feature = "m1-feature") {
+                        println!("m1-feature set");
+                    } else {
+                        println!("m1-feature not set");
+                    }
+                }
+            "#,
+        )
+        .build();
+
+    p.cargo("run -Zpackage-default-features -p member1 --features member1(-default)")
+        .masquerade_as_nightly_cargo(&["package-default-features"])
+        .with_stdout("m1-feature not set")
+        .run();
+
+    p.cargo("check -Zpackage-default-features --workspace --features member1(-default)")
+        .masquerade_as_nightly_cargo(&["package-default-features"])
+        .run();
+}
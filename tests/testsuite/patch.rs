@@ -1379,6 +1379,135 @@ Caused by:
         .run();
 }
 
+#[cargo_test]
+fn pin_registry_version() {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.1.1").publish();
+    Package::new("bar", "0.1.2").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["baz"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "0.1"
+                baz = { path = "baz" }
+
+                [patch.crates-io]
+                bar = { version = "=0.1.1" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "baz/Cargo.toml",
+            r#"
+                [package]
+                name = "baz"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "0.1.0"
+            "#,
+        )
+        .file("baz/src/lib.rs", "")
+        .build();
+
+    p.cargo("build -Zpatch-registry-version")
+        .masquerade_as_nightly_cargo(&["patch-registry-version"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v0.1.1 (registry `dummy-registry`)
+[COMPILING] bar v0.1.1
+[COMPILING] baz v0.0.1 ([CWD]/baz)
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // Lifting the pin updates to the newest version again.
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [workspace]
+            members = ["baz"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+            baz = { path = "baz" }
+        "#,
+    );
+    p.cargo("update -p bar")
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[UPDATING] bar v0.1.1 -> v0.1.2
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn pin_registry_version_unused() {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.1.1").publish();
+
+    // The pinned version doesn't match the requirement, so it isn't used.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "0.1.1"
+
+                [patch.crates-io]
+                bar = { version = "=0.1.0" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build -Zpatch-registry-version")
+        .masquerade_as_nightly_cargo(&["patch-registry-version"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[WARNING] Patch `bar v0.1.0` was not used in the crate graph.
+Check that [..]
+with the [..]
+what is [..]
+version. [..]
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v0.1.1 (registry `dummy-registry`)
+[COMPILING] bar v0.1.1
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn patch_in_virtual() {
     Package::new("bar", "0.1.0").publish();
@@ -2542,3 +2671,134 @@ foo v0.1.0 [..]
         ))
         .run();
 }
+
+#[cargo_test]
+fn patch_git_to_rev_of_same_repo() {
+    let (bar, bar_repo) = git::new_repo("bar", |p| {
+        p.file("Cargo.toml", &basic_manifest("bar", "0.1.0"))
+            .file("src/lib.rs", "")
+    });
+    let old_oid = bar_repo.head().unwrap().target().unwrap();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    bar = {{ git = "{}" }}
+                "#,
+                bar.url()
+            ),
+        )
+        .file("src/lib.rs", "pub fn foo() { bar::bar(); }")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    bar.change_file("src/lib.rs", "pub fn bar() {}");
+    git::add(&bar_repo);
+    let new_oid = git::commit(&bar_repo);
+
+    p.change_file(
+        "Cargo.toml",
+        &format!(
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = {{ git = "{0}" }}
+
+                [patch."{0}"]
+                bar = {{ git = "{0}", rev = "{1}" }}
+            "#,
+            bar.url(),
+            new_oid
+        ),
+    );
+
+    let change = format!(
+        "bar v0.1.0 (file://[ROOT]/bar#{}) -> v0.1.0 (file://[ROOT]/bar?rev={}#{}), \
+         patched by `[patch.\"file://[ROOT]/bar\"]`",
+        &old_oid.to_string()[..8],
+        new_oid,
+        &new_oid.to_string()[..8]
+    );
+
+    p.cargo("check --locked")
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] git repository `file://[ROOT]/bar`
+[ERROR] failed to resolve patches for `file://[ROOT]/bar`
+
+Caused by:
+  patch for `bar` in `file://[ROOT]/bar` points to the same source, but patches must point \
+  to different sources
+",
+        )
+        .run();
+
+    p.cargo("check --locked -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(&format!(
+            "\
+[UPDATING] git repository `file://[ROOT]/bar`
+[ERROR] the lock file [CWD]/Cargo.lock needs to be updated but --locked was passed to prevent this
+If you want to try to generate the lock file without accessing the network, \
+remove the --locked flag and use --offline instead.
+
+The following lock file entries are replaced by a `[patch]`:
+  {}
+To update only these entries, pass `--allow-patch-update` along with --locked.
+",
+            change
+        ))
+        .run();
+
+    p.cargo("check --locked --allow-patch-update")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--allow-patch-update` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    // Only the commands resolving the workspace accept the flag.
+    p.cargo("verify-project --allow-patch-update -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(1)
+        .with_stderr_contains("error: unexpected argument '--allow-patch-update' found")
+        .run();
+
+    p.cargo("check --locked --allow-patch-update -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(&format!(
+            "\
+[UPDATING] git repository `file://[ROOT]/bar`
+[PATCHING] {}
+[CHECKING] bar v0.1.0 (file://[ROOT]/bar?rev={}#{})
+[CHECKING] foo v0.1.0 ([CWD])
+[FINISHED] [..]
+",
+            change,
+            new_oid,
+            &new_oid.to_string()[..8]
+        ))
+        .run();
+
+    let lock = p.read_lockfile();
+    assert!(lock.contains(&format!("?rev={0}#{0}", new_oid)));
+
+    p.cargo("check --locked -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr("[FINISHED] [..]")
+        .run();
+}
@@ -1,7 +1,7 @@
 //! Tests for the `cargo pkgid` command.
 
-use cargo_test_support::project;
 use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_manifest, project};
 
 #[cargo_test]
 fn simple() {
@@ -126,3 +126,136 @@ Caused by:
         )
         .run();
 }
+
+#[cargo_test]
+fn qualified_spec() {
+    Package::new("two-ver", "0.1.0").publish();
+    Package::new("two-ver", "0.2.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2018"
+
+                [dependencies]
+                two-ver = "0.1.0"
+
+                [build-dependencies]
+                two-ver2 = { package = "two-ver", version = "0.2.0" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}")
+        .build();
+
+    p.cargo("generate-lockfile").run();
+
+    p.cargo("pkgid two-ver#dep-kind=build")
+        .with_status(101)
+        .with_stderr(
+            "\
+error: invalid package ID specification: `two-ver#dep-kind=build`
+
+Caused by:
+  qualifiers in package ID specifications are unstable, pass `-Z unstable-options` to enable them
+",
+        )
+        .run();
+
+    p.cargo("pkgid two-ver#dep-kind=build -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout("https://github.com/rust-lang/crates.io-index#two-ver@0.2.0")
+        .run();
+
+    p.cargo("pkgid two-ver#dep-kind=normal -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout("https://github.com/rust-lang/crates.io-index#two-ver@0.1.0")
+        .run();
+
+    p.cargo("pkgid two-ver#dep-kind=dev -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+error: package ID specification `two-ver#dep-kind=dev` did not match any packages
+no dependency on `two-ver` matches `dep-kind=dev`
+",
+        )
+        .run();
+
+    p.cargo("pkgid two-ver#artifact=bin:x -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+error: package ID specification `two-ver#artifact=bin:x` did not match any packages
+no dependency on `two-ver` matches `artifact=bin:x`
+",
+        )
+        .run();
+
+    p.cargo("tree -i two-ver#dep-kind=build -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+two-ver v0.2.0
+[build-dependencies]
+└── foo v0.1.0 ([ROOT]/foo)
+",
+        )
+        .run();
+
+    Package::new("two-ver", "0.2.1").publish();
+    p.cargo("update -p two-ver#dep-kind=build -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[UPDATING] two-ver v0.2.0 -> v0.2.1
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn qualified_spec_artifact() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2018"
+
+                [dependencies]
+                bar = { path = "bar" }
+                bar2 = { path = "bar2", package = "bar", artifact = "bin" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "")
+        .file("bar2/Cargo.toml", &basic_manifest("bar", "0.2.0"))
+        .file("bar2/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build -p bar -Zbindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_status(101)
+        .with_stderr_contains("[..]the specification `bar` is ambiguous[..]")
+        .run();
+
+    p.cargo("build -p bar#artifact=bin -Zbindeps -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["bindeps", "unstable-options"])
+        .with_stderr(
+            "\
+[COMPILING] bar v0.2.0 ([CWD]/bar2)
+[FINISHED] [..]
+",
+        )
+        .run();
+}
@@ -351,6 +351,55 @@ fn overrides_with_custom() {
         .run();
 }
 
+#[cargo_test]
+fn overrides_through_inherits_chain() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = {path = "bar"}
+
+                [profile.release.package."bar@0.5.0"]
+                opt-level = 1
+                codegen-units = 3
+
+                [profile.release-lto]
+                inherits = "release"
+                lto = true
+
+                [profile.ci]
+                inherits = "release-lto"
+                debug = 1
+
+                [profile.ci.package.bar]
+                codegen-units = 5
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_lib_manifest("bar"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    // Overrides from every profile in the chain apply, even when they are
+    // spelled differently, with the inheriting profile winning.
+    p.cargo("build --profile=ci -v")
+        .with_stderr_unordered(
+            "\
+[COMPILING] bar [..]
+[COMPILING] foo [..]
+[RUNNING] `rustc --crate-name bar [..] -C opt-level=1 -C linker-plugin-lto -C codegen-units=5 -C debuginfo=1 [..]`
+[RUNNING] `rustc --crate-name foo [..] -C opt-level=3 -C linker-plugin-lto -C debuginfo=1 [..]`
+[FINISHED] ci [optimized + debuginfo] target(s) in [..]
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn conflicting_usage() {
     let p = project()
@@ -513,3 +513,87 @@ fn build_override_shared() {
 
     p.cargo("run").run();
 }
+
+#[cargo_test]
+fn registry_packages() {
+    Package::new("dep", "1.0.0").publish();
+    Package::new("named", "1.0.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["profile-registry-packages"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = { path = "bar" }
+                dep = "1.0"
+                named = "1.0"
+
+                [profile.dev.package."*"]
+                opt-level = 1
+
+                [profile.dev.registry-packages]
+                opt-level = 3
+                debug = false
+
+                [profile.dev.package.named]
+                opt-level = 2
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_lib_manifest("bar"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("build -v")
+        .masquerade_as_nightly_cargo(&["profile-registry-packages"])
+        .with_stderr_contains("[RUNNING] `rustc --crate-name dep [..]-C opt-level=3 [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name named [..]-C opt-level=2 [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name bar [..]-C opt-level=1 -C embed-bitcode=no -C debuginfo=2 [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo [..]-C embed-bitcode=no -C debuginfo=2 [..]")
+        .with_stderr_does_not_contain("[RUNNING] `rustc --crate-name dep [..]debuginfo[..]")
+        .run();
+}
+
+#[cargo_test]
+fn registry_packages_requires_feature() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [profile.dev.registry-packages]
+                opt-level = 3
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo(&["profile-registry-packages"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[CWD]/Cargo.toml`
+
+Caused by:
+  feature `profile-registry-packages` is required
+
+  The package requires the Cargo feature called `profile-registry-packages`, \
+  but that feature is not stabilized in this version of Cargo (1.[..]).
+  Consider adding `cargo-features = [\"profile-registry-packages\"]` to the top of Cargo.toml \
+  (above the [package] table) to tell Cargo you are opting in to use this unstable feature.
+  See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#profile-registry-packages \
+  for more information about the status of this feature.
+",
+        )
+        .run();
+}
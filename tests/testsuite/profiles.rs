@@ -1,7 +1,7 @@
 //! Tests for profiles.
 
-use cargo_test_support::project;
 use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_manifest, project};
 use std::env;
 
 #[cargo_test]
@@ -670,6 +670,91 @@ fn rustflags_works_with_env() {
         .run();
 }
 
+#[cargo_test]
+fn rustflags_package_override() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            cargo-features = ["profile-rustflags"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            bar = { path = "bar" }
+            baz = { path = "baz" }
+
+            [profile.dev]
+            rustflags = ["-C", "link-dead-code=yes"]
+
+            [profile.dev.package.bar]
+            rustflags = ["--cfg", "bar_flag"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file(
+            "bar/src/lib.rs",
+            "#[cfg(not(bar_flag))] compile_error!(\"no flag\");",
+        )
+        .file("baz/Cargo.toml", &basic_manifest("baz", "0.0.1"))
+        .file("baz/src/lib.rs", "")
+        .build();
+
+    // The flags of the override are appended to the ones of the profile.
+    p.cargo("build -v")
+        .masquerade_as_nightly_cargo(&["profile-rustflags"])
+        .with_stderr_unordered(
+            "\
+[COMPILING] bar [..]
+[COMPILING] baz [..]
+[COMPILING] foo [..]
+[RUNNING] `rustc --crate-name bar [..] -C link-dead-code=yes --cfg bar_flag [..]
+[RUNNING] `rustc --crate-name baz [..] -C link-dead-code=yes [..]
+[RUNNING] `rustc --crate-name foo [..] -C link-dead-code=yes [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // Changing the flags of one package only rebuilds it and its dependents.
+    p.change_file(
+        "Cargo.toml",
+        r#"
+        cargo-features = ["profile-rustflags"]
+
+        [package]
+        name = "foo"
+        version = "0.0.1"
+
+        [dependencies]
+        bar = { path = "bar" }
+        baz = { path = "baz" }
+
+        [profile.dev]
+        rustflags = ["-C", "link-dead-code=yes"]
+
+        [profile.dev.package.bar]
+        rustflags = ["--cfg", "bar_flag", "--cfg", "other_flag"]
+        "#,
+    );
+    p.cargo("build -v")
+        .masquerade_as_nightly_cargo(&["profile-rustflags"])
+        .with_stderr_unordered(
+            "\
+[COMPILING] bar [..]
+[FRESH] baz [..]
+[COMPILING] foo [..]
+[RUNNING] `rustc --crate-name bar [..] -C link-dead-code=yes --cfg bar_flag --cfg other_flag [..]
+[RUNNING] `rustc --crate-name foo [..] -C link-dead-code=yes [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn rustflags_requires_cargo_feature() {
     let p = project()
@@ -742,3 +827,193 @@ Caused by:
         )
         .run();
 }
+
+/// Builds a compiler which runs `rustc`, to tell it apart from `rustc` in
+/// the output of `-v`.
+fn passthrough_compiler() {
+    let p = project()
+        .at("compiler")
+        .file("Cargo.toml", &basic_manifest("compiler", "0.1.0"))
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() {
+                    let status = std::process::Command::new("rustc")
+                        .args(std::env::args_os().skip(1))
+                        .status()
+                        .unwrap();
+                    std::process::exit(status.code().unwrap());
+                }
+            "#,
+        )
+        .build();
+    p.cargo("build").run();
+}
+
+#[cargo_test]
+fn rustc_package_override() {
+    passthrough_compiler();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    cargo-features = ["profile-rustc"]
+
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+
+                    [dependencies]
+                    bar = {{ path = "bar" }}
+
+                    [profile.dev.package.bar]
+                    rustc = "../compiler/target/debug/compiler{}"
+                "#,
+                env::consts::EXE_SUFFIX
+            ),
+        )
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/lib.rs", "")
+        .build();
+
+    p.cargo("check -v")
+        .masquerade_as_nightly_cargo(&["profile-rustc"])
+        .with_stderr_unordered(
+            "\
+[CHECKING] bar v0.0.1 ([CWD]/bar)
+[RUNNING] `[CWD]/../compiler/target/debug/compiler[EXE] --crate-name bar [..]
+[CHECKING] foo v0.0.1 ([CWD])
+[RUNNING] `rustc --crate-name foo [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    p.cargo("check -v")
+        .masquerade_as_nightly_cargo(&["profile-rustc"])
+        .with_stderr(
+            "\
+[FRESH] bar v0.0.1 ([CWD]/bar)
+[FRESH] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    let output = p
+        .cargo("check -Zunstable-options --unit-graph")
+        .masquerade_as_nightly_cargo(&["profile-rustc", "unit-graph"])
+        .exec_with_output()
+        .unwrap();
+    let graph: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for unit in graph["units"].as_array().unwrap() {
+        let rustc = &unit["profile"]["rustc"];
+        match unit["target"]["name"].as_str().unwrap() {
+            "bar" => assert!(rustc.as_str().unwrap().starts_with("../compiler/")),
+            _ => assert!(rustc.is_null()),
+        }
+    }
+
+    // Going back to `rustc` rebuilds the package.
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            cargo-features = ["profile-rustc"]
+
+            [package]
+            name = "foo"
+            version = "0.0.1"
+
+            [dependencies]
+            bar = { path = "bar" }
+        "#,
+    );
+    p.cargo("check -v")
+        .masquerade_as_nightly_cargo(&["profile-rustc"])
+        .with_stderr(
+            "\
+[CHECKING] bar v0.0.1 ([CWD]/bar)
+[RUNNING] `rustc --crate-name bar [..]
+[CHECKING] foo v0.0.1 ([CWD])
+[RUNNING] `rustc --crate-name foo [..]
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn rustc_missing_compiler() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                cargo-features = ["profile-rustc"]
+
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [profile.dev]
+                rustc = "./no-such-rustc"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["profile-rustc"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to load the compiler `./no-such-rustc` of profile `dev` for package `foo v0.0.1 ([CWD])`
+
+Caused by:
+  could not execute process `[CWD]/./no-such-rustc -vV` (never executed)
+
+Caused by:
+  [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn rustc_requires_feature() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [profile.dev.package.foo]
+                rustc = "rustc"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check")
+        .masquerade_as_nightly_cargo(&["profile-rustc"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to parse manifest at `[CWD]/Cargo.toml`
+
+Caused by:
+  feature `profile-rustc` is required
+
+  The package requires the Cargo feature called `profile-rustc`, but that feature is \
+  not stabilized in this version of Cargo (1.[..]).
+  Consider adding `cargo-features = [\"profile-rustc\"]` to the top of Cargo.toml \
+  (above the [package] table) to tell Cargo you are opting in to use this unstable feature.
+  See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#profile-rustc-option \
+  for more information about the status of this feature.
+",
+        )
+        .run();
+}
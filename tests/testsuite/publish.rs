@@ -2692,3 +2692,103 @@ See [..]
         )
         .run();
 }
+
+#[cargo_test]
+fn publish_provenance() {
+    // HACK below allows us to use a local registry
+    let registry = registry::init();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+                license = "MIT"
+                description = "foo"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [publish.provenance]
+                commit = "0123456789abcdef"
+                builder = "release workflow"
+            "#,
+        )
+        .build();
+
+    // HACK: Inject `foo` directly into the index so `publish` won't block for it to be in
+    // the index.
+    Package::new("foo", "0.0.1")
+        .file("src/lib.rs", "")
+        .publish();
+
+    p.cargo("publish --no-verify -Zpublish-provenance")
+        .masquerade_as_nightly_cargo(&["publish-provenance"])
+        .env(
+            "CARGO_PUBLISH_PROVENANCE_CI_RUN_URL",
+            "https://ci.example.com/runs/1",
+        )
+        .replace_crates_io(registry.index_url())
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+[WARNING] manifest has no documentation, [..]
+See [..]
+[PACKAGING] foo v0.0.1 ([CWD])
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] foo v0.0.1 ([CWD])
+[UPDATING] [..]
+",
+        )
+        .run();
+
+    publish::validate_upload_with_contents(
+        r#"
+        {
+          "authors": [],
+          "badges": {},
+          "categories": [],
+          "deps": [],
+          "description": "foo",
+          "documentation": null,
+          "features": {},
+          "homepage": null,
+          "keywords": [],
+          "license": "MIT",
+          "license_file": null,
+          "links": null,
+          "name": "foo",
+          "provenance": {
+            "builder": "release workflow",
+            "ci_run_url": "https://ci.example.com/runs/1",
+            "vcs_commit": "0123456789abcdef"
+          },
+          "readme": null,
+          "readme_file": null,
+          "repository": null,
+          "vers": "0.0.1"
+          }
+        "#,
+        "foo-0.0.1.crate",
+        &[
+            ".cargo_provenance.json",
+            "Cargo.lock",
+            "Cargo.toml",
+            "Cargo.toml.orig",
+            "src/main.rs",
+        ],
+        &[(
+            ".cargo_provenance.json",
+            r#"{
+  "vcs_commit": "0123456789abcdef",
+  "ci_run_url": "https://ci.example.com/runs/1",
+  "builder": "release workflow"
+}"#,
+        )],
+    );
+}
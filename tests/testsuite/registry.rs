@@ -11,7 +11,7 @@ use cargo_test_support::{git, install::cargo_home, t};
 use cargo_util::paths::remove_dir_all;
 use std::fs::{self, File};
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 fn cargo_http(p: &Project, s: &str) -> Execs {
     let mut e = p.cargo(s);
@@ -2849,6 +2849,306 @@ internal server error
         .run();
 }
 
+#[cargo_test]
+fn sparse_retry_rate_limited() {
+    let fail_count = Mutex::new(0);
+    let _registry = RegistryBuilder::new()
+        .http_index()
+        .add_responder("/index/3/b/bar", move |req, server| {
+            // Rate limits the first request of both builds below.
+            let mut fail_count = fail_count.lock().unwrap();
+            if *fail_count < 2 {
+                *fail_count += 1;
+                registry::Response {
+                    code: 429,
+                    headers: vec!["Retry-After: 1".to_string()],
+                    body: b"too many requests".to_vec(),
+                }
+            } else {
+                server.index(req)
+            }
+        })
+        .build();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = ">= 0.0.0"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    Package::new("bar", "0.0.1").publish();
+
+    // Without `-Zhttp-retries` a rate-limited request isn't retried.
+    cargo_http(&p, "generate-lockfile")
+        .with_status(101)
+        .with_stderr_contains("[..]got 429")
+        .with_stderr_does_not_contain("[..]spurious network error[..]")
+        .run();
+
+    let start = std::time::Instant::now();
+    p.cargo("build -Zsparse-registry -Zhttp-retries")
+        .masquerade_as_nightly_cargo(&["sparse-registry", "http-retries"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+warning: spurious network error (2 tries remaining): failed to get successful HTTP response from `[..]`, got 429
+body:
+too many requests
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v0.0.1 (registry `dummy-registry`)
+[COMPILING] bar v0.0.1
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]s
+",
+        )
+        .run();
+    assert!(start.elapsed() >= std::time::Duration::from_secs(1));
+}
+
+#[cargo_test]
+fn http_retries_config() {
+    let fail_count = Mutex::new(0);
+    let _registry = RegistryBuilder::new()
+        .http_index()
+        .add_responder("/dl/bar/0.0.1/download", move |req, server| {
+            let mut fail_count = fail_count.lock().unwrap();
+            *fail_count += 1;
+            if *fail_count < 3 {
+                server.internal_server_error(req)
+            } else {
+                server.dl(req)
+            }
+        })
+        .build();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = ">= 0.0.0"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [http]
+                retries = 1
+                retry-backoff = 10
+            "#,
+        )
+        .build();
+
+    Package::new("bar", "0.0.1").publish();
+
+    p.cargo("fetch -Zsparse-registry -Zhttp-retries")
+        .masquerade_as_nightly_cargo(&["sparse-registry", "http-retries"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+warning: spurious network error (1 tries remaining): failed to get successful HTTP response from `[..]`, got 500
+body:
+internal server error
+[ERROR] failed to download from `[..]/dl/bar/0.0.1/download`
+
+Caused by:
+  failed to get successful HTTP response from `[..]`, got 500
+  body:
+  internal server error
+",
+        )
+        .run();
+
+    // The third attempt succeeds.
+    p.cargo("fetch -Zsparse-registry -Zhttp-retries")
+        .masquerade_as_nightly_cargo(&["sparse-registry", "http-retries"])
+        .with_stderr(
+            "\
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v0.0.1 (registry `dummy-registry`)
+",
+        )
+        .run();
+}
+
+/// Serves the download of `bar` honoring the `Range` header, and records the
+/// ranges which were requested.
+fn range_responder(
+    ranges: Arc<Mutex<Vec<Option<String>>>>,
+    honor_range: bool,
+) -> impl Fn(&registry::Request, &registry::HttpServer) -> registry::Response {
+    move |req, server| {
+        ranges.lock().unwrap().push(req.range.clone());
+        let mut response = server.dl(req);
+        let start = req
+            .range
+            .as_deref()
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.strip_suffix('-'))
+            .map(|start| start.parse::<usize>().unwrap());
+        if let (Some(start), true) = (start, honor_range) {
+            response.code = 206;
+            response.body.drain(..start);
+        }
+        response
+    }
+}
+
+/// Replaces the downloaded `bar-0.0.1.crate` with a `.partial` file holding
+/// its first `len` bytes, preceded by `garbage`.
+fn truncate_crate_file(len: usize, garbage: &[u8]) {
+    let cache = paths::home().join(".cargo/registry/cache");
+    let dir = fs::read_dir(&cache)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let krate = dir.join("bar-0.0.1.crate");
+    let data = fs::read(&krate).unwrap();
+    let mut partial = garbage.to_vec();
+    partial.extend_from_slice(&data[..len]);
+    fs::write(dir.join("bar-0.0.1.crate.partial"), partial).unwrap();
+    fs::remove_file(&krate).unwrap();
+    paths::home().join(".cargo/registry/src").rm_rf();
+}
+
+#[cargo_test]
+fn resumable_download() {
+    let ranges = Arc::new(Mutex::new(Vec::new()));
+    let _registry = RegistryBuilder::new()
+        .http_index()
+        .add_responder(
+            "/dl/bar/0.0.1/download",
+            range_responder(ranges.clone(), true),
+        )
+        .build();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "0.0.1"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    Package::new("bar", "0.0.1").publish();
+
+    p.cargo("fetch -Zsparse-registry -Zresumable-downloads")
+        .masquerade_as_nightly_cargo(&["sparse-registry", "resumable-downloads"])
+        .run();
+    assert_eq!(*ranges.lock().unwrap(), [None]);
+
+    truncate_crate_file(10, b"");
+    p.cargo("build -Zsparse-registry -Zresumable-downloads")
+        .masquerade_as_nightly_cargo(&["sparse-registry", "resumable-downloads"])
+        .with_stderr(
+            "\
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v0.0.1 (registry `dummy-registry`)
+[COMPILING] bar v0.0.1
+[COMPILING] foo v0.0.1 ([CWD])
+[FINISHED] dev [unoptimized + debuginfo] target(s) in [..]s
+",
+        )
+        .run();
+    assert_eq!(
+        *ranges.lock().unwrap(),
+        [None, Some("bytes=10-".to_string())]
+    );
+    let cache = paths::home().join(".cargo/registry/cache");
+    let dir = fs::read_dir(&cache)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    assert!(!dir.join("bar-0.0.1.crate.partial").exists());
+
+    // Without `-Zresumable-downloads` the partial file is ignored.
+    truncate_crate_file(10, b"");
+    p.cargo("fetch -Zsparse-registry")
+        .masquerade_as_nightly_cargo(&["sparse-registry"])
+        .run();
+    assert_eq!(ranges.lock().unwrap()[2], None);
+}
+
+#[cargo_test]
+fn resumable_download_range_ignored() {
+    let ranges = Arc::new(Mutex::new(Vec::new()));
+    let _registry = RegistryBuilder::new()
+        .http_index()
+        .add_responder(
+            "/dl/bar/0.0.1/download",
+            range_responder(ranges.clone(), false),
+        )
+        .build();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+                authors = []
+
+                [dependencies]
+                bar = "0.0.1"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    Package::new("bar", "0.0.1").publish();
+
+    p.cargo("fetch -Zsparse-registry")
+        .masquerade_as_nightly_cargo(&["sparse-registry"])
+        .run();
+
+    // The server sends the whole file again, which replaces the partial one.
+    truncate_crate_file(10, b"garbage");
+    p.cargo("fetch -Zsparse-registry -Zresumable-downloads")
+        .masquerade_as_nightly_cargo(&["sparse-registry", "resumable-downloads"])
+        .with_stderr(
+            "\
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v0.0.1 (registry `dummy-registry`)
+",
+        )
+        .run();
+    assert_eq!(
+        *ranges.lock().unwrap(),
+        [None, Some("bytes=17-".to_string())]
+    );
+}
+
 #[cargo_test]
 fn deleted_entry() {
     // Checks the behavior when a package is removed from the index.
@@ -0,0 +1,452 @@
+//! Tests for the `cargo report` reports computed from the workspace.
+
+use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_manifest, project, Project};
+
+fn dep_kinds_project() -> Project {
+    Package::new("shared", "1.0.0").publish();
+    Package::new("normal", "1.0.0")
+        .dep("shared", "1.0")
+        .publish();
+    Package::new("build-only", "1.0.0")
+        .dep("shared", "1.0")
+        .publish();
+    Package::new("dev-only", "1.0.0")
+        .dep("build-only", "1.0")
+        .dev_dep("never-built", "1.0")
+        .publish();
+    Package::new("never-built", "1.0.0").publish();
+
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                normal = "1.0"
+                tool = { path = "tool", artifact = "bin" }
+
+                [build-dependencies]
+                build-only = "1.0"
+
+                [dev-dependencies]
+                dev-only = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}")
+        .file("tool/Cargo.toml", &basic_manifest("tool", "0.1.0"))
+        .file("tool/src/main.rs", "fn main() {}")
+        .build()
+}
+
+#[cargo_test]
+fn dep_kinds_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("report dep-kinds")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo report dep-kinds` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn dep_kinds_human() {
+    let p = dep_kinds_project();
+
+    p.cargo("report dep-kinds -Zunstable-options -Zbindeps")
+        .masquerade_as_nightly_cargo(&["unstable-options", "bindeps"])
+        .with_stdout(
+            "\
+build-only v1.0.0  build, dev
+dev-only v1.0.0    dev
+normal v1.0.0      normal
+shared v1.0.0      normal, build, dev
+tool v0.1.0        artifact (bin)
+",
+        )
+        .with_stderr_contains(
+            "[NOTE] 3 of 5 packages are only reachable through \
+             build, dev or artifact dependencies",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn dep_kinds_json() {
+    let p = dep_kinds_project();
+
+    p.cargo("report dep-kinds --format json -Zunstable-options -Zbindeps")
+        .masquerade_as_nightly_cargo(&["unstable-options", "bindeps"])
+        .with_json(
+            r#"
+                {
+                  "packages": [
+                    {
+                      "id": "build-only 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                      "kinds": ["build", "dev"],
+                      "artifact_kinds": []
+                    },
+                    {
+                      "id": "dev-only 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                      "kinds": ["dev"],
+                      "artifact_kinds": []
+                    },
+                    {
+                      "id": "normal 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                      "kinds": ["normal"],
+                      "artifact_kinds": []
+                    },
+                    {
+                      "id": "shared 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                      "kinds": ["build", "dev", "normal"],
+                      "artifact_kinds": []
+                    },
+                    {
+                      "id": "tool 0.1.0 (path+file://[..]/foo/tool)",
+                      "kinds": ["artifact"],
+                      "artifact_kinds": ["bin"]
+                    }
+                  ]
+                }
+            "#,
+        )
+        .run();
+}
+
+fn unused_patches_project() -> Project {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("baz", "1.0.0").publish();
+
+    let manifest = r#"
+        [package]
+        name = "foo"
+        version = "0.1.0"
+
+        [dependencies]
+        bar = "0.1"
+        baz = "1.0"
+    "#;
+    let p = project()
+        .file("Cargo.toml", manifest)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.1"))
+        .file("bar/src/lib.rs", "")
+        .file("baz/Cargo.toml", &basic_manifest("baz", "2.0.0"))
+        .file("baz/src/lib.rs", "")
+        .file("qux/Cargo.toml", &basic_manifest("qux", "0.1.0"))
+        .file("qux/src/lib.rs", "")
+        .build();
+    // `bar v0.1.0` stays locked when the patch is added.
+    p.cargo("generate-lockfile").run();
+    p.change_file(
+        "Cargo.toml",
+        &format!(
+            r#"
+                {}
+                [patch.crates-io]
+                bar = {{ path = "bar" }}
+                baz = {{ path = "baz" }}
+                qux = {{ path = "qux" }}
+            "#,
+            manifest
+        ),
+    );
+    p
+}
+
+#[cargo_test]
+fn unused_patches_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("report unused-patches")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo report unused-patches` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn unused_patches() {
+    let p = unused_patches_project();
+
+    p.cargo("report unused-patches -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+Patch `bar v0.1.1 ([CWD]/bar)` for `crates-io` is unused:
+  the requirements on `bar` are already satisfied by `bar v0.1.0`, which is used instead
+Patch `baz v2.0.0 ([CWD]/baz)` for `crates-io` is unused:
+  version 2.0.0 doesn't match any requirement on `baz`:
+    `^1.0` required by foo v0.1.0
+Patch `qux v0.1.0 ([CWD]/qux)` for `crates-io` is unused:
+  no package depends on `qux` from `crates-io`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn unused_patches_json() {
+    let p = unused_patches_project();
+
+    p.cargo("report unused-patches --format json -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+                {
+                  "unused_patches": [
+                    {
+                      "id": "bar 0.1.1 (path+file://[..]/foo/bar)",
+                      "patched_source": "crates-io",
+                      "reason": "already-satisfied",
+                      "used_instead": "bar 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)"
+                    },
+                    {
+                      "id": "baz 2.0.0 (path+file://[..]/foo/baz)",
+                      "patched_source": "crates-io",
+                      "reason": "version-mismatch",
+                      "requirements": [
+                        {
+                          "req": "^1.0",
+                          "required_by": "foo 0.1.0 (path+file://[..]/foo)"
+                        }
+                      ]
+                    },
+                    {
+                      "id": "qux 0.1.0 (path+file://[..]/foo/qux)",
+                      "patched_source": "crates-io",
+                      "reason": "name-mismatch",
+                      "used_from": []
+                    }
+                  ]
+                }
+            "#,
+        )
+        .run();
+}
+
+fn outdated_project() -> Project {
+    Package::new("qux", "0.1.0").publish();
+    Package::new("bar", "1.0.0").dep("qux", "0.1").publish();
+    Package::new("baz", "0.1.0").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+                baz = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("qux", "0.1.1").publish();
+    Package::new("qux", "0.2.0").publish();
+    Package::new("bar", "1.1.0").dep("qux", "0.1").publish();
+    Package::new("bar", "2.0.0").dep("qux", "0.2").publish();
+    Package::new("baz", "0.1.1-alpha.1").publish();
+    Package::new("baz", "0.1.2").yanked(true).publish();
+    p
+}
+
+#[cargo_test]
+fn outdated_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("report outdated")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo report outdated` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn outdated() {
+    let p = outdated_project();
+
+    p.cargo("report outdated -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+bar v1.0.0 (direct dependency of foo): 1.1.0 is compatible, 2.0.0 is the latest
+  `^1.0` required by foo v0.1.0
+qux v0.1.0 (transitive dependency of foo): 0.1.1 is compatible, 0.2.0 is the latest
+  `^0.1` required by bar v1.0.0
+",
+        )
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[NOTE] run `cargo update` to update the dependencies to their compatible versions
+",
+        )
+        .run();
+
+    p.cargo("update").run();
+    p.cargo("report outdated -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            "\
+bar v1.1.0 (direct dependency of foo): 2.0.0 is the latest
+  `^1.0` required by foo v0.1.0
+qux v0.1.1 (transitive dependency of foo): 0.2.0 is the latest
+  `^0.1` required by bar v1.1.0
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn outdated_json() {
+    let p = outdated_project();
+    p.cargo("update -p qux").run();
+
+    p.cargo("report outdated --format json -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+                {
+                  "packages": [
+                    {
+                      "id": "bar 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+                      "compatible": "1.1.0",
+                      "latest": "2.0.0",
+                      "direct": true,
+                      "members": ["foo 0.1.0 (path+file://[..]/foo)"],
+                      "held_back_by": [
+                        {
+                          "req": "^1.0",
+                          "required_by": "foo 0.1.0 (path+file://[..]/foo)"
+                        }
+                      ]
+                    },
+                    {
+                      "id": "qux 0.1.1 (registry+https://github.com/rust-lang/crates.io-index)",
+                      "compatible": null,
+                      "latest": "0.2.0",
+                      "direct": false,
+                      "members": ["foo 0.1.0 (path+file://[..]/foo)"],
+                      "held_back_by": [
+                        {
+                          "req": "^0.1",
+                          "required_by": "bar 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)"
+                        }
+                      ]
+                    }
+                  ]
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn size_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("report size")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo report size` command is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn size_without_report() {
+    let p = project().file("src/main.rs", "fn main() {}").build();
+
+    p.cargo("report size -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] no size report found in `[CWD]/target/cargo-timings`
+Build with `--timings=size -Z unstable-options` to record the sizes of the binaries.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn size() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["bar"]
+
+                [package]
+                name = "foo"
+                version = "0.1.0"
+            "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build --workspace --timings=size -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .run();
+    p.change_file("src/main.rs", r#"fn main() { println!("hello"); }"#);
+    p.cargo("build --timings=size -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .run();
+
+    // Binaries which weren't built again keep their sizes.
+    p.cargo("report size -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout_contains("bar v0.1.0 (bin \"bar\"): [CWD]/target/debug/bar[EXE]")
+        .with_stdout_contains("foo v0.1.0 (bin \"foo\"): [CWD]/target/debug/foo[EXE]")
+        .with_stdout_contains("  total [..]B (+[..]B)")
+        .run();
+
+    if cfg!(target_os = "linux") {
+        p.cargo("report size -p foo -Zunstable-options")
+            .masquerade_as_nightly_cargo(&["unstable-options"])
+            .with_stdout_contains("foo v0.1.0 (bin \"foo\"): [..]")
+            .with_stdout_does_not_contain("bar v0.1.0 [..]")
+            .with_stdout_contains("  sections:")
+            .with_stdout_contains("    .text [..]B (+[..]B)")
+            .with_stdout_contains("  crates:")
+            .with_stdout_contains("    std [..]B[..]")
+            .run();
+    }
+
+    let output = p
+        .cargo("report size --format json -p bar -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .exec_with_output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["version"], 1);
+    let artifacts = report["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts.len(), 1);
+    assert_eq!(artifacts[0]["target"], "bin \"bar\"");
+    assert!(artifacts[0]["current"]["file_size"].as_u64().unwrap() > 0);
+    assert!(artifacts[0]["previous"].is_null());
+}
@@ -5,7 +5,7 @@ use cargo_test_support::install::{
 };
 use cargo_test_support::is_nightly;
 use cargo_test_support::paths::CargoPathExt;
-use cargo_test_support::project;
+use cargo_test_support::{basic_manifest, project};
 
 #[cargo_test]
 fn build_bin_default_features() {
@@ -1333,3 +1333,98 @@ Consider enabling them by passing, e.g., `--features=\"a1/f1\"`
         .with_stdout("a1 f1\na2 f2")
         .run();
 }
+
+#[cargo_test]
+fn auto_required_features_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("check --auto-required-features")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--auto-required-features` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn auto_required_features() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [features]
+                a = []
+                b = ["dep:bar"]
+
+                [dependencies]
+                bar = { path = "bar", optional = true }
+
+                [[bin]]
+                name = "plain"
+
+                [[example]]
+                name = "needs_a"
+                required-features = ["a"]
+
+                [[bench]]
+                name = "needs_b"
+                harness = false
+                required-features = ["b"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("src/bin/plain.rs", "fn main() {}")
+        .file(
+            "examples/needs_a.rs",
+            r#"
+                #[cfg(not(feature = "a"))]
+                compile_error!("`a` is required");
+                fn main() {}
+            "#,
+        )
+        .file("benches/needs_b.rs", "fn main() { bar::bar(); }")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.0.1"))
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .build();
+
+    // Each target is built with its own required features, and the others
+    // don't see them.
+    p.cargo("check --all-targets --auto-required-features -Zunstable-options -v")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains("[RUNNING] `rustc --crate-name needs_a [..]--cfg 'feature=\"a\"'[..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name needs_b [..]--cfg 'feature=\"b\"'[..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name bar [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name plain [..]")
+        .with_stderr_line_without(
+            &["[RUNNING] `rustc --crate-name plain [..]--crate-type bin"],
+            &["feature="],
+        )
+        .with_stderr_line_without(
+            &["[RUNNING] `rustc --crate-name foo src/lib.rs [..]--crate-type lib"],
+            &["feature="],
+        )
+        .run();
+
+    // Without the flag, the targets are skipped, and the others are fresh.
+    p.cargo("check --all-targets -v")
+        .with_stderr_does_not_contain("[RUNNING][..]")
+        .with_stderr_does_not_contain("[..]needs_[..]")
+        .run();
+
+    // Enabling the features of a target builds it the same way.
+    p.cargo("check --examples --features a --auto-required-features -Zunstable-options -v")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[FRESH] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
@@ -0,0 +1,211 @@
+//! Tests for `-Z required-toolchain`.
+
+use cargo_test_support::{is_nightly, project, rustc_host};
+
+/// A toolchain name matching the rustc running the tests.
+fn active_toolchain() -> String {
+    if is_nightly() {
+        return "nightly".to_string();
+    }
+    let output = std::process::Command::new("rustc")
+        .arg("-V")
+        .output()
+        .unwrap();
+    let version = String::from_utf8(output.stdout).unwrap();
+    let version = version.split_whitespace().nth(1).unwrap();
+    if version.contains("-beta") {
+        "beta".to_string()
+    } else {
+        version.to_string()
+    }
+}
+
+#[cargo_test]
+fn requires_unstable_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("check --require-toolchain 1.0.0")
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--require-toolchain` flag is unstable, \
+             pass `-Z required-toolchain` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn cli() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("check -Zrequired-toolchain --require-toolchain 1.0.0")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the active toolchain, rustc [..], is not the toolchain `1.0.0` required by `--require-toolchain`
+Try `rustup toolchain install 1.0.0`, then run Cargo with `cargo +1.0.0`
+",
+        )
+        .run();
+
+    p.cargo("check -Zrequired-toolchain --require-toolchain nightly-2015-05-15")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the active toolchain, rustc [..], is not the toolchain \
+             `nightly-2015-05-15` required by `--require-toolchain`",
+        )
+        .run();
+
+    p.cargo(&format!(
+        "check -Zrequired-toolchain --require-toolchain {}-{}",
+        active_toolchain(),
+        rustc_host()
+    ))
+    .masquerade_as_nightly_cargo(&["required-toolchain"])
+    .with_stderr(
+        "\
+[CHECKING] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+    )
+    .run();
+
+    p.cargo("check -Zrequired-toolchain --require-toolchain my-toolchain")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] unrecognized toolchain `my-toolchain`, expected a channel like \
+             `stable` or `nightly-2022-10-01`, or a version like `1.65.0`",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn config() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                required-rust-version = "1.0"
+            "#,
+        )
+        .build();
+
+    // Ignored without the flag.
+    p.cargo("check").run();
+
+    p.cargo("check -Zrequired-toolchain")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the active toolchain, rustc [..], is not the toolchain `1.0` required by \
+`build.required-rust-version` in [..]/foo/.cargo/config.toml
+Try `rustup toolchain install 1.0`, then run Cargo with `cargo +1.0`
+",
+        )
+        .run();
+
+    // The command line takes precedence.
+    p.cargo(&format!(
+        "check -Zrequired-toolchain --require-toolchain {}",
+        active_toolchain()
+    ))
+    .masquerade_as_nightly_cargo(&["required-toolchain"])
+    .run();
+}
+
+#[cargo_test]
+fn toolchain_file() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "rust-toolchain.toml",
+            r#"
+                [toolchain]
+                channel = "1.0.0"
+                components = ["rustfmt"]
+            "#,
+        )
+        .build();
+
+    p.cargo("check -Zrequired-toolchain")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the active toolchain, rustc [..], is not the toolchain `1.0.0` required by \
+`[..]/foo/rust-toolchain.toml`
+rustup selects the toolchain of this file when Cargo is run through its proxy, \
+unless it's overridden with `cargo +<toolchain>` or `RUSTUP_TOOLCHAIN`
+Try `rustup toolchain install 1.0.0`, then run Cargo with `cargo +1.0.0`
+",
+        )
+        .run();
+
+    // The file is found from the current directory.
+    p.cargo("check -Zrequired-toolchain")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .cwd(p.root().join("src"))
+        .with_status(101)
+        .with_stderr_contains("[ERROR] the active toolchain, rustc [..]")
+        .run();
+
+    // The config takes precedence.
+    p.change_file(
+        ".cargo/config.toml",
+        &format!(
+            "[build]\nrequired-rust-version = \"{}\"",
+            active_toolchain()
+        ),
+    );
+    p.cargo("check -Zrequired-toolchain")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .run();
+}
+
+#[cargo_test]
+fn legacy_toolchain_file() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file("rust-toolchain", "1.0.0\n")
+        .build();
+
+    p.cargo("check -Zrequired-toolchain")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the active toolchain, rustc [..], is not the toolchain `1.0.0` \
+             required by `[..]/foo/rust-toolchain`",
+        )
+        .run();
+
+    // Custom toolchains can't be verified.
+    p.change_file("rust-toolchain", "my-toolchain\n");
+    p.cargo("check -Zrequired-toolchain")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .with_stderr(
+            "\
+[WARNING] the toolchain of `[..]/foo/rust-toolchain` can't be verified: unrecognized \
+toolchain `my-toolchain`, expected a channel like `stable` or `nightly-2022-10-01`, or a \
+version like `1.65.0`
+[CHECKING] foo v0.0.1 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // Files with a custom `path` name no channel.
+    p.change_file("rust-toolchain", "[toolchain]\npath = \"/opt/rust\"\n");
+    p.cargo("check -Zrequired-toolchain")
+        .masquerade_as_nightly_cargo(&["required-toolchain"])
+        .with_stderr(
+            "\
+[FINISHED] [..]
+",
+        )
+        .run();
+}
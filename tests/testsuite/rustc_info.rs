@@ -0,0 +1,133 @@
+//! Tests for the `cargo rustc-info` command.
+
+use cargo_test_support::{basic_manifest, project, rustc_host};
+
+fn rustc_info(p: &cargo_test_support::Project, args: &str) -> serde_json::Value {
+    let output = p
+        .cargo(&format!("rustc-info -Zunstable-options {}", args))
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .exec_with_output()
+        .unwrap();
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[cargo_test]
+fn requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("rustc-info")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo rustc-info` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn host() {
+    let p = project().file("src/lib.rs", "").build();
+
+    let info = rustc_info(&p, "");
+    assert_eq!(info["version"], 1);
+    assert_eq!(info["rustc"]["host"], rustc_host());
+    assert_eq!(info["host"]["triple"], rustc_host());
+    let targets = info["targets"].as_array().unwrap();
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0]["triple"], rustc_host());
+
+    let cfg = &info["host"]["cfg"];
+    let arch = cfg["values"]["target_arch"][0].as_str().unwrap();
+    assert!(rustc_host().starts_with(arch), "{}", arch);
+    assert_eq!(info["host"]["target_spec"]["arch"], arch);
+    assert!(!cfg["names"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|name| name == "debug_assertions"));
+
+    let sysroot = info["host"]["sysroot"].as_str().unwrap();
+    let target_libdir = info["host"]["sysroot_target_libdir"].as_str().unwrap();
+    assert!(target_libdir.starts_with(sysroot), "{}", target_libdir);
+    assert!(target_libdir.contains(rustc_host()), "{}", target_libdir);
+}
+
+#[cargo_test]
+fn rustflags_from_config() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                rustflags = ["--cfg", "from_config"]
+            "#,
+        )
+        .build();
+
+    let info = rustc_info(&p, "");
+    assert_eq!(
+        info["host"]["rustflags"],
+        serde_json::json!(["--cfg", "from_config"])
+    );
+    assert!(info["host"]["cfg"]["names"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|name| name == "from_config"));
+}
+
+#[cargo_test(nightly, reason = "custom target specifications are unstable in rustc")]
+fn custom_target() {
+    let target_spec = r#"
+        {
+            "llvm-target": "x86_64-unknown-none-gnu",
+            "data-layout": "e-m:e-i64:64-f80:128-n8:16:32:64-S128",
+            "arch": "x86_64",
+            "target-endian": "little",
+            "target-pointer-width": "64",
+            "target-c-int-width": "32",
+            "os": "none",
+            "linker-flavor": "ld.lld"
+        }
+    "#;
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.1.0"))
+        .file("src/lib.rs", "")
+        .file("custom-target.json", target_spec)
+        .build();
+
+    let info = rustc_info(&p, "--target custom-target.json");
+    let targets = info["targets"].as_array().unwrap();
+    assert_eq!(targets.len(), 1);
+    let target = &targets[0];
+    assert!(target["triple"]
+        .as_str()
+        .unwrap()
+        .ends_with("custom-target.json"));
+    assert_eq!(target["cfg"]["values"]["target_os"][0], "none");
+    assert_eq!(
+        target["target_spec"]["llvm-target"],
+        "x86_64-unknown-none-gnu"
+    );
+    // The host is always described, for build scripts and proc-macros.
+    assert_eq!(info["host"]["triple"], rustc_host());
+}
+
+#[cargo_test]
+fn shares_the_rustc_info_cache() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("rustc-info -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .env("CARGO_LOG", "cargo::util::rustc=debug")
+        .with_stderr_contains("[..]rustc info cache miss[..]")
+        .run();
+
+    p.cargo("build")
+        .env("CARGO_LOG", "cargo::util::rustc=debug")
+        .with_stderr_contains("[..]rustc info cache hit[..]")
+        .with_stderr_does_not_contain("[..]rustc info cache miss[..]")
+        .run();
+}
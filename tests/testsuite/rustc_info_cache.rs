@@ -1,6 +1,7 @@
 //! Tests for the cache file for the rustc version info.
 
-use cargo_test_support::{basic_bin_manifest, paths::CargoPathExt};
+use cargo_test_support::basic_bin_manifest;
+use cargo_test_support::paths::{self, CargoPathExt};
 use cargo_test_support::{basic_manifest, project};
 use std::env;
 
@@ -184,3 +185,89 @@ fn rustc_info_cache_with_wrappers() {
             .run();
     }
 }
+
+const GLOBAL_HIT: &str = "[..]global rustc info cache hit[..]";
+const GLOBAL_UPDATE: &str = "[..]updated global rustc info cache for `host`[..]";
+
+#[cargo_test]
+fn global_rustc_info_cache() {
+    let p1 = project().at("one").file("src/lib.rs", "").build();
+    let p2 = project().at("two").file("src/lib.rs", "").build();
+
+    p1.cargo("build -Zglobal-rustc-info-cache")
+        .masquerade_as_nightly_cargo(&["global-rustc-info-cache"])
+        .env("CARGO_LOG", "cargo::util::rustc=debug")
+        .with_stderr_contains(MISS)
+        .with_stderr_does_not_contain(GLOBAL_HIT)
+        .with_stderr_contains(GLOBAL_UPDATE)
+        .run();
+
+    // Another workspace has an empty target directory, but rustc doesn't
+    // need to run again.
+    p2.cargo("build -Zglobal-rustc-info-cache")
+        .masquerade_as_nightly_cargo(&["global-rustc-info-cache"])
+        .env("CARGO_LOG", "cargo::util::rustc=debug")
+        .with_stderr_contains(MISS)
+        .with_stderr_contains(GLOBAL_HIT)
+        .with_stderr_does_not_contain("[..]running `rustc[..]")
+        .with_stderr_does_not_contain(GLOBAL_UPDATE)
+        .with_stderr_contains(UPDATE)
+        .run();
+
+    // Without the flag, only the cache in the target directory is used.
+    p1.cargo("clean").run();
+    p1.cargo("build")
+        .env("CARGO_LOG", "cargo::util::rustc=debug")
+        .with_stderr_contains(MISS)
+        .with_stderr_does_not_contain(GLOBAL_HIT)
+        .run();
+}
+
+#[cargo_test]
+fn global_rustc_info_cache_invalidated() {
+    let wrapper_project = project()
+        .at("wrapper")
+        .file("Cargo.toml", &basic_bin_manifest("wrapper"))
+        .file(
+            "src/main.rs",
+            r#"
+            fn main() {
+                let mut args = std::env::args_os();
+                let _me = args.next().unwrap();
+                let rustc = args.next().unwrap();
+                let status = std::process::Command::new(rustc).args(args).status().unwrap();
+                std::process::exit(if status.success() { 0 } else { 1 })
+            }
+            "#,
+        )
+        .build();
+    wrapper_project.cargo("build").run();
+    let wrapper = wrapper_project.bin("wrapper");
+
+    let p1 = project().at("one").file("src/lib.rs", "").build();
+    let p2 = project().at("two").file("src/lib.rs", "").build();
+
+    p1.cargo("build -Zglobal-rustc-info-cache")
+        .masquerade_as_nightly_cargo(&["global-rustc-info-cache"])
+        .env("CARGO_LOG", "cargo::util::rustc=debug")
+        .env("RUSTC_WRAPPER", &wrapper)
+        .with_stderr_contains(GLOBAL_UPDATE)
+        .run();
+
+    // Updating the compiler in place invalidates what was cached for it.
+    wrapper.move_into_the_future();
+
+    p2.cargo("build -Zglobal-rustc-info-cache")
+        .masquerade_as_nightly_cargo(&["global-rustc-info-cache"])
+        .env("CARGO_LOG", "cargo::util::rustc=debug")
+        .env("RUSTC_WRAPPER", &wrapper)
+        .with_stderr_contains("[..]different compiler, invalidating global rustc info cache[..]")
+        .with_stderr_does_not_contain(GLOBAL_HIT)
+        .with_stderr_contains(GLOBAL_UPDATE)
+        .run();
+
+    let compilers = std::fs::read_dir(paths::home().join(".cargo/.rustc_info-v2"))
+        .unwrap()
+        .count();
+    assert_eq!(compilers, 1);
+}
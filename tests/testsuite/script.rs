@@ -0,0 +1,149 @@
+//! Tests for `cargo script`.
+
+use cargo_test_support::install::cargo_home;
+use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_manifest, project};
+
+const HELLO: &str = r#"#!/usr/bin/env cargo script
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    println!("hello {}", args.join(" "));
+}
+"#;
+
+#[cargo_test]
+fn requires_unstable_flag() {
+    let p = project().no_manifest().file("hello.rs", HELLO).build();
+
+    p.cargo("script hello.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo script` command is unstable, pass `-Z script` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn run_script() {
+    let p = project().no_manifest().file("hello.rs", HELLO).build();
+
+    p.cargo("-Zscript script hello.rs -- world again")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("hello world again")
+        .with_stderr(
+            "\
+[COMPILING] hello v0.0.0 ([ROOT]/home/.cargo/script/hello-[..])
+[FINISHED] dev [..]
+[RUNNING] `[ROOT]/home/.cargo/script/hello-[..]/target/debug/hello[EXE] world again`
+",
+        )
+        .run();
+
+    // The package, its lockfile and its target directory are kept.
+    let dirs: Vec<_> = cargo_home()
+        .join("script")
+        .read_dir()
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(dirs.len(), 1);
+    assert!(dirs[0].join("Cargo.lock").is_file());
+    assert!(p.root().read_dir().unwrap().count() == 1);
+
+    p.cargo("-Zscript script hello.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("hello ")
+        .with_stderr(
+            "\
+[FINISHED] dev [..]
+[RUNNING] `[..]/target/debug/hello[EXE]`
+",
+        )
+        .run();
+
+    // Changes of the script are picked up.
+    p.change_file("hello.rs", "fn main() { println!(\"bye\"); }");
+    p.cargo("-Zscript script -q hello.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("bye")
+        .with_stderr("")
+        .run();
+}
+
+#[cargo_test]
+fn embedded_manifest() {
+    Package::new("greeting", "1.0.0")
+        .file("src/lib.rs", "pub fn greet() -> &'static str { \"hi\" }")
+        .publish();
+    let p = project()
+        .no_manifest()
+        .file(
+            "scripts/greet.rs",
+            r#"//! Greets.
+//!
+//! ```cargo
+//! [package]
+//! edition = "2018"
+//!
+//! [dependencies]
+//! greeting = "1.0"
+//! local = { path = "../local" }
+//! ```
+
+fn main() {
+    println!("{} {}", greeting::greet(), local::NAME);
+}
+"#,
+        )
+        .file("local/Cargo.toml", &basic_manifest("local", "0.1.0"))
+        .file("local/src/lib.rs", "pub const NAME: &str = \"local\";")
+        .build();
+
+    p.cargo("-Zscript script scripts/greet.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("hi local")
+        .with_stderr_contains("[COMPILING] greeting v1.0.0")
+        .with_stderr_contains("[COMPILING] local v0.1.0 ([ROOT]/foo/local)")
+        .run();
+}
+
+#[cargo_test]
+fn invalid_manifest() {
+    let p = project()
+        .no_manifest()
+        .file(
+            "bad.rs",
+            "//! ```cargo\n//! [[bin]]\n//! name = \"other\"\n//! ```\nfn main() {}\n",
+        )
+        .file(
+            "open.rs",
+            "//! ```cargo\n//! [dependencies]\nfn main() {}\n",
+        )
+        .build();
+
+    p.cargo("-Zscript script bad.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] failed to read the manifest of `[ROOT]/foo/bad.rs`
+
+Caused by:
+  `bin` is not allowed in the manifest of a script
+",
+        )
+        .run();
+
+    p.cargo("-Zscript script open.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_status(101)
+        .with_stderr_contains("  the `cargo` code block of the doc comments is not closed")
+        .run();
+
+    p.cargo("-Zscript script missing.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_status(101)
+        .with_stderr_contains("[ERROR] failed to find the script `[ROOT]/foo/missing.rs`")
+        .run();
+}
@@ -190,3 +190,110 @@ fn auth_required() {
         .with_stdout_contains(SEARCH_RESULTS)
         .run();
 }
+
+#[cargo_test]
+fn json_and_sort_require_unstable_options() {
+    let registry = setup().build();
+
+    cargo_process("search postgres --message-format json")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--message-format` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    cargo_process("search postgres --sort downloads")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--sort` flag is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn json_results() {
+    let registry = setup().build();
+
+    cargo_process("search -Zunstable-options postgres --message-format json")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+    "crates": [
+        {
+            "name": "hoare",
+            "max_version": "0.1.1",
+            "description": "Design by contract style assertions for Rust",
+            "downloads": 2,
+            "recent_downloads": null
+        },
+        {
+            "name": "postgres",
+            "max_version": "0.17.3",
+            "description": "A native, synchronous PostgreSQL client",
+            "downloads": 535491,
+            "recent_downloads": 88321
+        }
+    ],
+    "total": 2
+}
+"#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn sorted_alt_registry() {
+    let _registry = RegistryBuilder::new()
+        .alternative()
+        .http_api()
+        .add_responder("/api/v1/crates", |req, _| {
+            let sorted = req
+                .url
+                .query_pairs()
+                .any(|(k, v)| k == "sort" && v == "recent-downloads");
+            Response {
+                code: if sorted { 200 } else { 400 },
+                headers: vec![],
+                body: SEARCH_API_RESPONSE.to_vec(),
+            }
+        })
+        .build();
+
+    cargo_process(
+        "search -Zunstable-options postgres --registry alternative --sort recent-downloads",
+    )
+    .masquerade_as_nightly_cargo(&["unstable-options"])
+    .with_stdout_contains(SEARCH_RESULTS)
+    .run();
+}
+
+#[cargo_test]
+fn registry_without_search_api() {
+    let registry = RegistryBuilder::new()
+        .http_api()
+        .add_responder("/api/v1/crates", |req, server| server.not_found(req))
+        .build();
+
+    cargo_process("search postgres")
+        .replace_crates_io(registry.index_url())
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+[ERROR] failed to retrieve search results from the registry at http://127.0.0.1:[..]
+
+Caused by:
+  the registry at http://127.0.0.1:[..] does not support searching crates
+
+  The API of the registry responded with HTTP status 404.
+",
+        )
+        .run();
+}
@@ -0,0 +1,244 @@
+//! Tests for `cargo semver-check` and the semver check of `cargo publish`.
+
+use cargo_test_support::registry::{self, Package};
+use cargo_test_support::{git, project, Project};
+
+/// Builds a semver checker reporting a breaking change when the function
+/// `old_api` is missing from the current version.
+fn checker() -> Project {
+    let checker = project()
+        .at("checker")
+        .file(
+            "Cargo.toml",
+            &cargo_test_support::basic_bin_manifest("checker"),
+        )
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() {
+                    let args: Vec<String> = std::env::args().collect();
+                    let baseline = std::fs::read_to_string(&args[1]).unwrap();
+                    let current = std::fs::read_to_string(&args[2]).unwrap();
+                    if baseline.contains("\"old_api\"") && !current.contains("\"old_api\"") {
+                        eprintln!("removed function `old_api`");
+                        std::process::exit(1);
+                    }
+                }
+            "#,
+        )
+        .build();
+    checker.cargo("build").run();
+    checker
+}
+
+fn backend_config(checker: &Project) -> String {
+    format!(
+        "[semver-check]\nbackend = '{}'\n",
+        checker.bin("checker").display()
+    )
+}
+
+fn foo_project(version: &str, lib: &str, config: &str) -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "{}"
+                    license = "MIT"
+                    description = "foo"
+                "#,
+                version
+            ),
+        )
+        .file("src/lib.rs", lib)
+        .file(".cargo/config.toml", config)
+        .build()
+}
+
+#[cargo_test]
+fn requires_z_flag() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("semver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo semver-check` command is unstable, \
+             pass `-Z semver-check` to enable it",
+        )
+        .run();
+
+    p.cargo("publish --allow-breaking")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--allow-breaking` flag is unstable, \
+             pass `-Z semver-check` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn requires_backend() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("semver-check -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] no semver checker is configured
+
+Set `semver-check.backend` in the Cargo configuration to the program \
+comparing the rustdoc JSON of two versions of a library.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn requires_library() {
+    let p = project().file("src/main.rs", "fn main() {}").build();
+
+    p.cargo("semver-check -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] `foo` does not have a library target, \
+             only libraries can be semver-checked",
+        )
+        .run();
+}
+
+#[cargo_test(nightly, reason = "rustdoc --output-format json is unstable")]
+fn registry_baseline() {
+    registry::init();
+    Package::new("foo", "0.1.0")
+        .file("src/lib.rs", "pub fn old_api() {}")
+        .publish();
+    Package::new("foo", "0.1.1")
+        .file("src/lib.rs", "pub fn old_api() {}")
+        .publish();
+    let checker = checker();
+    let p = foo_project("0.1.2", "pub fn new_api() {}", &backend_config(&checker));
+
+    p.cargo("semver-check -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[DOCUMENTING] foo v0.1.2 ([CWD])
+[FINISHED] [..]
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] foo v0.1.1 (registry `dummy-registry`)
+[DOCUMENTING] foo v0.1.1
+[FINISHED] [..]
+[COMPARING] foo v0.1.2 ([CWD]) against v0.1.1
+removed function `old_api`
+[ERROR] breaking changes detected in `foo` compared to v0.1.1
+",
+        )
+        .run();
+    assert!(p.root().join("target/doc-json/foo.json").is_file());
+    assert!(p
+        .root()
+        .join("target/semver-check/doc-json/foo.json")
+        .is_file());
+
+    p.change_file("src/lib.rs", "pub fn old_api() {} pub fn new_api() {}");
+    p.cargo("semver-check -Zsemver-check --baseline-version 0.1.0")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_stderr_contains("[COMPARING] foo v0.1.2 ([CWD]) against v0.1.0")
+        .with_stderr_does_not_contain("[ERROR][..]")
+        .run();
+}
+
+#[cargo_test(nightly, reason = "rustdoc --output-format json is unstable")]
+fn git_rev_baseline() {
+    let checker = checker();
+    let p = foo_project("0.1.0", "pub fn old_api() {}", &backend_config(&checker));
+    let repo = git::init(&p.root());
+    git::add(&repo);
+    git::commit(&repo);
+    git::tag(&repo, "v0.1.0");
+
+    p.change_file("src/lib.rs", "pub fn new_api() {}");
+    p.cargo("semver-check -Zsemver-check --baseline-rev v0.1.0")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[DOCUMENTING] foo v0.1.0 ([CWD])
+[FINISHED] [..]
+[DOCUMENTING] foo v0.1.0 ([CWD]/target/semver-check/baseline-src)
+[FINISHED] [..]
+[COMPARING] foo v0.1.0 ([CWD]) against revision `v0.1.0`
+removed function `old_api`
+[ERROR] breaking changes detected in `foo` compared to revision `v0.1.0`
+",
+        )
+        .run();
+
+    p.cargo("semver-check -Zsemver-check --baseline-rev v0.2.0")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr_contains("[ERROR] failed to find git revision `v0.2.0`")
+        .run();
+}
+
+#[cargo_test(nightly, reason = "rustdoc --output-format json is unstable")]
+fn publish_checks_breaking_changes() {
+    let registry = registry::init();
+    Package::new("foo", "0.1.0")
+        .file("src/lib.rs", "pub fn old_api() {}")
+        .publish();
+    let checker = checker();
+    let p = foo_project("0.1.1", "pub fn new_api() {}", &backend_config(&checker));
+
+    p.cargo("publish --dry-run --no-verify -Zsemver-check --index")
+        .arg(registry.index_url().as_str())
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr_contains("[COMPARING] foo v0.1.1 ([CWD]) against v0.1.0")
+        .with_stderr_contains(
+            "\
+[ERROR] breaking changes detected in `foo` compared to v0.1.0
+
+Bump the version to 0.2.0 to publish them, or pass `--allow-breaking` to publish anyway.
+",
+        )
+        .with_stderr_does_not_contain("[PACKAGING][..]")
+        .run();
+
+    p.cargo("publish --dry-run --no-verify -Zsemver-check --allow-breaking --index")
+        .arg(registry.index_url().as_str())
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_stderr_contains(
+            "[WARNING] breaking changes detected in `foo` compared to v0.1.0, \
+             publishing anyway because of `--allow-breaking`",
+        )
+        .with_stderr_contains("[UPLOADING] foo v0.1.1 ([CWD])")
+        .run();
+
+    // A semver-incompatible version is allowed to break the API.
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.2.0"
+            license = "MIT"
+            description = "foo"
+        "#,
+    );
+    p.cargo("publish --dry-run --no-verify -Zsemver-check --index")
+        .arg(registry.index_url().as_str())
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_stderr_does_not_contain("[COMPARING][..]")
+        .with_stderr_contains("[UPLOADING] foo v0.2.0 ([CWD])")
+        .run();
+}
@@ -0,0 +1,169 @@
+//! Tests for the `-Zsource-policy` allowlist of package sources.
+
+use cargo_test_support::registry::{self, Package};
+use cargo_test_support::{basic_manifest, git, project};
+
+#[cargo_test]
+fn registries() {
+    registry::alt_init();
+    Package::new("bar", "0.0.1").publish();
+    Package::new("baz", "0.0.1").alternative(true).publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "0.0.1"
+                baz = { version = "0.0.1", registry = "alternative" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [source-policy]
+                registries = ["crates-io"]
+            "#,
+        )
+        .build();
+
+    // The policy is ignored without the unstable flag.
+    p.cargo("generate-lockfile").run();
+    let lockfile = p.read_lockfile();
+
+    p.cargo("check --locked -Zsource-policy")
+        .masquerade_as_nightly_cargo(&["source-policy"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the sources of the following packages are not allowed by the `[source-policy]` \
+configuration:
+  baz v0.0.1 from registry `alternative`
+",
+        )
+        .run();
+    assert_eq!(lockfile, p.read_lockfile());
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [source-policy]
+            registries = ["crates-io", "alternative"]
+        "#,
+    );
+    p.cargo("check --locked -Zsource-policy")
+        .masquerade_as_nightly_cargo(&["source-policy"])
+        .with_stderr_contains("[CHECKING] baz v0.0.1 (registry `alternative`)")
+        .run();
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [source-policy]
+            registries = ["alternative"]
+        "#,
+    );
+    p.cargo("check --locked -Zsource-policy")
+        .masquerade_as_nightly_cargo(&["source-policy"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the sources of the following packages are not allowed by the `[source-policy]` \
+configuration:
+  bar v0.0.1 from registry `crates-io`
+",
+        )
+        .run();
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [source-policy]
+            registries = ["crates-io", "unknown"]
+        "#,
+    );
+    p.cargo("check --locked -Zsource-policy")
+        .masquerade_as_nightly_cargo(&["source-policy"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] failed to load the registries of `source-policy.registries`
+
+Caused by:
+  no index found for registry: `unknown`",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn git_repositories() {
+    let bar = git::new("bar", |p| {
+        p.file("Cargo.toml", &basic_manifest("bar", "0.1.0"))
+            .file("src/lib.rs", "")
+    });
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.0.1"
+
+                    [dependencies]
+                    bar = {{ git = '{}' }}
+                "#,
+                bar.url()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .file(".cargo/config.toml", "[source-policy]")
+        .build();
+
+    p.cargo("check -Zsource-policy")
+        .masquerade_as_nightly_cargo(&["source-policy"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] git repository `[..]`
+[ERROR] the sources of the following packages are not allowed by the `[source-policy]` \
+configuration:
+  bar v0.1.0 from file://[..]/bar#[..]
+",
+        )
+        .run();
+
+    p.change_file(
+        ".cargo/config.toml",
+        &format!(
+            r#"
+                [source-policy]
+                git = ["{}/"]
+            "#,
+            bar.url().as_str().rsplit_once('/').unwrap().0
+        ),
+    );
+    p.cargo("check -Zsource-policy")
+        .masquerade_as_nightly_cargo(&["source-policy"])
+        .with_stderr_contains("[CHECKING] bar v0.1.0 (file://[..]/bar#[..])")
+        .run();
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [source-policy]
+            git = ["github.com/rust-lang"]
+        "#,
+    );
+    p.cargo("check --locked -Zsource-policy")
+        .masquerade_as_nightly_cargo(&["source-policy"])
+        .with_status(101)
+        .with_stderr_contains("  bar v0.1.0 from file://[..]/bar#[..]")
+        .run();
+}
@@ -245,3 +245,149 @@ fn undefined_default() {
         )
         .run();
 }
+
+fn verify_mirror_project() -> cargo_test_support::Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn verify_mirror() {
+    let crates_io = setup_replacement(
+        r#"
+        [source.crates-io]
+        replace-with = 'alternative'
+    "#,
+    );
+    let _alternative = RegistryBuilder::new().alternative().build();
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.1.0").alternative(true).publish();
+
+    let p = verify_mirror_project();
+    p.cargo("generate-lockfile")
+        .replace_crates_io(crates_io.index_url())
+        .run();
+
+    p.cargo("verify-mirror")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(crates_io.index_url())
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `cargo verify-mirror` command is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("verify-mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["verify-mirror"])
+        .replace_crates_io(crates_io.index_url())
+        .with_stderr(
+            "\
+[VERIFYING] `alternative` index (which is replacing registry `crates-io`) against `crates-io`
+[UPDATING] `alternative` index
+[UPDATING] crates.io index
+[VERIFIED] 1 package
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn verify_mirror_divergence() {
+    let crates_io = setup_replacement(
+        r#"
+        [source.crates-io]
+        replace-with = 'alternative'
+    "#,
+    );
+    let _alternative = RegistryBuilder::new().alternative().build();
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.1.0")
+        .file("src/lib.rs", "pub fn modified() {}")
+        .alternative(true)
+        .publish();
+
+    let p = verify_mirror_project();
+    p.cargo("generate-lockfile")
+        .replace_crates_io(crates_io.index_url())
+        .run();
+
+    p.cargo("verify-mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["verify-mirror"])
+        .replace_crates_io(crates_io.index_url())
+        .with_status(101)
+        .with_stderr(
+            "\
+[VERIFYING] `alternative` index (which is replacing registry `crates-io`) against `crates-io`
+[UPDATING] `alternative` index
+[UPDATING] crates.io index
+[ERROR] checksum for `bar v0.1.0` differs between `crates-io` and its replacement registry `alternative`
+
+this could be indicative of a few possible errors:
+
+    * the replacement source is not an exact mirror, some changes were made
+    * either source may be corrupt in one way or another
+
+[ERROR] 1 package diverged from upstream
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn verify_mirror_lockfile_divergence() {
+    let crates_io = setup_replacement(
+        r#"
+        [source.crates-io]
+        replace-with = 'alternative'
+    "#,
+    );
+    let _alternative = RegistryBuilder::new().alternative().build();
+    Package::new("bar", "0.1.0").publish();
+    Package::new("bar", "0.1.0")
+        .file("src/lib.rs", "pub fn modified() {}")
+        .alternative(true)
+        .publish();
+
+    // Lock against the mirror, which then gets fixed up.
+    let p = verify_mirror_project();
+    p.cargo("generate-lockfile")
+        .replace_crates_io(crates_io.index_url())
+        .run();
+    Package::new("bar", "0.1.0").alternative(true).publish();
+
+    p.cargo("verify-mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["verify-mirror"])
+        .replace_crates_io(crates_io.index_url())
+        .with_status(101)
+        .with_stderr(
+            "\
+[VERIFYING] `alternative` index (which is replacing registry `crates-io`) against `crates-io`
+[UPDATING] `alternative` index
+[ERROR] checksum for `bar v0.1.0` changed between lock files
+
+this could be indicative of a few possible errors:
+
+    * the lock file is corrupt
+    * a replacement source in use (e.g., a mirror) returned a different checksum
+    * the source itself may be corrupt in one way or another
+
+unable to verify that `bar v0.1.0` is the same as when the lockfile was generated
+
+[ERROR] 1 package diverged from upstream
+",
+        )
+        .run();
+}
@@ -655,3 +655,96 @@ fn fetch() {
         .with_stderr_does_not_contain("[DOWNLOADED] [..]")
         .run();
 }
+
+#[cargo_test(build_std_mock)]
+fn build_std_config() {
+    let setup = setup();
+
+    let p = project()
+        .file(
+            "src/lib.rs",
+            r#"
+                #![no_std]
+                pub fn foo() {
+                    core::custom_api();
+                }
+            "#,
+        )
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                std = { crates = ["core"] }
+            "#,
+        )
+        .build();
+    let mut build = p.cargo("build -v --lib");
+    enable_build_std(&mut build, &setup);
+    build
+        .target_host()
+        .with_stderr_contains("[COMPILING] core [..]")
+        .with_stderr_does_not_contain("[..]libstd[..]")
+        .run();
+}
+
+#[cargo_test(build_std_mock)]
+fn target_std_features() {
+    let setup = setup();
+
+    let p = project()
+        .file(
+            "src/lib.rs",
+            "
+                pub fn foo() {
+                    std::conditional_function();
+                }
+            ",
+        )
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                    [build]
+                    std = {{ features = ["default"] }}
+
+                    [target.{}]
+                    std-features = ["feature1"]
+                "#,
+                rustc_host()
+            ),
+        )
+        .build();
+    let mut build = p.cargo("build");
+    enable_build_std(&mut build, &setup);
+    build.target_host().run();
+}
+
+#[cargo_test]
+fn build_std_config_requires_nightly() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [build]
+                std = { crates = ["core"] }
+            "#,
+        )
+        .build();
+    p.cargo("build --target")
+        .arg(rustc_host())
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `build.std` config requires the nightly channel of Cargo
+See https://doc.rust-lang.org/cargo/reference/unstable.html#build-std for more information about building the standard library.
+",
+        )
+        .run();
+
+    p.cargo("build")
+        .masquerade_as_nightly_cargo(&["build-std"])
+        .with_status(101)
+        .with_stderr("[ERROR] the `build.std` config requires --target or `build.target`")
+        .run();
+}
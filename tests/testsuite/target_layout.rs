@@ -0,0 +1,118 @@
+//! Tests for the v2 layout of the target directory (`-Z target-layout-v2`).
+
+use cargo_test_support::{basic_manifest, project, Project};
+
+fn v2_project() -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                resolver = "2"
+
+                [dependencies]
+                bar = { path = "bar", artifact = "bin" }
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"pub const BAR: &str = env!("CARGO_BIN_FILE_BAR");"#,
+        )
+        .file("build.rs", "fn main() {}")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/main.rs", "fn main() {}")
+        .build()
+}
+
+#[cargo_test]
+fn layout_v2() {
+    let p = v2_project();
+
+    p.cargo("build -Zbindeps -Ztarget-layout-v2")
+        .masquerade_as_nightly_cargo(&["bindeps", "target-layout-v2"])
+        .run();
+
+    assert!(p.root().join("target/CACHEDIR.TAG").is_file());
+    let manifest = p.read_file("target/debug/.cargo-layout.json");
+    assert!(manifest.starts_with(r#"{"version":2,"#), "{}", manifest);
+
+    // No directory is named after its package.
+    for (dir, count) in [("target/debug/.fingerprint", 4), ("target/debug/build", 2)] {
+        assert_eq!(p.glob(format!("{}/foo-*", dir)).count(), 0);
+        assert_eq!(p.glob(format!("{}/*", dir)).count(), count, "{}", dir);
+    }
+
+    // The artifact is found through the index.
+    let index: serde_json::Value =
+        serde_json::from_str(&p.read_file("target/debug/deps/artifact/index.json")).unwrap();
+    let index = index.as_object().unwrap();
+    assert_eq!(index.len(), 1);
+    let (name, dir) = index.iter().next().unwrap();
+    assert!(name.starts_with("bar-"));
+    assert!(p
+        .root()
+        .join("target/debug/deps/artifact")
+        .join(dir.as_str().unwrap())
+        .join("bin")
+        .is_dir());
+
+    p.cargo("build -v -Zbindeps -Ztarget-layout-v2")
+        .masquerade_as_nightly_cargo(&["bindeps", "target-layout-v2"])
+        .with_stderr_unordered(
+            "\
+[FRESH] bar v0.1.0 ([CWD]/bar)
+[FRESH] foo v0.1.0 ([CWD])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn layout_migration() {
+    let p = v2_project();
+
+    p.cargo("build -Zbindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .run();
+    assert!(p.glob("target/debug/.fingerprint/foo-*").count() > 0);
+
+    p.cargo("build -v -Zbindeps -Ztarget-layout-v2")
+        .masquerade_as_nightly_cargo(&["bindeps", "target-layout-v2"])
+        .with_stderr_contains("[..]Migrating `[CWD]/target/debug` to target layout v2")
+        .with_stderr_contains("[COMPILING] foo v0.1.0 ([CWD])")
+        .run();
+    assert_eq!(p.glob("target/debug/.fingerprint/foo-*").count(), 0);
+
+    // Going back to the original layout.
+    p.cargo("build -v -Zbindeps")
+        .masquerade_as_nightly_cargo(&["bindeps"])
+        .with_stderr_contains("[..]Migrating `[CWD]/target/debug` to target layout v1")
+        .with_stderr_contains("[COMPILING] foo v0.1.0 ([CWD])")
+        .run();
+    assert!(p.glob("target/debug/.fingerprint/foo-*").count() > 0);
+    assert!(!p.root().join("target/debug/.cargo-layout.json").exists());
+}
+
+#[cargo_test]
+fn layout_v2_clean_package() {
+    let p = v2_project();
+
+    p.cargo("build -Zbindeps -Ztarget-layout-v2")
+        .masquerade_as_nightly_cargo(&["bindeps", "target-layout-v2"])
+        .run();
+    assert_eq!(p.glob("target/debug/.fingerprint/*").count(), 4);
+
+    p.cargo("clean -p bar -Zbindeps -Ztarget-layout-v2")
+        .masquerade_as_nightly_cargo(&["bindeps", "target-layout-v2"])
+        .run();
+    assert_eq!(p.glob("target/debug/.fingerprint/*").count(), 3);
+    assert_eq!(p.glob("target/debug/deps/artifact/*/bin").count(), 0);
+
+    p.cargo("build -v -Zbindeps -Ztarget-layout-v2")
+        .masquerade_as_nightly_cargo(&["bindeps", "target-layout-v2"])
+        .with_stderr_contains("[COMPILING] bar v0.1.0 ([CWD]/bar)")
+        .run();
+}
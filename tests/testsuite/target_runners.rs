@@ -0,0 +1,170 @@
+//! Tests for runner fallbacks and per-kind runners (`-Z target-runners`).
+
+use cargo_test_support::{basic_bin_manifest, project, rustc_host, tools, Project};
+
+fn runner_project(runners: &str) -> Project {
+    project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() { println!("hello"); }
+
+                #[test]
+                fn test() {}
+            "#,
+        )
+        .file("examples/ex.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                    [target.{}]
+                    {}
+                "#,
+                rustc_host(),
+                runners
+            ),
+        )
+        .build()
+}
+
+fn wrapper() -> String {
+    tools::echo_wrapper()
+        .display()
+        .to_string()
+        .replace('\\', "/")
+}
+
+#[cargo_test]
+fn fallbacks_require_unstable_flag() {
+    let p = runner_project(&format!(
+        r#"
+            runner = "nonexistent-runner"
+            runner-fallbacks = ["{}"]
+            test-runner = "nonexistent-test-runner"
+        "#,
+        wrapper()
+    ));
+
+    p.cargo("run")
+        .with_status(101)
+        .with_stderr_contains("[RUNNING] `nonexistent-runner target/debug/foo[EXE]`")
+        .run();
+
+    p.cargo("test")
+        .with_status(101)
+        .with_stderr_contains(
+            "  could not execute process `nonexistent-runner [..]/target/debug/deps/foo-[..][EXE]` \
+             (never executed)",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn fallback_chain() {
+    let p = runner_project(&format!(
+        r#"
+            runner = "nonexistent-runner -r"
+            runner-fallbacks = ["nonexistent-fallback", "{}"]
+        "#,
+        wrapper()
+    ));
+
+    p.cargo("run -Ztarget-runners")
+        .masquerade_as_nightly_cargo(&["target-runners"])
+        .with_stdout("hello")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.5.0 ([CWD])
+[FINISHED] [..]
+[RUNNING] `[..]/rustc-echo-wrapper[EXE] target/debug/foo[EXE]`
+WRAPPER CALLED: target/debug/foo[EXE]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn per_kind_runners() {
+    let p = runner_project(&format!(
+        r#"
+            runner = "nonexistent-runner"
+            test-runner = "{0}"
+            example-runner = "{0}"
+        "#,
+        wrapper()
+    ));
+
+    p.cargo("test -Ztarget-runners")
+        .masquerade_as_nightly_cargo(&["target-runners"])
+        .with_stderr_contains("WRAPPER CALLED: [..]/target/debug/deps/foo-[..][EXE]")
+        .run();
+
+    p.cargo("run --example ex -Ztarget-runners")
+        .masquerade_as_nightly_cargo(&["target-runners"])
+        .with_stderr_contains("WRAPPER CALLED: target/debug/examples/ex[EXE]")
+        .run();
+
+    // Binaries and benches still use `runner`.
+    p.cargo("run -Ztarget-runners")
+        .masquerade_as_nightly_cargo(&["target-runners"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] could not find a runner to start for target `[..]`
+tried the following runners in order:
+  `nonexistent-runner` (from [..]/foo/.cargo/config.toml): [..]nonexistent-runner[..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn cfg_runners() {
+    let p = project()
+        .file("Cargo.toml", &basic_bin_manifest("foo"))
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                    [target.'cfg(not(target_os = "none"))']
+                    runner = "nonexistent-runner"
+                    runner-fallbacks = ["{}"]
+                "#,
+                wrapper()
+            ),
+        )
+        .build();
+
+    p.cargo("run -Ztarget-runners")
+        .masquerade_as_nightly_cargo(&["target-runners"])
+        .with_stderr_contains("WRAPPER CALLED: target/debug/foo[EXE]")
+        .run();
+}
+
+#[cargo_test]
+fn all_runners_missing() {
+    let p = runner_project(
+        r#"
+            bench-runner = "./runners/missing"
+            runner-fallbacks = ["nonexistent-fallback --arg"]
+        "#,
+    );
+
+    p.cargo("bench -Ztarget-runners")
+        .masquerade_as_nightly_cargo(&["target-runners"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[COMPILING] foo v0.5.0 ([CWD])
+[FINISHED] [..]
+[ERROR] could not find a runner to start for target `[..]`
+tried the following runners in order:
+  `[..]/foo/./runners/missing` (from [..]/foo/.cargo/config.toml): file not found
+  `nonexistent-fallback` (from [..]/foo/.cargo/config.toml): no executable for `nonexistent-fallback` found in PATH
+",
+        )
+        .run();
+}
@@ -51,3 +51,133 @@ fn timings_works() {
 
     p.cargo("doc --timings").run();
 }
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[cargo_test]
+fn timings_resource_usage() {
+    let p = project()
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("build --timings=html,json -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["timings=json"])
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "timing-info",
+                    "package_id": "foo 0.0.1 [..]",
+                    "target": "{...}",
+                    "mode": "build",
+                    "duration": "{...}",
+                    "max_rss": "{...}",
+                    "user_time": "{...}",
+                    "system_time": "{...}",
+                    "blocks_read": "{...}",
+                    "blocks_written": "{...}"
+                }
+
+                {
+                    "reason": "timing-info",
+                    "package_id": "foo 0.0.1 [..]",
+                    "target": "{...}",
+                    "mode": "run-custom-build",
+                    "duration": "{...}",
+                    "max_rss": "{...}",
+                    "user_time": "{...}",
+                    "system_time": "{...}",
+                    "blocks_read": "{...}",
+                    "blocks_written": "{...}"
+                }
+
+                {
+                    "reason": "timing-info",
+                    "package_id": "foo 0.0.1 [..]",
+                    "target": "{...}",
+                    "mode": "build",
+                    "duration": "{...}",
+                    "rmeta_time": "{...}",
+                    "max_rss": "{...}",
+                    "user_time": "{...}",
+                    "system_time": "{...}",
+                    "blocks_read": "{...}",
+                    "blocks_written": "{...}"
+                }
+            "#,
+        )
+        .run();
+
+    let report = std::fs::read_dir(p.root().join("target/cargo-timings"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().map_or(false, |ext| ext == "html"))
+        .unwrap();
+    let report = std::fs::read_to_string(report).unwrap();
+    assert!(report.contains("<th>Max RSS</th>"));
+    assert!(report.contains("<td>Max unit memory:</td>"));
+}
+
+#[cargo_test]
+fn timings_size() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("build --timings=size")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--timings=size` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    // Only binaries, cdylibs and dylibs are analyzed.
+    p.cargo("build --timings=size -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 [..]
+        Size foo (bin \"foo\") [..]B
+[FINISHED] [..]
+",
+        )
+        .run();
+    assert!(p
+        .root()
+        .join("target/cargo-timings/cargo-size.json")
+        .is_file());
+
+    p.change_file(
+        "src/main.rs",
+        r#"fn main() { println!("{:?}", std::collections::HashMap::<u8, u8>::new()); }"#,
+    );
+    p.cargo("build --timings=size,html -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 [..]
+        Size foo (bin \"foo\") [..]B (+[..]B)
+      Timing report saved to [..]/foo/target/cargo-timings/cargo-timing-[..].html
+[FINISHED] [..]
+",
+        )
+        .run();
+    let html = p.read_file("target/cargo-timings/cargo-timing.html");
+    assert!(html.contains("<h2>Binary sizes</h2>"));
+
+    // The previous size is kept until the binary changes again.
+    p.cargo("build --timings=size -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains("[..]Size foo (bin \"foo\") [..]B (+[..]B)")
+        .run();
+
+    // Failed builds aren't analyzed.
+    p.change_file("src/main.rs", "fn main() { error }");
+    p.cargo("build --timings=size -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr_does_not_contain("[..]Size[..]")
+        .run();
+}
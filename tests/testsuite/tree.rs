@@ -826,6 +826,71 @@ common v1.0.0
         .run();
 }
 
+#[cargo_test]
+fn invert_explain_edges() {
+    Package::new("common", "1.0.0")
+        .feature("std", &[])
+        .feature("alloc", &[])
+        .publish();
+    Package::new("optdep", "1.0.0")
+        .add_dep(Dependency::new("common", "1.0").enable_features(&["alloc"]))
+        .feature("extra", &[])
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            common = { version = "1.0", features = ["std"] }
+            optdep = { version = "1.0", optional = true }
+
+            [build-dependencies]
+            common = "1.0"
+
+            [features]
+            default = ["json"]
+            json = ["dep:optdep"]
+            fancy = ["optdep?/extra"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("tree -i common --explain-edges")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--explain-edges` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("tree --explain-edges -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["explain-edges"])
+        .with_status(101)
+        .with_stderr("[ERROR] the `--explain-edges` flag requires `--invert` or `--duplicates`")
+        .run();
+
+    p.cargo("tree -i common --explain-edges --features fancy -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["explain-edges"])
+        .with_stdout(
+            "\
+common v1.0.0
+├── foo v0.1.0 ([..]/foo) [normal; features: default, std]
+└── optdep v1.0.0 [normal; features: alloc, default]
+    └── foo v0.1.0 ([..]/foo) [normal; enabled by \"json\" (dep:optdep); \
+features: default, extra (optdep?/extra in \"fancy\")]
+[build-dependencies]
+└── foo v0.1.0 ([..]/foo) [build; features: default]
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn no_indent() {
     let p = make_simple_proj();
@@ -1206,6 +1271,62 @@ foo v0.1.0 ([..]/foo) [bar,default,dep,dep_that_is_awesome,foo,other-dep]
         .run();
 }
 
+#[cargo_test]
+fn format_provenance() {
+    let cksum = Package::new("dep", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "dep"
+                version = "1.0.0"
+                license = "Apache-2.0"
+                rust-version = "1.60"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            license = "MIT"
+
+            [dependencies]
+            dep = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("tree --format")
+        .arg("{p} {license} {source} [{checksum}] [{rust-version}]")
+        .with_stdout(&format!(
+            "\
+foo v0.1.0 ([..]/foo) MIT path+file://[..]/foo [] []
+└── dep v1.0.0 Apache-2.0 registry+https://github.com/rust-lang/crates.io-index [{}] [1.60]
+",
+            cksum
+        ))
+        .run();
+
+    p.cargo("tree --format {rust-versions}")
+        .with_stderr(
+            "\
+[ERROR] tree format `{rust-versions}` not valid
+
+Caused by:
+  unsupported pattern `rust-versions`
+",
+        )
+        .with_status(101)
+        .run();
+}
+
 #[cargo_test]
 fn dev_dep_feature() {
     // New feature resolver with optional dep
@@ -0,0 +1,271 @@
+//! Tests for `--warn-unused-dependencies`.
+
+use cargo_test_support::{basic_manifest, project, Project};
+
+fn unused_deps_project() -> Project {
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                used = { path = "used" }
+                unused = { path = "unused" }
+                bin-only = { path = "bin-only" }
+
+                [dependencies.renamed]
+                package = "other"
+                path = "other"
+
+                [dev-dependencies]
+                dev = { path = "dev" }
+
+                [build-dependencies]
+                build-unused = { path = "build-unused" }
+            "#,
+        )
+        .file("src/lib.rs", "pub fn foo() { used::f(); }")
+        .file("src/main.rs", "fn main() { foo::foo(); bin_only::f(); }")
+        .file("build.rs", "fn main() {}")
+        .file("used/Cargo.toml", &basic_manifest("used", "0.1.0"))
+        .file("used/src/lib.rs", "pub fn f() {}")
+        .file("unused/Cargo.toml", &basic_manifest("unused", "0.1.0"))
+        .file("unused/src/lib.rs", "")
+        .file("bin-only/Cargo.toml", &basic_manifest("bin-only", "0.1.0"))
+        .file("bin-only/src/lib.rs", "pub fn f() {}")
+        .file("other/Cargo.toml", &basic_manifest("other", "0.1.0"))
+        .file("other/src/lib.rs", "")
+        .file("dev/Cargo.toml", &basic_manifest("dev", "0.1.0"))
+        .file("dev/src/lib.rs", "")
+        .file(
+            "build-unused/Cargo.toml",
+            &basic_manifest("build-unused", "0.1.0"),
+        )
+        .file("build-unused/src/lib.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("check --warn-unused-dependencies")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--warn-unused-dependencies` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn warn_unused() {
+    let p = unused_deps_project();
+
+    let expected = "\
+[WARNING] unused dependency `build-unused` in `[build-dependencies]` of package `foo v0.1.0 ([CWD])`
+[WARNING] unused dependency `renamed` in `[dependencies]` of package `foo v0.1.0 ([CWD])`
+[WARNING] unused dependency `unused` in `[dependencies]` of package `foo v0.1.0 ([CWD])`
+[NOTE] pass `--warn-unused-dependencies=fix` to comment out the unused dependencies
+[FINISHED] [..]
+";
+    p.cargo("check --warn-unused-dependencies -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains(expected)
+        .with_stderr_does_not_contain("[..]is unused in crate[..]")
+        .with_stderr_does_not_contain("[..]`dev`[..]")
+        .run();
+
+    // The results are the same when all units are fresh.
+    p.cargo("check --warn-unused-dependencies -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(expected)
+        .run();
+
+    // Without the flag, the lint isn't enabled, so the units of `foo`
+    // are rebuilt.
+    p.cargo("check")
+        .with_stderr("[COMPILING] foo v0.1.0 ([CWD])\n[FINISHED] [..]")
+        .run();
+}
+
+#[cargo_test]
+fn dev_dependencies_checked_with_tests() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dev-dependencies]
+                dev-used = { path = "dev-used" }
+                dev-unused = { path = "dev-unused" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("tests/t.rs", "#[test] fn t() { dev_used::f(); }")
+        .file("dev-used/Cargo.toml", &basic_manifest("dev-used", "0.1.0"))
+        .file("dev-used/src/lib.rs", "pub fn f() {}")
+        .file(
+            "dev-unused/Cargo.toml",
+            &basic_manifest("dev-unused", "0.1.0"),
+        )
+        .file("dev-unused/src/lib.rs", "")
+        .build();
+
+    p.cargo("check --all-targets --warn-unused-dependencies -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains(
+            "[WARNING] unused dependency `dev-unused` in `[dev-dependencies]` \
+             of package `foo v0.1.0 ([CWD])`",
+        )
+        .with_stderr_does_not_contain("[..]`dev-used`[..]")
+        .run();
+}
+
+#[cargo_test]
+fn unused_json_message() {
+    let p = unused_deps_project();
+
+    p.cargo("check --warn-unused-dependencies -Zunstable-options --message-format json")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "unused-dependency",
+                    "package_id": "foo 0.1.0 [..]",
+                    "manifest_path": "[..]/foo/Cargo.toml",
+                    "dependency": "unused",
+                    "table": "dependencies"
+                }
+
+                {
+                    "reason": "unused-dependency",
+                    "package_id": "foo 0.1.0 [..]",
+                    "manifest_path": "[..]/foo/Cargo.toml",
+                    "dependency": "renamed",
+                    "table": "dependencies"
+                }
+
+                {
+                    "reason": "unused-dependency",
+                    "package_id": "foo 0.1.0 [..]",
+                    "manifest_path": "[..]/foo/Cargo.toml",
+                    "dependency": "build-unused",
+                    "table": "build-dependencies"
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn fix_unused() {
+    let p = unused_deps_project();
+
+    p.cargo("build --warn-unused-dependencies=fix -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains(
+            "[FIXED] [CWD]/Cargo.toml (commented out `build-unused`, `renamed`, `unused`)",
+        )
+        .with_stderr_does_not_contain("[WARNING] unused dependency[..]")
+        .run();
+
+    assert_eq!(
+        p.read_file("Cargo.toml"),
+        r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                used = { path = "used" }
+                # unused = { path = "unused" }
+                bin-only = { path = "bin-only" }
+
+                # [dependencies.renamed]
+                # package = "other"
+                # path = "other"
+
+                [dev-dependencies]
+                dev = { path = "dev" }
+
+                [build-dependencies]
+                # build-unused = { path = "build-unused" }
+            "#
+    );
+
+    p.cargo("build --warn-unused-dependencies -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_does_not_contain("[WARNING] unused dependency[..]")
+        .run();
+}
+
+#[cargo_test]
+fn fix_multi_line_and_target_dependencies() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "foo"
+version = "0.1.0"
+
+[dependencies]
+multi = { path = "multi", features = [
+    "a",
+] } # trailing comment
+used = { path = "used" }
+
+[target.'cfg(all())'.dependencies]
+platform = { path = "platform" }
+"#,
+        )
+        .file("src/lib.rs", "pub fn foo() { used::f(); }")
+        .file(
+            "multi/Cargo.toml",
+            r#"
+                [package]
+                name = "multi"
+                version = "0.1.0"
+
+                [features]
+                a = []
+            "#,
+        )
+        .file("multi/src/lib.rs", "")
+        .file("used/Cargo.toml", &basic_manifest("used", "0.1.0"))
+        .file("used/src/lib.rs", "pub fn f() {}")
+        .file("platform/Cargo.toml", &basic_manifest("platform", "0.1.0"))
+        .file("platform/src/lib.rs", "")
+        .build();
+
+    p.cargo("check --warn-unused-dependencies=fix -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr_contains("[FIXED] [CWD]/Cargo.toml (commented out `multi`, `platform`)")
+        .run();
+
+    assert_eq!(
+        p.read_file("Cargo.toml"),
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+
+[dependencies]
+# multi = { path = "multi", features = [
+    # "a",
+# ] } # trailing comment
+used = { path = "used" }
+
+[target.'cfg(all())'.dependencies]
+# platform = { path = "platform" }
+"#
+    );
+}
@@ -1,7 +1,9 @@
 //! Tests for the `cargo update` command.
 
-use cargo_test_support::registry::Package;
-use cargo_test_support::{basic_manifest, project};
+use std::fs;
+
+use cargo_test_support::registry::{registry_path, Package};
+use cargo_test_support::{basic_manifest, paths, project};
 
 #[cargo_test]
 fn minor_update_two_places() {
@@ -728,6 +730,138 @@ fn dry_run_update() {
     assert_eq!(old_lockfile, new_lockfile)
 }
 
+#[cargo_test]
+fn dry_run_json() {
+    Package::new("log", "0.1.0").publish();
+    Package::new("serde", "0.1.0").dep("log", "0.1").publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "bar"
+                version = "0.0.1"
+
+                [dependencies]
+                serde = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("update -Zunstable-options --dry-run --message-format json")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+    "dry_run": true,
+    "changes": [
+        {
+            "name": "bar",
+            "source": "path+file://[..]/foo",
+            "action": "add",
+            "old_version": null,
+            "new_version": "0.0.1",
+            "old_id": null,
+            "new_id": "bar 0.0.1 (path+file://[..]/foo)"
+        },
+        {
+            "name": "log",
+            "source": "registry+https://github.com/rust-lang/crates.io-index",
+            "action": "add",
+            "old_version": null,
+            "new_version": "0.1.0",
+            "old_id": null,
+            "new_id": "log 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)"
+        },
+        {
+            "name": "serde",
+            "source": "registry+https://github.com/rust-lang/crates.io-index",
+            "action": "add",
+            "old_version": null,
+            "new_version": "0.1.0",
+            "old_id": null,
+            "new_id": "serde 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)"
+        }
+    ]
+}
+"#,
+        )
+        .run();
+    assert!(!p.root().join("Cargo.lock").exists());
+
+    p.cargo("generate-lockfile").run();
+    let old_lockfile = p.read_lockfile();
+    Package::new("log", "0.1.1").publish();
+    Package::new("serde", "0.2.0").publish();
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+
+            [dependencies]
+            serde = "0.2"
+        "#,
+    );
+
+    p.cargo("update -Zunstable-options --dry-run --message-format json")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+    "dry_run": true,
+    "changes": [
+        {
+            "name": "log",
+            "source": "registry+https://github.com/rust-lang/crates.io-index",
+            "action": "remove",
+            "old_version": "0.1.0",
+            "new_version": null,
+            "old_id": "log 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+            "new_id": null
+        },
+        {
+            "name": "serde",
+            "source": "registry+https://github.com/rust-lang/crates.io-index",
+            "action": "upgrade",
+            "old_version": "0.1.0",
+            "new_version": "0.2.0",
+            "old_id": "serde 0.1.0 (registry+https://github.com/rust-lang/crates.io-index)",
+            "new_id": "serde 0.2.0 (registry+https://github.com/rust-lang/crates.io-index)"
+        }
+    ]
+}
+"#,
+        )
+        .with_stderr(
+            "\
+[UPDATING] `[..]` index
+[REMOVING] log v0.1.0
+[UPDATING] serde v0.1.0 -> v0.2.0
+[WARNING] not updating lockfile due to dry run
+",
+        )
+        .run();
+    assert_eq!(old_lockfile, p.read_lockfile());
+}
+
+#[cargo_test]
+fn message_format_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("update --message-format json")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--message-format` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn workspace_only() {
     let p = project().file("src/main.rs", "fn main() {}").build();
@@ -830,3 +964,80 @@ required by package `foo v0.1.0 ([ROOT]/foo)`
         )
         .run();
 }
+
+#[cargo_test]
+fn force_checksum() {
+    Package::new("bar", "0.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.0.1"
+
+                [dependencies]
+                bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("build").run();
+    let lock = p.read_lockfile();
+
+    // The registry now serves different contents for the same version.
+    fs::remove_file(registry_path().join("3/b/bar")).unwrap();
+    Package::new("bar", "0.1.0")
+        .file("src/lib.rs", "pub fn changed() {}")
+        .publish();
+
+    p.cargo("update -p bar")
+        .masquerade_as_nightly_cargo(&["force-checksum"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[ERROR] checksum for `bar v0.1.0` changed between lock files
+
+this could be indicative of a few possible errors:
+
+    * the lock file is corrupt
+    * a replacement source in use (e.g., a mirror) returned a different checksum
+    * the source itself may be corrupt in one way or another
+
+unable to verify that `bar v0.1.0` is the same as when the lockfile was generated
+
+If the new checksum is expected, it can be accepted with \
+`cargo update -Z unstable-options --force-checksum -p bar@0.1.0`
+",
+        )
+        .run();
+
+    p.cargo("update --force-checksum -p bar")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--force-checksum` flag is unstable, \
+             pass `-Z unstable-options` to enable it",
+        )
+        .run();
+
+    p.cargo("update -Zunstable-options --force-checksum -p bar")
+        .masquerade_as_nightly_cargo(&["force-checksum"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] bar v0.1.0 (registry `dummy-registry`)
+[UPDATING] checksum of bar v0.1.0 ([..] -> [..])
+",
+        )
+        .run();
+    assert_ne!(lock, p.read_lockfile());
+
+    let log = fs::read_to_string(paths::home().join(".cargo/checksum-audit.log")).unwrap();
+    assert_eq!(log.lines().count(), 1);
+    assert!(log.contains(r#""package":"bar 0.1.0 (registry+"#));
+
+    p.cargo("build").with_stderr("[FINISHED] [..]").run();
+}
@@ -961,3 +961,129 @@ fn no_remote_dependency_no_vendor() {
         .run();
     assert!(!p.root().join("vendor").exists());
 }
+
+#[cargo_test]
+fn prune_reproducible_and_verify() {
+    Package::new("bar", "1.0.0")
+        .file("src/lib.rs", "pub fn bar() {}")
+        .file("tests/t.rs", "")
+        .file("benches/b.rs", "")
+        .file("examples/e.rs", "fn main() {}")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("vendor --respect-source-config --prune --reproducible -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .run();
+    assert!(p.root().join("vendor/bar/src/lib.rs").exists());
+    assert!(!p.root().join("vendor/bar/tests").exists());
+    assert!(!p.root().join("vendor/bar/benches").exists());
+    assert!(!p.root().join("vendor/bar/examples").exists());
+    let manifest = p.read_file("vendor/bar/Cargo.toml");
+    assert!(manifest.contains("autotests = false"));
+    assert!(manifest.contains("autoexamples = false"));
+    let vendor_manifest = p.read_file("vendor/.vendor-manifest.json");
+    assert!(vendor_manifest.contains(r#""prune": true"#));
+    assert!(vendor_manifest.contains(r#""bar": {"#));
+
+    add_vendor_config(&p);
+    p.cargo("build").run();
+
+    p.cargo("vendor --verify -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr("[..]Verified 1 vendored package in `[..]vendor`")
+        .run();
+
+    p.change_file("vendor/bar/src/lib.rs", "pub fn baz() {}");
+    p.change_file("vendor/bar/src/extra.rs", "");
+    fs::remove_file(p.root().join("vendor/bar/Cargo.toml")).unwrap();
+    p.cargo("vendor --verify -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] vendor directory `[..]vendor` failed verification:
+  `bar/Cargo.toml` is missing
+  `bar/src/lib.rs` has been modified
+  `bar/src/extra.rs` is not part of the package
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+#[cfg(unix)]
+fn reproducible_normalizes_permissions() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    Package::new("bar", "1.0.0")
+        .file_with_mode("run.sh", 0o775, "#!/bin/sh")
+        .file_with_mode("src/lib.rs", 0o664, "")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("vendor --respect-source-config --reproducible -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .run();
+
+    let mode = |path: &str| fs::metadata(p.root().join(path)).unwrap().mode() & 0o777;
+    assert_eq!(mode("vendor/bar/src/lib.rs"), 0o644);
+    assert_eq!(mode("vendor/bar/run.sh"), 0o755);
+
+    fs::set_permissions(
+        p.root().join("vendor/bar/src/lib.rs"),
+        fs::Permissions::from_mode(0o666),
+    )
+    .unwrap();
+    p.cargo("vendor --verify -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] vendor directory `[..]vendor` failed verification:
+  `bar/src/lib.rs` has unnormalized permissions 666
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn prune_requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+
+    p.cargo("vendor --prune")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--prune` flag is unstable, pass `-Z unstable-options` to enable it",
+        )
+        .run();
+}
@@ -3,8 +3,8 @@
 use std::fs;
 
 use cargo_test_support::paths::CargoPathExt;
-use cargo_test_support::project;
-use cargo_test_support::registry;
+use cargo_test_support::registry::{self, Package, RegistryBuilder};
+use cargo_test_support::{cargo_process, project};
 
 fn setup(name: &str, version: &str) {
     let dir = registry::api_path().join(format!("api/v1/crates/{}/{}", name, version));
@@ -162,3 +162,159 @@ fn inline_and_explicit_version() {
         .with_stderr("error: cannot specify both `@0.0.1` and `--version`")
         .run();
 }
+
+#[cargo_test]
+fn batch_requires_unstable_options() {
+    let registry = registry::init();
+
+    for args in [
+        "yank foo --version 0.1.0 --version 0.2.0",
+        "yank foo --version <0.3",
+        "yank foo --version 0.1.0 --reason security",
+        "yank foo --version 0.1.0 --dry-run",
+    ] {
+        cargo_process(args)
+            .replace_crates_io(registry.index_url())
+            .masquerade_as_nightly_cargo(&["unstable-options"])
+            .with_status(101)
+            .with_stderr("[ERROR] the [..] is unstable, pass `-Z unstable-options` to enable it")
+            .run();
+    }
+}
+
+#[cargo_test]
+fn yank_with_reason() {
+    let registry = RegistryBuilder::new()
+        .http_api()
+        .add_responder("/api/v1/crates/foo/0.1.0/yank", |req, server| {
+            let body = req.body.as_deref().unwrap_or_default();
+            assert_eq!(body, br#"{"reason":"security"}"#);
+            server.ok(req)
+        })
+        .build();
+
+    cargo_process("yank foo --version 0.1.0 --reason security -Zunstable-options")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+        Yank foo@0.1.0
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn yank_version_requirement() {
+    let registry = RegistryBuilder::new().http_api().build();
+    for version in ["0.1.0", "0.1.1", "0.2.0", "0.3.0"] {
+        Package::new("foo", version).publish();
+    }
+    Package::new("foo", "0.2.1").yanked(true).publish();
+
+    cargo_process("yank foo --version <0.3 --version 0.1.0 --dry-run -Zunstable-options")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+        Yank foo@0.1.0
+        Yank foo@0.1.1
+        Yank foo@0.2.0
+[WARNING] aborting yank due to dry run
+",
+        )
+        .run();
+
+    // Several versions are only yanked after a confirmation.
+    cargo_process("yank foo --version ~0.1 -Zunstable-options")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdin("n\n")
+        .with_status(101)
+        .with_stdout(
+            "\
+Yank the following versions of `foo`:
+  0.1.0
+  0.1.1
+Continue? [y/N] ",
+        )
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+[ERROR] yank aborted
+",
+        )
+        .run();
+
+    cargo_process("yank foo --version ~0.1 -Zunstable-options")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdin("y\n")
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+        Yank foo@0.1.0
+        Yank foo@0.1.1
+",
+        )
+        .run();
+
+    // `--undo` only matches the yanked versions.
+    cargo_process("yank foo --undo --version ^0.2 -Zunstable-options")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+      Unyank foo@0.2.1
+",
+        )
+        .run();
+
+    cargo_process("yank foo --undo --version >=0.3 -Zunstable-options")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+[ERROR] no yanked versions of `foo` match `>=0.3`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn yank_retries_rate_limited() {
+    let fail_count = std::sync::Mutex::new(0);
+    let registry = RegistryBuilder::new()
+        .http_api()
+        .add_responder("/api/v1/crates/foo/0.1.0/yank", move |req, server| {
+            let mut fail_count = fail_count.lock().unwrap();
+            if *fail_count < 1 {
+                *fail_count += 1;
+                registry::Response {
+                    code: 429,
+                    headers: vec!["Retry-After: 0".to_string()],
+                    body: br#"{"errors":[{"detail":"slow down"}]}"#.to_vec(),
+                }
+            } else {
+                server.ok(req)
+            }
+        })
+        .build();
+
+    cargo_process("yank foo --version 0.1.0 -Zhttp-retries")
+        .replace_crates_io(registry.index_url())
+        .masquerade_as_nightly_cargo(&["http-retries"])
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+        Yank foo@0.1.0
+[WARNING] spurious network error (2 tries remaining): [..]slow down[..]
+",
+        )
+        .run();
+}